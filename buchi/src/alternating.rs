@@ -0,0 +1,147 @@
+// Alternating Büchi automata
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+use ltl::alphabet::{ApSet, Literals};
+
+use crate::nba::{Buchi, State};
+
+/// Alternating Büchi automaton with transitions guarded by literals
+///
+/// A transition leads into a set of states which all have to accept the rest of the word, the
+/// transitions of a state are alternatives. A run is a tree which is accepting if every infinite
+/// branch visits accepting states infinitely often, transitions into the empty set end a branch.
+#[derive(Debug, Clone, Default)]
+pub struct AlternatingBuchi {
+    aps: ApSet,
+    labels: Vec<String>,
+    /// Alternatives of every state, a guard and the states all branches continue in
+    transitions: Vec<Vec<(Literals, BTreeSet<State>)>>,
+    /// Alternatives for the states the run starts in
+    initial: Vec<BTreeSet<State>>,
+    accepting: HashSet<State>,
+}
+
+/// States of all branches and the ones which have not visited an accepting state yet
+type MacroState = (BTreeSet<State>, BTreeSet<State>);
+
+impl AlternatingBuchi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn aps(&self) -> &ApSet {
+        &self.aps
+    }
+
+    /// Atomic propositions the guards of the transitions refer to
+    pub fn set_aps(&mut self, aps: ApSet) {
+        self.aps = aps;
+    }
+
+    pub fn new_state(&mut self, label: String) -> State {
+        self.labels.push(label);
+        self.transitions.push(vec![]);
+        State {
+            id: self.labels.len() - 1,
+        }
+    }
+
+    pub fn states(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Add an alternative to the state: for every valuation satisfying the guard, the run may
+    /// continue in all of the targets
+    pub fn add_transition(
+        &mut self,
+        source: State,
+        guard: Literals,
+        targets: impl IntoIterator<Item = State>,
+    ) {
+        let transition = (guard, targets.into_iter().collect());
+        if !self.transitions[source.id].contains(&transition) {
+            self.transitions[source.id].push(transition);
+        }
+    }
+
+    /// Add an alternative for the start of the run, it starts in all of the states
+    pub fn add_initial(&mut self, states: impl IntoIterator<Item = State>) {
+        let states = states.into_iter().collect();
+        if !self.initial.contains(&states) {
+            self.initial.push(states);
+        }
+    }
+
+    pub fn set_accepting(&mut self, state: State) {
+        self.accepting.insert(state);
+    }
+
+    /// Equivalent NBA by the Miyano-Hayashi construction, only reachable states are created
+    ///
+    /// The NBA tracks the states of all branches together with the branches which still owe a
+    /// visit to an accepting state. Whenever all of them paid, it visits an accepting state and
+    /// starts over with the branches currently not in an accepting state.
+    pub fn to_nba(&self) -> Buchi {
+        let mut nba = Buchi::new();
+        nba.set_aps(self.aps.clone());
+        let mut index: HashMap<MacroState, State> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut id = |nba: &mut Buchi, queue: &mut VecDeque<_>, m: MacroState| {
+            *index.entry(m.clone()).or_insert_with(|| {
+                let new = nba.new_labeled_state(self.print_macro_state(&m));
+                queue.push_back((m, new));
+                new
+            })
+        };
+        for states in &self.initial {
+            let initial = id(&mut nba, &mut queue, (states.clone(), BTreeSet::new()));
+            nba.set_initial_state(initial);
+        }
+
+        let mut accepting = vec![];
+        while let Some(((states, owing), source)) = queue.pop_front() {
+            if owing.is_empty() {
+                accepting.push(source);
+            }
+            // Choose one alternative for every state, the targets of the owing states are tracked
+            let mut choices = vec![(Literals::empty(), BTreeSet::new(), BTreeSet::new())];
+            for state in &states {
+                let owes = owing.contains(state);
+                choices = choices
+                    .iter()
+                    .flat_map(|(guard, targets, owed)| {
+                        self.transitions[state.id].iter().filter_map(move |(g, t)| {
+                            let guard = guard.and(g)?;
+                            let targets = targets.union(t).copied().collect();
+                            let owed = if owes {
+                                owed.union(t).copied().collect()
+                            } else {
+                                owed.clone()
+                            };
+                            Some((guard, targets, owed))
+                        })
+                    })
+                    .collect_vec();
+            }
+            for (guard, targets, owed) in choices {
+                let owed = if owing.is_empty() { &targets } else { &owed };
+                let owed = owed
+                    .iter()
+                    .filter(|s| !self.accepting.contains(s))
+                    .copied()
+                    .collect();
+                let target = id(&mut nba, &mut queue, (targets, owed));
+                nba.add_guarded_transition(source, target, guard);
+            }
+        }
+        nba.add_accepting_set(accepting);
+        nba
+    }
+
+    fn print_macro_state(&self, (states, owing): &MacroState) -> String {
+        let names = |set: &BTreeSet<State>| set.iter().map(|s| &self.labels[s.id]).join(", ");
+        format!("{{{}}} {{{}}}", names(states), names(owing))
+    }
+}
@@ -0,0 +1,86 @@
+// Random automata and words for property based tests with proptest
+
+use ltl::alphabet::{ApSet, Literals};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::nba::{Buchi, Word};
+
+/// Propositions of the automata and words drawn by `any`
+pub const APS: [&str; 2] = ["a", "b"];
+
+/// Automata over the propositions with between one and `states` states, guarded transitions,
+/// possibly several initial states and up to two acceptance sets
+pub fn automata(aps: &[&str], states: usize) -> BoxedStrategy<Buchi> {
+    let aps: ApSet = aps.iter().collect();
+    let count = aps.len();
+    (1..=states.max(1))
+        .prop_flat_map(move |n| {
+            // Every proposition is required, forbidden or left open by a guard
+            let guard = vec(prop::option::of(any::<bool>()), count);
+            (
+                Just(n),
+                vec((0..n, 0..n, guard), 0..=3 * n),
+                vec(0..n, 1..=2),
+                vec(vec(0..n, 0..=n), 0..=2),
+            )
+        })
+        .prop_map(move |(n, transitions, initial, sets)| {
+            let mut buchi = Buchi::new();
+            buchi.set_aps(aps.clone());
+            let states: Vec<_> = (0..n).map(|_| buchi.new_state()).collect();
+            for (source, target, guard) in transitions {
+                let literals = (guard.into_iter().enumerate())
+                    .filter_map(|(i, value)| Some((i, value?)))
+                    .fold(Literals::empty(), |literals, (i, value)| literals.with(i, value));
+                buchi.add_guarded_transition(states[source], states[target], literals);
+            }
+            for state in initial {
+                buchi.set_initial_state(states[state]);
+            }
+            for set in sets {
+                buchi.add_accepting_set(set.into_iter().map(|state| states[state]));
+            }
+            buchi
+        })
+        .boxed()
+}
+
+/// Words prefix cycle^ω over the propositions with at most `length` letters in either part, the
+/// letters are full valuations as `accepts` expects them
+pub fn words(aps: &[&str], length: usize) -> BoxedStrategy<(Vec<Word>, Vec<Word>)> {
+    let aps: ApSet = aps.iter().collect();
+    let letter = vec(any::<bool>(), aps.len()).prop_map(move |values| {
+        let literals = (values.into_iter().enumerate())
+            .fold(Literals::empty(), |literals, (i, value)| literals.with(i, value));
+        Word::guard(&aps, literals)
+    });
+    (vec(letter.clone(), 0..=length), vec(letter, 1..=length.max(1))).boxed()
+}
+
+impl Arbitrary for Buchi {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Buchi>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        automata(&APS, 4)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hoa;
+
+    proptest! {
+        #[test]
+        fn round_trip(buchi: Buchi, (prefix, cycle) in words(&APS, 3)) {
+            let accepted = buchi.accepts(&prefix, &cycle);
+            let parsed = hoa::parse(&buchi.hoa()).unwrap();
+            prop_assert_eq!(parsed.accepts(&prefix, &cycle), accepted);
+            let nba = buchi.gnba_to_nba();
+            prop_assert_eq!(nba.accepts(&prefix, &cycle), accepted);
+            prop_assert_eq!(nba.verify().is_ok(), buchi.verify().is_ok());
+        }
+    }
+}
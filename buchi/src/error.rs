@@ -1,5 +1,18 @@
+use std::result;
 use thiserror::Error;
 
-#[derive(Error)]
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Error, Debug)]
 pub enum Error {
-}
\ No newline at end of file
+    #[error("invalid HOA automaton in line {line}: {reason}")]
+    Hoa { line: usize, reason: String },
+    #[error("invalid never claim in line {line}: {reason}")]
+    Never { line: usize, reason: String },
+    #[error("state s{0} does not belong to the automaton")]
+    UnknownState(usize),
+    #[error("no state is named '{0}'")]
+    UnknownName(String),
+    #[error("a state named '{0}' already exists")]
+    DuplicateName(String),
+}
@@ -0,0 +1,679 @@
+// Parser for automata in the Hanoi Omega-Automata format, with generalized Büchi, Rabin and
+// Streett acceptance conditions and explicit labels
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use itertools::Itertools;
+use ltl::alphabet::{ApSet, Literals};
+
+use crate::error::{Error, Result};
+use crate::nba::{Buchi, RabinAutomaton, State, StreettAutomaton, Word};
+
+/// A state of the file with its edges, the targets carry the acceptance marks of the edge
+#[derive(Default)]
+struct HoaState {
+    name: Option<String>,
+    marks: BTreeSet<usize>,
+    edges: Vec<(Vec<Word>, usize, BTreeSet<usize>)>,
+}
+
+/// The parts of a file which are needed to build an automaton
+struct Hoa {
+    aps: ApSet,
+    initial: Vec<usize>,
+    /// Line and value of the acceptance condition
+    acceptance: (usize, String),
+    states: HashMap<usize, HoaState>,
+}
+
+/// Header item of files whose propositions stand for words
+const WORDS_HEADER: &str = "lmc-words";
+
+/// Atomic propositions declared when writing an automaton
+pub(crate) enum Propositions {
+    /// Every word is a guard over the propositions
+    Guards(ApSet),
+    /// One proposition per word, sorted by the words, of which exactly one holds on every edge
+    Words(Vec<String>),
+}
+
+impl Propositions {
+    pub(crate) fn new<'a>(aps: &ApSet, words: impl IntoIterator<Item = &'a Word>) -> Self {
+        let words = words.into_iter().collect_vec();
+        if words.iter().all(|w| w.literals.is_some()) {
+            Propositions::Guards(aps.clone())
+        } else {
+            Propositions::Words(
+                words
+                    .iter()
+                    .map(|w| w.id.clone())
+                    .unique()
+                    .sorted()
+                    .collect(),
+            )
+        }
+    }
+
+    /// Header items declaring the propositions, terminated by a newline
+    pub(crate) fn header(&self) -> String {
+        let declare = |names: Vec<&str>| {
+            let quoted = names.iter().map(|n| format!(" \"{}\"", n)).join("");
+            format!("AP: {}{}\n", names.len(), quoted)
+        };
+        match self {
+            Propositions::Guards(aps) => declare(aps.iter().collect()),
+            Propositions::Words(words) => format!(
+                "{}{}: yes\n",
+                declare(words.iter().map(String::as_str).collect()),
+                WORDS_HEADER
+            ),
+        }
+    }
+
+    /// Explicit label of the word such as "[0&!1]"
+    pub(crate) fn label(&self, word: &Word) -> String {
+        let cube = match self {
+            Propositions::Guards(aps) => {
+                let literals = word.literals.expect("Every word is a guard");
+                (0..aps.len())
+                    .filter_map(|i| {
+                        if literals.positive.contains(i) {
+                            Some(i.to_string())
+                        } else if literals.negative.contains(i) {
+                            Some(format!("!{}", i))
+                        } else {
+                            None
+                        }
+                    })
+                    .join("&")
+            }
+            Propositions::Words(words) => {
+                let index = words
+                    .binary_search(&word.id)
+                    .expect("Every word has a proposition");
+                (0..words.len())
+                    .map(|i| {
+                        if i == index {
+                            i.to_string()
+                        } else {
+                            format!("!{}", i)
+                        }
+                    })
+                    .join("&")
+            }
+        };
+        format!("[{}]", if cube.is_empty() { "t".into() } else { cube })
+    }
+}
+
+/// Parse an automaton with generalized Büchi acceptance in HOA format
+pub fn parse(input: &str) -> Result<Buchi> {
+    let hoa = read(input)?;
+    let (line, value) = &hoa.acceptance;
+    let sets = acceptance(value).map_err(|reason| Error::Hoa {
+        line: *line,
+        reason,
+    })?;
+
+    // Marks of a state are marks of each of its outgoing transitions
+    let mut buchi = Buchi::new();
+    buchi.set_aps(hoa.aps.clone());
+    let mut index: HashMap<usize, State> = HashMap::new();
+    let mut queue = VecDeque::new();
+    let mut id = |buchi: &mut Buchi, queue: &mut VecDeque<_>, state: usize| {
+        *index.entry(state).or_insert_with(|| {
+            let new = buchi.new_labeled_state(hoa.name(state));
+            queue.push_back((state, new));
+            new
+        })
+    };
+    for state in &hoa.initial {
+        let new = id(&mut buchi, &mut queue, *state);
+        buchi.set_initial_state(new);
+    }
+    let mut transitions = vec![vec![]; sets];
+    while let Some((state, new)) = queue.pop_front() {
+        let Some(hoa_state) = hoa.states.get(&state) else {
+            continue;
+        };
+        for (words, target, marks) in &hoa_state.edges {
+            let target = id(&mut buchi, &mut queue, *target);
+            for word in words {
+                buchi.add_transition(new, target, word.clone());
+                for i in marks.union(&hoa_state.marks) {
+                    let set = transitions.get_mut(*i).ok_or_else(|| Error::Hoa {
+                        line: *line,
+                        reason: format!("mark {} is not one of the {} acceptance sets", i, sets),
+                    })?;
+                    set.push((new, word.clone(), target));
+                }
+            }
+        }
+    }
+    for set in transitions {
+        buchi.add_accepting_transitions(set);
+    }
+    Ok(buchi)
+}
+
+/// Parse an automaton with a Rabin condition such as "Fin(0)&Inf(1) | Fin(2)&Inf(3)"
+pub fn parse_rabin(input: &str) -> Result<RabinAutomaton> {
+    let hoa = read(input)?;
+    let pairs = hoa.pairs('|', '&')?;
+    let (automaton, sets) = hoa.state_based();
+    let mut rabin = RabinAutomaton::new(automaton);
+    for (fin, inf) in pairs {
+        rabin.add_pair(sets[fin].iter().copied(), sets[inf].iter().copied());
+    }
+    Ok(rabin)
+}
+
+/// Parse an automaton with a Streett condition such as "(Fin(0)|Inf(1)) & (Fin(2)|Inf(3))"
+pub fn parse_streett(input: &str) -> Result<StreettAutomaton> {
+    let hoa = read(input)?;
+    let pairs = hoa.pairs('&', '|')?;
+    let (automaton, sets) = hoa.state_based();
+    let mut streett = StreettAutomaton::new(automaton);
+    for (fin, inf) in pairs {
+        streett.add_pair(sets[fin].iter().copied(), sets[inf].iter().copied());
+    }
+    Ok(streett)
+}
+
+/// Read the header and states of a file. Labels are split into one word per cube of their
+/// disjunctive normal form, labels in curly braces are read as plain words as written by earlier
+/// versions of `Buchi::hoa`, and with the `lmc-words` header labels are read back as words.
+fn read(input: &str) -> Result<Hoa> {
+    let mut aps = ApSet::new();
+    let mut initial = vec![];
+    let mut acceptance = (0, "0 t".to_string());
+    let mut states: HashMap<usize, HoaState> = HashMap::new();
+    let mut current: Option<usize> = None;
+    let mut in_body = false;
+    let mut words_header = false;
+
+    for (number, line) in input.lines().enumerate() {
+        let invalid = |reason: String| Error::Hoa {
+            line: number + 1,
+            reason,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if !in_body {
+            if line == "--BODY--" {
+                in_body = true;
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid(format!("expected a header item, found '{}'", line)))?;
+            let value = value.trim();
+            match key {
+                "Start" => {
+                    let state = value
+                        .parse()
+                        .map_err(|_| invalid(format!("unsupported start state '{}'", value)))?;
+                    initial.push(state);
+                }
+                "AP" => {
+                    let (count, names) = value.split_once(' ').unwrap_or((value, ""));
+                    let names = names
+                        .split('"')
+                        .skip(1)
+                        .step_by(2)
+                        .map(String::from)
+                        .collect_vec();
+                    if count.parse() != Ok(names.len()) {
+                        return Err(invalid("number of atomic propositions differs".into()));
+                    }
+                    if names.len() > ApSet::MAX {
+                        return Err(invalid("too many atomic propositions".into()));
+                    }
+                    aps = names.iter().collect();
+                }
+                "Acceptance" => acceptance = (number + 1, value.to_string()),
+                WORDS_HEADER => words_header = true,
+                _ => {}
+            }
+            continue;
+        }
+
+        if line == "--END--" {
+            break;
+        }
+        if let Some(rest) = line.strip_prefix("State:") {
+            let (state, name, marks) = state_line(rest.trim()).map_err(invalid)?;
+            let entry = states.entry(state).or_default();
+            entry.name = name;
+            entry.marks = marks;
+            current = Some(state);
+            continue;
+        }
+        let Some(state) = current else {
+            return Err(invalid("edge before the first state".into()));
+        };
+        let (words, rest) = if let Some(rest) = line.strip_prefix('[') {
+            let (label, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| invalid("unclosed label".into()))?;
+            let cubes = Label::new(label, aps.len()).parse().map_err(invalid)?;
+            let words = if words_header {
+                cubes
+                    .into_iter()
+                    .map(|c| {
+                        let word = c
+                            .positive
+                            .iter()
+                            .exactly_one()
+                            .map_err(|_| invalid("labels have to name one word".into()))?;
+                        Ok(Word::new(aps.name(word)))
+                    })
+                    .collect::<Result<_>>()?
+            } else {
+                cubes.into_iter().map(|c| Word::guard(&aps, c)).collect()
+            };
+            (words, rest)
+        } else if let Some(rest) = line.strip_prefix('{') {
+            let (word, rest) = rest
+                .split_once('}')
+                .ok_or_else(|| invalid("unclosed label".into()))?;
+            (vec![Word::new(word)], rest)
+        } else {
+            return Err(invalid("implicit labels are not supported".into()));
+        };
+        let (target, marks) = target_marks(rest.trim()).map_err(invalid)?;
+        states
+            .entry(state)
+            .or_default()
+            .edges
+            .push((words, target, marks));
+    }
+    Ok(Hoa {
+        aps,
+        initial,
+        acceptance,
+        states,
+    })
+}
+
+impl Hoa {
+    fn name(&self, state: usize) -> String {
+        self.states
+            .get(&state)
+            .and_then(|s| s.name.clone())
+            .unwrap_or_else(|| state.to_string())
+    }
+
+    /// Pairs (fin, inf) of a condition which joins the pairs with `outer` and the two sets of a
+    /// pair with `inner`, an empty condition is "f" for Rabin and "t" for Streett
+    fn pairs(&self, outer: char, inner: char) -> Result<Vec<(usize, usize)>> {
+        let (line, value) = &self.acceptance;
+        let invalid = || Error::Hoa {
+            line: *line,
+            reason: format!("unsupported acceptance condition '{}'", value),
+        };
+        let (count, condition) = value.split_once(' ').unwrap_or((value, ""));
+        let count: usize = count.parse().map_err(|_| invalid())?;
+        let condition: String = condition.chars().filter(|c| !c.is_whitespace()).collect();
+        if condition == "f" || condition == "t" {
+            return Ok(vec![]);
+        }
+        let set = |term: &str, kind: &str| {
+            term.trim_start_matches('(')
+                .trim_end_matches(')')
+                .strip_prefix(kind)
+                .and_then(|i| i.parse::<usize>().ok())
+                .filter(|i| *i < count)
+        };
+        condition
+            .split(outer)
+            .map(|pair| {
+                let pair = pair.trim_start_matches('(').trim_end_matches(')');
+                let (fin, inf) = pair.split_once(inner).ok_or_else(invalid)?;
+                Ok((
+                    set(fin, "Fin(").ok_or_else(invalid)?,
+                    set(inf, "Inf(").ok_or_else(invalid)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Automaton in which the marks of the last transition are remembered in the states, with
+    /// the states belonging to each of the acceptance sets. Only reachable states are created.
+    fn state_based(&self) -> (Buchi, Vec<Vec<State>>) {
+        let mut buchi = Buchi::new();
+        buchi.set_aps(self.aps.clone());
+        let mut index: HashMap<(usize, BTreeSet<usize>), State> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut id = |buchi: &mut Buchi, queue: &mut VecDeque<_>, state, marks: BTreeSet<usize>| {
+            *index.entry((state, marks.clone())).or_insert_with(|| {
+                let label = if marks.is_empty() {
+                    self.name(state)
+                } else {
+                    format!("{} {{{}}}", self.name(state), marks.iter().join(" "))
+                };
+                let new = buchi.new_labeled_state(label);
+                queue.push_back((state, marks, new));
+                new
+            })
+        };
+        for state in &self.initial {
+            let new = id(&mut buchi, &mut queue, *state, BTreeSet::new());
+            buchi.set_initial_state(new);
+        }
+        let mut sets: Vec<Vec<State>> = vec![];
+        while let Some((state, marks, new)) = queue.pop_front() {
+            let Some(hoa_state) = self.states.get(&state) else {
+                continue;
+            };
+            for i in marks.union(&hoa_state.marks) {
+                if sets.len() <= *i {
+                    sets.resize(*i + 1, vec![]);
+                }
+                sets[*i].push(new);
+            }
+            for (words, target, marks) in &hoa_state.edges {
+                let target = id(&mut buchi, &mut queue, *target, marks.clone());
+                for word in words {
+                    buchi.add_transition(new, target, word.clone());
+                }
+            }
+        }
+        let count = self
+            .acceptance
+            .1
+            .split(' ')
+            .next()
+            .and_then(|c| c.parse().ok());
+        sets.resize(count.unwrap_or(0).max(sets.len()), vec![]);
+        (buchi, sets)
+    }
+}
+
+/// Number of acceptance sets of a generalized Büchi condition such as "2 Inf(0) & Inf(1)"
+fn acceptance(value: &str) -> std::result::Result<usize, String> {
+    let (count, condition) = value.split_once(' ').unwrap_or((value, "t"));
+    let count: usize = count
+        .parse()
+        .map_err(|_| format!("invalid number of acceptance sets '{}'", count))?;
+    let condition: String = condition.chars().filter(|c| !c.is_whitespace()).collect();
+    let condition = condition.trim_start_matches('(').trim_end_matches(')');
+    let sets: BTreeSet<usize> = if condition == "t" {
+        BTreeSet::new()
+    } else {
+        condition
+            .split('&')
+            .map(|inf| {
+                inf.trim_start_matches('(')
+                    .trim_end_matches(')')
+                    .strip_prefix("Inf(")
+                    .and_then(|i| i.parse().ok())
+                    .ok_or_else(|| format!("unsupported acceptance condition '{}'", value))
+            })
+            .collect::<std::result::Result<_, _>>()?
+    };
+    if sets != (0..count).collect() {
+        return Err(format!(
+            "every acceptance set has to occur exactly once in '{}'",
+            value
+        ));
+    }
+    Ok(count)
+}
+
+/// The index, name and acceptance marks of a state, e.g. `0 "q0" {1}`
+fn state_line(
+    value: &str,
+) -> std::result::Result<(usize, Option<String>, BTreeSet<usize>), String> {
+    if value.starts_with('[') {
+        return Err("state labels are not supported".into());
+    }
+    let (state, rest) = value.split_once(' ').unwrap_or((value, ""));
+    let state = state
+        .parse()
+        .map_err(|_| format!("invalid state '{}'", state))?;
+    let rest = rest.trim();
+    let (name, rest) = match rest.strip_prefix('"') {
+        Some(quoted) => {
+            let (name, rest) = quoted
+                .split_once('"')
+                .ok_or_else(|| "unclosed state name".to_string())?;
+            (Some(name.to_string()), rest.trim())
+        }
+        None => (None, rest),
+    };
+    Ok((state, name, marks(rest)?))
+}
+
+/// Target of an edge and its acceptance marks, e.g. `3 {0 1}`
+fn target_marks(value: &str) -> std::result::Result<(usize, BTreeSet<usize>), String> {
+    let (target, rest) = value.split_once(' ').unwrap_or((value, ""));
+    if target.contains('&') {
+        return Err("alternating automata are not supported".into());
+    }
+    let target = target
+        .parse()
+        .map_err(|_| format!("invalid target '{}'", target))?;
+    Ok((target, marks(rest.trim())?))
+}
+
+fn marks(value: &str) -> std::result::Result<BTreeSet<usize>, String> {
+    if value.is_empty() {
+        return Ok(BTreeSet::new());
+    }
+    value
+        .strip_prefix('{')
+        .and_then(|v| v.strip_suffix('}'))
+        .ok_or_else(|| format!("invalid acceptance marks '{}'", value))?
+        .split_whitespace()
+        .map(|m| m.parse().map_err(|_| format!("invalid mark '{}'", m)))
+        .collect()
+}
+
+/// Recursive descent parser for labels, the result is in disjunctive normal form
+pub(crate) struct Label<'a> {
+    input: &'a str,
+    /// Number of atomic propositions
+    aps: usize,
+    tokens: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> Label<'a> {
+    pub(crate) fn new(input: &'a str, aps: usize) -> Self {
+        let mut tokens = vec![];
+        let mut rest = input.trim_start();
+        while let Some(c) = rest.chars().next() {
+            let length = if c.is_ascii_alphanumeric() {
+                rest.find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(rest.len())
+            } else {
+                c.len_utf8()
+            };
+            tokens.push(&rest[..length]);
+            rest = rest[length..].trim_start();
+        }
+        Label {
+            input,
+            aps,
+            tokens,
+            position: 0,
+        }
+    }
+
+    pub(crate) fn parse(mut self) -> std::result::Result<Vec<Literals>, String> {
+        let dnf = self.or()?;
+        match self.tokens.get(self.position) {
+            None => Ok(dnf),
+            Some(token) => Err(format!("unexpected '{}' in label '{}'", token, self.input)),
+        }
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.tokens.get(self.position).copied();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> std::result::Result<Vec<Literals>, String> {
+        let mut dnf = self.and()?;
+        while self.tokens.get(self.position) == Some(&"|") {
+            self.position += 1;
+            dnf.extend(self.and()?);
+        }
+        Ok(dnf)
+    }
+
+    fn and(&mut self) -> std::result::Result<Vec<Literals>, String> {
+        let mut dnf = self.not()?;
+        while self.tokens.get(self.position) == Some(&"&") {
+            self.position += 1;
+            dnf = conjunction(&dnf, &self.not()?);
+        }
+        Ok(dnf)
+    }
+
+    fn not(&mut self) -> std::result::Result<Vec<Literals>, String> {
+        match self.next() {
+            Some("!") => {
+                // Negating a disjunction of cubes gives a conjunction of disjunctions of literals
+                let dnf = self.not()?;
+                Ok(dnf.iter().fold(vec![Literals::empty()], |result, cube| {
+                    let negated = cube
+                        .positive
+                        .iter()
+                        .map(|i| Literals::empty().with(i, false))
+                        .chain(
+                            cube.negative
+                                .iter()
+                                .map(|i| Literals::empty().with(i, true)),
+                        )
+                        .collect_vec();
+                    conjunction(&result, &negated)
+                }))
+            }
+            Some("(") => {
+                let dnf = self.or()?;
+                match self.next() {
+                    Some(")") => Ok(dnf),
+                    _ => Err(format!("unclosed parenthesis in label '{}'", self.input)),
+                }
+            }
+            Some("t") => Ok(vec![Literals::empty()]),
+            Some("f") => Ok(vec![]),
+            Some(token) => match token.parse::<usize>() {
+                Ok(i) if i < self.aps => Ok(vec![Literals::empty().with(i, true)]),
+                _ => Err(format!("unexpected '{}' in label '{}'", token, self.input)),
+            },
+            None => Err(format!("incomplete label '{}'", self.input)),
+        }
+    }
+}
+
+/// Satisfiable conjunctions of every pair of cubes
+fn conjunction(lhs: &[Literals], rhs: &[Literals]) -> Vec<Literals> {
+    lhs.iter()
+        .cartesian_product(rhs)
+        .filter_map(|(l, r)| l.and(r))
+        .unique()
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn parse_hoa() {
+        // G F a with transition based acceptance as written by Spot
+        let input = r#"HOA: v1
+name: "G(Fa)"
+States: 1
+Start: 0
+AP: 1 "a"
+acc-name: Buchi
+Acceptance: 1 Inf(0)
+properties: trans-labels explicit-labels trans-acc complete
+--BODY--
+State: 0
+[0] 0 {0}
+[!0] 0
+--END--"#;
+        let nba = parse(input).unwrap();
+        assert_eq!(nba.aps().iter().collect_vec(), ["a"]);
+        assert_eq!(nba.stats().states, 1);
+        assert_eq!(nba.accepting_transitions().len(), 1);
+        let (_, word, _) = nba.accepting_transitions()[0].iter().next().unwrap();
+        assert_eq!(word.id, "a");
+        // Marks of the last transition are remembered for state based acceptance
+        let state_based = nba.state_based();
+        assert_eq!(state_based.stats().states, 2);
+        let accepting = state_based.accepting_sets()[0].iter().next().unwrap();
+        assert_eq!(state_based.label(accepting), Some("0 {0}"));
+
+        // Disjunctions are split into cubes
+        let input = "States: 1\nStart: 0\nAP: 2 \"a\" \"b\"\nAcceptance: 0 t\n--BODY--\nState: 0 \"q\"\n[!(0 & !1) | f] 0\n--END--";
+        let nba = parse(input).unwrap();
+        let labels = nba
+            .transitions()
+            .iter()
+            .map(|t| t.label)
+            .sorted()
+            .join(" | ");
+        assert_eq!(labels, "b | ¬a");
+
+        // Automata without guards are read back from their own output
+        let mut words = Buchi::new();
+        let s = words.new_labeled_state("s".into());
+        words.add_transition(s, s, "x");
+        words.set_initial_state(s);
+        words.add_accepting_set([s]);
+        let parsed = parse(&words.hoa()).unwrap();
+        assert!(parsed.equivalent(&words).is_ok());
+
+        assert!(parse("Acceptance: 1 Fin(0)\n--BODY--\n--END--").is_err());
+        assert!(parse("AP: 1 \"a\"\n--BODY--\nState: 0\n[1] 0\n--END--").is_err());
+        assert!(parse("--BODY--\nState: 0\n0 1\n--END--").is_err());
+        assert!(parse("Start: 0\nAcceptance: 1 Inf(0)\n--BODY--\nState: 0\n{x} 0 {3}\n--END--").is_err());
+    }
+
+    #[test]
+    pub fn write_hoa() {
+        // Infinitely many a with two initial states over guards
+        let aps = ["a", "b"].iter().collect::<ApSet>();
+        let mut guarded = Buchi::new();
+        guarded.set_aps(aps.clone());
+        let p = guarded.new_labeled_state("p".into());
+        let q = guarded.new_labeled_state("q".into());
+        for s in [p, q] {
+            guarded.add_guarded_transition(s, q, Literals::empty().with(0, true));
+            guarded.add_guarded_transition(s, p, Literals::empty().with(0, false));
+            guarded.set_initial_state(s);
+        }
+        guarded.add_accepting_set([q]);
+        let hoa = guarded.hoa();
+        assert!(hoa.contains("Start: 0\nStart: 1\nAP: 2 \"a\" \"b\"\nacc-name: Buchi"));
+        assert!(
+            hoa.contains("State: 0 \"p\"\n  [0] 1 {0}\n  [!0] 0\n"),
+            "{}",
+            hoa
+        );
+        let parsed = parse(&hoa).unwrap();
+        assert_eq!(parsed.aps().iter().collect_vec(), ["a", "b"]);
+        assert!(parsed.equivalent(&guarded).is_ok());
+
+        // Words are encoded by one proposition each
+        let mut words = Buchi::new();
+        let s = words.new_state();
+        words.add_transition(s, s, "x");
+        words.add_transition(s, s, "y");
+        words.set_initial_state(s);
+        let hoa = words.hoa();
+        assert!(hoa.contains("AP: 2 \"x\" \"y\"\nlmc-words: yes\nacc-name: all"));
+        assert!(hoa.contains("[0&!1] 0\n  [!0&1] 0"), "{}", hoa);
+        assert!(parse(&hoa).unwrap().equivalent(&words).is_ok());
+        assert!(parse(&hoa.replace("[0&!1]", "[0&1]")).is_err());
+    }
+}
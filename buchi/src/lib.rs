@@ -1,8 +1,20 @@
+pub mod alternating;
+#[cfg(any(test, feature = "testing"))]
+pub mod arbitrary;
+pub mod error;
+pub mod hoa;
 pub mod nba;
+pub mod never;
 
 #[cfg(test)]
 mod test {
+    use super::alternating::AlternatingBuchi;
+    use super::error::Error;
     use super::nba::*;
+    use itertools::Itertools;
+    use ltl::alphabet::{ApSet, Literals};
+    use parity::Owner;
+    use std::convert::Infallible;
     #[test]
     pub fn two_state_nba() {
         let mut nba = Buchi::new();
@@ -14,8 +26,8 @@ mod test {
         nba.add_transition(s2, s1, w.clone());
 
         println!("{}", nba);
-        assert!(nba.transitions(s1).unwrap().get(&w).unwrap().contains(&s2));
-        assert!(nba.transitions(s2).unwrap().get(&w).unwrap().contains(&s1));
+        assert!(nba.successors(s1).unwrap().get(&w).unwrap().contains(&s2));
+        assert!(nba.successors(s2).unwrap().get(&w).unwrap().contains(&s1));
     }
 
     #[test]
@@ -31,9 +43,9 @@ mod test {
         nba.add_transition(s1, s3, "b");
         nba.add_transition(s3, s2, "b");
 
-        let s1_trans = nba.transitions(s1).unwrap();
-        let s2_trans = nba.transitions(s2).unwrap();
-        let s3_trans = nba.transitions(s3).unwrap();
+        let s1_trans = nba.successors(s1).unwrap();
+        let s2_trans = nba.successors(s2).unwrap();
+        let s3_trans = nba.successors(s3).unwrap();
 
         assert!(s1_trans.get(&a).unwrap().contains(&s2));
         assert!(s1_trans.get(&b).unwrap().contains(&s3));
@@ -100,7 +112,7 @@ mod test {
         nba.add_transition(f, g, "z");
 
         nba.set_initial_state(a);
-        nba.set_accepting_state(f);
+        nba.add_accepting_set([f]);
 
         let trace = nba.verify();
         assert!(trace.is_err(), "{:?}", trace);
@@ -119,16 +131,13 @@ mod test {
         nba.add_transition(s2, s1, "b");
 
         nba.set_initial_state(s1);
-        nba.set_accepting_state(s2);
+        nba.add_accepting_set([s2]);
 
         let result = nba.verify();
         assert!(result.is_err(), "{:?}", result);
         let trace = result.unwrap_err();
-        assert!(
-            format!("{}", trace) == String::from("(a)(b,a)ω"),
-            "{}",
-            trace
-        )
+        // The prefix is rolled into the cycle
+        assert!(format!("{}", trace) == "(a, b)ʷ", "{}", trace)
     }
 
     #[test]
@@ -142,10 +151,41 @@ mod test {
 
         nba.set_initial_state(s1);
 
+        // Without acceptance sets every infinite run is accepting
+        assert!(nba.verify().is_err());
+
+        nba.add_accepting_set([]);
         let result = nba.verify();
         assert!(result.is_ok(), "{:?}", result);
     }
 
+    #[test]
+    pub fn verify_generalized() {
+        // s0 and s1 are only visited on different cycles through s2
+        let mut gnba = Buchi::new();
+        let s0 = gnba.new_state();
+        let s1 = gnba.new_state();
+        let s2 = gnba.new_state();
+        let s3 = gnba.new_state();
+
+        gnba.add_transition(s2, s0, "a");
+        gnba.add_transition(s0, s2, "a");
+        gnba.add_transition(s2, s1, "b");
+        gnba.add_transition(s1, s2, "b");
+        gnba.add_transition(s3, s3, "c");
+
+        gnba.set_initial_state(s2);
+        gnba.add_accepting_set([s0]);
+        gnba.add_accepting_set([s1]);
+        let trace = gnba.verify().unwrap_err();
+        assert!(trace.omega_words.contains(&Word::from("a")), "{}", trace);
+        assert!(trace.omega_words.contains(&Word::from("b")), "{}", trace);
+
+        // s3 lies on a cycle but is not reachable
+        gnba.add_accepting_set([s3]);
+        assert!(gnba.verify().is_ok());
+    }
+
     #[test]
     pub fn gnba_to_nba() {
         let mut gnba = Buchi::new();
@@ -158,18 +198,886 @@ mod test {
         gnba.add_transition(c, a, "z");
 
         gnba.set_initial_state(c);
-        gnba.set_accepting_state(b);
-        gnba.set_accepting_state(a);
+        gnba.add_accepting_set([b]);
+        gnba.add_accepting_set([a]);
 
         let nba = gnba.gnba_to_nba();
-        assert!(nba.states().len() == 6, "{:?}", nba.states());
-        // The gnba originally had 2 accepting states, the resulting nba should only have one
-        assert!(gnba.accepting_states().len() == 2);
+        // (c, 0) (a, 0) (b, 0) (c, 1) (a, 1) are reachable, (b, 1) is not
+        assert!(nba.states().len() == 5, "{:?}", nba.states());
+        // The gnba originally had 2 accepting sets, the resulting nba should only have one
+        assert!(gnba.accepting_sets().len() == 2);
         assert!(
-            nba.accepting_states().len() == 1,
+            nba.accepting_sets().len() == 1,
             "{:?}",
-            nba.accepting_states()
+            nba.accepting_sets()
         );
         assert!(nba.verify().is_err(), "{}", nba);
+
+        // Labels and words are kept, the index of the next accepting set is appended
+        let mut labeled = Buchi::new();
+        let p = labeled.new_labeled_state("p".into());
+        let q = labeled.new_labeled_state("q".into());
+        labeled.add_transition(p, q, "x");
+        labeled.add_transition(q, p, "y");
+        labeled.set_initial_state(p);
+        labeled.add_accepting_set([p]);
+        labeled.add_accepting_set([q]);
+        let nba = labeled.gnba_to_nba();
+        let transitions = nba
+            .transitions()
+            .iter()
+            .map(|t| format!("{} -{}-> {}", t.from, t.label, t.to))
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(transitions, ["p (0) -x-> q (1)", "q (1) -y-> p (0)"]);
+        assert_eq!(nba.accepting_sets().len(), 1);
+        assert!(nba.accepting_sets()[0]
+            .iter()
+            .all(|s| nba.label(s) == Some("p (0)")));
+    }
+
+    #[test]
+    pub fn intersection() {
+        // Infinitely many a and infinitely many b respectively
+        let infinitely_often = |word: &str, other: &str| {
+            let mut nba = Buchi::new();
+            let seen = nba.new_state();
+            let waiting = nba.new_state();
+            for s in [seen, waiting] {
+                nba.add_transition(s, seen, word);
+                nba.add_transition(s, waiting, other);
+            }
+            nba.set_initial_state(waiting);
+            nba.add_accepting_set([seen]);
+            nba
+        };
+        let a = infinitely_often("a", "b");
+        let b = infinitely_often("b", "a");
+        let trace = a.intersect(&b).verify().unwrap_err();
+        assert!(trace.omega_words.contains(&Word::from("a")), "{}", trace);
+        assert!(trace.omega_words.contains(&Word::from("b")), "{}", trace);
+
+        // Only finitely many a
+        let mut finitely = Buchi::new();
+        let any = finitely.new_state();
+        let only_b = finitely.new_state();
+        finitely.add_transition(any, any, "a");
+        finitely.add_transition(any, any, "b");
+        finitely.add_transition(any, only_b, "b");
+        finitely.add_transition(only_b, only_b, "b");
+        finitely.set_initial_state(any);
+        finitely.add_accepting_set([only_b]);
+        assert!(a.intersect(&finitely).verify().is_ok());
+        assert!(b.intersect(&finitely).verify().is_err());
+    }
+
+    #[test]
+    pub fn guarded_intersection() {
+        let aps = |names: &[&str]| names.iter().collect::<ApSet>();
+        let mut left = Buchi::new();
+        left.set_aps(aps(&["a"]));
+        let p = left.new_state();
+        left.add_guarded_transition(p, p, Literals::empty().with(0, true));
+        left.set_initial_state(p);
+
+        let mut right = Buchi::new();
+        right.set_aps(aps(&["b", "a"]));
+        let q = right.new_state();
+        right.add_guarded_transition(q, q, Literals::empty().with(0, true));
+        right.set_initial_state(q);
+
+        let product = left.intersect(&right);
+        let transitions = product.transitions();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].label, "a, b");
+
+        // a contradicts ¬a
+        let mut contradicting = Buchi::new();
+        contradicting.set_aps(aps(&["a"]));
+        let r = contradicting.new_state();
+        contradicting.add_guarded_transition(r, r, Literals::empty().with(0, false));
+        contradicting.set_initial_state(r);
+        assert!(left.intersect(&contradicting).transitions().is_empty());
+    }
+
+    #[test]
+    pub fn complement() {
+        // Infinitely many a over the words a and b
+        let mut nba = Buchi::new();
+        let seen = nba.new_state();
+        let waiting = nba.new_state();
+        for s in [seen, waiting] {
+            nba.add_transition(s, seen, "a");
+            nba.add_transition(s, waiting, "b");
+        }
+        nba.set_initial_state(waiting);
+        nba.add_accepting_set([seen]);
+
+        let complement = nba.complement();
+        assert!(complement.stats().states > nba.stats().states);
+        assert!(nba.intersect(&complement).verify().is_ok());
+        // The complement accepts exactly the words with finitely many a
+        let trace = complement.verify().unwrap_err();
+        assert!(trace.omega_words.iter().all(|w| w.id == "b"), "{}", trace);
+        let mut a_forever = Buchi::new();
+        let s = a_forever.new_state();
+        a_forever.add_transition(s, s, "a");
+        a_forever.add_transition(s, s, "b");
+        a_forever.set_initial_state(s);
+        assert!(complement.intersect(&a_forever).verify().is_err());
+
+        // The empty language is complemented to every word
+        let mut empty = Buchi::new();
+        let s = empty.new_state();
+        empty.set_aps(["a"].iter().collect());
+        empty.add_guarded_transition(s, s, Literals::empty().with(0, true));
+        empty.set_initial_state(s);
+        empty.add_accepting_set([]);
+        let universal = empty.complement();
+        assert_eq!(universal.stats().accepting_sets, 1);
+        let words = universal
+            .transitions()
+            .iter()
+            .map(|t| t.label)
+            .unique()
+            .sorted()
+            .join(" | ");
+        assert_eq!(words, "a | ¬a");
+        assert!(universal.verify().is_err());
+
+        // Infinitely many a by a ring of five states, which may be left for the accepting one
+        let mut ring = Buchi::new();
+        let states = (0..5).map(|_| ring.new_state()).collect_vec();
+        for (i, s) in states.iter().enumerate() {
+            ring.add_transition(*s, states[(i + 1) % 5], "a");
+            ring.add_transition(*s, states[0], "a");
+            ring.add_transition(*s, states[i.max(1)], "b");
+        }
+        ring.set_initial_state(states[1]);
+        ring.add_accepting_set([states[0]]);
+        let complement = ring.complement();
+        assert!(ring.intersect(&complement).verify().is_ok());
+        let words = |w: &[&str]| w.iter().map(Word::new).collect_vec();
+        assert!(complement.accepts(&words(&["a", "a"]), &words(&["b"])));
+        assert!(!complement.accepts(&words(&["b"]), &words(&["b", "a"])));
+    }
+
+    #[test]
+    pub fn inclusion() {
+        // Every word, infinitely many a and a forever
+        let mut all = Buchi::new();
+        let s = all.new_state();
+        all.add_transition(s, s, "a");
+        all.add_transition(s, s, "b");
+        all.set_initial_state(s);
+
+        let mut often = Buchi::new();
+        let seen = often.new_state();
+        let waiting = often.new_state();
+        for s in [seen, waiting] {
+            often.add_transition(s, seen, "a");
+            often.add_transition(s, waiting, "b");
+        }
+        often.set_initial_state(waiting);
+        often.add_accepting_set([seen]);
+
+        let mut only_a = Buchi::new();
+        let s = only_a.new_state();
+        only_a.add_transition(s, s, "a");
+        only_a.set_initial_state(s);
+
+        assert!(all.includes(&often).is_ok());
+        assert!(often.includes(&only_a).is_ok());
+        // Words with b are missing in the alphabet of only_a
+        let trace = only_a.includes(&often).unwrap_err();
+        assert!(
+            trace
+                .words
+                .iter()
+                .chain(&trace.omega_words)
+                .any(|w| w.id == "b"),
+            "{}",
+            trace
+        );
+        assert!(often.equivalent(&all).is_err());
+        assert!(often.equivalent(&often.gnba_to_nba()).is_ok());
+    }
+
+    #[test]
+    pub fn simulation_reduction() {
+        // Two copies of a loop over a with one accepting state each, a dead state and an
+        // unreachable one
+        let mut nba = Buchi::new();
+        let init = nba.new_state();
+        let left = nba.new_state();
+        let right = nba.new_state();
+        let dead = nba.new_state();
+        let unreachable = nba.new_state();
+        nba.add_transition(init, left, "a");
+        nba.add_transition(init, right, "a");
+        nba.add_transition(left, left, "a");
+        nba.add_transition(right, right, "a");
+        nba.add_transition(init, dead, "b");
+        nba.add_transition(unreachable, init, "a");
+        nba.set_initial_state(init);
+        nba.add_accepting_set([left, right]);
+
+        let direct = nba.direct_simulation();
+        assert!(direct.contains(&(left, right)) && direct.contains(&(right, left)));
+        assert!(direct.contains(&(init, init)));
+        assert!(!direct.contains(&(left, init)));
+
+        // The initial state and the loops delayed simulate each other, all of them accept a^ω
+        let reduced = nba.reduce();
+        assert_eq!(reduced.stats().states, 1, "{}", reduced);
+        assert!(reduced.equivalent(&nba).is_ok());
+
+        // Delayed simulation only requires the accepting visit later on
+        let mut delayed = Buchi::new();
+        let p = delayed.new_state();
+        let q = delayed.new_state();
+        let r = delayed.new_state();
+        delayed.add_transition(p, q, "a");
+        delayed.add_transition(q, p, "a");
+        delayed.add_transition(r, r, "a");
+        delayed.set_initial_state(p);
+        delayed.set_initial_state(r);
+        delayed.add_accepting_set([p, r]);
+        let simulation = delayed.delayed_simulation();
+        assert!(simulation.contains(&(q, r)) && simulation.contains(&(p, r)));
+        assert!(simulation.contains(&(r, q)));
+        assert!(!delayed.direct_simulation().contains(&(r, q)));
+        let reduced = delayed.reduce();
+        assert_eq!(reduced.stats().states, 1, "{}", reduced);
+        assert!(reduced.equivalent(&delayed).is_ok());
+    }
+
+    /// Whether the automaton accepts prefix cycle^ω
+    fn dpa_accepts(automaton: &DeterministicAutomaton, prefix: &[&Word], cycle: &[&Word]) -> bool {
+        let state = prefix
+            .iter()
+            .fold(0, |s, w| automaton.successor(s, w).unwrap().0);
+        // Iterate the cycle until the state at its start repeats
+        let mut starts = vec![state];
+        let mut priorities = vec![];
+        loop {
+            let mut state = *starts.last().unwrap();
+            let mut lowest = usize::MAX;
+            for w in cycle {
+                let (next, priority) = automaton.successor(state, w).unwrap();
+                lowest = lowest.min(priority);
+                state = next;
+            }
+            priorities.push(lowest);
+            if let Some(i) = starts.iter().position(|s| *s == state) {
+                return priorities[i..].iter().min().unwrap() % 2 == 0;
+            }
+            starts.push(state);
+        }
+    }
+
+    #[test]
+    pub fn determinize_eventually_always() {
+        // F G a, the classic language without a deterministic Büchi automaton
+        let mut nba = Buchi::new();
+        nba.set_aps(["a"].iter().collect::<ApSet>());
+        let waiting = nba.new_state();
+        let stable = nba.new_state();
+        nba.add_guarded_transition(waiting, waiting, Literals::empty());
+        nba.add_guarded_transition(waiting, stable, Literals::empty().with(0, true));
+        nba.add_guarded_transition(stable, stable, Literals::empty().with(0, true));
+        nba.set_initial_state(waiting);
+        nba.add_accepting_set([stable]);
+
+        let dpa = nba.determinize();
+        let letters = dpa.letters().cloned().collect_vec();
+        let (not_a, a) = (&letters[0], &letters[1]);
+        assert_eq!(a.id, "a");
+        assert!(dpa_accepts(&dpa, &[], &[a]));
+        assert!(dpa_accepts(&dpa, &[not_a, not_a, a], &[a]));
+        assert!(!dpa_accepts(&dpa, &[], &[not_a]));
+        assert!(!dpa_accepts(&dpa, &[a], &[a, not_a]));
+
+        let hoa = dpa.hoa(Condition::Parity);
+        assert!(hoa.contains("acc-name: parity min even"), "{}", hoa);
+        assert!(dpa.hoa(Condition::Rabin).contains("Fin(0)&Inf(1)"));
+    }
+
+    #[test]
+    pub fn determinize_words() {
+        // Infinitely many a and infinitely many b over plain words, generalized acceptance
+        let mut gnba = Buchi::new();
+        let after_a = gnba.new_state();
+        let after_b = gnba.new_state();
+        for s in [after_a, after_b] {
+            gnba.add_transition(s, after_a, "a");
+            gnba.add_transition(s, after_b, "b");
+        }
+        gnba.set_initial_state(after_a);
+        gnba.add_accepting_set([after_a]);
+        gnba.add_accepting_set([after_b]);
+
+        let dpa = gnba.determinize();
+        let (a, b) = (Word::from("a"), Word::from("b"));
+        assert!(dpa_accepts(&dpa, &[], &[&a, &b]));
+        assert!(dpa_accepts(&dpa, &[&a, &a], &[&b, &a, &a]));
+        assert!(!dpa_accepts(&dpa, &[&b], &[&a]));
+        assert!(!dpa_accepts(&dpa, &[&a, &b], &[&b]));
+    }
+
+    #[test]
+    pub fn transition_acceptance() {
+        // Infinitely many a with the a transition accepting instead of a state
+        let mut tgba = Buchi::new();
+        let s = tgba.new_labeled_state("s".into());
+        tgba.add_transition(s, s, "a");
+        tgba.add_transition(s, s, "b");
+        tgba.set_initial_state(s);
+        tgba.add_accepting_transitions([(s, Word::new("a"), s)]);
+
+        let trace = tgba.verify().unwrap_err();
+        assert!(trace.omega_words.iter().any(|w| w.id == "a"));
+        let hoa = tgba.hoa();
+        assert!(
+            hoa.contains("[0&!1] 0 {0}") && !hoa.contains("[!0&1] 0 {"),
+            "{}",
+            hoa
+        );
+
+        let state_based = tgba.state_based();
+        assert_eq!(state_based.stats().states, 2);
+        assert!(state_based.equivalent(&tgba).is_ok());
+
+        // An accepting transition outside of every cycle is taken at most once
+        let mut once = Buchi::new();
+        let p = once.new_state();
+        let q = once.new_state();
+        once.add_transition(p, q, "a");
+        once.add_transition(q, q, "b");
+        once.set_initial_state(p);
+        once.add_accepting_transitions([(p, Word::new("a"), q)]);
+        assert!(once.verify().is_ok());
+    }
+
+    #[test]
+    pub fn membership() {
+        // Infinitely many a over the words a and b
+        let mut often = Buchi::new();
+        let seen = often.new_state();
+        let waiting = often.new_state();
+        for s in [seen, waiting] {
+            often.add_transition(s, seen, "a");
+            often.add_transition(s, waiting, "b");
+        }
+        often.set_initial_state(waiting);
+        often.add_accepting_set([seen]);
+        let words = |w: &[&str]| w.iter().map(Word::new).collect_vec();
+        assert!(often.accepts(&words(&["b"]), &words(&["b", "a"])));
+        assert!(!often.accepts(&words(&["a", "a"]), &words(&["b"])));
+        assert!(!often.accepts(&words(&["c"]), &words(&["a"])));
+        assert!(!often.accepts(&[], &[]));
+
+        // Letters with more literals than a guard can take its transition
+        let aps = ["a", "b"].iter().collect::<ApSet>();
+        let mut guarded = Buchi::new();
+        guarded.set_aps(aps.clone());
+        let s = guarded.new_state();
+        guarded.add_guarded_transition(s, s, Literals::empty().with(0, true));
+        guarded.set_initial_state(s);
+        let letter = |a: bool, b: bool| Word::guard(&aps, Literals::empty().with(0, a).with(1, b));
+        assert!(guarded.accepts(&[], &[letter(true, false), letter(true, true)]));
+        assert!(!guarded.accepts(&[letter(false, true)], &[letter(true, true)]));
+    }
+
+    #[test]
+    pub fn valuation_guards() {
+        // Words naming valuations as used by transition systems
+        let mut nba = Buchi::new();
+        let q0 = nba.new_state();
+        let q1 = nba.new_state();
+        nba.add_transition(q0, q0, "{}");
+        nba.add_transition(q0, q1, "{a, b}");
+        nba.add_transition(q1, q1, "{true}");
+        nba.set_initial_state(q0);
+        nba.add_accepting_set([q1]);
+
+        let aps = ["a", "b"].iter().collect::<ApSet>();
+        let guarded = nba.guard_valuation_words(&aps);
+        let letter = |a: bool, b: bool| Word::guard(&aps, Literals::empty().with(0, a).with(1, b));
+        assert!(guarded.accepts(
+            &[letter(false, false), letter(true, true)],
+            &[letter(true, false)]
+        ));
+        assert!(!guarded.accepts(&[letter(true, false)], &[letter(true, true)]));
+
+        // The product with a guard matches on valuations, b ∨ ¬a holds for {} and {a, b}
+        let mut either = Buchi::new();
+        either.set_aps(aps.clone());
+        let s = either.new_state();
+        let guard = aps
+            .guard(&ltl::Formula::parse("| b !a").unwrap().root_expr)
+            .unwrap();
+        either.add_guard_transition(s, s, &guard);
+        either.set_initial_state(s);
+        assert_eq!(either.stats().transitions, 2);
+        let product = guarded.intersect(&either);
+        assert!(product.verify().is_err());
+        assert!(product.accepts(&[], &[letter(true, true)]));
+        assert!(!product.accepts(&[letter(true, false)], &[letter(true, true)]));
+    }
+
+    #[test]
+    pub fn rabin_and_streett() {
+        // Eventually always a as deterministic Rabin automaton
+        let mut nba = Buchi::new();
+        nba.set_aps(["a"].iter().collect::<ApSet>());
+        let waiting = nba.new_state();
+        let stable = nba.new_state();
+        nba.add_guarded_transition(waiting, waiting, Literals::empty());
+        nba.add_guarded_transition(waiting, stable, Literals::empty().with(0, true));
+        nba.add_guarded_transition(stable, stable, Literals::empty().with(0, true));
+        nba.set_initial_state(waiting);
+        nba.add_accepting_set([stable]);
+        let rabin = nba.determinize().to_rabin();
+        let trace = rabin.verify().unwrap_err();
+        assert!(trace.omega_words.iter().all(|w| w.id == "a"), "{}", trace);
+        let parsed = crate::hoa::parse_rabin(&rabin.hoa()).unwrap();
+        assert_eq!(parsed.pairs().len(), rabin.pairs().len());
+        assert!(parsed.verify().is_err());
+
+        // Rabin pairs whose fin set contains every cycle are never satisfied
+        let mut looping = RabinAutomaton::new(nba.clone());
+        looping.add_pair([waiting, stable], [stable]);
+        assert!(looping.verify().is_ok());
+
+        // The last word read is remembered in the state
+        let mut words = Buchi::new();
+        let after_a = words.new_state();
+        let after_b = words.new_state();
+        for s in [after_a, after_b] {
+            words.add_transition(s, after_a, "a");
+            words.add_transition(s, after_b, "b");
+        }
+        words.set_initial_state(after_a);
+
+        // Infinitely many a imply infinitely many b
+        let mut streett = StreettAutomaton::new(words.clone());
+        streett.add_pair([after_a], [after_b]);
+        let trace = streett.verify().unwrap_err();
+        assert!(trace.omega_words.iter().any(|w| w.id == "b"), "{}", trace);
+        // Neither word may occur infinitely often
+        let mut finite = StreettAutomaton::new(words);
+        finite.add_pair([after_a], []);
+        finite.add_pair([after_b], []);
+        assert!(finite.verify().is_ok());
+
+        let parsed = crate::hoa::parse_streett(&streett.hoa()).unwrap();
+        assert_eq!(parsed.pairs().len(), 1);
+        assert!(parsed.verify().is_err());
+        assert!(crate::hoa::parse_streett(&finite.hoa())
+            .unwrap()
+            .verify()
+            .is_ok());
+        assert!(crate::hoa::parse_rabin(&finite.hoa()).is_err());
+    }
+
+    #[test]
+    pub fn trim() {
+        let mut nba = Buchi::new();
+        let unreachable = nba.new_labeled_state("unreachable".into());
+        let init = nba.new_labeled_state("init".into());
+        let dead = nba.new_labeled_state("dead".into());
+        let accepting = nba.new_labeled_state("accepting".into());
+        nba.add_transition(unreachable, init, "a");
+        nba.add_transition(init, dead, "b");
+        nba.add_transition(dead, dead, "b");
+        nba.add_transition(init, accepting, "a");
+        nba.add_transition(accepting, accepting, "a");
+        nba.set_initial_state(init);
+        nba.add_accepting_set([accepting]);
+
+        let trimmed = nba.trim();
+        let states = trimmed.states().into_iter().sorted().collect_vec();
+        assert_eq!(states, [State { id: 0 }, State { id: 1 }]);
+        assert_eq!(trimmed.label(&states[0]), Some("init"));
+        assert!(trimmed.equivalent(&nba).is_ok());
+        // Exports only contain the trimmed automaton
+        assert!(nba.hoa().contains("States: 2"));
+        assert!(!nba.to_dot().contains("dead"));
+    }
+
+    #[test]
+    pub fn dot_style() {
+        let mut nba = Buchi::new();
+        let p = nba.new_labeled_state("p \"quoted\"".into());
+        let q = nba.new_labeled_state("q".into());
+        nba.add_transition(p, q, "a");
+        nba.add_transition(p, q, "b");
+        nba.add_transition(q, q, "a");
+        nba.set_initial_state(p);
+        nba.add_accepting_set([q]);
+
+        let dot = nba.to_dot();
+        assert!(dot.contains("rankdir = LR"));
+        assert!(
+            dot.contains("s0 [label = \"p \\\"quoted\\\"\", shape = circle]"),
+            "{}",
+            dot
+        );
+        assert!(dot.contains("s1 [label = \"q {0}\", shape = doublecircle]"));
+        assert!(dot.contains("s0 -> s1 [label = \"a | b\"]"));
+        assert!(dot.contains("init0 -> s0"));
+
+        let plain = nba.to_dot_with(&DotStyle {
+            acceptance: false,
+            initial_arrows: false,
+            merge_edges: false,
+            labels: false,
+            rankdir: RankDir::TopBottom,
+        });
+        assert!(plain.contains("s1 [label = \"s1\", shape = circle]"));
+        assert!(plain.contains("s0 -> s1 [label = \"a\"]\ns0 -> s1 [label = \"b\"]"));
+        assert!(!plain.contains("init"));
+    }
+
+    #[test]
+    pub fn miyano_hayashi() {
+        // G F a: the globally spawns an eventually at every position
+        let aps = ["a"].iter().collect::<ApSet>();
+        let mut aba = AlternatingBuchi::new();
+        aba.set_aps(aps.clone());
+        let globally = aba.new_state("G F a".into());
+        let finally = aba.new_state("F a".into());
+        aba.add_transition(globally, Literals::empty(), [globally, finally]);
+        aba.add_transition(finally, Literals::empty().with(0, true), []);
+        aba.add_transition(finally, Literals::empty(), [finally]);
+        aba.add_initial([globally]);
+        aba.set_accepting(globally);
+
+        let nba = aba.to_nba();
+        let letter = |a: bool| Word::guard(&aps, Literals::empty().with(0, a));
+        assert!(nba.accepts(&[], &[letter(false), letter(true)]));
+        assert!(nba.accepts(&[letter(false)], &[letter(true)]));
+        assert!(!nba.accepts(&[letter(true)], &[letter(false)]));
+        // Without universal branching the eventually may be dropped
+        let mut existential = aba.clone();
+        existential.add_transition(globally, Literals::empty(), [globally]);
+        assert!(existential.to_nba().accepts(&[], &[letter(false)]));
+    }
+
+    #[test]
+    pub fn parity_game() {
+        // Infinitely many a
+        let mut often = Buchi::new();
+        let seen = often.new_state();
+        let waiting = often.new_state();
+        for s in [seen, waiting] {
+            often.add_transition(s, seen, "a");
+            often.add_transition(s, waiting, "b");
+        }
+        often.set_initial_state(waiting);
+        often.add_accepting_set([seen]);
+
+        // The owner of b decides whether to return to a
+        let game = |owner: Owner| {
+            let mut arena = Arena::new();
+            let a = arena.add_vertex(Owner::Odd, Word::new("a"));
+            let b = arena.add_vertex(owner, Word::new("b"));
+            arena.add_edge(a, b);
+            arena.add_edge(b, a);
+            arena.add_edge(b, b);
+            arena.set_initial(a);
+            let graph = often.to_parity_game(&arena);
+            let solution = graph.zielonka();
+            solution.even_region.iter().any(|v| v.id == 0)
+        };
+        assert!(game(Owner::Even));
+        // As a Kripke structure the path staying in b is rejected
+        assert!(!game(Owner::Odd));
+
+        // Plays reading other words are lost
+        let mut arena = Arena::new();
+        let c = arena.add_vertex(Owner::Even, Word::new("c"));
+        arena.add_edge(c, c);
+        arena.set_initial(c);
+        let graph = often.to_parity_game(&arena);
+        assert!(graph.zielonka().odd_region.iter().any(|v| v.id == 0));
+    }
+
+    /// Counter modulo the length, reading a when wrapping around
+    struct Counter(usize);
+
+    impl TransitionSystem for Counter {
+        type State = usize;
+        type Label = Word;
+        type Error = Infallible;
+
+        fn initial_states(&self) -> Vec<usize> {
+            vec![0]
+        }
+
+        fn successors(&self, i: &usize) -> Result<Vec<(Word, usize)>, Infallible> {
+            let word = if i + 1 == self.0 { "a" } else { "b" };
+            Ok(vec![(Word::new(word), (i + 1) % self.0)])
+        }
+    }
+
+    #[test]
+    pub fn lazy_product() {
+        // Infinitely many a
+        let mut often = Buchi::new();
+        let seen = often.new_state();
+        let waiting = often.new_state();
+        for s in [seen, waiting] {
+            often.add_transition(s, seen, "a");
+            often.add_transition(s, waiting, "b");
+        }
+        often.set_initial_state(waiting);
+        often.add_accepting_set([seen]);
+
+        let counter = Counter(4);
+        let trace = Product::new(&counter, &often).verify().unwrap_err();
+        assert!(often.accepts(&trace.words, &trace.omega_words));
+        assert_eq!(trace.omega_words.len(), 4);
+
+        // Only b, the counter can not wrap around
+        let mut never = Buchi::new();
+        let s = never.new_state();
+        never.add_transition(s, s, "b");
+        never.set_initial_state(s);
+        never.add_accepting_set([s]);
+        let product = Product::new(&counter, &never);
+        assert!(product.verify().is_ok());
+        assert_eq!(product.successors(&(3, s)), Ok(vec![]));
+
+        // Labels of states are the sets of propositions holding in them
+        let mut labeled = Buchi::new();
+        let s = labeled.new_labeled_state("{a, b}".into());
+        assert!(labeled.holds(&s, "b") && !labeled.holds(&s, "c"));
+    }
+
+    #[test]
+    pub fn shortest_lasso() {
+        // The accepting state closest to the start lies on a long cycle, the one further away on
+        // a self loop
+        let mut nba = Buchi::new();
+        let start = nba.new_state();
+        let near = nba.new_state();
+        let far = nba.new_state();
+        let detour = (0..4).map(|_| nba.new_state()).collect_vec();
+        nba.add_transition(start, near, "a");
+        nba.add_transition(near, detour[0], "b");
+        for (p, q) in detour.iter().tuple_windows() {
+            nba.add_transition(*p, *q, "b");
+        }
+        nba.add_transition(detour[3], near, "b");
+        nba.add_transition(start, detour[0], "c");
+        nba.add_transition(detour[0], far, "c");
+        nba.add_transition(far, far, "c");
+        nba.set_initial_state(start);
+        nba.add_accepting_set([near, far]);
+
+        let trace = nba.verify().unwrap_err();
+        assert_eq!(format!("{}", trace), "(c)ʷ");
+
+        // Repetitions of the cycle are cut and the prefix is rolled into it
+        let words = |w: &[&str]| w.iter().map(Word::new).collect_vec();
+        let trace = Trace::new(words(&["a", "b", "a"]), words(&["b", "a", "b", "a"])).minimize();
+        assert_eq!(format!("{}", trace), "(a, b)ʷ");
+        let trace = Trace::new(words(&["a"]), words(&["b", "c"])).minimize();
+        assert_eq!(format!("{}", trace), "a, (b, c)ʷ");
+    }
+
+    #[test]
+    pub fn labels_through_transformations() {
+        let mut gnba = Buchi::new();
+        let p = gnba.new_labeled_state("p".into());
+        let q = gnba.new_state();
+        gnba.add_transition(p, q, "a");
+        gnba.add_transition(q, p, "b");
+        gnba.set_initial_state(p);
+        gnba.add_accepting_set([p]);
+        gnba.add_accepting_set([q]);
+        assert_eq!(gnba.label_of(q), "s1");
+
+        // Unlabeled states are named after their id in composed labels
+        let nba = gnba.gnba_to_nba();
+        let labels = nba
+            .states()
+            .into_iter()
+            .map(|s| nba.label_of(s))
+            .sorted()
+            .collect_vec();
+        assert_eq!(labels, ["p (0)", "s1 (1)"]);
+        let product = gnba.intersect(&nba);
+        let initial = *product.initial_states().iter().next().unwrap();
+        assert_eq!(product.label_of(initial), "(p, p (0))");
+
+        let mut renamed = gnba.clone();
+        renamed.rename(q, "q".into());
+        assert_eq!(renamed.label(&q), Some("q"));
+        assert_eq!(renamed.intersect(&gnba).label_of(initial), "(p, p)");
+    }
+
+    #[test]
+    pub fn safety_subsets() {
+        // Always a, the second state guesses that b never occurs
+        let mut nba = Buchi::new();
+        let p = nba.new_labeled_state("p".into());
+        let q = nba.new_labeled_state("q".into());
+        nba.add_transition(p, p, "a");
+        nba.add_transition(p, q, "a");
+        nba.add_transition(q, q, "a");
+        nba.add_transition(q, p, "b");
+        nba.set_initial_state(p);
+        nba.add_accepting_set([p, q]);
+        assert!(nba.is_safety());
+
+        let complement = nba.complement();
+        // {p}, {p, q} and the empty set
+        assert_eq!(complement.states().len(), 3);
+        assert!(nba.intersect(&complement).verify().is_ok());
+        let words = |w: &[&str]| w.iter().map(Word::new).collect_vec();
+        assert!(complement.accepts(&words(&["a", "b", "b"]), &words(&["a"])));
+        assert!(!complement.accepts(&words(&["a", "b"]), &words(&["a"])));
+
+        let dpa = nba.determinize();
+        assert_eq!(dpa.states(), 3);
+        assert_eq!(dpa.priorities(), 2);
+        let (a, b) = (Word::new("a"), Word::new("b"));
+        assert!(dpa_accepts(&dpa, &[&a, &b], &[&a]));
+        assert!(!dpa_accepts(&dpa, &[&b], &[&a]));
+
+        // An accepting set without q is no safety condition
+        let mut buchi = nba.clone();
+        buchi.add_accepting_set([p]);
+        assert!(!buchi.is_safety());
+    }
+
+    #[test]
+    pub fn checked_builder() {
+        let mut builder = BuchiBuilder::new();
+        let p = builder.named_state("p").unwrap();
+        let q = builder.named_state("q").unwrap();
+        builder
+            .transition(p, q, "a")
+            .unwrap()
+            .named_transition("q", "p", "b")
+            .unwrap()
+            .initial(p)
+            .unwrap()
+            .accepting_set([q])
+            .unwrap();
+        assert_eq!(builder.state("q").unwrap(), q);
+        assert!(matches!(
+            builder.named_state("p"),
+            Err(Error::DuplicateName(name)) if name == "p"
+        ));
+        assert!(matches!(
+            builder.named_transition("p", "r", "a"),
+            Err(Error::UnknownName(name)) if name == "r"
+        ));
+
+        // States of another automaton are rejected
+        let mut other = Buchi::new();
+        other.new_state();
+        other.new_state();
+        let foreign = other.new_state();
+        assert!(matches!(
+            builder.transition(p, foreign, "a"),
+            Err(Error::UnknownState(2))
+        ));
+        assert!(builder.initial(foreign).is_err());
+
+        let nba = builder.build();
+        assert_eq!(nba.transitions().len(), 2);
+        assert_eq!(format!("{}", nba.verify().unwrap_err()), "(a, b)ʷ");
+    }
+
+    #[test]
+    pub fn ts_product() {
+        let mut ts = Buchi::new();
+        let s0 = ts.new_labeled_state("{a, b}".into());
+        let s1 = ts.new_labeled_state("{a}".into());
+        let s2 = ts.new_labeled_state("{a}".into());
+        let s3 = ts.new_labeled_state("{a, b}".into());
+        for (from, to) in [(s0, s1), (s1, s3), (s3, s1), (s3, s2), (s2, s1), (s2, s0)] {
+            ts.add_transition(from, to, "");
+        }
+        ts.set_initial_state(s0);
+
+        // Eventually b right after an odd number of a
+        let mut a = Buchi::new();
+        let q0 = a.new_labeled_state("q0".into());
+        let q1 = a.new_labeled_state("q1".into());
+        let q2 = a.new_labeled_state("q2".into());
+        for (from, to, word) in [
+            (q0, q0, "{}"),
+            (q0, q0, "{b}"),
+            (q0, q1, "{a}"),
+            (q0, q1, "{a, b}"),
+            (q1, q1, "{}"),
+            (q1, q1, "{b}"),
+            (q1, q0, "{a}"),
+            (q1, q0, "{a, b}"),
+            (q1, q2, "{b}"),
+            (q1, q2, "{a, b}"),
+            (q2, q2, "{true}"),
+        ] {
+            a.add_transition(from, to, word);
+        }
+        a.set_initial_state(q0);
+        a.add_accepting_set([q2]);
+
+        let product = ts_and_buchi_product(&ts, &a);
+        assert_eq!(product.stats().states, 12);
+        // The automaton already read the label of the initial state
+        let initial = product.initial_states().iter().collect_vec();
+        assert_eq!(initial.len(), 1);
+        assert_eq!(product.label_of(*initial[0]), "<s0,q1>({a, b},q1)");
+        let accepting = &product.accepting_sets()[0];
+        assert_eq!(accepting.len(), 4);
+        assert!(accepting
+            .iter()
+            .all(|s| product.label_of(*s).ends_with(",q2)")));
+        assert!(product.verify().is_err());
+
+        // Guards read the labels like the sets they hold for
+        let aps = ApSet::from_iter(["a", "b"]);
+        let guarded = ts_and_buchi_product(&ts, &a.guard_valuation_words(&aps));
+        assert_eq!(guarded.stats(), product.stats());
+        // Guards are evaluated on the propositions, whatever order the label lists them in
+        let mut both = Buchi::new();
+        let s = both.new_labeled_state("{b, a}".into());
+        both.add_transition(s, s, "");
+        both.set_initial_state(s);
+        assert!(ts_and_buchi_product(&both, &a).verify().is_ok());
+        assert!(ts_and_buchi_product(&both, &a.guard_valuation_words(&aps))
+            .verify()
+            .is_err());
+
+        // Without b the automaton never reaches q2
+        let mut only_a = Buchi::new();
+        let s = only_a.new_labeled_state("{a}".into());
+        only_a.add_transition(s, s, "");
+        only_a.set_initial_state(s);
+        let product = ts_and_buchi_product(&only_a, &a);
+        assert_eq!(product.stats().states, 2);
+        assert!(product.accepting_sets()[0].is_empty());
+        assert!(product.verify().is_ok());
+    }
+
+    #[test]
+    pub fn golden_files() {
+        let input = include_str!("../golden/gfa-gfb.hoa");
+        let gnba = crate::hoa::parse(input).unwrap();
+        assert_eq!(gnba.hoa(), input.trim_end());
+        // The pairs of the NBA are numbered in the order they are reached
+        let nba = gnba.gnba_to_nba();
+        assert_eq!(
+            nba.hoa(),
+            include_str!("../golden/gfa-gfb-nba.hoa").trim_end()
+        );
+        assert_eq!(nba.to_dot(), include_str!("../golden/gfa-gfb-nba.dot"));
     }
 }
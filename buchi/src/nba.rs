@@ -1,10 +1,29 @@
-use bimap::BiMap;
+use crate::hoa::Propositions;
 use itertools::Itertools;
+use ltl::alphabet::{ApSet, Guard, Literals};
+use std::convert::Infallible;
 use std::fmt::Write;
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     fmt::Display,
 };
+
+mod builder;
+mod complement;
+mod determinize;
+mod game;
+mod pairs;
+mod product;
+mod safety;
+mod simulation;
+
+pub use builder::BuchiBuilder;
+pub use determinize::{Condition, DeterministicAutomaton};
+pub use game::Arena;
+pub use pairs::{Pair, RabinAutomaton, StreettAutomaton};
+pub use product::{ts_and_buchi_product, Product};
+pub use ts::TransitionSystem;
+
 // A buchi automaton consists of 5 elements:
 // - Q: set of states
 // - E: an alphabet
@@ -15,20 +34,27 @@ use std::{
 /// A non-deterministic buchi automata (nba)
 /// States are constructed with the automata and must only be used with the automata it is generated from.
 /// If States are constructed in another way and used with an automata this can cause panics or incorrect behavior.
+/// `BuchiBuilder` checks the states it is given instead.
 #[derive(Clone, Debug)]
 pub struct Buchi {
     // A State and it's transitions
     // These transitions take a word as input and return a set of new states
     states: HashMap<State, HashMap<Word, HashSet<State>>>,
-    accepting_sets: HashSet<BTreeSet<State>>,
+    accepting_sets: Vec<HashSet<State>>,
+    /// Acceptance sets of transitions, an accepting run has to take each of them infinitely often
+    accepting_transitions: Vec<HashSet<(State, Word, State)>>,
     initial_states: HashSet<State>,
     labels: HashMap<State, String>,
+    /// Atomic propositions of the guards of the transitions
+    aps: ApSet,
     size: usize,
 }
 
 #[derive(Debug, Eq, Clone, Hash, PartialEq)]
 pub struct Word {
     pub id: String,
+    /// Guard of the transition if the word is a set of literals over the atomic propositions
+    pub literals: Option<Literals>,
 }
 
 #[derive(Debug, Eq, Clone, Copy, Hash, PartialEq, PartialOrd, Ord)]
@@ -42,120 +68,225 @@ pub struct Trace {
     pub omega_words: Vec<Word>,
 }
 
+/// Distance of states with the parent and word they were reached by
+type BreadthFirstTree<'a> = HashMap<State, (usize, Option<(State, &'a Word)>)>;
+
+/// Size of an automaton, e.g. to compare an automaton with its complement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub states: usize,
+    pub transitions: usize,
+    pub accepting_sets: usize,
+}
+
+/// Options for rendering an automaton with `Buchi::to_dot_with`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DotStyle {
+    /// Append the acceptance sets to the states and transitions which belong to them, accepting
+    /// states of automata with one acceptance set are drawn with a double circle
+    pub acceptance: bool,
+    /// Draw arrows into the initial states
+    pub initial_arrows: bool,
+    /// Draw one edge between two states with the words of all parallel edges as label
+    pub merge_edges: bool,
+    /// Name the states by their labels instead of their ids
+    pub labels: bool,
+    pub rankdir: RankDir,
+}
+
+/// Direction in which graphviz lays out the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RankDir {
+    TopBottom,
+    #[default]
+    LeftRight,
+    BottomTop,
+    RightLeft,
+}
+
 pub struct Transition<'a> {
     pub from: &'a str,
     pub from_state: State,
     pub to: &'a str,
     pub to_state: State,
     pub label: &'a str,
+    pub literals: Option<Literals>,
 }
 
 // Formatting
 impl Buchi {
-    /// Tranform the automataon into HOA formatted string
+    /// Tranform the trimmed automataon into HOA formatted string
     pub fn hoa(&self) -> String {
-        let version = "HOA: v1".into();
-        let states = format!("States: {}", self.states.len());
-        let start = if self.initial_states.is_empty() {
-            "".into()
-        } else {
-            format!(
-                "Start: {}",
-                self.initial_states
-                    .iter()
-                    .sorted_by_key(|s| s.id)
-                    .map(|s| s.id.to_string())
-                    .collect::<Vec<_>>()
-                    .join(" & ")
-            )
-        };
-        let acceptance_sets: BiMap<_, _> = self.accepting_sets.iter().enumerate().collect();
-
-        // If there are 0 accepting states any run is accepted since this is a GNBA
-        let acceptance = if acceptance_sets.len() > 0 {
-            format!(
-                "Acceptance: {} {}",
-                acceptance_sets.len(),
-                acceptance_sets
-                    .iter()
-                    .sorted_by_key(|(id, _)| *id)
-                    .map(|(mapped_id, _)| format!("Inf({})", mapped_id))
-                    .collect::<Vec<_>>()
-                    .join("&")
-            )
-        } else {
-            "Acceptance: 0 t".into()
-        };
-
-        let header = vec![version, states, start, acceptance].join("\n");
+        self.trim().write_hoa()
+    }
 
-        let mut states = Vec::with_capacity(self.states.len());
+    fn write_hoa(&self) -> String {
+        let mut out = String::new();
+        writeln!(&mut out, "HOA: v1").unwrap();
+        writeln!(&mut out, "States: {}", self.states.len()).unwrap();
+        for initial in self.initial_states.iter().sorted() {
+            writeln!(&mut out, "Start: {}", initial.id).unwrap();
+        }
+        let propositions =
+            Propositions::new(&self.aps, self.states.values().flat_map(|t| t.keys()));
+        write!(&mut out, "{}", propositions.header()).unwrap();
+
+        // The sets of transitions follow the sets of states, without sets every run is accepting
+        let sets = self.accepting_sets.len() + self.accepting_transitions.len();
+        match sets {
+            0 => writeln!(&mut out, "acc-name: all").unwrap(),
+            1 => writeln!(&mut out, "acc-name: Buchi").unwrap(),
+            _ => writeln!(&mut out, "acc-name: generalized-Buchi {}", sets).unwrap(),
+        }
+        let condition = (0..sets).map(|i| format!("Inf({})", i)).join("&");
+        writeln!(
+            &mut out,
+            "Acceptance: {} {}",
+            sets,
+            if condition.is_empty() {
+                "t"
+            } else {
+                &condition
+            }
+        )
+        .unwrap();
+        writeln!(
+            &mut out,
+            "properties: trans-labels explicit-labels trans-acc"
+        )
+        .unwrap();
+        writeln!(&mut out, "--BODY--").unwrap();
 
         for (state, transitions) in self.states.iter().sorted_by_key(|(s, _)| s.id) {
-            let state_name = format!(
-                "State: {}{}",
-                state.id,
-                if let Some(label) = self.labels.get(&state) {
-                    format!(" \"{}\"", label)
-                } else {
-                    "".into()
-                }
-            );
-
-            let mut edges = vec![];
+            write!(&mut out, "State: {}", state.id).unwrap();
+            if let Some(label) = self.labels.get(state) {
+                write!(&mut out, " \"{}\"", label).unwrap();
+            }
+            writeln!(&mut out).unwrap();
+            let transitions = transitions
+                .iter()
+                .sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id));
             for (word, targets) in transitions {
-                for t in targets {
-                    let acceptance_ids: Vec<_> = acceptance_sets
+                for target in targets.iter().sorted() {
+                    // Visiting an accepting state is marked on the transitions into it
+                    let transition = (*state, word.clone(), *target);
+                    let marks = self
+                        .accepting_sets
                         .iter()
-                        .filter_map(|(i, s)| {
-                            if s.contains(&t) {
-                                Some(i.to_string())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
-                    let id = if acceptance_ids.is_empty() {
-                        "".into()
-                    } else {
-                        format!(" {{{}}}", acceptance_ids.join(" "))
-                    };
-                    edges.push(format!("\n  {{{}}} {}{}", word.id, t.id, id));
+                        .map(|set| set.contains(target))
+                        .chain(
+                            self.accepting_transitions
+                                .iter()
+                                .map(|set| set.contains(&transition)),
+                        )
+                        .positions(|marked| marked)
+                        .collect_vec();
+                    write!(&mut out, "  {} {}", propositions.label(word), target.id).unwrap();
+                    if !marks.is_empty() {
+                        write!(&mut out, " {{{}}}", marks.iter().join(" ")).unwrap();
+                    }
+                    writeln!(&mut out).unwrap();
                 }
             }
-
-            states.push(format!("{}{}", state_name, edges.join("")));
         }
+        write!(&mut out, "--END--").unwrap();
+        out
+    }
 
-        let body = format!("--BODY--\n{}\n--END--", states.join("\n"));
+    /// Transform the trimmed automaton into a graph in DOT format with the default style
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(&DotStyle::default())
+    }
 
-        format!("{}\n{}", header, body)
+    /// Transform the trimmed automaton into a graph in DOT format
+    pub fn to_dot_with(&self, style: &DotStyle) -> String {
+        self.trim().write_dot(style)
     }
 
-    pub fn to_dot(&self) -> String {
+    fn write_dot(&self, style: &DotStyle) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
         let mut out = String::new();
 
-        writeln!(&mut out, "digraph g {{\nmindist = 2.0").unwrap();
+        writeln!(
+            &mut out,
+            "digraph g {{\nmindist = 2.0\nrankdir = {}",
+            style.rankdir
+        )
+        .unwrap();
+        for state in self.states.keys().sorted() {
+            let mut label = match self.labels.get(state) {
+                Some(label) if style.labels => label.clone(),
+                _ => format!("s{}", state.id),
+            };
+            let marks = (0..self.accepting_sets.len())
+                .filter(|i| self.accepting_sets[*i].contains(state))
+                .collect_vec();
+            if style.acceptance && !marks.is_empty() {
+                label = format!("{} {{{}}}", label, marks.iter().join(" "));
+            }
+            // Accepting states of NBAs are drawn as usual
+            let shape = if style.acceptance && self.accepting_sets.len() == 1 && !marks.is_empty() {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            writeln!(
+                &mut out,
+                "s{} [label = \"{}\", shape = {}]",
+                state.id,
+                escape(&label),
+                shape
+            )
+            .unwrap();
+        }
+
+        // Labels of the edges between each pair of states, marked with their transition sets
+        let mut edges: BTreeMap<(State, State), Vec<String>> = BTreeMap::new();
         for (state, transitions) in &self.states {
             for (word, targets) in transitions {
                 for target in targets {
-                    writeln!(
-                        &mut out,
-                        "\"{}\" -> {{\"{}\"}} [label = \"{}\"]",
-                        self.labels[&state], self.labels[&target], word.id
-                    )
-                    .unwrap();
+                    let transition = (*state, word.clone(), *target);
+                    let marks = (0..self.accepting_transitions.len())
+                        .filter(|i| self.accepting_transitions[*i].contains(&transition))
+                        .map(|i| self.accepting_sets.len() + i)
+                        .collect_vec();
+                    let label = if style.acceptance && !marks.is_empty() {
+                        format!("{} {{{}}}", word.id, marks.iter().join(" "))
+                    } else {
+                        word.id.clone()
+                    };
+                    edges.entry((*state, *target)).or_default().push(label);
                 }
             }
         }
+        for ((source, target), mut labels) in edges {
+            labels.sort();
+            let labels = if style.merge_edges {
+                vec![labels.join(" | ")]
+            } else {
+                labels
+            };
+            for label in labels {
+                writeln!(
+                    &mut out,
+                    "s{} -> s{} [label = \"{}\"]",
+                    source.id,
+                    target.id,
+                    escape(&label)
+                )
+                .unwrap();
+            }
+        }
 
-        for (i, initial) in self.initial_states.iter().enumerate() {
-            writeln!(
-                &mut out,
-                "init{0} [label=\"\", shape=point]\ninit{0} -> \"{1}\"",
-                i, self.labels[initial]
-            )
-            .unwrap();
+        if style.initial_arrows {
+            for (i, initial) in self.initial_states.iter().sorted().enumerate() {
+                writeln!(
+                    &mut out,
+                    "init{0} [label=\"\", shape=point]\ninit{0} -> s{1}",
+                    i, initial.id
+                )
+                .unwrap();
+            }
         }
 
         out.push('}');
@@ -170,15 +301,41 @@ impl Buchi {
         Buchi {
             states: HashMap::new(),
             labels: HashMap::new(),
-            accepting_sets: HashSet::new(),
+            accepting_sets: Vec::new(),
+            accepting_transitions: Vec::new(),
             initial_states: HashSet::new(),
+            aps: ApSet::new(),
             size: 0,
         }
     }
 
+    /// Atomic propositions the guards of the transitions refer to
+    pub fn aps(&self) -> &ApSet {
+        &self.aps
+    }
+
+    pub fn set_aps(&mut self, aps: ApSet) {
+        self.aps = aps;
+    }
+
+    /// Add an acceptance set, an accepting run visits each of the sets infinitely often
     pub fn add_accepting_set(&mut self, set: impl IntoIterator<Item = State>) {
-        self.accepting_sets
-            .insert(BTreeSet::from_iter(set.into_iter()));
+        let set = HashSet::from_iter(set);
+        if !self.accepting_sets.contains(&set) {
+            self.accepting_sets.push(set);
+        }
+    }
+
+    /// Add an acceptance set of transitions, an accepting run takes a transition of each of the
+    /// sets infinitely often
+    pub fn add_accepting_transitions(
+        &mut self,
+        set: impl IntoIterator<Item = (State, Word, State)>,
+    ) {
+        let set = HashSet::from_iter(set);
+        if !self.accepting_transitions.contains(&set) {
+            self.accepting_transitions.push(set);
+        }
     }
 
     /// Generate a new state. The return value is used to construct transitions and set the initial/accepting states
@@ -225,6 +382,21 @@ impl Buchi {
             .insert(target);
     }
 
+    /// Add a transition which can be taken by every valuation satisfying the literals, the
+    /// literals refer to the atomic propositions of the automaton
+    pub fn add_guarded_transition(&mut self, source: State, target: State, literals: Literals) {
+        let word = Word::guard(&self.aps, literals);
+        self.add_transition(source, target, word);
+    }
+
+    /// Add a guarded transition for every cube of the guard, nothing is added if the guard is
+    /// unsatisfiable
+    pub fn add_guard_transition(&mut self, source: State, target: State, guard: &Guard) {
+        for cube in guard.cubes() {
+            self.add_guarded_transition(source, target, *cube);
+        }
+    }
+
     /// Get a set of all states that exist in the automaton. It does not matter whether they're reachable or not.
     pub fn states(&self) -> HashSet<State> {
         self.states.keys().map(|s| s.clone()).collect()
@@ -234,25 +406,68 @@ impl Buchi {
         &self.initial_states
     }
 
-    pub fn accepting_sets(&self) -> &HashSet<BTreeSet<State>> {
+    pub fn accepting_sets(&self) -> &[HashSet<State>] {
         &self.accepting_sets
     }
 
+    pub fn accepting_transitions(&self) -> &[HashSet<(State, Word, State)>] {
+        &self.accepting_transitions
+    }
+
+    /// Whether some acceptance set consists of transitions
+    pub fn is_transition_based(&self) -> bool {
+        !self.accepting_transitions.is_empty()
+    }
+
     pub fn label(&self, state: &State) -> Option<&str> {
         self.labels.get(state).map(String::as_str)
     }
 
+    /// Label of the state, unlabeled states are named after their id such as "s3"
+    pub fn label_of(&self, state: State) -> String {
+        self.labels
+            .get(&state)
+            .cloned()
+            .unwrap_or_else(|| format!("s{}", state.id))
+    }
+
+    /// Replace the label of the state
+    pub fn rename(&mut self, state: State, label: String) {
+        self.labels.insert(state, label);
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            states: self.states.len(),
+            transitions: self.states.values().flatten().map(|(_, t)| t.len()).sum(),
+            accepting_sets: self.accepting_sets.len() + self.accepting_transitions.len(),
+        }
+    }
+
+    /// Targets of the outgoing transitions of the state for every word, `None` if the state does
+    /// not belong to the automaton
+    pub fn successors(&self, state: State) -> Option<&HashMap<Word, HashSet<State>>> {
+        self.states.get(&state)
+    }
+
+    /// Transitions ordered by their source, word and target, so what is built from them does
+    /// not depend on the order of the maps
     pub fn transitions(&self) -> Vec<Transition> {
         self.states
             .iter()
+            .sorted_by_key(|(s, _)| **s)
             .map(|(s, transitions)| {
-                transitions.iter().map(|(label, targets)| {
-                    targets.iter().map(|t| Transition {
+                let words = transitions
+                    .iter()
+                    .sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id));
+                words.map(|(label, targets)| {
+                    targets.iter().sorted().map(|t| Transition {
                         from: self.labels.get(s).map(String::as_str).unwrap_or(""),
                         from_state: *s,
                         to: self.labels.get(t).map(String::as_str).unwrap_or(""),
                         to_state: *t,
                         label: &label.id,
+                        literals: label.literals,
                     })
                 })
             })
@@ -348,220 +563,440 @@ impl Buchi {
 
     fn scc_is_trivial(&self, scc: &HashSet<State>) -> bool {
         scc.len() == 1 && {
-            let transitions = self.states.get(scc.iter().next().unwrap()).unwrap();
-            !transitions.values().contains(scc)
+            let state = scc.iter().next().unwrap();
+            let transitions = self.states.get(state).unwrap();
+            !transitions.values().any(|targets| targets.contains(state))
         }
     }
 
     /// Verify that there exists no trace which satisfies the automaton
     /// If there exists a counter example give one back
+    ///
+    /// A trace exists iff a reachable non trivial SCC intersects every accepting set. If there are
+    /// no accepting sets every non trivial SCC is accepting. Transition based automata are checked
+    /// on their state based counterpart.
     pub fn verify(&self) -> Result<(), Trace> {
-        // TODO adjust this for acceptance sets instead of a single acceptance set of states
-        // Gather all the final states which are contained in a non trivial SCC
+        if self.is_transition_based() {
+            return self.state_based().verify();
+        }
         let sccs: Vec<_> = self
             .tarjans_scc()
             .into_iter()
             .filter(|c| !self.scc_is_trivial(c))
+            .filter(|c| self.accepting_sets.iter().all(|set| !set.is_disjoint(c)))
             .collect();
+        if sccs.is_empty() {
+            return Ok(());
+        }
 
-        // If there exists an accepting set where no state is in a non trivial SCC then there is no trace that satisfies
-        for set in &self.accepting_sets {
-            if set
-                .iter()
-                .any(|f| sccs.iter().all(|component| !component.contains(f)))
+        // The cycle starts in a state of the first accepting set, entries are tried in the order
+        // of their distance until no shorter lasso is possible
+        let parents = self.breadth_first_tree();
+        let entries = sccs
+            .iter()
+            .flatten()
+            .filter(|s| self.accepting_sets.first().is_none_or(|f| f.contains(s)))
+            .filter_map(|s| Some((parents.get(s)?.0, *s)))
+            .sorted();
+        let mut shortest: Option<(usize, State, Vec<Word>)> = None;
+        for (distance, entry) in entries {
+            if shortest
+                .as_ref()
+                .is_some_and(|(length, _, _)| distance >= *length)
+            {
+                break;
+            }
+            let scc = sccs.iter().find(|c| c.contains(&entry)).unwrap();
+            let cycle = self.accepting_cycle(entry, scc);
+            if shortest
+                .as_ref()
+                .is_none_or(|(length, _, _)| distance + cycle.len() < *length)
             {
-                return Ok(());
+                shortest = Some((distance + cycle.len(), entry, cycle));
             }
         }
-
-        // If there are no accepting sets and there is no non trivial SCC then there also cannot be a trace
-        if sccs.iter().all(|c| self.scc_is_trivial(c)) {
+        let Some((_, entry, cycle)) = shortest else {
             return Ok(());
-        }
-
-        let nba = self.gnba_to_nba();
-        let sccs: Vec<_> = nba
-            .tarjans_scc()
-            .into_iter()
-            .filter(|c| !nba.scc_is_trivial(c))
-            .collect();
-
-        // We already know that the accepting set is part of an SCC, no need to check
-        let accepting: HashSet<_> = nba.accepting_sets.iter().flatten().collect();
-
-        // If there are no accepting states place an accepting state in every SCC because every infinite run is valid
-        // But if the accepting sets are empty simply place an accepting state in every SCC
-        let accepting = if accepting.is_empty() {
-            sccs.iter().map(|scc| scc.iter().next().unwrap()).collect()
-        } else {
-            accepting
         };
 
-        // If we can reach any of these accepting states we have found a counter example
-        let mut visited = HashMap::new();
-
-        for initial_state in &nba.initial_states {
-            // Do DFS for every initial_state in the list
-            // Except when we already visited it
-            if visited.contains_key(initial_state) {
-                continue;
-            }
-
-            let mut queue = vec![];
-            visited.insert(initial_state, vec![]);
-            queue.push(initial_state);
-
-            while let Some(state) = queue.pop() {
-                if accepting.contains(state) {
-                    // Found a counter example, return the trace and calculate an omega trace
-                    let scc = sccs
-                        .iter()
-                        .filter(|c| c.contains(state))
-                        .collect::<Vec<_>>()[0];
-
-                    let trace = visited.remove(state).unwrap();
-                    let omega_trace = nba.constrained_cycle_searcher(state, scc).unwrap();
-
-                    return Err(Trace::new(trace, omega_trace));
-                }
+        let mut prefix = vec![];
+        let mut current = entry;
+        while let Some((parent, word)) = parents[&current].1 {
+            prefix.push(word.clone());
+            current = parent;
+        }
+        prefix.reverse();
+        Err(Trace::new(prefix, cycle).minimize())
+    }
 
-                for transition in nba.states.get(state) {
-                    for (word, successors) in transition {
-                        for successor in successors {
-                            if !visited.contains_key(successor) {
-                                // Create a new trace for the newly discovered state by copying the previous one
-                                let mut new_trace = visited.get(state).unwrap().clone();
-                                new_trace.push(word.clone());
-                                visited.insert(successor, new_trace);
-                                queue.push(successor);
-                            }
-                        }
+    /// Distance of every reachable state from the initial states together with the parent and
+    /// word it was first reached by
+    fn breadth_first_tree(&self) -> BreadthFirstTree<'_> {
+        let mut tree = HashMap::new();
+        let mut queue = VecDeque::new();
+        for initial in self.initial_states.iter().sorted() {
+            tree.insert(*initial, (0, None));
+            queue.push_back(*initial);
+        }
+        while let Some(state) = queue.pop_front() {
+            let distance = tree[&state].0;
+            let transitions = self.states.get(&state).into_iter().flatten();
+            for (word, successors) in transitions.sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id)) {
+                for successor in successors.iter().sorted() {
+                    if !tree.contains_key(successor) {
+                        tree.insert(*successor, (distance + 1, Some((state, word))));
+                        queue.push_back(*successor);
                     }
                 }
             }
         }
+        tree
+    }
 
-        Ok(())
+    /// Words of a cycle from the entry through the SCC which visits the accepting sets in order
+    fn accepting_cycle(&self, entry: State, scc: &HashSet<State>) -> Vec<Word> {
+        let mut cycle = vec![];
+        let mut current = entry;
+        for set in self.accepting_sets.iter().skip(1) {
+            let (words, reached) = self
+                .shortest_path(current, |s| set.contains(s), |s| scc.contains(s), false)
+                .expect("Every state of an SCC is reachable from the others");
+            cycle.extend(words);
+            current = reached;
+        }
+        let (words, _) = self
+            .shortest_path(
+                current,
+                |s| *s == entry,
+                |s| scc.contains(s),
+                cycle.is_empty(),
+            )
+            .expect("Every state of an SCC is reachable from the others");
+        cycle.extend(words);
+        cycle
     }
 
-    fn constrained_cycle_searcher(
-        &self,
-        initial_state: &State,
-        states: &HashSet<State>,
-    ) -> Option<Vec<Word>> {
-        let mut queue = vec![];
-        let mut visited = HashMap::new();
-        visited.insert(initial_state, vec![]);
-        queue.push(initial_state);
-
-        while let Some(state) = queue.pop() {
-            for transition in self.states.get(state) {
-                for (word, successors) in transition {
-                    for successor in successors.iter().filter(|s| states.contains(s)) {
-                        if successor == initial_state {
-                            // Found the initial state again, return the trace
-                            let mut trace = visited.remove(state).unwrap();
-                            trace.push(word.clone());
-                            return Some(trace);
-                        }
+    /// Whether the automaton accepts the word prefix cycle^ω, words match transitions as in
+    /// `Word::matches`. An empty cycle is never accepted.
+    pub fn accepts(&self, prefix: &[Word], cycle: &[Word]) -> bool {
+        if cycle.is_empty() {
+            return false;
+        }
+        let lasso = Lasso {
+            letters: prefix.iter().chain(cycle).collect(),
+            loop_start: prefix.len(),
+        };
+        Product::new(&lasso, self).verify().is_err()
+    }
 
-                        let mut new_trace = visited.get(state).unwrap().clone();
-                        new_trace.push(word.clone());
-                        if !visited.contains_key(successor) {
-                            queue.push(successor);
-                            visited.insert(successor, new_trace);
+    /// Shortest sequence of words leading from the state to one of the targets without leaving
+    /// the states satisfying `within`, at least one word is read if `nonempty` is set
+    fn shortest_path(
+        &self,
+        from: State,
+        target: impl Fn(&State) -> bool,
+        within: impl Fn(&State) -> bool,
+        nonempty: bool,
+    ) -> Option<(Vec<Word>, State)> {
+        if !nonempty && target(&from) {
+            return Some((vec![], from));
+        }
+        let mut parents: HashMap<State, (State, &Word)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(state) = queue.pop_front() {
+            let transitions = self.states.get(&state).into_iter().flatten();
+            for (word, successors) in transitions.sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id)) {
+                for successor in successors.iter().sorted() {
+                    if !within(successor) || parents.contains_key(successor) {
+                        continue;
+                    }
+                    parents.insert(*successor, (state, word));
+                    if target(successor) {
+                        // Walk back until the start is reached, at least one step is taken
+                        let mut words = vec![];
+                        let mut current = *successor;
+                        loop {
+                            let (parent, word) = parents[&current];
+                            words.push(word.clone());
+                            current = parent;
+                            if current == from {
+                                break;
+                            }
                         }
+                        words.reverse();
+                        return Some((words, *successor));
                     }
+                    queue.push_back(*successor);
                 }
             }
         }
-
         None
     }
 
+    /// Degeneralize the automaton with the counter construction
+    ///
+    /// The states of the NBA are pairs of a state and the index of the accepting set which is
+    /// visited next. Leaving a state of that set moves on to the next index, so the accepting
+    /// states of the first set with index 0 are visited infinitely often iff every set is. Only
+    /// reachable pairs are created, labels are suffixed with the index and words are kept.
     pub fn gnba_to_nba(&self) -> Self {
+        if self.is_transition_based() {
+            return self.state_based().gnba_to_nba();
+        }
         // If the accepting states are empty or there's only one it doesn't matter what you do, just return the whole gnba since it's already an nba
         if self.accepting_sets.len() <= 1 {
             return self.clone();
         }
-        // Clone the accepting states into a vec for deterministic ordering
         let mut nba = Buchi::new();
+        nba.aps = self.aps.clone();
+
+        let mut pairs: HashMap<(State, usize), State> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut pair = |nba: &mut Buchi, queue: &mut VecDeque<_>, state: State, index: usize| {
+            *pairs.entry((state, index)).or_insert_with(|| {
+                let new = nba.new_labeled_state(format!("{} ({})", self.label_of(state), index));
+                queue.push_back((state, index, new));
+                new
+            })
+        };
 
-        for (i, accepting_set) in self.accepting_sets.iter().enumerate() {
-            // Create a copy of the statespace for every accepting state and rename them to s0_0, s0_1, s0_2 etc for each iteration
-            let mut new_states: HashMap<State, HashMap<Word, HashSet<State>>> = self
-                .states
-                .clone()
-                .into_iter()
-                .map(|(mut k, mut v)| {
-                    // Rename the source state
-                    k.id += self.size * i;
-                    // Rename the target states while leaving the word the same
-                    for targets in v.values_mut() {
-                        *targets = targets
-                            .iter()
-                            .map(|s| State {
-                                id: s.id + self.size * i,
-                            })
-                            .collect();
-                    }
-                    (k, v)
-                })
-                .collect();
-
-            // Add new labels
-            for (new, _) in &new_states {
-                nba.labels.insert(
-                    *new,
-                    self.labels
-                        .get(&State {
-                            id: new.id % self.size,
-                        })
-                        .unwrap()
-                        .clone(),
-                );
+        for initial_state in self.initial_states.iter().sorted() {
+            let new = pair(&mut nba, &mut queue, *initial_state, 0);
+            nba.set_initial_state(new);
+        }
+        let mut accepting = vec![];
+        while let Some((state, index, new)) = queue.pop_front() {
+            let visited = self.accepting_sets[index].contains(&state);
+            if visited && index == 0 {
+                accepting.push(new);
             }
-
-            // Map the transitions of the current accepting states to point towards the next one (potentially the first)
-            for accepting in accepting_set {
-                new_states
-                    .entry(State {
-                        id: accepting.id + self.size * i,
-                    })
-                    .and_modify(|transitions| {
-                        for targets in transitions.values_mut() {
-                            *targets = targets
-                                .iter()
-                                .map(|target| State {
-                                    id: (target.id + self.size)
-                                        % (self.size * self.accepting_sets.len()),
-                                })
-                                .collect();
-                        }
-                    });
+            let next = if visited {
+                (index + 1) % self.accepting_sets.len()
+            } else {
+                index
+            };
+            let transitions = self.states.get(&state).into_iter().flatten();
+            for (word, targets) in transitions.sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id)) {
+                for target in targets.iter().sorted() {
+                    let target = pair(&mut nba, &mut queue, *target, next);
+                    nba.add_transition(new, target, word.clone());
+                }
             }
+        }
+        nba.add_accepting_set(accepting);
+
+        nba
+    }
 
-            nba.states.extend(new_states);
+    /// Equivalent automaton with acceptance sets of states only
+    ///
+    /// States are paired with the transition sets of the transition they were entered by, a set
+    /// of transitions becomes the set of pairs entered by one of its transitions. Only reachable
+    /// pairs are created.
+    pub fn state_based(&self) -> Self {
+        if !self.is_transition_based() {
+            return self.clone();
+        }
+        let mut automaton = Buchi::new();
+        automaton.aps = self.aps.clone();
+
+        let mut pairs: HashMap<(State, BTreeSet<usize>), State> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut pair = |automaton: &mut Buchi,
+                        queue: &mut VecDeque<_>,
+                        state: State,
+                        marks: BTreeSet<usize>| {
+            *pairs.entry((state, marks.clone())).or_insert_with(|| {
+                let new = if marks.is_empty() {
+                    automaton.new_labeled_state(self.label_of(state))
+                } else {
+                    automaton.new_labeled_state(format!(
+                        "{} {{{}}}",
+                        self.label_of(state),
+                        marks.iter().join(" ")
+                    ))
+                };
+                queue.push_back((state, marks, new));
+                new
+            })
+        };
 
-            // Make the last set accepting
-            if i == self.accepting_sets.len() - 1 {
-                nba.add_accepting_set(accepting_set.iter().map(|s| State {
-                    id: s.id + self.size * i,
-                }));
+        for initial in self.initial_states.iter().sorted() {
+            let new = pair(&mut automaton, &mut queue, *initial, BTreeSet::new());
+            automaton.set_initial_state(new);
+        }
+        let mut states = vec![];
+        while let Some((state, marks, new)) = queue.pop_front() {
+            states.push((state, marks, new));
+            let transitions = self.states.get(&state).into_iter().flatten();
+            for (word, targets) in transitions.sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id)) {
+                for target in targets.iter().sorted() {
+                    let transition = (state, word.clone(), *target);
+                    let marks = (0..self.accepting_transitions.len())
+                        .filter(|i| self.accepting_transitions[*i].contains(&transition))
+                        .collect();
+                    let target = pair(&mut automaton, &mut queue, *target, marks);
+                    automaton.add_transition(new, target, word.clone());
+                }
             }
         }
 
-        for initial_state in &self.initial_states {
-            nba.set_initial_state(*initial_state)
+        for set in &self.accepting_sets {
+            automaton.add_accepting_set(
+                states
+                    .iter()
+                    .filter(|(s, _, _)| set.contains(s))
+                    .map(|(_, _, new)| *new),
+            );
         }
+        for i in 0..self.accepting_transitions.len() {
+            automaton.add_accepting_set(
+                states
+                    .iter()
+                    .filter(|(_, marks, _)| marks.contains(&i))
+                    .map(|(_, _, new)| *new),
+            );
+        }
+        automaton
+    }
+}
 
-        // Update the size of the nba
-        nba.size += self.size * self.accepting_sets.len();
+impl Buchi {
+    /// Product automaton accepting the intersection of both languages
+    ///
+    /// Transitions synchronize on words with the same id, guarded transitions on satisfiable
+    /// conjunctions of their literals over the union of the atomic propositions. The product is
+    /// a GNBA with the acceptance sets of both automata lifted to the pairs of states, which is
+    /// correct without the usual copy construction. Only reachable pairs are created.
+    pub fn intersect(&self, other: &Buchi) -> Buchi {
+        if self.is_transition_based() || other.is_transition_based() {
+            return self.state_based().intersect(&other.state_based());
+        }
+        let mut product = Buchi::new();
+        let aps: ApSet = self.aps.iter().chain(other.aps.iter()).collect();
+        product.aps = aps.clone();
+
+        let mut pairs: HashMap<(State, State), State> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut pair = |product: &mut Buchi, queue: &mut VecDeque<_>, p: State, q: State| {
+            *pairs.entry((p, q)).or_insert_with(|| {
+                let new = product.new_labeled_state(format!(
+                    "({}, {})",
+                    self.label_of(p),
+                    other.label_of(q)
+                ));
+                queue.push_back((p, q, new));
+                new
+            })
+        };
 
-        nba
+        for p in self.initial_states.iter().sorted() {
+            for q in other.initial_states.iter().sorted() {
+                let new = pair(&mut product, &mut queue, *p, *q);
+                product.set_initial_state(new);
+            }
+        }
+        let mut states = vec![];
+        while let Some((p, q, new)) = queue.pop_front() {
+            states.push((p, q, new));
+            let lhs = self.states.get(&p).into_iter().flatten();
+            for (word, targets) in lhs.sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id)) {
+                let rhs = other.states.get(&q).into_iter().flatten();
+                for (other_word, other_targets) in rhs.sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id)) {
+                    let word = match (word.literals, other_word.literals) {
+                        (Some(l), Some(r)) => {
+                            let l = rename(l, &self.aps, &aps);
+                            let r = rename(r, &other.aps, &aps);
+                            match l.and(&r) {
+                                Some(literals) => Word::guard(&aps, literals),
+                                None => continue,
+                            }
+                        }
+                        _ if word.id == other_word.id => word.clone(),
+                        _ => continue,
+                    };
+                    for (p, q) in targets
+                        .iter()
+                        .sorted()
+                        .cartesian_product(other_targets.iter().sorted())
+                    {
+                        let target = pair(&mut product, &mut queue, *p, *q);
+                        product.add_transition(new, target, word.clone());
+                    }
+                }
+            }
+        }
+
+        for set in &self.accepting_sets {
+            product.add_accepting_set(
+                states
+                    .iter()
+                    .filter(|(p, _, _)| set.contains(p))
+                    .map(|(_, _, new)| *new),
+            );
+        }
+        for set in &other.accepting_sets {
+            product.add_accepting_set(
+                states
+                    .iter()
+                    .filter(|(_, q, _)| set.contains(q))
+                    .map(|(_, _, new)| *new),
+            );
+        }
+        product
+    }
+}
+
+impl Buchi {
+    /// Automaton over the given propositions in which words naming a valuation, such as "{a, b}",
+    /// are replaced by guards which hold for exactly that valuation and "{true}" by a guard which
+    /// always holds. Other words are kept, guards have to refer to the given propositions.
+    ///
+    /// Products of the result with guarded automata match on valuations instead of labels.
+    pub fn guard_valuation_words(&self, aps: &ApSet) -> Buchi {
+        let rename = |word: &Word| {
+            let valuation = match word.id.as_str() {
+                _ if word.literals.is_some() => None,
+                "{true}" => Some(Literals::empty()),
+                id => aps
+                    .parse_valuation(id)
+                    .map(|v| Guard::valuation(v, aps.len()).cubes()[0]),
+            };
+            valuation.map_or_else(|| word.clone(), |l| Word::guard(aps, l))
+        };
+        let mut guarded = self.clone();
+        guarded.aps = aps.clone();
+        for transitions in guarded.states.values_mut() {
+            let mut renamed: HashMap<Word, HashSet<State>> = HashMap::new();
+            for (word, targets) in transitions.drain() {
+                renamed.entry(rename(&word)).or_default().extend(targets);
+            }
+            *transitions = renamed;
+        }
+        for set in &mut guarded.accepting_transitions {
+            *set = set.iter().map(|(s, w, t)| (*s, rename(w), *t)).collect();
+        }
+        guarded
     }
 }
 
+/// Literals over the propositions of `from` as literals over the propositions of `to`, which has
+/// to contain all of them
+fn rename(literals: Literals, from: &ApSet, to: &ApSet) -> Literals {
+    let index = |i: usize| {
+        to.index(from.name(i))
+            .expect("Propositions are part of the union")
+    };
+    let positive = literals
+        .positive
+        .iter()
+        .fold(Literals::empty(), |l, i| l.with(index(i), true));
+    literals
+        .negative
+        .iter()
+        .fold(positive, |l, i| l.with(index(i), false))
+}
+
 impl Display for Buchi {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(
@@ -569,6 +1004,7 @@ impl Display for Buchi {
             "Initial States: ({})",
             self.initial_states
                 .iter()
+                .sorted()
                 .map(|s| format!("s{}", s.id))
                 .collect::<Vec<_>>()
                 .join(", ")
@@ -578,31 +1014,136 @@ impl Display for Buchi {
             "Accepting Sets: ({})",
             self.accepting_sets()
                 .iter()
-                .map(|s| format!("{{{}}}", s.iter().map(|a| format!("s{}", a.id)).join(", ")))
+                .map(|s| {
+                    format!(
+                        "{{{}}}",
+                        s.iter().sorted().map(|a| format!("s{}", a.id)).join(", ")
+                    )
+                })
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
+        if self.is_transition_based() {
+            writeln!(
+                f,
+                "Accepting Transitions: ({})",
+                self.accepting_transitions
+                    .iter()
+                    .map(|s| {
+                        format!(
+                            "{{{}}}",
+                            s.iter()
+                                .sorted_by_key(|(s, w, t)| (*s, w.id.clone(), *t))
+                                .map(|(s, w, t)| format!("s{} --({})--> s{}", s.id, w.id, t.id))
+                                .join(", ")
+                        )
+                    })
+                    .join(", ")
+            )?;
+        }
         writeln!(f, "Transitions:")?;
-        for (s, transitions) in &self.states {
-            for (word, targets) in transitions {
-                for t in targets {
-                    writeln!(f, "s{} --({})--> s{}", s.id, word.id, t.id)?;
-                }
-            }
+        for t in self.transitions() {
+            writeln!(
+                f,
+                "s{} --({})--> s{}",
+                t.from_state.id, t.label, t.to_state.id
+            )?;
         }
         Ok(())
     }
 }
 
+impl Default for DotStyle {
+    fn default() -> Self {
+        DotStyle {
+            acceptance: true,
+            initial_arrows: true,
+            merge_edges: true,
+            labels: true,
+            rankdir: RankDir::default(),
+        }
+    }
+}
+
+impl Display for RankDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = match self {
+            RankDir::TopBottom => "TB",
+            RankDir::LeftRight => "LR",
+            RankDir::BottomTop => "BT",
+            RankDir::RightLeft => "RL",
+        };
+        write!(f, "{}", direction)
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} states, {} transitions, {} accepting sets",
+            self.states, self.transitions, self.accepting_sets
+        )
+    }
+}
+
 impl Word {
     pub fn new<T: ToString>(id: T) -> Self {
-        Word { id: id.to_string() }
+        Word {
+            id: id.to_string(),
+            literals: None,
+        }
+    }
+
+    /// Word for the literals, the id is the printed conjunction such as "a, ¬b"
+    pub fn guard(aps: &ApSet, literals: Literals) -> Self {
+        Word {
+            id: aps.print_literals(&literals),
+            literals: Some(literals),
+        }
+    }
+
+    /// Whether a transition labeled with this word can be taken when reading the letter, a guard
+    /// has to be implied by the literals of the letter and other words have to be equal
+    pub fn matches(&self, letter: &Word) -> bool {
+        match (self.literals, letter.literals) {
+            (Some(guard), Some(literals)) => {
+                guard.positive.is_subset(&literals.positive)
+                    && guard.negative.is_subset(&literals.negative)
+            }
+            _ => self.id == letter.id,
+        }
     }
 }
 
 impl<T: ToString> From<T> for Word {
     fn from(w: T) -> Self {
-        Self { id: w.to_string() }
+        Word::new(w)
+    }
+}
+
+/// Positions of the word prefix cycle^ω, position i reads the i-th letter
+struct Lasso<'a> {
+    letters: Vec<&'a Word>,
+    loop_start: usize,
+}
+
+impl TransitionSystem for Lasso<'_> {
+    type State = usize;
+    type Label = Word;
+    type Error = Infallible;
+
+    fn initial_states(&self) -> Vec<usize> {
+        vec![0]
+    }
+
+    fn successors(&self, i: &usize) -> Result<Vec<(Word, usize)>, Infallible> {
+        let next = if i + 1 < self.letters.len() {
+            i + 1
+        } else {
+            self.loop_start
+        };
+        Ok(vec![(self.letters[*i].clone(), next)])
     }
 }
 
@@ -610,6 +1151,22 @@ impl Trace {
     pub fn new(words: Vec<Word>, omega_words: Vec<Word>) -> Self {
         Trace { words, omega_words }
     }
+
+    /// Shorter lasso for the same word: a cycle repeating a shorter word is cut down to it and
+    /// the prefix is rolled into the cycle while both end with the same word
+    pub fn minimize(mut self) -> Self {
+        let n = self.omega_words.len();
+        if let Some(period) = (1..n).find(|p| {
+            n.is_multiple_of(*p) && (*p..n).all(|i| self.omega_words[i] == self.omega_words[i - p])
+        }) {
+            self.omega_words.truncate(period);
+        }
+        while !self.words.is_empty() && self.words.last() == self.omega_words.last() {
+            self.words.pop();
+            self.omega_words.rotate_right(1);
+        }
+        self
+    }
 }
 
 impl Display for Trace {
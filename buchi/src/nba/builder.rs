@@ -0,0 +1,112 @@
+// Checked construction of Büchi automata
+
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+
+use super::{Buchi, State, Word};
+
+/// Builder which rejects states of other automata and looks states up by their names
+///
+/// The methods of `Buchi` trust that the given states were created by the same automaton, the
+/// builder validates every state it is given.
+#[derive(Debug, Clone)]
+pub struct BuchiBuilder {
+    automaton: Buchi,
+    names: HashMap<String, State>,
+}
+
+impl Default for BuchiBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuchiBuilder {
+    pub fn new() -> Self {
+        Self::from_automaton(Buchi::new())
+    }
+
+    /// Continue building an existing automaton, its labels are used as names
+    pub fn from_automaton(automaton: Buchi) -> Self {
+        let names = automaton
+            .labels
+            .iter()
+            .map(|(state, label)| (label.clone(), *state))
+            .collect();
+        BuchiBuilder { automaton, names }
+    }
+
+    pub fn new_state(&mut self) -> State {
+        self.automaton.new_state()
+    }
+
+    /// Add a state labeled with the name, names have to be unique
+    pub fn named_state(&mut self, name: &str) -> Result<State> {
+        if self.names.contains_key(name) {
+            return Err(Error::DuplicateName(name.into()));
+        }
+        let state = self.automaton.new_labeled_state(name.into());
+        self.names.insert(name.into(), state);
+        Ok(state)
+    }
+
+    /// State with the given name
+    pub fn state(&self, name: &str) -> Result<State> {
+        self.names
+            .get(name)
+            .copied()
+            .ok_or_else(|| Error::UnknownName(name.into()))
+    }
+
+    pub fn transition(
+        &mut self,
+        source: State,
+        target: State,
+        word: impl Into<Word>,
+    ) -> Result<&mut Self> {
+        self.check(source)?;
+        self.check(target)?;
+        self.automaton.add_transition(source, target, word);
+        Ok(self)
+    }
+
+    /// Add a transition between the states with the given names
+    pub fn named_transition(
+        &mut self,
+        source: &str,
+        target: &str,
+        word: impl Into<Word>,
+    ) -> Result<&mut Self> {
+        let source = self.state(source)?;
+        let target = self.state(target)?;
+        self.transition(source, target, word)
+    }
+
+    pub fn initial(&mut self, state: State) -> Result<&mut Self> {
+        self.check(state)?;
+        self.automaton.set_initial_state(state);
+        Ok(self)
+    }
+
+    pub fn accepting_set(&mut self, states: impl IntoIterator<Item = State>) -> Result<&mut Self> {
+        let states: Vec<State> = states.into_iter().collect();
+        for state in &states {
+            self.check(*state)?;
+        }
+        self.automaton.add_accepting_set(states);
+        Ok(self)
+    }
+
+    pub fn build(self) -> Buchi {
+        self.automaton
+    }
+
+    fn check(&self, state: State) -> Result<()> {
+        if self.automaton.states.contains_key(&state) {
+            Ok(())
+        } else {
+            Err(Error::UnknownState(state.id))
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// Rank based complementation of Büchi automata with tight rankings (Friedgut, Kupferman and Vardi)
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+use ltl::alphabet::{Literals, Valuation};
+
+use super::{Buchi, State, Trace, Word};
+
+/// States of the complement: the reached states while the ranking is not guessed yet, afterwards
+/// the ranks of the reached states, sorted by the states, and the states with even ranks which
+/// have not visited an odd rank since the last accepting visit
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum MacroState {
+    Subset(BTreeSet<State>),
+    Ranked(Vec<(State, usize)>, BTreeSet<State>),
+}
+
+impl Buchi {
+    /// Automaton accepting exactly the words this automaton rejects
+    ///
+    /// Guarded automata are complemented over all valuations of their atomic propositions, other
+    /// automata over the words occurring on their transitions. The automaton is reduced by
+    /// simulation first and safety automata are complemented by the subset construction.
+    ///
+    /// A word is rejected iff the run DAG on it can be ranked such that ranks never increase
+    /// along edges, accepting states have even ranks and every path eventually gets stuck in an
+    /// odd rank. Such a ranking can be chosen to be tight from some level on: the highest rank is
+    /// odd and every odd rank below it is taken. The complement follows the reached states by the
+    /// subset construction, guesses when the ranking becomes tight and then only guesses tight
+    /// level rankings, accepting whenever the states with even ranks have all reached an odd
+    /// rank. Ranks are below 2(n - |F|), the blowup can be compared with `Buchi::stats`.
+    pub fn complement(&self) -> Buchi {
+        self.complement_over(&self.letters())
+    }
+
+    /// Check whether this automaton accepts every word `other` accepts, otherwise a word only
+    /// accepted by `other` is returned
+    ///
+    /// Inclusion holds if every initial state of `other` is simulated by an initial state of
+    /// this automaton. Otherwise `other` is intersected with the complement of the determinized
+    /// automaton, which is usually much smaller than the one of the ranking construction.
+    pub fn includes(&self, other: &Buchi) -> Result<(), Trace> {
+        if self.simulates(other) {
+            return Ok(());
+        }
+        // Words of the other automaton which do not occur here are rejected by this automaton
+        let letters = if self.is_guarded() && other.is_guarded() {
+            self.letters()
+        } else {
+            self.letters()
+                .into_iter()
+                .chain(other.letters())
+                .map(|(word, _)| (Word::new(word.id), None))
+                .unique()
+                .collect()
+        };
+        other
+            .intersect(&self.determinize().complement_over(&letters))
+            .verify()
+    }
+
+    /// Check whether both automata accept the same words, otherwise a word accepted by only one
+    /// of them is returned
+    pub fn equivalent(&self, other: &Buchi) -> Result<(), Trace> {
+        self.includes(other)?;
+        other.includes(self)
+    }
+
+    /// Whether every initial state of `other` is delayed simulated by an initial state of this
+    /// automaton in the disjoint union of both, guards have to refer to the same propositions
+    fn simulates(&self, other: &Buchi) -> bool {
+        if self.aps != other.aps && (self.is_guarded() || other.is_guarded()) {
+            return false;
+        }
+        let (this, other) = (self.gnba_to_nba(), other.gnba_to_nba());
+        let mut union = Buchi::new();
+        union.aps = this.aps.clone();
+        let mut accepting = vec![];
+        let mut copy = |automaton: &Buchi| {
+            let states: HashMap<State, State> = automaton
+                .states()
+                .into_iter()
+                .sorted()
+                .map(|s| (s, union.new_state()))
+                .collect();
+            for (source, target, word) in automaton.states.iter().flat_map(|(s, t)| {
+                t.iter()
+                    .flat_map(move |(w, ts)| ts.iter().map(move |t| (s, t, w)))
+            }) {
+                union.add_transition(states[source], states[target], word.clone());
+            }
+            // Without acceptance sets every state is accepting
+            accepting.extend(
+                automaton
+                    .states()
+                    .into_iter()
+                    .filter(|s| automaton.accepting_sets.iter().all(|set| set.contains(s)))
+                    .map(|s| states[&s]),
+            );
+            automaton
+                .initial_states
+                .iter()
+                .map(|s| states[s])
+                .collect_vec()
+        };
+        let this_initial = copy(&this);
+        let other_initial = copy(&other);
+        union.add_accepting_set(accepting);
+
+        let simulation = union.delayed_simulation();
+        other_initial
+            .iter()
+            .all(|q| this_initial.iter().any(|p| simulation.contains(&(*q, *p))))
+    }
+
+    fn complement_over(&self, letters: &[(Word, Option<Valuation>)]) -> Buchi {
+        let nba = self.gnba_to_nba().reduce();
+        if nba.is_safety() {
+            return nba.complement_safety(letters);
+        }
+        // Without acceptance sets every run is accepting
+        let accepting = nba
+            .accepting_sets
+            .first()
+            .cloned()
+            .unwrap_or_else(|| nba.states());
+
+        let mut complement = Buchi::new();
+        complement.aps = nba.aps.clone();
+        let mut index: HashMap<MacroState, State> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut id = |complement: &mut Buchi, queue: &mut VecDeque<_>, m: MacroState| {
+            if let Some(state) = index.get(&m) {
+                return *state;
+            }
+            let state = complement.new_labeled_state(nba.print_macro_state(&m));
+            index.insert(m.clone(), state);
+            queue.push_back((m, state));
+            state
+        };
+
+        let initial = MacroState::Subset(nba.initial_states.iter().copied().collect());
+        let initial = id(&mut complement, &mut queue, initial);
+        complement.set_initial_state(initial);
+
+        let mut final_states = vec![];
+        while let Some((macro_state, state)) = queue.pop_front() {
+            for (word, valuation) in letters {
+                let successors = match &macro_state {
+                    MacroState::Subset(set) => {
+                        let reached: BTreeSet<State> = set
+                            .iter()
+                            .flat_map(|s| nba.letter_successors(*s, word, *valuation))
+                            .collect();
+                        // A tight ranking takes every odd rank below its highest rank with a
+                        // state which is not accepting
+                        let odd_states = reached.iter().filter(|t| !accepting.contains(t)).count();
+                        let highest = (2 * odd_states).max(1) - 1;
+                        let bounds = reached.iter().map(|t| (*t, highest)).collect_vec();
+                        tight_rankings(&bounds, &accepting)
+                            .into_iter()
+                            .map(|ranking| MacroState::Ranked(ranking, BTreeSet::new()))
+                            .chain([MacroState::Subset(reached)])
+                            .collect_vec()
+                    }
+                    MacroState::Ranked(ranking, pending) => {
+                        // Successors may not get a higher rank than any of their predecessors
+                        let mut bounds: HashMap<State, usize> = HashMap::new();
+                        for (s, rank) in ranking {
+                            for t in nba.letter_successors(*s, word, *valuation) {
+                                let bound = bounds.entry(t).or_insert(*rank);
+                                *bound = (*bound).min(*rank);
+                            }
+                        }
+                        let bounds = bounds.into_iter().sorted().collect_vec();
+                        let pending_successors: HashSet<State> = pending
+                            .iter()
+                            .flat_map(|s| nba.letter_successors(*s, word, *valuation))
+                            .collect();
+                        tight_rankings(&bounds, &accepting)
+                            .into_iter()
+                            .map(|successor| {
+                                let even = successor
+                                    .iter()
+                                    .filter(|(_, r)| r % 2 == 0)
+                                    .map(|(t, _)| *t);
+                                let next_pending = if pending.is_empty() {
+                                    even.collect()
+                                } else {
+                                    even.filter(|t| pending_successors.contains(t)).collect()
+                                };
+                                MacroState::Ranked(successor, next_pending)
+                            })
+                            .collect_vec()
+                    }
+                };
+                for successor in successors {
+                    let target = id(&mut complement, &mut queue, successor);
+                    complement.add_transition(state, target, word.clone());
+                }
+            }
+            if matches!(&macro_state, MacroState::Ranked(_, pending) if pending.is_empty()) {
+                final_states.push(state);
+            }
+        }
+        complement.add_accepting_set(final_states);
+        complement
+    }
+
+    /// The letters of the automaton, the valuations of the atomic propositions if every
+    /// transition is guarded and the distinct words otherwise
+    pub(super) fn letters(&self) -> Vec<(Word, Option<Valuation>)> {
+        if self.is_guarded() {
+            self.aps
+                .valuations()
+                .map(|v| {
+                    let cube = (0..self.aps.len())
+                        .fold(Literals::empty(), |l, i| l.with(i, v.contains(i)));
+                    (Word::guard(&self.aps, cube), Some(v))
+                })
+                .collect()
+        } else {
+            self.states
+                .values()
+                .flat_map(|t| t.keys())
+                .unique()
+                .sorted_by(|a, b| a.id.cmp(&b.id))
+                .map(|w| (w.clone(), None))
+                .collect()
+        }
+    }
+
+    fn is_guarded(&self) -> bool {
+        self.states
+            .values()
+            .flat_map(|t| t.keys())
+            .all(|w| w.literals.is_some())
+    }
+
+    /// Successors of the state when reading the letter
+    pub(super) fn letter_successors<'a>(
+        &'a self,
+        state: State,
+        word: &'a Word,
+        valuation: Option<Valuation>,
+    ) -> impl Iterator<Item = State> + 'a {
+        self.states
+            .get(&state)
+            .into_iter()
+            .flatten()
+            .filter(move |(w, _)| match valuation {
+                Some(v) => w.literals.is_some_and(|l| l.holds(v)),
+                None => w.id == word.id,
+            })
+            .flat_map(|(_, targets)| targets.iter().copied())
+    }
+
+    fn print_macro_state(&self, macro_state: &MacroState) -> String {
+        let name = |s: &State| self.label_of(*s);
+        match macro_state {
+            MacroState::Subset(set) => format!("{{{}}}", set.iter().map(name).join(", ")),
+            MacroState::Ranked(ranking, pending) => format!(
+                "{{{}}} {{{}}}",
+                ranking
+                    .iter()
+                    .map(|(s, r)| format!("{}: {}", name(s), r))
+                    .join(", "),
+                pending.iter().map(name).join(", ")
+            ),
+        }
+    }
+}
+
+/// Tight rankings giving every state a rank up to its bound, accepting states even ones
+fn tight_rankings(
+    bounds: &[(State, usize)],
+    accepting: &HashSet<State>,
+) -> Vec<Vec<(State, usize)>> {
+    fn extend(
+        ranking: &mut Vec<(State, usize)>,
+        bounds: &[(State, usize)],
+        accepting: &HashSet<State>,
+        rankings: &mut Vec<Vec<(State, usize)>>,
+    ) {
+        let Some(((state, bound), rest)) = bounds.split_first() else {
+            if is_tight(ranking) {
+                rankings.push(ranking.clone());
+            }
+            return;
+        };
+        let odd_states = rest.iter().filter(|(s, _)| !accepting.contains(s)).count();
+        for rank in (0..=*bound).filter(|r| r % 2 == 0 || !accepting.contains(state)) {
+            ranking.push((*state, rank));
+            // The odd ranks missing below the highest one have to be taken by the other states
+            if missing_odd_ranks(ranking) <= odd_states {
+                extend(ranking, rest, accepting, rankings);
+            }
+            ranking.pop();
+        }
+    }
+    let mut rankings = vec![];
+    extend(&mut vec![], bounds, accepting, &mut rankings);
+    rankings
+}
+
+/// Number of odd ranks below the highest odd rank of the ranking which no state takes
+fn missing_odd_ranks(ranking: &[(State, usize)]) -> usize {
+    let odd: BTreeSet<usize> = ranking
+        .iter()
+        .map(|(_, r)| *r)
+        .filter(|r| r % 2 == 1)
+        .collect();
+    odd.last()
+        .map_or(0, |highest| highest.div_ceil(2) - odd.len())
+}
+
+/// Whether the highest rank is odd and every odd rank below it is taken, the empty ranking of
+/// an empty level is tight as well
+fn is_tight(ranking: &[(State, usize)]) -> bool {
+    ranking
+        .iter()
+        .map(|(_, r)| *r)
+        .max()
+        .is_none_or(|highest| highest % 2 == 1 && missing_odd_ranks(ranking) == 0)
+}
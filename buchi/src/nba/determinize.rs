@@ -0,0 +1,548 @@
+// Determinization of Büchi automata into deterministic parity automata
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+use itertools::Itertools;
+use ltl::alphabet::{ApSet, Valuation};
+
+use super::{Buchi, RabinAutomaton, State, Word};
+use crate::hoa::Propositions;
+
+/// Acceptance condition used when writing a deterministic automaton in HOA format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    /// parity min even
+    Parity,
+    /// One pair Fin(2i) & Inf(2i + 1) per even priority 2i
+    Rabin,
+}
+
+/// Complete deterministic automaton with priorities on its transitions, state 0 is initial
+pub struct DeterministicAutomaton {
+    aps: ApSet,
+    labels: Vec<String>,
+    /// Successor and priority of every state for every letter, the letters are the same for
+    /// every state
+    transitions: Vec<Vec<(Word, usize, usize)>>,
+}
+
+/// Node of a Safra tree, names are dense in the order of creation (Piterman's compact Safra
+/// trees). Children are split off for the accepting states of their parent and a node is marked
+/// once all of its states have passed an accepting state since it was created.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Node {
+    name: usize,
+    states: BTreeSet<State>,
+    marked: bool,
+    children: Vec<Node>,
+}
+
+impl Buchi {
+    /// Equivalent deterministic parity automaton, only reachable Safra trees are created. Safety
+    /// automata are determinized by the subset construction.
+    ///
+    /// A run is accepting iff some node is marked infinitely often while no node with a smaller
+    /// name is removed from some point on. Removing the node with name i yields priority 2i + 1
+    /// and marking it 2i + 2, removal takes precedence as the name may be reused for a new node.
+    pub fn determinize(&self) -> DeterministicAutomaton {
+        let nba = self.gnba_to_nba();
+        // Without acceptance sets every state is accepting
+        let accepting = nba
+            .accepting_sets
+            .first()
+            .cloned()
+            .unwrap_or_else(|| nba.states());
+        let letters = nba.letters();
+        if nba.is_safety() {
+            return nba.determinize_safety(&letters);
+        }
+        // Names of nodes, including the new ones of a step, are smaller than twice the number of
+        // states, so this priority is higher than every other one
+        let idle = 4 * nba.states.len() + 3;
+
+        let initial = (!nba.initial_states.is_empty()).then(|| Node {
+            name: 0,
+            states: nba.initial_states.iter().copied().collect(),
+            marked: false,
+            children: vec![],
+        });
+        let mut index: HashMap<Option<Node>, usize> = HashMap::from([(initial.clone(), 0)]);
+        let mut trees = vec![initial];
+        let mut transitions = vec![];
+        while transitions.len() < trees.len() {
+            let tree = trees[transitions.len()].clone();
+            let mut successors = vec![];
+            for (word, valuation) in &letters {
+                let (successor, priority) = match &tree {
+                    Some(root) => {
+                        let step = |s: &BTreeSet<State>| {
+                            s.iter()
+                                .flat_map(|q| nba.letter_successors(*q, word, *valuation))
+                                .collect()
+                        };
+                        root.step(&accepting, step, idle)
+                    }
+                    None => (None, idle),
+                };
+                let next = index.len();
+                let target = *index.entry(successor.clone()).or_insert_with(|| {
+                    trees.push(successor);
+                    next
+                });
+                successors.push((word.clone(), target, priority));
+            }
+            transitions.push(successors);
+        }
+
+        let mut automaton = DeterministicAutomaton {
+            aps: nba.aps.clone(),
+            labels: trees
+                .iter()
+                .map(|tree| match tree {
+                    Some(root) => nba.print_node(root),
+                    None => "∅".into(),
+                })
+                .collect(),
+            transitions,
+        };
+        automaton.compress_priorities();
+        automaton
+    }
+
+    /// Subset construction for safety automata, transitions into the empty set have priority 1
+    fn determinize_safety(&self, letters: &[(Word, Option<Valuation>)]) -> DeterministicAutomaton {
+        let subsets = self.subsets(letters);
+        let transitions = subsets
+            .successors
+            .iter()
+            .map(|successors| {
+                letters
+                    .iter()
+                    .zip(successors)
+                    .map(|((word, _), target)| {
+                        let priority = usize::from(subsets.sets[*target].is_empty());
+                        (word.clone(), *target, priority)
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut automaton = DeterministicAutomaton {
+            aps: self.aps.clone(),
+            labels: subsets.sets.iter().map(|s| self.print_subset(s)).collect(),
+            transitions,
+        };
+        automaton.compress_priorities();
+        automaton
+    }
+
+    fn print_node(&self, node: &Node) -> String {
+        let name = |s: &State| self.label_of(*s);
+        format!(
+            "({}{}: {{{}}}{})",
+            node.name,
+            if node.marked { "!" } else { "" },
+            node.states.iter().map(name).join(", "),
+            node.children
+                .iter()
+                .map(|c| format!(" {}", self.print_node(c)))
+                .join("")
+        )
+    }
+}
+
+impl Node {
+    /// Successor tree and priority of the transition, `None` if the tree becomes empty
+    fn step(
+        &self,
+        accepting: &HashSet<State>,
+        successors: impl Fn(&BTreeSet<State>) -> BTreeSet<State>,
+        idle: usize,
+    ) -> (Option<Node>, usize) {
+        let mut root = self.clone();
+        let mut next_name = root.max_name() + 1;
+        root.branch(accepting, &mut next_name);
+        root.update(&successors);
+        root.merge_horizontally(&BTreeSet::new());
+
+        let mut removed = vec![];
+        let mut marked = vec![];
+        if root.states.is_empty() {
+            root.collect_names(&mut removed);
+            return (None, 2 * removed.iter().min().unwrap() + 1);
+        }
+        root.remove_empty(&mut removed);
+        root.merge_vertically(&mut removed, &mut marked);
+
+        let priority = marked
+            .iter()
+            .map(|m| 2 * m + 2)
+            .chain(removed.iter().map(|r| 2 * r + 1))
+            .min()
+            .unwrap_or(idle);
+        // Names are kept dense and in the order of creation
+        let mut names = vec![];
+        root.collect_names(&mut names);
+        let dense: HashMap<usize, usize> = names
+            .into_iter()
+            .sorted()
+            .enumerate()
+            .map(|(i, n)| (n, i))
+            .collect();
+        root.rename(&dense);
+        (Some(root), priority)
+    }
+
+    fn max_name(&self) -> usize {
+        self.children
+            .iter()
+            .map(Node::max_name)
+            .fold(self.name, usize::max)
+    }
+
+    /// Unmark every node and split off the accepting states of every node as youngest child
+    fn branch(&mut self, accepting: &HashSet<State>, next_name: &mut usize) {
+        self.marked = false;
+        for child in &mut self.children {
+            child.branch(accepting, next_name);
+        }
+        let states: BTreeSet<State> = self
+            .states
+            .iter()
+            .filter(|s| accepting.contains(s))
+            .copied()
+            .collect();
+        if !states.is_empty() {
+            self.children.push(Node {
+                name: *next_name,
+                states,
+                marked: false,
+                children: vec![],
+            });
+            *next_name += 1;
+        }
+    }
+
+    fn update(&mut self, successors: &impl Fn(&BTreeSet<State>) -> BTreeSet<State>) {
+        self.states = successors(&self.states);
+        for child in &mut self.children {
+            child.update(successors);
+        }
+    }
+
+    /// Remove states which also occur in older siblings of the node or its ancestors
+    fn merge_horizontally(&mut self, taken: &BTreeSet<State>) {
+        self.states = self.states.difference(taken).copied().collect();
+        let mut taken = taken.clone();
+        for child in &mut self.children {
+            child.merge_horizontally(&taken);
+            taken.extend(child.states.iter().copied());
+        }
+    }
+
+    fn remove_empty(&mut self, removed: &mut Vec<usize>) {
+        for child in self.children.iter().filter(|c| c.states.is_empty()) {
+            child.collect_names(removed);
+        }
+        self.children.retain(|c| !c.states.is_empty());
+        for child in &mut self.children {
+            child.remove_empty(removed);
+        }
+    }
+
+    /// Mark the nodes whose states are all covered by their children and remove the children
+    fn merge_vertically(&mut self, removed: &mut Vec<usize>, marked: &mut Vec<usize>) {
+        if self.children.is_empty() {
+            return;
+        }
+        let covered: BTreeSet<State> = self
+            .children
+            .iter()
+            .flat_map(|c| c.states.iter().copied())
+            .collect();
+        if covered == self.states {
+            for child in &self.children {
+                child.collect_names(removed);
+            }
+            self.children.clear();
+            self.marked = true;
+            marked.push(self.name);
+        } else {
+            for child in &mut self.children {
+                child.merge_vertically(removed, marked);
+            }
+        }
+    }
+
+    fn collect_names(&self, names: &mut Vec<usize>) {
+        names.push(self.name);
+        for child in &self.children {
+            child.collect_names(names);
+        }
+    }
+
+    fn rename(&mut self, names: &HashMap<usize, usize>) {
+        self.name = names[&self.name];
+        for child in &mut self.children {
+            child.rename(names);
+        }
+    }
+}
+
+impl DeterministicAutomaton {
+    /// Atomic propositions the guards of the letters refer to
+    pub fn aps(&self) -> &ApSet {
+        &self.aps
+    }
+
+    pub fn states(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Number of priorities, they range from 0 to this number exclusively
+    pub fn priorities(&self) -> usize {
+        self.transitions
+            .iter()
+            .flatten()
+            .map(|(_, _, p)| p + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Successor and priority of the transition for the word, the words are the letters the
+    /// automaton was determinized over
+    pub fn successor(&self, state: usize, word: &Word) -> Option<(usize, usize)> {
+        self.transitions[state]
+            .iter()
+            .find(|(w, _, _)| w == word)
+            .map(|(_, target, priority)| (*target, *priority))
+    }
+
+    /// Letters of the automaton, each state has exactly one transition for every letter
+    pub fn letters(&self) -> impl Iterator<Item = &Word> {
+        self.transitions
+            .first()
+            .into_iter()
+            .flatten()
+            .map(|(w, _, _)| w)
+    }
+
+    /// Rabin automaton with state based acceptance, states are paired with the priority of the
+    /// transition they were entered by. Even priorities p become pairs which must not see a
+    /// priority below p.
+    pub fn to_rabin(&self) -> RabinAutomaton {
+        let mut automaton = Buchi::new();
+        automaton.aps = self.aps.clone();
+        let mut index: HashMap<(usize, Option<usize>), State> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut id = |automaton: &mut Buchi, queue: &mut VecDeque<_>, state, priority| {
+            *index.entry((state, priority)).or_insert_with(|| {
+                let label = match priority {
+                    Some(p) => format!("{} {{{}}}", self.labels[state], p),
+                    None => self.labels[state].clone(),
+                };
+                let new = automaton.new_labeled_state(label);
+                queue.push_back((state, new));
+                new
+            })
+        };
+        let initial = id(&mut automaton, &mut queue, 0, None);
+        automaton.set_initial_state(initial);
+        while let Some((state, new)) = queue.pop_front() {
+            for (word, target, priority) in &self.transitions[state] {
+                let target = id(&mut automaton, &mut queue, *target, Some(*priority));
+                automaton.add_transition(new, target, word.clone());
+            }
+        }
+
+        let mut rabin = RabinAutomaton::new(automaton);
+        for p in (0..self.priorities()).step_by(2) {
+            let with = |accept: fn(usize, usize) -> bool| {
+                index
+                    .iter()
+                    .filter(move |((_, q), _)| q.is_some_and(|q| accept(q, p)))
+                    .map(|(_, s)| *s)
+            };
+            rabin.add_pair(with(|q, p| q < p), with(|q, p| q == p));
+        }
+        rabin
+    }
+
+    /// Büchi automaton accepting the words over the letters this automaton rejects, letters
+    /// without a transition here lead to an accepting sink
+    ///
+    /// A word is rejected iff the least priority seen infinitely often is odd. The complement
+    /// follows this automaton and guesses that priority p, afterwards it only takes transitions
+    /// with priorities of at least p and accepts whenever one with priority p is taken.
+    pub(super) fn complement_over(&self, letters: &[(Word, Option<Valuation>)]) -> Buchi {
+        let mut complement = Buchi::new();
+        complement.aps = self.aps.clone();
+        // States with the guessed priority and whether it was seen by the last transition
+        let mut index: HashMap<(usize, Option<usize>, bool), State> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut id = |complement: &mut Buchi, queue: &mut VecDeque<_>, key| {
+            *index.entry(key).or_insert_with(|| {
+                let label = match key {
+                    (state, Some(p), true) => format!("{} {{{}}}", self.labels[state], p),
+                    (state, Some(p), false) => format!("{} ({})", self.labels[state], p),
+                    (state, None, _) => self.labels[state].clone(),
+                };
+                let new = complement.new_labeled_state(label);
+                queue.push_back((key, new));
+                new
+            })
+        };
+        let initial = id(&mut complement, &mut queue, (0, None, false));
+        complement.set_initial_state(initial);
+
+        let odd = (1..self.priorities()).step_by(2).collect_vec();
+        let mut sink = None;
+        let mut accepting = vec![];
+        while let Some(((state, guess, seen), new)) = queue.pop_front() {
+            if seen {
+                accepting.push(new);
+            }
+            for (word, _) in letters {
+                let Some((target, priority)) = self.successor(state, word) else {
+                    let sink = *sink.get_or_insert_with(|| complement.new_state());
+                    complement.add_transition(new, sink, word.clone());
+                    continue;
+                };
+                let targets = match guess {
+                    None => odd
+                        .iter()
+                        .filter(|p| **p <= priority)
+                        .map(|p| (target, Some(*p), *p == priority))
+                        .chain([(target, None, false)])
+                        .collect_vec(),
+                    Some(p) if priority >= p => vec![(target, Some(p), priority == p)],
+                    Some(_) => vec![],
+                };
+                for key in targets {
+                    let target = id(&mut complement, &mut queue, key);
+                    complement.add_transition(new, target, word.clone());
+                }
+            }
+        }
+        if let Some(sink) = sink {
+            for (word, _) in letters {
+                complement.add_transition(sink, sink, word.clone());
+            }
+            accepting.push(sink);
+        }
+        complement.add_accepting_set(accepting);
+        complement
+    }
+
+    /// Renumber the priorities into a dense range without changing the parity of any of them
+    fn compress_priorities(&mut self) {
+        let mut map = HashMap::new();
+        let mut next = 0;
+        for p in self
+            .transitions
+            .iter()
+            .flatten()
+            .map(|(_, _, p)| *p)
+            .unique()
+            .sorted()
+        {
+            if next % 2 != p % 2 {
+                next += 1;
+            }
+            map.insert(p, next);
+        }
+        for (_, _, p) in self.transitions.iter_mut().flatten() {
+            *p = map[p];
+        }
+    }
+
+    /// Transform the automaton into a HOA formatted string with transition based acceptance
+    pub fn hoa(&self, condition: Condition) -> String {
+        let priorities = self.priorities();
+        let mut out = String::new();
+        writeln!(&mut out, "HOA: v1").unwrap();
+        writeln!(&mut out, "States: {}", self.states()).unwrap();
+        writeln!(&mut out, "Start: 0").unwrap();
+        let propositions = Propositions::new(&self.aps, self.letters());
+        write!(&mut out, "{}", propositions.header()).unwrap();
+        let pairs = (0..priorities).step_by(2).collect_vec();
+        match condition {
+            Condition::Parity => {
+                writeln!(&mut out, "acc-name: parity min even {}", priorities).unwrap();
+                writeln!(
+                    &mut out,
+                    "Acceptance: {} {}",
+                    priorities,
+                    parity_condition(0, priorities)
+                )
+                .unwrap();
+            }
+            Condition::Rabin => {
+                writeln!(&mut out, "acc-name: Rabin {}", pairs.len()).unwrap();
+                let condition = (0..pairs.len())
+                    .map(|i| format!("(Fin({})&Inf({}))", 2 * i, 2 * i + 1))
+                    .join(" | ");
+                writeln!(
+                    &mut out,
+                    "Acceptance: {} {}",
+                    2 * pairs.len(),
+                    if condition.is_empty() {
+                        "f"
+                    } else {
+                        &condition
+                    }
+                )
+                .unwrap();
+            }
+        }
+        writeln!(&mut out, "properties: deterministic complete trans-acc").unwrap();
+        writeln!(&mut out, "--BODY--").unwrap();
+        for (state, transitions) in self.transitions.iter().enumerate() {
+            writeln!(&mut out, "State: {} \"{}\"", state, self.labels[state]).unwrap();
+            for (word, target, priority) in transitions {
+                let label = propositions.label(word);
+                let marks = match condition {
+                    Condition::Parity => vec![*priority],
+                    // A transition is bad for the pairs of higher priorities and good for its own
+                    Condition::Rabin => pairs
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, p)| match priority.cmp(p) {
+                            std::cmp::Ordering::Less => Some(2 * i),
+                            std::cmp::Ordering::Equal => Some(2 * i + 1),
+                            std::cmp::Ordering::Greater => None,
+                        })
+                        .collect(),
+                };
+                let marks = if marks.is_empty() {
+                    "".into()
+                } else {
+                    format!(" {{{}}}", marks.iter().join(" "))
+                };
+                writeln!(&mut out, "  {} {}{}", label, target, marks).unwrap();
+            }
+        }
+        write!(&mut out, "--END--").unwrap();
+        out
+    }
+}
+
+/// Condition of parity min even starting at the given priority, e.g. Inf(0) | (Fin(1) & Inf(2))
+fn parity_condition(priority: usize, priorities: usize) -> String {
+    if priority + 1 >= priorities {
+        return match (priority < priorities, priority.is_multiple_of(2)) {
+            (false, _) => "f".into(),
+            (true, true) => format!("Inf({})", priority),
+            (true, false) => format!("Fin({})", priority),
+        };
+    }
+    let mut rest = parity_condition(priority + 1, priorities);
+    if rest.contains(' ') {
+        rest = format!("({})", rest);
+    }
+    if priority.is_multiple_of(2) {
+        format!("Inf({}) | {}", priority, rest)
+    } else {
+        format!("Fin({}) & {}", priority, rest)
+    }
+}
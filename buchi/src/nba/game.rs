@@ -0,0 +1,124 @@
+// Parity games from the product of a game arena with a deterministic parity automaton
+
+use std::collections::{HashMap, VecDeque};
+
+use parity::{Graph, MetaData, NodeIndex, Owner};
+
+use super::{Buchi, Word};
+
+/// Game arena whose vertices are labeled with words of an automaton
+#[derive(Debug, Clone, Default)]
+pub struct Arena {
+    vertices: Vec<(Owner, Word)>,
+    edges: Vec<Vec<usize>>,
+    initial: Vec<usize>,
+}
+
+impl Arena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a vertex in which the player chooses the successor, plays visiting it read the word
+    pub fn add_vertex(&mut self, owner: Owner, word: Word) -> usize {
+        self.vertices.push((owner, word));
+        self.edges.push(vec![]);
+        self.vertices.len() - 1
+    }
+
+    pub fn add_edge(&mut self, source: usize, target: usize) {
+        if !self.edges[source].contains(&target) {
+            self.edges[source].push(target);
+        }
+    }
+
+    pub fn set_initial(&mut self, vertex: usize) {
+        if !self.initial.contains(&vertex) {
+            self.initial.push(vertex);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+}
+
+impl Buchi {
+    /// Parity game in which the even player wins iff the word of the play is accepted, only
+    /// vertices reachable from the initial vertices of the arena are created and the initial
+    /// ones come first in the order of the arena. Players without a move lose, as do plays
+    /// reading a word which is not a letter of the automaton.
+    ///
+    /// The vertex (v, q) moves to (v', q') for the successors v' of v where q' is the successor of
+    /// q for the word of v. The automaton uses min even priorities while the solvers use max even
+    /// ones, so priorities are mirrored at an even bound. With every vertex of a Kripke structure
+    /// belonging to the odd player, the even player wins iff every path is accepted.
+    pub fn to_parity_game(&self, arena: &Arena) -> Graph {
+        let dpa = self.determinize();
+        // Mirror min even priorities at an even bound to obtain max even priorities
+        let bound = dpa.priorities().next_multiple_of(2);
+        let mut graph = Graph::new();
+        let mut index: HashMap<(usize, usize), NodeIndex> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut vertex = |graph: &mut Graph, queue: &mut VecDeque<_>, v: usize, q: usize| {
+            *index.entry((v, q)).or_insert_with(|| {
+                let (owner, word) = &arena.vertices[v];
+                let priority = match dpa.successor(q, word) {
+                    Some((_, priority)) => bound - priority,
+                    None => 1,
+                };
+                let new = graph.add_vertex(MetaData {
+                    id: graph.len(),
+                    label: Some(format!("({}, q{})", v, q)),
+                    owner: *owner,
+                    priority,
+                });
+                queue.push_back((v, q, new));
+                new
+            })
+        };
+        for initial in &arena.initial {
+            vertex(&mut graph, &mut queue, *initial, 0);
+        }
+
+        // Sinks won by each player, created on demand
+        let mut sinks: HashMap<Owner, NodeIndex> = HashMap::new();
+        let mut sink = |graph: &mut Graph, winner: Owner| {
+            *sinks.entry(winner).or_insert_with(|| {
+                let sink = graph.add_vertex(MetaData {
+                    id: graph.len(),
+                    label: Some(format!("sink {}", winner)),
+                    owner: winner,
+                    priority: if winner == Owner::Even { 0 } else { 1 },
+                });
+                graph.add_edge(sink, sink);
+                sink
+            })
+        };
+        while let Some((v, q, source)) = queue.pop_front() {
+            let (owner, word) = &arena.vertices[v];
+            let Some((q_next, _)) = dpa.successor(q, word) else {
+                let target = sink(&mut graph, Owner::Odd);
+                graph.add_edge(source, target);
+                continue;
+            };
+            if arena.edges[v].is_empty() {
+                let winner = match owner {
+                    Owner::Even => Owner::Odd,
+                    Owner::Odd => Owner::Even,
+                };
+                let target = sink(&mut graph, winner);
+                graph.add_edge(source, target);
+            }
+            for v_next in &arena.edges[v] {
+                let target = vertex(&mut graph, &mut queue, *v_next, q_next);
+                graph.add_edge(source, target);
+            }
+        }
+        graph
+    }
+}
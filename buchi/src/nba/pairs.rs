@@ -0,0 +1,279 @@
+// Automata with Rabin and Streett acceptance conditions
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use super::{Buchi, State, Trace};
+use crate::hoa::Propositions;
+
+/// Pair of sets of states of a Rabin or Streett condition
+///
+/// A run satisfies a Rabin pair if it visits `fin` finitely often and `inf` infinitely often, and
+/// a Streett pair if it visits `fin` finitely often or `inf` infinitely often.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pair {
+    pub fin: HashSet<State>,
+    pub inf: HashSet<State>,
+}
+
+/// Automaton accepting the runs which satisfy one of its pairs
+#[derive(Debug, Clone)]
+pub struct RabinAutomaton {
+    automaton: Buchi,
+    pairs: Vec<Pair>,
+}
+
+/// Automaton accepting the runs which satisfy all of its pairs
+#[derive(Debug, Clone)]
+pub struct StreettAutomaton {
+    automaton: Buchi,
+    pairs: Vec<Pair>,
+}
+
+impl RabinAutomaton {
+    /// Automaton with the states and transitions of the Büchi automaton and no pairs, the
+    /// acceptance sets of the Büchi automaton are dropped
+    pub fn new(automaton: Buchi) -> Self {
+        RabinAutomaton {
+            automaton: without_acceptance(automaton),
+            pairs: vec![],
+        }
+    }
+
+    pub fn add_pair(
+        &mut self,
+        fin: impl IntoIterator<Item = State>,
+        inf: impl IntoIterator<Item = State>,
+    ) {
+        self.pairs.push(Pair {
+            fin: fin.into_iter().collect(),
+            inf: inf.into_iter().collect(),
+        });
+    }
+
+    pub fn automaton(&self) -> &Buchi {
+        &self.automaton
+    }
+
+    pub fn pairs(&self) -> &[Pair] {
+        &self.pairs
+    }
+
+    /// Verify that no run is accepting, otherwise the word of an accepting run is returned. Each
+    /// pair is checked by searching a cycle through `inf` in the automaton without `fin`.
+    pub fn verify(&self) -> Result<(), Trace> {
+        let automaton = &self.automaton;
+        for pair in &self.pairs {
+            let allowed = automaton.states().difference(&pair.fin).copied().collect();
+            let restricted = automaton.restrict(&allowed);
+            for component in restricted.tarjans_scc() {
+                if restricted.scc_is_trivial(&component) || component.is_disjoint(&pair.inf) {
+                    continue;
+                }
+                if let Some(trace) = automaton.lasso(&component, &[&pair.inf]) {
+                    return Err(trace);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Transform the automaton into a HOA formatted string
+    pub fn hoa(&self) -> String {
+        let condition = (0..self.pairs.len())
+            .map(|i| format!("Fin({})&Inf({})", 2 * i, 2 * i + 1))
+            .join(" | ");
+        let condition = if condition.is_empty() {
+            "f".into()
+        } else {
+            condition
+        };
+        write_hoa(&self.automaton, &self.pairs, "Rabin", &condition)
+    }
+}
+
+impl StreettAutomaton {
+    /// Automaton with the states and transitions of the Büchi automaton and no pairs, the
+    /// acceptance sets of the Büchi automaton are dropped
+    pub fn new(automaton: Buchi) -> Self {
+        StreettAutomaton {
+            automaton: without_acceptance(automaton),
+            pairs: vec![],
+        }
+    }
+
+    pub fn add_pair(
+        &mut self,
+        fin: impl IntoIterator<Item = State>,
+        inf: impl IntoIterator<Item = State>,
+    ) {
+        self.pairs.push(Pair {
+            fin: fin.into_iter().collect(),
+            inf: inf.into_iter().collect(),
+        });
+    }
+
+    pub fn automaton(&self) -> &Buchi {
+        &self.automaton
+    }
+
+    pub fn pairs(&self) -> &[Pair] {
+        &self.pairs
+    }
+
+    /// Verify that no run is accepting, otherwise the word of an accepting run is returned. The
+    /// SCCs are refined (Emerson and Lei): a component in which `fin` of a pair occurs but `inf`
+    /// does not can only be used without the states of `fin`.
+    pub fn verify(&self) -> Result<(), Trace> {
+        let automaton = &self.automaton;
+        let mut components = automaton.tarjans_scc();
+        while let Some(component) = components.pop() {
+            let restricted = automaton.restrict(&component);
+            if restricted.scc_is_trivial(&component) {
+                continue;
+            }
+            // States of pairs whose inf set can not be visited in the component
+            let unfair: HashSet<State> = self
+                .pairs
+                .iter()
+                .filter(|p| p.inf.is_disjoint(&component))
+                .flat_map(|p| p.fin.intersection(&component).copied())
+                .collect();
+            if unfair.is_empty() {
+                let visits = self
+                    .pairs
+                    .iter()
+                    .map(|p| &p.inf)
+                    .filter(|inf| !inf.is_disjoint(&component))
+                    .collect_vec();
+                if let Some(trace) = automaton.lasso(&component, &visits) {
+                    return Err(trace);
+                }
+            } else {
+                let allowed = component.difference(&unfair).copied().collect();
+                components.extend(automaton.restrict(&allowed).tarjans_scc());
+            }
+        }
+        Ok(())
+    }
+
+    /// Transform the automaton into a HOA formatted string
+    pub fn hoa(&self) -> String {
+        let condition = (0..self.pairs.len())
+            .map(|i| format!("(Fin({})|Inf({}))", 2 * i, 2 * i + 1))
+            .join(" & ");
+        let condition = if condition.is_empty() {
+            "t".into()
+        } else {
+            condition
+        };
+        write_hoa(&self.automaton, &self.pairs, "Streett", &condition)
+    }
+}
+
+fn without_acceptance(mut automaton: Buchi) -> Buchi {
+    automaton.accepting_sets.clear();
+    automaton.accepting_transitions.clear();
+    automaton
+}
+
+impl Buchi {
+    /// Automaton with only the given states and the transitions between them, the states keep
+    /// their ids
+    fn restrict(&self, states: &HashSet<State>) -> Buchi {
+        let mut restricted = self.clone();
+        restricted.states.retain(|s, _| states.contains(s));
+        for transitions in restricted.states.values_mut() {
+            for targets in transitions.values_mut() {
+                targets.retain(|t| states.contains(t));
+            }
+            transitions.retain(|_, targets| !targets.is_empty());
+        }
+        restricted.initial_states.retain(|s| states.contains(s));
+        restricted
+    }
+
+    /// Word leading into the non trivial component and around a cycle within it which visits
+    /// each of the sets, `None` if the component is not reachable
+    fn lasso(&self, component: &HashSet<State>, sets: &[&HashSet<State>]) -> Option<Trace> {
+        let entries: HashSet<State> = match sets.first() {
+            Some(set) => set.intersection(component).copied().collect(),
+            None => component.clone(),
+        };
+        let (prefix, entry) = self
+            .initial_states
+            .iter()
+            .sorted()
+            .find_map(|i| self.shortest_path(*i, |s| entries.contains(s), |_| true, false))?;
+
+        let within = |s: &State| component.contains(s);
+        let mut cycle = vec![];
+        let mut current = entry;
+        for set in sets.iter().skip(1) {
+            let (words, reached) = self
+                .shortest_path(current, |s| set.contains(s), within, false)
+                .expect("Every state of a component is reachable from the others");
+            cycle.extend(words);
+            current = reached;
+        }
+        let (words, _) = self
+            .shortest_path(current, |s| *s == entry, within, cycle.is_empty())
+            .expect("Every state of a component is reachable from the others");
+        cycle.extend(words);
+        Some(Trace::new(prefix, cycle))
+    }
+}
+
+/// HOA with state based acceptance, the sets of pair i are 2i and 2i + 1
+fn write_hoa(automaton: &Buchi, pairs: &[Pair], name: &str, condition: &str) -> String {
+    // States are numbered densely in the order of their ids
+    let states = automaton.states().into_iter().sorted().collect_vec();
+    let index = |s: &State| states.binary_search(s).unwrap();
+    let mut out = String::new();
+    writeln!(&mut out, "HOA: v1").unwrap();
+    writeln!(&mut out, "States: {}", states.len()).unwrap();
+    for initial in automaton.initial_states.iter().sorted() {
+        writeln!(&mut out, "Start: {}", index(initial)).unwrap();
+    }
+    let propositions = Propositions::new(
+        &automaton.aps,
+        automaton.states.values().flat_map(|t| t.keys()),
+    );
+    write!(&mut out, "{}", propositions.header()).unwrap();
+    writeln!(&mut out, "acc-name: {} {}", name, pairs.len()).unwrap();
+    writeln!(&mut out, "Acceptance: {} {}", 2 * pairs.len(), condition).unwrap();
+    writeln!(&mut out, "properties: state-acc").unwrap();
+    writeln!(&mut out, "--BODY--").unwrap();
+    for state in &states {
+        let marks = pairs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, p)| {
+                [(2 * i, &p.fin), (2 * i + 1, &p.inf)]
+                    .into_iter()
+                    .filter(|(_, set)| set.contains(state))
+                    .map(|(m, _)| m)
+            })
+            .collect_vec();
+        write!(&mut out, "State: {}", index(state)).unwrap();
+        if let Some(label) = automaton.labels.get(state) {
+            write!(&mut out, " \"{}\"", label).unwrap();
+        }
+        if !marks.is_empty() {
+            write!(&mut out, " {{{}}}", marks.iter().join(" ")).unwrap();
+        }
+        writeln!(&mut out).unwrap();
+        let transitions = automaton.states[state]
+            .iter()
+            .sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id));
+        for (word, targets) in transitions {
+            for target in targets.iter().sorted() {
+                writeln!(&mut out, "  {} {}", propositions.label(word), index(target)).unwrap();
+            }
+        }
+    }
+    write!(&mut out, "--END--").unwrap();
+    out
+}
@@ -0,0 +1,206 @@
+// Lazily evaluated products of transition systems and Büchi automata
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+
+use itertools::Itertools;
+use ltl::alphabet::Valuation;
+use ts::{accepting_lasso, TransitionSystem};
+
+use super::{Buchi, State, Trace, Word};
+
+impl TransitionSystem for Buchi {
+    type State = State;
+    type Label = Word;
+    type Error = Infallible;
+
+    fn initial_states(&self) -> Vec<State> {
+        self.initial_states().iter().copied().sorted().collect()
+    }
+
+    fn successors(&self, state: &State) -> Result<Vec<(Word, State)>, Infallible> {
+        Ok(self
+            .states
+            .get(state)
+            .into_iter()
+            .flatten()
+            .sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id))
+            .flat_map(|(word, targets)| targets.iter().sorted().map(|t| (word.clone(), *t)))
+            .collect())
+    }
+
+    /// States labeled with a set such as '{a, b}' satisfy its elements
+    fn holds(&self, state: &State, proposition: &str) -> bool {
+        self.label(state).is_some_and(|label| {
+            label
+                .trim_start_matches('{')
+                .trim_end_matches('}')
+                .split(',')
+                .any(|element| element.trim() == proposition)
+        })
+    }
+}
+
+/// Product of a transition system with an automaton, accepting states are the ones whose
+/// automaton state is accepting
+///
+/// Successors are computed on demand, so the nested depth first search of `ts::accepting_lasso`
+/// only explores the part of the product it visits.
+pub struct Product<'a, T: TransitionSystem<Label = Word>> {
+    system: &'a T,
+    /// Degeneralized automaton, without acceptance sets every state is accepting
+    automaton: Buchi,
+}
+
+impl<'a, T: TransitionSystem<Label = Word>> Product<'a, T> {
+    pub fn new(system: &'a T, automaton: &Buchi) -> Self {
+        Product {
+            system,
+            automaton: automaton.gnba_to_nba(),
+        }
+    }
+
+    pub fn is_accepting(&self, (_, q): &(T::State, State)) -> bool {
+        self.automaton
+            .accepting_sets
+            .first()
+            .is_none_or(|set| set.contains(q))
+    }
+}
+
+impl<T: TransitionSystem<Label = Word, Error = Infallible>> Product<'_, T> {
+    /// Verify that no run of the system is accepted by the automaton, otherwise the word of an
+    /// accepting run is returned
+    pub fn verify(&self) -> Result<(), Trace> {
+        let words = |path: Vec<(Word, _)>| path.into_iter().map(|(word, _)| word).collect();
+        match accepting_lasso(self, |s| self.is_accepting(s)) {
+            Ok(None) => Ok(()),
+            Ok(Some((stem, cycle))) => Err(Trace::new(words(stem), words(cycle))),
+            Err(never) => match never {},
+        }
+    }
+}
+
+impl<T: TransitionSystem<Label = Word>> TransitionSystem for Product<'_, T> {
+    type State = (T::State, State);
+    type Label = Word;
+    type Error = T::Error;
+
+    fn initial_states(&self) -> Vec<Self::State> {
+        self.system
+            .initial_states()
+            .into_iter()
+            .cartesian_product(self.automaton.initial_states().iter().copied().sorted())
+            .collect()
+    }
+
+    fn successors(&self, (s, q): &Self::State) -> Result<Vec<(Word, Self::State)>, T::Error> {
+        let transitions = self.automaton.states.get(q).into_iter().flatten();
+        let transitions = transitions
+            .sorted_by(|(a, _), (b, _)| a.id.cmp(&b.id))
+            .collect_vec();
+        let mut successors = vec![];
+        for (letter, s_next) in self.system.successors(s)? {
+            for (_, targets) in transitions.iter().filter(|(w, _)| w.matches(&letter)) {
+                for q_next in targets.iter().sorted() {
+                    successors.push((letter.clone(), (s_next.clone(), *q_next)));
+                }
+            }
+        }
+        Ok(successors)
+    }
+
+    fn holds(&self, (s, _): &Self::State, proposition: &str) -> bool {
+        self.system.holds(s, proposition)
+    }
+}
+
+/// Product of a transition system, given as automaton whose states are labeled with the set of
+/// propositions holding in them such as "{a, b}", with an automaton reading these sets
+///
+/// The initial states are the pairs of an initial state s of the system and the states the
+/// automaton reaches from an initial state by reading the label of s. A transition of the system
+/// from s to t is paired with every transition of the automaton reading the label of t, and keeps
+/// the word of the system. Only reachable pairs are created, labeled like "<s0,q1>({a},q1)". The
+/// acceptance sets are those of the automaton projected onto the pairs, so the product accepts
+/// exactly the runs of the system whose labels the automaton accepts.
+///
+/// Guards of the automaton match a state if they hold for the propositions holding in it, see
+/// `Buchi::guard_valuation_words` for turning words naming sets into guards. Other words match a
+/// state if they are "{true}" or equal to its label.
+pub fn ts_and_buchi_product(ts: &Buchi, automaton: &Buchi) -> Buchi {
+    let automaton = match automaton.is_transition_based() {
+        true => automaton.state_based(),
+        false => automaton.clone(),
+    };
+    let mut product = Buchi::new();
+    let mut states: HashMap<(State, State), State> = HashMap::new();
+    let mut queue = VecDeque::new();
+    let mut pair = |product: &mut Buchi, queue: &mut VecDeque<_>, s: State, q: State| {
+        *states.entry((s, q)).or_insert_with(|| {
+            let label = format!(
+                "<s{},q{}>({},{})",
+                s.id,
+                q.id,
+                ts.label_of(s),
+                automaton.label_of(q)
+            );
+            queue.push_back((s, q));
+            product.new_labeled_state(label)
+        })
+    };
+    // States of the automaton reached from q when the system enters s
+    let aps = automaton.aps();
+    let reading = |q: State, s: State| {
+        let label = Word::new(ts.label_of(s));
+        let valuation = aps.valuation(aps.iter().filter(|ap| TransitionSystem::holds(ts, &s, ap)));
+        automaton
+            .successors(q)
+            .into_iter()
+            .flatten()
+            .filter(|(word, _)| reads(word, valuation, &label))
+            .flat_map(|(_, targets)| targets.iter().copied())
+            .sorted()
+            .dedup()
+            .collect_vec()
+    };
+
+    for s in ts.initial_states().iter().sorted() {
+        for q0 in automaton.initial_states().iter().sorted() {
+            for q in reading(*q0, *s) {
+                let initial = pair(&mut product, &mut queue, *s, q);
+                product.set_initial_state(initial);
+            }
+        }
+    }
+    while let Some((s, q)) = queue.pop_front() {
+        let source = pair(&mut product, &mut queue, s, q);
+        for (word, t) in TransitionSystem::successors(ts, &s).unwrap_or_else(|never| match never {})
+        {
+            for p in reading(q, t) {
+                let target = pair(&mut product, &mut queue, t, p);
+                product.add_transition(source, target, word.clone());
+            }
+        }
+    }
+
+    for set in automaton.accepting_sets() {
+        product.add_accepting_set(
+            states
+                .iter()
+                .filter(|((_, q), _)| set.contains(q))
+                .map(|(_, state)| *state),
+        );
+    }
+    product
+}
+
+/// Whether the word of the automaton can be read in a state of the system. Guards are evaluated
+/// on the valuation of the propositions of the automaton in the state, other words have to match
+/// the label of the state, see `Word::matches`.
+fn reads(word: &Word, valuation: Valuation, label: &Word) -> bool {
+    match word.literals {
+        Some(literals) => literals.holds(valuation),
+        None => word.id == "{true}" || word.matches(label),
+    }
+}
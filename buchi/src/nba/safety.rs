@@ -0,0 +1,91 @@
+// Subset construction for safety automata
+
+use std::collections::{BTreeSet, HashMap};
+
+use itertools::Itertools;
+use ltl::alphabet::Valuation;
+
+use super::{Buchi, State, Word};
+
+/// Reachable sets of states, the first one consists of the initial states, and the index of the
+/// successor of every set for each letter
+///
+/// In a safety automaton a word is accepted iff every prefix has a run, as the automaton is
+/// finitely branching (König's lemma), so the word is rejected once its set becomes empty.
+pub(super) struct Subsets {
+    pub(super) sets: Vec<BTreeSet<State>>,
+    pub(super) successors: Vec<Vec<usize>>,
+}
+
+impl Buchi {
+    /// Whether every state is accepting, the language then consists of the words with an infinite
+    /// run. Determinization and complementation use the subset construction for such automata.
+    pub fn is_safety(&self) -> bool {
+        !self.is_transition_based()
+            && self
+                .accepting_sets
+                .iter()
+                .all(|set| self.states.keys().all(|s| set.contains(s)))
+    }
+
+    pub(super) fn subsets(&self, letters: &[(Word, Option<Valuation>)]) -> Subsets {
+        let initial: BTreeSet<State> = self.initial_states.iter().copied().collect();
+        let mut index = HashMap::from([(initial.clone(), 0)]);
+        let mut subsets = Subsets {
+            sets: vec![initial],
+            successors: vec![],
+        };
+        while subsets.successors.len() < subsets.sets.len() {
+            let set = subsets.sets[subsets.successors.len()].clone();
+            let mut successors = vec![];
+            for (word, valuation) in letters {
+                let successor: BTreeSet<State> = set
+                    .iter()
+                    .flat_map(|s| self.letter_successors(*s, word, *valuation))
+                    .collect();
+                let next = index.len();
+                successors.push(*index.entry(successor.clone()).or_insert_with(|| {
+                    subsets.sets.push(successor);
+                    next
+                }));
+            }
+            subsets.successors.push(successors);
+        }
+        subsets
+    }
+
+    pub(super) fn print_subset(&self, set: &BTreeSet<State>) -> String {
+        if set.is_empty() {
+            "∅".into()
+        } else {
+            format!("{{{}}}", set.iter().map(|s| self.label_of(*s)).join(", "))
+        }
+    }
+
+    /// Complement of a safety automaton, it accepts once no run is left
+    pub(super) fn complement_safety(&self, letters: &[(Word, Option<Valuation>)]) -> Buchi {
+        let subsets = self.subsets(letters);
+        let mut complement = Buchi::new();
+        complement.aps = self.aps.clone();
+        let states = subsets
+            .sets
+            .iter()
+            .map(|set| complement.new_labeled_state(self.print_subset(set)))
+            .collect_vec();
+        complement.set_initial_state(states[0]);
+        for (source, successors) in states.iter().zip(&subsets.successors) {
+            for ((word, _), target) in letters.iter().zip(successors) {
+                complement.add_transition(*source, states[*target], word.clone());
+            }
+        }
+        complement.add_accepting_set(
+            subsets
+                .sets
+                .iter()
+                .zip(&states)
+                .filter(|(set, _)| set.is_empty())
+                .map(|(_, state)| *state),
+        );
+        complement
+    }
+}
@@ -0,0 +1,256 @@
+// Simulation relations and the reduction of automata by quotienting
+
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use super::{Buchi, State, Word};
+
+impl Buchi {
+    /// Pairs (q, p) such that p directly simulates q: p matches every transition of q with one
+    /// for the same letters such that the targets are again related, and p is accepting whenever
+    /// q is
+    ///
+    /// Panics if the automaton has accepting transitions, see `Buchi::state_based`.
+    pub fn direct_simulation(&self) -> HashSet<(State, State)> {
+        assert!(
+            !self.is_transition_based(),
+            "Simulation requires state based acceptance"
+        );
+        let states = self.states().into_iter().sorted().collect_vec();
+        let mut relation: HashSet<(State, State)> = states
+            .iter()
+            .cartesian_product(&states)
+            .filter(|(q, p)| {
+                self.accepting_sets
+                    .iter()
+                    .all(|set| !set.contains(q) || set.contains(p))
+            })
+            .map(|(q, p)| (*q, *p))
+            .collect();
+        loop {
+            let refined: HashSet<_> = relation
+                .iter()
+                .filter(|(q, p)| {
+                    self.edges(*q).all(|(word, q_target)| {
+                        self.matching_edges(*p, word)
+                            .any(|p_target| relation.contains(&(q_target, p_target)))
+                    })
+                })
+                .copied()
+                .collect();
+            if refined.len() == relation.len() {
+                return relation;
+            }
+            relation = refined;
+        }
+    }
+
+    /// Pairs (q, p) such that p delayed simulates q, where p only has to visit an accepting state
+    /// at some point after q did. The game is solved as a Büchi game in which the duplicator has to
+    /// clear the pending obligation infinitely often.
+    ///
+    /// Panics if the automaton has more than one acceptance set or accepting transitions, delayed
+    /// simulation is only defined for NBAs.
+    pub fn delayed_simulation(&self) -> HashSet<(State, State)> {
+        assert!(
+            self.accepting_sets.len() <= 1 && !self.is_transition_based(),
+            "Delayed simulation requires at most one acceptance set"
+        );
+        // Without acceptance sets every state is accepting
+        let accepting = |s: &State| self.accepting_sets.first().is_none_or(|f| f.contains(s));
+        // Whether an obligation is pending after the spoiler moved to q and the duplicator to p
+        let pending = |b: bool, q: &State, p: &State| (b || accepting(q)) && !accepting(p);
+
+        let states = self.states().into_iter().sorted().collect_vec();
+        let positions = states
+            .iter()
+            .cartesian_product(&states)
+            .cartesian_product([false, true])
+            .map(|((q, p), b)| (*q, *p, b))
+            .collect_vec();
+        // Positions from which the duplicator can force the next position into the set
+        let controllable = |(q, p, b): &(State, State, bool),
+                            set: &HashSet<(State, State, bool)>| {
+            self.edges(*q).all(|(word, q_target)| {
+                self.matching_edges(*p, word).any(|p_target| {
+                    set.contains(&(q_target, p_target, pending(*b, &q_target, &p_target)))
+                })
+            })
+        };
+
+        // νZ. μY. (¬b ∧ CPre(Z)) ∨ CPre(Y)
+        let mut z: HashSet<_> = positions.iter().copied().collect();
+        loop {
+            let mut y = HashSet::new();
+            loop {
+                let next: HashSet<_> = positions
+                    .iter()
+                    .filter(|pos| (!pos.2 && controllable(pos, &z)) || controllable(pos, &y))
+                    .copied()
+                    .collect();
+                if next.len() == y.len() {
+                    break;
+                }
+                y = next;
+            }
+            if y.len() == z.len() {
+                break;
+            }
+            z = y;
+        }
+
+        states
+            .iter()
+            .cartesian_product(&states)
+            .filter(|(q, p)| z.contains(&(**q, **p, pending(false, q, p))))
+            .map(|(q, p)| (*q, *p))
+            .collect()
+    }
+
+    /// Remove states which are unreachable or from which no accepting run starts and merge
+    /// states which simulate each other, using delayed simulation if there is at most one
+    /// acceptance set and direct simulation otherwise
+    pub fn reduce(&self) -> Buchi {
+        if self.is_transition_based() {
+            return self.state_based().reduce();
+        }
+        let trimmed = self.trim();
+        let simulation = if trimmed.accepting_sets.len() <= 1 {
+            trimmed.delayed_simulation()
+        } else {
+            trimmed.direct_simulation()
+        };
+        // Every state is represented by the smallest state it is equivalent to
+        let mut representatives: HashMap<State, State> = HashMap::new();
+        for (q, p) in simulation.iter().sorted() {
+            if simulation.contains(&(*p, *q)) {
+                let representative = representatives.entry(*q).or_insert(*p);
+                *representative = (*representative).min(*p);
+            }
+        }
+        trimmed.quotient(|s| Some(representatives.get(&s).copied().unwrap_or(s)))
+    }
+
+    /// Remove states which are unreachable or from which no accepting run starts, the remaining
+    /// states are renumbered densely in the order of their ids
+    pub fn trim(&self) -> Buchi {
+        let useful = self.useful_states();
+        self.quotient(|s| useful.contains(&s).then_some(s))
+    }
+
+    /// States reachable from an initial state from which an accepting run starts
+    fn useful_states(&self) -> HashSet<State> {
+        let accepting_sccs = self
+            .tarjans_scc()
+            .into_iter()
+            .filter(|c| !self.scc_is_trivial(c))
+            .filter(|c| self.accepting_sets.iter().all(|set| !set.is_disjoint(c)))
+            .filter(|c| {
+                self.accepting_transitions
+                    .iter()
+                    .all(|set| set.iter().any(|(s, _, t)| c.contains(s) && c.contains(t)))
+            })
+            .collect_vec();
+        let reachable = self.reachable(self.initial_states.iter().copied());
+        // States which can reach one of the components, found by searching backwards
+        let mut predecessors: HashMap<State, Vec<State>> = HashMap::new();
+        for (source, transitions) in &self.states {
+            for target in transitions.values().flatten() {
+                predecessors.entry(*target).or_default().push(*source);
+            }
+        }
+        let mut productive: HashSet<State> = accepting_sccs.into_iter().flatten().collect();
+        let mut stack = productive.iter().copied().collect_vec();
+        while let Some(state) = stack.pop() {
+            for p in predecessors.get(&state).into_iter().flatten() {
+                if productive.insert(*p) {
+                    stack.push(*p);
+                }
+            }
+        }
+        reachable.intersection(&productive).copied().collect()
+    }
+
+    /// States reachable from the given ones, including them
+    fn reachable(&self, from: impl IntoIterator<Item = State>) -> HashSet<State> {
+        let mut reachable: HashSet<State> = from.into_iter().collect();
+        let mut stack = reachable.iter().copied().collect_vec();
+        while let Some(state) = stack.pop() {
+            for target in self
+                .states
+                .get(&state)
+                .into_iter()
+                .flat_map(|t| t.values().flatten())
+            {
+                if reachable.insert(*target) {
+                    stack.push(*target);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Automaton in which every state is replaced by its class, states without a class are
+    /// removed. A class belongs to an acceptance set if one of its states does. The classes are
+    /// numbered in the order of their smallest states.
+    fn quotient(&self, class: impl Fn(State) -> Option<State>) -> Buchi {
+        let mut quotient = Buchi::new();
+        quotient.aps = self.aps.clone();
+        let mut index: HashMap<State, State> = HashMap::new();
+        for state in self.states().into_iter().sorted() {
+            if let Some(c) = class(state) {
+                index.entry(c).or_insert_with(|| match self.labels.get(&c) {
+                    Some(label) => quotient.new_labeled_state(label.clone()),
+                    None => quotient.new_state(),
+                });
+            }
+        }
+        let map = |s: &State| class(*s).map(|c| index[&c]);
+
+        for (source, transitions) in &self.states {
+            let Some(source) = map(source) else {
+                continue;
+            };
+            for (word, targets) in transitions {
+                for target in targets.iter().filter_map(map) {
+                    quotient.add_transition(source, target, word.clone());
+                }
+            }
+        }
+        for initial in self.initial_states.iter().filter_map(map) {
+            quotient.set_initial_state(initial);
+        }
+        for set in &self.accepting_sets {
+            quotient.add_accepting_set(set.iter().filter_map(map));
+        }
+        for set in &self.accepting_transitions {
+            quotient.add_accepting_transitions(
+                set.iter()
+                    .filter_map(|(s, w, t)| Some((map(s)?, w.clone(), map(t)?))),
+            );
+        }
+        quotient
+    }
+
+    /// Transitions of the state as pairs of words and targets
+    fn edges(&self, state: State) -> impl Iterator<Item = (&Word, State)> {
+        self.states
+            .get(&state)
+            .into_iter()
+            .flatten()
+            .flat_map(|(word, targets)| targets.iter().map(move |t| (word, *t)))
+    }
+
+    /// Targets of the transitions of the state which can be taken for every letter of the word,
+    /// guards have to be weaker than the guard of the word
+    fn matching_edges<'a>(
+        &'a self,
+        state: State,
+        word: &'a Word,
+    ) -> impl Iterator<Item = State> + 'a {
+        self.edges(state)
+            .filter(move |(w, _)| w.matches(word))
+            .map(|(_, t)| t)
+    }
+}
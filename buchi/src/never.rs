@@ -0,0 +1,420 @@
+// Never claims, the Büchi automata of SPIN written in Promela
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use ltl::alphabet::{ApSet, Literals};
+
+use crate::error::{Error, Result};
+use crate::hoa::Label;
+use crate::nba::{Buchi, State, Word};
+
+/// A state of the claim with its options, the guards and the labels they jump to
+struct ClaimState {
+    name: String,
+    options: Vec<(Vec<Literals>, String)>,
+}
+
+/// Parse a never claim into a Büchi automaton
+///
+/// A claim is a sequence of labeled states, each a selection of options which jump to another
+/// state if their guard holds:
+///
+/// ```text
+/// never { /* G F a */
+/// T0_init:
+///   if
+///   :: (a) -> goto accept_S1
+///   :: (1) -> goto T0_init
+///   fi;
+/// accept_S1:
+///   if
+///   :: (1) -> goto T0_init
+///   fi;
+/// }
+/// ```
+///
+/// The first state is initial and states whose label starts with 'accept' are accepting. Guards
+/// over propositions with '!', '&&' and '||' become one guarded transition per cube. `do` loops
+/// are read like selections whose options without a jump stay in the state, `skip` is a state
+/// looping on every letter and `false` a state without successors.
+pub fn parse(input: &str) -> Result<Buchi> {
+    let tokens = tokens(input)?;
+    let mut aps = ApSet::new();
+    let states = Parser {
+        tokens: &tokens,
+        position: 0,
+    }
+    .claim(&mut aps)?;
+
+    let mut buchi = Buchi::new();
+    buchi.set_aps(aps.clone());
+    let mut index: HashMap<&str, State> = HashMap::new();
+    for (line, state) in &states {
+        let new = buchi.new_labeled_state(state.name.clone());
+        if index.insert(&state.name, new).is_some() {
+            return Err(invalid(
+                *line,
+                format!("state '{}' is declared twice", state.name),
+            ));
+        }
+    }
+    if let Some((_, first)) = states.first() {
+        buchi.set_initial_state(index[first.name.as_str()]);
+    }
+    for (line, state) in &states {
+        let source = index[state.name.as_str()];
+        for (cubes, target) in &state.options {
+            let target = *index
+                .get(target.as_str())
+                .ok_or_else(|| invalid(*line, format!("no state is labeled '{}'", target)))?;
+            for cube in cubes {
+                buchi.add_transition(source, target, Word::guard(&aps, *cube));
+            }
+        }
+    }
+    buchi.add_accepting_set(
+        states
+            .iter()
+            .filter(|(_, s)| s.name.starts_with("accept"))
+            .map(|(_, s)| index[s.name.as_str()]),
+    );
+    Ok(buchi)
+}
+
+impl Buchi {
+    /// The trimmed and degeneralized automaton as never claim
+    ///
+    /// Automata with several initial states start in an extra state `T0_init` with the
+    /// transitions of all of them, without acceptance sets every state is accepting. Words which
+    /// are no guards are written as a proposition of the same name.
+    pub fn never_claim(&self) -> String {
+        let nba = self.trim().gnba_to_nba();
+        let accepting = |state: &State| match nba.accepting_sets() {
+            [] => true,
+            sets => sets[0].contains(state),
+        };
+        let initial = nba.initial_states().iter().sorted().collect_vec();
+        let name = |state: &State| {
+            let prefix = if accepting(state) { "accept" } else { "T0" };
+            if initial.len() == 1 && initial[0] == state {
+                format!("{}_init", prefix)
+            } else {
+                format!("{}_S{}", prefix, state.id)
+            }
+        };
+        let guard = |literals: Option<Literals>, label: &str| match literals {
+            Some(literals) => {
+                let guard = (0..nba.aps().len())
+                    .filter_map(|i| {
+                        if literals.positive.contains(i) {
+                            Some(nba.aps().name(i).to_string())
+                        } else if literals.negative.contains(i) {
+                            Some(format!("!{}", nba.aps().name(i)))
+                        } else {
+                            None
+                        }
+                    })
+                    .join(" && ");
+                format!("({})", if guard.is_empty() { "1".into() } else { guard })
+            }
+            None => format!("({})", label),
+        };
+
+        let transitions = nba.transitions();
+        let mut out = String::from("never {\n");
+        let write_state = |out: &mut String, label: String, sources: &[&State]| {
+            out.push_str(&format!("{}:\n", label));
+            let options = transitions
+                .iter()
+                .filter(|t| sources.contains(&&t.from_state))
+                .map(|t| (guard(t.literals, t.label), t.to_state))
+                .unique()
+                .collect_vec();
+            if options.is_empty() {
+                out.push_str("  false;\n");
+                return;
+            }
+            out.push_str("  if\n");
+            for (guard, target) in options {
+                out.push_str(&format!("  :: {} -> goto {}\n", guard, name(&target)));
+            }
+            out.push_str("  fi;\n");
+        };
+        match initial.len() {
+            0 => write_state(&mut out, "T0_init".into(), &[]),
+            1 => {}
+            _ => write_state(&mut out, "T0_init".into(), &initial),
+        }
+        // The initial state comes first
+        let states = nba.states();
+        let states = states
+            .iter()
+            .sorted_by_key(|s| (!(initial.len() == 1 && initial[0] == *s), s.id));
+        for state in states {
+            write_state(&mut out, name(state), &[state]);
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn invalid(line: usize, reason: String) -> Error {
+    Error::Never { line, reason }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    /// '::' starting an option
+    Option,
+    Arrow,
+    Colon,
+    Semicolon,
+    Symbol(&'static str),
+}
+
+/// Tokens with their lines, comments are dropped
+fn tokens(input: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let mut last = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            last = c;
+                        }
+                        None => return Err(invalid(line, "unterminated comment".into())),
+                    }
+                }
+                continue;
+            }
+            ':' if chars.next_if_eq(&':').is_some() => Token::Option,
+            ':' => Token::Colon,
+            ';' => Token::Semicolon,
+            '-' if chars.next_if_eq(&'>').is_some() => Token::Arrow,
+            '&' if chars.next_if_eq(&'&').is_some() => Token::Symbol("&"),
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Symbol("|"),
+            '!' => Token::Symbol("!"),
+            '(' => Token::Symbol("("),
+            ')' => Token::Symbol(")"),
+            '{' => Token::Symbol("{"),
+            '}' => Token::Symbol("}"),
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                Token::Name(name)
+            }
+            c => return Err(invalid(line, format!("unexpected character '{}'", c))),
+        };
+        tokens.push((line, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    position: usize,
+}
+
+impl Parser<'_> {
+    /// Line of the current token, or of the last one at the end of the input
+    fn line(&self) -> usize {
+        let last = self.tokens.last().map_or(1, |(line, _)| *line);
+        self.tokens
+            .get(self.position)
+            .map_or(last, |(line, _)| *line)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        self.position += 1;
+        self.tokens.get(self.position - 1).map(|(_, token)| token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        let line = self.line();
+        match self.next() {
+            Some(token) if *token == expected => Ok(()),
+            Some(token) => Err(invalid(
+                line,
+                format!("expected {:?}, found {:?}", expected, token),
+            )),
+            None => Err(invalid(line, format!("expected {:?}", expected))),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<()> {
+        self.expect(Token::Name(keyword.into()))
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let line = self.line();
+        match self.next() {
+            Some(Token::Name(name)) => Ok(name.clone()),
+            token => Err(invalid(
+                line,
+                format!("expected a label, found {:?}", token),
+            )),
+        }
+    }
+
+    fn skip_semicolon(&mut self) {
+        if self.peek() == Some(&Token::Semicolon) {
+            self.position += 1;
+        }
+    }
+
+    fn claim(&mut self, aps: &mut ApSet) -> Result<Vec<(usize, ClaimState)>> {
+        self.keyword("never")?;
+        self.expect(Token::Symbol("{"))?;
+        let mut states = vec![];
+        while self.peek() != Some(&Token::Symbol("}")) {
+            let line = self.line();
+            let name = self.name()?;
+            self.expect(Token::Colon)?;
+            let options = self.body(&name, aps)?;
+            states.push((line, ClaimState { name, options }));
+        }
+        self.expect(Token::Symbol("}"))?;
+        match self.peek() {
+            None => Ok(states),
+            Some(token) => Err(invalid(
+                self.line(),
+                format!("unexpected {:?} after the claim", token),
+            )),
+        }
+    }
+
+    /// Options of the state with the given name
+    fn body(&mut self, name: &str, aps: &mut ApSet) -> Result<Vec<(Vec<Literals>, String)>> {
+        let line = self.line();
+        let options = match self.name()?.as_str() {
+            "skip" => vec![(vec![Literals::empty()], name.to_string())],
+            "false" => vec![],
+            "goto" => vec![(vec![Literals::empty()], self.name()?)],
+            keyword @ ("if" | "do") => {
+                let end = if keyword == "if" { "fi" } else { "od" };
+                let mut options = vec![];
+                while self.peek() == Some(&Token::Option) {
+                    self.position += 1;
+                    let guard = self.guard(aps)?;
+                    let target = if self.peek() == Some(&Token::Arrow) {
+                        self.position += 1;
+                        self.keyword("goto")?;
+                        self.name()?
+                    } else if keyword == "do" {
+                        name.to_string()
+                    } else {
+                        return Err(invalid(
+                            self.line(),
+                            "options have to jump to a state".into(),
+                        ));
+                    };
+                    self.skip_semicolon();
+                    options.push((guard, target));
+                }
+                self.keyword(end)?;
+                options
+            }
+            statement => {
+                return Err(invalid(
+                    line,
+                    format!("unsupported statement '{}'", statement),
+                ))
+            }
+        };
+        self.skip_semicolon();
+        Ok(options)
+    }
+
+    /// Guard of an option in disjunctive normal form, read as a label of a HOA file
+    fn guard(&mut self, aps: &mut ApSet) -> Result<Vec<Literals>> {
+        let line = self.line();
+        let mut label = vec![];
+        while let Some(token) = self.peek() {
+            let part = match token {
+                Token::Name(name) => match name.as_str() {
+                    "fi" | "od" => break,
+                    "1" | "true" => "t".to_string(),
+                    "0" | "false" => "f".to_string(),
+                    name => {
+                        if aps.len() == ApSet::MAX && aps.index(name).is_none() {
+                            return Err(invalid(line, "too many atomic propositions".into()));
+                        }
+                        aps.insert(name).to_string()
+                    }
+                },
+                Token::Symbol(symbol @ ("!" | "&" | "|" | "(" | ")")) => symbol.to_string(),
+                _ => break,
+            };
+            label.push(part);
+            self.position += 1;
+        }
+        Label::new(&label.join(" "), aps.len())
+            .parse()
+            .map_err(|reason| invalid(line, reason))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn never_claims() {
+        // F G a as written by Spot, up to the order of the options
+        let input = "never { /* F(G(a)) */
+T0_init:
+  if
+  :: (a) -> goto accept_S1
+  :: (1) -> goto T0_init
+  fi;
+accept_S1:
+  if
+  :: (a) -> goto accept_S1
+  fi;
+}
+";
+        let nba = parse(input).unwrap();
+        assert_eq!(nba.aps().iter().collect_vec(), ["a"]);
+        assert_eq!(nba.stats().states, 2);
+        assert_eq!(nba.never_claim(), input.replace(" /* F(G(a)) */", ""));
+        let hoa = crate::hoa::parse(&nba.hoa()).unwrap();
+        assert!(hoa.equivalent(&nba).is_ok());
+
+        // Loops, skip and guards which are no cubes
+        let nba = parse(
+            "never {\nT0_init:\n  do\n  :: (!a || b) -> goto accept_all\n  :: (a && !b)\n  od;\n\
+             accept_all:\n  skip\n}",
+        )
+        .unwrap();
+        assert_eq!(nba.stats().transitions, 4);
+        let written = parse(&nba.never_claim()).unwrap();
+        assert!(written.equivalent(&nba).is_ok());
+
+        assert!(parse("never { T0_init: if :: (a) -> goto missing fi; }").is_err());
+        assert!(parse("never { T0_init: if :: (a) fi; }").is_err());
+        assert!(parse("never { T0_init: false; T0_init: false; }").is_err());
+        assert!(parse("never { T0_init: printf; }").is_err());
+        assert!(parse("never { T0_init: if :: (a &&) -> goto T0_init fi; }").is_err());
+    }
+}
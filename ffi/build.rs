@@ -0,0 +1,14 @@
+// Generates include/lmc.h from the functions and types of src/lib.rs
+
+use std::env;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_root_or_default(&crate_dir);
+    cbindgen::generate_with_config(&crate_dir, config)
+        .expect("Could not generate the C header")
+        .write_to_file(Path::new(&crate_dir).join("include/lmc.h"));
+}
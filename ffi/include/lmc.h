@@ -0,0 +1,123 @@
+#ifndef LMC_H
+#define LMC_H
+
+/* Generated by cbindgen from src/lib.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of a call. Functions returning a status pass their results through pointers, which
+// are only written on success, and after a failure `lmc_last_error` describes it.
+typedef enum LmcStatus {
+  LMC_STATUS_OK,
+  // A pointer is null or a string is not UTF-8
+  LMC_STATUS_INVALID_ARGUMENT,
+  // A game, net or formula could not be parsed
+  LMC_STATUS_PARSE_ERROR,
+  // The formula refers to places or transitions the net does not have, or the net overflowed
+  LMC_STATUS_CHECK_ERROR,
+  // The game has no vertex with the id, or the vertex has no strategy
+  LMC_STATUS_NOT_FOUND,
+  // A bug in lmc caught before it reached the caller, the handles passed to the call should no
+  // longer be used
+  LMC_STATUS_PANIC,
+} LmcStatus;
+
+typedef enum LmcAlgorithm {
+  LMC_ALGORITHM_FPI,
+  LMC_ALGORITHM_ZIELONKA,
+  LMC_ALGORITHM_TANGLE,
+  LMC_ALGORITHM_SPM,
+} LmcAlgorithm;
+
+typedef enum LmcPlayer {
+  LMC_PLAYER_EVEN,
+  LMC_PLAYER_ODD,
+} LmcPlayer;
+
+typedef enum LmcVerdict {
+  LMC_VERDICT_HOLDS,
+  LMC_VERDICT_VIOLATED,
+} LmcVerdict;
+
+// Parity game in the PGSolver format
+typedef struct LmcGame LmcGame;
+
+// Petri net in PNML, the TINA .net format or LoLA's format
+typedef struct LmcNet LmcNet;
+
+// Winner of each vertex of a game and the successor chosen in the vertices with a strategy
+typedef struct LmcSolution LmcSolution;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Description of the last failed call on this thread, empty if none failed
+const char *lmc_last_error(void);
+
+// Parse a parity game in the PGSolver format, to be released by `lmc_game_free`
+//
+// # Safety
+// `input` is a nul terminated string and `game` is valid for writes
+enum LmcStatus lmc_parse_game(const char *input, struct LmcGame **game);
+
+// # Safety
+// `game` is null or a game which is not used afterwards
+void lmc_game_free(struct LmcGame *game);
+
+// Solve the game, the solution is to be released by `lmc_solution_free`
+//
+// # Safety
+// `game` is a game and `solution` is valid for writes
+enum LmcStatus lmc_solve(const struct LmcGame *game,
+                         enum LmcAlgorithm algorithm,
+                         struct LmcSolution **solution);
+
+// Player winning from the vertex with the id
+//
+// # Safety
+// `solution` is a solution and `winner` is valid for writes
+enum LmcStatus lmc_solution_winner(const struct LmcSolution *solution,
+                                   uintptr_t vertex,
+                                   enum LmcPlayer *winner);
+
+// Successor the winner of the vertex moves to, if the vertex is owned by the winner
+//
+// # Safety
+// `solution` is a solution and `next` is valid for writes
+enum LmcStatus lmc_solution_strategy(const struct LmcSolution *solution,
+                                     uintptr_t vertex,
+                                     uintptr_t *next);
+
+// # Safety
+// `solution` is null or a solution which is not used afterwards
+void lmc_solution_free(struct LmcSolution *solution);
+
+// Parse a net in PNML, the TINA .net format or LoLA's format, detected by its content, to be
+// released by `lmc_net_free`
+//
+// # Safety
+// `input` is a nul terminated string and `net` is valid for writes
+enum LmcStatus lmc_parse_net(const char *input, struct LmcNet **net);
+
+// # Safety
+// `net` is null or a net which is not used afterwards
+void lmc_net_free(struct LmcNet *net);
+
+// Check whether every run of the net satisfies the LTL formula in prefix notation, such as
+// 'G F 1<=tokens(p1)'
+//
+// # Safety
+// `net` is a net, `formula` is a nul terminated string and `verdict` is valid for writes
+enum LmcStatus lmc_check_ltl(const struct LmcNet *net,
+                             const char *formula,
+                             enum LmcVerdict *verdict);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LMC_H */
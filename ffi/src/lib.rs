@@ -0,0 +1,355 @@
+// C interface to the solvers, for verification frontends calling them in-process
+// include/lmc.h is generated from this file by cbindgen whenever the crate is built
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use lmc::check::Checker;
+use lmc::ltl::Formula;
+use lmc::parity::Graph;
+use lmc::petri::{self, PetriNet};
+
+/// Outcome of a call. Functions returning a status pass their results through pointers, which
+/// are only written on success, and after a failure `lmc_last_error` describes it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LmcStatus {
+    Ok,
+    /// A pointer is null or a string is not UTF-8
+    InvalidArgument,
+    /// A game, net or formula could not be parsed
+    ParseError,
+    /// The formula refers to places or transitions the net does not have, or the net overflowed
+    CheckError,
+    /// The game has no vertex with the id, or the vertex has no strategy
+    NotFound,
+    /// A bug in lmc caught before it reached the caller, the handles passed to the call should no
+    /// longer be used
+    Panic,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LmcAlgorithm {
+    Fpi,
+    Zielonka,
+    Tangle,
+    Spm,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LmcPlayer {
+    Even,
+    Odd,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LmcVerdict {
+    Holds,
+    Violated,
+}
+
+/// Parity game in the PGSolver format
+pub struct LmcGame {
+    graph: Graph,
+}
+
+/// Winner of each vertex of a game and the successor chosen in the vertices with a strategy
+pub struct LmcSolution {
+    winners: HashMap<usize, LmcPlayer>,
+    strategy: HashMap<usize, usize>,
+}
+
+/// Petri net in PNML, the TINA .net format or LoLA's format
+pub struct LmcNet {
+    net: PetriNet,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+type Failure = (LmcStatus, String);
+
+/// Run the body of a call, recording its failure or panic for `lmc_last_error`
+fn call(body: impl FnOnce() -> Result<(), Failure>) -> LmcStatus {
+    let (status, message) = match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => return LmcStatus::Ok,
+        Ok(Err(failure)) => failure,
+        Err(panic) => {
+            let message = (panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            (LmcStatus::Panic, format!("panicked: {}", message))
+        }
+    };
+    // Messages are Rust strings, which may contain nul bytes
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    status
+}
+
+fn invalid(argument: &str) -> Failure {
+    (LmcStatus::InvalidArgument, format!("{} is null", argument))
+}
+
+/// # Safety
+/// `s` is null or a nul terminated string
+unsafe fn string<'a>(s: *const c_char, argument: &str) -> Result<&'a str, Failure> {
+    if s.is_null() {
+        return Err(invalid(argument));
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| {
+        (
+            LmcStatus::InvalidArgument,
+            format!("{} is not UTF-8", argument),
+        )
+    })
+}
+
+/// # Safety
+/// `handle` is null or points to a live handle
+unsafe fn handle<'a, T>(handle: *const T, argument: &str) -> Result<&'a T, Failure> {
+    handle.as_ref().ok_or_else(|| invalid(argument))
+}
+
+/// # Safety
+/// `out` is null or valid for writes
+unsafe fn write<T>(out: *mut T, value: T, argument: &str) -> Result<(), Failure> {
+    if out.is_null() {
+        return Err(invalid(argument));
+    }
+    out.write(value);
+    Ok(())
+}
+
+/// Description of the last failed call on this thread, empty if none failed
+#[no_mangle]
+pub extern "C" fn lmc_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Parse a parity game in the PGSolver format, to be released by `lmc_game_free`
+///
+/// # Safety
+/// `input` is a nul terminated string and `game` is valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn lmc_parse_game(
+    input: *const c_char,
+    game: *mut *mut LmcGame,
+) -> LmcStatus {
+    call(|| {
+        let input = string(input, "input")?;
+        let graph = lmc::parity::parse_game(input).map_err(|e| {
+            (
+                LmcStatus::ParseError,
+                format!("Could not parse parity game: {}", e),
+            )
+        })?;
+        let handle = Box::into_raw(Box::new(LmcGame { graph }));
+        write(game, handle, "game").inspect_err(|_| drop(Box::from_raw(handle)))
+    })
+}
+
+/// # Safety
+/// `game` is null or a game which is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn lmc_game_free(game: *mut LmcGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Solve the game, the solution is to be released by `lmc_solution_free`
+///
+/// # Safety
+/// `game` is a game and `solution` is valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn lmc_solve(
+    game: *const LmcGame,
+    algorithm: LmcAlgorithm,
+    solution: *mut *mut LmcSolution,
+) -> LmcStatus {
+    call(|| {
+        let graph = &handle(game, "game")?.graph;
+        let solved = match algorithm {
+            LmcAlgorithm::Fpi => graph.fpi(),
+            LmcAlgorithm::Zielonka => graph.zielonka(),
+            LmcAlgorithm::Tangle => graph.tangle(),
+            LmcAlgorithm::Spm => graph.spm(),
+        };
+        let winners = (solved.even_region.iter().map(|v| (v.id, LmcPlayer::Even)))
+            .chain(solved.odd_region.iter().map(|v| (v.id, LmcPlayer::Odd)))
+            .collect();
+        let strategy = (solved.strategy.iter())
+            .filter_map(|(vertex, strategy)| Some((*vertex, strategy.next_node_id?)))
+            .collect();
+        let handle = Box::into_raw(Box::new(LmcSolution { winners, strategy }));
+        write(solution, handle, "solution").inspect_err(|_| drop(Box::from_raw(handle)))
+    })
+}
+
+/// Player winning from the vertex with the id
+///
+/// # Safety
+/// `solution` is a solution and `winner` is valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn lmc_solution_winner(
+    solution: *const LmcSolution,
+    vertex: usize,
+    winner: *mut LmcPlayer,
+) -> LmcStatus {
+    call(|| {
+        let solution = handle(solution, "solution")?;
+        match solution.winners.get(&vertex) {
+            Some(player) => write(winner, *player, "winner"),
+            None => Err((LmcStatus::NotFound, format!("No vertex {}", vertex))),
+        }
+    })
+}
+
+/// Successor the winner of the vertex moves to, if the vertex is owned by the winner
+///
+/// # Safety
+/// `solution` is a solution and `next` is valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn lmc_solution_strategy(
+    solution: *const LmcSolution,
+    vertex: usize,
+    next: *mut usize,
+) -> LmcStatus {
+    call(|| {
+        let solution = handle(solution, "solution")?;
+        match solution.strategy.get(&vertex) {
+            Some(successor) => write(next, *successor, "next"),
+            None => Err((
+                LmcStatus::NotFound,
+                format!("Vertex {} has no strategy", vertex),
+            )),
+        }
+    })
+}
+
+/// # Safety
+/// `solution` is null or a solution which is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn lmc_solution_free(solution: *mut LmcSolution) {
+    if !solution.is_null() {
+        drop(Box::from_raw(solution));
+    }
+}
+
+/// Parse a net in PNML, the TINA .net format or LoLA's format, detected by its content, to be
+/// released by `lmc_net_free`
+///
+/// # Safety
+/// `input` is a nul terminated string and `net` is valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn lmc_parse_net(input: *const c_char, net: *mut *mut LmcNet) -> LmcStatus {
+    call(|| {
+        let input = string(input, "input")?;
+        let format = petri::Format::detect(Path::new(""), input);
+        let parsed = petri::parse(input, format)
+            .map_err(|e| (LmcStatus::ParseError, format!("Could not parse net: {}", e)))?;
+        let handle = Box::into_raw(Box::new(LmcNet { net: parsed }));
+        write(net, handle, "net").inspect_err(|_| drop(Box::from_raw(handle)))
+    })
+}
+
+/// # Safety
+/// `net` is null or a net which is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn lmc_net_free(net: *mut LmcNet) {
+    if !net.is_null() {
+        drop(Box::from_raw(net));
+    }
+}
+
+/// Check whether every run of the net satisfies the LTL formula in prefix notation, such as
+/// 'G F 1<=tokens(p1)'
+///
+/// # Safety
+/// `net` is a net, `formula` is a nul terminated string and `verdict` is valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn lmc_check_ltl(
+    net: *const LmcNet,
+    formula: *const c_char,
+    verdict: *mut LmcVerdict,
+) -> LmcStatus {
+    call(|| {
+        let net = &handle(net, "net")?.net;
+        let formula = Formula::parse(string(formula, "formula")?).map_err(|e| {
+            (
+                LmcStatus::ParseError,
+                format!("Could not parse formula: {}", e),
+            )
+        })?;
+        let outcome = (Checker::new(net, false).check(&formula))
+            .map_err(|e| (LmcStatus::CheckError, e.to_string()))?;
+        let checked = match outcome.counterexample {
+            Some(_) => LmcVerdict::Violated,
+            None => LmcVerdict::Holds,
+        };
+        write(verdict, checked, "verdict")
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    pub fn calls() {
+        unsafe {
+            let input = CString::new("parity 2;\n0 2 0 1;\n1 1 1 0;").unwrap();
+            let mut game = ptr::null_mut();
+            assert_eq!(lmc_parse_game(input.as_ptr(), &mut game), LmcStatus::Ok);
+            let mut solution = ptr::null_mut();
+            assert_eq!(
+                lmc_solve(game, LmcAlgorithm::Zielonka, &mut solution),
+                LmcStatus::Ok
+            );
+            let mut winner = LmcPlayer::Odd;
+            assert_eq!(lmc_solution_winner(solution, 1, &mut winner), LmcStatus::Ok);
+            assert_eq!(winner, LmcPlayer::Even);
+            assert_eq!(
+                lmc_solution_winner(solution, 7, &mut winner),
+                LmcStatus::NotFound
+            );
+            assert_eq!(
+                CStr::from_ptr(lmc_last_error()).to_str().unwrap(),
+                "No vertex 7"
+            );
+            lmc_solution_free(solution);
+            lmc_game_free(game);
+
+            let input = CString::new("tr t0 p0 -> p1\ntr t1 p1 -> p0\npl p0 (1)").unwrap();
+            let mut net = ptr::null_mut();
+            assert_eq!(lmc_parse_net(input.as_ptr(), &mut net), LmcStatus::Ok);
+            let mut verdict = LmcVerdict::Holds;
+            let formula = CString::new("G tokens(p1)<=0").unwrap();
+            assert_eq!(
+                lmc_check_ltl(net, formula.as_ptr(), &mut verdict),
+                LmcStatus::Ok
+            );
+            assert_eq!(verdict, LmcVerdict::Violated);
+            let formula = CString::new("G tokens(p2)<=0").unwrap();
+            assert_eq!(
+                lmc_check_ltl(net, formula.as_ptr(), &mut verdict),
+                LmcStatus::CheckError
+            );
+            assert_eq!(
+                lmc_check_ltl(net, ptr::null(), &mut verdict),
+                LmcStatus::InvalidArgument
+            );
+            lmc_net_free(net);
+        }
+    }
+}
@@ -8,4 +8,12 @@ pub enum Error {
     Leftover(String, String),
     #[error("Error while parsing formula: '{0}'")]
     Parsing(String),
+    #[error("Invalid TLSF specification: {0}")]
+    Tlsf(String),
+    #[error("Could not read property file: {0}")]
+    Xml(String),
+    #[error("Invalid trace '{0}': {1}")]
+    Trace(String, String),
+    #[error("Invalid property '{id}': {reason}")]
+    Property { id: String, reason: String },
 }
@@ -1,12 +1,26 @@
+pub mod alphabet;
+#[cfg(any(test, feature = "testing"))]
+pub mod arbitrary;
+pub mod bmc;
+pub mod explain;
+pub mod intern;
+pub mod metrics;
+pub mod spot;
+pub mod text;
+pub mod tlsf;
+pub mod vacuity;
+mod visit;
 pub mod xml;
 use itertools::Itertools;
 use std::{cmp::Ordering, collections::BTreeSet, fmt::Display};
+pub use visit::{ExprRewriter, ExprVisitor};
 
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_till},
-    character::{is_space, streaming::char},
-    sequence::{preceded, separated_pair},
+    character::{complete::digit1, is_space, streaming::char},
+    combinator::map_res,
+    sequence::{delimited, pair, preceded, separated_pair},
     IResult, Parser,
 };
 
@@ -32,6 +46,12 @@ pub enum Expr {
     WeakUntil(Box<Expr>, Box<Expr>),
     Release(Box<Expr>, Box<Expr>),
     StrongRelease(Box<Expr>, Box<Expr>),
+    /// F[<=k], the operand holds within the next k steps
+    BoundedFinally(usize, Box<Expr>),
+    /// G[<=k], the operand holds for the next k steps
+    BoundedGlobally(usize, Box<Expr>),
+    /// U[<=k], the right operand holds within the next k steps and the left one until then
+    BoundedUntil(usize, Box<Expr>, Box<Expr>),
 }
 
 impl Formula {
@@ -41,6 +61,12 @@ impl Formula {
         }
     }
 
+    pub fn negation(&self) -> Self {
+        Formula {
+            root_expr: Expr::Not(Box::new(self.root_expr.clone())),
+        }
+    }
+
     pub fn parse(input: &str) -> Result<Self, crate::error::Error> {
         let root_expr = Expr::parse(input);
         let root_expr = root_expr.map_err(|e| {
@@ -65,34 +91,11 @@ impl Formula {
     }
 
     pub fn elementary(&self) -> Vec<BTreeSet<Expr>> {
-        // All non negated subformulae
-        let closure = self.root_expr.subformula();
-        let elementary = closure
-            .clone()
+        let mut table = intern::ExprTable::new();
+        self.elementary_ids(&mut table)
             .into_iter()
-            .powerset()
-            .map(|s| {
-                let mut s: BTreeSet<_> = s.into_iter().collect();
-                for f in &closure {
-                    if let Expr::False | Expr::True = f {
-                        continue;
-                    }
-                    if !s.contains(f) {
-                        s.insert(Expr::Not(Box::new(f.clone())));
-                    }
-                }
-                s
-            })
-            .filter(|s| {
-                for e in &closure {
-                    if !satisfies(s, e) {
-                        return false;
-                    }
-                }
-
-                true
-            });
-        elementary.collect()
+            .map(|s| s.into_iter().map(|id| table.expr(id)).collect())
+            .collect()
     }
 
     pub fn consistent_subformula(&self) -> BTreeSet<BTreeSet<Expr>> {
@@ -109,6 +112,49 @@ impl Formula {
         BTreeSet::new()
     }
 
+    /// Split a top level conjunction into conjuncts over pairwise disjoint sets of atomic
+    /// propositions, each of them can be checked on its own with a smaller automaton
+    pub fn decompose(&self) -> Vec<Formula> {
+        let mut conjuncts = vec![];
+        let mut pending = vec![&self.root_expr];
+        while let Some(expr) = pending.pop() {
+            match expr {
+                Expr::And(lhs, rhs) => pending.extend([rhs.as_ref(), lhs.as_ref()]),
+                e => conjuncts.push(e),
+            }
+        }
+
+        // Merge groups sharing a proposition, the groups keep the order of their first conjunct
+        let mut groups: Vec<(BTreeSet<Expr>, Vec<&Expr>)> = vec![];
+        for conjunct in conjuncts {
+            let mut alphabet = conjunct.alphabet();
+            let mut members = vec![conjunct];
+            let mut first = groups.len();
+            for i in (0..groups.len()).rev() {
+                if !groups[i].0.is_disjoint(&alphabet) {
+                    let (a, m) = groups.remove(i);
+                    alphabet.extend(a);
+                    members = m.into_iter().chain(members).collect();
+                    first = i;
+                }
+            }
+            groups.insert(first, (alphabet, members));
+        }
+
+        groups
+            .into_iter()
+            .map(|(_, members)| Formula {
+                root_expr: members
+                    .into_iter()
+                    .cloned()
+                    .reduce(|lhs, rhs| Expr::And(Box::new(lhs), Box::new(rhs)))
+                    .expect("Groups are never empty"),
+            })
+            .collect()
+    }
+
+    /// The atomic propositions and their negations as expressions, see `aps` for indexed
+    /// propositions
     pub fn alphabet(&self) -> BTreeSet<Expr> {
         let a = self.root_expr.alphabet();
         let mut b = a.clone();
@@ -124,32 +170,6 @@ impl Formula {
     }
 }
 
-fn satisfies(set: &BTreeSet<Expr>, expr: &Expr) -> bool {
-    let exists = set.contains(expr) || set.contains(&expr.negated());
-    let satisfies = match expr {
-        e @ Expr::False => return !set.contains(e),
-        e @ Expr::True => set.contains(e),
-        e @ Expr::And(lhs, rhs) => {
-            (set.contains(e) && set.contains(lhs) && set.contains(rhs))
-                || (!set.contains(e) && !(set.contains(lhs) && set.contains(rhs)))
-        }
-        e @ Expr::Or(lhs, rhs) => {
-            (set.contains(e) && (set.contains(lhs) || set.contains(rhs)))
-                || (!set.contains(e) && !set.contains(lhs) && !set.contains(rhs))
-        }
-        e @ Expr::Until(lhs, rhs) => {
-            (!set.contains(rhs) || set.contains(e))
-                && (!(set.contains(e) && set.contains(&rhs.negated())) || set.contains(lhs))
-        }
-        e @ Expr::Release(lhs, rhs) => {
-            (!(set.contains(lhs) && set.contains(rhs)) || set.contains(e))
-                && (!set.contains(e) || set.contains(rhs))
-        }
-        _ => true,
-    };
-    exists && satisfies
-}
-
 impl Display for Formula {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.root_expr)
@@ -157,15 +177,6 @@ impl Display for Formula {
 }
 
 impl Expr {
-    fn negated(&self) -> Self {
-        match self {
-            Expr::True => Expr::False,
-            Expr::False => Expr::True,
-            Expr::Not(e) => *e.clone(),
-            _ => Expr::Not(Box::new(self.clone())),
-        }
-    }
-
     pub fn alphabet(&self) -> BTreeSet<Expr> {
         match self {
             Expr::True | Expr::False => BTreeSet::new(),
@@ -204,6 +215,38 @@ impl Expr {
                 alphabet.extend(rhs.alphabet());
                 alphabet
             }
+            Expr::BoundedFinally(_, e) | Expr::BoundedGlobally(_, e) => e.alphabet(),
+            Expr::BoundedUntil(_, lhs, rhs) => {
+                let mut alphabet = lhs.alphabet();
+                alphabet.extend(rhs.alphabet());
+                alphabet
+            }
+        }
+    }
+
+    /// Unfold a bounded operator by one step into its next step expansion
+    pub fn unfold(&self) -> Expr {
+        let next = |e: Expr| Box::new(Expr::Next(Box::new(e)));
+        match self {
+            Expr::BoundedFinally(0, e) | Expr::BoundedGlobally(0, e) => *e.clone(),
+            Expr::BoundedUntil(0, _, rhs) => *rhs.clone(),
+            // F[<=k] a = a ∨ X F[<=k-1] a
+            Expr::BoundedFinally(k, e) => {
+                Expr::Or(e.clone(), next(Expr::BoundedFinally(k - 1, e.clone())))
+            }
+            // G[<=k] a = a ∧ X G[<=k-1] a
+            Expr::BoundedGlobally(k, e) => {
+                Expr::And(e.clone(), next(Expr::BoundedGlobally(k - 1, e.clone())))
+            }
+            // a U[<=k] b = b ∨ (a ∧ X (a U[<=k-1] b))
+            Expr::BoundedUntil(k, lhs, rhs) => Expr::Or(
+                rhs.clone(),
+                Box::new(Expr::And(
+                    lhs.clone(),
+                    next(Expr::BoundedUntil(k - 1, lhs.clone(), rhs.clone())),
+                )),
+            ),
+            e => e.clone(),
         }
     }
 
@@ -227,61 +270,9 @@ impl Expr {
     }
 
     fn subformula(&self) -> BTreeSet<Self> {
-        match self {
-            e @ Expr::False | e @ Expr::True | e @ Expr::Atomic(_) => BTreeSet::from([e.clone()]),
-            Expr::Not(ex) => ex.subformula(),
-            e @ Expr::Next(ex) => {
-                let mut closure = BTreeSet::from([e.clone()]);
-                closure.extend(ex.subformula());
-                closure
-            }
-            e @ Expr::Globally(ex) => {
-                let mut closure = BTreeSet::from([e.clone()]);
-                closure.extend(ex.subformula());
-                closure
-            }
-            e @ Expr::Finally(ex) => {
-                let mut closure = BTreeSet::from([e.clone()]);
-                closure.extend(ex.subformula());
-                closure
-            }
-            e @ Expr::And(lhs, rhs) => {
-                let mut closure = BTreeSet::from([e.clone()]);
-                closure.extend(lhs.subformula());
-                closure.extend(rhs.subformula());
-                closure
-            }
-            e @ Expr::Or(lhs, rhs) => {
-                let mut closure = BTreeSet::from([e.clone()]);
-                closure.extend(lhs.subformula());
-                closure.extend(rhs.subformula());
-                closure
-            }
-            e @ Expr::Until(lhs, rhs) => {
-                let mut closure = BTreeSet::from([e.clone()]);
-                closure.extend(lhs.subformula());
-                closure.extend(rhs.subformula());
-                closure
-            }
-            e @ Expr::WeakUntil(lhs, rhs) => {
-                let mut closure = BTreeSet::from([e.clone()]);
-                closure.extend(lhs.subformula());
-                closure.extend(rhs.subformula());
-                closure
-            }
-            e @ Expr::Release(lhs, rhs) => {
-                let mut closure = BTreeSet::from([e.clone()]);
-                closure.extend(lhs.subformula());
-                closure.extend(rhs.subformula());
-                closure
-            }
-            e @ Expr::StrongRelease(lhs, rhs) => {
-                let mut closure = BTreeSet::from([e.clone()]);
-                closure.extend(lhs.subformula());
-                closure.extend(rhs.subformula());
-                closure
-            }
-        }
+        let mut subformulas = Subformulas(BTreeSet::new());
+        self.accept(&mut subformulas);
+        subformulas.0
     }
 
     fn closure(&self) -> BTreeSet<Self> {
@@ -299,102 +290,138 @@ impl Expr {
     }
 
     fn simplify(&self) -> Self {
-        match self {
+        self.rewrite(&mut Simplify)
+    }
+}
+
+/// A single step of pushing negations inwards and replacing derived operators, repeated until a
+/// fixpoint is reached by `pnf`
+struct Simplify;
+
+impl ExprRewriter for Simplify {
+    fn rewrite(&mut self, expr: &Expr) -> Expr {
+        match expr {
             // Duality laws
             not_expr @ Expr::Not(ex) => match &**ex {
                 Expr::True => Expr::False,
                 Expr::False => Expr::True,
                 Expr::Atomic(_) => not_expr.clone(),
                 Expr::And(lhs, rhs) => Expr::Or(
-                    Box::new(Expr::Not(Box::new(lhs.simplify()))),
-                    Box::new(Expr::Not(Box::new(rhs.simplify()))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(lhs)))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(rhs)))),
                 ),
                 Expr::Or(lhs, rhs) => Expr::And(
-                    Box::new(Expr::Not(Box::new(lhs.simplify()))),
-                    Box::new(Expr::Not(Box::new(rhs.simplify()))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(lhs)))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(rhs)))),
                 ),
-                Expr::Next(ex) => Expr::Next(Box::new(Expr::Not(Box::new(ex.simplify())))),
-                Expr::Finally(ex) => Expr::Globally(Box::new(Expr::Not(Box::new(ex.simplify())))),
-                Expr::Globally(ex) => Expr::Finally(Box::new(Expr::Not(Box::new(ex.simplify())))),
+                Expr::Next(ex) => Expr::Next(Box::new(Expr::Not(Box::new(self.rewrite(ex))))),
+                Expr::Finally(ex) => {
+                    Expr::Globally(Box::new(Expr::Not(Box::new(self.rewrite(ex)))))
+                }
+                Expr::Globally(ex) => {
+                    Expr::Finally(Box::new(Expr::Not(Box::new(self.rewrite(ex)))))
+                }
                 Expr::Until(lhs, rhs) => Expr::Release(
-                    Box::new(Expr::Not(Box::new(lhs.simplify()))),
-                    Box::new(Expr::Not(Box::new(rhs.simplify()))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(lhs)))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(rhs)))),
                 ),
                 Expr::Release(lhs, rhs) => Expr::Until(
-                    Box::new(Expr::Not(Box::new(lhs.simplify()))),
-                    Box::new(Expr::Not(Box::new(rhs.simplify()))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(lhs)))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(rhs)))),
                 ),
                 Expr::WeakUntil(lhs, rhs) => Expr::Until(
-                    Box::new(Expr::Not(Box::new(rhs.simplify()))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(rhs)))),
                     Box::new(Expr::And(
-                        Box::new(Expr::Not(Box::new(lhs.simplify()))),
-                        Box::new(Expr::Not(Box::new(rhs.simplify()))),
+                        Box::new(Expr::Not(Box::new(self.rewrite(lhs)))),
+                        Box::new(Expr::Not(Box::new(self.rewrite(rhs)))),
                     )),
                 ),
                 Expr::StrongRelease(lhs, rhs) => Expr::Release(
-                    Box::new(Expr::Not(Box::new(rhs.simplify()))),
+                    Box::new(Expr::Not(Box::new(self.rewrite(rhs)))),
                     Box::new(Expr::Or(
-                        Box::new(Expr::Not(Box::new(lhs.simplify()))),
-                        Box::new(Expr::Not(Box::new(rhs.simplify()))),
+                        Box::new(Expr::Not(Box::new(self.rewrite(lhs)))),
+                        Box::new(Expr::Not(Box::new(self.rewrite(rhs)))),
                     )),
                 ),
-                Expr::Not(ex) => ex.simplify(),
+                Expr::Not(ex) => self.rewrite(ex),
+                // Bounded operators are expanded into chains of next operators
+                bounded @ (Expr::BoundedFinally(_, _)
+                | Expr::BoundedGlobally(_, _)
+                | Expr::BoundedUntil(_, _, _)) => {
+                    self.rewrite(&Expr::Not(Box::new(bounded.unfold())))
+                }
             },
-            e @ Expr::True | e @ Expr::False | e @ Expr::Atomic(_) => e.clone(),
-            Expr::Next(e) => Expr::Next(Box::new(e.simplify())),
+            Expr::True
+            | Expr::False
+            | Expr::Atomic(_)
+            | Expr::Next(_)
+            | Expr::Until(_, _)
+            | Expr::Release(_, _) => self.rewrite_children(expr),
             Expr::And(lhs, rhs) => match (&**lhs, &**rhs) {
                 (Expr::Next(le), Expr::Next(re)) => Expr::Next(Box::new(Expr::And(
-                    Box::new(le.simplify()),
-                    Box::new(re.simplify()),
+                    Box::new(self.rewrite(le)),
+                    Box::new(self.rewrite(re)),
                 ))),
                 (Expr::False, _) | (_, Expr::False) => Expr::False,
-                (Expr::True, e @ _) | (e @ _, Expr::True) => e.simplify(),
-                (lhs @ _, rhs @ Expr::Not(inner_r)) => {
+                (Expr::True, e) | (e, Expr::True) => self.rewrite(e),
+                (lhs, Expr::Not(inner_r)) => {
                     if lhs == &**inner_r {
                         Expr::False
                     } else {
-                        Expr::And(Box::new(lhs.simplify()), Box::new(rhs.simplify()))
+                        self.rewrite_children(expr)
                     }
                 }
-                (lhs @ Expr::Not(inner_l), rhs @ _) => {
+                (Expr::Not(inner_l), rhs) => {
                     if rhs == &**inner_l {
                         Expr::False
                     } else {
-                        Expr::And(Box::new(lhs.simplify()), Box::new(rhs.simplify()))
+                        self.rewrite_children(expr)
                     }
                 }
-                (lhs @ _, rhs @ _) => Expr::And(Box::new(lhs.simplify()), Box::new(rhs.simplify())),
+                _ => self.rewrite_children(expr),
             },
             Expr::Or(lhs, rhs) => match (&**lhs, &**rhs) {
                 (Expr::Next(le), Expr::Next(re)) => Expr::Next(Box::new(Expr::Or(
-                    Box::new(le.simplify()),
-                    Box::new(re.simplify()),
+                    Box::new(self.rewrite(le)),
+                    Box::new(self.rewrite(re)),
                 ))),
                 (Expr::True, _) | (_, Expr::True) => Expr::True,
-                (Expr::False, e @ _) | (e @ _, Expr::False) => e.simplify(),
-                (lhs @ _, rhs @ _) => Expr::Or(Box::new(lhs.simplify()), Box::new(rhs.simplify())),
+                (Expr::False, e) | (e, Expr::False) => self.rewrite(e),
+                _ => self.rewrite_children(expr),
             },
-            Expr::Until(lhs, rhs) => {
-                Expr::Until(Box::new(lhs.simplify()), Box::new(rhs.simplify()))
-            }
-            Expr::Release(lhs, rhs) => {
-                Expr::Release(Box::new(lhs.simplify()), Box::new(rhs.simplify()))
-            }
             // The ones below have to be changed to allowed symbols
             Expr::WeakUntil(lhs, rhs) => Expr::Release(
-                Box::new(rhs.simplify()),
-                Box::new(Expr::Or(Box::new(lhs.simplify()), Box::new(rhs.simplify()))),
+                Box::new(self.rewrite(rhs)),
+                Box::new(Expr::Or(
+                    Box::new(self.rewrite(lhs)),
+                    Box::new(self.rewrite(rhs)),
+                )),
             ),
-            Expr::Globally(ex) => Expr::Release(Box::new(Expr::False), Box::new(ex.simplify())),
-            Expr::Finally(ex) => Expr::Until(Box::new(Expr::True), Box::new(ex.simplify())),
+            Expr::Globally(ex) => Expr::Release(Box::new(Expr::False), Box::new(self.rewrite(ex))),
+            Expr::Finally(ex) => Expr::Until(Box::new(Expr::True), Box::new(self.rewrite(ex))),
             Expr::StrongRelease(lhs, rhs) => Expr::Until(
-                Box::new(rhs.simplify()),
+                Box::new(self.rewrite(rhs)),
                 Box::new(Expr::And(
-                    Box::new(lhs.simplify()),
-                    Box::new(rhs.simplify()),
+                    Box::new(self.rewrite(lhs)),
+                    Box::new(self.rewrite(rhs)),
                 )),
             ),
+            Expr::BoundedFinally(_, _)
+            | Expr::BoundedGlobally(_, _)
+            | Expr::BoundedUntil(_, _, _) => self.rewrite(&expr.unfold()),
+        }
+    }
+}
+
+/// Collects all subformulas, negations are left out since the closure adds them anyway
+struct Subformulas(BTreeSet<Expr>);
+
+impl ExprVisitor for Subformulas {
+    fn visit(&mut self, expr: &Expr) {
+        if !matches!(expr, Expr::Not(_)) {
+            self.0.insert(expr.clone());
         }
+        self.visit_children(expr)
     }
 }
 
@@ -457,7 +484,7 @@ impl Expr {
                 if let Ordering::Equal = a1.cmp(b1) {
                     a2.cmp(b2)
                 } else {
-                    a1.cmp(b2)
+                    a1.cmp(b1)
                 }
             }
             (
@@ -475,7 +502,7 @@ impl Expr {
                 if let Ordering::Equal = a1.cmp(b1) {
                     a2.cmp(b2)
                 } else {
-                    a1.cmp(b2)
+                    a1.cmp(b1)
                 }
             }
             (
@@ -494,12 +521,17 @@ impl Expr {
                 if let Ordering::Equal = a1.cmp(b1) {
                     a2.cmp(b2)
                 } else {
-                    a1.cmp(b2)
+                    a1.cmp(b1)
                 }
             }
             (
                 Expr::Until(_, _),
-                Expr::WeakUntil(_, _) | Expr::Release(_, _) | Expr::StrongRelease(_, _),
+                Expr::WeakUntil(_, _)
+                | Expr::Release(_, _)
+                | Expr::StrongRelease(_, _)
+                | Expr::BoundedFinally(_, _)
+                | Expr::BoundedGlobally(_, _)
+                | Expr::BoundedUntil(_, _, _),
             ) => Ordering::Less,
             (Expr::Until(_, _), _) => Ordering::Greater,
 
@@ -507,32 +539,65 @@ impl Expr {
                 if let Ordering::Equal = a1.cmp(b1) {
                     a2.cmp(b2)
                 } else {
-                    a1.cmp(b2)
+                    a1.cmp(b1)
                 }
             }
-            (Expr::WeakUntil(_, _), Expr::Release(_, _) | Expr::StrongRelease(_, _)) => {
-                Ordering::Less
-            }
+            (
+                Expr::WeakUntil(_, _),
+                Expr::Release(_, _)
+                | Expr::StrongRelease(_, _)
+                | Expr::BoundedFinally(_, _)
+                | Expr::BoundedGlobally(_, _)
+                | Expr::BoundedUntil(_, _, _),
+            ) => Ordering::Less,
             (Expr::WeakUntil(_, _), _) => Ordering::Greater,
 
             (Expr::Release(a1, a2), Expr::Release(b1, b2)) => {
                 if let Ordering::Equal = a1.cmp(b1) {
                     a2.cmp(b2)
                 } else {
-                    a1.cmp(b2)
+                    a1.cmp(b1)
                 }
             }
-            (Expr::Release(_, _), Expr::StrongRelease(_, _)) => Ordering::Less,
+            (
+                Expr::Release(_, _),
+                Expr::StrongRelease(_, _)
+                | Expr::BoundedFinally(_, _)
+                | Expr::BoundedGlobally(_, _)
+                | Expr::BoundedUntil(_, _, _),
+            ) => Ordering::Less,
             (Expr::Release(_, _), _) => Ordering::Greater,
 
             (Expr::StrongRelease(a1, a2), Expr::StrongRelease(b1, b2)) => {
                 if let Ordering::Equal = a1.cmp(b1) {
                     a2.cmp(b2)
                 } else {
-                    a1.cmp(b2)
+                    a1.cmp(b1)
                 }
             }
+            (
+                Expr::StrongRelease(_, _),
+                Expr::BoundedFinally(_, _)
+                | Expr::BoundedGlobally(_, _)
+                | Expr::BoundedUntil(_, _, _),
+            ) => Ordering::Less,
             (Expr::StrongRelease(_, _), _) => Ordering::Greater,
+
+            (Expr::BoundedFinally(k, a), Expr::BoundedFinally(l, b))
+            | (Expr::BoundedGlobally(k, a), Expr::BoundedGlobally(l, b)) => {
+                k.cmp(l).then_with(|| a.cmp(b))
+            }
+            (
+                Expr::BoundedFinally(_, _),
+                Expr::BoundedGlobally(_, _) | Expr::BoundedUntil(_, _, _),
+            ) => Ordering::Less,
+            (Expr::BoundedFinally(_, _), _) => Ordering::Greater,
+            (Expr::BoundedGlobally(_, _), Expr::BoundedUntil(_, _, _)) => Ordering::Less,
+            (Expr::BoundedGlobally(_, _), _) => Ordering::Greater,
+            (Expr::BoundedUntil(k, a1, a2), Expr::BoundedUntil(l, b1, b2)) => {
+                k.cmp(l).then_with(|| a1.cmp(b1)).then_with(|| a2.cmp(b2))
+            }
+            (Expr::BoundedUntil(_, _, _), _) => Ordering::Greater,
         }
     }
 }
@@ -553,6 +618,11 @@ impl Display for Expr {
             Expr::WeakUntil(lhs, rhs) => format!("{} W {}", lhs.fmt_braces(), rhs.fmt_braces()),
             Expr::Release(lhs, rhs) => format!("{} R {}", lhs.fmt_braces(), rhs.fmt_braces()),
             Expr::StrongRelease(lhs, rhs) => format!("{} M {}", lhs.fmt_braces(), rhs.fmt_braces()),
+            Expr::BoundedFinally(k, ex) => format!("F[<={}] {}", k, ex.fmt_braces()),
+            Expr::BoundedGlobally(k, ex) => format!("G[<={}] {}", k, ex.fmt_braces()),
+            Expr::BoundedUntil(k, lhs, rhs) => {
+                format!("{} U[<={}] {}", lhs.fmt_braces(), k, rhs.fmt_braces())
+            }
         };
         write!(f, "{}", symbol)
     }
@@ -568,6 +638,9 @@ impl Expr {
             Expr::parse_and,
             Expr::parse_or,
             Expr::parse_next,
+            Expr::parse_bounded_finally,
+            Expr::parse_bounded_globally,
+            Expr::parse_bounded_until,
             Expr::parse_finally,
             Expr::parse_globally,
             Expr::parse_until,
@@ -603,6 +676,35 @@ impl Expr {
         preceded(tag("F "), Expr::parse.map(|e| Expr::Finally(Box::new(e))))(input)
     }
 
+    fn parse_bound<'a>(op: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, usize> {
+        delimited(
+            pair(tag(op), tag("[<=")),
+            map_res(digit1, str::parse),
+            tag("] "),
+        )
+    }
+
+    fn parse_bounded_finally(input: &str) -> IResult<&str, Self> {
+        pair(Expr::parse_bound("F"), Expr::parse)
+            .map(|(k, e)| Expr::BoundedFinally(k, Box::new(e)))
+            .parse(input)
+    }
+
+    fn parse_bounded_globally(input: &str) -> IResult<&str, Self> {
+        pair(Expr::parse_bound("G"), Expr::parse)
+            .map(|(k, e)| Expr::BoundedGlobally(k, Box::new(e)))
+            .parse(input)
+    }
+
+    fn parse_bounded_until(input: &str) -> IResult<&str, Self> {
+        pair(
+            Expr::parse_bound("U"),
+            separated_pair(Expr::parse, char(' '), Expr::parse),
+        )
+        .map(|(k, (e1, e2))| Expr::BoundedUntil(k, Box::new(e1), Box::new(e2)))
+        .parse(input)
+    }
+
     fn parse_and(input: &str) -> IResult<&str, Self> {
         preceded(
             tag("& "),
@@ -655,6 +757,30 @@ impl Expr {
 #[cfg(test)]
 mod test {
     use super::*;
+
+    #[test]
+    pub fn decompose() {
+        let cases = vec![
+            ("G a", vec!["G a"]),
+            ("& G a F b", vec!["G a", "F b"]),
+            ("& & G a F b U a c", vec!["& G a U a c", "F b"]),
+            ("& & a b U a b", vec!["& & a b U a b"]),
+            ("& a & b & c U c a", vec!["& & a c U c a", "b"]),
+            ("| a b", vec!["| a b"]),
+        ];
+        for (input, expected) in cases {
+            let expected: Vec<_> = expected
+                .into_iter()
+                .map(|f| Formula::parse(f).unwrap())
+                .collect();
+            assert_eq!(
+                Formula::parse(input).unwrap().decompose(),
+                expected,
+                "{}",
+                input
+            );
+        }
+    }
     // Expression tests
     #[test]
     pub fn simple_pnf() {
@@ -674,6 +800,11 @@ mod test {
             ("!G a", "U true !a"),
             ("!U a b", "R !a !b"),
             ("!R a b", "U !a !b"),
+            ("F[<=0] a", "a"),
+            ("F[<=2] a", "| a X | a X a"),
+            ("G[<=1] a", "& a X a"),
+            ("!G[<=1] a", "| !a X !a"),
+            ("U[<=1] a b", "| b & a X b"),
         ];
 
         for (input, expected) in cases {
@@ -0,0 +1,434 @@
+// Atomic propositions and valuations
+
+use std::fmt::Display;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{Expr, Formula};
+
+/// Atomic propositions with stable indices, the index of a proposition is its bit in a `Valuation`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ApSet {
+    names: Vec<String>,
+}
+
+/// Set of atomic propositions of an `ApSet`, the i-th bit is the proposition with index i
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct Valuation(u64);
+
+/// Conjunction of literals, the propositions in `positive` have to hold and the ones in `negative`
+/// must not hold
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct Literals {
+    pub positive: Valuation,
+    pub negative: Valuation,
+}
+
+/// Disjunction of satisfiable conjunctions of literals, no cube implies another one
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Guard {
+    cubes: Vec<Literals>,
+}
+
+impl ApSet {
+    /// Valuations are 64 bit masks
+    pub const MAX: usize = 64;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of the proposition, it is added if it is not part of the set yet
+    ///
+    /// Panics if the set already holds `ApSet::MAX` propositions.
+    pub fn insert(&mut self, ap: &str) -> usize {
+        if let Some(i) = self.index(ap) {
+            return i;
+        }
+        assert!(
+            self.names.len() < ApSet::MAX,
+            "At most {} atomic propositions are supported",
+            ApSet::MAX
+        );
+        self.names.push(ap.to_string());
+        self.names.len() - 1
+    }
+
+    pub fn index(&self, ap: &str) -> Option<usize> {
+        self.names.iter().position(|name| name == ap)
+    }
+
+    pub fn name(&self, index: usize) -> &str {
+        &self.names[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    /// All 2^n valuations of the propositions
+    pub fn valuations(&self) -> impl Iterator<Item = Valuation> {
+        let n = self.len() as u32;
+        (0..1u128 << n).map(|bits| Valuation(bits as u64))
+    }
+
+    /// Valuation in which exactly the given propositions of the set hold, others are ignored
+    pub fn valuation<'a>(&self, aps: impl IntoIterator<Item = &'a str>) -> Valuation {
+        aps.into_iter()
+            .filter_map(|ap| self.index(ap))
+            .fold(Valuation::empty(), |v, i| v.with(i, true))
+    }
+
+    /// Literal for the expression if it is an atomic proposition of the set or its negation
+    pub fn literal(&self, expr: &Expr) -> Option<Literals> {
+        match expr {
+            Expr::Atomic(ap) => Some(Literals::empty().with(self.index(ap)?, true)),
+            Expr::Not(e) => match e.as_ref() {
+                Expr::Atomic(ap) => Some(Literals::empty().with(self.index(ap)?, false)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Guard of a boolean combination of propositions of the set, `None` if the expression
+    /// contains temporal operators or unknown propositions
+    pub fn guard(&self, expr: &Expr) -> Option<Guard> {
+        Some(match expr {
+            Expr::True => Guard::always(),
+            Expr::False => Guard::never(),
+            Expr::Atomic(_) => Guard::cube(self.literal(expr)?),
+            Expr::Not(e) => self.guard(e)?.not(),
+            Expr::And(lhs, rhs) => self.guard(lhs)?.and(&self.guard(rhs)?),
+            Expr::Or(lhs, rhs) => self.guard(lhs)?.or(&self.guard(rhs)?),
+            _ => return None,
+        })
+    }
+
+    /// Valuation of a label listing the propositions which hold, such as "{a, b}", `None` if the
+    /// label is not of this form or names unknown propositions
+    pub fn parse_valuation(&self, label: &str) -> Option<Valuation> {
+        let names = label.trim().strip_prefix('{')?.strip_suffix('}')?.trim();
+        if names.is_empty() {
+            return Some(Valuation::empty());
+        }
+        names.split(',').try_fold(Valuation::empty(), |v, name| {
+            Some(v.with(self.index(name.trim())?, true))
+        })
+    }
+
+    /// Print the valuation as set of the propositions which hold, e.g. "{a, b}"
+    pub fn print_valuation(&self, valuation: Valuation) -> String {
+        format!(
+            "{{{}}}",
+            valuation
+                .iter()
+                .map(|i| self.name(i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    /// Print the literals as for example "a, ¬b", the empty conjunction is "true"
+    pub fn print_literals(&self, literals: &Literals) -> String {
+        if literals.positive.is_empty() && literals.negative.is_empty() {
+            return "true".into();
+        }
+        (0..self.len())
+            .filter_map(|i| {
+                if literals.positive.contains(i) {
+                    Some(self.name(i).to_string())
+                } else if literals.negative.contains(i) {
+                    Some(format!("¬{}", self.name(i)))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Print the guard as disjunction of its cubes, e.g. "a, ¬b | c"
+    pub fn print_guard(&self, guard: &Guard) -> String {
+        match guard.cubes() {
+            [] => "false".into(),
+            cubes => cubes
+                .iter()
+                .map(|c| self.print_literals(c))
+                .collect::<Vec<_>>()
+                .join(" | "),
+        }
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for ApSet {
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        let mut aps = ApSet::new();
+        for ap in iter {
+            aps.insert(ap.as_ref());
+        }
+        aps
+    }
+}
+
+impl Valuation {
+    pub fn empty() -> Self {
+        Valuation(0)
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.0 >> index & 1 == 1
+    }
+
+    /// Copy of the valuation in which the proposition has the given value
+    pub fn with(self, index: usize, value: bool) -> Self {
+        if value {
+            Valuation(self.0 | 1 << index)
+        } else {
+            Valuation(self.0 & !(1 << index))
+        }
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_disjoint(&self, other: &Valuation) -> bool {
+        self.0 & other.0 == 0
+    }
+
+    pub fn is_subset(&self, other: &Valuation) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    pub fn union(&self, other: &Valuation) -> Valuation {
+        Valuation(self.0 | other.0)
+    }
+
+    /// Indices of the propositions which hold
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let bits = self.0;
+        (0..ApSet::MAX).filter(move |i| bits >> i & 1 == 1)
+    }
+}
+
+impl From<u64> for Valuation {
+    fn from(bits: u64) -> Self {
+        Valuation(bits)
+    }
+}
+
+impl Literals {
+    /// The empty conjunction which holds for every valuation
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Copy with the additional literal, `value` is false for a negated proposition
+    pub fn with(self, index: usize, value: bool) -> Self {
+        if value {
+            Literals {
+                positive: self.positive.with(index, true),
+                ..self
+            }
+        } else {
+            Literals {
+                negative: self.negative.with(index, true),
+                ..self
+            }
+        }
+    }
+
+    pub fn holds(&self, valuation: Valuation) -> bool {
+        self.positive.is_subset(&valuation) && self.negative.is_disjoint(&valuation)
+    }
+
+    /// Whether some valuation satisfies the literals
+    pub fn is_satisfiable(&self) -> bool {
+        self.positive.is_disjoint(&self.negative)
+    }
+
+    /// Conjunction of both literals, `None` if it is unsatisfiable
+    pub fn and(&self, other: &Literals) -> Option<Literals> {
+        let literals = Literals {
+            positive: self.positive.union(&other.positive),
+            negative: self.negative.union(&other.negative),
+        };
+        literals.is_satisfiable().then_some(literals)
+    }
+}
+
+impl Guard {
+    /// The guard which holds for every valuation
+    pub fn always() -> Self {
+        Guard::cube(Literals::empty())
+    }
+
+    /// The guard which holds for no valuation
+    pub fn never() -> Self {
+        Self::default()
+    }
+
+    pub fn cube(literals: Literals) -> Self {
+        Guard::from_cubes([literals])
+    }
+
+    /// Guard which holds for exactly the valuation among the first `aps` propositions
+    pub fn valuation(valuation: Valuation, aps: usize) -> Self {
+        Guard::cube((0..aps).fold(Literals::empty(), |l, i| l.with(i, valuation.contains(i))))
+    }
+
+    /// Disjunction of the cubes, unsatisfiable and subsumed cubes are dropped
+    pub fn from_cubes(cubes: impl IntoIterator<Item = Literals>) -> Self {
+        let mut cubes: Vec<Literals> = cubes.into_iter().filter(Literals::is_satisfiable).collect();
+        cubes.sort();
+        cubes.dedup();
+        let implies = |c: &Literals, d: &Literals| {
+            d.positive.is_subset(&c.positive) && d.negative.is_subset(&c.negative)
+        };
+        let kept = cubes
+            .iter()
+            .filter(|c| !cubes.iter().any(|d| d != *c && implies(c, d)))
+            .copied()
+            .collect();
+        Guard { cubes: kept }
+    }
+
+    pub fn cubes(&self) -> &[Literals] {
+        &self.cubes
+    }
+
+    pub fn holds(&self, valuation: Valuation) -> bool {
+        self.cubes.iter().any(|c| c.holds(valuation))
+    }
+
+    pub fn is_satisfiable(&self) -> bool {
+        !self.cubes.is_empty()
+    }
+
+    /// Whether both guards hold for some common valuation
+    pub fn intersects(&self, other: &Guard) -> bool {
+        self.and(other).is_satisfiable()
+    }
+
+    pub fn and(&self, other: &Guard) -> Guard {
+        Guard::from_cubes(
+            self.cubes
+                .iter()
+                .flat_map(|c| other.cubes.iter().filter_map(move |d| c.and(d))),
+        )
+    }
+
+    pub fn or(&self, other: &Guard) -> Guard {
+        Guard::from_cubes(self.cubes.iter().chain(&other.cubes).copied())
+    }
+
+    /// Negation by De Morgan, every cube turns into a disjunction of its negated literals
+    pub fn not(&self) -> Guard {
+        self.cubes.iter().fold(Guard::always(), |guard, cube| {
+            let negated = Guard::from_cubes(
+                cube.positive
+                    .iter()
+                    .map(|i| Literals::empty().with(i, false))
+                    .chain(
+                        cube.negative
+                            .iter()
+                            .map(|i| Literals::empty().with(i, true)),
+                    ),
+            );
+            guard.and(&negated)
+        })
+    }
+}
+
+impl Display for ApSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{{}}}", self.names.join(", "))
+    }
+}
+
+impl Formula {
+    /// The atomic propositions of the formula, indexed in lexicographic order
+    pub fn aps(&self) -> ApSet {
+        self.root_expr
+            .alphabet()
+            .iter()
+            .filter_map(|ap| match ap {
+                Expr::Atomic(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn valuations() {
+        let aps = Formula::parse("U b & a !c").unwrap().aps();
+        assert_eq!(aps.iter().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+        assert_eq!(aps.valuations().count(), 8);
+
+        let v = aps.valuation(["c", "a", "unknown"]);
+        assert_eq!(aps.print_valuation(v), "{a, c}");
+
+        let a = aps
+            .literal(&Formula::parse("a").unwrap().root_expr)
+            .unwrap();
+        let not_c = aps
+            .literal(&Formula::parse("!c").unwrap().root_expr)
+            .unwrap();
+        let both = a.and(&not_c).unwrap();
+        assert_eq!(aps.print_literals(&both), "a, ¬c");
+        assert_eq!(aps.print_literals(&Literals::empty()), "true");
+        assert!(!both.holds(v));
+        assert!(both.holds(aps.valuation(["a", "b"])));
+        assert!(not_c.and(&Literals::empty().with(2, true)).is_none());
+        assert!(Literals::empty().holds(v));
+    }
+
+    #[test]
+    pub fn guards() {
+        let aps: ApSet = ["a", "b", "c"].iter().collect();
+        let guard = |f: &str| aps.guard(&Formula::parse(f).unwrap().root_expr).unwrap();
+        let v = |names: &[&str]| aps.valuation(names.iter().copied());
+
+        let a_or_b = guard("| a b");
+        assert!(a_or_b.holds(v(&["b"])) && !a_or_b.holds(v(&["c"])));
+        let not_a_or_b = guard("!| a b");
+        assert_eq!(aps.print_guard(&not_a_or_b), "¬a, ¬b");
+        assert!(!a_or_b.intersects(&not_a_or_b));
+        assert_eq!(aps.print_guard(&a_or_b.or(&not_a_or_b)), "¬a, ¬b | a | b");
+        // Subsumed cubes are dropped
+        assert_eq!(aps.print_guard(&guard("| a & a c")), "a");
+        assert_eq!(aps.print_guard(&guard("& a !a")), "false");
+        assert_eq!(aps.print_guard(&guard("| a !a")), "¬a | a");
+        assert!(aps
+            .guard(&Formula::parse("F a").unwrap().root_expr)
+            .is_none());
+
+        assert_eq!(aps.parse_valuation("{a, c}"), Some(v(&["a", "c"])));
+        assert_eq!(aps.parse_valuation("{}"), Some(Valuation::empty()));
+        assert_eq!(aps.parse_valuation("{d}"), None);
+        let exactly_a = Guard::valuation(v(&["a"]), aps.len());
+        assert!(exactly_a.holds(v(&["a"])) && !exactly_a.holds(v(&["a", "b"])));
+    }
+}
@@ -0,0 +1,92 @@
+// Random formulas and lassos for property based tests with proptest
+
+use proptest::collection::{btree_set, vec};
+use proptest::prelude::*;
+
+use crate::bmc::Lasso;
+use crate::{Expr, Formula};
+
+/// Propositions of the formulas and lassos drawn by `any`
+pub const APS: [&str; 3] = ["a", "b", "c"];
+
+/// Formulas over the propositions with at most `depth` nested operators, including the step
+/// bounded ones
+pub fn formulas(aps: &[&str], depth: u32) -> BoxedStrategy<Formula> {
+    let aps: Vec<String> = aps.iter().map(|ap| ap.to_string()).collect();
+    let leaf = prop_oneof![
+        1 => Just(Expr::True),
+        1 => Just(Expr::False),
+        6 => proptest::sample::select(aps).prop_map(Expr::Atomic),
+    ];
+    leaf.prop_recursive(depth, 32, 2, |inner| {
+        let unary = |f: fn(Box<Expr>) -> Expr| inner.clone().prop_map(move |e| f(Box::new(e)));
+        let binary = |f: fn(Box<Expr>, Box<Expr>) -> Expr| {
+            (inner.clone(), inner.clone()).prop_map(move |(l, r)| f(Box::new(l), Box::new(r)))
+        };
+        let bound = 0..4usize;
+        prop_oneof![
+            unary(Expr::Not),
+            unary(Expr::Next),
+            unary(Expr::Globally),
+            unary(Expr::Finally),
+            binary(Expr::Or),
+            binary(Expr::And),
+            binary(Expr::Until),
+            binary(Expr::WeakUntil),
+            binary(Expr::Release),
+            binary(Expr::StrongRelease),
+            (bound.clone(), inner.clone()).prop_map(|(k, e)| Expr::BoundedFinally(k, Box::new(e))),
+            (bound.clone(), inner.clone())
+                .prop_map(|(k, e)| Expr::BoundedGlobally(k, Box::new(e))),
+            (bound, inner.clone(), inner.clone())
+                .prop_map(|(k, l, r)| Expr::BoundedUntil(k, Box::new(l), Box::new(r))),
+        ]
+    })
+    .prop_map(|root_expr| Formula { root_expr })
+    .boxed()
+}
+
+/// Lassos over the propositions with at most `length` letters in the prefix and the cycle each,
+/// models to evaluate formulas on so translations can be compared by what they accept
+pub fn lassos(aps: &[&str], length: usize) -> BoxedStrategy<Lasso> {
+    let aps: Vec<String> = aps.iter().map(|ap| ap.to_string()).collect();
+    let letter = btree_set(proptest::sample::select(aps.clone()), 0..=aps.len());
+    (vec(letter.clone(), 0..=length), vec(letter, 1..=length.max(1)))
+        .prop_map(|(prefix, cycle)| Lasso { prefix, cycle })
+        .boxed()
+}
+
+impl Arbitrary for Formula {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Formula>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        formulas(&APS, 4)
+    }
+}
+
+impl Arbitrary for Lasso {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Lasso>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        lassos(&APS, 3)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::spot;
+
+    proptest! {
+        #[test]
+        fn round_trip(formula: Formula, lasso: Lasso) {
+            let spot = formula.to_spot().unwrap();
+            prop_assert_eq!(spot::parse(&spot).unwrap(), formula.clone());
+            let holds = lasso.satisfies(&formula);
+            prop_assert_eq!(lasso.satisfies(&formula.pnf()), holds);
+            prop_assert_eq!(lasso.satisfies(&formula.negation()), !holds);
+        }
+    }
+}
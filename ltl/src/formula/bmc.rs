@@ -0,0 +1,507 @@
+// Bounded satisfiability checking of LTL formulas via SAT
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use sat::{Lit, Solver, Var};
+use serde_derive::Serialize;
+
+use crate::error::Error;
+use crate::{Expr, Formula};
+
+/// An ultimately periodic word, the prefix is followed by infinitely many repetitions of the cycle.
+/// Every letter is the set of atomic propositions which hold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Lasso {
+    pub prefix: Vec<BTreeSet<String>>,
+    pub cycle: Vec<BTreeSet<String>>,
+}
+
+/// Search for a lasso shaped model of the formula with at most k + 1 distinct positions.
+/// Shorter lassos are tried first so the witness is as small as possible.
+pub fn satisfiable(formula: &Formula, k: usize) -> Option<Lasso> {
+    let formula = formula.pnf();
+    let mut unrolling = Unrolling::new(&formula);
+    while !unrolling.solve() {
+        if unrolling.bound() == k {
+            return None;
+        }
+        unrolling.extend();
+    }
+    Some(unrolling.lasso())
+}
+
+/// Linear encoding of Biere et al. of a formula over a lasso whose last position loops back to a
+/// position chosen by the solver. Eventualities of Until formulas are enforced by requiring their
+/// right hand side somewhere inside the loop.
+///
+/// The unrolling starts with a single position and is extended by one at a time in the same
+/// solver, which keeps what it learnt about the shorter lassos. Constraints which only hold while
+/// a position is the last one, the loop back and the eventualities, are guarded by `last`, which
+/// is assumed when solving and ruled out once the next position is added.
+pub struct Unrolling<'a> {
+    pub solver: Solver,
+    /// Exactly one is true, the position the successor of the last one is
+    pub loops: Vec<Var>,
+    /// Truth of each atomic proposition at every position
+    pub aps: HashMap<&'a str, Vec<Var>>,
+    in_loop: Vec<Var>,
+    last: Vec<Var>,
+    truth: Var,
+    holds: HashMap<(&'a Expr, usize), Lit>,
+    // Temporal subformulas at the last position, related to the next one once it is added
+    frontier: Vec<&'a Expr>,
+    // For right hand sides of Until formulas whether they hold inside the loop up to a position
+    eventually: HashMap<&'a Expr, Vec<Lit>>,
+}
+
+impl<'a> Unrolling<'a> {
+    /// Encode that the formula in positive normal form holds at position 0 of a lasso, whose
+    /// atomic propositions and loop position are left to be constrained by a model of a system
+    pub fn new(formula: &'a Formula) -> Self {
+        let mut solver = Solver::new();
+        let truth = solver.new_var();
+        solver.add_clause([truth.positive()]);
+        let mut unrolling = Unrolling {
+            solver,
+            loops: vec![],
+            aps: HashMap::new(),
+            in_loop: vec![],
+            last: vec![],
+            truth,
+            holds: HashMap::new(),
+            frontier: vec![],
+            eventually: HashMap::new(),
+        };
+        unrolling.add_position();
+        let root = unrolling.holds(&formula.root_expr, 0);
+        unrolling.solver.add_clause([root]);
+        unrolling
+    }
+
+    /// The last position of the lasso
+    pub fn bound(&self) -> usize {
+        self.loops.len() - 1
+    }
+
+    /// The literal stating that the bound is the last position, to be assumed when solving
+    pub fn last(&self) -> Lit {
+        self.last[self.bound()].positive()
+    }
+
+    /// Add a position after the last one
+    pub fn extend(&mut self) {
+        let previous = self.bound();
+        self.solver.add_clause([!self.last()]);
+        self.add_position();
+        for expr in std::mem::take(&mut self.frontier) {
+            self.successor(expr, previous);
+        }
+    }
+
+    /// Whether a lasso of the current bound exists, it can then be read with `lasso`
+    pub fn solve(&mut self) -> bool {
+        let last = self.last();
+        self.solver.solve_with(&[last])
+    }
+
+    fn add_position(&mut self) {
+        let (loops, in_loop, last) = (
+            self.solver.new_var(),
+            self.solver.new_var(),
+            self.solver.new_var(),
+        );
+        // At most one loop position, and at least one if this is the last position
+        for l in &self.loops {
+            self.solver.add_clause([l.negative(), loops.negative()]);
+        }
+        self.loops.push(loops);
+        self.solver.add_clause(
+            [last.negative()]
+                .into_iter()
+                .chain(self.loops.iter().map(Var::positive)),
+        );
+        // A position is inside the loop if the loop starts at or before it
+        self.solver.add_clause(
+            [in_loop.negative(), loops.positive()]
+                .into_iter()
+                .chain(self.in_loop.last().map(Var::positive)),
+        );
+        self.in_loop.push(in_loop);
+        self.last.push(last);
+        for vars in self.aps.values_mut() {
+            vars.push(self.solver.new_var());
+        }
+    }
+
+    fn ap(&mut self, name: &'a str, position: usize) -> Var {
+        if !self.aps.contains_key(name) {
+            let vars = (0..=self.bound()).map(|_| self.solver.new_var()).collect();
+            self.aps.insert(name, vars);
+        }
+        self.aps[name][position]
+    }
+
+    /// A literal which implies that the expression in positive normal form holds at the position
+    fn holds(&mut self, expr: &'a Expr, position: usize) -> Lit {
+        if let Some(lit) = self.holds.get(&(expr, position)) {
+            return *lit;
+        }
+
+        let lit = match expr {
+            Expr::True => return self.truth.positive(),
+            Expr::False => return self.truth.negative(),
+            Expr::Atomic(name) => return self.ap(name, position).positive(),
+            Expr::Not(inner) => match &**inner {
+                Expr::Atomic(name) => return self.ap(name, position).negative(),
+                _ => panic!("formula is not in positive normal form: {}", expr),
+            },
+            _ => self.solver.new_var().positive(),
+        };
+        // Insert before recursing, temporal operators refer to themselves at other positions
+        self.holds.insert((expr, position), lit);
+
+        match expr {
+            Expr::And(lhs, rhs) => {
+                let (l, r) = (self.holds(lhs, position), self.holds(rhs, position));
+                self.solver.add_clause([!lit, l]);
+                self.solver.add_clause([!lit, r]);
+            }
+            Expr::Or(lhs, rhs) => {
+                let (l, r) = (self.holds(lhs, position), self.holds(rhs, position));
+                self.solver.add_clause([!lit, l, r]);
+            }
+            Expr::Next(_) => self.successor(expr, position),
+            Expr::Until(lhs, rhs) => {
+                let (l, r) = (self.holds(lhs, position), self.holds(rhs, position));
+                self.solver.add_clause([!lit, r, l]);
+                self.successor(expr, position);
+            }
+            Expr::Release(_, rhs) => {
+                let r = self.holds(rhs, position);
+                self.solver.add_clause([!lit, r]);
+                self.successor(expr, position);
+            }
+            _ => panic!("formula is not in positive normal form: {}", expr),
+        }
+
+        lit
+    }
+
+    /// Relate the temporal expression at the position to its successor. That is the next
+    /// position, or, if the position is the last one, the position the loop variables choose.
+    fn successor(&mut self, expr: &'a Expr, position: usize) {
+        let lit = self.holds[&(expr, position)];
+        // The successor is only needed unless the expression is already fulfilled
+        let (fulfilled, next) = match expr {
+            Expr::Next(inner) => (None, &**inner),
+            Expr::Until(_, rhs) => (Some(self.holds(rhs, position)), expr),
+            Expr::Release(lhs, _) => (Some(self.holds(lhs, position)), expr),
+            _ => return,
+        };
+        if position < self.bound() {
+            let next = self.holds(next, position + 1);
+            self.solver
+                .add_clause([Some(!lit), fulfilled, Some(next)].into_iter().flatten());
+            return;
+        }
+
+        let last = self.last[position].negative();
+        for l in 0..=position {
+            let looped = self.holds(next, l);
+            let guard = self.loops[l].negative();
+            self.solver.add_clause(
+                [Some(last), Some(guard), Some(!lit), fulfilled, Some(looped)]
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+        // Postponing the right hand side forever inside the loop is not allowed
+        if let Expr::Until(_, rhs) = expr {
+            let eventually = self.eventually_in_loop(rhs, position);
+            self.solver.add_clause([last, !lit, eventually]);
+        }
+        self.frontier.push(expr);
+    }
+
+    /// A literal which implies that the expression holds at some position inside the loop up to
+    /// the given one
+    fn eventually_in_loop(&mut self, expr: &'a Expr, position: usize) -> Lit {
+        loop {
+            let chain = self.eventually.get(expr).map_or(&[][..], Vec::as_slice);
+            if let Some(eventually) = chain.get(position) {
+                return *eventually;
+            }
+            let (i, previous) = (chain.len(), chain.last().copied());
+            let eventually = self.solver.new_var().positive();
+            let here = self.solver.new_var().positive();
+            let holds = self.holds(expr, i);
+            self.solver.add_clause([!here, self.in_loop[i].positive()]);
+            self.solver.add_clause([!here, holds]);
+            self.solver.add_clause(
+                [Some(!eventually), previous, Some(here)]
+                    .into_iter()
+                    .flatten(),
+            );
+            self.eventually.entry(expr).or_default().push(eventually);
+        }
+    }
+
+    /// The lasso of the model found by `solve`
+    pub fn lasso(&self) -> Lasso {
+        let loop_start = self
+            .loops
+            .iter()
+            .position(|l| self.solver.value(*l) == Some(true))
+            .unwrap();
+        let mut letters = (0..=self.bound()).map(|_| BTreeSet::new()).collect_vec();
+        for (name, vars) in &self.aps {
+            for (i, var) in vars.iter().enumerate() {
+                if self.solver.value(*var) == Some(true) {
+                    letters[i].insert(name.to_string());
+                }
+            }
+        }
+        let cycle = letters.split_off(loop_start);
+
+        Lasso {
+            prefix: letters,
+            cycle,
+        }
+    }
+}
+
+impl Lasso {
+    fn len(&self) -> usize {
+        self.prefix.len() + self.cycle.len()
+    }
+
+    fn letter(&self, position: usize) -> &BTreeSet<String> {
+        if position < self.prefix.len() {
+            &self.prefix[position]
+        } else {
+            &self.cycle[position - self.prefix.len()]
+        }
+    }
+
+    pub(crate) fn successor(&self, position: usize) -> usize {
+        if position + 1 == self.len() {
+            self.prefix.len()
+        } else {
+            position + 1
+        }
+    }
+
+    /// Whether the word satisfies the formula
+    pub fn satisfies(&self, formula: &Formula) -> bool {
+        self.evaluate(&formula.root_expr)[0]
+    }
+
+    /// The truth value of the expression at every distinct position of the word
+    pub fn evaluate(&self, expr: &Expr) -> Vec<bool> {
+        let n = self.len();
+        match expr {
+            Expr::True => vec![true; n],
+            Expr::False => vec![false; n],
+            Expr::Atomic(name) => (0..n).map(|i| self.letter(i).contains(name)).collect(),
+            Expr::Not(inner) => self.evaluate(inner).into_iter().map(|v| !v).collect(),
+            Expr::And(lhs, rhs) => {
+                let (l, r) = (self.evaluate(lhs), self.evaluate(rhs));
+                l.iter().zip(r).map(|(l, r)| *l && r).collect()
+            }
+            Expr::Or(lhs, rhs) => {
+                let (l, r) = (self.evaluate(lhs), self.evaluate(rhs));
+                l.iter().zip(r).map(|(l, r)| *l || r).collect()
+            }
+            Expr::Next(inner) => {
+                let values = self.evaluate(inner);
+                (0..n).map(|i| values[self.successor(i)]).collect()
+            }
+            Expr::Finally(inner) => self.until(&vec![true; n], &self.evaluate(inner)),
+            Expr::Globally(inner) => self.release(&vec![false; n], &self.evaluate(inner)),
+            Expr::Until(lhs, rhs) => self.until(&self.evaluate(lhs), &self.evaluate(rhs)),
+            Expr::Release(lhs, rhs) => self.release(&self.evaluate(lhs), &self.evaluate(rhs)),
+            Expr::WeakUntil(lhs, rhs) => {
+                // a W b = b R (a ∨ b)
+                let (l, r) = (self.evaluate(lhs), self.evaluate(rhs));
+                let or = l.iter().zip(&r).map(|(l, r)| *l || *r).collect_vec();
+                self.release(&r, &or)
+            }
+            Expr::StrongRelease(lhs, rhs) => {
+                // a M b = b U (a ∧ b)
+                let (l, r) = (self.evaluate(lhs), self.evaluate(rhs));
+                let and = l.iter().zip(&r).map(|(l, r)| *l && *r).collect_vec();
+                self.until(&r, &and)
+            }
+            Expr::BoundedFinally(_, _)
+            | Expr::BoundedGlobally(_, _)
+            | Expr::BoundedUntil(_, _, _) => self.evaluate(&expr.unfold()),
+        }
+    }
+
+    /// Least fixpoint of x = rhs ∨ (lhs ∧ X x)
+    fn until(&self, lhs: &[bool], rhs: &[bool]) -> Vec<bool> {
+        let mut values = vec![false; self.len()];
+        for _ in 0..=self.len() {
+            for i in (0..self.len()).rev() {
+                values[i] = rhs[i] || (lhs[i] && values[self.successor(i)]);
+            }
+        }
+        values
+    }
+
+    /// Greatest fixpoint of x = rhs ∧ (lhs ∨ X x)
+    fn release(&self, lhs: &[bool], rhs: &[bool]) -> Vec<bool> {
+        let mut values = vec![true; self.len()];
+        for _ in 0..=self.len() {
+            for i in (0..self.len()).rev() {
+                values[i] = rhs[i] && (lhs[i] || values[self.successor(i)]);
+            }
+        }
+        values
+    }
+}
+
+impl Display for Lasso {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let letter = |l: &BTreeSet<String>| format!("{{{}}}", l.iter().join(", "));
+        if !self.prefix.is_empty() {
+            write!(f, "{}, ", self.prefix.iter().map(letter).join(", "))?;
+        }
+        write!(f, "({})ʷ", self.cycle.iter().map(letter).join(", "))
+    }
+}
+
+impl FromStr for Lasso {
+    type Err = Error;
+
+    /// Parse a lasso in the format it is displayed in, e.g. "{a}, ({a, b}, {})ʷ". The suffix
+    /// marking the cycle may also be written as "^w" or left out.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| Error::Trace(input.into(), reason.into());
+        let rest = input.trim();
+        let rest = ["ʷ", "ω", "^w"]
+            .iter()
+            .find_map(|suffix| rest.strip_suffix(suffix))
+            .unwrap_or(rest);
+        let (prefix, rest) = letters(rest).ok_or_else(|| invalid("unclosed letter"))?;
+        let rest = rest
+            .strip_prefix('(')
+            .ok_or_else(|| invalid("expected a cycle in parentheses"))?;
+        let (cycle, rest) = letters(rest).ok_or_else(|| invalid("unclosed letter"))?;
+        if rest.trim() != ")" {
+            return Err(invalid("expected the cycle to end the trace"));
+        }
+        if cycle.is_empty() {
+            return Err(invalid("the cycle is empty"));
+        }
+        Ok(Lasso { prefix, cycle })
+    }
+}
+
+/// Parse a comma separated sequence of letters, `None` if a letter is not closed
+fn letters(mut input: &str) -> Option<(Vec<BTreeSet<String>>, &str)> {
+    let mut letters = vec![];
+    while let Some(rest) = input.trim_start().strip_prefix('{') {
+        let (letter, rest) = rest.split_once('}')?;
+        // Propositions may contain commas inside parentheses, e.g. "tokens(p0,p1)<=1"
+        let mut depth = 0;
+        let mut aps = BTreeSet::new();
+        let mut start = 0;
+        for (i, c) in letter.char_indices().chain([(letter.len(), ',')]) {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    let ap = letter[start..i].trim();
+                    if !ap.is_empty() {
+                        aps.insert(ap.to_string());
+                    }
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        letters.push(aps);
+        let rest = rest.trim_start();
+        input = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+    Some((letters, input.trim_start()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn satisfiable_witnesses() {
+        let cases = [
+            "a",
+            "& a X !a",
+            "U a b",
+            "G F a",
+            "& G F a G F !a",
+            "& F G a X X !a",
+            "R a b",
+            "& G | !r F g G F r",
+            "W a b",
+            "M a b",
+            "& F[<=2] a X X X G !a",
+            "& G[<=1] a X X !a",
+        ];
+        for input in cases {
+            let formula = Formula::parse(input).unwrap();
+            let lasso = satisfiable(&formula, 4);
+            assert!(lasso.is_some(), "'{}' should be satisfiable", input);
+            let lasso = lasso.unwrap();
+            assert!(
+                lasso.satisfies(&formula),
+                "{} does not satisfy '{}'",
+                lasso,
+                input
+            );
+        }
+    }
+
+    #[test]
+    pub fn unsatisfiable() {
+        let cases = [
+            "& a !a",
+            "& G a F !a",
+            "& G F a F G !a",
+            "& U a b G !b",
+            "& F[<=2] a G[<=2] !a",
+            "& U[<=1] a b & !b X !b",
+            "false",
+        ];
+        for input in cases {
+            let formula = Formula::parse(input).unwrap();
+            assert_eq!(
+                satisfiable(&formula, 4),
+                None,
+                "'{}' is unsatisfiable",
+                input
+            );
+        }
+    }
+
+    #[test]
+    pub fn parse_lasso() {
+        let lasso: Lasso = "{a}, {}, ({a, tokens(p0,p1)<=1}, {b})ʷ".parse().unwrap();
+        assert_eq!(lasso.prefix.len(), 2);
+        assert!(lasso.cycle[0].contains("tokens(p0,p1)<=1"));
+        assert_eq!(lasso.to_string().parse::<Lasso>().unwrap(), lasso);
+        assert_eq!("({a})^w".parse::<Lasso>().unwrap().prefix.len(), 0);
+        assert!("{a}, {b}".parse::<Lasso>().is_err());
+        assert!("{a}, ()".parse::<Lasso>().is_err());
+    }
+
+    #[test]
+    pub fn shortest_witness() {
+        let formula = Formula::parse("& & !a X !a X X G a").unwrap();
+        let lasso = satisfiable(&formula, 5).unwrap();
+        assert_eq!(lasso.prefix.len() + lasso.cycle.len(), 3, "{}", lasso);
+    }
+}
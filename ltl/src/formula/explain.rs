@@ -0,0 +1,193 @@
+// Localization of the parts of a formula violated by a counterexample
+
+use std::fmt::Display;
+
+use crate::bmc::Lasso;
+use crate::{Expr, Formula};
+
+/// A subformula which does not hold at a position of a word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub subformula: Expr,
+    pub position: usize,
+}
+
+impl Lasso {
+    /// The minimal failing subformulas of a formula the word does not satisfy in the order they
+    /// are encountered, empty if the word satisfies the formula
+    pub fn explain(&self, formula: &Formula) -> Vec<Failure> {
+        let mut failures = vec![];
+        if !self.satisfies(formula) {
+            self.blame(&formula.root_expr, 0, &mut failures);
+        }
+        failures
+    }
+
+    /// Whether the position is part of the cycle
+    pub fn in_cycle(&self, position: usize) -> bool {
+        position >= self.prefix.len()
+    }
+
+    /// Positions visited by the word starting at the given one
+    fn from(&self, position: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(Some(position), |p| Some(self.successor(*p)))
+    }
+
+    /// Collect the failures explaining why the expression is false at the position
+    ///
+    /// A conjunction is blamed on its false conjuncts, a disjunction on both disjuncts, G e on the
+    /// first position at which e fails and so on. Eventualities which are never fulfilled on the
+    /// word are reported as a whole.
+    fn blame(&self, expr: &Expr, position: usize, failures: &mut Vec<Failure>) {
+        let holds = |e: &Expr, p: usize| self.evaluate(e)[p];
+        // Every reachable position is visited within len steps
+        let unbounded = self.prefix.len() + self.cycle.len();
+        let report = |failures: &mut Vec<Failure>| {
+            let failure = Failure {
+                subformula: expr.clone(),
+                position,
+            };
+            if !failures.contains(&failure) {
+                failures.push(failure);
+            }
+        };
+        match expr {
+            Expr::True | Expr::False | Expr::Atomic(_) => report(failures),
+            Expr::Not(inner) => match negate(inner) {
+                Some(negated) => self.blame(&negated, position, failures),
+                None => report(failures),
+            },
+            Expr::And(lhs, rhs) => {
+                for side in [lhs, rhs] {
+                    if !holds(side, position) {
+                        self.blame(side, position, failures);
+                    }
+                }
+            }
+            Expr::Or(lhs, rhs) => {
+                self.blame(lhs, position, failures);
+                self.blame(rhs, position, failures);
+            }
+            Expr::Next(inner) => self.blame(inner, self.successor(position), failures),
+            Expr::Globally(inner) => self.first_violation(inner, position, unbounded, failures),
+            Expr::BoundedGlobally(k, inner) => {
+                self.first_violation(inner, position, k + 1, failures)
+            }
+            Expr::Release(_, rhs) => self.first_violation(rhs, position, unbounded, failures),
+            Expr::StrongRelease(_, rhs) => {
+                // Either rhs fails before lhs holds or lhs never holds
+                match self
+                    .from(position)
+                    .take(unbounded)
+                    .find(|p| !holds(rhs, *p))
+                {
+                    Some(p) => self.blame(rhs, p, failures),
+                    None => report(failures),
+                }
+            }
+            Expr::Until(lhs, rhs) | Expr::WeakUntil(lhs, rhs) | Expr::BoundedUntil(_, lhs, rhs) => {
+                let steps = match expr {
+                    Expr::BoundedUntil(k, _, _) => k + 1,
+                    _ => unbounded,
+                };
+                // The first position at which neither side holds, otherwise rhs is never reached
+                match self
+                    .from(position)
+                    .take(steps)
+                    .find(|p| !holds(lhs, *p) && !holds(rhs, *p))
+                {
+                    Some(p) => {
+                        self.blame(lhs, p, failures);
+                        self.blame(rhs, p, failures);
+                    }
+                    None => report(failures),
+                }
+            }
+            Expr::Finally(_) | Expr::BoundedFinally(_, _) => report(failures),
+        }
+    }
+
+    /// Blame the first position within the given number of steps at which the expression fails
+    fn first_violation(
+        &self,
+        expr: &Expr,
+        position: usize,
+        steps: usize,
+        failures: &mut Vec<Failure>,
+    ) {
+        let values = self.evaluate(expr);
+        if let Some(p) = self.from(position).take(steps).find(|p| !values[*p]) {
+            self.blame(expr, p, failures);
+        }
+    }
+}
+
+/// Push a negation one operator inwards, `None` for atomic propositions. Only one operator at a
+/// time, so the reported subformulas stay close to the input.
+fn negate(expr: &Expr) -> Option<Expr> {
+    let not = |e: &Expr| match e {
+        Expr::Not(inner) => (**inner).clone(),
+        e => Expr::Not(Box::new(e.clone())),
+    };
+    let b = Box::new;
+    Some(match expr {
+        Expr::True => Expr::False,
+        Expr::False => Expr::True,
+        Expr::Atomic(_) => return None,
+        Expr::Not(inner) => (**inner).clone(),
+        Expr::And(lhs, rhs) => Expr::Or(b(not(lhs)), b(not(rhs))),
+        Expr::Or(lhs, rhs) => Expr::And(b(not(lhs)), b(not(rhs))),
+        Expr::Next(inner) => Expr::Next(b(not(inner))),
+        Expr::Finally(inner) => Expr::Globally(b(not(inner))),
+        Expr::Globally(inner) => Expr::Finally(b(not(inner))),
+        Expr::Until(lhs, rhs) => Expr::Release(b(not(lhs)), b(not(rhs))),
+        Expr::Release(lhs, rhs) => Expr::Until(b(not(lhs)), b(not(rhs))),
+        // ¬(a W b) = ¬a M ¬b and ¬(a M b) = ¬a W ¬b
+        Expr::WeakUntil(lhs, rhs) => Expr::StrongRelease(b(not(lhs)), b(not(rhs))),
+        Expr::StrongRelease(lhs, rhs) => Expr::WeakUntil(b(not(lhs)), b(not(rhs))),
+        Expr::BoundedFinally(k, inner) => Expr::BoundedGlobally(*k, b(not(inner))),
+        Expr::BoundedGlobally(k, inner) => Expr::BoundedFinally(*k, b(not(inner))),
+        bounded @ Expr::BoundedUntil(_, _, _) => not(&bounded.unfold()),
+    })
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}' fails at position {}",
+            self.subformula, self.position
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn explain(formula: &str, trace: &str) -> Vec<String> {
+        let lasso: Lasso = trace.parse().unwrap();
+        lasso
+            .explain(&Formula::parse(formula).unwrap())
+            .iter()
+            .map(|f| format!("{}@{}", f.subformula, f.position))
+            .collect()
+    }
+
+    #[test]
+    pub fn failing_subformulas() {
+        assert!(explain("G | !r X g", "{r}, ({g})ʷ").is_empty());
+        // The request in the second position is never granted
+        assert_eq!(explain("G | !r X g", "{}, {r}, ({})ʷ"), ["¬r@1", "g@2"]);
+        // Only the violated conjunct is reported
+        assert_eq!(
+            explain("& G a F b", "{a}, ({a}, {a})ʷ"),
+            [Formula::parse("F b").unwrap().to_string() + "@0"]
+        );
+        assert_eq!(explain("U a b", "{a}, ({})"), ["a@1", "b@1"]);
+        // Negations are pushed through the operators
+        assert_eq!(explain("!F a", "{}, ({a})ʷ"), ["¬a@1"]);
+        assert_eq!(explain("G[<=1] a", "{a}, {a}, ({})ʷ"), Vec::<String>::new());
+        assert_eq!(explain("G[<=2] a", "{a}, {a}, ({})ʷ"), ["a@2"]);
+    }
+}
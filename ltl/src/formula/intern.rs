@@ -0,0 +1,244 @@
+// Hash consed representation of expressions
+
+use std::collections::{BTreeSet, HashMap};
+
+use itertools::Itertools;
+
+use crate::{Expr, Formula};
+
+/// Index of an expression in the `ExprTable` it was interned in
+#[derive(Debug, Eq, Clone, Copy, Hash, PartialEq, PartialOrd, Ord)]
+pub struct ExprId(u32);
+
+/// An expression node whose children are interned
+#[derive(Debug, Eq, Clone, Hash, PartialEq)]
+pub enum Node {
+    True,
+    False,
+    Not(ExprId),
+    Atomic(String),
+    Next(ExprId),
+    Globally(ExprId),
+    Finally(ExprId),
+    Or(ExprId, ExprId),
+    And(ExprId, ExprId),
+    Until(ExprId, ExprId),
+    WeakUntil(ExprId, ExprId),
+    Release(ExprId, ExprId),
+    StrongRelease(ExprId, ExprId),
+    BoundedFinally(usize, ExprId),
+    BoundedGlobally(usize, ExprId),
+    BoundedUntil(usize, ExprId, ExprId),
+}
+
+/// Store of every distinct subexpression. The tableau construction works on large numbers of sets
+/// of subformulas, with ids these sets share all subtrees and compare by integers.
+#[derive(Debug, Default, Clone)]
+pub struct ExprTable {
+    nodes: Vec<Node>,
+    ids: HashMap<Node, ExprId>,
+}
+
+impl ExprTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Id of the node, structurally equal nodes always get the same id
+    pub fn insert(&mut self, node: Node) -> ExprId {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        let id = ExprId(self.nodes.len() as u32);
+        self.nodes.push(node.clone());
+        self.ids.insert(node, id);
+        id
+    }
+
+    pub fn intern(&mut self, expr: &Expr) -> ExprId {
+        let node = match expr {
+            Expr::True => Node::True,
+            Expr::False => Node::False,
+            Expr::Atomic(ap) => Node::Atomic(ap.clone()),
+            Expr::Not(e) => Node::Not(self.intern(e)),
+            Expr::Next(e) => Node::Next(self.intern(e)),
+            Expr::Globally(e) => Node::Globally(self.intern(e)),
+            Expr::Finally(e) => Node::Finally(self.intern(e)),
+            Expr::Or(lhs, rhs) => Node::Or(self.intern(lhs), self.intern(rhs)),
+            Expr::And(lhs, rhs) => Node::And(self.intern(lhs), self.intern(rhs)),
+            Expr::Until(lhs, rhs) => Node::Until(self.intern(lhs), self.intern(rhs)),
+            Expr::WeakUntil(lhs, rhs) => Node::WeakUntil(self.intern(lhs), self.intern(rhs)),
+            Expr::Release(lhs, rhs) => Node::Release(self.intern(lhs), self.intern(rhs)),
+            Expr::StrongRelease(lhs, rhs) => {
+                Node::StrongRelease(self.intern(lhs), self.intern(rhs))
+            }
+            Expr::BoundedFinally(k, e) => Node::BoundedFinally(*k, self.intern(e)),
+            Expr::BoundedGlobally(k, e) => Node::BoundedGlobally(*k, self.intern(e)),
+            Expr::BoundedUntil(k, lhs, rhs) => {
+                Node::BoundedUntil(*k, self.intern(lhs), self.intern(rhs))
+            }
+        };
+        self.insert(node)
+    }
+
+    /// Id of an already interned node
+    pub fn get(&self, node: &Node) -> Option<ExprId> {
+        self.ids.get(node).copied()
+    }
+
+    pub fn node(&self, id: ExprId) -> &Node {
+        &self.nodes[id.0 as usize]
+    }
+
+    /// Rebuild the tree of the expression
+    pub fn expr(&self, id: ExprId) -> Expr {
+        let sub = |id: &ExprId| Box::new(self.expr(*id));
+        match self.node(id) {
+            Node::True => Expr::True,
+            Node::False => Expr::False,
+            Node::Atomic(ap) => Expr::Atomic(ap.clone()),
+            Node::Not(e) => Expr::Not(sub(e)),
+            Node::Next(e) => Expr::Next(sub(e)),
+            Node::Globally(e) => Expr::Globally(sub(e)),
+            Node::Finally(e) => Expr::Finally(sub(e)),
+            Node::Or(lhs, rhs) => Expr::Or(sub(lhs), sub(rhs)),
+            Node::And(lhs, rhs) => Expr::And(sub(lhs), sub(rhs)),
+            Node::Until(lhs, rhs) => Expr::Until(sub(lhs), sub(rhs)),
+            Node::WeakUntil(lhs, rhs) => Expr::WeakUntil(sub(lhs), sub(rhs)),
+            Node::Release(lhs, rhs) => Expr::Release(sub(lhs), sub(rhs)),
+            Node::StrongRelease(lhs, rhs) => Expr::StrongRelease(sub(lhs), sub(rhs)),
+            Node::BoundedFinally(k, e) => Expr::BoundedFinally(*k, sub(e)),
+            Node::BoundedGlobally(k, e) => Expr::BoundedGlobally(*k, sub(e)),
+            Node::BoundedUntil(k, lhs, rhs) => Expr::BoundedUntil(*k, sub(lhs), sub(rhs)),
+        }
+    }
+
+    /// The negation of the expression, double negations and constants are resolved
+    pub fn negated(&mut self, id: ExprId) -> ExprId {
+        match self.node(id) {
+            Node::True => self.insert(Node::False),
+            Node::False => self.insert(Node::True),
+            Node::Not(e) => *e,
+            _ => self.insert(Node::Not(id)),
+        }
+    }
+
+    /// Like `negated` but without inserting, `None` if the negation is not part of the table
+    fn find_negated(&self, id: ExprId) -> Option<ExprId> {
+        match self.node(id) {
+            Node::True => self.get(&Node::False),
+            Node::False => self.get(&Node::True),
+            Node::Not(e) => Some(*e),
+            _ => self.get(&Node::Not(id)),
+        }
+    }
+
+    /// Whether the set is consistent with respect to the expression, see `Formula::elementary`
+    pub fn satisfies(&self, set: &BTreeSet<ExprId>, id: ExprId) -> bool {
+        let contains = |id: &ExprId| set.contains(id);
+        let contains_negated = |id: &ExprId| self.find_negated(*id).is_some_and(|n| contains(&n));
+        let exists = contains(&id) || contains_negated(&id);
+        let satisfies = match self.node(id) {
+            Node::False => return !contains(&id),
+            Node::True => contains(&id),
+            Node::And(lhs, rhs) => contains(&id) == (contains(lhs) && contains(rhs)),
+            Node::Or(lhs, rhs) => contains(&id) == (contains(lhs) || contains(rhs)),
+            Node::Until(lhs, rhs) => {
+                (!contains(rhs) || contains(&id))
+                    && (!(contains(&id) && contains_negated(rhs)) || contains(lhs))
+            }
+            Node::Release(lhs, rhs) => {
+                (!(contains(lhs) && contains(rhs)) || contains(&id))
+                    && (!contains(&id) || contains(rhs))
+            }
+            _ => true,
+        };
+        exists && satisfies
+    }
+}
+
+impl Formula {
+    /// Like `elementary` but the sets consist of ids interned in the given table
+    pub fn elementary_ids(&self, table: &mut ExprTable) -> Vec<BTreeSet<ExprId>> {
+        // All non negated subformulae, in the same order as the sets of expressions
+        let closure: Vec<ExprId> = self
+            .root_expr
+            .subformula()
+            .iter()
+            .map(|e| table.intern(e))
+            .collect();
+        // Intern all negations beforehand so the checks do not need to modify the table
+        let negations: Vec<Option<ExprId>> = closure
+            .iter()
+            .map(|&id| match table.node(id) {
+                Node::True | Node::False => {
+                    table.negated(id);
+                    None
+                }
+                _ => Some(table.insert(Node::Not(id))),
+            })
+            .collect();
+
+        let table = &*table;
+        closure
+            .iter()
+            .copied()
+            .powerset()
+            .map(|s| {
+                let mut s: BTreeSet<_> = s.into_iter().collect();
+                for (f, negation) in closure.iter().zip(&negations) {
+                    if let Some(negation) = negation {
+                        if !s.contains(f) {
+                            s.insert(*negation);
+                        }
+                    }
+                }
+                s
+            })
+            .filter(|s| closure.iter().all(|&e| table.satisfies(s, e)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn structural_sharing() {
+        let mut table = ExprTable::new();
+        let expr = Formula::parse("& U a b X U a b").unwrap().root_expr;
+        let id = table.intern(&expr);
+        // a, b, U a b, X U a b and the conjunction
+        assert_eq!(table.len(), 5);
+        assert_eq!(table.intern(&expr), id);
+        assert_eq!(table.expr(id), expr);
+
+        let not_a = table.intern(&Formula::parse("!a").unwrap().root_expr);
+        let a = table.intern(&Formula::parse("a").unwrap().root_expr);
+        assert_eq!(table.negated(not_a), a);
+        assert_eq!(table.negated(a), not_a);
+    }
+
+    #[test]
+    pub fn elementary_sets_agree() {
+        for f in ["& a b", "U a b", "G | !r F g", "R X a b"] {
+            let formula = Formula::parse(f).unwrap();
+            let mut table = ExprTable::new();
+            let from_ids: Vec<BTreeSet<Expr>> = formula
+                .elementary_ids(&mut table)
+                .into_iter()
+                .map(|s| s.into_iter().map(|id| table.expr(id)).collect())
+                .collect();
+            assert_eq!(from_ids, formula.elementary(), "formula: {}", f);
+        }
+    }
+}
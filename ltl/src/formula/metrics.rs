@@ -0,0 +1,157 @@
+// Structural measures of formulas, useful to estimate the size of the automata before
+// translating them
+
+use std::fmt::Display;
+
+use serde_derive::Serialize;
+
+use crate::{Expr, Formula};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Metrics {
+    /// Number of nodes in the syntax tree
+    pub size: usize,
+    /// Maximal nesting of temporal operators
+    pub temporal_depth: usize,
+    /// Maximal nesting of until and release like operators, F and G count as well
+    pub until_release_nesting: usize,
+    /// Number of distinct atomic propositions
+    pub propositions: usize,
+    pub fragments: Fragments,
+}
+
+/// Syntactic fragments a formula belongs to, these are sufficient but not necessary conditions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Fragments {
+    /// No temporal operators at all
+    pub propositional: bool,
+    /// No until in positive normal form, violations have a finite bad prefix
+    pub safety: bool,
+    /// No release in positive normal form, satisfaction has a finite good prefix
+    pub guarantee: bool,
+    /// No next operator, so the formula cannot distinguish stuttering words
+    pub stutter_invariant: bool,
+}
+
+impl Formula {
+    pub fn metrics(&self) -> Metrics {
+        let pnf = self.pnf();
+        let temporal_depth = depth(&self.root_expr, &|e| {
+            !matches!(
+                e,
+                Expr::True
+                    | Expr::False
+                    | Expr::Atomic(_)
+                    | Expr::Not(_)
+                    | Expr::And(_, _)
+                    | Expr::Or(_, _)
+            )
+        });
+        let until_release_nesting = depth(&self.root_expr, &|e| {
+            !matches!(
+                e,
+                Expr::True
+                    | Expr::False
+                    | Expr::Atomic(_)
+                    | Expr::Not(_)
+                    | Expr::And(_, _)
+                    | Expr::Or(_, _)
+                    | Expr::Next(_)
+            )
+        });
+
+        Metrics {
+            size: count(&self.root_expr, &|_| true),
+            temporal_depth,
+            until_release_nesting,
+            propositions: self.root_expr.alphabet().len(),
+            fragments: Fragments {
+                propositional: temporal_depth == 0,
+                safety: count(&pnf.root_expr, &|e| matches!(e, Expr::Until(_, _))) == 0,
+                guarantee: count(&pnf.root_expr, &|e| matches!(e, Expr::Release(_, _))) == 0,
+                stutter_invariant: count(&pnf.root_expr, &|e| matches!(e, Expr::Next(_))) == 0,
+            },
+        }
+    }
+}
+
+/// Number of nodes for which the predicate holds
+fn count(expr: &Expr, predicate: &impl Fn(&Expr) -> bool) -> usize {
+    predicate(expr) as usize
+        + expr
+            .children()
+            .into_iter()
+            .map(|e| count(e, predicate))
+            .sum::<usize>()
+}
+
+/// Maximal number of nodes on a path from the root for which the predicate holds
+fn depth(expr: &Expr, predicate: &impl Fn(&Expr) -> bool) -> usize {
+    predicate(expr) as usize
+        + expr
+            .children()
+            .into_iter()
+            .map(|e| depth(e, predicate))
+            .max()
+            .unwrap_or(0)
+}
+
+impl Display for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Size: {}", self.size)?;
+        writeln!(f, "Temporal depth: {}", self.temporal_depth)?;
+        writeln!(f, "Until/release nesting: {}", self.until_release_nesting)?;
+        writeln!(f, "Atomic propositions: {}", self.propositions)?;
+        let fragments = [
+            (self.fragments.propositional, "propositional"),
+            (self.fragments.safety, "safety"),
+            (self.fragments.guarantee, "guarantee"),
+            (self.fragments.stutter_invariant, "stutter invariant"),
+        ]
+        .into_iter()
+        .filter(|(member, _)| *member)
+        .map(|(_, name)| name)
+        .collect::<Vec<_>>();
+        if fragments.is_empty() {
+            write!(f, "Fragments: none")
+        } else {
+            write!(f, "Fragments: {}", fragments.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn metrics() {
+        let metrics = Formula::parse("G | !r F & g X a").unwrap().metrics();
+        assert_eq!(metrics.size, 9);
+        assert_eq!(metrics.temporal_depth, 3);
+        assert_eq!(metrics.until_release_nesting, 2);
+        assert_eq!(metrics.propositions, 3);
+        assert_eq!(
+            metrics.fragments,
+            Fragments {
+                propositional: false,
+                safety: false,
+                guarantee: false,
+                stutter_invariant: false,
+            }
+        );
+    }
+
+    #[test]
+    pub fn fragments() {
+        let fragments = |f: &str| Formula::parse(f).unwrap().metrics().fragments;
+        assert!(fragments("| a !b").propositional);
+        assert!(fragments("G | !a X b").safety);
+        assert!(!fragments("G | !a X b").guarantee);
+        // Negation swaps safety and guarantee
+        assert!(fragments("!G a").guarantee);
+        assert!(!fragments("!G a").safety);
+        assert!(fragments("U a b").stutter_invariant);
+        assert!(!fragments("X a").stutter_invariant);
+    }
+}
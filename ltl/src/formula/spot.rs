@@ -0,0 +1,406 @@
+// Parser for LTL formulas in the infix syntax used by Spot, for example 'G (r -> F g)'
+
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag, take_while, take_while1},
+    character::complete::{char, digit1, multispace0, satisfy},
+    combinator::{map, map_res, not, recognize, verify},
+    multi::many0,
+    sequence::{delimited, pair, preceded, terminated},
+    IResult,
+};
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{error::Error, Expr, Formula};
+
+/// Parse a formula written in Spot's syntax
+///
+/// Precedence from weakest to strongest binding: '<->' and 'xor', '->' (right associative),
+/// '||', '&&', the binary temporal operators 'U', 'W', 'R', 'V' and 'M' (right associative) and
+/// finally the unary operators '!', 'X', 'F', 'G', '[]' and '<>'. Atomic propositions are
+/// identifiers or arbitrary strings in double quotes. The step bounded operators are written
+/// 'F[<=k]', 'G[<=k]' and 'U[<=k]'.
+pub fn parse(input: &str) -> Result<Formula, Error> {
+    let (rest, root_expr) =
+        delimited(multispace0, equivalence, multispace0)(input).map_err(|e| {
+            if e.is_incomplete() {
+                Error::Incomplete(input.into())
+            } else {
+                Error::Parsing(e.to_string())
+            }
+        })?;
+    if !rest.is_empty() {
+        return Err(Error::Leftover(input.into(), rest.into()));
+    }
+    Ok(Formula { root_expr })
+}
+
+pub(crate) fn implies(lhs: Expr, rhs: Expr) -> Expr {
+    Expr::Or(Box::new(Expr::Not(Box::new(lhs))), Box::new(rhs))
+}
+
+fn equivalent(lhs: Expr, rhs: Expr) -> Expr {
+    Expr::And(
+        Box::new(implies(lhs.clone(), rhs.clone())),
+        Box::new(implies(rhs, lhs)),
+    )
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+/// An operator keyword which is not the beginning of a longer identifier
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag(word), not(satisfy(is_ident_char)))
+}
+
+fn operator<'a>(op: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    delimited(multispace0, tag(op), multispace0)
+}
+
+fn equivalence(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = implication(input)?;
+    let (input, rest) = many0(pair(
+        alt((
+            operator("<->"),
+            operator("<=>"),
+            operator("xor"),
+            operator("^"),
+        )),
+        implication,
+    ))(input)?;
+    Ok((
+        input,
+        rest.into_iter().fold(first, |lhs, (op, rhs)| match op {
+            "<->" | "<=>" => equivalent(lhs, rhs),
+            _ => Expr::Not(Box::new(equivalent(lhs, rhs))),
+        }),
+    ))
+}
+
+fn implication(input: &str) -> IResult<&str, Expr> {
+    let (input, lhs) = disjunction(input)?;
+    match preceded(alt((operator("->"), operator("=>"))), implication)(input) {
+        Ok((input, rhs)) => Ok((input, implies(lhs, rhs))),
+        Err(_) => Ok((input, lhs)),
+    }
+}
+
+fn disjunction(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = conjunction(input)?;
+    let (input, rest) = many0(preceded(
+        alt((operator("||"), operator("|"), operator("\\/"))),
+        conjunction,
+    ))(input)?;
+    Ok((
+        input,
+        rest.into_iter()
+            .fold(first, |lhs, rhs| Expr::Or(Box::new(lhs), Box::new(rhs))),
+    ))
+}
+
+fn conjunction(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = temporal(input)?;
+    let (input, rest) = many0(preceded(
+        alt((operator("&&"), operator("&"), operator("/\\"))),
+        temporal,
+    ))(input)?;
+    Ok((
+        input,
+        rest.into_iter()
+            .fold(first, |lhs, rhs| Expr::And(Box::new(lhs), Box::new(rhs))),
+    ))
+}
+
+/// The bound of a step bounded operator, e.g. 'F[<=3]'
+fn bound<'a>(op: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, usize> {
+    delimited(
+        pair(tag(op), tag("[<=")),
+        delimited(multispace0, map_res(digit1, str::parse), multispace0),
+        char(']'),
+    )
+}
+
+fn temporal(input: &str) -> IResult<&str, Expr> {
+    let (input, lhs) = unary(input)?;
+    if let Ok((input, (k, rhs))) =
+        pair(delimited(multispace0, bound("U"), multispace0), temporal)(input)
+    {
+        return Ok((input, Expr::BoundedUntil(k, Box::new(lhs), Box::new(rhs))));
+    }
+    let binary = delimited(
+        multispace0,
+        alt((
+            keyword("U"),
+            keyword("W"),
+            keyword("R"),
+            keyword("V"),
+            keyword("M"),
+        )),
+        multispace0,
+    );
+    match pair(binary, temporal)(input) {
+        Ok((input, (op, rhs))) => {
+            let (lhs, rhs) = (Box::new(lhs), Box::new(rhs));
+            let expr = match op {
+                "U" => Expr::Until(lhs, rhs),
+                "W" => Expr::WeakUntil(lhs, rhs),
+                "M" => Expr::StrongRelease(lhs, rhs),
+                _ => Expr::Release(lhs, rhs),
+            };
+            Ok((input, expr))
+        }
+        Err(_) => Ok((input, lhs)),
+    }
+}
+
+/// The unary letter operators may be written without a space in front of their operand as long
+/// as the operand does not make them look like an identifier, e.g. 'GFa' is 'G (F a)'
+fn letter_operator(input: &str) -> IResult<&str, char> {
+    let operand_follows = |rest: &str| {
+        let after = rest.trim_start_matches(['F', 'G', 'X']);
+        match after.chars().next() {
+            Some(c) => {
+                c.is_lowercase() || c.is_whitespace() || "(!\"[<".contains(c) || after == rest
+            }
+            None => false,
+        }
+    };
+    let (rest, op) = satisfy(|c| c == 'F' || c == 'G' || c == 'X')(input)?;
+    if rest.starts_with(is_ident_char) && !operand_follows(rest) {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+    Ok((rest, op))
+}
+
+fn unary(input: &str) -> IResult<&str, Expr> {
+    let (input, _) = multispace0(input)?;
+    alt((
+        map(preceded(pair(char('!'), multispace0), unary), |e| {
+            Expr::Not(Box::new(e))
+        }),
+        map(preceded(pair(tag("[]"), multispace0), unary), |e| {
+            Expr::Globally(Box::new(e))
+        }),
+        map(preceded(pair(tag("<>"), multispace0), unary), |e| {
+            Expr::Finally(Box::new(e))
+        }),
+        map(pair(bound("F"), preceded(multispace0, unary)), |(k, e)| {
+            Expr::BoundedFinally(k, Box::new(e))
+        }),
+        map(pair(bound("G"), preceded(multispace0, unary)), |(k, e)| {
+            Expr::BoundedGlobally(k, Box::new(e))
+        }),
+        map(
+            pair(letter_operator, preceded(multispace0, unary)),
+            |(op, e)| {
+                let e = Box::new(e);
+                match op {
+                    'F' => Expr::Finally(e),
+                    'G' => Expr::Globally(e),
+                    _ => Expr::Next(e),
+                }
+            },
+        ),
+        primary,
+    ))(input)
+}
+
+fn primary(input: &str) -> IResult<&str, Expr> {
+    alt((
+        delimited(
+            pair(char('('), multispace0),
+            equivalence,
+            pair(multispace0, char(')')),
+        ),
+        map(alt((keyword("true"), keyword("1"))), |_| Expr::True),
+        map(alt((keyword("false"), keyword("0"))), |_| Expr::False),
+        map(delimited(char('"'), is_not("\""), char('"')), |s: &str| {
+            Expr::Atomic(s.to_string())
+        }),
+        map(identifier, |s| Expr::Atomic(s.to_string())),
+    ))(input)
+}
+
+fn identifier(input: &str) -> IResult<&str, &str> {
+    verify(
+        recognize(pair(
+            take_while1(|c: char| c.is_alphabetic() || c == '_'),
+            take_while(is_ident_char),
+        )),
+        |s: &str| !["U", "W", "R", "V", "M", "xor"].contains(&s),
+    )(input)
+}
+
+impl Expr {
+    /// Print the expression in Spot's syntax, `None` if a proposition contains a double quote.
+    /// Every binary operator is put in parentheses, so the text does not depend on precedences.
+    pub fn to_spot(&self) -> Option<String> {
+        let binary = |op: &str, lhs: &Expr, rhs: &Expr| {
+            Some(format!("({} {} {})", lhs.to_spot()?, op, rhs.to_spot()?))
+        };
+        Some(match self {
+            Expr::True => "true".into(),
+            Expr::False => "false".into(),
+            Expr::Atomic(ap) if is_plain_identifier(ap) => ap.clone(),
+            Expr::Atomic(ap) if !ap.contains('"') && !ap.is_empty() => format!("\"{}\"", ap),
+            Expr::Atomic(_) => return None,
+            Expr::Not(e) => format!("!{}", e.to_spot()?),
+            Expr::Next(e) => format!("X {}", e.to_spot()?),
+            Expr::Globally(e) => format!("G {}", e.to_spot()?),
+            Expr::Finally(e) => format!("F {}", e.to_spot()?),
+            Expr::And(lhs, rhs) => binary("&", lhs, rhs)?,
+            Expr::Or(lhs, rhs) => binary("|", lhs, rhs)?,
+            Expr::Until(lhs, rhs) => binary("U", lhs, rhs)?,
+            Expr::WeakUntil(lhs, rhs) => binary("W", lhs, rhs)?,
+            Expr::Release(lhs, rhs) => binary("R", lhs, rhs)?,
+            Expr::StrongRelease(lhs, rhs) => binary("M", lhs, rhs)?,
+            Expr::BoundedFinally(k, e) => format!("F[<={}] {}", k, e.to_spot()?),
+            Expr::BoundedGlobally(k, e) => format!("G[<={}] {}", k, e.to_spot()?),
+            Expr::BoundedUntil(k, lhs, rhs) => binary(&format!("U[<={}]", k), lhs, rhs)?,
+        })
+    }
+}
+
+impl Formula {
+    pub fn to_spot(&self) -> Option<String> {
+        self.root_expr.to_spot()
+    }
+}
+
+/// Whether the proposition can be written without quotes, identifiers starting with a letter
+/// operator would be split, e.g. 'Fa' is 'F a'
+fn is_plain_identifier(ap: &str) -> bool {
+    matches!(identifier(ap), Ok(("", _)))
+        && !ap.starts_with(['F', 'G', 'X'])
+        && !["true", "false"].contains(&ap)
+}
+
+impl Serialize for Expr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let spot = self.to_spot().ok_or_else(|| {
+            ser::Error::custom("atomic propositions must not contain double quotes")
+        })?;
+        serializer.serialize_str(&spot)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        parse(&input)
+            .map(|f| f.root_expr)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Formula {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.root_expr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Formula {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Expr::deserialize(deserializer).map(|root_expr| Formula { root_expr })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn spot_syntax() {
+        let cases = [
+            ("a", "a"),
+            ("!a", "!a"),
+            ("a && b", "& a b"),
+            ("a & b | c", "| & a b c"),
+            ("a | b & c", "| a & b c"),
+            ("a -> b", "| !a b"),
+            ("a -> b -> c", "| !a | !b c"),
+            ("G (r -> F g)", "G | !r F g"),
+            ("[](r -> <>g)", "G | !r F g"),
+            ("GFa", "G F a"),
+            ("X X a", "X X a"),
+            ("a U b U c", "U a U b c"),
+            ("a U b && c", "& U a b c"),
+            ("a W b", "W a b"),
+            ("a R b", "R a b"),
+            ("a V b", "R a b"),
+            ("a M b", "M a b"),
+            ("!(a || b)", "!| a b"),
+            ("true U \"x = 1\"", "U true x = 1"),
+            ("FF1b_2 && Fred", "& FF1b_2 F red"),
+            ("1 & 0", "& true false"),
+            ("F[<=3] a", "F[<=3] a"),
+            ("G[<= 2]!a", "G[<=2] !a"),
+            ("a U[<=1] b U c", "U[<=1] a U b c"),
+        ];
+
+        for (input, expected) in cases {
+            let parsed = parse(input);
+            assert!(parsed.is_ok(), "could not parse '{}': {:?}", input, parsed);
+            // Quoted propositions may contain spaces which the prefix parser does not allow
+            let expected = if expected.contains("x = 1") {
+                Formula {
+                    root_expr: Expr::Until(
+                        Box::new(Expr::True),
+                        Box::new(Expr::Atomic("x = 1".into())),
+                    ),
+                }
+            } else {
+                Formula::parse(expected).unwrap()
+            };
+            assert_eq!(parsed.unwrap(), expected, "input: '{}'", input);
+        }
+    }
+
+    #[test]
+    pub fn equivalence() {
+        let parsed = parse("a <-> b").unwrap();
+        let expected = Formula::parse("& | !a b | !b a").unwrap();
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    pub fn invalid_spot_syntax() {
+        for input in ["a &&", "(a", "a b", "U a b", "G ("] {
+            assert!(parse(input).is_err(), "'{}' should not parse", input);
+        }
+    }
+
+    #[test]
+    pub fn serialization() {
+        for input in [
+            "G | !r F g",
+            "U true W a M b R !c d",
+            "& X Fa G tokens(p1,p2)<=3",
+            "| true false",
+            "& xor U",
+            "U[<=2] F[<=1] a G[<=0] b",
+        ] {
+            let formula = Formula::parse(input).unwrap();
+            let json = serde_json::to_string(&formula).unwrap();
+            assert_eq!(serde_json::from_str::<Formula>(&json).unwrap(), formula);
+        }
+        let formula = Formula::parse("G | !r F g").unwrap();
+        assert_eq!(formula.to_spot().unwrap(), "G (!r | F g)");
+        assert_eq!(
+            serde_json::to_string(&formula.root_expr).unwrap(),
+            "\"G (!r | F g)\""
+        );
+
+        let quote = Formula {
+            root_expr: Expr::Atomic("\"".into()),
+        };
+        assert!(serde_json::to_string(&quote).is_err());
+        assert!(serde_json::from_str::<Formula>("\"a &&\"").is_err());
+    }
+}
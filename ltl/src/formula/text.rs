@@ -0,0 +1,61 @@
+// Plain text property files with one formula per line
+
+use crate::{error::Error, Formula};
+
+/// Parse every property of the file with the given formula parser, for example `Formula::parse`
+///
+/// Empty lines and lines starting with '#' are skipped. A line may start with an id followed by a
+/// colon, otherwise the property is named after its line number.
+pub fn parse(
+    input: &str,
+    parse_formula: impl Fn(&str) -> Result<Formula, Error>,
+) -> Result<Vec<(String, Formula)>, Error> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let (id, formula) = match line.split_once(": ") {
+                Some((id, formula)) if !id.contains(char::is_whitespace) => {
+                    (id.to_string(), formula)
+                }
+                _ => (format!("line-{}", number), line),
+            };
+            match parse_formula(formula) {
+                Ok(formula) => Ok((id, formula)),
+                Err(e) => Err(Error::Property {
+                    id,
+                    reason: e.to_string(),
+                }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::spot;
+
+    #[test]
+    pub fn lines() {
+        let input = "# response\nG | !r F g\n\nsafety: G !a\n";
+        let properties = parse(input, Formula::parse).unwrap();
+        assert_eq!(
+            properties,
+            vec![
+                ("line-2".to_string(), Formula::parse("G | !r F g").unwrap()),
+                ("safety".to_string(), Formula::parse("G !a").unwrap()),
+            ]
+        );
+
+        let properties = parse("G (r -> F g)", spot::parse).unwrap();
+        assert_eq!(properties[0].1, Formula::parse("G | !r F g").unwrap());
+
+        match parse("a\n& a", Formula::parse) {
+            Err(Error::Property { id, .. }) => assert_eq!(id, "line-2"),
+            other => panic!("expected an invalid property, found {:?}", other),
+        }
+    }
+}
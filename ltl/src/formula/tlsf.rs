@@ -0,0 +1,301 @@
+// Parser for specifications in the basic Temporal Logic Synthesis Format (TLSF) of SYNTCOMP,
+// parameterized ones have to be instantiated first, for example with 'syfco -f basic'
+
+use crate::{error::Error, spot, Expr, Formula};
+
+/// A synthesis problem given by the partition of the atomic propositions and an LTL formula
+#[derive(Debug, Clone)]
+pub struct Specification {
+    pub title: Option<String>,
+    pub semantics: Option<String>,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub formula: Formula,
+}
+
+/// Parse a specification in basic TLSF
+///
+/// The sections of the main block are combined as
+/// 'INITIALLY -> (PRESET && (G REQUIRE -> (G ASSERT && (ASSUMPTIONS -> GUARANTEES))))'.
+/// Specifications with Moore semantics are rejected, controllers are Mealy machines which read
+/// the inputs of a step before choosing its outputs.
+pub fn parse(input: &str) -> Result<Specification, Error> {
+    let input = strip_comments(input);
+    let top_level = blocks(&input)?;
+
+    let mut title = None;
+    let mut semantics = None;
+    let mut main = None;
+    for (name, body) in top_level {
+        match name {
+            "INFO" => {
+                for (key, value) in info_fields(body) {
+                    match key {
+                        "TITLE" => title = Some(value),
+                        "SEMANTICS" if value.split(',').any(|s| s.trim() == "Moore") => {
+                            return Err(Error::Tlsf(
+                                "Moore semantics are not supported, the outputs of a step may \
+                                 only be chosen after its inputs"
+                                    .into(),
+                            ))
+                        }
+                        "SEMANTICS" => semantics = Some(value),
+                        _ => {}
+                    }
+                }
+            }
+            "MAIN" => main = Some(body),
+            "GLOBAL" => {
+                return Err(Error::Tlsf(
+                    "parameterized specifications are not supported, convert to basic TLSF first"
+                        .into(),
+                ))
+            }
+            _ => return Err(Error::Tlsf(format!("unknown block '{}'", name))),
+        }
+    }
+    let main = main.ok_or_else(|| Error::Tlsf("missing MAIN block".into()))?;
+
+    let mut inputs = vec![];
+    let mut outputs = vec![];
+    let mut initially = vec![];
+    let mut preset = vec![];
+    let mut require = vec![];
+    let mut assert = vec![];
+    let mut assumptions = vec![];
+    let mut guarantees = vec![];
+    for (name, body) in blocks(main)? {
+        match name {
+            "INPUTS" => inputs.extend(propositions(body)?),
+            "OUTPUTS" => outputs.extend(propositions(body)?),
+            "INITIALLY" => initially.extend(statements(body)?),
+            "PRESET" => preset.extend(statements(body)?),
+            "REQUIRE" => require.extend(statements(body)?),
+            "ASSERT" | "INVARIANTS" => assert.extend(statements(body)?),
+            "ASSUME" | "ASSUMPTIONS" => assumptions.extend(statements(body)?),
+            "GUARANTEE" | "GUARANTEES" => guarantees.extend(statements(body)?),
+            _ => return Err(Error::Tlsf(format!("unknown section '{}'", name))),
+        }
+    }
+
+    let invariants = implies(
+        globally(conjunction(require)),
+        and(
+            globally(conjunction(assert)),
+            implies(conjunction(assumptions), conjunction(guarantees)),
+        ),
+    );
+    let root_expr = implies(conjunction(initially), and(conjunction(preset), invariants));
+
+    Ok(Specification {
+        title,
+        semantics,
+        inputs,
+        outputs,
+        formula: Formula { root_expr },
+    })
+}
+
+// Empty sections are true, so the constructors below avoid introducing trivial subformulas
+fn conjunction(exprs: Vec<Expr>) -> Expr {
+    exprs.into_iter().fold(Expr::True, and)
+}
+
+fn and(lhs: Expr, rhs: Expr) -> Expr {
+    match (lhs, rhs) {
+        (Expr::True, e) | (e, Expr::True) => e,
+        (lhs, rhs) => Expr::And(Box::new(lhs), Box::new(rhs)),
+    }
+}
+
+fn implies(lhs: Expr, rhs: Expr) -> Expr {
+    match (lhs, rhs) {
+        (Expr::True, e) => e,
+        (_, Expr::True) => Expr::True,
+        (lhs, rhs) => spot::implies(lhs, rhs),
+    }
+}
+
+fn globally(expr: Expr) -> Expr {
+    match expr {
+        Expr::True => Expr::True,
+        e => Expr::Globally(Box::new(e)),
+    }
+}
+
+fn strip_comments(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("//") {
+            rest = after.find('\n').map_or("", |i| &after[i..]);
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.find("*/").map_or("", |i| &after[i + 2..]);
+        } else {
+            let c = rest.chars().next().unwrap();
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+/// Split the input into a sequence of 'NAME { body }' blocks
+fn blocks(input: &str) -> Result<Vec<(&str, &str)>, Error> {
+    let mut result = vec![];
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        let open = rest
+            .find('{')
+            .ok_or_else(|| Error::Tlsf(format!("expected a block, found '{}'", rest.trim())))?;
+        let name = rest[..open].trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+            return Err(Error::Tlsf(format!("invalid block name '{}'", name)));
+        }
+
+        let mut depth = 0;
+        let mut quoted = false;
+        let mut close = None;
+        for (i, c) in rest[open..].char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                '{' if !quoted => depth += 1,
+                '}' if !quoted => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(open + i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let close = close.ok_or_else(|| Error::Tlsf(format!("unclosed block '{}'", name)))?;
+        result.push((name, &rest[open + 1..close]));
+        rest = rest[close + 1..].trim_start();
+    }
+    Ok(result)
+}
+
+/// The 'KEY: value' pairs of the info block, values may be quoted strings
+fn info_fields(body: &str) -> Vec<(&str, String)> {
+    let mut fields: Vec<(&str, String)> = vec![];
+    let mut rest = body.trim_start();
+    while !rest.is_empty() {
+        let (word, after) = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let word = &rest[..end];
+            match word.strip_suffix(':') {
+                Some(key) if !key.is_empty() && key.chars().all(|c| c.is_ascii_uppercase()) => {
+                    fields.push((key, String::new()));
+                    rest = rest[end..].trim_start();
+                    continue;
+                }
+                _ => (word, &rest[end..]),
+            }
+        };
+        if let Some((_, value)) = fields.last_mut() {
+            if !value.is_empty() {
+                value.push(' ');
+            }
+            value.push_str(word);
+        }
+        rest = after.trim_start();
+    }
+    fields
+}
+
+fn statements(body: &str) -> Result<Vec<Expr>, Error> {
+    body.split(';')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| spot::parse(s).map(|f| f.root_expr))
+        .collect()
+}
+
+fn propositions(body: &str) -> Result<Vec<String>, Error> {
+    statements(body)?
+        .into_iter()
+        .map(|e| match e {
+            Expr::Atomic(ap) => Ok(ap),
+            e => Err(Error::Tlsf(format!("'{}' is not a proposition", e))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ARBITER: &str = r#"
+INFO {
+  TITLE:       "Simple Arbiter"
+  DESCRIPTION: "Every request is eventually granted"
+  SEMANTICS:   Mealy
+  TARGET:      Mealy
+}
+
+MAIN {
+  INPUTS {
+    r;
+  }
+  OUTPUTS {
+    g; // the grant
+  }
+  /* no assumptions on the environment */
+  GUARANTEES {
+    G (r -> F g);
+  }
+}
+"#;
+
+    #[test]
+    pub fn arbiter() {
+        let spec = parse(ARBITER).unwrap();
+        assert_eq!(spec.title.as_deref(), Some("Simple Arbiter"));
+        assert_eq!(spec.semantics.as_deref(), Some("Mealy"));
+        assert_eq!(spec.inputs, vec!["r".to_string()]);
+        assert_eq!(spec.outputs, vec!["g".to_string()]);
+        assert_eq!(spec.formula, Formula::parse("G | !r F g").unwrap());
+    }
+
+    #[test]
+    pub fn sections_are_combined() {
+        let spec = parse(
+            "MAIN { INPUTS { a; } OUTPUTS { b; } ASSUMPTIONS { F a; } \
+             ASSERT { a -> b; } GUARANTEES { F b; } }",
+        )
+        .unwrap();
+        assert_eq!(
+            spec.formula,
+            Formula::parse("& G | !a b | !F a F b").unwrap()
+        );
+    }
+
+    #[test]
+    pub fn single_line_info() {
+        let spec = parse("INFO { TITLE: \"A: B\" SEMANTICS: Mealy } MAIN { GUARANTEES { G a; } }")
+            .unwrap();
+        assert_eq!(spec.title.as_deref(), Some("A: B"));
+        assert_eq!(spec.semantics.as_deref(), Some("Mealy"));
+    }
+
+    #[test]
+    pub fn invalid_tlsf() {
+        assert!(parse("INFO { TITLE: \"x\" }").is_err());
+        assert!(parse("MAIN { INPUTS { a & b; } }").is_err());
+        assert!(parse("MAIN { GUARANTEES { G a } ").is_err());
+        assert!(parse("GLOBAL { PARAMETERS { n = 2; } } MAIN { }").is_err());
+        // Controllers can not choose their outputs before the inputs of a step
+        for semantics in ["Moore", "Moore,Strict"] {
+            let moore = ARBITER.replace("SEMANTICS:   Mealy", &format!("SEMANTICS: {}", semantics));
+            match parse(&moore) {
+                Err(Error::Tlsf(reason)) => assert!(reason.contains("Moore"), "{}", reason),
+                other => panic!("expected Moore semantics to be rejected, found {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+// Vacuity detection by strengthening the formula
+
+use std::fmt::Display;
+
+use crate::{Expr, ExprRewriter, ExprVisitor, Formula};
+
+/// Result of the vacuity check of a formula with respect to a model
+#[derive(Debug, Clone)]
+pub struct Vacuity {
+    /// Every behaviour of the model satisfies the formula
+    pub valid: bool,
+    /// No behaviour of the model satisfies the formula
+    pub contradiction: bool,
+    /// Occurrences of propositions which do not influence the satisfaction of a valid formula
+    pub vacuous: Vec<Strengthening>,
+}
+
+/// A formula in which a single occurrence of an atomic proposition has been replaced
+#[derive(Debug, Clone)]
+pub struct Strengthening {
+    pub proposition: String,
+    /// Index of the occurrence from left to right, starting at 0
+    pub occurrence: usize,
+    pub formula: Formula,
+}
+
+impl Formula {
+    /// All formulas obtained by replacing one occurrence of an atomic proposition with the
+    /// constant that makes the formula stronger
+    ///
+    /// All operators are monotone, so every occurrence is either positive (below an even number
+    /// of negations) or negative. Positive occurrences are replaced by false, negative ones by
+    /// true.
+    pub fn strengthenings(&self) -> Vec<Strengthening> {
+        let mut occurrences = Occurrences(vec![]);
+        self.root_expr.accept(&mut occurrences);
+        occurrences
+            .0
+            .into_iter()
+            .enumerate()
+            .map(|(occurrence, proposition)| Strengthening {
+                proposition,
+                occurrence,
+                formula: Formula {
+                    root_expr: self.root_expr.rewrite(&mut Strengthen {
+                        index: occurrence,
+                        seen: 0,
+                        positive: true,
+                    }),
+                },
+            })
+            .collect()
+    }
+
+    /// Check the formula for validity, contradiction and vacuity with respect to a model
+    ///
+    /// `exists` has to decide whether some behaviour of the model satisfies the given formula.
+    /// For the model of all words this is plain satisfiability.
+    pub fn vacuity(&self, mut exists: impl FnMut(&Formula) -> bool) -> Vacuity {
+        let valid = !exists(&self.negation());
+        let contradiction = !exists(self);
+        let vacuous = if valid && !contradiction {
+            self.strengthenings()
+                .into_iter()
+                .filter(|s| !exists(&s.formula.negation()))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Vacuity {
+            valid,
+            contradiction,
+            vacuous,
+        }
+    }
+}
+
+/// Collects the atomic propositions in the order of their occurrence
+struct Occurrences(Vec<String>);
+
+impl ExprVisitor for Occurrences {
+    fn visit(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Atomic(ap) => self.0.push(ap.clone()),
+            _ => self.visit_children(expr),
+        }
+    }
+}
+
+/// Replaces the occurrence with the given index by the constant which strengthens the formula
+struct Strengthen {
+    index: usize,
+    seen: usize,
+    positive: bool,
+}
+
+impl ExprRewriter for Strengthen {
+    fn rewrite(&mut self, expr: &Expr) -> Expr {
+        match expr {
+            Expr::Atomic(_) => {
+                self.seen += 1;
+                match (self.seen - 1 == self.index, self.positive) {
+                    (true, true) => Expr::False,
+                    (true, false) => Expr::True,
+                    (false, _) => expr.clone(),
+                }
+            }
+            Expr::Not(_) => {
+                self.positive = !self.positive;
+                let rewritten = self.rewrite_children(expr);
+                self.positive = !self.positive;
+                rewritten
+            }
+            _ => self.rewrite_children(expr),
+        }
+    }
+}
+
+impl Display for Vacuity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.contradiction {
+            return write!(f, "Contradiction, the formula is never satisfied");
+        }
+        if !self.valid {
+            return write!(f, "The formula is neither valid nor a contradiction");
+        }
+        if self.vacuous.is_empty() {
+            return write!(f, "Valid, every occurrence of a proposition is relevant");
+        }
+        write!(f, "Valid, but vacuously satisfied:")?;
+        for s in &self.vacuous {
+            write!(
+                f,
+                "\n  occurrence {} of '{}' is irrelevant, '{}' holds as well",
+                s.occurrence, s.proposition, s.formula
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bmc;
+
+    use super::*;
+
+    // Lasso shaped models with up to 4 positions are enough for these small formulas
+    fn satisfiable(f: &Formula) -> bool {
+        bmc::satisfiable(f, 3).is_some()
+    }
+
+    #[test]
+    pub fn strengthening_respects_polarity() {
+        let formula = Formula::parse("G | !r F g").unwrap();
+        let strengthened = formula
+            .strengthenings()
+            .into_iter()
+            .map(|s| s.formula)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            strengthened,
+            vec![
+                Formula::parse("G | !true F g").unwrap(),
+                Formula::parse("G | !r F false").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn tautology_and_contradiction() {
+        let tautology = Formula::parse("| a !a").unwrap().vacuity(satisfiable);
+        assert!(tautology.valid && !tautology.contradiction);
+
+        let contradiction = Formula::parse("& G a F !a").unwrap().vacuity(satisfiable);
+        assert!(contradiction.contradiction && !contradiction.valid);
+
+        let neither = Formula::parse("F a").unwrap().vacuity(satisfiable);
+        assert!(!neither.valid && !neither.contradiction);
+    }
+
+    #[test]
+    pub fn vacuous_antecedent() {
+        // A model in which r never holds, described by G !r
+        let model = Formula::parse("G !r").unwrap();
+        let exists = |f: &Formula| {
+            satisfiable(&Formula {
+                root_expr: Expr::And(
+                    Box::new(model.root_expr.clone()),
+                    Box::new(f.root_expr.clone()),
+                ),
+            })
+        };
+        let result = Formula::parse("G | !r F g").unwrap().vacuity(exists);
+        assert!(result.valid);
+        assert_eq!(result.vacuous.len(), 1);
+        assert_eq!(result.vacuous[0].proposition, "g");
+    }
+}
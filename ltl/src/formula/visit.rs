@@ -0,0 +1,141 @@
+// Generic traversal of expressions
+
+use crate::Expr;
+
+/// Read only traversal of an expression in pre order
+pub trait ExprVisitor {
+    /// Visit a node, the default continues with the children
+    fn visit(&mut self, expr: &Expr) {
+        self.visit_children(expr)
+    }
+
+    fn visit_children(&mut self, expr: &Expr) {
+        for child in expr.children() {
+            self.visit(child);
+        }
+    }
+}
+
+/// Top down transformation of an expression into a new one
+pub trait ExprRewriter {
+    /// Rewrite a node, the default keeps the operator and rewrites the children
+    fn rewrite(&mut self, expr: &Expr) -> Expr {
+        self.rewrite_children(expr)
+    }
+
+    fn rewrite_children(&mut self, expr: &Expr) -> Expr {
+        expr.map_children(|child| self.rewrite(child))
+    }
+}
+
+impl Expr {
+    /// The direct subexpressions from left to right
+    pub fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::True | Expr::False | Expr::Atomic(_) => vec![],
+            Expr::Not(e)
+            | Expr::Next(e)
+            | Expr::Globally(e)
+            | Expr::Finally(e)
+            | Expr::BoundedFinally(_, e)
+            | Expr::BoundedGlobally(_, e) => vec![e],
+            Expr::And(lhs, rhs)
+            | Expr::Or(lhs, rhs)
+            | Expr::Until(lhs, rhs)
+            | Expr::WeakUntil(lhs, rhs)
+            | Expr::Release(lhs, rhs)
+            | Expr::StrongRelease(lhs, rhs)
+            | Expr::BoundedUntil(_, lhs, rhs) => vec![lhs, rhs],
+        }
+    }
+
+    /// Rebuild the node with `f` applied to its direct subexpressions from left to right
+    pub fn map_children(&self, mut f: impl FnMut(&Expr) -> Expr) -> Expr {
+        let mut unary = |e: &Expr| Box::new(f(e));
+        match self {
+            Expr::True | Expr::False | Expr::Atomic(_) => self.clone(),
+            Expr::Not(e) => Expr::Not(unary(e)),
+            Expr::Next(e) => Expr::Next(unary(e)),
+            Expr::Globally(e) => Expr::Globally(unary(e)),
+            Expr::Finally(e) => Expr::Finally(unary(e)),
+            Expr::And(lhs, rhs) => Expr::And(unary(lhs), unary(rhs)),
+            Expr::Or(lhs, rhs) => Expr::Or(unary(lhs), unary(rhs)),
+            Expr::Until(lhs, rhs) => Expr::Until(unary(lhs), unary(rhs)),
+            Expr::WeakUntil(lhs, rhs) => Expr::WeakUntil(unary(lhs), unary(rhs)),
+            Expr::Release(lhs, rhs) => Expr::Release(unary(lhs), unary(rhs)),
+            Expr::StrongRelease(lhs, rhs) => Expr::StrongRelease(unary(lhs), unary(rhs)),
+            Expr::BoundedFinally(k, e) => Expr::BoundedFinally(*k, unary(e)),
+            Expr::BoundedGlobally(k, e) => Expr::BoundedGlobally(*k, unary(e)),
+            Expr::BoundedUntil(k, lhs, rhs) => Expr::BoundedUntil(*k, unary(lhs), unary(rhs)),
+        }
+    }
+
+    pub fn accept(&self, visitor: &mut impl ExprVisitor) {
+        visitor.visit(self)
+    }
+
+    pub fn rewrite(&self, rewriter: &mut impl ExprRewriter) -> Expr {
+        rewriter.rewrite(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Formula;
+
+    struct CountNext(usize);
+
+    impl ExprVisitor for CountNext {
+        fn visit(&mut self, expr: &Expr) {
+            if let Expr::Next(_) = expr {
+                self.0 += 1;
+            }
+            self.visit_children(expr)
+        }
+    }
+
+    struct SwapUntil;
+
+    impl ExprRewriter for SwapUntil {
+        fn rewrite(&mut self, expr: &Expr) -> Expr {
+            match expr {
+                Expr::Until(lhs, rhs) => {
+                    Expr::Until(Box::new(self.rewrite(rhs)), Box::new(self.rewrite(lhs)))
+                }
+                _ => self.rewrite_children(expr),
+            }
+        }
+    }
+
+    #[test]
+    pub fn visitor() {
+        let mut count = CountNext(0);
+        Formula::parse("& X a U X X b c")
+            .unwrap()
+            .root_expr
+            .accept(&mut count);
+        assert_eq!(count.0, 3);
+    }
+
+    #[test]
+    pub fn rewriter() {
+        let rewritten = Formula::parse("G U a U b c")
+            .unwrap()
+            .root_expr
+            .rewrite(&mut SwapUntil);
+        assert_eq!(rewritten, Formula::parse("G U U c b a").unwrap().root_expr);
+    }
+
+    #[test]
+    pub fn map_children_keeps_order() {
+        let expr = Formula::parse("W a b").unwrap().root_expr;
+        let mut seen = vec![];
+        let mapped = expr.map_children(|e| {
+            seen.push(e.clone());
+            Expr::Not(Box::new(e.clone()))
+        });
+        assert_eq!(expr.children(), seen.iter().collect::<Vec<_>>());
+        assert_eq!(mapped, Formula::parse("W !a !b").unwrap().root_expr);
+    }
+}
@@ -3,28 +3,29 @@ use std::fmt::Display;
 use quick_xml::de::from_str;
 use serde_derive::Deserialize;
 
-use crate::{error::Error, Formula};
+use crate::{error::Error, Expr, Formula};
 
-pub fn parse(input: &str) -> Option<Vec<(String, Formula)>> {
-    let properties = from_str::<PropertySet>(input).ok()?.properties;
+/// Parse the LTL properties of a property file in the MCC format, together with their ids
+pub fn parse(input: &str) -> Result<Vec<(String, Formula)>, Error> {
+    let properties = from_str::<PropertySet>(input)
+        .map_err(|e| Error::Xml(e.to_string()))?
+        .properties;
 
-    Some(
-        properties
-            .into_iter()
-            .map(|p| {
-                (
-                    p.id,
-                    property_to_formula(p.formula)
-                        .expect(&format!("Could not parse input {}", input)),
-                )
-            })
-            .collect(),
-    )
-}
-
-fn property_to_formula(base: AllPathFormula) -> Result<Formula, Error> {
-    let raw = base.all_paths.root_formula.to_string();
-    Formula::parse(&raw)
+    properties
+        .into_iter()
+        .map(|p| {
+            let root_expr = p
+                .formula
+                .all_paths
+                .root_formula
+                .to_expr()
+                .map_err(|reason| Error::Property {
+                    id: p.id.clone(),
+                    reason,
+                })?;
+            Ok((p.id, Formula { root_expr }))
+        })
+        .collect()
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,66 +83,65 @@ enum BooleanFormula {
         before: Before,
         reach: Reach,
     },
+    /// At least one of the transitions is fireable
     #[serde(rename = "is-fireable")]
     Atom(Transitions),
+    IntegerLe {
+        #[serde(rename = "$value")]
+        operands: Vec<IntegerExpression>,
+    },
 }
 
-impl Display for BooleanFormula {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Finally { inner } => write!(f, "F {}", inner),
-            Self::Globally { inner } => write!(f, "G {}", inner),
-            Self::Next { inner } => write!(f, "X {}", inner),
-            Self::Negation { inner } => write!(f, "!{}", inner),
-            c @ Self::Conjunction { inner } => {
-                if inner.len() <= 1 {
-                    panic!(
-                        "Conjunction: {:?} does not have at least two subformulas",
-                        c
-                    )
-                }
-                for _ in 0..inner.len() - 1 {
-                    write!(f, "{}", "& ")?;
-                }
-                write!(
-                    f,
-                    "{}",
-                    inner
-                        .iter()
-                        .map(Self::to_string)
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                )
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum IntegerExpression {
+    TokensCount(Places),
+    IntegerConstant(usize),
+}
+
+impl BooleanFormula {
+    fn to_expr(&self) -> Result<Expr, String> {
+        let unary = |inner: &BooleanFormula| inner.to_expr().map(Box::new);
+        // Operands of n-ary operators are nested to the left
+        let nary = |name: &str, inner: &[BooleanFormula], op: fn(Box<Expr>, Box<Expr>) -> Expr| {
+            if inner.len() < 2 {
+                return Err(format!("{} needs at least two operands", name));
             }
-            d @ Self::Disjunction { inner } => {
-                if inner.len() <= 1 {
-                    panic!(
-                        "Disjunction: {:?} does not have at least two subformulas",
-                        d
-                    )
-                }
-                for _ in 0..inner.len() - 1 {
-                    write!(f, "{}", "| ")?;
-                }
-                write!(
-                    f,
-                    "{}",
-                    inner
-                        .iter()
-                        .map(Self::to_string)
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                )
+            let mut operands = inner.iter().map(BooleanFormula::to_expr);
+            let first = operands.next().unwrap()?;
+            operands.try_fold(first, |lhs, rhs| Ok(op(Box::new(lhs), Box::new(rhs?))))
+        };
+        Ok(match self {
+            Self::Finally { inner } => Expr::Finally(unary(inner)?),
+            Self::Globally { inner } => Expr::Globally(unary(inner)?),
+            Self::Next { inner } => Expr::Next(unary(inner)?),
+            Self::Negation { inner } => Expr::Not(unary(inner)?),
+            Self::Conjunction { inner } => nary("Conjunction", inner, Expr::And)?,
+            Self::Disjunction { inner } => nary("Disjunction", inner, Expr::Or)?,
+            Self::Until { before, reach } => {
+                Expr::Until(unary(&before.inner)?, unary(&reach.inner)?)
             }
-            Self::Until { before, reach } => write!(f, "U {} {}", before.inner, reach.inner),
             Self::Atom(transitions) => {
-                for _ in 0..transitions.transitions.len() - 1 {
-                    write!(f, "& ")?;
-                }
-
-                write!(f, "{}", transitions.transitions.join(" "))
+                let atoms = transitions
+                    .transitions
+                    .iter()
+                    .map(|t| Expr::Atomic(Atom::Fireable(t.clone()).to_string()))
+                    .reduce(|lhs, rhs| Expr::Or(Box::new(lhs), Box::new(rhs)));
+                atoms.ok_or("is-fireable needs at least one transition")?
             }
-        }
+            Self::IntegerLe { operands } => {
+                let [lhs, rhs] = match &operands[..] {
+                    [lhs, rhs] => [lhs, rhs].map(|op| match op {
+                        IntegerExpression::TokensCount(places) => {
+                            IntegerExpr::TokensCount(places.places.clone())
+                        }
+                        IntegerExpression::IntegerConstant(c) => IntegerExpr::Constant(*c),
+                    }),
+                    _ => return Err("integer-le needs exactly two operands".into()),
+                };
+                Expr::Atomic(Atom::IntegerLe(lhs, rhs).to_string())
+            }
+        })
     }
 }
 
@@ -162,3 +162,171 @@ struct Transitions {
     #[serde(rename = "transition")]
     transitions: Vec<String>,
 }
+
+#[derive(Debug, Deserialize)]
+struct Places {
+    #[serde(rename = "place")]
+    places: Vec<String>,
+}
+
+/// Atomic propositions of properties in the MCC format
+///
+/// Formulas refer to them by name, which can be converted back with `Atom::from_name`. Token
+/// counts are written as 'tokens(p1,p2)<=3'.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Atom {
+    /// The transition with the given name is fireable
+    Fireable(String),
+    /// Comparison 'lhs <= rhs' of two integer expressions
+    IntegerLe(IntegerExpr, IntegerExpr),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IntegerExpr {
+    Constant(usize),
+    /// Sum of the tokens in the given places
+    TokensCount(Vec<String>),
+}
+
+impl Atom {
+    pub fn from_name(name: &str) -> Self {
+        match name.split_once("<=") {
+            Some((lhs, rhs)) => match (IntegerExpr::from_name(lhs), IntegerExpr::from_name(rhs)) {
+                (Some(lhs), Some(rhs)) => Atom::IntegerLe(lhs, rhs),
+                _ => Atom::Fireable(name.to_string()),
+            },
+            None => Atom::Fireable(name.to_string()),
+        }
+    }
+}
+
+impl IntegerExpr {
+    fn from_name(name: &str) -> Option<Self> {
+        match name
+            .strip_prefix("tokens(")
+            .and_then(|s| s.strip_suffix(')'))
+        {
+            Some(places) => Some(IntegerExpr::TokensCount(
+                places.split(',').map(str::to_string).collect(),
+            )),
+            None => name.parse().ok().map(IntegerExpr::Constant),
+        }
+    }
+
+    /// Evaluate the expression given the number of tokens of each place
+    pub fn evaluate(&self, mut tokens: impl FnMut(&str) -> usize) -> usize {
+        match self {
+            IntegerExpr::Constant(c) => *c,
+            IntegerExpr::TokensCount(places) => places.iter().map(|p| tokens(p)).sum(),
+        }
+    }
+}
+
+impl Display for Atom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Atom::Fireable(t) => write!(f, "{}", t),
+            Atom::IntegerLe(lhs, rhs) => write!(f, "{}<={}", lhs, rhs),
+        }
+    }
+}
+
+impl Display for IntegerExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegerExpr::Constant(c) => write!(f, "{}", c),
+            IntegerExpr::TokensCount(places) => write!(f, "tokens({})", places.join(",")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Expr;
+
+    #[test]
+    pub fn tokens_count() {
+        let input = r#"<?xml version="1.0"?>
+<property-set xmlns="http://mcc.lip6.fr/">
+  <property>
+    <id>Net-LTLCardinality-00</id>
+    <description>Automatically generated</description>
+    <formula>
+      <all-paths>
+        <globally>
+          <integer-le>
+            <tokens-count>
+              <place>p1</place>
+              <place>p2</place>
+            </tokens-count>
+            <integer-constant>3</integer-constant>
+          </integer-le>
+        </globally>
+      </all-paths>
+    </formula>
+  </property>
+</property-set>"#;
+        let properties = parse(input).unwrap();
+        assert_eq!(properties.len(), 1);
+        let (id, formula) = &properties[0];
+        assert_eq!(id, "Net-LTLCardinality-00");
+
+        let name = match &formula.root_expr {
+            Expr::Globally(inner) => match &**inner {
+                Expr::Atomic(name) => name.clone(),
+                e => panic!("expected an atomic proposition, found {}", e),
+            },
+            e => panic!("expected G, found {}", e),
+        };
+        let atom = Atom::from_name(&name);
+        assert_eq!(
+            atom,
+            Atom::IntegerLe(
+                IntegerExpr::TokensCount(vec!["p1".into(), "p2".into()]),
+                IntegerExpr::Constant(3)
+            )
+        );
+    }
+
+    #[test]
+    pub fn invalid_properties() {
+        let property = |formula: &str| {
+            format!(
+                "<property-set><property><id>P-1</id><formula><all-paths>{}</all-paths></formula></property></property-set>",
+                formula
+            )
+        };
+        assert!(matches!(parse("<property-set>"), Err(Error::Xml(_))));
+
+        let single =
+            "<conjunction><is-fireable><transition>t</transition></is-fireable></conjunction>";
+        match parse(&property(single)) {
+            Err(Error::Property { id, reason }) => {
+                assert_eq!(id, "P-1");
+                assert!(reason.contains("Conjunction"));
+            }
+            other => panic!("expected an invalid property, found {:?}", other),
+        }
+
+        let fireable = "<finally><is-fireable><transition>t1</transition><transition>t2</transition></is-fireable></finally>";
+        let (_, formula) = parse(&property(fireable)).unwrap().remove(0);
+        assert_eq!(formula, Formula::parse("F | t1 t2").unwrap());
+    }
+
+    #[test]
+    pub fn atom_names() {
+        for atom in [
+            Atom::Fireable("t1".into()),
+            Atom::IntegerLe(
+                IntegerExpr::Constant(2),
+                IntegerExpr::TokensCount(vec!["p".into()]),
+            ),
+        ] {
+            assert_eq!(Atom::from_name(&atom.to_string()), atom);
+        }
+
+        let count = IntegerExpr::TokensCount(vec!["p1".into(), "p2".into()]);
+        assert_eq!(count.evaluate(|p| if p == "p1" { 2 } else { 1 }), 3);
+    }
+}
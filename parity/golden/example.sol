@@ -0,0 +1,10 @@
+paritysol 9;
+0 1;
+1 1 5;
+2 1;
+3 1 4;
+4 1 8;
+5 1;
+6 1;
+7 1;
+8 1 7;
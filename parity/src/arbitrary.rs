@@ -0,0 +1,68 @@
+// Random parity games for property based tests with proptest
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::{Graph, MetaData, Owner};
+
+/// Games with between one and `vertices` vertices, whose priorities are at most `vertices`. Every
+/// vertex has at least one successor like the games parsed by `parse_game`, so all solvers accept
+/// them.
+pub fn games(vertices: usize) -> BoxedStrategy<Graph> {
+    (1..=vertices.max(1))
+        .prop_flat_map(|n| {
+            let owner = prop_oneof![Just(Owner::Even), Just(Owner::Odd)];
+            vec((0..=n, owner, vec(0..n, 1..=3)), n)
+        })
+        .prop_map(|vertices| {
+            let mut game = Graph::new();
+            let indices: Vec<_> = (vertices.iter().enumerate())
+                .map(|(id, (priority, owner, _))| {
+                    game.add_vertex(MetaData {
+                        priority: *priority,
+                        owner: *owner,
+                        ..MetaData::new(id)
+                    })
+                })
+                .collect();
+            for (source, (_, _, successors)) in vertices.into_iter().enumerate() {
+                for target in successors {
+                    game.add_edge(indices[source], indices[target]);
+                }
+            }
+            game
+        })
+        .boxed()
+}
+
+impl Arbitrary for Graph {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Graph>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        games(8)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::Solution;
+
+    fn even(solution: &Solution) -> BTreeSet<usize> {
+        solution.even_region.iter().map(|v| v.id).collect()
+    }
+
+    proptest! {
+        #[test]
+        fn solvers_agree(game: Graph) {
+            let zielonka = game.zielonka();
+            prop_assert_eq!(zielonka.even_region.len() + zielonka.odd_region.len(), game.len());
+            prop_assert_eq!(even(&game.fpi()), even(&zielonka));
+            prop_assert_eq!(even(&game.tangle()), even(&zielonka));
+            prop_assert_eq!(even(&game.spm()), even(&zielonka));
+        }
+    }
+}
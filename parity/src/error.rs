@@ -0,0 +1,14 @@
+use std::result;
+use thiserror::Error;
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("invalid parity game header '{0}', expected 'parity <vertices>;'")]
+    Header(String),
+    #[error("invalid parity game in line {line}: {reason}")]
+    Line { line: usize, reason: String },
+    #[error("vertex {0} has no successors")]
+    DeadEnd(usize),
+}
@@ -1,5 +1,4 @@
 use crate::{Graph, Owner, Solution};
-use colored::Colorize;
 use itertools::Itertools;
 use petgraph::graph::NodeIndex;
 use std::collections::{BTreeSet, HashMap, HashSet};
@@ -53,14 +52,15 @@ impl Graph {
     }
 
     pub fn fpi<'a>(&'a self) -> Solution<'a> {
-        log::info!("solving with FPI + freezing");
+        let _span = tracing::info_span!("fpi").entered();
+        if self.inner.node_count() == 0 {
+            return Solution::empty();
+        }
         let mut z = BTreeSet::new();
         let mut frozen = HashMap::new();
         let mut strategy = HashMap::new();
         let mut p = 0;
-        let max_priority = self
-            .highest_priority()
-            .expect("Graph was empty, cannot determine highest priority");
+        let max_priority = self.highest_priority().unwrap_or(0);
 
         while p <= max_priority {
             let parity = p % 2;
@@ -80,7 +80,7 @@ impl Graph {
                 }
                 if alpha != parity {
                     chg = true;
-                    log::debug!("distractions <- {}", self.debug_vertice(v));
+                    tracing::debug!(vertex = %self.debug_vertice(v), "distraction");
                     z.insert(v);
                 }
             }
@@ -95,20 +95,15 @@ impl Graph {
                     .collect_vec()
                 {
                     if self.winner(v, &z) == (p + 1) % 2 {
-                        log::debug!(
-                            "{} {} at priority {}",
-                            "freezing".cyan(),
-                            self.debug_vertice(v),
-                            p
-                        );
+                        tracing::debug!(vertex = %self.debug_vertice(v), priority = p, "freezing");
                         frozen.insert(v, p);
                     } else {
-                        log::debug!("{} {}", "resetting".red(), self.debug_vertice(v));
+                        tracing::debug!(vertex = %self.debug_vertice(v), "resetting");
                         z.remove(&v);
                     }
                 }
                 p = 0;
-                log::debug!("restarting after finding distractions");
+                tracing::debug!("restarting after finding distractions");
             } else {
                 for v in self
                     .inner
@@ -118,7 +113,7 @@ impl Graph {
                     .filter(|v| frozen.get(v) == Some(&p))
                     .collect_vec()
                 {
-                    log::debug!("{} {}", "thawing".bright_red(), self.debug_vertice(v),);
+                    tracing::debug!(vertex = %self.debug_vertice(v), "thawing");
                     frozen.remove(&v);
                 }
                 p += 1;
@@ -1,11 +1,17 @@
+#[cfg(any(test, feature = "testing"))]
+pub mod arbitrary;
+mod error;
 mod fpi;
+mod normalize;
+mod output;
 mod parse;
-mod tangle;
 mod spm;
+mod tangle;
 mod zielonka;
+pub use error::{Error, Result};
 use itertools::Itertools;
 pub use parse::parse_game;
-use petgraph::graph::NodeIndex;
+pub use petgraph::graph::NodeIndex;
 use petgraph::stable_graph::StableDiGraph;
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::Display;
@@ -65,18 +71,38 @@ impl Display for Owner {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default, Debug)]
 pub struct Graph {
     inner: StableDiGraph<MetaData, ()>,
 }
 
 impl Graph {
-    fn new() -> Self {
+    /// Create an empty game, vertices and edges are added with `add_vertex` and `add_edge`
+    pub fn new() -> Self {
         Graph {
             inner: StableDiGraph::new(),
         }
     }
 
+    /// Add a vertex to the game, the returned index is used to construct edges
+    pub fn add_vertex(&mut self, meta_data: MetaData) -> NodeIndex {
+        self.inner.add_node(meta_data)
+    }
+
+    /// Add an edge from source to target, both have to be vertices of this game
+    pub fn add_edge(&mut self, source: NodeIndex, target: NodeIndex) {
+        self.inner.add_edge(source, target, ());
+    }
+
+    /// Number of vertices in the game
+    pub fn len(&self) -> usize {
+        self.inner.node_count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.node_count() == 0
+    }
+
     fn highest_priority(&self) -> Option<usize> {
         self.inner.node_weights().map(|n| n.priority).max()
     }
@@ -88,21 +114,6 @@ impl Graph {
             .filter(move |v| self.inner[*v].owner == player)
     }
 
-    fn remove_vertices(&self, purge: &HashSet<NodeIndex>) -> Self {
-        Graph {
-            inner: self.inner.filter_map(
-                |v, w| {
-                    if purge.contains(&&v) {
-                        None
-                    } else {
-                        Some(w.clone())
-                    }
-                },
-                |_, _| Some(()),
-            ),
-        }
-    }
-
     fn remove_vertices_b_tree(&self, purge: &BTreeSet<NodeIndex>) -> Self {
         Graph {
             inner: self.inner.filter_map(
@@ -125,7 +136,7 @@ impl Graph {
         s_0: HashMap<NodeIndex, NodeIndex>,
         s_1: HashMap<NodeIndex, NodeIndex>,
     ) -> Solution {
-        log::info!("constructing solution from regions and strategies");
+        tracing::info!("constructing solution from regions and strategies");
         let mut strat = s_0;
         strat.extend(s_1.into_iter());
         let mut strategy = strat
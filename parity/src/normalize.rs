@@ -0,0 +1,143 @@
+// Normal form of games, with the fewest priorities and the vertices numbered from 0
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use crate::{Graph, MetaData};
+
+impl Graph {
+    /// Equivalent game with compressed priorities whose vertices are numbered 0 to n - 1 in the
+    /// order of their ids, keeping their labels
+    ///
+    /// Priorities are walked in ascending order, moving on to the next number only when the
+    /// parity changes. This keeps the order of the priorities and their parity, and with them the
+    /// winner of every play.
+    pub fn normalize(&self) -> Graph {
+        let priorities: BTreeSet<usize> = self.inner.node_weights().map(|v| v.priority).collect();
+        let mut compressed = HashMap::new();
+        let mut last: Option<(usize, usize)> = None;
+        for priority in priorities {
+            let next = match last {
+                None => priority % 2,
+                Some((previous, next)) if previous % 2 == priority % 2 => next,
+                Some((_, next)) => next + 1,
+            };
+            compressed.insert(priority, next);
+            last = Some((priority, next));
+        }
+
+        let vertices = self
+            .inner
+            .node_indices()
+            .sorted_by_key(|v| self.inner[*v].id)
+            .collect_vec();
+        let mut game = Graph::new();
+        let mut index = HashMap::new();
+        for (id, v) in vertices.iter().enumerate() {
+            let MetaData {
+                label,
+                owner,
+                priority,
+                ..
+            } = &self.inner[*v];
+            let new = game.add_vertex(MetaData {
+                id,
+                label: label.clone(),
+                owner: *owner,
+                priority: compressed[priority],
+            });
+            index.insert(*v, new);
+        }
+        for v in &vertices {
+            for w in self
+                .inner
+                .neighbors(*v)
+                .sorted_by_key(|w| self.inner[*w].id)
+            {
+                game.add_edge(index[v], index[&w]);
+            }
+        }
+        game
+    }
+}
+
+impl Display for Graph {
+    /// The game in PGSolver's format, whose header is one more than the highest id
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let vertices = self
+            .inner
+            .node_indices()
+            .sorted_by_key(|v| self.inner[*v].id)
+            .collect_vec();
+        let size = vertices.last().map_or(0, |v| self.inner[*v].id + 1);
+        writeln!(f, "parity {};", size)?;
+        for v in vertices {
+            let MetaData {
+                id,
+                label,
+                owner,
+                priority,
+            } = &self.inner[v];
+            let mut successors = self.inner.neighbors(v).map(|w| self.inner[w].id).sorted();
+            write!(f, "{} {} {} {}", id, priority, owner, successors.join(","))?;
+            if let Some(label) = label {
+                write!(f, " \"{}\"", label)?;
+            }
+            writeln!(f, ";")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parse_game, Graph, MetaData, Owner};
+
+    #[test]
+    pub fn normalize() {
+        let input = "parity 3;\n0 2 0 1,2;\n1 1 1 0;\n2 0 0 2 \"sink\";\n";
+        let game = parse_game(input).unwrap();
+        assert_eq!(game.to_string(), input);
+        assert_eq!(game.normalize().to_string(), input);
+
+        // Priorities 3 and 5 merge, as do 6 and 8, ids 10 and 20 become 0 and 1
+        let mut game = Graph::new();
+        let vertex = |id, priority| MetaData {
+            id,
+            label: None,
+            owner: Owner::Odd,
+            priority,
+        };
+        let a = game.add_vertex(vertex(20, 8));
+        let b = game.add_vertex(vertex(10, 3));
+        let c = game.add_vertex(vertex(30, 5));
+        let d = game.add_vertex(vertex(40, 6));
+        game.add_edge(a, b);
+        game.add_edge(b, a);
+        game.add_edge(b, c);
+        game.add_edge(c, d);
+        game.add_edge(d, c);
+        let normalized = game.normalize();
+        assert_eq!(
+            normalized.to_string(),
+            "parity 4;\n0 1 1 1,2;\n1 2 1 0;\n2 1 1 3;\n3 2 1 2;\n"
+        );
+        let winners = |game: &Graph| {
+            let solution = game.zielonka();
+            let mut winners = solution
+                .strategy
+                .iter()
+                .map(|(id, s)| (*id, s.winner))
+                .collect::<Vec<_>>();
+            winners.sort_by_key(|(id, _)| *id);
+            winners.into_iter().map(|(_, w)| w).collect::<Vec<_>>()
+        };
+        assert_eq!(winners(&normalized), winners(&game));
+        assert_eq!(
+            parse_game(&normalized.to_string()).unwrap().to_string(),
+            normalized.to_string()
+        );
+    }
+}
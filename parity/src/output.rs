@@ -0,0 +1,124 @@
+// Solutions for other tools, besides the PGSolver solution format written by `Display`
+
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::{Graph, MetaData, Owner, Solution};
+
+fn player(owner: Owner) -> &'static str {
+    match owner {
+        Owner::Even => "even",
+        Owner::Odd => "odd",
+    }
+}
+
+impl Solution<'_> {
+    /// The header 'vertex,winner,next' and a row per vertex in ascending order, next is empty
+    /// unless the winner owns the vertex
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("vertex,winner,next\n");
+        for (vertex, strategy) in self.strategy.iter().sorted_by_key(|(v, _)| **v) {
+            let next = strategy.next_node_id.map(|n| n.to_string());
+            writeln!(
+                csv,
+                "{},{},{}",
+                vertex,
+                player(strategy.winner),
+                next.unwrap_or_default()
+            )
+            .unwrap();
+        }
+        csv
+    }
+}
+
+impl Graph {
+    /// The game in DOT format with the winning regions and the strategy of the solution, drawn
+    /// like PGSolver does: vertices of even as diamonds and those of odd as boxes, filled
+    /// with the colour of their winner and with the edges chosen by the strategy in bold
+    pub fn to_dot(&self, solution: &Solution) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot = String::from("digraph game {\n");
+        let vertices = self
+            .inner
+            .node_indices()
+            .sorted_by_key(|v| self.inner[*v].id);
+        for v in vertices.clone() {
+            let MetaData {
+                id,
+                label,
+                owner,
+                priority,
+            } = &self.inner[v];
+            let label = match label {
+                Some(label) => format!("{} {}", label, priority),
+                None => format!("{}/{}", id, priority),
+            };
+            let shape = match owner {
+                Owner::Even => "diamond",
+                Owner::Odd => "box",
+            };
+            let fill = match solution.strategy.get(id).map(|s| s.winner) {
+                Some(Owner::Even) => ", style = filled, fillcolor = lightblue",
+                Some(Owner::Odd) => ", style = filled, fillcolor = lightpink",
+                None => "",
+            };
+            writeln!(
+                dot,
+                "v{} [label = \"{}\", shape = {}{}]",
+                id,
+                escape(&label),
+                shape,
+                fill
+            )
+            .unwrap();
+        }
+        for v in vertices {
+            let source = self.inner[v].id;
+            let chosen = solution.strategy.get(&source).and_then(|s| s.next_node_id);
+            for target in self.inner.neighbors(v).map(|w| self.inner[w].id).sorted() {
+                let style = if chosen == Some(target) {
+                    " [style = bold]"
+                } else {
+                    ""
+                };
+                writeln!(dot, "v{} -> v{}{}", source, target, style).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse_game;
+
+    #[test]
+    pub fn formats() {
+        let game = parse_game("parity 3;\n0 2 0 1,2;\n1 1 1 0;\n2 0 0 2 \"sink\";").unwrap();
+        let solution = game.zielonka();
+        assert_eq!(
+            solution.to_csv(),
+            "vertex,winner,next\n0,even,1\n1,even,\n2,even,2\n"
+        );
+        let dot = game.to_dot(&solution);
+        assert!(dot
+            .contains("v1 [label = \"1/1\", shape = box, style = filled, fillcolor = lightblue]"));
+        assert!(dot.contains("v2 [label = \"sink 0\", shape = diamond"));
+        assert!(dot.contains("v0 -> v1 [style = bold]\nv0 -> v2\n"));
+    }
+
+    #[test]
+    pub fn golden_files() {
+        let game = parse_game(include_str!("../golden/example.pg")).unwrap();
+        let solution = game.zielonka();
+        assert_eq!(solution.to_string(), include_str!("../golden/example.sol"));
+        assert_eq!(solution.to_csv(), include_str!("../golden/example.csv"));
+        assert_eq!(
+            game.to_dot(&solution),
+            include_str!("../golden/example.dot")
+        );
+    }
+}
@@ -1,14 +1,22 @@
+use crate::error::{Error, Result};
 use crate::{Graph, MetaData, Owner};
 use nom::bytes::complete::tag;
 use nom::character::complete::{alphanumeric1, char, digit1, multispace1};
-use nom::combinator::{map, opt};
+use nom::combinator::{map, map_res, opt};
 use nom::multi::separated_list0;
 use nom::sequence::{delimited, tuple};
 use nom::IResult;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::iter;
 fn parse_usize(input: &str) -> IResult<&str, usize> {
-    map(digit1, |s: &str| {
-        s.parse::<usize>().expect("Could not parse usize")
+    map_res(digit1, str::parse::<usize>)(input)
+}
+
+fn parse_owner(input: &str) -> IResult<&str, Owner> {
+    map_res(parse_usize, |owner| match owner {
+        0 => Ok(Owner::Even),
+        1 => Ok(Owner::Odd),
+        _ => Err("Expected 0 or 1 as owner"),
     })(input)
 }
 
@@ -35,7 +43,7 @@ pub fn parse_game_line(input: &str) -> IResult<&str, GameLine> {
             multispace1,
             parse_usize,
             multispace1,
-            parse_usize,
+            parse_owner,
             multispace1,
             separated_list0(tag(","), parse_usize),
             opt(tuple((
@@ -46,41 +54,65 @@ pub fn parse_game_line(input: &str) -> IResult<&str, GameLine> {
         |t| GameLine {
             id: t.0,
             priority: t.2,
-            owner: match t.4 {
-                0 => Owner::Even,
-                1 => Owner::Odd,
-                _ => panic!("Expected 0 or 1, cannot parse owner"),
-            },
+            owner: t.4,
             successors: t.6,
             label: t.7.map(|l| l.1),
         },
     )(input)
 }
 
-pub fn parse_game(game: &str) -> Option<Graph> {
-    let mut g = Graph::new();
-
-    let lines: Vec<_> = game.lines().collect();
+/// Parse a game in the PGSolver format, where the header gives the number of vertices and every
+/// vertex has a line with at least one successor
+pub fn parse_game(game: &str) -> Result<Graph> {
+    let mut lines = (game.lines().enumerate()).filter(|(_, line)| !line.trim().is_empty());
+    let header = lines.next().map_or("", |(_, line)| line);
+    let number_of_nodes = parse_game_header(header)
+        .map_err(|_| Error::Header(header.to_string()))?
+        .1;
 
-    if lines.is_empty() {
-        return None;
+    let mut vertices = Vec::new();
+    for (i, line) in lines {
+        let data = parse_game_line(line)
+            .map_err(|_| Error::Line {
+                line: i + 1,
+                reason: format!(
+                    "expected '<vertex> <priority> <owner> <successors>;', found '{}'",
+                    line
+                ),
+            })?
+            .1;
+        let mut vertices_of_line = iter::once(data.id).chain(data.successors.iter().copied());
+        if let Some(vertex) = vertices_of_line.find(|v| *v >= number_of_nodes) {
+            return Err(Error::Line {
+                line: i + 1,
+                reason: format!(
+                    "vertex {} is not one of the {} vertices of the game",
+                    vertex, number_of_nodes
+                ),
+            });
+        }
+        vertices.push(data);
+    }
+    // The first vertex without successors is at most the number of lines, so headers claiming
+    // too many vertices are rejected before they are allocated
+    let with_successors: HashSet<usize> = (vertices.iter())
+        .filter(|v| !v.successors.is_empty())
+        .map(|v| v.id)
+        .collect();
+    if let Some(dead_end) = (0..number_of_nodes).find(|v| !with_successors.contains(v)) {
+        return Err(Error::DeadEnd(dead_end));
     }
 
-    let number_of_nodes = parse_game_header(lines[0]).ok()?.1;
-
+    let mut g = Graph::new();
     let mut nodes = HashMap::new();
     for i in 0..number_of_nodes {
         let node_index = g.inner.add_node(MetaData::new(i));
         nodes.insert(i, node_index);
     }
 
-    for line in lines[1..].iter() {
-        let data: GameLine = parse_game_line(line).ok()?.1;
+    for data in vertices {
         let node_index = nodes[&data.id];
-        let mut meta_data = g
-            .inner
-            .node_weight_mut(node_index)
-            .expect("Could not find node with given index");
+        let meta_data = &mut g.inner[node_index];
         meta_data.label = data.label.map(String::from);
         meta_data.owner = data.owner;
         meta_data.priority = data.priority;
@@ -91,11 +123,8 @@ pub fn parse_game(game: &str) -> Option<Graph> {
         }
     }
 
-    log::info!(
-        "parsed parity game with {} vertices: {}",
-        number_of_nodes,
-        g.debug_all()
-    );
+    tracing::info!(vertices = number_of_nodes, "parsed parity game");
+    tracing::debug!(game = %g.debug_all());
 
-    Some(g)
+    Ok(g)
 }
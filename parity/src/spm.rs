@@ -3,7 +3,6 @@ use std::{
     fmt::Display,
 };
 
-use colored::Colorize;
 use itertools::Itertools;
 use petgraph::{graph::NodeIndex, EdgeDirection::Incoming};
 
@@ -118,17 +117,17 @@ impl Display for Measure {
 
 impl Graph {
     pub fn spm(&self) -> Solution {
-        log::info!("solving with SPM");
+        let _span = tracing::info_span!("spm").entered();
         if self.inner.node_count() == 0 {
             return Solution::empty();
         }
 
         let (w_0, w_1, s_0) = self.progress_measure(Owner::Even);
         let s_1 = if w_1.is_empty() {
-            log::info!("odd has no winning vertices, no need to recompute");
+            tracing::info!("odd has no winning vertices, no need to recompute");
             HashMap::new()
         } else {
-            log::info!(
+            tracing::info!(
                 "odd has a winning region, recomputing progress measure to determine strategy"
             );
             self.progress_measure(Owner::Odd).2
@@ -145,10 +144,10 @@ impl Graph {
         HashSet<NodeIndex>,
         HashMap<NodeIndex, NodeIndex>,
     ) {
-        log::info!("executing small progress measure for player {}", player);
+        let _span = tracing::info_span!("progress_measure", %player).entered();
         let measure_factory = MeasureFactory::new(self, player);
 
-        log::debug!(
+        tracing::debug!(
             "the maximum measure is <{}>",
             measure_factory
                 .max_measure
@@ -173,7 +172,7 @@ impl Graph {
         while let Some(v) = q.pop_front() {
             let lift = self.lift(player, &measures, v, &measure_factory.max_measure);
             if measures[&v] < lift {
-                log::debug!("{} {} to {}", "lifting".red(), self.debug_vertice(v), lift);
+                tracing::debug!(vertex = %self.debug_vertice(v), measure = %lift, "lifting");
                 measures.insert(v, lift);
                 for n in self.inner.neighbors_directed(v, Incoming) {
                     if !q.contains(&n) {
@@ -183,7 +182,7 @@ impl Graph {
             }
         }
 
-        log::debug!(
+        tracing::debug!(
             "final measures: {}",
             measures
                 .iter()
@@ -195,8 +194,11 @@ impl Graph {
             .node_indices()
             .partition(|v| !measures[&v].is_max);
 
-        log::debug!("w_alpha: {}", self.debug(&w_alpha));
-        log::debug!("w_beta: {}", self.debug(&w_beta));
+        tracing::debug!(
+            w_alpha = %self.debug(&w_alpha),
+            w_beta = %self.debug(&w_beta),
+            "regions"
+        );
 
         let sigma_alpha: HashMap<_, _> = w_alpha
             .iter()
@@ -219,9 +221,8 @@ impl Graph {
             })
             .collect();
 
-        log::debug!(
-            "{} for player {} {{{}}}",
-            "strategy calculated".bright_green(),
+        tracing::debug!(
+            "strategy calculated for player {} {{{}}}",
             player,
             sigma_alpha
                 .iter()
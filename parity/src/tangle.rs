@@ -1,5 +1,5 @@
-use log::debug;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use tracing::debug;
 
 use itertools::{Either, Itertools};
 use petgraph::{algo, graph::NodeIndex};
@@ -273,6 +273,7 @@ impl Graph {
     }
 
     pub fn tangle(&self) -> Solution {
+        let _span = tracing::info_span!("tangle").entered();
         let mut w_even = HashSet::new();
         let mut sigma_even = HashMap::new();
         let mut w_odd = HashSet::new();
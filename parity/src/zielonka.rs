@@ -1,6 +1,5 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
-use colored::Colorize;
 use itertools::Itertools;
 use petgraph::stable_graph::NodeIndex;
 
@@ -9,10 +8,10 @@ use crate::{Graph, Owner, Solution};
 impl Graph {
     fn attract(
         &self,
-        attractor: &HashSet<NodeIndex>,
+        attractor: &BTreeSet<NodeIndex>,
         player: Owner,
-        strategy: &HashMap<NodeIndex, NodeIndex>,
-    ) -> (HashSet<NodeIndex>, HashMap<NodeIndex, NodeIndex>) {
+        strategy: &BTreeMap<NodeIndex, NodeIndex>,
+    ) -> (BTreeSet<NodeIndex>, BTreeMap<NodeIndex, NodeIndex>) {
         let mut z = attractor.clone();
         let mut q: Vec<_> = z.iter().cloned().collect();
         let mut strategy = strategy.clone();
@@ -30,7 +29,7 @@ impl Graph {
                     q.push(u);
                 }
 
-                if z.intersection(&self.player_vertices(player).collect::<HashSet<_>>())
+                if z.intersection(&self.player_vertices(player).collect::<BTreeSet<_>>())
                     .contains(&u)
                     && !strategy.contains_key(&u)
                 {
@@ -39,42 +38,46 @@ impl Graph {
             }
         }
 
-        log::debug!(
-            "{} {} {} in subgraph {}",
-            self.debug(attractor),
-            "attracts".green(),
-            self.debug(&z),
-            self.debug_all()
+        tracing::debug!(
+            target = %self.debug(attractor),
+            attractor = %self.debug(&z),
+            subgraph = %self.debug_all(),
+            "attracted"
         );
         (z, strategy)
     }
 
     pub fn zielonka(&self) -> Solution {
-        log::info!("solving with zielonka's");
+        let _span = tracing::info_span!("zielonka").entered();
         if self.inner.node_count() == 0 {
             return Solution::empty();
         }
 
         let (w_0, w_1, s_0, s_1) = self.zielonka_r();
 
-        self.construct_solution(w_0, w_1, s_0, s_1)
+        self.construct_solution(
+            w_0.into_iter().collect(),
+            w_1.into_iter().collect(),
+            s_0.into_iter().collect(),
+            s_1.into_iter().collect(),
+        )
     }
 
     fn zielonka_r(
         &self,
     ) -> (
-        HashSet<NodeIndex>,
-        HashSet<NodeIndex>,
-        HashMap<NodeIndex, NodeIndex>,
-        HashMap<NodeIndex, NodeIndex>,
+        BTreeSet<NodeIndex>,
+        BTreeSet<NodeIndex>,
+        BTreeMap<NodeIndex, NodeIndex>,
+        BTreeMap<NodeIndex, NodeIndex>,
     ) {
-        log::debug!("applying zielonka's to graph {}", self.debug_all());
+        let _span = tracing::debug_span!("subgame", vertices = %self.debug_all()).entered();
         if self.inner.node_count() == 0 {
             return (
-                HashSet::new(),
-                HashSet::new(),
-                HashMap::new(),
-                HashMap::new(),
+                BTreeSet::new(),
+                BTreeSet::new(),
+                BTreeMap::new(),
+                BTreeMap::new(),
             );
         }
 
@@ -87,14 +90,14 @@ impl Graph {
             .inner
             .node_indices()
             .filter(|v| self.inner[*v].priority == highest_priority)
-            .collect::<HashSet<_>>();
+            .collect::<BTreeSet<_>>();
 
         // Calculate the attractor for the highest priority vertices
-        let (a, strat_a) = self.attract(&z, player_alpha, &HashMap::new());
+        let (a, strat_a) = self.attract(&z, player_alpha, &BTreeMap::new());
 
         // Recursively find out the winning areas in that subgraph
         let (mut w_even, mut w_odd, mut strat_even, mut strat_odd) =
-            self.remove_vertices(&a).zielonka_r();
+            self.remove_vertices_b_tree(&a).zielonka_r();
 
         let (strat_alpha, w_beta, strat_beta) = match player_alpha {
             Owner::Even => (&mut strat_even, &w_odd, &strat_odd),
@@ -104,22 +107,10 @@ impl Graph {
         let (b, strat_b) = self.attract(w_beta, player_beta, strat_beta);
 
         if b == *w_beta {
-            log::debug!(
-                "{}({}) {} {}",
-                "α".blue(),
-                player_alpha,
-                "wins".blue(),
-                self.debug(&a),
-            );
+            tracing::debug!(player = %player_alpha, region = %self.debug(&a), "alpha wins");
             let w_alpha = match player_alpha {
-                Owner::Even => {
-                    log::debug!("extending {} by {}", "W_even".blue(), self.debug(&a));
-                    &mut w_even
-                }
-                Owner::Odd => {
-                    log::debug!("extending {} by {}", "W_odd".red(), self.debug(&a));
-                    &mut w_odd
-                }
+                Owner::Even => &mut w_even,
+                Owner::Odd => &mut w_odd,
             };
             w_alpha.extend(a);
             strat_alpha.extend(strat_a);
@@ -138,31 +129,20 @@ impl Graph {
 
             (w_even, w_odd, strat_even, strat_odd)
         } else {
-            log::debug!(
-                "{}({}) {} {}",
-                "β".red(),
-                player_beta,
-                "wins".red(),
-                self.debug(&b),
-            );
+            tracing::debug!(player = %player_beta, region = %self.debug(&b), "beta wins");
             let (mut w_even, mut w_odd, mut strat_even, mut strat_odd) =
-                self.remove_vertices(&b).zielonka_r();
-            log::debug!(
-                "{} {} and {} with {} and {}",
-                "overwrote".magenta(),
-                "W_even".blue(),
-                "W_odd".red(),
-                self.debug(&w_even),
-                self.debug(&w_odd)
+                self.remove_vertices_b_tree(&b).zielonka_r();
+            tracing::debug!(
+                even = %self.debug(&w_even),
+                odd = %self.debug(&w_odd),
+                "regions of the subgame without beta's attractor"
             );
             let strat_beta = match player_beta {
                 Owner::Even => {
-                    log::debug!("extending {} by {}", "W_even".blue(), self.debug(&b));
                     w_even.extend(b);
                     &mut strat_even
                 }
                 Owner::Odd => {
-                    log::debug!("extending {} by {}", "W_odd".red(), self.debug(&b));
                     w_odd.extend(b);
                     &mut strat_odd
                 }
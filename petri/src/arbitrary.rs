@@ -0,0 +1,71 @@
+// Random place/transition nets for property based tests with proptest
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::PetriNet;
+
+/// Nets with between one and `places` places and at most `transitions` transitions, each
+/// place initially holds at most two tokens and arcs have weights of one or two. The nets may be
+/// unbounded, tests exploring them should bound the markings they visit.
+pub fn nets(places: usize, transitions: usize) -> BoxedStrategy<PetriNet> {
+    (1..=places.max(1))
+        .prop_flat_map(move |n| {
+            let arcs = || vec((0..n, 1..=2usize), 0..=2);
+            (vec(0..=2usize, n), vec((arcs(), arcs()), 0..=transitions))
+        })
+        .prop_map(|(places, transitions)| {
+            let mut net = PetriNet::new();
+            for (p, tokens) in places.into_iter().enumerate() {
+                net.add_place(format!("p{}", p), tokens).unwrap();
+            }
+            for (t, (inputs, outputs)) in transitions.into_iter().enumerate() {
+                let transition = format!("t{}", t);
+                net.add_transition(transition.clone()).unwrap();
+                for (p, weight) in inputs {
+                    net.add_arc(format!("p{}", p), transition.clone(), weight)
+                        .unwrap();
+                }
+                for (p, weight) in outputs {
+                    net.add_arc(transition.clone(), format!("p{}", p), weight)
+                        .unwrap();
+                }
+            }
+            net
+        })
+        .boxed()
+}
+
+impl Arbitrary for PetriNet {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<PetriNet>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        nets(4, 4)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::from_xml;
+
+    proptest! {
+        #[test]
+        fn round_trip(net: PetriNet) {
+            let pnml = net.to_pnml();
+            prop_assert_eq!(from_xml(&pnml).unwrap().to_pnml(), pnml);
+
+            // Firing changes the marking by the columns of the incidence matrices
+            let incidence = net.incidence();
+            let initial = net.initial_marking();
+            for (transition, next) in net.transitions(&initial).unwrap() {
+                let t = incidence.transitions.iter().position(|n| n == transition).unwrap();
+                for (p, place) in incidence.places.iter().enumerate() {
+                    let tokens = net.tokens(&initial, place).unwrap() + incidence.post[p][t];
+                    prop_assert_eq!(net.tokens(&next, place).unwrap(), tokens - incidence.pre[p][t]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,368 @@
+// Colored nets with finite color domains, unfolded into place/transition nets
+
+use std::collections::{BTreeSet, HashMap};
+
+use itertools::Itertools;
+
+use crate::{Error, PetriNet, Result};
+
+/// Color as the indices of its components in their basic sorts
+type Color = Vec<usize>;
+
+/// Expression denoting a multiset of colors, a single color counts once
+#[derive(Debug, Clone)]
+pub(crate) enum Term {
+    Add(Vec<Term>),
+    NumberOf(usize, Box<Term>),
+    /// Every color of the sort once
+    All(String),
+    Variable(String),
+    /// Color of a basic sort given by its index
+    Constant(usize, usize),
+    Tuple(Vec<Term>),
+    Successor(Box<Term>),
+    Predecessor(Box<Term>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Guard {
+    Constant(bool),
+    Equality(Term, Term),
+    Inequality(Term, Term),
+    And(Vec<Guard>),
+    Or(Vec<Guard>),
+    Not(Box<Guard>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ColoredPlace {
+    pub(crate) label: String,
+    pub(crate) sort: String,
+    pub(crate) initial_marking: Option<Term>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ColoredTransition {
+    pub(crate) label: String,
+    pub(crate) guard: Guard,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ColoredArc {
+    pub(crate) source: String,
+    pub(crate) target: String,
+    pub(crate) inscription: Term,
+}
+
+/// Net whose places hold colored tokens of their sort, with arcs inscribed with multisets of colors
+/// built from the variables of the transition and transitions guarded by conditions on them
+#[derive(Debug, Clone, Default)]
+pub struct ColoredNet {
+    /// Names of the colors of the sorts which cannot be split further
+    pub(crate) basic_sorts: Vec<Vec<String>>,
+    /// Sorts with the basic sorts of their components
+    pub(crate) sorts: HashMap<String, Vec<usize>>,
+    /// Variables with their sorts
+    pub(crate) variables: HashMap<String, String>,
+    pub(crate) places: Vec<ColoredPlace>,
+    pub(crate) transitions: Vec<ColoredTransition>,
+    pub(crate) arcs: Vec<ColoredArc>,
+}
+
+/// Values of the variables of a transition
+type Binding = HashMap<String, Color>;
+
+impl ColoredNet {
+    /// Number of places and transitions of the unfolded net, before unfolding it
+    pub fn unfolded_size(&self) -> Result<(usize, usize)> {
+        let places = self
+            .places
+            .iter()
+            .map(|p| Ok(self.colors(&p.sort)?.len()))
+            .sum::<Result<usize>>()?;
+        let transitions = self
+            .transitions
+            .iter()
+            .map(|t| Ok(self.bindings(t)?.len()))
+            .sum::<Result<usize>>()?;
+        Ok((places, transitions))
+    }
+
+    /// The place/transition net with a place for every color and a transition for every
+    /// binding satisfying the guard. These are labeled with the colors and values of the variables
+    /// appended with underscores, places of the dot sort and transitions without variables keep
+    /// their labels.
+    pub fn unfold(&self) -> Result<PetriNet> {
+        let mut net = PetriNet::new();
+        for place in &self.places {
+            let marking = match &place.initial_marking {
+                Some(term) => self.evaluate(term, &Binding::new())?,
+                None => vec![],
+            };
+            for color in self.colors(&place.sort)? {
+                let count = marking
+                    .iter()
+                    .filter(|(c, _)| *c == color)
+                    .map(|(_, n)| n)
+                    .sum();
+                net.add_place(self.unfolded_place(place, &color)?, count)?;
+            }
+        }
+
+        let places: HashMap<&str, &ColoredPlace> =
+            self.places.iter().map(|p| (p.label.as_str(), p)).collect();
+        for transition in &self.transitions {
+            let inputs = self.arcs.iter().filter(|a| a.target == transition.label);
+            let outputs = self.arcs.iter().filter(|a| a.source == transition.label);
+            let variables = self.variables_of(transition);
+            for binding in self.bindings(transition)? {
+                let label = if variables.is_empty() {
+                    transition.label.clone()
+                } else {
+                    let values = variables
+                        .iter()
+                        .map(|v| self.color_name(&binding[*v], &self.variables[*v]))
+                        .collect::<Result<Vec<_>>>()?;
+                    format!("{}_{}", transition.label, values.join("_"))
+                };
+                net.add_transition(label.clone())?;
+                for (arc, input) in inputs
+                    .clone()
+                    .map(|a| (a, true))
+                    .chain(outputs.clone().map(|a| (a, false)))
+                {
+                    let place_label = if input { &arc.source } else { &arc.target };
+                    let place = places
+                        .get(place_label.as_str())
+                        .ok_or_else(|| Error::UnknownPlace(place_label.clone()))?;
+                    let multiset = self.evaluate(&arc.inscription, &binding)?;
+                    for (color, count) in multiset.into_iter().filter(|(_, n)| *n > 0) {
+                        let unfolded = self.unfolded_place(place, &color)?;
+                        if input {
+                            net.add_arc(unfolded, label.clone(), count)?;
+                        } else {
+                            net.add_arc(label.clone(), unfolded, count)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(net)
+    }
+
+    fn unfolded_place(&self, place: &ColoredPlace, color: &Color) -> Result<String> {
+        Ok(if place.sort == "dot" {
+            place.label.clone()
+        } else {
+            format!("{}_{}", place.label, self.color_name(color, &place.sort)?)
+        })
+    }
+
+    /// Names of the components of the color of the sort joined by underscores
+    fn color_name(&self, color: &Color, sort: &str) -> Result<String> {
+        Ok(color
+            .iter()
+            .zip(self.sort(sort)?)
+            .map(|(index, basic)| self.basic_sorts[*basic][*index].as_str())
+            .join("_"))
+    }
+
+    fn sort(&self, sort: &str) -> Result<&Vec<usize>> {
+        self.sorts
+            .get(sort)
+            .ok_or_else(|| Error::Colored(format!("unknown sort '{}'", sort)))
+    }
+
+    /// Every color of the sort
+    fn colors(&self, sort: &str) -> Result<Vec<Color>> {
+        let domains = self
+            .sort(sort)?
+            .iter()
+            .map(|basic| (0..self.basic_sorts[*basic].len()).collect());
+        Ok(product(domains.collect()))
+    }
+
+    /// Variables occurring in the guard or arcs of the transition, in order of their names
+    fn variables_of<'a>(&'a self, transition: &'a ColoredTransition) -> Vec<&'a str> {
+        let mut variables = BTreeSet::new();
+        guard_variables(&transition.guard, &mut variables);
+        for arc in &self.arcs {
+            if arc.source == transition.label || arc.target == transition.label {
+                term_variables(&arc.inscription, &mut variables);
+            }
+        }
+        variables.into_iter().collect()
+    }
+
+    /// Bindings of the variables of the transition which satisfy its guard
+    fn bindings(&self, transition: &ColoredTransition) -> Result<Vec<Binding>> {
+        let variables = self.variables_of(transition);
+        let domains = variables
+            .iter()
+            .map(|v| {
+                let sort = self
+                    .variables
+                    .get(*v)
+                    .ok_or_else(|| Error::Colored(format!("unknown variable '{}'", v)))?;
+                self.colors(sort)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut bindings = vec![];
+        for values in product(domains) {
+            let binding: Binding = variables
+                .iter()
+                .map(|v| v.to_string())
+                .zip(values)
+                .collect();
+            if self.satisfies(&transition.guard, &binding)? {
+                bindings.push(binding);
+            }
+        }
+        Ok(bindings)
+    }
+
+    fn satisfies(&self, guard: &Guard, binding: &Binding) -> Result<bool> {
+        Ok(match guard {
+            Guard::Constant(value) => *value,
+            Guard::Equality(lhs, rhs) => self.color(lhs, binding)? == self.color(rhs, binding)?,
+            Guard::Inequality(lhs, rhs) => self.color(lhs, binding)? != self.color(rhs, binding)?,
+            Guard::And(guards) => {
+                for guard in guards {
+                    if !self.satisfies(guard, binding)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            Guard::Or(guards) => {
+                for guard in guards {
+                    if self.satisfies(guard, binding)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            Guard::Not(guard) => !self.satisfies(guard, binding)?,
+        })
+    }
+
+    /// Colors with their counts in the multiset denoted by the term
+    fn evaluate(&self, term: &Term, binding: &Binding) -> Result<Vec<(Color, usize)>> {
+        Ok(match term {
+            Term::Add(terms) => {
+                let mut sum: Vec<(Color, usize)> = vec![];
+                for term in terms {
+                    for (color, count) in self.evaluate(term, binding)? {
+                        match sum.iter_mut().find(|(c, _)| *c == color) {
+                            Some((_, n)) => *n += count,
+                            None => sum.push((color, count)),
+                        }
+                    }
+                }
+                sum
+            }
+            Term::NumberOf(n, term) => self
+                .evaluate(term, binding)?
+                .into_iter()
+                .map(|(color, count)| (color, count * n))
+                .collect(),
+            Term::All(sort) => self.colors(sort)?.into_iter().map(|c| (c, 1)).collect(),
+            _ => vec![(self.color(term, binding)?.0, 1)],
+        })
+    }
+
+    /// Next or previous color of a basic sort, the first color follows the last one
+    fn shift(&self, term: &Term, binding: &Binding, by: isize) -> Result<(Color, Vec<usize>)> {
+        let (color, sorts) = self.color(term, binding)?;
+        let ([index], [sort]) = (color.as_slice(), sorts.as_slice()) else {
+            return Err(Error::Colored(
+                "successors are only defined for basic sorts".into(),
+            ));
+        };
+        let size = self.basic_sorts[*sort].len() as isize;
+        let index = (*index as isize + by).rem_euclid(size) as usize;
+        Ok((vec![index], sorts))
+    }
+
+    /// The single color denoted by the term, with the basic sorts of its components
+    fn color(&self, term: &Term, binding: &Binding) -> Result<(Color, Vec<usize>)> {
+        Ok(match term {
+            Term::Variable(v) => {
+                let color = binding
+                    .get(v)
+                    .ok_or_else(|| Error::Colored(format!("unbound variable '{}'", v)))?;
+                let sort = self.sort(&self.variables[v])?;
+                (color.clone(), sort.clone())
+            }
+            Term::Constant(sort, index) => (vec![*index], vec![*sort]),
+            Term::Tuple(terms) => {
+                let mut color = vec![];
+                let mut sorts = vec![];
+                for term in terms {
+                    let (c, s) = self.color(term, binding)?;
+                    color.extend(c);
+                    sorts.extend(s);
+                }
+                (color, sorts)
+            }
+            Term::Successor(term) => self.shift(term, binding, 1)?,
+            Term::Predecessor(term) => self.shift(term, binding, -1)?,
+            _ => {
+                return Err(Error::Colored(
+                    "expected a single color, found a multiset".into(),
+                ))
+            }
+        })
+    }
+}
+
+/// Every combination of one value of each domain, a single empty one without domains
+fn product<T: Clone>(domains: Vec<Vec<T>>) -> Vec<Vec<T>> {
+    domains
+        .into_iter()
+        .fold(vec![vec![]], |combinations, domain| {
+            combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    domain.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.push(value.clone());
+                        combination
+                    })
+                })
+                .collect()
+        })
+}
+
+fn term_variables<'a>(term: &'a Term, variables: &mut BTreeSet<&'a str>) {
+    match term {
+        Term::Variable(v) => {
+            variables.insert(v);
+        }
+        Term::Add(terms) | Term::Tuple(terms) => {
+            for term in terms {
+                term_variables(term, variables);
+            }
+        }
+        Term::NumberOf(_, term) | Term::Successor(term) | Term::Predecessor(term) => {
+            term_variables(term, variables)
+        }
+        Term::All(_) | Term::Constant(..) => {}
+    }
+}
+
+fn guard_variables<'a>(guard: &'a Guard, variables: &mut BTreeSet<&'a str>) {
+    match guard {
+        Guard::Constant(_) => {}
+        Guard::Equality(lhs, rhs) | Guard::Inequality(lhs, rhs) => {
+            term_variables(lhs, variables);
+            term_variables(rhs, variables);
+        }
+        Guard::And(guards) | Guard::Or(guards) => {
+            for guard in guards {
+                guard_variables(guard, variables);
+            }
+        }
+        Guard::Not(guard) => guard_variables(guard, variables),
+    }
+}
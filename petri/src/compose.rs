@@ -0,0 +1,72 @@
+// Parallel composition of nets
+
+use crate::{Error, PetriNet, Result, Transition};
+
+impl PetriNet {
+    /// Compose the nets, synchronizing the transitions with the given labels: these occur in both
+    /// nets and are merged into one transition which fires for both. Places and unsynchronized
+    /// transitions need distinct labels in both nets. The composed net is only asserted to be
+    /// safe if both nets are.
+    pub fn compose(&self, other: &PetriNet, sync_labels: &[&str]) -> Result<PetriNet> {
+        for label in sync_labels {
+            for net in [self, other] {
+                if !net.has_transition(label) {
+                    return Err(Error::UnknownTransition(label.to_string()));
+                }
+            }
+        }
+        let mut net = PetriNet::new();
+        net.safe = self.safe && other.safe;
+        for place in self.places.iter().chain(&other.places) {
+            net.add_place(place.label.clone(), place.initial_marking)?;
+        }
+
+        // Places of the other net follow those of this one
+        let offset = self.places.len();
+        let shift = |arcs: &[(usize, usize)]| -> Vec<(usize, usize)> {
+            arcs.iter().map(|(p, w)| (p + offset, *w)).collect()
+        };
+        let mut transitions: Vec<Transition> = vec![];
+        for transition in &self.transitions {
+            let mut composed = Transition {
+                label: transition.label.clone(),
+                inputs: transition.inputs.clone(),
+                outputs: transition.outputs.clone(),
+                rate: transition.rate,
+            };
+            if sync_labels.contains(&transition.label.as_str()) {
+                let partner = &other.transitions[*other
+                    .transition_labels
+                    .get_by_left(&transition.label)
+                    .ok_or(Error::InvalidIndex)?];
+                composed.inputs.extend(shift(&partner.inputs));
+                composed.outputs.extend(shift(&partner.outputs));
+                // The slower partner determines when both fire together
+                composed.rate = match (transition.rate, partner.rate) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (rate, None) | (None, rate) => rate,
+                };
+            }
+            transitions.push(composed);
+        }
+        for transition in &other.transitions {
+            if !sync_labels.contains(&transition.label.as_str()) {
+                transitions.push(Transition {
+                    label: transition.label.clone(),
+                    inputs: shift(&transition.inputs),
+                    outputs: shift(&transition.outputs),
+                    rate: transition.rate,
+                });
+            }
+        }
+        for transition in transitions {
+            if net.transition_labels.contains_left(&transition.label) {
+                return Err(Error::DuplicateTransition(transition.label));
+            }
+            net.transition_labels
+                .insert(transition.label.clone(), net.transitions.len());
+            net.transitions.push(transition);
+        }
+        Ok(net)
+    }
+}
@@ -0,0 +1,121 @@
+// Coverability by the Karp-Miller construction
+
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use crate::{Error, PetriNet, Result};
+
+/// Tokens per place, `None` stands for ω
+type OmegaMarking = Vec<Option<usize>>;
+
+/// Whether `larger` holds at least as many tokens as `smaller` in every place
+fn covers(larger: &OmegaMarking, smaller: &OmegaMarking) -> bool {
+    larger.iter().zip(smaller).all(|(l, s)| match (l, s) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(l), Some(s)) => l >= s,
+    })
+}
+
+/// Most tokens a place holds in any reachable marking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Finite(usize),
+    Unbounded,
+}
+
+impl Display for Bound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bound::Finite(tokens) => write!(f, "{}", tokens),
+            Bound::Unbounded => write!(f, "unbounded"),
+        }
+    }
+}
+
+impl PetriNet {
+    /// Bound of every place, also for nets with infinitely many reachable markings. The finite
+    /// counts of the ω-markings are reached exactly, so the largest one of a place is its bound.
+    pub fn bounds(&self) -> Vec<(&str, Bound)> {
+        let markings = self.karp_miller();
+        self.places
+            .iter()
+            .enumerate()
+            .map(|(index, place)| {
+                let bound = markings
+                    .iter()
+                    .map(|m| m[index])
+                    .try_fold(0, |max, tokens| tokens.map(|t| max.max(t)))
+                    .map_or(Bound::Unbounded, Bound::Finite);
+                (place.label.as_str(), bound)
+            })
+            .collect()
+    }
+
+    /// Check whether a reachable marking holds at least the given number of tokens in each of the
+    /// places
+    pub fn coverable(&self, query: &[(&str, usize)]) -> Result<bool> {
+        let mut target = vec![Some(0); self.places.len()];
+        for (place, tokens) in query {
+            let index = self
+                .place_labels
+                .get(*place)
+                .ok_or_else(|| Error::UnknownPlace(place.to_string()))?;
+            target[*index] = target[*index].max(Some(*tokens));
+        }
+        Ok(self
+            .karp_miller()
+            .iter()
+            .any(|marking| covers(marking, &target)))
+    }
+
+    /// The ω-markings of the Karp-Miller tree, nodes with the same ω-marking are only expanded once
+    ///
+    /// A marking which strictly covers one of its ancestors can repeat the transitions in between
+    /// forever, so the places which grew are set to ω. The construction terminates and a marking
+    /// is coverable iff some ω-marking covers it.
+    fn karp_miller(&self) -> HashSet<OmegaMarking> {
+        let initial: OmegaMarking = self
+            .places
+            .iter()
+            .map(|p| Some(p.initial_marking))
+            .collect();
+        let mut visited = HashSet::from([initial.clone()]);
+        // Markings with their ancestors in the tree
+        let mut stack = vec![(initial, vec![])];
+        while let Some((marking, ancestors)) = stack.pop() {
+            let mut path: Vec<OmegaMarking> = ancestors;
+            path.push(marking.clone());
+            for transition in &self.transitions {
+                let enabled = transition
+                    .inputs
+                    .iter()
+                    .all(|(place, weight)| marking[*place].is_none_or(|t| t >= *weight));
+                if !enabled {
+                    continue;
+                }
+                let mut next = marking.clone();
+                for (place, weight) in &transition.inputs {
+                    next[*place] = next[*place].map(|t| t - weight);
+                }
+                for (place, weight) in &transition.outputs {
+                    next[*place] = next[*place].and_then(|t| t.checked_add(*weight));
+                }
+                // Accelerate the places which grew since a covered ancestor
+                for ancestor in &path {
+                    if covers(&next, ancestor) {
+                        for (tokens, old) in next.iter_mut().zip(ancestor) {
+                            if *tokens != *old {
+                                *tokens = None;
+                            }
+                        }
+                    }
+                }
+                if visited.insert(next.clone()) {
+                    stack.push((next, path.clone()));
+                }
+            }
+        }
+        visited
+    }
+}
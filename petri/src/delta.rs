@@ -0,0 +1,109 @@
+// Changes between two versions of a net
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::{PetriNet, Transition};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    AddedPlace(String),
+    RemovedPlace(String),
+    InitialMarking {
+        place: String,
+        before: usize,
+        after: usize,
+    },
+    AddedTransition(String),
+    RemovedTransition(String),
+    /// The places a transition consumes from or produces in, or the weights of its arcs
+    Arcs(String),
+    Rate(String),
+}
+
+impl Change {
+    /// Whether the change may alter the runs of the net and with them the verdicts of LTL
+    /// properties, which only changed rates do not as they only matter for the Markov chain
+    pub fn alters_runs(&self) -> bool {
+        !matches!(self, Change::Rate(_))
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::AddedPlace(place) => write!(f, "added place '{}'", place),
+            Change::RemovedPlace(place) => write!(f, "removed place '{}'", place),
+            Change::InitialMarking {
+                place,
+                before,
+                after,
+            } => write!(
+                f,
+                "changed the initial tokens of '{}' from {} to {}",
+                place, before, after
+            ),
+            Change::AddedTransition(t) => write!(f, "added transition '{}'", t),
+            Change::RemovedTransition(t) => write!(f, "removed transition '{}'", t),
+            Change::Arcs(t) => write!(f, "changed the arcs of transition '{}'", t),
+            Change::Rate(t) => write!(f, "changed the rate of transition '{}'", t),
+        }
+    }
+}
+
+impl PetriNet {
+    /// Changes turning the previous version of the net into this one, places before transitions,
+    /// each in the order of this net followed by the removed ones. Places and transitions are
+    /// matched by their names, so reordering them or editing the layout of a PNML file changes
+    /// nothing.
+    pub fn changes(&self, previous: &PetriNet) -> Vec<Change> {
+        let mut changes = vec![];
+        for place in &self.places {
+            let Some(p) = previous.place_labels.get(&place.label) else {
+                changes.push(Change::AddedPlace(place.label.clone()));
+                continue;
+            };
+            let before = previous.places[*p].initial_marking;
+            if before != place.initial_marking {
+                changes.push(Change::InitialMarking {
+                    place: place.label.clone(),
+                    before,
+                    after: place.initial_marking,
+                });
+            }
+        }
+        for place in &previous.places {
+            if !self.place_labels.contains_key(&place.label) {
+                changes.push(Change::RemovedPlace(place.label.clone()));
+            }
+        }
+
+        for transition in &self.transitions {
+            let Some(t) = previous.transition_labels.get_by_left(&transition.label) else {
+                changes.push(Change::AddedTransition(transition.label.clone()));
+                continue;
+            };
+            let before = &previous.transitions[*t];
+            if previous.arcs(before) != self.arcs(transition) {
+                changes.push(Change::Arcs(transition.label.clone()));
+            } else if before.rate != transition.rate {
+                changes.push(Change::Rate(transition.label.clone()));
+            }
+        }
+        for transition in &previous.transitions {
+            if !self.transition_labels.contains_left(&transition.label) {
+                changes.push(Change::RemovedTransition(transition.label.clone()));
+            }
+        }
+        changes
+    }
+
+    /// Weights of the input and output arcs of the transition by the names of their places
+    fn arcs(&self, transition: &Transition) -> [BTreeMap<&str, usize>; 2] {
+        [&transition.inputs, &transition.outputs].map(|arcs| {
+            (arcs.iter())
+                .map(|(place, weight)| (self.places[*place].label.as_str(), *weight))
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,223 @@
+// Breadth first exploration with the visited markings on disk
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{Marking, PetriNet, Result};
+
+/// Result of the exploration on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskSummary {
+    /// Number of reachable markings
+    pub markings: usize,
+    /// Number of reachable markings in which no transition is enabled
+    pub deadlocks: usize,
+    /// Number of breadth first layers
+    pub layers: usize,
+    /// Bytes written to disk in total
+    pub written: u64,
+}
+
+impl PetriNet {
+    /// Explore the reachable markings keeping at most `memory` bytes of successors in memory,
+    /// spilling the rest to files in a new directory below `directory`, which is removed again
+    pub fn disk_analysis(&self, directory: &Path, memory: usize) -> Result<DiskSummary> {
+        let work = directory.join(format!("lmc-{}", std::process::id()));
+        fs::create_dir_all(&work)?;
+        let result = Explorer {
+            net: self,
+            work: work.clone(),
+            record: 2 * self.places.len(),
+            capacity: (memory / (2 * self.places.len()).max(1)).max(1),
+            files: 0,
+            written: 0,
+        }
+        .explore();
+        fs::remove_dir_all(&work)?;
+        result
+    }
+}
+
+struct Explorer<'a> {
+    net: &'a PetriNet,
+    work: PathBuf,
+    /// Bytes per marking
+    record: usize,
+    /// Successors buffered before writing a run
+    capacity: usize,
+    /// Files created so far, used to name the next one
+    files: usize,
+    written: u64,
+}
+
+
+impl Explorer<'_> {
+    /// Breadth first exploration expanding the markings of a layer one after another. Whenever
+    /// the buffer of successors is full it is sorted, deduplicated and written to a run file. At
+    /// the end of the layer the runs are merged with the sorted file of visited markings in a
+    /// single pass, which yields both the new visited file and the next layer.
+    fn explore(&mut self) -> Result<DiskSummary> {
+        let initial = vec![encode(&self.net.initial_marking())];
+        let mut visited = self.write(initial.clone())?;
+        let mut frontier = self.write(initial)?;
+        let mut markings = 1;
+        let mut deadlocks = 0;
+        let mut layers = 0;
+        while markings > 0 {
+            layers += 1;
+            let mut runs = vec![];
+            let mut buffer = vec![];
+            let mut reader = Records::open(&frontier, self.record)?;
+            while let Some(record) = reader.next()? {
+                let successors = self.net.next_markings(&decode(&record))?;
+                if successors.is_empty() {
+                    deadlocks += 1;
+                }
+                buffer.extend(successors.iter().map(encode));
+                if buffer.len() >= self.capacity {
+                    runs.push(self.write(std::mem::take(&mut buffer))?);
+                }
+            }
+            runs.push(self.write(buffer)?);
+            fs::remove_file(&frontier)?;
+
+            let (next_visited, next_frontier, new) = self.merge(&visited, &runs)?;
+            for run in runs {
+                fs::remove_file(run)?;
+            }
+            fs::remove_file(&visited)?;
+            visited = next_visited;
+            frontier = next_frontier;
+            markings = new;
+        }
+        let total = fs::metadata(&visited)?.len() as usize / self.record.max(1);
+        Ok(DiskSummary {
+            // Nets without places have a single marking of no bytes
+            markings: if self.record == 0 { 1 } else { total },
+            deadlocks,
+            layers,
+            written: self.written,
+        })
+    }
+
+    /// Sort and deduplicate the records and write them to a new file
+    fn write(&mut self, mut records: Vec<Vec<u8>>) -> Result<PathBuf> {
+        records.sort_unstable();
+        records.dedup();
+        let (path, mut writer) = self.create()?;
+        for record in records {
+            writer.write_all(&record)?;
+        }
+        self.finish(&path, writer)?;
+        Ok(path)
+    }
+
+    fn create(&mut self) -> Result<(PathBuf, BufWriter<File>)> {
+        let path = self.work.join(format!("{}.bin", self.files));
+        self.files += 1;
+        let writer = BufWriter::new(File::create(&path)?);
+        Ok((path, writer))
+    }
+
+    fn finish(&mut self, path: &Path, mut writer: BufWriter<File>) -> Result<()> {
+        writer.flush()?;
+        self.written += fs::metadata(path)?.len();
+        Ok(())
+    }
+
+    /// Merge the sorted runs with the visited markings, giving the files of all visited
+    /// markings and of the new ones with the number of new ones
+    fn merge(&mut self, visited: &Path, runs: &[PathBuf]) -> Result<(PathBuf, PathBuf, usize)> {
+        let mut heap = BinaryHeap::new();
+        let mut readers = vec![];
+        for (index, run) in runs.iter().enumerate() {
+            let mut reader = Records::open(run, self.record)?;
+            if let Some(record) = reader.next()? {
+                heap.push(Reverse((record, index)));
+            }
+            readers.push(reader);
+        }
+        let mut old = Records::open(visited, self.record)?;
+        let mut next_old = old.next()?;
+
+        let (visited_path, mut visited_writer) = self.create()?;
+        let (frontier_path, mut frontier_writer) = self.create()?;
+        let mut new = 0;
+        let mut last: Option<Vec<u8>> = None;
+        while let Some(Reverse((record, index))) = heap.pop() {
+            if let Some(next) = readers[index].next()? {
+                heap.push(Reverse((next, index)));
+            }
+            if last.as_ref() == Some(&record) {
+                continue;
+            }
+            // Visited markings before the candidate are copied over
+            while let Some(old_record) = next_old.take() {
+                if old_record < record {
+                    visited_writer.write_all(&old_record)?;
+                    next_old = old.next()?;
+                } else {
+                    next_old = Some(old_record);
+                    break;
+                }
+            }
+            if next_old.as_ref() != Some(&record) {
+                frontier_writer.write_all(&record)?;
+                visited_writer.write_all(&record)?;
+                new += 1;
+            }
+            last = Some(record);
+        }
+        while let Some(old_record) = next_old {
+            visited_writer.write_all(&old_record)?;
+            next_old = old.next()?;
+        }
+        self.finish(&visited_path, visited_writer)?;
+        self.finish(&frontier_path, frontier_writer)?;
+        Ok((visited_path, frontier_path, new))
+    }
+}
+
+/// Fixed size records read from a file
+struct Records {
+    reader: BufReader<File>,
+    record: usize,
+}
+
+impl Records {
+    fn open(path: &Path, record: usize) -> Result<Records> {
+        Ok(Records {
+            reader: BufReader::new(File::open(path)?),
+            record,
+        })
+    }
+
+    fn next(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut record = vec![0; self.record];
+        match self.reader.read_exact(&mut record) {
+            Ok(()) if self.record > 0 => Ok(Some(record)),
+            Ok(()) => Ok(None),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Two bytes per place, big endian so that sorting the records as bytes is consistent between runs
+fn encode(marking: &Marking) -> Vec<u8> {
+    (0..marking.len())
+        .flat_map(|p| (marking.count(p) as u16).to_be_bytes())
+        .collect()
+}
+
+fn decode(record: &[u8]) -> Marking {
+    Marking::pack(
+        record
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]) as usize)
+            .collect(),
+    )
+}
@@ -0,0 +1,112 @@
+// Graphviz output of nets and their reachability graphs, meant for small models
+
+use std::fmt::Write;
+
+use crate::{MarkingStore, PetriNet, Result};
+
+impl PetriNet {
+    /// The net in DOT format, places are circles with their initial tokens and transitions boxes
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(&mut out, "digraph g {{\nmindist = 2.0").unwrap();
+        for (index, place) in self.places.iter().enumerate() {
+            let tokens = match place.initial_marking {
+                0 => String::new(),
+                tokens => format!("\\n{}", tokens),
+            };
+            writeln!(
+                &mut out,
+                "p{} [label = \"{}{}\", shape = circle]",
+                index,
+                escape(&place.label),
+                tokens
+            )
+            .unwrap();
+        }
+        for (index, transition) in self.transitions.iter().enumerate() {
+            writeln!(
+                &mut out,
+                "t{} [label = \"{}\", shape = box]",
+                index,
+                escape(&transition.label)
+            )
+            .unwrap();
+            let edges = transition
+                .inputs
+                .iter()
+                .map(|(place, weight)| (format!("p{}", place), format!("t{}", index), *weight))
+                .chain(transition.outputs.iter().map(|(place, weight)| {
+                    (format!("t{}", index), format!("p{}", place), *weight)
+                }));
+            for (source, target, weight) in edges {
+                write!(&mut out, "{} -> {}", source, target).unwrap();
+                if weight != 1 {
+                    write!(&mut out, " [label = \"{}\"]", weight).unwrap();
+                }
+                writeln!(&mut out).unwrap();
+            }
+        }
+        out.push('}');
+        out.push('\n');
+        out
+    }
+
+    /// The reachability graph in DOT format with at most `limit` markings, explored breadth
+    /// first. Deadlocks are drawn with a double border and markings whose successors were cut
+    /// off by the limit with a dashed one.
+    pub fn reachability_graph_dot(&self, limit: usize) -> Result<String> {
+        let mut out = String::new();
+        writeln!(&mut out, "digraph g {{\nmindist = 2.0").unwrap();
+        let mut store = MarkingStore::new();
+        if limit > 0 {
+            store.insert(&self.initial_marking());
+        }
+        let mut next = 0;
+        while next < store.len() {
+            let marking = store.get(next);
+            let mut truncated = false;
+            let successors = marking.next(self)?;
+            for (transition, successor) in &successors {
+                if store.len() >= limit && !store.contains(successor) {
+                    truncated = true;
+                    continue;
+                }
+                let (target, _) = store.insert(successor);
+                writeln!(
+                    &mut out,
+                    "m{} -> m{} [label = \"{}\"]",
+                    next,
+                    target,
+                    escape(transition)
+                )
+                .unwrap();
+            }
+            let style = if successors.is_empty() {
+                ", peripheries = 2"
+            } else if truncated {
+                ", style = dashed"
+            } else {
+                ""
+            };
+            writeln!(
+                &mut out,
+                "m{} [label = \"{}\"{}]",
+                next,
+                escape(&self.marking_label(&marking)),
+                style
+            )
+            .unwrap();
+            next += 1;
+        }
+        if !store.is_empty() {
+            writeln!(&mut out, "init [label=\"\", shape=point]\ninit -> m0").unwrap();
+        }
+        out.push('}');
+        out.push('\n');
+        Ok(out)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
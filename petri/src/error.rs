@@ -12,6 +12,38 @@ pub enum Error {
     DuplicateTransition(String),
     #[error("cannot create arc from '{0}' to '{1}'")]
     InvalidArc(String, String),
+    #[error("arc from '{0}' to '{1}' has weight 0")]
+    InvalidWeight(String, String),
+    #[error("unknown place '{0}'")]
+    UnknownPlace(String),
+    #[error("unknown transition '{0}'")]
+    UnknownTransition(String),
+    #[error("'{0}' is neither a place nor a transition")]
+    UnknownNode(String),
+    #[error("transition '{0}' is not enabled")]
+    NotEnabled(String),
+    #[error("transition '{0}' has a rate which is not positive")]
+    InvalidRate(String),
+    #[error("place '{0}' exceeds the maximum number of tokens")]
+    Overflow(String),
+    #[error("place '{0}' is unbounded")]
+    Unbounded(String),
+    #[error("place '{0}' holds more than one token in a net asserted to be safe")]
+    Unsafe(String),
+    #[error("could not start threads: {0}")]
+    ThreadPool(String),
+    #[error("invalid PNML net in line {line}: {reason}")]
+    Pnml { line: usize, reason: String },
+    #[error("invalid TINA net in line {line}: {reason}")]
+    Tina { line: usize, reason: String },
+    #[error("invalid LoLA net in line {line}: {reason}")]
+    Lola { line: usize, reason: String },
+    #[error("invalid colored net: {0}")]
+    Colored(String),
+    #[error("invalid query: {0}")]
+    Query(String),
+    #[error("exploration stopped, {0}")]
+    Stopped(ts::Stop),
     #[error("invalid index")]
     InvalidIndex,
     #[error("could not parse xml petri net")]
@@ -0,0 +1,335 @@
+// Incidence matrices and the state equation
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::{Marking, PetriNet};
+
+/// Tokens consumed and produced by the transitions, indexed by place then transition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incidence {
+    pub places: Vec<String>,
+    pub transitions: Vec<String>,
+    pub pre: Vec<Vec<usize>>,
+    pub post: Vec<Vec<usize>>,
+}
+
+/// Most rows of the Farkas algorithm per place before the semiflows are given up on
+const SEMIFLOW_ROWS: usize = 8;
+
+/// Changes by the transitions and weights of the places of a row of the Farkas algorithm
+type Row = (BTreeMap<usize, i64>, BTreeMap<usize, i64>);
+
+impl Incidence {
+    /// Change of the tokens in each place by firing each transition
+    pub fn matrix(&self) -> Vec<Vec<i64>> {
+        self.pre
+            .iter()
+            .zip(&self.post)
+            .map(|(pre, post)| {
+                pre.iter()
+                    .zip(post)
+                    .map(|(consumed, produced)| *produced as i64 - *consumed as i64)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Semiflows of the net, nonnegative weights of places whose weighted sum of tokens no
+    /// transition changes, as the places they cover with their weights. `None` if computing them
+    /// takes too many rows.
+    ///
+    /// The Farkas algorithm starts with a row per place, its changes by the transitions and a
+    /// weight of one for itself. Transitions are eliminated one after another by replacing the
+    /// rows they change with the sums of every row they increase and every row they decrease,
+    /// scaled such that the changes cancel out. The weights of the rows left are the semiflows.
+    pub fn semiflows(&self) -> Option<Vec<Vec<(usize, u64)>>> {
+        let limit = SEMIFLOW_ROWS * self.places.len();
+        let mut rows: Vec<Row> = (self.matrix().into_iter().enumerate())
+            .map(|(p, changes)| {
+                let changes = (changes.into_iter().enumerate())
+                    .filter(|(_, change)| *change != 0)
+                    .collect();
+                (changes, BTreeMap::from([(p, 1)]))
+            })
+            .collect();
+        for t in 0..self.transitions.len() {
+            let (changing, mut kept): (Vec<_>, Vec<_>) = rows
+                .into_iter()
+                .partition(|(changes, _)| changes.contains_key(&t));
+            let (increasing, decreasing): (Vec<_>, Vec<_>) =
+                changing.iter().partition(|(changes, _)| changes[&t] > 0);
+            for a in &increasing {
+                for b in &decreasing {
+                    kept.push(cancel(a, b, t)?);
+                    if kept.len() > limit {
+                        return None;
+                    }
+                }
+            }
+            rows = kept;
+        }
+        let semiflows = rows.into_iter().map(|(_, weights)| {
+            (weights.into_iter())
+                .map(|(p, weight)| (p, weight as u64))
+                .collect()
+        });
+        Some(semiflows.collect())
+    }
+}
+
+impl PetriNet {
+    pub fn incidence(&self) -> Incidence {
+        let mut pre = vec![vec![0; self.transitions.len()]; self.places.len()];
+        let mut post = pre.clone();
+        for (t, transition) in self.transitions.iter().enumerate() {
+            for (place, weight) in &transition.inputs {
+                pre[*place][t] += weight;
+            }
+            for (place, weight) in &transition.outputs {
+                post[*place][t] += weight;
+            }
+        }
+        Incidence {
+            places: self.places.iter().map(|p| p.label.clone()).collect(),
+            transitions: self.transitions.iter().map(|t| t.label.clone()).collect(),
+            pre,
+            post,
+        }
+    }
+
+    /// Most tokens each place holds in any reachable marking by the semiflows covering it, `None`
+    /// for the places no semiflow covers. The weighted sum of a semiflow keeps the value it has
+    /// in the initial marking, which a place cannot exceed with its weight.
+    pub fn semiflow_bounds(&self) -> Vec<Option<usize>> {
+        let initial = self.initial_marking();
+        let mut bounds = vec![None; self.places.len()];
+        for semiflow in self.incidence().semiflows().unwrap_or_default() {
+            let total = (semiflow.iter())
+                .map(|(p, weight)| weight.saturating_mul(initial.count(*p) as u64))
+                .fold(0, u64::saturating_add);
+            for (p, weight) in &semiflow {
+                let bound = (total / weight) as usize;
+                bounds[*p] = Some(bounds[*p].map_or(bound, |b: usize| b.min(bound)));
+            }
+        }
+        bounds
+    }
+
+    /// Whether the state equation for the target may have a solution, if not the target is
+    /// not reachable
+    ///
+    /// Firing a sequence in which t occurs x(t) times changes m0 into m0 + C x. Solving over the
+    /// nonnegative integers is hard in general, so two relaxations are checked: a nonnegative
+    /// rational solution and an integer solution of any sign. Arithmetic overflowing 128 bits is
+    /// taken as a solution, so reachability is only ever refuted soundly.
+    pub fn state_equation(&self, target: &Marking) -> bool {
+        let matrix: Vec<Vec<i128>> = self
+            .incidence()
+            .matrix()
+            .into_iter()
+            .map(|row| row.into_iter().map(i128::from).collect())
+            .collect();
+        let initial = self.initial_marking();
+        let difference: Vec<i128> = (0..self.places.len())
+            .map(|p| target.count(p) as i128 - initial.count(p) as i128)
+            .collect();
+        integer_solution(&matrix, &difference).unwrap_or(true)
+            && nonnegative_solution(&matrix, &difference).unwrap_or(true)
+    }
+}
+
+/// Whether `a x = b` has a solution over the integers by reducing `a` to Hermite normal form,
+/// `None` on overflow
+fn integer_solution(a: &[Vec<i128>], b: &[i128]) -> Option<bool> {
+    let mut a = a.to_vec();
+    let columns = a.first().map_or(0, |row| row.len());
+    // Column operations bring the matrix to lower echelon form, each row with a pivot solves
+    // for one entry of the transformed solution y with a x = (a u) y
+    let mut solution: Vec<i128> = vec![];
+    for (r, row_b) in b.iter().enumerate() {
+        let pivot = solution.len();
+        for c in pivot + 1..columns {
+            // Euclid on the columns pivot and c until the entry in column c vanishes
+            while a[r][c] != 0 {
+                let quotient = a[r][pivot].checked_div(a[r][c]).unwrap_or(0);
+                for row in a.iter_mut() {
+                    row[pivot] = row[pivot].checked_sub(quotient.checked_mul(row[c])?)?;
+                    row.swap(pivot, c);
+                }
+            }
+        }
+        let mut rest = *row_b;
+        for (j, y) in solution.iter().enumerate() {
+            rest = rest.checked_sub(a[r][j].checked_mul(*y)?)?;
+        }
+        match a[r].get(pivot) {
+            Some(entry) if *entry != 0 => {
+                if rest % entry != 0 {
+                    return Some(false);
+                }
+                solution.push(rest / entry);
+            }
+            _ if rest != 0 => return Some(false),
+            _ => {}
+        }
+    }
+    Some(true)
+}
+
+/// Fraction with a positive denominator in lowest terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rational(i128, i128);
+
+impl Rational {
+    fn new(numerator: i128, denominator: i128) -> Option<Rational> {
+        let divisor = gcd(numerator, denominator).max(1);
+        let sign = if denominator < 0 { -1 } else { 1 };
+        Some(Rational(
+            numerator / divisor * sign,
+            denominator.checked_abs()? / divisor,
+        ))
+    }
+
+    fn sub(self, other: Rational) -> Option<Rational> {
+        Rational::new(
+            self.0
+                .checked_mul(other.1)?
+                .checked_sub(other.0.checked_mul(self.1)?)?,
+            self.1.checked_mul(other.1)?,
+        )
+    }
+
+    fn mul(self, other: Rational) -> Option<Rational> {
+        Rational::new(self.0.checked_mul(other.0)?, self.1.checked_mul(other.1)?)
+    }
+
+    fn div(self, other: Rational) -> Option<Rational> {
+        Rational::new(self.0.checked_mul(other.1)?, self.1.checked_mul(other.0)?)
+    }
+
+    fn cmp(self, other: Rational) -> Option<Ordering> {
+        Some(
+            self.0
+                .checked_mul(other.1)?
+                .cmp(&other.0.checked_mul(self.1)?),
+        )
+    }
+}
+
+/// The sum of the row increased by the transition and the row decreased by it, scaled such that
+/// the change by the transition cancels out and divided by the greatest common divisor of the
+/// weights. `None` on overflow.
+fn cancel(increasing: &Row, decreasing: &Row, t: usize) -> Option<Row> {
+    let factors = [-decreasing.0[&t], increasing.0[&t]];
+    let sum = |maps: [&BTreeMap<usize, i64>; 2]| -> Option<BTreeMap<usize, i64>> {
+        let mut sum = BTreeMap::new();
+        for (map, factor) in maps.into_iter().zip(factors) {
+            for (key, value) in map {
+                let total: &mut i64 = sum.entry(*key).or_default();
+                *total = total.checked_add(value.checked_mul(factor)?)?;
+            }
+        }
+        sum.retain(|_, value| *value != 0);
+        Some(sum)
+    };
+    let (mut changes, mut weights) = (
+        sum([&increasing.0, &decreasing.0])?,
+        sum([&increasing.1, &decreasing.1])?,
+    );
+    let divisor = weights.values().fold(0, |d, w| gcd(d, *w as i128)) as i64;
+    for value in changes.values_mut().chain(weights.values_mut()) {
+        *value /= divisor;
+    }
+    Some((changes, weights))
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Whether `a x = b` has a solution with `x >= 0` over the rationals, `None` on overflow.
+/// The first phase of the simplex method minimizes the sum of one artificial variable per row.
+fn nonnegative_solution(a: &[Vec<i128>], b: &[i128]) -> Option<bool> {
+    let rows = b.len();
+    let columns = a.first().map_or(0, |row| row.len());
+    let zero = Rational(0, 1);
+    let one = Rational(1, 1);
+    // Rows of the tableau are the original columns, the artificial ones and the right side
+    let mut tableau: Vec<Vec<Rational>> = (0..rows)
+        .map(|r| {
+            let sign = if b[r] < 0 { -1 } else { 1 };
+            let mut row: Vec<Rational> = a[r].iter().map(|v| Rational(v * sign, 1)).collect();
+            row.extend((0..rows).map(|i| if i == r { one } else { zero }));
+            row.push(Rational(b[r] * sign, 1));
+            row
+        })
+        .collect();
+    let mut basis: Vec<usize> = (columns..columns + rows).collect();
+    let width = columns + rows + 1;
+
+    loop {
+        // Reduced costs of the objective, the sum of the artificial variables
+        let mut entering = None;
+        for c in 0..columns {
+            if basis.contains(&c) {
+                continue;
+            }
+            let mut cost = zero;
+            for (row, basic) in tableau.iter().zip(&basis) {
+                if *basic >= columns {
+                    cost = cost.sub(row[c])?;
+                }
+            }
+            // Bland's rule, the first improving column avoids cycling
+            if cost.cmp(zero)? == Ordering::Less {
+                entering = Some(c);
+                break;
+            }
+        }
+        let Some(entering) = entering else {
+            break;
+        };
+        let mut leaving: Option<(usize, Rational)> = None;
+        for r in 0..rows {
+            if tableau[r][entering].cmp(zero)? != Ordering::Greater {
+                continue;
+            }
+            let ratio = tableau[r][width - 1].div(tableau[r][entering])?;
+            let better = match leaving {
+                None => true,
+                Some((l, best)) => match ratio.cmp(best)? {
+                    Ordering::Less => true,
+                    Ordering::Equal => basis[r] < basis[l],
+                    Ordering::Greater => false,
+                },
+            };
+            if better {
+                leaving = Some((r, ratio));
+            }
+        }
+        // The objective is bounded below by zero, so some row limits the entering column
+        let (pivot, _) = leaving?;
+        let factor = tableau[pivot][entering];
+        for value in tableau[pivot].iter_mut() {
+            *value = value.div(factor)?;
+        }
+        let pivot_row = tableau[pivot].clone();
+        for (r, row) in tableau.iter_mut().enumerate() {
+            if r == pivot || row[entering] == zero {
+                continue;
+            }
+            let factor = row[entering];
+            for (value, reduced) in row.iter_mut().zip(&pivot_row) {
+                *value = value.sub(factor.mul(*reduced)?)?;
+            }
+        }
+        basis[pivot] = entering;
+    }
+    // Feasible iff every artificial variable left in the basis is zero
+    Some((0..rows).all(|r| basis[r] < columns || tableau[r][width - 1] == zero))
+}
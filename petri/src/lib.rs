@@ -1,28 +1,96 @@
+#[cfg(any(test, feature = "testing"))]
+pub mod arbitrary;
+mod colored;
+mod compose;
+mod coverability;
+mod delta;
+#[cfg(feature = "fs")]
+mod disk;
+mod dot;
 mod error;
+mod incidence;
+mod liveness;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod parser;
+mod query;
+mod reachability;
+mod reduction;
+mod simulation;
+mod smv;
+mod stochastic;
+mod store;
+mod stubborn;
+mod symbolic;
+mod writer;
 
 use bimap::BiMap;
-use bitvec::prelude::BitVec;
+pub use colored::ColoredNet;
+pub use coverability::Bound;
+pub use delta::Change;
+#[cfg(feature = "fs")]
+pub use disk::DiskSummary;
 pub use error::{Error, Result};
-pub use parser::from_xml;
+pub use incidence::Incidence;
+pub use liveness::Liveness;
+#[cfg(feature = "fs")]
+pub use parser::from_file;
+pub use parser::{from_colored_xml, from_lola, from_tina, from_xml, parse, Format};
+pub use query::{Answer, MarkingIndex, Quantifier, Query};
+pub use reduction::Reduction;
+use serde_derive::{Deserialize, Serialize};
+pub use simulation::Simulation;
 use std::collections::HashMap;
+use std::fmt::Write;
+pub use stochastic::Ctmc;
+pub use store::{HashCompaction, MarkingStore, Visited};
+pub use stubborn::StubbornSets;
+pub use symbolic::SymbolicSummary;
 
+#[derive(Clone, Debug)]
 struct Place {
+    label: String,
     initial_marking: usize,
 }
 
-#[derive(Debug)]
+/// Most tokens a place can hold
+pub const MAX_TOKENS: usize = u16::MAX as usize;
+
+#[derive(Clone, Debug)]
 struct Transition {
     label: String,
-    inputs: Vec<usize>,
-    outputs: Vec<usize>,
+    /// Places with the number of tokens firing consumes from them
+    inputs: Vec<(usize, usize)>,
+    /// Places with the number of tokens firing produces in them
+    outputs: Vec<(usize, usize)>,
+    /// Rate of the exponentially distributed delay before firing, if given
+    rate: Option<f64>,
+}
+
+impl Transition {
+    fn enabled(&self, marking: &Marking) -> bool {
+        self.inputs
+            .iter()
+            .all(|(place, weight)| marking.count(*place) >= *weight)
+    }
 }
 
+/// Add the weight to the arc to the place, parallel arcs add up
+fn add_weight(arcs: &mut Vec<(usize, usize)>, place: usize, weight: usize) {
+    match arcs.iter_mut().find(|(p, _)| *p == place) {
+        Some((_, w)) => *w += weight,
+        None => arcs.push((place, weight)),
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct PetriNet {
     places: Vec<Place>,
     transitions: Vec<Transition>,
     place_labels: HashMap<String, usize>,
     transition_labels: BiMap<String, usize>,
+    /// Whether markings with more than one token in a place are rejected
+    safe: bool,
 }
 
 impl PetriNet {
@@ -32,15 +100,21 @@ impl PetriNet {
             transitions: vec![],
             place_labels: HashMap::new(),
             transition_labels: BiMap::new(),
+            safe: false,
         }
     }
 
     fn add_place(&mut self, place: String, initial_marking: usize) -> Result<()> {
         if self.place_labels.contains_key(&place) {
             Err(Error::DuplicatePlace(place))
+        } else if initial_marking > MAX_TOKENS {
+            Err(Error::Overflow(place))
         } else {
             let index = self.places.len();
-            self.places.push(Place { initial_marking });
+            self.places.push(Place {
+                label: place.clone(),
+                initial_marking,
+            });
             self.place_labels.insert(place, index);
             Ok(())
         }
@@ -55,24 +129,29 @@ impl PetriNet {
                 label: transition.clone(),
                 inputs: vec![],
                 outputs: vec![],
+                rate: None,
             });
             self.transition_labels.insert(transition, index);
             Ok(())
         }
     }
 
-    fn add_arc(&mut self, source: String, target: String) -> Result<()> {
+    /// Add an arc which moves `weight` tokens
+    fn add_arc(&mut self, source: String, target: String, weight: usize) -> Result<()> {
+        if weight == 0 {
+            return Err(Error::InvalidWeight(source, target));
+        }
         if let (Some(place_index), Some(transition_index)) = (
             self.place_labels.get(&source),
             self.transition_labels.get_by_left(&target),
         ) {
             // Source is a place
             // Target is a transition
-            self.transitions
+            let transition = self
+                .transitions
                 .get_mut(*transition_index)
-                .ok_or(Error::InvalidIndex)?
-                .inputs
-                .push(*place_index);
+                .ok_or(Error::InvalidIndex)?;
+            add_weight(&mut transition.inputs, *place_index, weight);
             Ok(())
         } else if let (Some(transition_index), Some(place_index)) = (
             self.transition_labels.get_by_left(&source),
@@ -80,24 +159,37 @@ impl PetriNet {
         ) {
             // Source is a transition
             // Target is a place
-            self.transitions
+            let transition = self
+                .transitions
                 .get_mut(*transition_index)
-                .ok_or(Error::InvalidIndex)?
-                .outputs
-                .push(*place_index);
+                .ok_or(Error::InvalidIndex)?;
+            add_weight(&mut transition.outputs, *place_index, weight);
             Ok(())
         } else {
             Err(Error::InvalidArc(source, target))
         }
     }
 
-    pub fn initial_marking(&self) -> Marking {
-        Marking {
-            markings: self.places.iter().map(|p| p.initial_marking > 0).collect(),
+    /// Assert that the net is 1-safe, firing a transition into a marked place is an error from
+    /// then on
+    pub fn assert_safe(&mut self) -> Result<()> {
+        if let Some(place) = self.places.iter().find(|p| p.initial_marking > 1) {
+            return Err(Error::Unsafe(place.label.clone()));
         }
+        self.safe = true;
+        Ok(())
+    }
+
+    /// Whether the net was asserted to be 1-safe
+    pub fn is_safe(&self) -> bool {
+        self.safe
+    }
+
+    pub fn initial_marking(&self) -> Marking {
+        Marking::pack(self.places.iter().map(|p| p.initial_marking).collect())
     }
 
-    pub fn transitions<'a>(&'a self, marking: &'a Marking) -> Result<Vec<(&'a str, Marking)>> {
+    pub fn transitions<'a>(&'a self, marking: &Marking) -> Result<Vec<(&'a str, Marking)>> {
         marking.next(self)
     }
 
@@ -110,51 +202,165 @@ impl PetriNet {
     pub fn deadlock(&self, marking: &Marking) -> Result<bool> {
         marking.deadlock(self)
     }
+
+    /// Labels of the places in the order of the net
+    pub fn place_names(&self) -> Vec<&str> {
+        self.places.iter().map(|p| p.label.as_str()).collect()
+    }
+
+    /// Labels of the transitions in the order of the net
+    pub fn transition_names(&self) -> Vec<&str> {
+        self.transitions.iter().map(|t| t.label.as_str()).collect()
+    }
+
+    pub fn has_transition(&self, transition: &str) -> bool {
+        self.transition_labels.contains_left(transition)
+    }
+
+    pub fn has_place(&self, place: &str) -> bool {
+        self.place_labels.contains_key(place)
+    }
+
+    /// Number of tokens in the place with the given label
+    pub fn tokens(&self, marking: &Marking, place: &str) -> Result<usize> {
+        let index = self
+            .place_labels
+            .get(place)
+            .ok_or_else(|| Error::UnknownPlace(place.to_string()))?;
+        if *index < marking.len() {
+            Ok(marking.count(*index))
+        } else {
+            Err(Error::InvalidIndex)
+        }
+    }
+
+    /// Places holding tokens with their counts, such as 'p q=2'
+    pub fn marking_label(&self, marking: &Marking) -> String {
+        let mut label = String::new();
+        for (index, place) in self.places.iter().enumerate() {
+            match marking.count(index) {
+                0 => {}
+                1 => write!(&mut label, "{} ", place.label).unwrap(),
+                count => write!(&mut label, "{}={} ", place.label, count).unwrap(),
+            }
+        }
+        label.trim_end().to_string()
+    }
+}
+
+/// The reachability graph with edges labeled by the fired transitions. A transition holds in the
+/// markings in which it is enabled and a place, unless a transition has its name, in those in
+/// which it has a token.
+impl ts::TransitionSystem for PetriNet {
+    type State = Marking;
+    type Label = String;
+    type Error = Error;
+
+    fn initial_states(&self) -> Vec<Marking> {
+        vec![self.initial_marking()]
+    }
+
+    fn successors(&self, marking: &Marking) -> Result<Vec<(String, Marking)>> {
+        Ok(self
+            .transitions(marking)?
+            .into_iter()
+            .map(|(t, m)| (t.to_string(), m))
+            .collect())
+    }
+
+    fn holds(&self, marking: &Marking, proposition: &str) -> bool {
+        match self.transition_labels.get_by_left(proposition) {
+            Some(t) => self.transitions[*t].enabled(marking),
+            None => self
+                .place_labels
+                .get(proposition)
+                .is_some_and(|place| marking.count(*place) > 0),
+        }
+    }
+}
+
+/// Token counts of the places, stored in a single byte per place as long as every count fits
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+enum Counts {
+    Small(Vec<u8>),
+    Large(Vec<u16>),
 }
 
 /// Maps stores the number of tokens for each place in a net
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Marking {
-    markings: BitVec,
+    tokens: Counts,
 }
 
 impl Marking {
+    /// Store the counts in the narrowest representation, so equal markings are stored equally.
+    /// The counts must not exceed `MAX_TOKENS`.
+    fn pack(counts: Vec<usize>) -> Self {
+        let tokens = if counts.iter().all(|c| *c <= u8::MAX as usize) {
+            Counts::Small(counts.into_iter().map(|c| c as u8).collect())
+        } else {
+            Counts::Large(counts.into_iter().map(|c| c as u16).collect())
+        };
+        Marking { tokens }
+    }
+
+    fn count(&self, place: usize) -> usize {
+        match &self.tokens {
+            Counts::Small(counts) => counts[place] as usize,
+            Counts::Large(counts) => counts[place] as usize,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match &self.tokens {
+            Counts::Small(counts) => counts.len(),
+            Counts::Large(counts) => counts.len(),
+        }
+    }
+
     /// Calculate the next marking
-    /// Will panic if indices do not match ( but this shouldn't happen as long as the underlying
-    /// petri net never gets mutated )
-    fn next<'a>(&'a self, net: &'a PetriNet) -> Result<Vec<(&'a str, Marking)>> {
-        if self.markings.len() != net.places.len() {
+    fn next<'a>(&self, net: &'a PetriNet) -> Result<Vec<(&'a str, Marking)>> {
+        if self.len() != net.places.len() {
             return Err(Error::InvalidIndex);
         }
-        // Get transitions which are active
-        let active_transitions = net.transitions.iter().filter(|t| {
-            t.inputs
-                .iter()
-                .fold(true, |acc, i| if acc { self.markings[*i] } else { acc })
-        });
-
-        Ok(active_transitions
-            .map(|t| {
-                let mut marking = self.clone();
-                for &i in &t.inputs {
-                    marking.markings.set(i, false);
-                }
-                for &i in &t.outputs {
-                    marking.markings.set(i, true);
-                }
-                (t.label.as_str(), marking)
-            })
-            .collect())
+        if let Some(reason) = ts::stopped() {
+            return Err(Error::Stopped(reason));
+        }
+        net.transitions
+            .iter()
+            .filter(|t| t.enabled(self))
+            .map(|t| Ok((t.label.as_str(), self.fire(net, t)?)))
+            .collect()
+    }
+
+    /// Fire the enabled transition, consuming and producing the weights of its arcs
+    /// Fails if a place would exceed `MAX_TOKENS` or, for safe nets, hold more than one token
+    fn fire(&self, net: &PetriNet, transition: &Transition) -> Result<Marking> {
+        let bound = if net.safe { 1 } else { MAX_TOKENS };
+        let mut counts = (0..self.len()).map(|p| self.count(p)).collect::<Vec<_>>();
+        for (place, weight) in &transition.inputs {
+            counts[*place] -= weight;
+        }
+        for (place, weight) in &transition.outputs {
+            counts[*place] = counts[*place]
+                .checked_add(*weight)
+                .filter(|c| *c <= bound)
+                .ok_or_else(|| {
+                    let label = net.places[*place].label.clone();
+                    if net.safe {
+                        Error::Unsafe(label)
+                    } else {
+                        Error::Overflow(label)
+                    }
+                })?;
+        }
+        Ok(Marking::pack(counts))
     }
 
-    pub fn active_transitions<'a>(&'a self, net: &'a PetriNet) -> Vec<&'a str> {
+    pub fn active_transitions<'a>(&self, net: &'a PetriNet) -> Vec<&'a str> {
         net.transitions
             .iter()
-            .filter(|t| {
-                t.inputs
-                    .iter()
-                    .fold(true, |acc, i| if acc { self.markings[*i] } else { acc })
-            })
+            .filter(|t| t.enabled(self))
             .map(|t| t.label.as_str())
             .collect()
     }
@@ -163,3 +369,739 @@ impl Marking {
         self.next(net).map(|m| m.is_empty())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashSet, VecDeque};
+
+    use ts::TransitionSystem;
+
+    use crate::{
+        from_colored_xml, from_lola, from_tina, from_xml, Bound, Change, Error, Format,
+        HashCompaction, Liveness, Marking, MarkingStore, PetriNet, Reduction, Simulation,
+        StubbornSets, MAX_TOKENS,
+    };
+
+    const WEIGHTED: &str = r#"<?xml version="1.0"?>
+<pnml>
+  <net id="weighted" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="page">
+      <place id="p"><initialMarking><text>3</text></initialMarking></place>
+      <place id="q"/>
+      <transition id="t"/>
+      <arc id="a1" source="p" target="t"><inscription><text>2</text></inscription></arc>
+      <arc id="a2" source="t" target="q"><inscription><text>5</text></inscription></arc>
+    </page>
+  </net>
+</pnml>"#;
+
+    #[test]
+    pub fn weighted_arcs() {
+        let net = from_xml(WEIGHTED).unwrap();
+        let initial = net.initial_marking();
+        assert_eq!(net.tokens(&initial, "p").unwrap(), 3);
+
+        let next = net.next_markings(&initial).unwrap();
+        assert_eq!(next.len(), 1);
+        assert_eq!(net.tokens(&next[0], "p").unwrap(), 1);
+        assert_eq!(net.tokens(&next[0], "q").unwrap(), 5);
+        // A single token does not suffice for an arc of weight 2
+        assert!(net.deadlock(&next[0]).unwrap());
+    }
+
+    #[test]
+    pub fn counted_markings() {
+        let mut net = from_xml(WEIGHTED).unwrap();
+        let next = net.next_markings(&net.initial_marking()).unwrap();
+        assert_eq!(net.tokens(&next[0], "q").unwrap(), 5);
+
+        // Producing more than one token is rejected for safe nets
+        assert!(matches!(net.assert_safe(), Err(Error::Unsafe(p)) if p == "p"));
+        let large = WEIGHTED.replace("<text>3</text>", "<text>1000</text>");
+        let net = from_xml(&large).unwrap();
+        let next = net.next_markings(&net.initial_marking()).unwrap();
+        assert_eq!(net.tokens(&next[0], "p").unwrap(), 998);
+        assert_eq!(next[0], Marking::pack(vec![998, 5]));
+
+        let overflow = WEIGHTED.replace(
+            "<text>5</text>",
+            &format!("<text>{}</text>", MAX_TOKENS + 1),
+        );
+        let net = from_xml(&overflow).unwrap();
+        assert!(matches!(
+            net.next_markings(&net.initial_marking()),
+            Err(Error::Overflow(p)) if p == "q"
+        ));
+    }
+
+    #[test]
+    pub fn karp_miller() {
+        let net = from_xml(WEIGHTED).unwrap();
+        assert!(net.coverable(&[("q", 5)]).unwrap());
+        assert!(!net.coverable(&[("q", 6)]).unwrap());
+        assert!(!net.coverable(&[("p", 2), ("q", 5)]).unwrap());
+
+        // Returning a token to p lets q grow without bound
+        let pump = WEIGHTED.replace(
+            "</page>",
+            r#"<arc id="a3" source="t" target="p"><inscription><text>2</text></inscription></arc></page>"#,
+        );
+        let net = from_xml(&pump).unwrap();
+        assert!(net.coverable(&[("p", 3), ("q", 1000)]).unwrap());
+        assert!(!net.coverable(&[("p", 4)]).unwrap());
+        assert!(net.coverable(&[("r", 1)]).is_err());
+        assert_eq!(
+            net.bounds(),
+            vec![("p", Bound::Finite(3)), ("q", Bound::Unbounded)]
+        );
+        let net = from_xml(WEIGHTED).unwrap();
+        assert_eq!(
+            net.bounds(),
+            vec![("p", Bound::Finite(3)), ("q", Bound::Finite(5))]
+        );
+    }
+
+    #[test]
+    pub fn reachable_with_witness() {
+        let pipeline = WEIGHTED
+            .replace(r#"<place id="q"/>"#, r#"<place id="q"/><place id="r"/>"#)
+            .replace(r#"<transition id="t"/>"#, r#"<transition id="t"/><transition id="u"/>"#)
+            .replace(
+                "</page>",
+                r#"<arc id="a3" source="q" target="u"/><arc id="a4" source="u" target="r"/></page>"#,
+            );
+        let net = from_xml(&pipeline).unwrap();
+        let target = net.marking(&[("p", 1), ("q", 3), ("r", 2)]).unwrap();
+        assert_eq!(net.reachable(&target).unwrap(), Some(vec!["t", "u", "u"]));
+        let initial = net.initial_marking();
+        assert_eq!(net.reachable(&initial).unwrap(), Some(vec![]));
+        let target = net.marking(&[("p", 2)]).unwrap();
+        assert_eq!(net.reachable(&target).unwrap(), None);
+    }
+
+    /// Net of independent transitions ti moving a token from pi to qi
+    fn concurrent(n: usize) -> PetriNet {
+        let places = (0..n)
+            .map(|i| {
+                format!(
+                    r#"<place id="p{i}"><initialMarking><text>1</text></initialMarking></place><place id="q{i}"/>"#
+                )
+            })
+            .collect::<String>();
+        let transitions = (0..n)
+            .map(|i| format!(r#"<transition id="t{i}"/>"#))
+            .collect::<String>();
+        let arcs = (0..n)
+            .map(|i| {
+                format!(
+                    r#"<arc id="i{i}" source="p{i}" target="t{i}"/><arc id="o{i}" source="t{i}" target="q{i}"/>"#
+                )
+            })
+            .collect::<String>();
+        from_xml(&format!(
+            r#"<pnml><net id="n" type="ptnet"><page id="page">{places}{transitions}{arcs}</page></net></pnml>"#
+        ))
+        .unwrap()
+    }
+
+    fn explore(net: &PetriNet, reduction: &StubbornSets) -> HashSet<Marking> {
+        let mut visited = HashSet::from([net.initial_marking()]);
+        let mut queue = VecDeque::from([net.initial_marking()]);
+        while let Some(marking) = queue.pop_front() {
+            for (_, next) in reduction.transitions(&marking).unwrap() {
+                if visited.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        visited
+    }
+
+    #[test]
+    pub fn stubborn_sets() {
+        let net = concurrent(6);
+        let markings = explore(&net, &StubbornSets::deadlocks(&net));
+        assert_eq!(markings.len(), 7);
+        let deadlocks = markings
+            .iter()
+            .filter(|m| net.deadlock(m).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(deadlocks.len(), 1);
+        assert_eq!(net.tokens(deadlocks[0], "q5").unwrap(), 1);
+
+        // Observing q0 and the fireability of t1 keeps both orders of t0 and t1
+        let reduction = StubbornSets::ltl(&net, &["q0"], &["t1"]).unwrap();
+        let markings = explore(&net, &reduction);
+        assert!(markings.len() < 1 << 6);
+        let fired = |m: &Marking, t: usize| net.tokens(m, &format!("q{t}")).unwrap() == 1;
+        assert!(markings.iter().any(|m| fired(m, 0) && !fired(m, 1)));
+        assert!(markings.iter().any(|m| !fired(m, 0) && fired(m, 1)));
+        assert!(StubbornSets::ltl(&net, &["r"], &[]).is_err());
+    }
+
+    #[test]
+    pub fn symbolic_reachability() {
+        let net = concurrent(10);
+        let summary = net.symbolic_analysis().unwrap();
+        assert_eq!(
+            (summary.markings, summary.deadlocks, summary.bits),
+            (1024, 1, 1)
+        );
+
+        // q needs more bits than the initial marking
+        let net = from_xml(WEIGHTED).unwrap();
+        let summary = net.symbolic_analysis().unwrap();
+        assert_eq!(
+            (summary.markings, summary.deadlocks, summary.bits),
+            (2, 1, 4)
+        );
+
+        let pump = WEIGHTED.replace("</page>", r#"<arc id="a3" source="t" target="p"/></page>"#);
+        let net = from_xml(&pump).unwrap();
+        assert_eq!(net.symbolic_analysis().unwrap().markings, 3);
+        let pump = WEIGHTED.replace(
+            "</page>",
+            r#"<arc id="a3" source="t" target="p"><inscription><text>2</text></inscription></arc></page>"#,
+        );
+        let net = from_xml(&pump).unwrap();
+        assert!(matches!(net.symbolic_analysis(), Err(Error::Overflow(p)) if p == "q"));
+    }
+
+    #[test]
+    pub fn parallel_exploration() {
+        let net = concurrent(8);
+        let markings = net.reachable_markings(4).unwrap();
+        assert_eq!(markings.len(), 256);
+        let distinct: HashSet<_> = markings.iter().collect();
+        assert_eq!(distinct.len(), 256);
+        assert_eq!(net.reachable_markings(1).unwrap().len(), 256);
+        let deadlocks = markings.iter().filter(|m| net.deadlock(m).unwrap()).count();
+        assert_eq!(deadlocks, 1);
+    }
+
+    #[test]
+    pub fn interned_store() {
+        let net = from_xml(WEIGHTED).unwrap();
+        let mut store = MarkingStore::new();
+        let initial = net.initial_marking();
+        let large = Marking::pack(vec![1000, 3]);
+        assert_eq!(store.insert(&initial), (0, true));
+        assert_eq!(store.insert(&large), (1, true));
+        assert_eq!(store.insert(&initial), (0, false));
+        assert_eq!(store.get(1), large);
+        assert!(store.contains(&large) && !store.contains(&Marking::pack(vec![0, 0])));
+
+        let markings = concurrent(8).reachable_markings(1).unwrap();
+        let mut store = MarkingStore::new();
+        let mut compaction = HashCompaction::new();
+        for marking in &markings {
+            assert!(store.insert(marking).1);
+            assert!(compaction.insert(marking));
+        }
+        assert_eq!(store.iter().collect::<Vec<_>>(), markings);
+        assert_eq!(compaction.len(), 256);
+        assert!(compaction.collision_probability() < 1e-12);
+    }
+
+    #[test]
+    pub fn transition_liveness() {
+        let net = from_xml(WEIGHTED).unwrap();
+        assert_eq!(net.liveness().unwrap(), vec![("t", Liveness::QuasiLive)]);
+
+        // A token cycles between p0 and p1, t2 is never fireable
+        let net = from_xml(
+            r#"<pnml><net id="n"><page id="pg">
+            <place id="p0"><initialMarking><text>1</text></initialMarking></place>
+            <place id="p1"/><place id="p2"/>
+            <transition id="t0"/><transition id="t1"/><transition id="t2"/>
+            <arc id="a0" source="p0" target="t0"/><arc id="a1" source="t0" target="p1"/>
+            <arc id="a2" source="p1" target="t1"/><arc id="a3" source="t1" target="p0"/>
+            <arc id="a4" source="p2" target="t2"/><arc id="a5" source="t2" target="p0"/>
+            </page></net></pnml>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            net.liveness().unwrap(),
+            vec![
+                ("t0", Liveness::Live),
+                ("t1", Liveness::Live),
+                ("t2", Liveness::Dead)
+            ]
+        );
+    }
+
+    #[test]
+    pub fn home_markings() {
+        let cycle = from_tina("tr a p -> q\ntr b q -> p\npl p (1)").unwrap();
+        assert_eq!(cycle.home_markings().unwrap().len(), 2);
+        assert!(cycle.reversible().unwrap());
+        // The cycle is entered once and never left
+        let entered = from_tina("tr s i -> p\ntr a p -> q\ntr b q -> p\npl i (1)").unwrap();
+        let home = entered.home_markings().unwrap();
+        let labels: Vec<_> = home.iter().map(|m| entered.marking_label(m)).collect();
+        assert_eq!(labels, vec!["p", "q"]);
+        assert!(!entered.reversible().unwrap());
+        // Two deadlocks cannot reach each other
+        let choice = from_tina("tr a i -> p\ntr b i -> q\npl i (1)").unwrap();
+        assert!(choice.home_markings().unwrap().is_empty());
+        assert!(!choice.reversible().unwrap());
+    }
+
+    #[test]
+    pub fn arc_validation() {
+        let pnml = |arcs: &str| {
+            format!(
+                r#"<pnml><net id="n"><page id="pg">
+<place id="p"><initialMarking><text>2</text></initialMarking></place>
+<place id="q"/><transition id="t"/>
+{}
+</page></net></pnml>"#,
+                arcs
+            )
+        };
+        // Parallel arcs add up
+        let net = from_xml(&pnml(
+            r#"<arc id="a1" source="p" target="t"/>
+<arc id="a2" source="p" target="t"/><arc id="a3" source="t" target="q"/>"#,
+        ))
+        .unwrap();
+        assert_eq!(net.incidence().pre, vec![vec![2], vec![0]]);
+        assert_eq!(net.reachable_markings(1).unwrap().len(), 2);
+
+        let error = |arcs: &str| match from_xml(&pnml(arcs)) {
+            Err(Error::Pnml { line, reason }) => (line, reason),
+            other => panic!("expected an error, got {:?}", other.map(|n| n.to_pnml())),
+        };
+        assert_eq!(
+            error(r#"<arc id="a1" source="p" target="u"/>"#),
+            (4, "undefined target 'u'".to_string())
+        );
+        assert_eq!(
+            error(
+                r#"<arc id="a1" source="p" target="t"/>
+<arc id="a1" source="t" target="q"/>"#
+            ),
+            (5, "duplicate arc 'a1'".to_string())
+        );
+        assert_eq!(
+            error(r#"<arc id="a1" source="p" target="q"/>"#),
+            (4, "arc from 'p' to 'q' connects two places".to_string())
+        );
+    }
+
+    #[test]
+    pub fn transition_system() {
+        let net = from_tina("tr t0 p0 -> p0\ntr t1 p0 -> p1\npl p0 (1)").unwrap();
+        let initial = net.initial_marking();
+        assert!(net.holds(&initial, "p0") && net.holds(&initial, "t1"));
+        assert!(!net.holds(&initial, "p1") && !net.holds(&initial, "p2"));
+        // Only t0 can be repeated forever, the deadlock after t1 ends every other run
+        let (stem, cycle) = ts::accepting_lasso(&net, |m| net.holds(m, "p0"))
+            .unwrap()
+            .unwrap();
+        assert!(stem.is_empty());
+        assert_eq!(cycle, vec![("t0".to_string(), initial)]);
+        assert!(ts::accepting_lasso(&net, |m| net.holds(m, "p1"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    pub fn pnml_round_trip() {
+        let net = from_xml(WEIGHTED).unwrap();
+        let pnml = net.to_pnml();
+        assert!(pnml.contains(
+            r#"<arc id="a1" source="t" target="q"><inscription><text>5</text></inscription></arc>"#
+        ));
+        let read = from_xml(&pnml).unwrap();
+        assert_eq!(read.to_pnml(), pnml);
+        assert_eq!(read.bounds(), net.bounds());
+        let markings = |net: &PetriNet| {
+            net.reachable_markings(1)
+                .unwrap()
+                .into_iter()
+                .collect::<HashSet<_>>()
+        };
+        assert_eq!(markings(&read), markings(&net));
+    }
+
+    #[test]
+    pub fn structural_reductions() {
+        // The token moves from p0 to p2 through p1, u0 duplicates t0, d duplicates p0, dead
+        // needs a token from the empty source place s and i has no arcs
+        let mut net = from_xml(
+            r#"<pnml><net id="n"><page id="pg">
+            <place id="p0"><initialMarking><text>1</text></initialMarking></place>
+            <place id="p1"/><place id="p2"/><place id="s"/>
+            <place id="d"><initialMarking><text>1</text></initialMarking></place>
+            <place id="i"/>
+            <transition id="t0"/><transition id="t1"/><transition id="u0"/><transition id="dead"/>
+            <arc id="a0" source="p0" target="t0"/><arc id="a1" source="t0" target="p1"/>
+            <arc id="a2" source="p1" target="t1"/><arc id="a3" source="t1" target="p2"/>
+            <arc id="a4" source="p0" target="u0"/><arc id="a5" source="u0" target="p1"/>
+            <arc id="a6" source="d" target="t0"/><arc id="a7" source="d" target="u0"/>
+            <arc id="a8" source="s" target="dead"/><arc id="a9" source="dead" target="p2"/>
+            </page></net></pnml>"#,
+        )
+        .unwrap();
+        let deadlocks = |net: &PetriNet| {
+            net.reachable_markings(1)
+                .unwrap()
+                .iter()
+                .filter(|m| net.deadlock(m).unwrap())
+                .count()
+        };
+        assert_eq!(deadlocks(&net), 1);
+        let applied = net.reduce();
+        assert_eq!(
+            applied,
+            vec![
+                Reduction::IsolatedPlace("i".into()),
+                Reduction::DeadTransition("dead".into()),
+                Reduction::IsolatedPlace("s".into()),
+                Reduction::IdenticalTransitions("t0".into(), "u0".into()),
+                Reduction::IdenticalPlaces("p0".into(), "d".into()),
+                Reduction::Agglomeration {
+                    place: "p1".into(),
+                    first: "t0".into(),
+                    second: "t1".into(),
+                    fused: "t0.t1".into()
+                },
+            ]
+        );
+        assert_eq!(net.reachable_markings(1).unwrap().len(), 2);
+        assert_eq!(deadlocks(&net), 1);
+        assert_eq!(
+            net.bounds(),
+            vec![("p0", Bound::Finite(1)), ("p2", Bound::Finite(1))]
+        );
+        assert_eq!(
+            net.liveness().unwrap(),
+            vec![("t0.t1", Liveness::QuasiLive)]
+        );
+    }
+
+    #[test]
+    pub fn dot_export() {
+        let net = from_xml(WEIGHTED).unwrap();
+        let dot = net.to_dot();
+        assert!(dot.contains(r#"p0 [label = "p\n3", shape = circle]"#));
+        assert!(dot.contains(r#"t0 [label = "t", shape = box]"#));
+        assert!(dot.contains(r#"t0 -> p1 [label = "5"]"#));
+
+        let graph = net.reachability_graph_dot(10).unwrap();
+        assert!(graph.contains(r#"m0 [label = "p=3"]"#));
+        assert!(graph.contains(r#"m0 -> m1 [label = "t"]"#));
+        assert!(graph.contains(r#"m1 [label = "p q=5", peripheries = 2]"#));
+        let graph = net.reachability_graph_dot(1).unwrap();
+        assert!(graph.contains(r#"m0 [label = "p=3", style = dashed]"#));
+        assert!(!graph.contains("m1"));
+    }
+
+    #[test]
+    pub fn smv_export() {
+        let net = from_tina("tr t p0*2 -> p1 p0\ntr u p1 -> p0\npl p0 (2)").unwrap();
+        let smv = net
+            .to_smv([("p1 stays empty".into(), "G p_p1 <= 0".into())])
+            .unwrap();
+        assert!(smv.contains("p_p0 : 0..2;"));
+        assert!(smv.contains("t_t := p_p0 >= 2;"));
+        assert!(smv.contains("init(p_p0) := 2;"));
+        assert!(smv.contains("(t_t & next(p_p0) = p_p0 - 1 & next(p_p1) = p_p1 + 1)"));
+        assert!(smv.contains("(deadlock & next(p_p0) = p_p0 & next(p_p1) = p_p1)"));
+        assert!(smv.ends_with("-- p1 stays empty\nLTLSPEC G p_p1 <= 0;\n"));
+
+        assert_eq!(net.smv_place("p1").unwrap(), "p_p1");
+        assert!(net.smv_transition("v").is_err());
+        let net = from_tina("tr {a-b} p -> p p\npl p (1)").unwrap();
+        assert_eq!(net.smv_transition("a-b").unwrap(), "t_a$2d#b");
+        assert!(matches!(net.to_smv([]), Err(Error::Unbounded(_))));
+    }
+
+    #[test]
+    pub fn tina_and_lola() {
+        let pnml = from_xml(WEIGHTED).unwrap().to_pnml();
+        let tina = "net weighted\n# comment\ntr t [0,w[ p*2 -> q*5\npl p (3)\n";
+        assert_eq!(from_tina(tina).unwrap().to_pnml(), pnml);
+        assert_eq!(
+            from_tina(&from_xml(WEIGHTED).unwrap().to_tina())
+                .unwrap()
+                .to_pnml(),
+            pnml
+        );
+        let net = from_tina("tr {t-1} {p\\}} -> q*2 r\npl r\npl q (4)").unwrap();
+        assert_eq!(
+            net.to_tina(),
+            "net net\npl {p\\}}\npl q (4)\npl r\ntr {t-1} {p\\}} -> q*2 r\n"
+        );
+        assert_eq!(from_tina(&net.to_tina()).unwrap().to_tina(), net.to_tina());
+        let lola = "{ weighted }\nPLACE SAFE 5: p; q;\nMARKING p: 3;\n\
+                    TRANSITION t CONSUME p: 2; PRODUCE q: 5;\n";
+        assert_eq!(from_lola(lola).unwrap().to_pnml(), pnml);
+        let path = std::path::Path::new("model");
+        assert_eq!(Format::detect(path, lola), Format::Lola);
+        assert_eq!(Format::detect(path, tina), Format::Tina);
+        assert_eq!(Format::detect(path, WEIGHTED), Format::Pnml);
+
+        // A test arc needs a token in r without consuming it
+        let net = from_tina("tr t {p 1} r?1 -> q\npl {p 1} (1)\npl r (1)").unwrap();
+        let next = net.next_markings(&net.initial_marking()).unwrap();
+        assert_eq!(net.tokens(&next[0], "r").unwrap(), 1);
+        assert_eq!(net.tokens(&next[0], "p 1").unwrap(), 0);
+        assert!(matches!(
+            from_tina("tr t p ?-1 -> q"),
+            Err(Error::Tina { line: 1, .. })
+        ));
+        assert!(matches!(
+            from_lola("PLACE p;\nMARKING q: 1;"),
+            Err(Error::Lola { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    pub fn simulation() {
+        let net = concurrent(2);
+        let mut simulation = Simulation::new(&net);
+        assert_eq!(simulation.enabled(), vec!["t0", "t1"]);
+        simulation.fire("t1").unwrap();
+        assert_eq!(net.marking_label(simulation.marking()), "p0 q1");
+        assert!(matches!(simulation.fire("t1"), Err(Error::NotEnabled(t)) if t == "t1"));
+        assert!(matches!(
+            simulation.fire("u"),
+            Err(Error::UnknownTransition(_))
+        ));
+        assert_eq!(simulation.undo(), Some("t1"));
+        assert_eq!(simulation.undo(), None);
+        assert_eq!(simulation.marking(), &net.initial_marking());
+
+        let walked = simulation.random_walk(5, 7).unwrap();
+        assert_eq!(walked.len(), 2);
+        assert_eq!(simulation.trace(), walked.as_slice());
+        assert!(simulation.enabled().is_empty());
+        simulation.reset();
+        assert_eq!(simulation.random_walk(5, 7).unwrap(), walked);
+        let weighted = from_xml(WEIGHTED).unwrap();
+        assert_eq!(weighted.marking_label(&weighted.initial_marking()), "p=3");
+    }
+
+    #[test]
+    pub fn colored_unfolding() {
+        // Philosophers 1 to 3 take both forks at once, only philosopher 3 needs the dot token
+        let x = r#"<subterm><variable refvariable="x"/></subterm>"#;
+        let succ = r#"<subterm><successor><subterm><variable refvariable="x"/></subterm></successor></subterm>"#;
+        let three = r#"<useroperator declaration="p3"/>"#;
+        let pnml = format!(
+            r#"<pnml><net id="philo" type="http://www.pnml.org/version-2009/grammar/symmetricnet">
+            <page id="page">
+            <place id="think"><type><structure><usersort declaration="philo"/></structure></type>
+              <hlinitialMarking><structure><all><usersort declaration="philo"/></all></structure></hlinitialMarking></place>
+            <place id="fork"><type><structure><usersort declaration="philo"/></structure></type>
+              <hlinitialMarking><structure><all><usersort declaration="philo"/></all></structure></hlinitialMarking></place>
+            <place id="eat"><type><structure><usersort declaration="philo"/></structure></type></place>
+            <place id="mutex"><type><structure><dot/></structure></type>
+              <hlinitialMarking><structure><dotconstant/></structure></hlinitialMarking></place>
+            <transition id="take"><condition><structure><inequality>
+              {x}<subterm>{three}</subterm></inequality></structure></condition></transition>
+            <transition id="take3"/>
+            <transition id="release"/>
+            <arc id="a0" source="think" target="take"><hlinscription><structure><variable refvariable="x"/></structure></hlinscription></arc>
+            <arc id="a1" source="fork" target="take"><hlinscription><structure><add>{x}{succ}</add></structure></hlinscription></arc>
+            <arc id="a2" source="take" target="eat"><hlinscription><structure><variable refvariable="x"/></structure></hlinscription></arc>
+            <arc id="a3" source="think" target="take3"><hlinscription><structure>{three}</structure></hlinscription></arc>
+            <arc id="a4" source="mutex" target="take3"><hlinscription><structure><dotconstant/></structure></hlinscription></arc>
+            <arc id="a5" source="fork" target="take3"><hlinscription><structure><numberof>
+              <subterm><numberconstant value="1"><positive/></numberconstant></subterm>
+              <subterm><add><subterm>{three}</subterm><subterm><useroperator declaration="p1"/></subterm></add></subterm>
+              </numberof></structure></hlinscription></arc>
+            <arc id="a6" source="take3" target="eat"><hlinscription><structure>{three}</structure></hlinscription></arc>
+            <arc id="a7" source="eat" target="release"><hlinscription><structure><variable refvariable="x"/></structure></hlinscription></arc>
+            <arc id="a8" source="release" target="think"><hlinscription><structure><variable refvariable="x"/></structure></hlinscription></arc>
+            <arc id="a9" source="release" target="fork"><hlinscription><structure><add>{x}{succ}</add></structure></hlinscription></arc>
+            </page>
+            <declaration><structure><declarations>
+              <namedsort id="philo" name="Philo"><cyclicenumeration>
+                <feconstant id="p1" name="1"/><feconstant id="p2" name="2"/><feconstant id="p3" name="3"/>
+              </cyclicenumeration></namedsort>
+              <variabledecl id="x" name="x"><usersort declaration="philo"/></variabledecl>
+            </declarations></structure></declaration>
+            </net></pnml>"#
+        );
+        let colored = from_colored_xml(&pnml).unwrap();
+        assert_eq!(colored.unfolded_size().unwrap(), (10, 6));
+        let net = from_xml(&pnml).unwrap();
+        let initial = net.initial_marking();
+        assert_eq!(
+            net.marking_label(&initial),
+            "think_1 think_2 think_3 fork_1 fork_2 fork_3 mutex"
+        );
+        assert_eq!(
+            initial.active_transitions(&net),
+            vec!["take_1", "take_2", "take3"]
+        );
+        let (_, next) = net
+            .transitions(&initial)
+            .unwrap()
+            .into_iter()
+            .find(|(t, _)| *t == "take_2")
+            .unwrap();
+        assert_eq!(
+            net.marking_label(&next),
+            "think_1 think_3 fork_1 eat_2 mutex"
+        );
+        // Neighbours share a fork, so one philosopher eats at a time and 3 only once
+        let markings = net.reachable_markings(1).unwrap();
+        assert_eq!(markings.len(), 7);
+        assert!(markings.iter().all(|m| !net.deadlock(m).unwrap()));
+    }
+
+    #[test]
+    pub fn state_equation() {
+        let net = from_xml(WEIGHTED).unwrap();
+        let incidence = net.incidence();
+        assert_eq!(incidence.pre, vec![vec![2], vec![0]]);
+        assert_eq!(incidence.post, vec![vec![0], vec![5]]);
+        assert_eq!(incidence.matrix(), vec![vec![-2], vec![5]]);
+        assert!(net.state_equation(&net.marking(&[("p", 1), ("q", 5)]).unwrap()));
+        assert!(!net.state_equation(&net.marking(&[("p", 3), ("q", 5)]).unwrap()));
+
+        // Half a firing of t would consume a single token
+        let net = from_tina("tr t p*2\npl p (3)").unwrap();
+        assert!(!net.state_equation(&net.marking(&[("p", 2)]).unwrap()));
+        assert!(net.state_equation(&net.marking(&[("p", 1)]).unwrap()));
+        // Only firing t backwards would return a token to p0
+        let net = concurrent(1);
+        let target = net.marking(&[("p0", 2)]).unwrap();
+        assert!(!net.state_equation(&target));
+        assert_eq!(net.reachable(&target).unwrap(), None);
+    }
+
+    #[test]
+    pub fn semiflows() {
+        // 5 tokens in p are worth 2 in q
+        let net = from_xml(WEIGHTED).unwrap();
+        assert_eq!(net.incidence().semiflows(), Some(vec![vec![(0, 5), (1, 2)]]));
+        assert_eq!(net.semiflow_bounds(), vec![Some(3), Some(7)]);
+
+        // The token circles between p and q while s fills r without bound
+        let net = from_tina("tr t p -> q\ntr u q -> p\ntr s -> r\npl p (1)").unwrap();
+        let bounds = net.semiflow_bounds();
+        let bound = |place: &str| bounds[net.place_labels[place]];
+        assert_eq!((bound("p"), bound("q"), bound("r")), (Some(1), Some(1), None));
+    }
+
+    #[test]
+    pub fn stochastic_rates() {
+        let net = from_xml(
+            r#"<pnml><net id="n"><page id="pg">
+            <place id="p"><initialMarking><text>1</text></initialMarking></place><place id="q"/>
+            <transition id="t"><toolspecific tool="lmc" version="0.1"><rate>2</rate></toolspecific></transition>
+            <transition id="u"><toolspecific tool="lmc" version="0.1"><delay>0.25</delay></toolspecific></transition>
+            <arc id="a0" source="p" target="t"/><arc id="a1" source="t" target="q"/>
+            <arc id="a2" source="q" target="u"/><arc id="a3" source="u" target="p"/>
+            </page></net></pnml>"#,
+        )
+        .unwrap();
+        assert_eq!(net.rate("u").unwrap(), Some(4.0));
+        assert_eq!(
+            from_xml(&net.to_pnml()).unwrap().rate("t").unwrap(),
+            Some(2.0)
+        );
+        let ctmc = net.ctmc().unwrap();
+        assert_eq!(ctmc.to_tra(), "2 2\n0 1 2\n1 0 4\n");
+        // The token stays twice as long in p as in q
+        let steady = ctmc.steady_state(1e-12);
+        assert!((steady[0] - 2.0 / 3.0).abs() < 1e-9);
+        assert!((steady[1] - 1.0 / 3.0).abs() < 1e-9);
+        assert!(from_xml(&WEIGHTED.replace(
+            r#"<transition id="t"/>"#,
+            r#"<transition id="t"><toolspecific tool="lmc"><rate>0</rate></toolspecific></transition>"#
+        ))
+        .is_err());
+    }
+
+    #[test]
+    pub fn net_changes() {
+        let net = from_xml(WEIGHTED).unwrap();
+        assert!(net.changes(&net).is_empty());
+        let rated = WEIGHTED.replace(
+            r#"<transition id="t"/>"#,
+            r#"<transition id="t"><toolspecific tool="lmc"><rate>2</rate></toolspecific></transition>"#,
+        );
+        let changes = from_xml(&rated).unwrap().changes(&net);
+        assert_eq!(changes, [Change::Rate("t".into())]);
+        assert!(!changes[0].alters_runs());
+
+        let edited = WEIGHTED
+            .replace("<text>3</text>", "<text>4</text>")
+            .replace(r#"<place id="q"/>"#, r#"<place id="r"/>"#)
+            .replace(r#"target="q""#, r#"target="r""#)
+            .replace(
+                r#"<transition id="t"/>"#,
+                r#"<transition id="t"/><transition id="u"/>"#,
+            );
+        let changes = from_xml(&edited).unwrap().changes(&net);
+        assert_eq!(
+            changes.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+            [
+                "changed the initial tokens of 'p' from 3 to 4",
+                "added place 'r'",
+                "removed place 'q'",
+                "changed the arcs of transition 't'",
+                "added transition 'u'",
+            ]
+        );
+        assert!(changes.iter().all(|c| c.alters_runs()));
+        // Names are matched, not the order of the places
+        let reordered = from_tina("pl q\npl p (3)\ntr t p*2 -> q*5").unwrap();
+        assert!(reordered.changes(&net).is_empty());
+    }
+
+    #[test]
+    pub fn composition() {
+        let sender = from_tina("tr send a0 -> a1\ntr work a1 -> a0\npl a0 (1)").unwrap();
+        let receiver = from_tina("tr send b0 -> b1\ntr rest b1 -> b0\npl b0 (1)").unwrap();
+        let net = sender.compose(&receiver, &["send"]).unwrap();
+        assert_eq!(net.place_names(), vec!["a0", "a1", "b0", "b1"]);
+        assert_eq!(net.transition_names(), vec!["send", "work", "rest"]);
+        let initial = net.initial_marking();
+        assert_eq!(initial.active_transitions(&net), vec!["send"]);
+        let next = net.next_markings(&initial).unwrap();
+        assert_eq!(net.marking_label(&next[0]), "a1 b1");
+        assert_eq!(net.reachable_markings(1).unwrap().len(), 4);
+
+        assert!(matches!(
+            sender.compose(&receiver, &["work"]),
+            Err(Error::UnknownTransition(t)) if t == "work"
+        ));
+        assert!(matches!(
+            sender.compose(&receiver, &[]),
+            Err(Error::DuplicateTransition(t)) if t == "send"
+        ));
+        assert!(matches!(
+            sender.compose(&sender, &["send", "work"]),
+            Err(Error::DuplicatePlace(p)) if p == "a0"
+        ));
+    }
+
+    #[test]
+    pub fn disk_analysis() {
+        let net = from_tina("tr t a -> b\ntr u b -> c\ntr v x -> y\npl a (3)\npl x (2)").unwrap();
+        // A buffer of a single marking writes a run for every successor
+        let summary = net.disk_analysis(&std::env::temp_dir(), 1).unwrap();
+        assert_eq!(summary.markings, net.reachable_markings(1).unwrap().len());
+        assert_eq!(summary.markings, 30);
+        assert_eq!(summary.deadlocks, 1);
+        assert_eq!(summary.layers, 9);
+        assert!(summary.written > 0);
+    }
+
+    #[test]
+    pub fn golden_files() {
+        let net = from_tina(include_str!("../golden/mutex.net")).unwrap();
+        assert_eq!(net.to_dot(), include_str!("../golden/mutex.dot"));
+        assert_eq!(
+            net.reachability_graph_dot(100).unwrap(),
+            include_str!("../golden/mutex-reachability.dot")
+        );
+    }
+}
@@ -0,0 +1,182 @@
+// Liveness of transitions in the reachability graph
+
+use std::fmt::Display;
+
+use crate::{Marking, MarkingStore, PetriNet, Result};
+
+/// Transitions leaving each reachable marking with the index of the marking they lead to
+type Edges = Vec<Vec<(usize, usize)>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liveness {
+    /// Never enabled in a reachable marking
+    Dead,
+    /// Enabled in some reachable marking, but not live
+    QuasiLive,
+    /// Can be enabled again from every reachable marking
+    Live,
+}
+
+impl Display for Liveness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Liveness::Dead => write!(f, "dead"),
+            Liveness::QuasiLive => write!(f, "quasi-live"),
+            Liveness::Live => write!(f, "live"),
+        }
+    }
+}
+
+impl PetriNet {
+    /// Liveness of every transition, only terminates if finitely many markings are reachable
+    ///
+    /// Every marking reaches a bottom strongly connected component of the reachability graph and
+    /// cannot leave it, so a transition is live iff it is enabled somewhere in every bottom
+    /// component.
+    pub fn liveness(&self) -> Result<Vec<(&str, Liveness)>> {
+        let (_, edges) = self.reachability_graph()?;
+        let mut quasi_live = vec![false; self.transitions.len()];
+        for (t, _) in edges.iter().flatten() {
+            quasi_live[*t] = true;
+        }
+        let (components, count) = components(&edges);
+        let bottom = bottom_components(&edges, &components, count);
+        let mut enabled = vec![vec![false; self.transitions.len()]; count];
+        for (source, successors) in edges.iter().enumerate() {
+            for (t, _) in successors {
+                enabled[components[source]][*t] = true;
+            }
+        }
+
+        Ok(self
+            .transitions
+            .iter()
+            .enumerate()
+            .map(|(t, transition)| {
+                let live = (0..count).all(|c| !bottom[c] || enabled[c][t]);
+                let liveness = match (quasi_live[t], live) {
+                    (false, _) => Liveness::Dead,
+                    (true, false) => Liveness::QuasiLive,
+                    (true, true) => Liveness::Live,
+                };
+                (transition.label.as_str(), liveness)
+            })
+            .collect())
+    }
+
+    /// Markings reachable from every reachable marking, the initial one first if it is one of
+    /// them. These are the markings of the bottom component if there is only one. Only terminates
+    /// if finitely many markings are reachable.
+    pub fn home_markings(&self) -> Result<Vec<Marking>> {
+        let (store, edges) = self.reachability_graph()?;
+        let (components, count) = components(&edges);
+        let bottom = bottom_components(&edges, &components, count);
+        let mut bottoms = (0..count).filter(|c| bottom[*c]);
+        match (bottoms.next(), bottoms.next()) {
+            (Some(home), None) => Ok((0..store.len())
+                .filter(|m| components[*m] == home)
+                .map(|m| store.get(m))
+                .collect()),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Whether the initial marking can be reached again from every reachable marking, i.e. is a
+    /// home marking
+    pub fn reversible(&self) -> Result<bool> {
+        Ok(self
+            .home_markings()?
+            .first()
+            .is_some_and(|home| *home == self.initial_marking()))
+    }
+
+    /// Reachable markings and the transitions leading from each to the others
+    fn reachability_graph(&self) -> Result<(MarkingStore, Edges)> {
+        let mut store = MarkingStore::new();
+        store.insert(&self.initial_marking());
+        let mut edges: Edges = vec![];
+        while edges.len() < store.len() {
+            let marking = store.get(edges.len());
+            let mut successors = vec![];
+            for (t, transition) in self.transitions.iter().enumerate() {
+                if transition.enabled(&marking) {
+                    let (target, _) = store.insert(&marking.fire(self, transition)?);
+                    successors.push((t, target));
+                }
+            }
+            edges.push(successors);
+        }
+        Ok((store, edges))
+    }
+}
+
+/// Whether each component is a bottom one, which no edge leaves
+fn bottom_components(edges: &Edges, components: &[usize], count: usize) -> Vec<bool> {
+    let mut bottom = vec![true; count];
+    for (source, successors) in edges.iter().enumerate() {
+        for (_, target) in successors {
+            if components[*target] != components[source] {
+                bottom[components[source]] = false;
+            }
+        }
+    }
+    bottom
+}
+
+/// Strongly connected component of every vertex and the number of components (Tarjan)
+fn components(edges: &Edges) -> (Vec<usize>, usize) {
+    const UNVISITED: usize = usize::MAX;
+    let n = edges.len();
+    let mut index = vec![UNVISITED; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut component = vec![UNVISITED; n];
+    let mut stack = vec![];
+    let mut count = 0;
+    let mut next = 0;
+    for root in 0..n {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        // Vertices with the position of the next edge to follow
+        let mut path = vec![(root, 0)];
+        index[root] = next;
+        low[root] = next;
+        next += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        while let Some((v, edge)) = path.last_mut() {
+            let v = *v;
+            if let Some((_, w)) = edges[v].get(*edge) {
+                *edge += 1;
+                let w = *w;
+                if index[w] == UNVISITED {
+                    index[w] = next;
+                    low[w] = next;
+                    next += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    path.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+            path.pop();
+            if let Some((parent, _)) = path.last() {
+                low[*parent] = low[*parent].min(low[v]);
+            }
+            if low[v] == index[v] {
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component[w] = count;
+                    if w == v {
+                        break;
+                    }
+                }
+                count += 1;
+            }
+        }
+    }
+    (component, count)
+}
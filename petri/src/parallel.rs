@@ -0,0 +1,64 @@
+// Parallel explicit-state exploration
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+use crate::{Error, Marking, PetriNet, Result};
+
+/// Shards per thread, more shards than threads keep contention low
+const SHARDS_PER_JOB: usize = 16;
+
+impl PetriNet {
+    /// Every reachable marking, explored by `jobs` threads or one per core if `jobs` is 0
+    ///
+    /// The markings are explored breadth first one level at a time. The threads insert the
+    /// successors of the frontier into a visited set split into shards by hash, each behind its own
+    /// lock, and the successors which were new in their shard form the next frontier.
+    pub fn reachable_markings(&self, jobs: usize) -> Result<Vec<Marking>> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()
+            .map_err(|e| Error::ThreadPool(e.to_string()))?;
+        let shards: Vec<Mutex<HashSet<Marking>>> = (0..jobs.max(1) * SHARDS_PER_JOB)
+            .map(|_| Mutex::new(HashSet::new()))
+            .collect();
+        // Whether the marking was not visited before
+        let insert = |marking: &Marking| {
+            let mut hasher = DefaultHasher::new();
+            marking.hash(&mut hasher);
+            let shard = hasher.finish() as usize % shards.len();
+            shards[shard].lock().unwrap().insert(marking.clone())
+        };
+
+        let initial = self.initial_marking();
+        insert(&initial);
+        let mut frontier = vec![initial];
+        pool.install(|| -> Result<()> {
+            while !frontier.is_empty() {
+                frontier = frontier
+                    .par_iter()
+                    .map(|marking| {
+                        Ok(self
+                            .next_markings(marking)?
+                            .into_iter()
+                            .filter(|m| insert(m))
+                            .collect::<Vec<_>>())
+                    })
+                    .collect::<Result<Vec<_>>>()?
+                    .into_iter()
+                    .flatten()
+                    .collect();
+            }
+            Ok(())
+        })?;
+        Ok(shards
+            .into_iter()
+            .flat_map(|shard| shard.into_inner().unwrap())
+            .collect())
+    }
+}
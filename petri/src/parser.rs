@@ -1,7 +1,119 @@
 use super::PetriNet;
-use crate::error::Result;
+use crate::error::{Error, Result};
 use serde_derive::Deserialize;
 use serde_xml_rs::from_str;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "fs")]
+use std::fs;
+use std::path::Path;
+
+mod colored;
+mod lola;
+mod tina;
+
+pub use colored::from_colored_xml;
+pub use lola::from_lola;
+pub use tina::from_tina;
+
+/// Formats nets are read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Pnml,
+    /// The textual `.net` format of TINA
+    Tina,
+    Lola,
+}
+
+impl Format {
+    /// Format given by the extension of the file, otherwise guessed from the content
+    pub fn detect(path: &Path, input: &str) -> Format {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("pnml" | "xml") => Format::Pnml,
+            Some("net" | "ndr") => Format::Tina,
+            Some("lola" | "llnet") => Format::Lola,
+            _ => {
+                if input.trim_start().starts_with('<') {
+                    Format::Pnml
+                } else if lola::starts_net(input) {
+                    Format::Lola
+                } else {
+                    Format::Tina
+                }
+            }
+        }
+    }
+}
+
+/// Read the net in the given format
+pub fn parse(input: &str, format: Format) -> Result<PetriNet> {
+    match format {
+        Format::Pnml => from_xml(input),
+        Format::Tina => from_tina(input),
+        Format::Lola => from_lola(input),
+    }
+}
+
+/// Read the net from the file in the detected format
+#[cfg(feature = "fs")]
+pub fn from_file(path: impl AsRef<Path>) -> Result<PetriNet> {
+    let input = fs::read_to_string(path.as_ref())?;
+    parse(&input, Format::detect(path.as_ref(), &input))
+}
+
+/// Places, transitions and arcs in the order they were declared
+#[derive(Default)]
+struct Elements {
+    places: Vec<(String, usize)>,
+    place_index: HashMap<String, usize>,
+    transitions: Vec<String>,
+    transition_set: HashSet<String>,
+    arcs: Vec<(String, String, usize)>,
+}
+
+impl Elements {
+    /// Declare the place if it is new, a positive marking replaces the previous one
+    fn place(&mut self, label: &str, marking: usize) {
+        match self.place_index.get(label) {
+            Some(index) if marking > 0 => self.places[*index].1 = marking,
+            Some(_) => {}
+            None => {
+                self.place_index
+                    .insert(label.to_string(), self.places.len());
+                self.places.push((label.to_string(), marking));
+            }
+        }
+    }
+
+    fn has_place(&self, label: &str) -> bool {
+        self.place_index.contains_key(label)
+    }
+
+    /// Declare the transition if it is new
+    fn transition(&mut self, label: &str) {
+        if self.transition_set.insert(label.to_string()) {
+            self.transitions.push(label.to_string());
+        }
+    }
+
+    fn arc(&mut self, source: &str, target: &str, weight: usize) {
+        self.arcs
+            .push((source.to_string(), target.to_string(), weight));
+    }
+
+    fn build(self) -> Result<PetriNet> {
+        let mut net = PetriNet::new();
+        for (place, marking) in self.places {
+            net.add_place(place, marking)?;
+        }
+        for transition in self.transitions {
+            net.add_transition(transition)?;
+        }
+        for (source, target, weight) in self.arcs {
+            net.add_arc(source, target, weight)?;
+        }
+        Ok(net)
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct Pnml {
@@ -17,11 +129,11 @@ struct Net {
 
 #[derive(Debug, Deserialize)]
 struct Page {
-    #[serde(rename = "place")]
+    #[serde(rename = "place", default)]
     places: Vec<Place>,
-    #[serde(rename = "transition")]
+    #[serde(rename = "transition", default)]
     transitions: Vec<Transition>,
-    #[serde(rename = "arc")]
+    #[serde(rename = "arc", default)]
     arcs: Vec<Arc>,
 }
 
@@ -40,15 +152,37 @@ struct InitialMarking {
 #[derive(Debug, Deserialize)]
 struct Transition {
     id: String,
+    #[serde(default)]
+    toolspecific: Vec<ToolSpecific>,
+}
+
+/// Extension of a tool, the rate of a transition or its mean delay
+#[derive(Debug, Deserialize)]
+struct ToolSpecific {
+    rate: Option<f64>,
+    delay: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Arc {
+    #[serde(default)]
+    id: String,
     source: String,
     target: String,
+    /// Weight of the arc, 1 if it is missing
+    inscription: Option<Inscription>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Inscription {
+    text: usize,
 }
 
+/// Read a net in PNML, colored nets are unfolded
 pub fn from_xml(input: &str) -> Result<PetriNet> {
+    if colored::is_colored(input) {
+        return from_colored_xml(input)?.unfold();
+    }
     let raw_pnml: Pnml = from_str(input)?;
     let raw_net = raw_pnml.net;
     let mut net = PetriNet::new();
@@ -77,11 +211,70 @@ pub fn from_xml(input: &str) -> Result<PetriNet> {
     }
 
     for transition in transitions {
-        net.add_transition(transition.id)?;
+        let rate = transition.toolspecific.iter().find_map(|t| match t {
+            ToolSpecific {
+                rate: Some(rate), ..
+            } => Some(*rate),
+            ToolSpecific {
+                delay: Some(delay), ..
+            } => Some(1.0 / delay),
+            _ => None,
+        });
+        net.add_transition(transition.id.clone())?;
+        if let Some(rate) = rate {
+            net.set_rate(&transition.id, rate)?;
+        }
     }
 
+    // Parallel arcs add up to a single weighted one
+    let mut ids: HashMap<String, usize> = HashMap::new();
     for arc in arcs {
-        net.add_arc(arc.source, arc.target)?;
+        let occurrence = ids.entry(arc.id.clone()).or_default();
+        *occurrence += 1;
+        let occurrence = *occurrence - 1;
+        let invalid = |reason: String| {
+            // Locate the arc by its id, which is required, or else by its source
+            let position = if arc.id.is_empty() {
+                input.find(&format!("source=\"{}\"", arc.source))
+            } else {
+                input
+                    .match_indices(&format!("id=\"{}\"", arc.id))
+                    .nth(occurrence)
+                    .map(|(position, _)| position)
+            };
+            let line = position.map_or(1, |p| input[..p].matches('\n').count() + 1);
+            Err(Error::Pnml { line, reason })
+        };
+        let is_place = |id: &str| match (net.place_labels.contains_key(id), net.has_transition(id))
+        {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        };
+        let weight = arc.inscription.as_ref().map_or(1, |i| i.text);
+        if !arc.id.is_empty() && occurrence > 0 {
+            return invalid(format!("duplicate arc '{}'", arc.id));
+        }
+        match (is_place(&arc.source), is_place(&arc.target)) {
+            (None, _) => return invalid(format!("undefined source '{}'", arc.source)),
+            (_, None) => return invalid(format!("undefined target '{}'", arc.target)),
+            (Some(a), Some(b)) if a == b => {
+                return invalid(format!(
+                    "arc from '{}' to '{}' connects two {}",
+                    arc.source,
+                    arc.target,
+                    if a { "places" } else { "transitions" }
+                ))
+            }
+            _ if weight == 0 => {
+                return invalid(format!(
+                    "arc from '{}' to '{}' has weight 0",
+                    arc.source, arc.target
+                ))
+            }
+            _ => {}
+        }
+        net.add_arc(arc.source, arc.target, weight)?;
     }
 
     Ok(net)
@@ -0,0 +1,369 @@
+// Symmetric nets in PNML, the colored nets of the model checking contest
+
+use std::collections::HashMap;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::colored::{ColoredArc, ColoredNet, ColoredPlace, ColoredTransition, Guard, Term};
+use crate::{Error, Result};
+
+/// Element of the document with its attributes and child elements, text is dropped
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Result<&str> {
+        self.attributes
+            .get(name)
+            .map(|a| a.as_str())
+            .ok_or_else(|| {
+                invalid(format!(
+                    "missing attribute '{}' of element '{}'",
+                    name, self.name
+                ))
+            })
+    }
+
+    fn child(&self, name: &str) -> Result<&Element> {
+        self.children
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| invalid(format!("missing '{}' in element '{}'", name, self.name)))
+    }
+
+    /// The only child element, looking through `subterm` and `structure` wrappers
+    fn inner(&self) -> Result<&Element> {
+        match self.children.as_slice() {
+            [child] if child.name == "subterm" || child.name == "structure" => child.inner(),
+            [child] => Ok(child),
+            _ => Err(invalid(format!(
+                "expected a single element in '{}'",
+                self.name
+            ))),
+        }
+    }
+
+    /// Terms of the `subterm` children
+    fn subterms(&self) -> Vec<&Element> {
+        self.children
+            .iter()
+            .filter(|c| c.name == "subterm")
+            .collect()
+    }
+
+    /// Elements with the name anywhere below this one, in document order
+    fn descendants<'a>(&'a self, name: &str, found: &mut Vec<&'a Element>) {
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            }
+            child.descendants(name, found);
+        }
+    }
+}
+
+fn invalid(reason: String) -> Error {
+    Error::Colored(reason)
+}
+
+fn element(start: &BytesStart) -> Result<Element> {
+    let name = String::from_utf8_lossy(start.local_name()).into_owned();
+    let mut attributes = HashMap::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(|e| invalid(e.to_string()))?;
+        let value = attribute
+            .unescaped_value()
+            .map_err(|e| invalid(e.to_string()))?;
+        attributes.insert(
+            String::from_utf8_lossy(attribute.key).into_owned(),
+            String::from_utf8_lossy(&value).into_owned(),
+        );
+    }
+    Ok(Element {
+        name,
+        attributes,
+        children: vec![],
+    })
+}
+
+fn document(input: &str) -> Result<Element> {
+    let mut reader = Reader::from_str(input);
+    reader.trim_text(true);
+    let mut buffer = vec![];
+    // Open elements, the first one collects the root
+    let mut open = vec![Element {
+        name: String::new(),
+        attributes: HashMap::new(),
+        children: vec![],
+    }];
+    loop {
+        match reader
+            .read_event(&mut buffer)
+            .map_err(|e| invalid(e.to_string()))?
+        {
+            Event::Start(start) => open.push(element(&start)?),
+            Event::Empty(start) => {
+                let element = element(&start)?;
+                open.last_mut().unwrap().children.push(element);
+            }
+            Event::End(_) => {
+                let element = open.pop().unwrap();
+                open.last_mut()
+                    .ok_or_else(|| invalid("unbalanced end tag".into()))?
+                    .children
+                    .push(element);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buffer.clear();
+    }
+    match open.pop() {
+        Some(mut document) if open.is_empty() && document.children.len() == 1 => {
+            Ok(document.children.remove(0))
+        }
+        _ => Err(invalid("unbalanced elements".into())),
+    }
+}
+
+/// Whether the PNML document describes a colored net
+pub fn is_colored(input: &str) -> bool {
+    input.contains("symmetricnet") || input.contains("<declaration>")
+}
+
+/// Read a symmetric net, the colored nets of the model checking contest
+///
+/// Supported are sorts which are cyclic or finite enumerations, integer ranges, products of these
+/// and the dot sort, inscriptions built from constants, variables, tuples, successors, `all`,
+/// `numberof` and `add`, and guards comparing colors for (in)equality.
+pub fn from_colored_xml(input: &str) -> Result<ColoredNet> {
+    let root = document(input)?;
+    let mut net = ColoredNet::default();
+    let mut reader = Declarations::default();
+
+    net.basic_sorts.push(vec!["dot".into()]);
+    net.sorts.insert("dot".into(), vec![0]);
+    let mut sorts = vec![];
+    root.descendants("namedsort", &mut sorts);
+    // Products refer to other sorts, which are declared first
+    for sort in &sorts {
+        let id = sort.attribute("id")?.to_string();
+        let definition = sort.inner()?;
+        let basic = net.basic_sorts.len();
+        let colors = match definition.name.as_str() {
+            "cyclicenumeration" | "finiteenumeration" => definition
+                .children
+                .iter()
+                .enumerate()
+                .map(|(index, constant)| {
+                    let constant_id = constant.attribute("id")?;
+                    reader
+                        .constants
+                        .insert(constant_id.to_string(), (basic, index));
+                    Ok(constant
+                        .attribute("name")
+                        .unwrap_or(constant_id)
+                        .to_string())
+                })
+                .collect::<Result<Vec<_>>>()?,
+            "finiteintrange" => {
+                let (start, end) = range(definition)?;
+                reader.ranges.insert((start, end), basic);
+                (start..=end).map(|i| i.to_string()).collect()
+            }
+            "productsort" => continue,
+            "dot" => vec!["dot".into()],
+            other => return Err(invalid(format!("unsupported sort '{}'", other))),
+        };
+        net.basic_sorts.push(colors);
+        net.sorts.insert(id, vec![basic]);
+    }
+    for sort in &sorts {
+        let definition = sort.inner()?;
+        if definition.name == "productsort" {
+            let mut components = vec![];
+            for component in &definition.children {
+                components.extend(net.sorts[sort_of(component)?].clone());
+            }
+            net.sorts
+                .insert(sort.attribute("id")?.to_string(), components);
+        }
+    }
+
+    let mut variables = vec![];
+    root.descendants("variabledecl", &mut variables);
+    for variable in variables {
+        net.variables.insert(
+            variable.attribute("id")?.to_string(),
+            sort_of(variable.inner()?)?.to_string(),
+        );
+    }
+
+    let mut places = vec![];
+    root.descendants("place", &mut places);
+    for place in places {
+        let initial_marking = match place.children.iter().find(|c| c.name == "hlinitialMarking") {
+            Some(marking) => Some(reader.term(marking.child("structure")?.inner()?)?),
+            None => None,
+        };
+        net.places.push(ColoredPlace {
+            label: place.attribute("id")?.to_string(),
+            sort: sort_of(place.child("type")?.child("structure")?.inner()?)?.to_string(),
+            initial_marking,
+        });
+    }
+
+    let mut transitions = vec![];
+    root.descendants("transition", &mut transitions);
+    for transition in transitions {
+        let guard = match transition.children.iter().find(|c| c.name == "condition") {
+            Some(condition) => reader.guard(condition.child("structure")?.inner()?)?,
+            None => Guard::Constant(true),
+        };
+        net.transitions.push(ColoredTransition {
+            label: transition.attribute("id")?.to_string(),
+            guard,
+        });
+    }
+
+    let mut arcs = vec![];
+    root.descendants("arc", &mut arcs);
+    for arc in arcs {
+        let inscription = match arc.children.iter().find(|c| c.name == "hlinscription") {
+            Some(inscription) => reader.term(inscription.child("structure")?.inner()?)?,
+            None => Term::Constant(0, 0),
+        };
+        net.arcs.push(ColoredArc {
+            source: arc.attribute("source")?.to_string(),
+            target: arc.attribute("target")?.to_string(),
+            inscription,
+        });
+    }
+    Ok(net)
+}
+
+/// Id of the sort referred to by a `usersort` or `dot` element
+fn sort_of(element: &Element) -> Result<&str> {
+    match element.name.as_str() {
+        "usersort" => element.attribute("declaration"),
+        "dot" => Ok("dot"),
+        other => Err(invalid(format!("expected a sort, found '{}'", other))),
+    }
+}
+
+fn range(element: &Element) -> Result<(i64, i64)> {
+    let bound = |name: &str| {
+        let value = element.attribute(name)?;
+        value
+            .parse()
+            .map_err(|_| invalid(format!("invalid bound '{}' of a range", value)))
+    };
+    Ok((bound("start")?, bound("end")?))
+}
+
+/// Constants of the declared sorts
+#[derive(Default)]
+struct Declarations {
+    /// Enumeration constants with their basic sort and index in it
+    constants: HashMap<String, (usize, usize)>,
+    /// Bounds of integer ranges with their basic sort
+    ranges: HashMap<(i64, i64), usize>,
+}
+
+impl Declarations {
+    fn term(&self, element: &Element) -> Result<Term> {
+        let subterms = || {
+            element
+                .subterms()
+                .into_iter()
+                .map(|s| self.term(s.inner()?))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(match element.name.as_str() {
+            "add" => Term::Add(subterms()?),
+            "tuple" => Term::Tuple(subterms()?),
+            "numberof" => match element.subterms().as_slice() {
+                [count, term] => {
+                    let count = count.inner()?.attribute("value")?;
+                    let count = count
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid count '{}'", count)))?;
+                    Term::NumberOf(count, Box::new(self.term(term.inner()?)?))
+                }
+                _ => return Err(invalid("expected a count and a term in 'numberof'".into())),
+            },
+            "all" => Term::All(sort_of(element.inner()?)?.to_string()),
+            "successor" => Term::Successor(Box::new(self.term(element.inner()?)?)),
+            "predecessor" => Term::Predecessor(Box::new(self.term(element.inner()?)?)),
+            "variable" => Term::Variable(element.attribute("refvariable")?.to_string()),
+            "dotconstant" => Term::Constant(0, 0),
+            "useroperator" => {
+                let constant = element.attribute("declaration")?;
+                let (sort, index) = self
+                    .constants
+                    .get(constant)
+                    .ok_or_else(|| invalid(format!("unknown constant '{}'", constant)))?;
+                Term::Constant(*sort, *index)
+            }
+            "finiteintrangeconstant" => {
+                let (start, end) = range(element.child("finiteintrange")?)?;
+                let value: i64 = element.attribute("value")?.parse().map_err(|_| {
+                    invalid(format!(
+                        "invalid integer constant in range {}..{}",
+                        start, end
+                    ))
+                })?;
+                let sort = self
+                    .ranges
+                    .get(&(start, end))
+                    .ok_or_else(|| invalid(format!("undeclared range {}..{}", start, end)))?;
+                if value < start || value > end {
+                    return Err(invalid(format!(
+                        "constant {} outside of its range {}..{}",
+                        value, start, end
+                    )));
+                }
+                Term::Constant(*sort, (value - start) as usize)
+            }
+            other => return Err(invalid(format!("unsupported term '{}'", other))),
+        })
+    }
+
+    fn guard(&self, element: &Element) -> Result<Guard> {
+        let operands = || {
+            element
+                .subterms()
+                .into_iter()
+                .map(|s| self.term(s.inner()?))
+                .collect::<Result<Vec<_>>>()
+        };
+        let guards = || {
+            element
+                .subterms()
+                .into_iter()
+                .map(|s| self.guard(s.inner()?))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(match element.name.as_str() {
+            "booleanconstant" => Guard::Constant(element.attribute("value")? == "true"),
+            "equality" | "inequality" => {
+                let [lhs, rhs]: [Term; 2] = operands()?
+                    .try_into()
+                    .map_err(|_| invalid(format!("expected two operands in '{}'", element.name)))?;
+                if element.name == "equality" {
+                    Guard::Equality(lhs, rhs)
+                } else {
+                    Guard::Inequality(lhs, rhs)
+                }
+            }
+            "and" => Guard::And(guards()?),
+            "or" => Guard::Or(guards()?),
+            "not" => Guard::Not(Box::new(self.guard(element.inner()?)?)),
+            other => return Err(invalid(format!("unsupported condition '{}'", other))),
+        })
+    }
+}
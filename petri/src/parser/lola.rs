@@ -0,0 +1,198 @@
+// The net format of the LoLA model checker
+
+use super::Elements;
+use crate::{Error, PetriNet, Result};
+
+const DELIMITERS: &[char] = &[',', ';', ':', '(', ')', '{', '}'];
+
+/// Read a net such as
+///
+/// ```text
+/// PLACE SAFE: p1, p2; p3;
+/// MARKING p1: 1, p3: 2;
+/// TRANSITION t CONSUME p1: 1, p3: 1; PRODUCE p2: 1;
+/// ```
+///
+/// Capacities and fairness assumptions are dropped, comments are enclosed in braces or C style.
+pub fn from_lola(input: &str) -> Result<PetriNet> {
+    let tokens = tokens(input).map_err(|(line, reason)| Error::Lola { line, reason })?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        elements: Elements::default(),
+    };
+    parser.net().map_err(|reason| Error::Lola {
+        line: parser.line(),
+        reason,
+    })?;
+    parser.elements.build()
+}
+
+/// Whether the first keyword of the input starts a LoLA net
+pub(super) fn starts_net(input: &str) -> bool {
+    tokens(input).is_ok_and(|tokens| tokens.first().map(|(token, _)| *token) == Some("PLACE"))
+}
+
+struct Parser<'a> {
+    /// Tokens with their lines
+    tokens: Vec<(&'a str, usize)>,
+    position: usize,
+    elements: Elements,
+}
+
+impl<'a> Parser<'a> {
+    fn net(&mut self) -> std::result::Result<(), String> {
+        self.expect("PLACE")?;
+        while !self.accept("MARKING") {
+            if self.accept("SAFE") {
+                if self.peek() != Some(":") {
+                    self.number()?;
+                }
+                self.expect(":")?;
+            }
+            for (place, _) in self.list(false)? {
+                if self.elements.has_place(place) {
+                    return Err(format!("duplicate place '{}'", place));
+                }
+                self.elements.place(place, 0);
+            }
+        }
+        for (place, tokens) in self.list(true)? {
+            if !self.elements.has_place(place) {
+                return Err(format!("unknown place '{}'", place));
+            }
+            self.elements.place(place, tokens);
+        }
+        while self.peek().is_some() {
+            self.expect("TRANSITION")?;
+            let transition = self.name()?;
+            self.elements.transition(transition);
+            if self.accept("STRONG") || self.accept("WEAK") {
+                self.expect("FAIR")?;
+            }
+            self.expect("CONSUME")?;
+            for (place, weight) in self.list(true)? {
+                self.known(place)?;
+                self.elements.arc(place, transition, weight);
+            }
+            self.expect("PRODUCE")?;
+            for (place, weight) in self.list(true)? {
+                self.known(place)?;
+                self.elements.arc(transition, place, weight);
+            }
+        }
+        Ok(())
+    }
+
+    /// Comma separated names, optionally with a count, terminated by a semicolon
+    fn list(&mut self, counts: bool) -> std::result::Result<Vec<(&'a str, usize)>, String> {
+        let mut list = vec![];
+        if self.accept(";") {
+            return Ok(list);
+        }
+        loop {
+            let name = self.name()?;
+            let count = if counts && self.accept(":") {
+                self.number()?
+            } else {
+                1
+            };
+            list.push((name, count));
+            if self.accept(";") {
+                return Ok(list);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn known(&self, place: &str) -> std::result::Result<(), String> {
+        if self.elements.has_place(place) {
+            Ok(())
+        } else {
+            Err(format!("unknown place '{}'", place))
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).map(|(token, _)| *token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn accept(&mut self, token: &str) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> std::result::Result<(), String> {
+        if self.accept(token) {
+            Ok(())
+        } else {
+            Err(match self.peek() {
+                Some(found) => format!("expected '{}', found '{}'", token, found),
+                None => format!("expected '{}' at the end of the input", token),
+            })
+        }
+    }
+
+    fn name(&mut self) -> std::result::Result<&'a str, String> {
+        match self.peek() {
+            Some(name) if !name.starts_with(DELIMITERS) => {
+                self.position += 1;
+                Ok(name)
+            }
+            Some(found) => Err(format!("expected a name, found '{}'", found)),
+            None => Err("expected a name at the end of the input".into()),
+        }
+    }
+
+    fn number(&mut self) -> std::result::Result<usize, String> {
+        let token = self.name()?;
+        token
+            .parse()
+            .map_err(|_| format!("expected a number, found '{}'", token))
+    }
+}
+
+/// Names and delimiters with their lines, or the line and reason of an unterminated comment
+fn tokens(input: &str) -> std::result::Result<Vec<(&str, usize)>, (usize, String)> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        let (token, length) = if c.is_whitespace() {
+            (None, c.len_utf8())
+        } else if let Some(comment) = rest.strip_prefix('{') {
+            let end = comment
+                .find('}')
+                .ok_or((line, "unterminated comment".to_string()))?;
+            (None, end + 2)
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment
+                .find("*/")
+                .ok_or((line, "unterminated comment".to_string()))?;
+            (None, end + 4)
+        } else if rest.starts_with("//") {
+            (None, rest.find('\n').unwrap_or(rest.len()))
+        } else if DELIMITERS.contains(&c) {
+            (Some(&rest[..1]), 1)
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || DELIMITERS.contains(&c))
+                .unwrap_or(rest.len());
+            (Some(&rest[..end]), end)
+        };
+        tokens.extend(token.map(|token| (token, line)));
+        line += rest[..length].matches('\n').count();
+        rest = &rest[length..];
+    }
+    Ok(tokens)
+}
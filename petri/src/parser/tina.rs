@@ -0,0 +1,198 @@
+// The textual net format of the TINA toolbox
+
+use super::Elements;
+use crate::{Error, PetriNet, Result};
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Arrow,
+    Colon,
+    Interval,
+    Marking(usize),
+    /// Weight of a normal arc
+    Weight(usize),
+    /// Weight of a test arc
+    Test(usize),
+}
+
+/// Read a net whose lines describe transitions with their arcs, places with their marking and
+/// arcs, or something ignored here such as labels, priorities and notes:
+///
+/// ```text
+/// net philosophers
+/// tr eat fork*2 think -> eating
+/// pl fork (5)
+/// ```
+///
+/// Places and transitions are declared by their first occurrence, time intervals are dropped and
+/// test arcs `?n` are read as an arc consuming and an arc producing n tokens. Inhibitor and reset
+/// arcs cannot be represented.
+pub fn from_tina(input: &str) -> Result<PetriNet> {
+    let mut elements = Elements::default();
+    for (line, text) in input.lines().enumerate() {
+        let invalid = |reason: String| Error::Tina {
+            line: line + 1,
+            reason,
+        };
+        let tokens = tokens(text).map_err(invalid)?;
+        description(&mut elements, &tokens).map_err(invalid)?;
+    }
+    elements.build()
+}
+
+fn description(elements: &mut Elements, tokens: &[Token]) -> std::result::Result<(), String> {
+    let (kind, rest) = match tokens {
+        [] => return Ok(()),
+        [Token::Name(kind), rest @ ..] => (kind.as_str(), rest),
+        [token, ..] => return Err(format!("expected a keyword, found {:?}", token)),
+    };
+    if !matches!(kind, "tr" | "pl") {
+        return match kind {
+            "net" | "lb" | "pr" | "nt" => Ok(()),
+            _ => Err(format!("unknown description '{}'", kind)),
+        };
+    }
+    let (name, mut rest) = match rest {
+        [Token::Name(name), rest @ ..] => (name.clone(), rest),
+        _ => return Err(format!("expected a name after '{}'", kind)),
+    };
+    if let [Token::Colon, Token::Name(_), tail @ ..] = rest {
+        rest = tail;
+    }
+    if kind == "tr" {
+        elements.transition(&name);
+        if let [Token::Interval, tail @ ..] = rest {
+            rest = tail;
+        }
+    } else {
+        let marking = match rest {
+            [Token::Marking(marking), tail @ ..] => {
+                rest = tail;
+                *marking
+            }
+            _ => 0,
+        };
+        elements.place(&name, marking);
+    }
+
+    let mut producing = false;
+    while let Some((token, tail)) = rest.split_first() {
+        rest = tail;
+        let other = match token {
+            Token::Arrow if !producing => {
+                producing = true;
+                continue;
+            }
+            Token::Name(other) => other.clone(),
+            token => return Err(format!("unexpected {:?}", token)),
+        };
+        let (weight, test) = match rest {
+            [Token::Weight(weight), tail @ ..] => {
+                rest = tail;
+                (*weight, false)
+            }
+            [Token::Test(weight), tail @ ..] if kind == "tr" && !producing => {
+                rest = tail;
+                (*weight, true)
+            }
+            _ => (1, false),
+        };
+        // Arcs of transitions connect places and the other way around
+        let (place, transition) = if kind == "tr" {
+            elements.place(&other, 0);
+            (other, name.clone())
+        } else {
+            elements.transition(&other);
+            (name.clone(), other)
+        };
+        // Inputs of transitions are consumed, inputs of places produced
+        if (kind == "tr") != producing || test {
+            elements.arc(&place, &transition, weight);
+        }
+        if (kind == "tr") == producing || test {
+            elements.arc(&transition, &place, weight);
+        }
+    }
+    Ok(())
+}
+
+fn tokens(text: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '#' => break,
+            ':' => Token::Colon,
+            '-' if chars.next_if_eq(&'>').is_some() => Token::Arrow,
+            '[' | ']' => {
+                // Both ends of an interval are '[' or ']'
+                chars.by_ref().find(|c| *c == '[' || *c == ']');
+                Token::Interval
+            }
+            '(' => {
+                let marking: String = chars.by_ref().take_while(|c| *c != ')').collect();
+                Token::Marking(number(marking.trim())?)
+            }
+            '*' => Token::Weight(number(&word(&mut chars))?),
+            '?' | '!' => {
+                if chars.next_if_eq(&'-').is_some() {
+                    return Err("inhibitor arcs are not supported".into());
+                }
+                if c == '!' {
+                    return Err("reset and stopwatch arcs are not supported".into());
+                }
+                Token::Test(number(&word(&mut chars))?)
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => name.extend(chars.next()),
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err("unterminated name".into()),
+                    }
+                }
+                Token::Name(name)
+            }
+            c if is_name(c) => {
+                let mut name = c.to_string();
+                name.push_str(&word(&mut chars));
+                Token::Name(name)
+            }
+            c => return Err(format!("unexpected character '{}'", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn is_name(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '\'' | '.')
+}
+
+fn word(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut word = String::new();
+    while let Some(c) = chars.next_if(|c| is_name(*c)) {
+        word.push(c);
+    }
+    word
+}
+
+/// Numbers may be scaled by the suffixes K and M
+fn number(text: &str) -> std::result::Result<usize, String> {
+    let (digits, scale) = match text.strip_suffix('K') {
+        Some(digits) => (digits, 1_000),
+        None => match text.strip_suffix('M') {
+            Some(digits) => (digits, 1_000_000),
+            None => (text, 1),
+        },
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(scale))
+        .ok_or_else(|| format!("invalid number '{}'", text))
+}
@@ -0,0 +1,468 @@
+// Queries over the reachable markings of a net, such as
+
+use std::fmt::Display;
+
+use crate::{Bound, Error, Marking, MarkingStore, PetriNet, Result};
+
+/// Reachable markings numbered in breadth first order with the deadlocks among them, so any
+/// number of queries are answered without firing a transition again
+#[derive(Debug, Clone, Default)]
+pub struct MarkingIndex {
+    store: MarkingStore,
+    deadlocks: Vec<bool>,
+}
+
+impl MarkingIndex {
+    /// Marking with the number
+    pub fn get(&self, index: usize) -> Marking {
+        self.store.get(index)
+    }
+
+    pub fn is_deadlock(&self, index: usize) -> bool {
+        self.deadlocks[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+}
+
+impl PetriNet {
+    /// Explore the reachable markings. Fails if a place is unbounded.
+    pub fn marking_index(&self) -> Result<MarkingIndex> {
+        if let Some((place, _)) = (self.bounds().into_iter()).find(|(_, b)| *b == Bound::Unbounded)
+        {
+            return Err(Error::Unbounded(place.to_string()));
+        }
+        let mut store = MarkingStore::new();
+        store.insert(&self.initial_marking());
+        let mut deadlocks = vec![];
+        while deadlocks.len() < store.len() {
+            let successors = store.get(deadlocks.len()).next(self)?;
+            for (_, successor) in &successors {
+                store.insert(successor);
+            }
+            deadlocks.push(successors.is_empty());
+        }
+        Ok(MarkingIndex { store, deadlocks })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantifier {
+    Count,
+    Exists,
+    Forall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+/// Sum of token counts of places and numbers, each added or subtracted
+type Sum = Vec<(bool, Term)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Place(usize),
+    Number(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    True,
+    False,
+    Deadlock,
+    Compare(Sum, Comparison, Sum),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+/// A query about the reachable markings of a net, such as
+///
+/// ```text
+/// count where p3>0 and deadlock
+/// exists marking where p1+p2>4
+/// forall markings where not (p1>0 and p2>0)
+/// ```
+///
+/// Conditions combine comparisons of sums of token counts and numbers with 'and', 'or', 'not'
+/// and parentheses, 'deadlock' holds in markings enabling no transition. Places whose names are
+/// no plain words are written in double quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub quantifier: Quantifier,
+    condition: Condition,
+    text: String,
+}
+
+/// Answer to a query, markings are given by their number in the `MarkingIndex`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    /// Markings satisfying the condition and all markings
+    Count(usize, usize),
+    /// A marking satisfying the condition, if one does
+    Exists(Option<usize>),
+    /// A marking violating the condition, if one does
+    Forall(Option<usize>),
+}
+
+impl Answer {
+    /// Whether the condition holds for some or for every marking, none for counts
+    pub fn holds(&self) -> Option<bool> {
+        match self {
+            Answer::Count(..) => None,
+            Answer::Exists(witness) => Some(witness.is_some()),
+            Answer::Forall(violation) => Some(violation.is_none()),
+        }
+    }
+
+    /// Number of the marking satisfying an exists query or violating a forall query
+    pub fn marking(&self) -> Option<usize> {
+        match self {
+            Answer::Count(..) => None,
+            Answer::Exists(marking) | Answer::Forall(marking) => *marking,
+        }
+    }
+}
+
+impl Query {
+    /// Parse the query, the places have to belong to the net
+    pub fn parse(net: &PetriNet, text: &str) -> Result<Query> {
+        let invalid = |reason: String| Error::Query(format!("{} in '{}'", reason, text.trim()));
+        let tokens = tokens(text).map_err(invalid)?;
+        let mut parser = Parser {
+            net,
+            tokens: &tokens,
+            position: 0,
+        };
+        let query = parser.query(text.trim()).map_err(invalid)?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(token) => Err(invalid(format!("unexpected {}", token))),
+        }
+    }
+
+    /// Answer the query about the markings of the index
+    pub fn answer(&self, markings: &MarkingIndex) -> Answer {
+        let mut satisfying = (0..markings.len()).filter(|i| self.holds(markings, *i));
+        match self.quantifier {
+            Quantifier::Count => Answer::Count(satisfying.count(), markings.len()),
+            Quantifier::Exists => Answer::Exists(satisfying.next()),
+            Quantifier::Forall => {
+                Answer::Forall((0..markings.len()).find(|i| !self.holds(markings, *i)))
+            }
+        }
+    }
+
+    fn holds(&self, markings: &MarkingIndex, index: usize) -> bool {
+        self.condition
+            .holds(&markings.get(index), markings.is_deadlock(index))
+    }
+}
+
+impl Condition {
+    fn holds(&self, marking: &Marking, deadlock: bool) -> bool {
+        let sum = |sum: &Sum| {
+            sum.iter()
+                .map(|(negative, term)| {
+                    let value = match term {
+                        Term::Place(place) => marking.count(*place) as i64,
+                        Term::Number(n) => *n as i64,
+                    };
+                    if *negative {
+                        -value
+                    } else {
+                        value
+                    }
+                })
+                .sum::<i64>()
+        };
+        match self {
+            Condition::True => true,
+            Condition::False => false,
+            Condition::Deadlock => deadlock,
+            Condition::Compare(lhs, comparison, rhs) => {
+                let (lhs, rhs) = (sum(lhs), sum(rhs));
+                match comparison {
+                    Comparison::Less => lhs < rhs,
+                    Comparison::LessEqual => lhs <= rhs,
+                    Comparison::Greater => lhs > rhs,
+                    Comparison::GreaterEqual => lhs >= rhs,
+                    Comparison::Equal => lhs == rhs,
+                    Comparison::NotEqual => lhs != rhs,
+                }
+            }
+            Condition::Not(inner) => !inner.holds(marking, deadlock),
+            Condition::And(lhs, rhs) => {
+                lhs.holds(marking, deadlock) && rhs.holds(marking, deadlock)
+            }
+            Condition::Or(lhs, rhs) => lhs.holds(marking, deadlock) || rhs.holds(marking, deadlock),
+        }
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    /// A name in double quotes, never a keyword
+    Quoted(String),
+    Symbol(&'static str),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "'{}'", word),
+            Token::Quoted(name) => write!(f, "\"{}\"", name),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+fn tokens(text: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Symbol("("),
+            ')' => Token::Symbol(")"),
+            '+' => Token::Symbol("+"),
+            '-' => Token::Symbol("-"),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Symbol("<="),
+            '<' => Token::Symbol("<"),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Symbol(">="),
+            '>' => Token::Symbol(">"),
+            '=' => {
+                chars.next_if_eq(&'=');
+                Token::Symbol("=")
+            }
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Symbol("!="),
+            '"' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => name.push(c),
+                        None => return Err("unterminated name".into()),
+                    }
+                }
+                Token::Quoted(name)
+            }
+            c if is_name(c) => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| is_name(*c)) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+            c => return Err(format!("unexpected character '{}'", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn is_name(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '\'' | '.')
+}
+
+/// Recursive descent parser, conditions bind 'not' tighter than 'and' and 'and' tighter than 'or'
+struct Parser<'a> {
+    net: &'a PetriNet,
+    tokens: &'a [Token],
+    position: usize,
+}
+
+type Parsed<T> = std::result::Result<T, String>;
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        self.position += 1;
+        self.tokens.get(self.position - 1)
+    }
+
+    /// Skip the word if it comes next
+    fn word(&mut self, word: &str) -> bool {
+        let next = matches!(self.peek(), Some(Token::Word(w)) if w == word);
+        if next {
+            self.position += 1;
+        }
+        next
+    }
+
+    fn query(&mut self, text: &str) -> Parsed<Query> {
+        let quantifier = match self.next() {
+            Some(Token::Word(word)) if word == "count" => Quantifier::Count,
+            Some(Token::Word(word)) if word == "exists" => Quantifier::Exists,
+            Some(Token::Word(word)) if word == "forall" => Quantifier::Forall,
+            Some(token) => {
+                return Err(format!("expected count, exists or forall, found {}", token))
+            }
+            None => return Err("expected count, exists or forall".into()),
+        };
+        let _ = self.word("marking") || self.word("markings");
+        let condition = match self.word("where") {
+            true => self.or()?,
+            false => Condition::True,
+        };
+        Ok(Query {
+            quantifier,
+            condition,
+            text: text.to_string(),
+        })
+    }
+
+    fn or(&mut self) -> Parsed<Condition> {
+        let mut condition = self.and()?;
+        while self.word("or") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Parsed<Condition> {
+        let mut condition = self.not()?;
+        while self.word("and") {
+            condition = Condition::And(Box::new(condition), Box::new(self.not()?));
+        }
+        Ok(condition)
+    }
+
+    fn not(&mut self) -> Parsed<Condition> {
+        if self.word("not") {
+            return Ok(Condition::Not(Box::new(self.not()?)));
+        }
+        for (word, condition) in [
+            ("true", Condition::True),
+            ("false", Condition::False),
+            ("deadlock", Condition::Deadlock),
+        ] {
+            if self.word(word) {
+                return Ok(condition);
+            }
+        }
+        if self.peek() == Some(&Token::Symbol("(")) {
+            self.position += 1;
+            let condition = self.or()?;
+            return match self.next() {
+                Some(Token::Symbol(")")) => Ok(condition),
+                _ => Err("unclosed parenthesis".into()),
+            };
+        }
+        let lhs = self.sum()?;
+        let comparison = match self.next() {
+            Some(Token::Symbol("<")) => Comparison::Less,
+            Some(Token::Symbol("<=")) => Comparison::LessEqual,
+            Some(Token::Symbol(">")) => Comparison::Greater,
+            Some(Token::Symbol(">=")) => Comparison::GreaterEqual,
+            Some(Token::Symbol("=")) => Comparison::Equal,
+            Some(Token::Symbol("!=")) => Comparison::NotEqual,
+            Some(token) => return Err(format!("expected a comparison, found {}", token)),
+            None => return Err("expected a comparison".into()),
+        };
+        Ok(Condition::Compare(lhs, comparison, self.sum()?))
+    }
+
+    fn sum(&mut self) -> Parsed<Sum> {
+        let mut sum = vec![];
+        let mut negative = self.peek() == Some(&Token::Symbol("-"));
+        if negative {
+            self.position += 1;
+        }
+        loop {
+            let term = match self.next().cloned() {
+                Some(Token::Word(word)) if word.chars().all(|c| c.is_ascii_digit()) => {
+                    Term::Number(
+                        word.parse()
+                            .map_err(|_| format!("invalid number '{}'", word))?,
+                    )
+                }
+                Some(Token::Word(name) | Token::Quoted(name)) => {
+                    let place = self.net.place_labels.get(name.as_str());
+                    Term::Place(*place.ok_or_else(|| format!("unknown place '{}'", name))?)
+                }
+                Some(token) => {
+                    return Err(format!("expected a place or a number, found {}", token))
+                }
+                None => return Err("expected a place or a number".into()),
+            };
+            sum.push((negative, term));
+            negative = match self.peek() {
+                Some(Token::Symbol("+")) => false,
+                Some(Token::Symbol("-")) => true,
+                _ => return Ok(sum),
+            };
+            self.position += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::from_tina;
+
+    use super::*;
+
+    #[test]
+    pub fn queries() {
+        // Two tokens move from p1 to p2 and then to p3, where they stay
+        let net = from_tina("tr t p1 -> p2\ntr u p2 -> p3\npl p1 (2)").unwrap();
+        let markings = net.marking_index().unwrap();
+        assert_eq!(markings.len(), 6);
+        let answer = |query: &str| Query::parse(&net, query).unwrap().answer(&markings);
+        assert_eq!(answer("count"), Answer::Count(6, 6));
+        assert_eq!(answer("count where p3>0 and deadlock"), Answer::Count(1, 6));
+        assert_eq!(answer("count markings where p3 > 0"), Answer::Count(3, 6));
+        assert_eq!(answer("exists marking where p1+p2>2"), Answer::Exists(None));
+        let witness = answer("exists marking where p2 = 2").marking().unwrap();
+        assert_eq!(net.marking_label(&markings.get(witness)), "p2=2");
+        assert_eq!(answer("forall where p1 + p2 + p3 == 2").holds(), Some(true));
+        let violation = answer("forall where not (p1>0 and p2>0) or p3-1 >= 0");
+        assert_eq!(
+            net.marking_label(&markings.get(violation.marking().unwrap())),
+            "p1 p2"
+        );
+        assert_eq!(answer("exists where \"p1\" != 0").holds(), Some(true));
+
+        for invalid in [
+            "count where",
+            "sum where p1>0",
+            "count where q>0",
+            "count where (p1>0",
+            "count where p1",
+            "exists where p1>0 p2",
+            "count where \"p1>0",
+        ] {
+            assert!(matches!(Query::parse(&net, invalid), Err(Error::Query(_))));
+        }
+
+        let unbounded = from_tina("tr t p -> p q\npl p (1)").unwrap();
+        assert!(matches!(
+            unbounded.marking_index(),
+            Err(Error::Unbounded(_))
+        ));
+    }
+}
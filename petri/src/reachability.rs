@@ -0,0 +1,60 @@
+// Reachability of a target marking
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{Error, Marking, PetriNet, Result};
+
+impl PetriNet {
+    /// Marking with the given number of tokens in the places, other places hold no tokens
+    pub fn marking(&self, tokens: &[(&str, usize)]) -> Result<Marking> {
+        let mut counts = vec![0; self.places.len()];
+        for (place, count) in tokens {
+            let index = self
+                .place_labels
+                .get(*place)
+                .ok_or_else(|| Error::UnknownPlace(place.to_string()))?;
+            if *count > crate::MAX_TOKENS {
+                return Err(Error::Overflow(place.to_string()));
+            }
+            if self.safe && *count > 1 {
+                return Err(Error::Unsafe(place.to_string()));
+            }
+            counts[*index] = *count;
+        }
+        Ok(Marking::pack(counts))
+    }
+
+    /// Shortest firing sequence from the initial marking to the target, `None` if the target is
+    /// not reachable. Only terminates if finitely many markings are reachable or the state
+    /// equation has no solution. The markings are explored breadth first, remembering the
+    /// transition each marking was first reached by.
+    pub fn reachable(&self, target: &Marking) -> Result<Option<Vec<&str>>> {
+        if !self.state_equation(target) {
+            return Ok(None);
+        }
+        let initial = self.initial_marking();
+        // Transition and marking each marking was first reached from
+        let mut parents: HashMap<Marking, Option<(&str, Marking)>> =
+            HashMap::from([(initial.clone(), None)]);
+        let mut queue = VecDeque::from([initial]);
+        while let Some(marking) = queue.pop_front() {
+            if marking == *target {
+                let mut sequence = vec![];
+                let mut current = &marking;
+                while let Some((transition, parent)) = &parents[current] {
+                    sequence.push(*transition);
+                    current = parent;
+                }
+                sequence.reverse();
+                return Ok(Some(sequence));
+            }
+            for (transition, next) in self.transitions(&marking)? {
+                if !parents.contains_key(&next) {
+                    parents.insert(next.clone(), Some((transition, marking.clone())));
+                    queue.push_back(next);
+                }
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,216 @@
+// Structural reductions of place/transition nets
+
+use std::fmt::Display;
+
+use crate::{PetriNet, Place, Transition};
+
+/// Rule applied by `PetriNet::reduce`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reduction {
+    /// The place has no arcs and only carries its initial tokens
+    IsolatedPlace(String),
+    /// The transition consumes more tokens from a place than it ever holds
+    DeadTransition(String),
+    /// The second transition has the same arcs as the first one
+    IdenticalTransitions(String, String),
+    /// The second place has the same initial marking and arcs as the first one
+    IdenticalPlaces(String, String),
+    /// The transitions around the place are fused into the given one. The first one produces only
+    /// into the initially empty place, which only the second one consumes from and which is the
+    /// only input of the second one. Once the first fired, the second stays enabled and commutes
+    /// with every other transition.
+    Agglomeration {
+        place: String,
+        first: String,
+        second: String,
+        fused: String,
+    },
+}
+
+impl Display for Reduction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reduction::IsolatedPlace(p) => write!(f, "removed isolated place '{}'", p),
+            Reduction::DeadTransition(t) => write!(f, "removed dead transition '{}'", t),
+            Reduction::IdenticalTransitions(kept, removed) => {
+                write!(
+                    f,
+                    "fused transition '{}' into identical '{}'",
+                    removed, kept
+                )
+            }
+            Reduction::IdenticalPlaces(kept, removed) => {
+                write!(f, "removed place '{}' identical to '{}'", removed, kept)
+            }
+            Reduction::Agglomeration {
+                place,
+                first,
+                second,
+                fused,
+            } => write!(
+                f,
+                "agglomerated '{}' and '{}' around place '{}' into '{}'",
+                first, second, place, fused
+            ),
+        }
+    }
+}
+
+impl PetriNet {
+    /// Apply the reduction rules until none applies, returning the applied rules in order
+    ///
+    /// The rules keep whether and how many deadlocks are reachable. The reachable markings of the
+    /// reduced net are those of the original one projected onto the remaining places, except for
+    /// markings with tokens in agglomerated places. Places and transitions referred to by
+    /// properties may be removed, so reduce only for analysing the net.
+    pub fn reduce(&mut self) -> Vec<Reduction> {
+        let mut places: Vec<Option<Place>> = self.places.drain(..).map(Some).collect();
+        let mut transitions: Vec<Option<Transition>> =
+            self.transitions.drain(..).map(Some).collect();
+        for transition in transitions.iter_mut().flatten() {
+            transition.inputs.sort();
+            transition.outputs.sort();
+        }
+        let mut applied = vec![];
+        while let Some(reduction) = reduce_once(&mut places, &mut transitions) {
+            applied.push(reduction);
+        }
+
+        // Rebuild the net from the remaining places and transitions
+        let mut net = PetriNet::new();
+        net.safe = self.safe;
+        let mut index = vec![0; places.len()];
+        for (i, place) in places.into_iter().enumerate() {
+            if let Some(place) = place {
+                index[i] = net.places.len();
+                net.add_place(place.label, place.initial_marking)
+                    .expect("Labels of the reduced net are unique");
+            }
+        }
+        for mut transition in transitions.into_iter().flatten() {
+            for (place, _) in transition.inputs.iter_mut().chain(&mut transition.outputs) {
+                *place = index[*place];
+            }
+            net.transition_labels
+                .insert(transition.label.clone(), net.transitions.len());
+            net.transitions.push(transition);
+        }
+        *self = net;
+        applied
+    }
+}
+
+/// Apply the first applicable rule
+fn reduce_once(
+    places: &mut [Option<Place>],
+    transitions: &mut [Option<Transition>],
+) -> Option<Reduction> {
+    let isolated = (0..places.len())
+        .find(|&p| places[p].is_some() && arcs_of(transitions, p) == (vec![], vec![]));
+    if let Some(p) = isolated {
+        let place = places[p].take()?;
+        return Some(Reduction::IsolatedPlace(place.label));
+    }
+
+    let dead = (0..transitions.len()).find(|&t| {
+        transitions[t].as_ref().is_some_and(|transition| {
+            transition.inputs.iter().any(|(p, w)| {
+                let initial = places[*p].as_ref().map_or(0, |p| p.initial_marking);
+                arcs_of(transitions, *p).1.is_empty() && initial < *w
+            })
+        })
+    });
+    if let Some(t) = dead {
+        let transition = transitions[t].take()?;
+        return Some(Reduction::DeadTransition(transition.label));
+    }
+
+    let identical = |a: &Transition, b: &Transition| a.inputs == b.inputs && a.outputs == b.outputs;
+    for t in 0..transitions.len() {
+        let Some(first) = &transitions[t] else {
+            continue;
+        };
+        let duplicate = (t + 1..transitions.len())
+            .find(|&u| transitions[u].as_ref().is_some_and(|u| identical(first, u)));
+        if let Some(u) = duplicate {
+            let kept = first.label.clone();
+            let removed = transitions[u].take()?;
+            return Some(Reduction::IdenticalTransitions(kept, removed.label));
+        }
+    }
+
+    for p in 0..places.len() {
+        let Some(first) = &places[p] else { continue };
+        let duplicate = (p + 1..places.len()).find(|&q| {
+            places[q].as_ref().is_some_and(|second| {
+                first.initial_marking == second.initial_marking
+                    && arcs_of(transitions, p) == arcs_of(transitions, q)
+            })
+        });
+        if let Some(q) = duplicate {
+            let kept = first.label.clone();
+            let removed = places[q].take()?;
+            for transition in transitions.iter_mut().flatten() {
+                transition.inputs.retain(|(place, _)| *place != q);
+                transition.outputs.retain(|(place, _)| *place != q);
+            }
+            return Some(Reduction::IdenticalPlaces(kept, removed.label));
+        }
+    }
+
+    // Place with the transitions producing into and consuming from it
+    let agglomeration = (0..places.len()).find_map(|p| {
+        let place = places[p].as_ref()?;
+        let (consumers, producers) = arcs_of(transitions, p);
+        let ([(f, 1)], [(h, 1)]) = (consumers.as_slice(), producers.as_slice()) else {
+            return None;
+        };
+        let (first, second) = (transitions[*h].as_ref()?, transitions[*f].as_ref()?);
+        let applies = place.initial_marking == 0
+            && h != f
+            && first.outputs == [(p, 1)]
+            && second.inputs == [(p, 1)];
+        applies.then_some((p, *h, *f))
+    });
+    let (p, h, f) = agglomeration?;
+    let place = places[p].take()?;
+    let first = transitions[h].take()?;
+    let second = transitions[f].take()?;
+    let mut label = format!("{}.{}", first.label, second.label);
+    while transitions.iter().flatten().any(|t| t.label == label) {
+        label.push('\'');
+    }
+    transitions[h] = Some(Transition {
+        label: label.clone(),
+        inputs: first.inputs,
+        outputs: second.outputs,
+        rate: None,
+    });
+    Some(Reduction::Agglomeration {
+        place: place.label,
+        first: first.label,
+        second: second.label,
+        fused: label,
+    })
+}
+
+/// Transitions consuming from and producing into the place, with the weights of the arcs
+type PlaceArcs = (Vec<(usize, usize)>, Vec<(usize, usize)>);
+
+fn arcs_of(transitions: &[Option<Transition>], place: usize) -> PlaceArcs {
+    let weight = |arcs: &[(usize, usize)]| arcs.iter().find(|(p, _)| *p == place).map(|(_, w)| *w);
+    let mut consumers = vec![];
+    let mut producers = vec![];
+    for (t, transition) in transitions.iter().enumerate() {
+        let Some(transition) = transition else {
+            continue;
+        };
+        if let Some(w) = weight(&transition.inputs) {
+            consumers.push((t, w));
+        }
+        if let Some(w) = weight(&transition.outputs) {
+            producers.push((t, w));
+        }
+    }
+    (consumers, producers)
+}
@@ -0,0 +1,88 @@
+// Stepping through a net by hand, one transition at a time
+
+use crate::{Error, Marking, PetriNet, Result};
+
+/// Current marking of a net with the firing sequence leading to it
+pub struct Simulation<'a> {
+    net: &'a PetriNet,
+    /// Markings from the initial one on, the last one is the current marking
+    markings: Vec<Marking>,
+    /// Transitions fired between the markings
+    fired: Vec<&'a str>,
+}
+
+impl<'a> Simulation<'a> {
+    pub fn new(net: &'a PetriNet) -> Self {
+        Simulation {
+            net,
+            markings: vec![net.initial_marking()],
+            fired: vec![],
+        }
+    }
+
+    pub fn marking(&self) -> &Marking {
+        self.markings
+            .last()
+            .expect("The initial marking is never undone")
+    }
+
+    /// Transitions enabled in the current marking
+    pub fn enabled(&self) -> Vec<&'a str> {
+        self.marking().active_transitions(self.net)
+    }
+
+    /// Transitions fired from the initial marking on
+    pub fn trace(&self) -> &[&'a str] {
+        &self.fired
+    }
+
+    pub fn fire(&mut self, label: &str) -> Result<()> {
+        let index = self
+            .net
+            .transition_labels
+            .get_by_left(label)
+            .ok_or_else(|| Error::UnknownTransition(label.to_string()))?;
+        let transition = &self.net.transitions[*index];
+        if !transition.enabled(self.marking()) {
+            return Err(Error::NotEnabled(label.to_string()));
+        }
+        let next = self.marking().fire(self.net, transition)?;
+        self.markings.push(next);
+        self.fired.push(&transition.label);
+        Ok(())
+    }
+
+    /// Return to the previous marking, giving the transition fired last
+    pub fn undo(&mut self) -> Option<&'a str> {
+        let transition = self.fired.pop()?;
+        self.markings.pop();
+        Some(transition)
+    }
+
+    /// Return to the initial marking
+    pub fn reset(&mut self) {
+        self.markings.truncate(1);
+        self.fired.clear();
+    }
+
+    /// Fire up to `steps` transitions chosen uniformly among the enabled ones, stopping early in
+    /// deadlocks. The same seed always chooses the same transitions. Returns the fired ones.
+    pub fn random_walk(&mut self, steps: usize, seed: u64) -> Result<Vec<&'a str>> {
+        let mut state = seed;
+        let mut walked = vec![];
+        for _ in 0..steps {
+            let enabled = self.enabled();
+            if enabled.is_empty() {
+                break;
+            }
+            // Linear congruential generator, the high bits are the most random ones
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let choice = enabled[(state >> 33) as usize % enabled.len()];
+            self.fire(choice)?;
+            walked.push(choice);
+        }
+        Ok(walked)
+    }
+}
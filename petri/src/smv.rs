@@ -0,0 +1,139 @@
+// SMV models of bounded nets, readable by NuSMV and nuXmv
+
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::{Bound, Error, PetriNet, Result};
+
+impl PetriNet {
+    /// SMV model of the net with the LTL specifications, pairs of a comment and a formula over
+    /// the identifiers of `smv_place` and `smv_transition`. Fails if a place is unbounded.
+    ///
+    /// Every place is an integer variable ranging up to its bound and every transition is defined
+    /// as the condition enabling it. A step fires one enabled transition, deadlocks repeat their
+    /// marking forever like in the LTL checks of lmc.
+    pub fn to_smv(&self, specs: impl IntoIterator<Item = (String, String)>) -> Result<String> {
+        let mut smv = String::new();
+        writeln!(smv, "MODULE main").unwrap();
+        writeln!(smv, "VAR").unwrap();
+        for (place, bound) in self.bounds() {
+            match bound {
+                Bound::Finite(bound) => writeln!(smv, "  {} : 0..{};", escape("p_", place), bound),
+                Bound::Unbounded => return Err(Error::Unbounded(place.to_string())),
+            }
+            .unwrap();
+        }
+
+        writeln!(smv, "DEFINE").unwrap();
+        for transition in &self.transitions {
+            let condition = transition
+                .inputs
+                .iter()
+                .map(|(place, weight)| format!("{} >= {}", self.place_identifier(*place), weight))
+                .join(" & ");
+            let condition = if condition.is_empty() {
+                "TRUE".into()
+            } else {
+                condition
+            };
+            writeln!(
+                smv,
+                "  {} := {};",
+                escape("t_", &transition.label),
+                condition
+            )
+            .unwrap();
+        }
+        let enabled = self
+            .transitions
+            .iter()
+            .map(|t| escape("t_", &t.label))
+            .join(" | ");
+        match enabled.is_empty() {
+            true => writeln!(smv, "  deadlock := TRUE;").unwrap(),
+            false => writeln!(smv, "  deadlock := !({});", enabled).unwrap(),
+        }
+
+        writeln!(smv, "ASSIGN").unwrap();
+        for (index, place) in self.places.iter().enumerate() {
+            let variable = self.place_identifier(index);
+            writeln!(smv, "  init({}) := {};", variable, place.initial_marking).unwrap();
+        }
+
+        // Every step updates all places, deadlocks leave them unchanged
+        let update = |place: usize, change: i64| {
+            let variable = self.place_identifier(place);
+            match change {
+                0 => format!("next({}) = {}", variable, variable),
+                c if c > 0 => format!("next({}) = {} + {}", variable, variable, c),
+                c => format!("next({}) = {} - {}", variable, variable, -c),
+            }
+        };
+        let mut steps = vec![];
+        for transition in &self.transitions {
+            let mut step = vec![escape("t_", &transition.label)];
+            for place in 0..self.places.len() {
+                let tokens = |arcs: &[(usize, usize)]| {
+                    arcs.iter()
+                        .filter(|(p, _)| *p == place)
+                        .map(|(_, weight)| *weight as i64)
+                        .sum::<i64>()
+                };
+                step.push(update(
+                    place,
+                    tokens(&transition.outputs) - tokens(&transition.inputs),
+                ));
+            }
+            steps.push(step.join(" & "));
+        }
+        let stutter = (0..self.places.len()).map(|place| update(place, 0));
+        steps.push(
+            ["deadlock".to_string()]
+                .into_iter()
+                .chain(stutter)
+                .join(" & "),
+        );
+        writeln!(smv, "TRANS").unwrap();
+        let steps = steps.iter().map(|step| format!("({})", step));
+        writeln!(smv, "  {};", steps.format("\n  | ")).unwrap();
+
+        for (comment, spec) in specs {
+            writeln!(smv, "-- {}", comment.replace('\n', " ")).unwrap();
+            writeln!(smv, "LTLSPEC {};", spec).unwrap();
+        }
+        Ok(smv)
+    }
+
+    /// Identifier of the variable holding the tokens of the place in SMV models
+    pub fn smv_place(&self, place: &str) -> Result<String> {
+        match self.place_labels.get(place) {
+            Some(index) => Ok(self.place_identifier(*index)),
+            None => Err(Error::UnknownPlace(place.to_string())),
+        }
+    }
+
+    /// Identifier defined as the condition enabling the transition in SMV models
+    pub fn smv_transition(&self, transition: &str) -> Result<String> {
+        match self.has_transition(transition) {
+            true => Ok(escape("t_", transition)),
+            false => Err(Error::UnknownTransition(transition.to_string())),
+        }
+    }
+
+    fn place_identifier(&self, index: usize) -> String {
+        escape("p_", &self.places[index].label)
+    }
+}
+
+/// SMV identifier keeping letters, digits and underscores, any other character is written as '$'
+/// with its code point and '#'
+fn escape(prefix: &str, name: &str) -> String {
+    name.chars().fold(prefix.to_string(), |mut identifier, c| {
+        match c.is_ascii_alphanumeric() || c == '_' {
+            true => identifier.push(c),
+            false => write!(identifier, "${:x}#", c as u32).unwrap(),
+        }
+        identifier
+    })
+}
@@ -0,0 +1,118 @@
+// Stochastic nets and their continuous time Markov chains
+
+use std::fmt::Write;
+
+use crate::{Error, Marking, MarkingStore, PetriNet, Result};
+
+/// Continuous time Markov chain of the reachable markings
+#[derive(Debug, Clone)]
+pub struct Ctmc {
+    /// Reachable markings, the initial one first
+    pub markings: Vec<Marking>,
+    /// Source and target marking of every transition with its rate, parallel edges add up
+    pub rates: Vec<(usize, usize, f64)>,
+}
+
+impl PetriNet {
+    /// Rate of the transition, `None` if it has none
+    pub fn rate(&self, transition: &str) -> Result<Option<f64>> {
+        let index = self
+            .transition_labels
+            .get_by_left(transition)
+            .ok_or_else(|| Error::UnknownTransition(transition.to_string()))?;
+        Ok(self.transitions[*index].rate)
+    }
+
+    pub(crate) fn set_rate(&mut self, transition: &str, rate: f64) -> Result<()> {
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(Error::InvalidRate(transition.to_string()));
+        }
+        let index = self
+            .transition_labels
+            .get_by_left(transition)
+            .ok_or_else(|| Error::UnknownTransition(transition.to_string()))?;
+        self.transitions[*index].rate = Some(rate);
+        Ok(())
+    }
+
+    /// The Markov chain of the reachable markings, only terminates if finitely many markings
+    /// are reachable. Every enabled transition fires after an exponentially distributed delay
+    /// with its rate, transitions without a rate fire with rate 1.
+    pub fn ctmc(&self) -> Result<Ctmc> {
+        let mut store = MarkingStore::new();
+        store.insert(&self.initial_marking());
+        let mut rates = vec![];
+        let mut next = 0;
+        while next < store.len() {
+            let marking = store.get(next);
+            let mut edges: Vec<(usize, usize, f64)> = vec![];
+            for transition in self.transitions.iter().filter(|t| t.enabled(&marking)) {
+                let (target, _) = store.insert(&marking.fire(self, transition)?);
+                let rate = transition.rate.unwrap_or(1.0);
+                match edges.iter_mut().find(|(_, t, _)| *t == target) {
+                    Some((_, _, r)) => *r += rate,
+                    None => edges.push((next, target, rate)),
+                }
+            }
+            rates.extend(edges);
+            next += 1;
+        }
+        Ok(Ctmc {
+            markings: store.iter().collect(),
+            rates,
+        })
+    }
+}
+
+impl Ctmc {
+    /// Long run probability of each marking, iterating until no probability changes by more
+    /// than `precision`. The uniformized chain is iterated from the initial marking, which also
+    /// converges for chains with several bottom components.
+    pub fn steady_state(&self, precision: f64) -> Vec<f64> {
+        let n = self.markings.len();
+        let mut exit = vec![0.0; n];
+        for (source, target, rate) in &self.rates {
+            if source != target {
+                exit[*source] += rate;
+            }
+        }
+        // Uniformization with a rate exceeding every exit rate makes the chain aperiodic
+        let uniform = exit.iter().cloned().fold(0.0, f64::max) * 1.1 + f64::MIN_POSITIVE;
+        let mut distribution = vec![0.0; n];
+        distribution[0] = 1.0;
+        loop {
+            let mut next: Vec<f64> = distribution
+                .iter()
+                .zip(&exit)
+                .map(|(p, e)| p * (1.0 - e / uniform))
+                .collect();
+            for (source, target, rate) in &self.rates {
+                if source != target {
+                    next[*target] += distribution[*source] * rate / uniform;
+                }
+            }
+            let change = next
+                .iter()
+                .zip(&distribution)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            distribution = next;
+            if change <= precision {
+                return distribution;
+            }
+        }
+    }
+
+    /// The chain in the explicit transition format of PRISM, the markings are numbered in the
+    /// order of `markings`
+    pub fn to_tra(&self) -> String {
+        let mut out = String::new();
+        writeln!(&mut out, "{} {}", self.markings.len(), self.rates.len()).unwrap();
+        let mut rates = self.rates.clone();
+        rates.sort_by_key(|(source, target, _)| (*source, *target));
+        for (source, target, rate) in rates {
+            writeln!(&mut out, "{} {} {}", source, target, rate).unwrap();
+        }
+        out
+    }
+}
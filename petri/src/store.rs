@@ -0,0 +1,212 @@
+// Compact storage of visited markings
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+
+use crate::{Counts, Marking};
+
+const EMPTY: u32 = u32::MAX;
+
+/// Markings interned into one arena, each gets the index it was inserted with. Unlike in a
+/// `HashSet<Marking>` a marking costs its encoding and a few bytes of index, no allocation.
+#[derive(Debug, Clone, Default)]
+pub struct MarkingStore {
+    bytes: Vec<u8>,
+    /// Start of each marking in `bytes`, the encoding ends at the start of the next one
+    offsets: Vec<usize>,
+    /// Open addressing table of indices into `offsets`
+    slots: Vec<u32>,
+}
+
+impl MarkingStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of the marking and whether it was inserted now
+    pub fn insert(&mut self, marking: &Marking) -> (usize, bool) {
+        let encoded = encode(marking);
+        if (self.offsets.len() + 1) * 2 > self.slots.len() {
+            self.grow();
+        }
+        let mut slot = hash(&encoded) as usize % self.slots.len();
+        loop {
+            match self.slots[slot] {
+                EMPTY => break,
+                index if self.encoding(index as usize) == encoded.as_slice() => {
+                    return (index as usize, false);
+                }
+                _ => slot = (slot + 1) % self.slots.len(),
+            }
+        }
+        let index = self.offsets.len();
+        self.slots[slot] = index as u32;
+        self.offsets.push(self.bytes.len());
+        self.bytes.extend(encoded);
+        (index, true)
+    }
+
+    pub fn contains(&self, marking: &Marking) -> bool {
+        if self.slots.is_empty() {
+            return false;
+        }
+        let encoded = encode(marking);
+        let mut slot = hash(&encoded) as usize % self.slots.len();
+        while self.slots[slot] != EMPTY {
+            if self.encoding(self.slots[slot] as usize) == encoded.as_slice() {
+                return true;
+            }
+            slot = (slot + 1) % self.slots.len();
+        }
+        false
+    }
+
+    /// Marking with the given index
+    pub fn get(&self, index: usize) -> Marking {
+        decode(self.encoding(index))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Marking> + '_ {
+        (0..self.len()).map(|i| self.get(i))
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Bytes allocated for the markings and the table
+    pub fn memory(&self) -> usize {
+        self.bytes.capacity()
+            + self.offsets.capacity() * size_of::<usize>()
+            + self.slots.capacity() * size_of::<u32>()
+    }
+
+    fn encoding(&self, index: usize) -> &[u8] {
+        let end = self
+            .offsets
+            .get(index + 1)
+            .copied()
+            .unwrap_or(self.bytes.len());
+        &self.bytes[self.offsets[index]..end]
+    }
+
+    fn grow(&mut self) {
+        let size = (self.slots.len() * 2).max(16);
+        self.slots = vec![EMPTY; size];
+        for index in 0..self.offsets.len() {
+            let mut slot = hash(self.encoding(index)) as usize % size;
+            while self.slots[slot] != EMPTY {
+                slot = (slot + 1) % size;
+            }
+            self.slots[slot] = index as u32;
+        }
+    }
+}
+
+/// Visited markings represented by their hashes only. Two markings with the same hash are taken
+/// to be the same, so a marking may wrongly be considered visited and its successors never
+/// explored.
+#[derive(Debug, Clone, Default)]
+pub struct HashCompaction {
+    hashes: HashSet<u64>,
+}
+
+impl HashCompaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether no marking with the same hash was inserted before
+    pub fn insert(&mut self, marking: &Marking) -> bool {
+        self.hashes.insert(hash(&encode(marking)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn memory(&self) -> usize {
+        self.hashes.capacity() * size_of::<u64>()
+    }
+
+    /// Upper bound on the probability that two of the stored markings share a hash, in which
+    /// case some markings may have been missed
+    pub fn collision_probability(&self) -> f64 {
+        let n = self.hashes.len() as f64;
+        (n * (n - 1.0) / 2.0 / 2f64.powi(64)).min(1.0)
+    }
+}
+
+/// Visited markings either interned or hash compacted
+#[derive(Debug, Clone)]
+pub enum Visited {
+    Interned(MarkingStore),
+    Compacted(HashCompaction),
+}
+
+impl Visited {
+    /// Whether the marking was not visited before
+    pub fn insert(&mut self, marking: &Marking) -> bool {
+        match self {
+            Visited::Interned(store) => store.insert(marking).1,
+            Visited::Compacted(hashes) => hashes.insert(marking),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Visited::Interned(store) => store.len(),
+            Visited::Compacted(hashes) => hashes.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn memory(&self) -> usize {
+        match self {
+            Visited::Interned(store) => store.memory(),
+            Visited::Compacted(hashes) => hashes.memory(),
+        }
+    }
+}
+
+/// A tag for the width of the counts followed by the counts, little endian
+fn encode(marking: &Marking) -> Vec<u8> {
+    match &marking.tokens {
+        Counts::Small(counts) => std::iter::once(0).chain(counts.iter().copied()).collect(),
+        Counts::Large(counts) => std::iter::once(1)
+            .chain(counts.iter().flat_map(|c| c.to_le_bytes()))
+            .collect(),
+    }
+}
+
+fn decode(bytes: &[u8]) -> Marking {
+    let tokens = match bytes[0] {
+        0 => Counts::Small(bytes[1..].to_vec()),
+        _ => Counts::Large(
+            bytes[1..]
+                .chunks(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect(),
+        ),
+    };
+    Marking { tokens }
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
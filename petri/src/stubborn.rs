@@ -0,0 +1,174 @@
+// Partial order reduction by stubborn sets
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use crate::{Error, Marking, PetriNet, Result};
+
+/// Successors of markings restricted to stubborn sets, sets of transitions which can only be
+/// disabled or enabled by transitions in the set
+pub struct StubbornSets<'a> {
+    net: &'a PetriNet,
+    /// Transitions consuming from and producing into each place
+    consumers: Vec<Vec<usize>>,
+    producers: Vec<Vec<usize>>,
+    visible: Vec<bool>,
+    /// Whether the cycle proviso is applied, successors are then memoized so repeated calls for
+    /// a marking agree
+    proviso: bool,
+    successors: RefCell<HashMap<Marking, Vec<(&'a str, Marking)>>>,
+    generated: RefCell<HashSet<Marking>>,
+}
+
+impl<'a> StubbornSets<'a> {
+    /// Reduction preserving the reachable deadlocks. A set with an enabled transition contains
+    /// every transition consuming from its input places, with a disabled one every transition
+    /// producing into one place lacking tokens.
+    pub fn deadlocks(net: &'a PetriNet) -> Self {
+        let mut consumers = vec![vec![]; net.places.len()];
+        let mut producers = vec![vec![]; net.places.len()];
+        for (index, transition) in net.transitions.iter().enumerate() {
+            for (place, _) in &transition.inputs {
+                consumers[*place].push(index);
+            }
+            for (place, _) in &transition.outputs {
+                producers[*place].push(index);
+            }
+        }
+        StubbornSets {
+            net,
+            consumers,
+            producers,
+            visible: vec![false; net.transitions.len()],
+            proviso: false,
+            successors: RefCell::new(HashMap::new()),
+            generated: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Reduction preserving next-free LTL properties over the tokens in the places and the
+    /// fireability of the transitions
+    ///
+    /// A set with an enabled visible transition, one changing the propositions, contains all of
+    /// them. No transition may be ignored forever along a cycle, so a marking is fully expanded if
+    /// one of its reduced successors has been generated before: the last marking of a cycle to be
+    /// expanded then sees its successor on the cycle as generated.
+    pub fn ltl(net: &'a PetriNet, places: &[&str], fireable: &[&str]) -> Result<Self> {
+        let mut observed = vec![false; net.places.len()];
+        for place in places {
+            let index = net
+                .place_labels
+                .get(*place)
+                .ok_or_else(|| Error::UnknownPlace(place.to_string()))?;
+            observed[*index] = true;
+        }
+        for transition in fireable {
+            let index = net
+                .transition_labels
+                .get_by_left(*transition)
+                .ok_or_else(|| Error::UnknownTransition(transition.to_string()))?;
+            for (place, _) in &net.transitions[*index].inputs {
+                observed[*place] = true;
+            }
+        }
+
+        let mut reduction = Self::deadlocks(net);
+        reduction.proviso = true;
+        // Transitions changing the number of tokens in an observed place
+        for (index, transition) in net.transitions.iter().enumerate() {
+            let effect = |place: usize| {
+                let weight = |arcs: &[(usize, usize)]| {
+                    arcs.iter()
+                        .find(|(p, _)| *p == place)
+                        .map_or(0, |(_, w)| *w)
+                };
+                weight(&transition.outputs) as isize - weight(&transition.inputs) as isize
+            };
+            reduction.visible[index] = (0..net.places.len()).any(|p| observed[p] && effect(p) != 0);
+        }
+        Ok(reduction)
+    }
+
+    /// Fire the enabled transitions of a smallest stubborn set
+    pub fn transitions(&self, marking: &Marking) -> Result<Vec<(&'a str, Marking)>> {
+        if !self.proviso {
+            return self.reduced(marking);
+        }
+        if let Some(successors) = self.successors.borrow().get(marking) {
+            return Ok(successors.clone());
+        }
+        let mut successors = self.reduced(marking)?;
+        let mut generated = self.generated.borrow_mut();
+        generated.insert(marking.clone());
+        if successors.iter().any(|(_, m)| generated.contains(m)) {
+            successors = self.net.transitions(marking)?;
+        }
+        generated.extend(successors.iter().map(|(_, m)| m.clone()));
+        self.successors
+            .borrow_mut()
+            .insert(marking.clone(), successors.clone());
+        Ok(successors)
+    }
+
+    fn reduced(&self, marking: &Marking) -> Result<Vec<(&'a str, Marking)>> {
+        if marking.len() != self.net.places.len() {
+            return Err(Error::InvalidIndex);
+        }
+        if let Some(reason) = ts::stopped() {
+            return Err(Error::Stopped(reason));
+        }
+        let enabled: Vec<usize> = (0..self.net.transitions.len())
+            .filter(|t| self.net.transitions[*t].enabled(marking))
+            .collect();
+        let Some(stubborn) = enabled
+            .iter()
+            .map(|seed| self.stubborn_set(marking, *seed))
+            .min_by_key(|set| enabled.iter().filter(|t| set[**t]).count())
+        else {
+            return Ok(vec![]);
+        };
+        enabled
+            .into_iter()
+            .filter(|t| stubborn[*t])
+            .map(|t| {
+                let transition = &self.net.transitions[t];
+                Ok((
+                    transition.label.as_str(),
+                    marking.fire(self.net, transition)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Closure of the seed transition, as membership of every transition
+    fn stubborn_set(&self, marking: &Marking, seed: usize) -> Vec<bool> {
+        let mut stubborn = vec![false; self.net.transitions.len()];
+        stubborn[seed] = true;
+        let mut stack = vec![seed];
+        while let Some(index) = stack.pop() {
+            let transition = &self.net.transitions[index];
+            let mut required = vec![];
+            if transition.enabled(marking) {
+                for (place, _) in &transition.inputs {
+                    required.extend(&self.consumers[*place]);
+                }
+                if self.visible[index] {
+                    required.extend((0..self.visible.len()).filter(|t| self.visible[*t]));
+                }
+            } else if let Some((place, _)) = transition
+                .inputs
+                .iter()
+                .find(|(place, weight)| marking.count(*place) < *weight)
+            {
+                required.extend(&self.producers[*place]);
+            }
+            for t in required {
+                if !stubborn[t] {
+                    stubborn[t] = true;
+                    stack.push(t);
+                }
+            }
+        }
+        stubborn
+    }
+}
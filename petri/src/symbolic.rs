@@ -0,0 +1,364 @@
+// Symbolic reachability with binary decision diagrams
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{Error, PetriNet, Result, MAX_TOKENS};
+
+/// Index of a node in the `Bdd`
+type Node = u32;
+
+const FALSE: Node = 0;
+const TRUE: Node = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Op {
+    And,
+    Or,
+    /// Left operand without the right one
+    Diff,
+}
+
+/// Store of shared BDD nodes, each node is a variable with the nodes of its low and high branches
+struct Bdd {
+    nodes: Vec<(u32, Node, Node)>,
+    unique: HashMap<(u32, Node, Node), Node>,
+    cache: HashMap<(Op, Node, Node), Node>,
+}
+
+impl Bdd {
+    fn new(variables: u32) -> Self {
+        // The terminals come after every variable
+        Bdd {
+            nodes: vec![(variables, FALSE, FALSE), (variables, TRUE, TRUE)],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    fn var(&self, node: Node) -> u32 {
+        self.nodes[node as usize].0
+    }
+
+    fn node(&mut self, var: u32, low: Node, high: Node) -> Node {
+        if low == high {
+            return low;
+        }
+        let next = self.nodes.len() as Node;
+        *self.unique.entry((var, low, high)).or_insert_with(|| {
+            self.nodes.push((var, low, high));
+            next
+        })
+    }
+
+    /// Low and high branch of the node for the variable
+    fn cofactors(&self, node: Node, var: u32) -> (Node, Node) {
+        let (v, low, high) = self.nodes[node as usize];
+        if v == var {
+            (low, high)
+        } else {
+            (node, node)
+        }
+    }
+
+    fn apply(&mut self, op: Op, a: Node, b: Node) -> Node {
+        match (op, a, b) {
+            (Op::And, FALSE, _) | (Op::And, _, FALSE) => return FALSE,
+            (Op::And, TRUE, x) | (Op::And, x, TRUE) => return x,
+            (Op::Or, TRUE, _) | (Op::Or, _, TRUE) => return TRUE,
+            (Op::Or, FALSE, x) | (Op::Or, x, FALSE) => return x,
+            (Op::Diff, FALSE, _) | (Op::Diff, _, TRUE) => return FALSE,
+            (Op::Diff, x, FALSE) => return x,
+            _ if a == b => return if op == Op::Diff { FALSE } else { a },
+            _ => {}
+        }
+        if let Some(result) = self.cache.get(&(op, a, b)) {
+            return *result;
+        }
+        let var = self.var(a).min(self.var(b));
+        let (a_low, a_high) = self.cofactors(a, var);
+        let (b_low, b_high) = self.cofactors(b, var);
+        let low = self.apply(op, a_low, b_low);
+        let high = self.apply(op, a_high, b_high);
+        let result = self.node(var, low, high);
+        self.cache.insert((op, a, b), result);
+        result
+    }
+
+    fn and(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::And, a, b)
+    }
+
+    fn or(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::Or, a, b)
+    }
+
+    fn diff(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::Diff, a, b)
+    }
+
+    /// Quantify the variables `first..last` existentially
+    fn exists(&mut self, node: Node, first: u32, last: u32) -> Node {
+        let mut cache = HashMap::new();
+        self.exists_cached(node, first, last, &mut cache)
+    }
+
+    fn exists_cached(
+        &mut self,
+        node: Node,
+        first: u32,
+        last: u32,
+        cache: &mut HashMap<Node, Node>,
+    ) -> Node {
+        let (var, low, high) = self.nodes[node as usize];
+        if var >= last {
+            return node;
+        }
+        if let Some(result) = cache.get(&node) {
+            return *result;
+        }
+        let low = self.exists_cached(low, first, last, cache);
+        let high = self.exists_cached(high, first, last, cache);
+        let result = if var >= first {
+            self.or(low, high)
+        } else {
+            self.node(var, low, high)
+        };
+        cache.insert(node, result);
+        result
+    }
+
+    /// The variables `first..first + bits` encode the value, most significant bit first
+    fn value(&mut self, first: u32, bits: u32, value: usize) -> Node {
+        (0..bits).rev().fold(TRUE, |node, bit| {
+            if value >> (bits - 1 - bit) & 1 == 1 {
+                self.node(first + bit, FALSE, node)
+            } else {
+                self.node(first + bit, node, FALSE)
+            }
+        })
+    }
+
+    /// Values at least `bound` of the variables `first..first + bits`
+    fn at_least(&mut self, first: u32, bits: u32, bound: usize) -> Node {
+        if bound >> bits != 0 {
+            return FALSE;
+        }
+        // Equal bits so far are followed by a comparison of the remaining ones
+        (0..bits).rev().fold(TRUE, |node, bit| {
+            if bound >> (bits - 1 - bit) & 1 == 1 {
+                self.node(first + bit, FALSE, node)
+            } else {
+                self.node(first + bit, node, TRUE)
+            }
+        })
+    }
+
+    /// Values the variables `first..first + bits` take in the assignments satisfying the node
+    fn values(&mut self, node: Node, first: u32, bits: u32) -> Vec<usize> {
+        let variables = self.var(TRUE);
+        let node = self.exists(node, 0, first);
+        let node = self.exists(node, first + bits, variables);
+        let mut values = vec![];
+        let mut stack = vec![(node, first, 0)];
+        while let Some((node, var, value)) = stack.pop() {
+            if node == FALSE {
+                continue;
+            }
+            if var == first + bits {
+                values.push(value);
+                continue;
+            }
+            let (low, high) = self.cofactors(node, var);
+            stack.push((low, var + 1, value << 1));
+            stack.push((high, var + 1, value << 1 | 1));
+        }
+        values
+    }
+
+    /// Number of assignments to all variables satisfying the node
+    fn count(&self, node: Node) -> u128 {
+        let mut cache = HashMap::new();
+        let var = self.var(node);
+        self.count_cached(node, &mut cache)
+            .saturating_mul(1u128.checked_shl(var).unwrap_or(u128::MAX))
+    }
+
+    /// Number of assignments to the variables from the one of the node on
+    fn count_cached(&self, node: Node, cache: &mut HashMap<Node, u128>) -> u128 {
+        match node {
+            FALSE => return 0,
+            TRUE => return 1,
+            _ => {}
+        }
+        if let Some(count) = cache.get(&node) {
+            return *count;
+        }
+        let (var, low, high) = self.nodes[node as usize];
+        let branch = |child: Node, cache: &mut HashMap<Node, u128>| {
+            let skipped = self.var(child) - var - 1;
+            self.count_cached(child, cache)
+                .saturating_mul(1u128.checked_shl(skipped).unwrap_or(u128::MAX))
+        };
+        let count = branch(low, cache).saturating_add(branch(high, cache));
+        cache.insert(node, count);
+        count
+    }
+}
+
+/// Result of the symbolic exploration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolicSummary {
+    /// Number of reachable markings
+    pub markings: u128,
+    /// Number of reachable markings in which no transition is enabled
+    pub deadlocks: u128,
+    /// Bits used per place in the final encoding
+    pub bits: u32,
+    /// Number of nodes of the BDD of the reachable markings
+    pub nodes: usize,
+}
+
+/// Why the exploration with a number of bits per place did not finish
+enum Unfinished {
+    /// Marking of a place which does not fit into the encoding
+    Exceeded(usize),
+    Stopped(ts::Stop),
+}
+
+impl PetriNet {
+    /// Explore the reachable markings symbolically, which handles state spaces far too large
+    /// for enumerating the markings one by one
+    ///
+    /// The encoding starts with as many bits per place as the initial marking needs and is widened
+    /// whenever a marking would exceed the representable counts, so the exploration is restarted
+    /// at most four times before reaching `MAX_TOKENS`.
+    pub fn symbolic_analysis(&self) -> Result<SymbolicSummary> {
+        let largest = self
+            .places
+            .iter()
+            .map(|p| p.initial_marking)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut bits = usize::BITS - largest.leading_zeros();
+        loop {
+            match self.symbolic_reachability(bits) {
+                Ok(summary) => return Ok(summary),
+                Err(Unfinished::Stopped(reason)) => return Err(Error::Stopped(reason)),
+                Err(Unfinished::Exceeded(place)) => {
+                    let label = self.places[place].label.clone();
+                    if self.safe {
+                        return Err(Error::Unsafe(label));
+                    }
+                    if (1 << bits) > MAX_TOKENS {
+                        return Err(Error::Overflow(label));
+                    }
+                    bits = (bits * 2).min(usize::BITS - MAX_TOKENS.leading_zeros());
+                }
+            }
+        }
+    }
+
+    /// Reachable markings with the tokens of every place encoded in binary, the places ordered as
+    /// in the net with the most significant bit first. The image of a set under a transition
+    /// restricts it to the markings enabling the transition and then shifts the counts of the
+    /// places it changes, one value at a time.
+    fn symbolic_reachability(&self, bits: u32) -> std::result::Result<SymbolicSummary, Unfinished> {
+        let capacity = (1usize << bits) - 1;
+        let mut bdd = Bdd::new(self.places.len() as u32 * bits);
+        let first = |place: usize| place as u32 * bits;
+
+        let mut initial = TRUE;
+        for (index, place) in self.places.iter().enumerate().rev() {
+            let value = bdd.value(first(index), bits, place.initial_marking);
+            initial = bdd.and(value, initial);
+        }
+
+        // Markings with at least the given tokens in the place
+        let mut enabled = vec![];
+        for transition in &self.transitions {
+            let mut set = TRUE;
+            for (place, weight) in &transition.inputs {
+                let tokens = bdd.at_least(first(*place), bits, *weight);
+                set = bdd.and(set, tokens);
+            }
+            enabled.push(set);
+        }
+        // Change of the tokens in each place by each transition
+        let effects: Vec<Vec<(usize, isize)>> = self
+            .transitions
+            .iter()
+            .map(|t| {
+                let mut effect: HashMap<usize, isize> = HashMap::new();
+                for (place, weight) in &t.inputs {
+                    *effect.entry(*place).or_default() -= *weight as isize;
+                }
+                for (place, weight) in &t.outputs {
+                    *effect.entry(*place).or_default() += *weight as isize;
+                }
+                let mut effect: Vec<_> = effect.into_iter().filter(|(_, d)| *d != 0).collect();
+                effect.sort();
+                effect
+            })
+            .collect();
+
+        let mut reached = initial;
+        let mut frontier = initial;
+        while frontier != FALSE {
+            if let Some(reason) = ts::stopped() {
+                return Err(Unfinished::Stopped(reason));
+            }
+            let mut image = FALSE;
+            for (t, effect) in effects.iter().enumerate() {
+                let mut set = bdd.and(frontier, enabled[t]);
+                for (place, delta) in effect {
+                    let mut shifted = FALSE;
+                    for v in bdd.values(set, first(*place), bits) {
+                        let value = bdd.value(first(*place), bits, v);
+                        let with_value = bdd.and(set, value);
+                        let target = v as isize + delta;
+                        if target > capacity as isize {
+                            return Err(Unfinished::Exceeded(*place));
+                        }
+                        let rest = bdd.exists(with_value, first(*place), first(*place + 1));
+                        let value = bdd.value(first(*place), bits, target as usize);
+                        let moved = bdd.and(rest, value);
+                        shifted = bdd.or(shifted, moved);
+                    }
+                    set = shifted;
+                }
+                image = bdd.or(image, set);
+            }
+            frontier = bdd.diff(image, reached);
+            reached = bdd.or(reached, frontier);
+            bdd.cache.clear();
+        }
+
+        let live = enabled.iter().fold(FALSE, |set, e| bdd.or(set, *e));
+        let deadlocks = bdd.diff(reached, live);
+        Ok(SymbolicSummary {
+            markings: bdd.count(reached),
+            deadlocks: bdd.count(deadlocks),
+            bits,
+            nodes: node_count(&bdd, reached),
+        })
+    }
+}
+
+/// Number of nodes reachable from the root, terminals included
+fn node_count(bdd: &Bdd, root: Node) -> usize {
+    let mut seen = HashSet::from([root]);
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if node <= TRUE {
+            continue;
+        }
+        let (_, low, high) = bdd.nodes[node as usize];
+        for child in [low, high] {
+            if seen.insert(child) {
+                stack.push(child);
+            }
+        }
+    }
+    seen.len()
+}
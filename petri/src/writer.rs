@@ -0,0 +1,143 @@
+// PNML and TINA output of place/transition nets, readable by `from_xml`, `from_tina` and other
+// tools. TINA has no rates, so they are only kept in PNML.
+
+use std::fmt::Write;
+
+use crate::PetriNet;
+
+impl PetriNet {
+    /// The net as a PNML document with a single page
+    pub fn to_pnml(&self) -> String {
+        let mut pnml = String::new();
+        writeln!(pnml, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(
+            pnml,
+            r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">"#
+        )
+        .unwrap();
+        writeln!(
+            pnml,
+            r#"  <net id="net" type="http://www.pnml.org/version-2009/grammar/ptnet">"#
+        )
+        .unwrap();
+        writeln!(pnml, r#"    <page id="page">"#).unwrap();
+        for place in &self.places {
+            let id = escape(&place.label);
+            write!(
+                pnml,
+                r#"      <place id="{id}"><name><text>{id}</text></name>"#
+            )
+            .unwrap();
+            if place.initial_marking > 0 {
+                write!(
+                    pnml,
+                    "<initialMarking><text>{}</text></initialMarking>",
+                    place.initial_marking
+                )
+                .unwrap();
+            }
+            writeln!(pnml, "</place>").unwrap();
+        }
+        for transition in &self.transitions {
+            let id = escape(&transition.label);
+            write!(
+                pnml,
+                r#"      <transition id="{id}"><name><text>{id}</text></name>"#
+            )
+            .unwrap();
+            if let Some(rate) = transition.rate {
+                write!(
+                    pnml,
+                    r#"<toolspecific tool="lmc" version="0.1"><rate>{}</rate></toolspecific>"#,
+                    rate
+                )
+                .unwrap();
+            }
+            writeln!(pnml, "</transition>").unwrap();
+        }
+        let mut arcs = 0;
+        for transition in &self.transitions {
+            let arcs_of = |arcs: &[(usize, usize)]| {
+                arcs.iter()
+                    .map(|(place, weight)| (escape(&self.places[*place].label), *weight))
+                    .collect::<Vec<_>>()
+            };
+            let label = escape(&transition.label);
+            let inputs = arcs_of(&transition.inputs)
+                .into_iter()
+                .map(|(place, weight)| (place, label.clone(), weight));
+            let outputs = arcs_of(&transition.outputs)
+                .into_iter()
+                .map(|(place, weight)| (label.clone(), place, weight));
+            for (source, target, weight) in inputs.chain(outputs) {
+                write!(
+                    pnml,
+                    r#"      <arc id="a{arcs}" source="{source}" target="{target}">"#
+                )
+                .unwrap();
+                if weight != 1 {
+                    write!(pnml, "<inscription><text>{}</text></inscription>", weight).unwrap();
+                }
+                writeln!(pnml, "</arc>").unwrap();
+                arcs += 1;
+            }
+        }
+        writeln!(pnml, "    </page>").unwrap();
+        writeln!(pnml, "  </net>").unwrap();
+        writeln!(pnml, "</pnml>").unwrap();
+        pnml
+    }
+
+    /// The net in the textual `.net` format of TINA, a line per place with its initial tokens and
+    /// per transition with its arcs
+    pub fn to_tina(&self) -> String {
+        let mut net = String::from("net net\n");
+        let arcs = |arcs: &[(usize, usize)]| {
+            arcs.iter()
+                .map(|(place, weight)| {
+                    let place = tina_name(&self.places[*place].label);
+                    match weight {
+                        1 => format!(" {}", place),
+                        weight => format!(" {}*{}", place, weight),
+                    }
+                })
+                .collect::<String>()
+        };
+        // Places come first so they are declared in the same order when the net is read back
+        for place in &self.places {
+            write!(net, "pl {}", tina_name(&place.label)).unwrap();
+            if place.initial_marking > 0 {
+                write!(net, " ({})", place.initial_marking).unwrap();
+            }
+            writeln!(net).unwrap();
+        }
+        for transition in &self.transitions {
+            writeln!(
+                net,
+                "tr {}{} ->{}",
+                tina_name(&transition.label),
+                arcs(&transition.inputs),
+                arcs(&transition.outputs)
+            )
+            .unwrap();
+        }
+        net
+    }
+}
+
+/// Names with other characters than letters, digits, '_', '\'' and '.' are put in braces
+fn tina_name(name: &str) -> String {
+    let plain = |c: char| c.is_alphanumeric() || matches!(c, '_' | '\'' | '.');
+    if !name.is_empty() && name.chars().all(plain) {
+        name.to_string()
+    } else {
+        format!("{{{}}}", name.replace('\\', "\\\\").replace('}', "\\}"))
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
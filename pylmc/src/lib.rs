@@ -0,0 +1,386 @@
+// Python module `pylmc`, for scripting the checkers in courses and experiments
+// Built with `maturin develop --release`
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+use std::path::Path;
+
+use lmc::buchi::hoa;
+use lmc::buchi::nba::{Buchi, Word};
+use lmc::check::Checker;
+use lmc::ltl::alphabet::Literals;
+use lmc::ltl::{self, Formula};
+use lmc::parity::{Graph, MetaData};
+use lmc::petri::{self, PetriNet};
+use lmc::transform::ltl_to_gnba;
+use pyo3::exceptions::{PyOSError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+fn invalid(e: impl Display) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// LTL formula, in prefix notation such as 'G | !r F g' or with syntax="spot" in Spot's infix
+/// notation such as 'G (r -> F g)'
+#[pyclass(name = "Formula", module = "pylmc", frozen, eq, hash, from_py_object)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PyFormula {
+    formula: Formula,
+}
+
+#[pymethods]
+impl PyFormula {
+    #[new]
+    #[pyo3(signature = (text, syntax = "prefix"))]
+    fn new(text: &str, syntax: &str) -> PyResult<Self> {
+        let formula = match syntax {
+            "prefix" => Formula::parse(text),
+            "spot" => ltl::spot::parse(text),
+            _ => return Err(invalid(format!("Unknown syntax '{}'", syntax))),
+        };
+        Ok(PyFormula {
+            formula: formula.map_err(invalid)?,
+        })
+    }
+
+    /// Equivalent formula in positive normal form
+    fn pnf(&self) -> Self {
+        PyFormula {
+            formula: self.formula.pnf(),
+        }
+    }
+
+    fn negation(&self) -> Self {
+        PyFormula {
+            formula: self.formula.negation(),
+        }
+    }
+
+    /// Atomic propositions in lexicographic order
+    fn propositions(&self) -> Vec<String> {
+        self.formula.aps().iter().map(String::from).collect()
+    }
+
+    /// Büchi automaton accepting the runs satisfying the formula, with an acceptance set per
+    /// until subformula if `generalized`
+    #[pyo3(signature = (generalized = false))]
+    fn automaton(&self, generalized: bool) -> PyAutomaton {
+        let gnba = ltl_to_gnba(&self.formula.pnf());
+        PyAutomaton {
+            automaton: if generalized {
+                gnba
+            } else {
+                gnba.gnba_to_nba()
+            },
+        }
+    }
+
+    fn __str__(&self) -> String {
+        self.formula.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        match self.formula.to_spot() {
+            Some(spot) => format!("Formula({:?}, syntax=\"spot\")", spot),
+            None => format!("<Formula {}>", self.formula),
+        }
+    }
+}
+
+/// Büchi automaton over valuations of atomic propositions
+#[pyclass(name = "Automaton", module = "pylmc", frozen)]
+struct PyAutomaton {
+    automaton: Buchi,
+}
+
+#[pymethods]
+impl PyAutomaton {
+    /// Read an automaton in the Hanoi Omega Automata format
+    #[staticmethod]
+    fn from_hoa(text: &str) -> PyResult<Self> {
+        Ok(PyAutomaton {
+            automaton: hoa::parse(text).map_err(invalid)?,
+        })
+    }
+
+    fn states(&self) -> Vec<usize> {
+        let states = self.automaton.states().into_iter().map(|s| s.id);
+        states.collect::<BTreeSet<_>>().into_iter().collect()
+    }
+
+    fn initial_states(&self) -> Vec<usize> {
+        let states = self.automaton.initial_states().iter().map(|s| s.id);
+        states.collect::<BTreeSet<_>>().into_iter().collect()
+    }
+
+    /// States of each acceptance set
+    fn accepting_sets(&self) -> Vec<Vec<usize>> {
+        (self.automaton.accepting_sets().iter())
+            .map(|set| {
+                let states = set.iter().map(|s| s.id);
+                states.collect::<BTreeSet<_>>().into_iter().collect()
+            })
+            .collect()
+    }
+
+    /// Whether the automaton accepts no word
+    fn is_empty(&self) -> bool {
+        self.automaton.verify().is_ok()
+    }
+
+    /// Whether the word prefix cycle^ω is accepted, every letter is the set of the propositions
+    /// holding in it
+    fn accepts(&self, prefix: Vec<BTreeSet<String>>, cycle: Vec<BTreeSet<String>>) -> bool {
+        let aps = self.automaton.aps();
+        let letter = |holding: &BTreeSet<String>| {
+            let literals = (aps.iter().enumerate()).fold(Literals::empty(), |literals, (i, ap)| {
+                literals.with(i, holding.contains(ap))
+            });
+            Word::guard(aps, literals)
+        };
+        let prefix: Vec<Word> = prefix.iter().map(letter).collect();
+        let cycle: Vec<Word> = cycle.iter().map(letter).collect();
+        self.automaton.accepts(&prefix, &cycle)
+    }
+
+    fn hoa(&self) -> String {
+        self.automaton.hoa()
+    }
+
+    fn dot(&self) -> String {
+        self.automaton.to_dot()
+    }
+
+    fn __len__(&self) -> usize {
+        self.automaton.states().len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Automaton with {}>", self.automaton.stats())
+    }
+}
+
+/// Petri net in PNML, the TINA .net format or LoLA's format
+#[pyclass(name = "PetriNet", module = "pylmc", frozen)]
+struct PyPetriNet {
+    net: PetriNet,
+}
+
+#[pymethods]
+impl PyPetriNet {
+    /// Read the net in the format given by the extension of the file or its content
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        let input = std::fs::read_to_string(path).map_err(PyOSError::new_err)?;
+        let format = petri::Format::detect(Path::new(path), &input);
+        Ok(PyPetriNet {
+            net: petri::parse(&input, format).map_err(invalid)?,
+        })
+    }
+
+    /// Parse the net in the format detected from its content
+    #[staticmethod]
+    fn parse(text: &str) -> PyResult<Self> {
+        let format = petri::Format::detect(Path::new(""), text);
+        Ok(PyPetriNet {
+            net: petri::parse(text, format).map_err(invalid)?,
+        })
+    }
+
+    fn places(&self) -> Vec<String> {
+        self.net
+            .place_names()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn transitions(&self) -> Vec<String> {
+        (self.net.transition_names().into_iter())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Tokens on each place in the initial marking
+    fn initial_marking(&self) -> PyResult<BTreeMap<String, usize>> {
+        let marking = self.net.initial_marking();
+        (self.net.place_names().into_iter())
+            .map(|place| {
+                Ok((
+                    place.to_string(),
+                    self.net.tokens(&marking, place).map_err(invalid)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Transitions fired before and in the repeated cycle of a run violating the formula, None
+    /// if every run satisfies it. Propositions are place names, holding if the place has
+    /// tokens, or token constraints such as '2<=tokens(p1)'.
+    fn counterexample(&self, formula: &PyFormula) -> PyResult<Option<(Vec<String>, Vec<String>)>> {
+        let outcome = (Checker::new(&self.net, false).check(&formula.formula))
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        let names = |firings: &[(&str, _)]| firings.iter().map(|(t, _)| t.to_string()).collect();
+        Ok(outcome
+            .counterexample
+            .map(|run| (names(&run.stem), names(&run.cycle))))
+    }
+
+    /// Whether every run satisfies the formula
+    fn holds(&self, formula: &PyFormula) -> PyResult<bool> {
+        Ok(self.counterexample(formula)?.is_none())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<PetriNet with {} places, {} transitions>",
+            self.net.place_names().len(),
+            self.net.transition_names().len()
+        )
+    }
+}
+
+/// Parity game in the PGSolver format
+#[pyclass(name = "ParityGame", module = "pylmc", frozen)]
+struct PyParityGame {
+    graph: Graph,
+}
+
+#[pymethods]
+impl PyParityGame {
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        let input = std::fs::read_to_string(path).map_err(PyOSError::new_err)?;
+        Self::parse(&input)
+    }
+
+    #[staticmethod]
+    fn parse(text: &str) -> PyResult<Self> {
+        Ok(PyParityGame {
+            graph: lmc::parity::parse_game(text).map_err(invalid)?,
+        })
+    }
+
+    /// Solve the game with "fpi", "zielonka", "tangle" or "spm"
+    #[pyo3(signature = (algorithm = "fpi"))]
+    fn solve(&self, py: Python<'_>, algorithm: &str) -> PyResult<PySolution> {
+        let solver = match algorithm {
+            "fpi" => Graph::fpi,
+            "zielonka" => Graph::zielonka,
+            "tangle" => Graph::tangle,
+            "spm" => Graph::spm,
+            _ => return Err(invalid(format!("Unknown algorithm '{}'", algorithm))),
+        };
+        let solution = py.detach(|| solver(&self.graph));
+        let region = |region: &HashSet<&MetaData>| region.iter().map(|v| v.id).collect();
+        Ok(PySolution {
+            even: region(&solution.even_region),
+            odd: region(&solution.odd_region),
+            strategy: (solution.strategy.iter())
+                .filter_map(|(vertex, strategy)| Some((*vertex, strategy.next_node_id?)))
+                .collect(),
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.graph.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<ParityGame with {} vertices>", self.graph.len())
+    }
+}
+
+/// Winning regions of the players and the successors the winners move to
+#[pyclass(name = "Solution", module = "pylmc", frozen, get_all)]
+struct PySolution {
+    even: BTreeSet<usize>,
+    odd: BTreeSet<usize>,
+    strategy: HashMap<usize, usize>,
+}
+
+#[pymethods]
+impl PySolution {
+    /// 0 if even wins from the vertex, 1 if odd does
+    fn winner(&self, vertex: usize) -> PyResult<u8> {
+        if self.even.contains(&vertex) {
+            Ok(0)
+        } else if self.odd.contains(&vertex) {
+            Ok(1)
+        } else {
+            Err(PyValueError::new_err(format!("No vertex {}", vertex)))
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Solution with {} vertices won by even, {} by odd>",
+            self.even.len(),
+            self.odd.len()
+        )
+    }
+}
+
+/// Checkers of lmc: `Formula` parses LTL formulas and translates them into `Automaton`s,
+/// `PetriNet` loads nets and checks formulas on them, and `ParityGame` solves games into a
+/// `Solution`. Parse errors raise ValueError, unreadable files OSError and failed checks
+/// RuntimeError.
+#[pymodule]
+fn pylmc(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyFormula>()?;
+    m.add_class::<PyAutomaton>()?;
+    m.add_class::<PyPetriNet>()?;
+    m.add_class::<PyParityGame>()?;
+    m.add_class::<PySolution>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use pyo3::ffi::c_str;
+    use pyo3::types::PyDict;
+
+    use super::*;
+
+    #[test]
+    pub fn module() {
+        Python::attach(|py| {
+            let module = pyo3::wrap_pymodule!(pylmc)(py);
+            let globals = PyDict::new(py);
+            globals.set_item("pylmc", module).unwrap();
+            py.run(
+                c_str!(
+                    r#"
+f = pylmc.Formula("G (a -> F b)", syntax="spot")
+assert f.propositions() == ["a", "b"]
+assert f == pylmc.Formula("G | !a F b")
+assert eval(repr(f), {"Formula": pylmc.Formula}) == f
+automaton = f.automaton()
+assert not automaton.is_empty()
+assert automaton.accepts([], [{"a", "b"}])
+assert not automaton.accepts([{"a"}], [set()])
+assert pylmc.Formula("& a !a").automaton().is_empty()
+try:
+    pylmc.Formula("U a")
+    assert False
+except ValueError:
+    pass
+
+net = pylmc.PetriNet.parse("tr t0 p0 -> p1\ntr t1 p1 -> p0\npl p0 (1)")
+assert net.initial_marking() == {"p0": 1, "p1": 0}
+assert net.holds(pylmc.Formula("G F 1<=tokens(p1)"))
+stem, cycle = net.counterexample(pylmc.Formula("G tokens(p1)<=0"))
+assert stem + cycle
+
+game = pylmc.ParityGame.parse("parity 2;\n0 2 0 1;\n1 1 1 0;")
+solution = game.solve("zielonka")
+assert solution.even == {0, 1} and solution.winner(1) == 0
+"#
+                ),
+                Some(&globals),
+                None,
+            )
+            .unwrap();
+        });
+    }
+}
@@ -0,0 +1,283 @@
+mod solver;
+
+pub use solver::Solver;
+use std::fmt::Display;
+use std::ops::Not;
+
+/// A propositional variable, created by a solver
+#[derive(Debug, Eq, Clone, Copy, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Var(u32);
+
+/// A variable or its negation
+#[derive(Debug, Eq, Clone, Copy, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Lit(u32);
+
+impl Var {
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn positive(&self) -> Lit {
+        Lit(self.0 << 1)
+    }
+
+    pub fn negative(&self) -> Lit {
+        Lit(self.0 << 1 | 1)
+    }
+}
+
+impl Lit {
+    pub fn var(&self) -> Var {
+        Var(self.0 >> 1)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+impl Display for Lit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_negative() {
+            write!(f, "-{}", self.var().0 + 1)
+        } else {
+            write!(f, "{}", self.var().0 + 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn simple_sat() {
+        let mut solver = Solver::new();
+        let a = solver.new_var();
+        let b = solver.new_var();
+        solver.add_clause([a.positive(), b.positive()]);
+        solver.add_clause([a.negative()]);
+
+        assert!(solver.solve());
+        assert_eq!(solver.value(a), Some(false));
+        assert_eq!(solver.value(b), Some(true));
+    }
+
+    #[test]
+    pub fn simple_unsat() {
+        let mut solver = Solver::new();
+        let a = solver.new_var();
+        let b = solver.new_var();
+        solver.add_clause([a.positive(), b.positive()]);
+        solver.add_clause([a.negative(), b.positive()]);
+        solver.add_clause([a.positive(), b.negative()]);
+        solver.add_clause([a.negative(), b.negative()]);
+
+        assert!(!solver.solve());
+    }
+
+    #[test]
+    pub fn empty_clause() {
+        let mut solver = Solver::new();
+        solver.add_clause([]);
+        assert!(!solver.solve());
+    }
+
+    #[test]
+    pub fn pigeonhole() {
+        // 5 pigeons do not fit into 4 holes
+        let (pigeons, holes) = (5, 4);
+        let mut solver = Solver::new();
+        let vars: Vec<Vec<Var>> = (0..pigeons)
+            .map(|_| (0..holes).map(|_| solver.new_var()).collect())
+            .collect();
+        for p in &vars {
+            solver.add_clause(p.iter().map(Var::positive));
+        }
+        for (p1, p2) in (0..pigeons).flat_map(|p1| (p1 + 1..pigeons).map(move |p2| (p1, p2))) {
+            for (h1, h2) in vars[p1].iter().zip(&vars[p2]) {
+                solver.add_clause([h1.negative(), h2.negative()]);
+            }
+        }
+        assert!(!solver.solve());
+    }
+
+    #[test]
+    pub fn random_3sat_matches_brute_force() {
+        // Small linear congruential generator to stay deterministic without dependencies
+        let mut seed: u64 = 42;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+
+        for _ in 0..200 {
+            let num_vars = 8;
+            let clauses: Vec<Vec<(usize, bool)>> = (0..35)
+                .map(|_| {
+                    (0..3)
+                        .map(|_| (next(num_vars) as usize, next(2) == 0))
+                        .collect()
+                })
+                .collect();
+
+            let brute_force = (0..1u32 << num_vars).any(|assignment| {
+                clauses.iter().all(|c| {
+                    c.iter()
+                        .any(|(v, positive)| (assignment >> v & 1 == 1) == *positive)
+                })
+            });
+
+            let mut solver = Solver::new();
+            let vars: Vec<Var> = (0..num_vars).map(|_| solver.new_var()).collect();
+            for c in &clauses {
+                solver.add_clause(c.iter().map(|(v, positive)| {
+                    if *positive {
+                        vars[*v].positive()
+                    } else {
+                        vars[*v].negative()
+                    }
+                }));
+            }
+
+            let result = solver.solve();
+            assert_eq!(result, brute_force);
+            if result {
+                assert!(clauses.iter().all(|c| c
+                    .iter()
+                    .any(|(v, positive)| solver.value(vars[*v]) == Some(*positive))));
+            }
+        }
+    }
+
+    #[test]
+    pub fn incremental_matches_brute_force() {
+        // Clauses are added between calls, each solved under two assumed literals
+        let mut seed: u64 = 3;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+
+        for _ in 0..50 {
+            let num_vars = 8;
+            let mut solver = Solver::new();
+            let vars: Vec<Var> = (0..num_vars).map(|_| solver.new_var()).collect();
+            let mut clauses: Vec<Vec<(usize, bool)>> = vec![];
+            let satisfied = |assignment: u32, c: &[(usize, bool)]| {
+                c.iter()
+                    .any(|(v, positive)| (assignment >> v & 1 == 1) == *positive)
+            };
+            let lit = |(v, positive): (usize, bool)| match positive {
+                true => vars[v].positive(),
+                false => vars[v].negative(),
+            };
+            for _ in 0..8 {
+                for _ in 0..5 {
+                    let clause = (0..3)
+                        .map(|_| (next(num_vars) as usize, next(2) == 0))
+                        .collect::<Vec<_>>();
+                    solver.add_clause(clause.iter().map(|l| lit(*l)));
+                    clauses.push(clause);
+                }
+                let assumptions = (0..2)
+                    .map(|_| (next(num_vars) as usize, next(2) == 0))
+                    .collect::<Vec<_>>();
+
+                let brute_force = (0..1u32 << num_vars).any(|assignment| {
+                    clauses.iter().all(|c| satisfied(assignment, c))
+                        && assumptions.iter().all(|a| satisfied(assignment, &[*a]))
+                });
+                let result =
+                    solver.solve_with(&assumptions.iter().map(|a| lit(*a)).collect::<Vec<_>>());
+                assert_eq!(result, brute_force);
+                if result {
+                    let model = (0..num_vars as usize)
+                        .filter(|v| solver.value(vars[*v]) == Some(true))
+                        .map(|v| 1 << v)
+                        .sum();
+                    assert!(clauses.iter().all(|c| satisfied(model, c)));
+                    assert!(assumptions.iter().all(|a| satisfied(model, &[*a])));
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn large_random_3sat() {
+        // Instances near the threshold take thousands of conflicts, so learnt clauses get deleted
+        let mut seed: u64 = 7;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+
+        for _ in 0..5 {
+            let num_vars = 150;
+            let mut solver = Solver::new();
+            let vars: Vec<Var> = (0..num_vars).map(|_| solver.new_var()).collect();
+            let clauses: Vec<Vec<Lit>> = (0..630)
+                .map(|_| {
+                    (0..3)
+                        .map(|_| match next(2) {
+                            0 => vars[next(num_vars) as usize].positive(),
+                            _ => vars[next(num_vars) as usize].negative(),
+                        })
+                        .collect()
+                })
+                .collect();
+            for c in &clauses {
+                solver.add_clause(c.iter().cloned());
+            }
+
+            let result = solver.solve();
+            if result {
+                assert!(clauses.iter().all(|c| c
+                    .iter()
+                    .any(|l| solver.value(l.var()) == Some(!l.is_negative()))));
+            }
+        }
+    }
+
+    #[test]
+    pub fn model_satisfies_clauses() {
+        // A chain of implications x0 -> x1 -> ... -> x19 with x0 forced and x19 in a xor
+        let mut solver = Solver::new();
+        let vars: Vec<Var> = (0..20).map(|_| solver.new_var()).collect();
+        let mut clauses = vec![vec![vars[0].positive()]];
+        for w in vars.windows(2) {
+            clauses.push(vec![w[0].negative(), w[1].positive()]);
+        }
+        let extra = solver.new_var();
+        clauses.push(vec![vars[19].negative(), extra.negative()]);
+        clauses.push(vec![vars[19].positive(), extra.positive()]);
+        for c in &clauses {
+            solver.add_clause(c.iter().cloned());
+        }
+
+        assert!(solver.solve());
+        for c in &clauses {
+            assert!(c
+                .iter()
+                .any(|l| solver.value(l.var()) == Some(!l.is_negative())));
+        }
+    }
+}
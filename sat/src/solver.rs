@@ -0,0 +1,548 @@
+use crate::{Lit, Var};
+
+/// A conflict driven clause learning (CDCL) SAT solver with two watched literals, first UIP
+/// learning, VSIDS branching, deletion of inactive learnt clauses and Luby restarts.
+///
+/// After `solve` found a model, `value` returns it until clauses are added. Solving again keeps
+/// the learnt clauses, so a problem can be extended and solved incrementally, with
+/// `solve_with` assuming literals for a single call only.
+pub struct Solver {
+    clauses: Vec<Clause>,
+    // Clauses of length one are enqueued at the start of solving
+    units: Vec<Lit>,
+    // For every literal the clauses which currently watch it
+    watches: Vec<Vec<usize>>,
+    assignment: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    activity: Vec<f64>,
+    // Variables which may be unassigned, the most active first
+    order: Heap,
+    phase: Vec<bool>,
+    activity_increment: f64,
+    clause_increment: f64,
+    learnts: usize,
+    // Learnt clauses kept before the less active half of them is deleted
+    max_learnts: f64,
+    trail: Vec<Lit>,
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    unsatisfiable: bool,
+}
+
+struct Clause {
+    lits: Vec<Lit>,
+    learnt: bool,
+    // How often a learnt clause took part in recent conflicts
+    activity: f64,
+}
+
+const ACTIVITY_DECAY: f64 = 0.95;
+const CLAUSE_DECAY: f64 = 0.999;
+/// Conflicts between restarts are this interval times the next element of the Luby sequence
+const RESTART_INTERVAL: usize = 100;
+/// Learnt clauses kept at first, relative to the clauses of the problem but at least
+/// `MIN_LEARNTS`, the limit grows by `LEARNT_GROWTH` whenever clauses are deleted
+const LEARNT_FRACTION: f64 = 1.0 / 3.0;
+const MIN_LEARNTS: f64 = 1000.0;
+const LEARNT_GROWTH: f64 = 1.1;
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver {
+            clauses: vec![],
+            units: vec![],
+            watches: vec![],
+            assignment: vec![],
+            level: vec![],
+            reason: vec![],
+            activity: vec![],
+            order: Heap::default(),
+            phase: vec![],
+            activity_increment: 1.0,
+            clause_increment: 1.0,
+            learnts: 0,
+            max_learnts: 0.0,
+            trail: vec![],
+            trail_limits: vec![],
+            propagated: 0,
+            unsatisfiable: false,
+        }
+    }
+
+    /// Generate a new variable which can be used in clauses
+    pub fn new_var(&mut self) -> Var {
+        let var = Var(self.assignment.len() as u32);
+        self.assignment.push(None);
+        self.level.push(0);
+        self.reason.push(None);
+        self.activity.push(0.0);
+        self.order.insert(var.index(), &self.activity);
+        self.phase.push(false);
+        self.watches.push(vec![]);
+        self.watches.push(vec![]);
+        var
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.assignment.len()
+    }
+
+    /// Clauses of the problem and the learnt clauses currently kept
+    pub fn num_clauses(&self) -> usize {
+        self.clauses.len() + self.units.len()
+    }
+
+    /// Add a disjunction of literals, the literals must belong to variables of this solver
+    pub fn add_clause(&mut self, clause: impl IntoIterator<Item = Lit>) {
+        let mut clause: Vec<Lit> = clause.into_iter().collect();
+        clause.sort();
+        clause.dedup();
+        // Tautologies are always satisfied
+        if clause.windows(2).any(|w| w[0] == !w[1]) {
+            return;
+        }
+        // Literals assigned at level 0 by an earlier call to `solve` are fixed, the watched
+        // literals of the clause must not be false among them
+        self.backtrack(0);
+        if clause.iter().any(|l| self.lit_value(*l) == Some(true)) {
+            return;
+        }
+        clause.retain(|l| self.lit_value(*l).is_none());
+
+        match clause.len() {
+            0 => self.unsatisfiable = true,
+            1 => self.units.push(clause[0]),
+            _ => {
+                self.attach(clause, false);
+            }
+        }
+    }
+
+    /// The value of the variable in the model found by the last call to `solve`
+    pub fn value(&self, var: Var) -> Option<bool> {
+        self.assignment[var.index()]
+    }
+
+    fn lit_value(&self, lit: Lit) -> Option<bool> {
+        self.assignment[lit.var().index()].map(|v| v != lit.is_negative())
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    /// Store the clause and watch its first two literals
+    fn attach(&mut self, lits: Vec<Lit>, learnt: bool) -> usize {
+        let index = self.clauses.len();
+        self.watches[lits[0].index()].push(index);
+        self.watches[lits[1].index()].push(index);
+        self.clauses.push(Clause {
+            lits,
+            learnt,
+            activity: 0.0,
+        });
+        index
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var().index();
+        self.assignment[var] = Some(!lit.is_negative());
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let limit = self.trail_limits[level];
+        for lit in self.trail.drain(limit..) {
+            let var = lit.var().index();
+            self.phase[var] = !lit.is_negative();
+            self.assignment[var] = None;
+            self.reason[var] = None;
+            self.order.insert(var, &self.activity);
+        }
+        self.trail_limits.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    /// Propagate all enqueued literals, returns a conflicting clause if one is found
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = !self.trail[self.propagated];
+            self.propagated += 1;
+
+            let watching = std::mem::take(&mut self.watches[false_lit.index()]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+
+            for (i, &c) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[i..]);
+                    break;
+                }
+
+                // Make sure the false literal is the second watch
+                let lits = &mut self.clauses[c].lits;
+                if lits[0] == false_lit {
+                    lits.swap(0, 1);
+                }
+                let first = lits[0];
+                if self.lit_value(first) == Some(true) {
+                    kept.push(c);
+                    continue;
+                }
+
+                // Look for a new literal to watch
+                let lits = &self.clauses[c].lits;
+                let replacement = (2..lits.len()).find(|&j| self.lit_value(lits[j]) != Some(false));
+                match replacement {
+                    Some(j) => {
+                        self.clauses[c].lits.swap(1, j);
+                        let new_watch = self.clauses[c].lits[1];
+                        self.watches[new_watch.index()].push(c);
+                    }
+                    None => {
+                        kept.push(c);
+                        match self.lit_value(first) {
+                            Some(false) => conflict = Some(c),
+                            _ => self.enqueue(first, Some(c)),
+                        }
+                    }
+                }
+            }
+
+            self.watches[false_lit.index()] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Derive the first UIP clause from the conflict, returns it with the level to backtrack to.
+    /// The asserting literal is the first one in the clause.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut seen = vec![false; self.num_vars()];
+        let mut learnt = vec![];
+        let mut counter = 0;
+        let mut clause = conflict;
+        let mut asserting: Option<Lit> = None;
+        let mut index = self.trail.len();
+
+        loop {
+            if self.clauses[clause].learnt {
+                self.bump_clause(clause);
+            }
+            let literals = self.clauses[clause].lits.clone();
+            // The first literal of a reason clause is the implied literal itself
+            let skip = if asserting.is_some() { 1 } else { 0 };
+            for &q in &literals[skip..] {
+                let var = q.var().index();
+                if !seen[var] && self.level[var] > 0 {
+                    seen[var] = true;
+                    self.bump(var);
+                    if self.level[var] == self.decision_level() {
+                        counter += 1;
+                    } else {
+                        learnt.push(q);
+                    }
+                }
+            }
+
+            // Select the next literal of the current level to resolve on
+            loop {
+                index -= 1;
+                if seen[self.trail[index].var().index()] {
+                    break;
+                }
+            }
+            let p = self.trail[index];
+            seen[p.var().index()] = false;
+            counter -= 1;
+            asserting = Some(p);
+            if counter == 0 {
+                break;
+            }
+            clause = self.reason[p.var().index()].expect("Implied literal without reason");
+        }
+
+        learnt.insert(0, !asserting.unwrap());
+        // Watch the literal with the highest level as second literal
+        let mut backtrack_level = 0;
+        if learnt.len() > 1 {
+            let (max_index, _) = learnt
+                .iter()
+                .enumerate()
+                .skip(1)
+                .max_by_key(|(_, l)| self.level[l.var().index()])
+                .unwrap();
+            learnt.swap(1, max_index);
+            backtrack_level = self.level[learnt[1].var().index()];
+        }
+
+        self.activity_increment /= ACTIVITY_DECAY;
+        self.clause_increment /= CLAUSE_DECAY;
+        (learnt, backtrack_level)
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.activity_increment;
+        if self.activity[var] > 1e100 {
+            for a in self.activity.iter_mut() {
+                *a *= 1e-100;
+            }
+            self.activity_increment *= 1e-100;
+        }
+        self.order.increased(var, &self.activity);
+    }
+
+    fn bump_clause(&mut self, clause: usize) {
+        self.clauses[clause].activity += self.clause_increment;
+        if self.clauses[clause].activity > 1e20 {
+            for c in self.clauses.iter_mut().filter(|c| c.learnt) {
+                c.activity *= 1e-20;
+            }
+            self.clause_increment *= 1e-20;
+        }
+    }
+
+    /// Delete the less active half of the learnt clauses, keeping binary ones. Only called at
+    /// level 0, where the reasons of assignments are no longer needed for conflict analysis.
+    fn reduce(&mut self) {
+        let mut candidates = (0..self.clauses.len())
+            .filter(|c| self.clauses[*c].learnt && self.clauses[*c].lits.len() > 2)
+            .collect::<Vec<_>>();
+        candidates
+            .sort_by(|a, b| (self.clauses[*a].activity).total_cmp(&self.clauses[*b].activity));
+        let mut deleted = vec![false; self.clauses.len()];
+        for c in &candidates[..candidates.len() / 2] {
+            deleted[*c] = true;
+        }
+        self.learnts -= candidates.len() / 2;
+
+        // Renumber the remaining clauses and watch them again
+        let mut renumbered = vec![None; self.clauses.len()];
+        let mut kept = 0;
+        for (c, deleted) in deleted.iter().enumerate() {
+            if !deleted {
+                renumbered[c] = Some(kept);
+                kept += 1;
+            }
+        }
+        let mut index = 0;
+        self.clauses.retain(|_| {
+            index += 1;
+            !deleted[index - 1]
+        });
+        for reason in self.reason.iter_mut() {
+            *reason = reason.and_then(|c| renumbered[c]);
+        }
+        for watches in self.watches.iter_mut() {
+            watches.clear();
+        }
+        for (c, clause) in self.clauses.iter().enumerate() {
+            self.watches[clause.lits[0].index()].push(c);
+            self.watches[clause.lits[1].index()].push(c);
+        }
+    }
+
+    fn pick_branch(&mut self) -> Option<Var> {
+        while let Some(var) = self.order.pop(&self.activity) {
+            if self.assignment[var].is_none() {
+                return Some(Var(var as u32));
+            }
+        }
+        None
+    }
+
+    /// Decide whether the clauses are satisfiable, the model can then be queried with `value`
+    pub fn solve(&mut self) -> bool {
+        self.solve_with(&[])
+    }
+
+    /// Decide whether the clauses are satisfiable with the assumed literals true. The assumptions
+    /// are the first decisions and only hold for this call, unlike clauses of a single literal.
+    pub fn solve_with(&mut self, assumptions: &[Lit]) -> bool {
+        if self.unsatisfiable {
+            return false;
+        }
+        self.backtrack(0);
+        for lit in self.units.clone() {
+            match self.lit_value(lit) {
+                Some(false) => {
+                    self.unsatisfiable = true;
+                    return false;
+                }
+                Some(true) => {}
+                None => self.enqueue(lit, None),
+            }
+        }
+        let problem = (self.clauses.len() - self.learnts) as f64;
+        self.max_learnts = (problem * LEARNT_FRACTION)
+            .max(MIN_LEARNTS)
+            .max(self.max_learnts);
+
+        let mut conflicts = 0;
+        let mut restarts = 0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.decision_level() == 0 {
+                    self.unsatisfiable = true;
+                    return false;
+                }
+                conflicts += 1;
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                if learnt.len() == 1 {
+                    self.units.push(learnt[0]);
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let index = self.attach(learnt, true);
+                    self.bump_clause(index);
+                    self.learnts += 1;
+                    self.enqueue(asserting, Some(index));
+                }
+            } else if conflicts >= RESTART_INTERVAL * luby(restarts)
+                || self.learnts as f64 >= self.max_learnts
+            {
+                if conflicts >= RESTART_INTERVAL * luby(restarts) {
+                    conflicts = 0;
+                    restarts += 1;
+                }
+                self.backtrack(0);
+                if self.learnts as f64 >= self.max_learnts {
+                    self.reduce();
+                    self.max_learnts *= LEARNT_GROWTH;
+                }
+            } else if let Some(&assumption) = assumptions.get(self.decision_level()) {
+                match self.lit_value(assumption) {
+                    Some(false) => return false,
+                    // Keep the levels in line with the assumptions
+                    Some(true) => self.trail_limits.push(self.trail.len()),
+                    None => {
+                        self.trail_limits.push(self.trail.len());
+                        self.enqueue(assumption, None);
+                    }
+                }
+            } else {
+                match self.pick_branch() {
+                    Some(var) => {
+                        self.trail_limits.push(self.trail.len());
+                        let lit = if self.phase[var.index()] {
+                            var.positive()
+                        } else {
+                            var.negative()
+                        };
+                        self.enqueue(lit, None);
+                    }
+                    None => return true,
+                }
+            }
+        }
+    }
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Element i of the Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ...
+fn luby(mut i: usize) -> usize {
+    // Size of the smallest complete subsequence 1, ..., 2^k containing the element
+    let (mut size, mut power) = (1, 1);
+    while size < i + 1 {
+        size = 2 * size + 1;
+        power *= 2;
+    }
+    while size - 1 != i {
+        size = (size - 1) / 2;
+        power /= 2;
+        i %= size;
+    }
+    power
+}
+
+/// Binary heap of variables, the most active one on top
+#[derive(Default)]
+struct Heap {
+    vars: Vec<usize>,
+    // Index of every variable in `vars`, if it is in the heap
+    positions: Vec<Option<usize>>,
+}
+
+impl Heap {
+    fn insert(&mut self, var: usize, activity: &[f64]) {
+        if self.positions.len() <= var {
+            self.positions.resize(var + 1, None);
+        }
+        if self.positions[var].is_none() {
+            self.vars.push(var);
+            self.sift_up(self.vars.len() - 1, activity);
+        }
+    }
+
+    /// Restore the order after the activity of the variable increased
+    fn increased(&mut self, var: usize, activity: &[f64]) {
+        if let Some(position) = self.positions[var] {
+            self.sift_up(position, activity);
+        }
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        let top = *self.vars.first()?;
+        let last = self.vars.pop().unwrap();
+        self.positions[top] = None;
+        if !self.vars.is_empty() {
+            self.vars[0] = last;
+            self.sift_down(0, activity);
+        }
+        Some(top)
+    }
+
+    fn sift_up(&mut self, mut i: usize, activity: &[f64]) {
+        let var = self.vars[i];
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if activity[self.vars[parent]] >= activity[var] {
+                break;
+            }
+            self.vars[i] = self.vars[parent];
+            self.positions[self.vars[i]] = Some(i);
+            i = parent;
+        }
+        self.vars[i] = var;
+        self.positions[var] = Some(i);
+    }
+
+    fn sift_down(&mut self, mut i: usize, activity: &[f64]) {
+        let var = self.vars[i];
+        loop {
+            let left = 2 * i + 1;
+            if left >= self.vars.len() {
+                break;
+            }
+            let right = left + 1;
+            let child = match right < self.vars.len()
+                && activity[self.vars[right]] > activity[self.vars[left]]
+            {
+                true => right,
+                false => left,
+            };
+            if activity[self.vars[child]] <= activity[var] {
+                break;
+            }
+            self.vars[i] = self.vars[child];
+            self.positions[self.vars[i]] = Some(i);
+            i = child;
+        }
+        self.vars[i] = var;
+        self.positions[var] = Some(i);
+    }
+}
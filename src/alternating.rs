@@ -0,0 +1,163 @@
+// Translation of LTL formulas to NBAs via alternating Büchi automata
+
+use std::collections::{BTreeSet, HashMap};
+
+use buchi::alternating::AlternatingBuchi;
+use buchi::nba::Buchi;
+use itertools::Itertools;
+use ltl::alphabet::{ApSet, Literals};
+use ltl::intern::{ExprId, ExprTable, Node};
+use ltl::Formula;
+
+/// Alternatives of a formula, a guard and the formulas which have to hold at the next position
+type Transitions = Vec<(Literals, BTreeSet<ExprId>)>;
+
+/// Translate the formula into an alternating automaton, only reachable states are created
+///
+/// The states are the subformulas of the formula in PNF. Untils may not be postponed forever, so
+/// every state except the untils is accepting.
+pub fn ltl_to_aba(formula: &Formula) -> AlternatingBuchi {
+    let formula = formula.pnf();
+    let mut table = ExprTable::new();
+    let root = table.intern(&formula.root_expr);
+    let aps = formula.aps();
+    let mut aba = AlternatingBuchi::new();
+    aba.set_aps(aps.clone());
+
+    let mut states = HashMap::new();
+    let mut pending = vec![];
+    let mut state = |aba: &mut AlternatingBuchi, pending: &mut Vec<_>, expr: ExprId| {
+        *states.entry(expr).or_insert_with(|| {
+            let new = aba.new_state(table.expr(expr).to_string());
+            pending.push((expr, new));
+            new
+        })
+    };
+    let initial = state(&mut aba, &mut pending, root);
+    aba.add_initial([initial]);
+
+    while let Some((expr, source)) = pending.pop() {
+        if !matches!(table.node(expr), Node::Until(_, _)) {
+            aba.set_accepting(source);
+        }
+        for (guard, targets) in transitions(&table, &aps, expr) {
+            let targets = targets
+                .into_iter()
+                .map(|t| state(&mut aba, &mut pending, t))
+                .collect_vec();
+            aba.add_transition(source, guard, targets);
+        }
+    }
+    aba
+}
+
+/// Translate the formula into an alternating automaton and remove the alternation
+pub fn ltl_to_nba_alternating(formula: &Formula) -> Buchi {
+    ltl_to_aba(formula).to_nba()
+}
+
+/// Expansion of the formula for one letter, the formula has to be in PNF. A conjunction branches
+/// universally, a disjunction existentially.
+fn transitions(table: &ExprTable, aps: &ApSet, expr: ExprId) -> Transitions {
+    let conjunction = |lhs: Transitions, rhs: Transitions| {
+        lhs.iter()
+            .cartesian_product(&rhs)
+            .filter_map(|((l, l_next), (r, r_next))| {
+                Some((l.and(r)?, l_next.union(r_next).copied().collect()))
+            })
+            .collect_vec()
+    };
+    let stay = || vec![(Literals::empty(), BTreeSet::from([expr]))];
+    let next = |e: ExprId| transitions(table, aps, e);
+    match table.node(expr) {
+        Node::True => vec![(Literals::empty(), BTreeSet::new())],
+        Node::False => vec![],
+        Node::Atomic(_) | Node::Not(_) => {
+            let literal = aps
+                .literal(&table.expr(expr))
+                .expect("Formulas in PNF only negate propositions");
+            vec![(literal, BTreeSet::new())]
+        }
+        Node::And(a, b) => conjunction(next(*a), next(*b)),
+        Node::Or(a, b) => next(*a).into_iter().chain(next(*b)).collect(),
+        Node::Next(e) => vec![(Literals::empty(), BTreeSet::from([*e]))],
+        // a U b ≡ b ∨ (a ∧ X(a U b))
+        Node::Until(a, b) => next(*b)
+            .into_iter()
+            .chain(conjunction(next(*a), stay()))
+            .collect(),
+        // a R b ≡ b ∧ (a ∨ X(a R b))
+        Node::Release(a, b) => conjunction(next(*b), next(*a).into_iter().chain(stay()).collect()),
+        _ => unreachable!("Formulas in PNF do not contain derived operators"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::{ltl_to_gnba, ltl_to_gnba_powerset};
+
+    #[test]
+    pub fn same_language_as_tableau() {
+        for f in [
+            "a",
+            "X !a",
+            "U a b",
+            "R a b",
+            "G | !r F g",
+            "& G F a G F b",
+            "| X X a U b !c",
+            "& F a G !a",
+            "U[<=2] a F[<=1] b",
+        ] {
+            let formula = Formula::parse(f).unwrap();
+            let negation = formula.negation();
+            assert!(
+                ltl_to_nba_alternating(&formula)
+                    .intersect(&ltl_to_gnba(&negation))
+                    .verify()
+                    .is_ok(),
+                "{} accepts too much",
+                f
+            );
+            assert!(
+                ltl_to_gnba(&formula)
+                    .intersect(&ltl_to_nba_alternating(&negation))
+                    .verify()
+                    .is_ok(),
+                "{} accepts too little",
+                f
+            );
+        }
+    }
+
+    #[test]
+    pub fn translations_are_equivalent() {
+        for f in ["U a b", "G | !r F g", "& G F a G F b", "U a U b c", "| G a F b"] {
+            let formula = Formula::parse(f).unwrap();
+            let translations = [
+                ltl_to_gnba(&formula),
+                ltl_to_gnba_powerset(&formula),
+                ltl_to_nba_alternating(&formula),
+            ];
+            for (a, b) in translations.iter().tuple_combinations() {
+                assert!(a.equivalent(b).is_ok(), "{} is translated differently", f);
+            }
+        }
+        // A word of only one of the languages is found
+        let until = ltl_to_gnba_powerset(&Formula::parse("U a U b c").unwrap());
+        let eventually = ltl_to_nba_alternating(&Formula::parse("F c").unwrap());
+        assert!(eventually.includes(&until).is_ok());
+        assert!(until.includes(&eventually).is_err());
+    }
+
+    #[test]
+    pub fn subformulas_as_states() {
+        // Propositions become guards, only the until is a state
+        let aba = ltl_to_aba(&Formula::parse("U a b").unwrap());
+        assert_eq!(aba.states(), 1);
+        // Contradictions accept nothing
+        let nba = ltl_to_nba_alternating(&Formula::parse("& a !a").unwrap());
+        assert!(nba.verify().is_ok());
+    }
+}
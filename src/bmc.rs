@@ -0,0 +1,423 @@
+// Bounded model checking of LTL properties of nets with a SAT solver
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use ltl::bmc::Unrolling;
+use ltl::xml::{Atom, IntegerExpr};
+use ltl::{Expr, Formula};
+use petri::{Incidence, PetriNet};
+use sat::{Lit, Var};
+
+use crate::check::{check_atoms, Counterexample, Outcome};
+use crate::transform::petri_atom;
+
+/// Search a counterexample to the formula visiting at most `depth` + 1 markings
+///
+/// The negation of the property is unrolled over the positions 0..=k of a lasso and the markings
+/// at these positions are constrained to form a run of the net. A satisfying assignment is a
+/// counterexample visiting at most k + 1 markings, while unsatisfiability only rules out shorter
+/// ones. Bounds are tried in increasing order, so the counterexample found is as short as possible.
+/// A single encoding is extended by one position per bound and solved incrementally.
+pub fn bmc<'a>(net: &'a PetriNet, formula: &Formula, depth: usize) -> petri::Result<Outcome<'a>> {
+    let _span = tracing::info_span!("bmc").entered();
+    check_atoms(net, formula)?;
+    let negation = Formula {
+        root_expr: Expr::Not(Box::new(formula.root_expr.clone())),
+    }
+    .pnf();
+    let mut encoding = Encoding::new(net, &negation);
+    let mut counterexample = None;
+    for k in 0..=depth {
+        if let Some(reason) = ts::stopped() {
+            return Err(petri::Error::Stopped(reason));
+        }
+        let _span = tracing::debug_span!("depth", k).entered();
+        if k > 0 {
+            encoding.extend();
+        }
+        counterexample = encoding.solve()?;
+        if counterexample.is_some() {
+            break;
+        }
+    }
+    Ok(Outcome {
+        counterexample,
+        states: 0,
+        bounded: true,
+        stages: vec![],
+    })
+}
+
+/// Number in order encoding, the literal at index c - 1 states that it is at least c. After i
+/// steps a place holds at most its initial tokens plus i times the most a transition adds to it,
+/// and never more than its semiflows allow, which bounds the literals needed.
+type Count = Vec<Lit>;
+
+struct Encoding<'a, 'f> {
+    net: &'a PetriNet,
+    incidence: Incidence,
+    unrolling: Unrolling<'f>,
+    truth: Lit,
+    /// Initial tokens of every place, the most a transition adds to it and its bound
+    initial: Vec<usize>,
+    grows: Vec<usize>,
+    bounds: Vec<Option<usize>>,
+    /// Tokens of every place at the positions 0..=k + 1
+    tokens: Vec<Vec<Count>>,
+    /// Whether each transition is enabled at the positions 0..=k
+    enabled: Vec<Vec<Lit>>,
+    /// Transition fired in the step from each position to the next
+    fired: Vec<Vec<Var>>,
+    /// Positions at which each atomic proposition is equivalent to its atom
+    linked: HashMap<&'f str, usize>,
+}
+
+impl<'a, 'f> Encoding<'a, 'f> {
+    /// The run of the net of a single position, the formula has to be in positive normal form
+    fn new(net: &'a PetriNet, formula: &'f Formula) -> Self {
+        let mut unrolling = Unrolling::new(formula);
+        let truth = unrolling.solver.new_var().positive();
+        unrolling.solver.add_clause([truth]);
+        let incidence = net.incidence();
+        let marking = net.initial_marking();
+        let places = incidence.places.len();
+        let initial = (incidence.places.iter())
+            .map(|place| net.tokens(&marking, place).unwrap())
+            .collect_vec();
+        let grows = (0..places)
+            .map(|p| {
+                let (pre, post) = (&incidence.pre[p], &incidence.post[p]);
+                let grows = pre.iter().zip(post).map(|(c, p)| p.saturating_sub(*c));
+                grows.max().unwrap_or(0)
+            })
+            .collect();
+        let start = initial.iter().map(|tokens| vec![truth; *tokens]).collect();
+        let mut encoding = Encoding {
+            net,
+            incidence,
+            unrolling,
+            truth,
+            initial,
+            grows,
+            bounds: net.semiflow_bounds(),
+            tokens: vec![start],
+            enabled: vec![],
+            fired: vec![],
+            linked: HashMap::new(),
+        };
+        encoding.add_position();
+        encoding
+    }
+
+    /// Add the position after the last one
+    fn extend(&mut self) {
+        self.unrolling.extend();
+        self.add_position();
+    }
+
+    /// Encode the step from the last position of the unrolling to the marking after it, which is
+    /// the marking the lasso loops back to while the position stays the last one
+    fn add_position(&mut self) {
+        let k = self.unrolling.bound();
+        let places = self.incidence.places.len();
+        let marking = (0..places)
+            .map(|p| {
+                let cap = self.initial[p] + (k + 1) * self.grows[p];
+                self.count(self.bounds[p].map_or(cap, |bound| bound.min(cap)))
+            })
+            .collect();
+        self.tokens.push(marking);
+        self.step(k);
+
+        let last = !self.unrolling.last();
+        for l in 0..=k {
+            let closes = self.unrolling.loops[l].negative();
+            for p in 0..places {
+                for c in 1..=self.tokens[k + 1][p].len() as i64 {
+                    let after = self.at_least(&self.tokens[k + 1][p], c);
+                    let looped = self.at_least(&self.tokens[l][p], c);
+                    self.unrolling
+                        .solver
+                        .add_clause([last, closes, !after, looped]);
+                    self.unrolling
+                        .solver
+                        .add_clause([last, closes, after, !looped]);
+                }
+            }
+        }
+
+        // Propositions at the new position, and at every position if the unrolling just added them
+        let aps = (self.unrolling.aps.iter())
+            .map(|(name, vars)| (*name, vars.clone()))
+            .collect_vec();
+        for (name, vars) in aps {
+            let atom = petri_atom(self.net, name).expect("The atoms are checked before unrolling");
+            let linked = self.linked.get(name).copied().unwrap_or(0);
+            for (i, var) in vars.iter().enumerate().skip(linked) {
+                let holds = self.atom(i, &atom);
+                self.equivalent(var.positive(), holds);
+            }
+            self.linked.insert(name, vars.len());
+        }
+    }
+
+    /// Variables for a number of at most `cap`
+    fn count(&mut self, cap: usize) -> Count {
+        let count = (0..cap)
+            .map(|_| self.unrolling.solver.new_var().positive())
+            .collect_vec();
+        for pair in count.windows(2) {
+            self.unrolling.solver.add_clause([!pair[1], pair[0]]);
+        }
+        count
+    }
+
+    /// Whether the number is at least c
+    fn at_least(&self, count: &Count, c: i64) -> Lit {
+        match c {
+            c if c <= 0 => self.truth,
+            c if c as usize > count.len() => !self.truth,
+            c => count[c as usize - 1],
+        }
+    }
+
+    fn equivalent(&mut self, a: Lit, b: Lit) {
+        self.unrolling.solver.add_clause([!a, b]);
+        self.unrolling.solver.add_clause([a, !b]);
+    }
+
+    /// Fire exactly one enabled transition from position i or stay in a deadlock
+    fn step(&mut self, i: usize) {
+        let transitions = self.incidence.transitions.len();
+        let places = self.incidence.places.len();
+        let enabled = (0..transitions)
+            .map(|t| {
+                let inputs = (0..places)
+                    .filter(|p| self.incidence.pre[*p][t] > 0)
+                    .map(|p| self.at_least(&self.tokens[i][p], self.incidence.pre[p][t] as i64))
+                    .collect_vec();
+                let enabled = self.unrolling.solver.new_var().positive();
+                for input in &inputs {
+                    self.unrolling.solver.add_clause([!enabled, *input]);
+                }
+                self.unrolling
+                    .solver
+                    .add_clause(inputs.iter().map(|input| !*input).chain([enabled]));
+                enabled
+            })
+            .collect_vec();
+        let fired = (0..transitions)
+            .map(|_| self.unrolling.solver.new_var())
+            .collect_vec();
+        let stutter = self.unrolling.solver.new_var().positive();
+        let choices = fired
+            .iter()
+            .map(Var::positive)
+            .chain([stutter])
+            .collect_vec();
+        self.exactly_one(&choices);
+        for (fire, enabled) in fired.iter().zip(&enabled) {
+            self.unrolling
+                .solver
+                .add_clause([fire.negative(), *enabled]);
+            self.unrolling.solver.add_clause([!stutter, !*enabled]);
+        }
+
+        for p in 0..places {
+            let changes = (0..transitions)
+                .map(|t| self.incidence.post[p][t] as i64 - self.incidence.pre[p][t] as i64)
+                .collect_vec();
+            let changing = (0..transitions).filter(|t| changes[*t] != 0).collect_vec();
+            for c in 1..=self.tokens[i + 1][p].len() as i64 {
+                let next = self.at_least(&self.tokens[i + 1][p], c);
+                let before = self.at_least(&self.tokens[i][p], c);
+                // Unchanged unless a transition changing the place fires
+                let changed = changing.iter().map(|t| fired[*t].positive()).collect_vec();
+                self.unrolling
+                    .solver
+                    .add_clause([!before, next].into_iter().chain(changed.clone()));
+                self.unrolling
+                    .solver
+                    .add_clause([before, !next].into_iter().chain(changed));
+                for t in &changing {
+                    let before = self.at_least(&self.tokens[i][p], c - changes[*t]);
+                    let fire = fired[*t].negative();
+                    self.unrolling.solver.add_clause([fire, !before, next]);
+                    self.unrolling.solver.add_clause([fire, before, !next]);
+                }
+            }
+        }
+        self.enabled.push(enabled);
+        self.fired.push(fired);
+    }
+
+    /// At least one of the literals holds and, by a sequential counter, at most one
+    fn exactly_one(&mut self, lits: &[Lit]) {
+        self.unrolling.solver.add_clause(lits.iter().copied());
+        let mut previous: Option<Lit> = None;
+        for lit in lits {
+            let seen = self.unrolling.solver.new_var().positive();
+            self.unrolling.solver.add_clause([!*lit, seen]);
+            if let Some(previous) = previous {
+                self.unrolling.solver.add_clause([!previous, seen]);
+                self.unrolling.solver.add_clause([!*lit, !previous]);
+            }
+            previous = Some(seen);
+        }
+    }
+
+    /// A literal equivalent to the atom at position i
+    fn atom(&mut self, i: usize, atom: &Atom) -> Lit {
+        match atom {
+            Atom::Fireable(t) => {
+                let t = self.incidence.transitions.iter().position(|n| n == t);
+                self.enabled[i][t.expect("The atoms refer to transitions of the net")]
+            }
+            Atom::IntegerLe(lhs, rhs) => {
+                let (lhs, rhs) = (self.integer(i, lhs), self.integer(i, rhs));
+                self.less_or_equal(&lhs, &rhs)
+            }
+        }
+    }
+
+    fn integer(&mut self, i: usize, expr: &IntegerExpr) -> Count {
+        match expr {
+            IntegerExpr::Constant(c) => vec![self.truth; *c],
+            IntegerExpr::TokensCount(places) => {
+                let counts = places
+                    .iter()
+                    .map(|place| {
+                        let p = self.incidence.places.iter().position(|n| n == place);
+                        self.tokens[i][p.expect("The atoms refer to places of the net")].clone()
+                    })
+                    .collect_vec();
+                counts
+                    .into_iter()
+                    .reduce(|x, y| self.add(&x, &y))
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    /// Totalizer of two numbers
+    fn add(&mut self, x: &Count, y: &Count) -> Count {
+        let sum = self.count(x.len() + y.len());
+        for a in 0..=x.len() as i64 {
+            for b in 0..=y.len() as i64 {
+                let (xa, yb) = (self.at_least(x, a), self.at_least(y, b));
+                let (xa1, yb1) = (self.at_least(x, a + 1), self.at_least(y, b + 1));
+                if a + b > 0 {
+                    let at_least = self.at_least(&sum, a + b);
+                    self.unrolling.solver.add_clause([!xa, !yb, at_least]);
+                }
+                let above = self.at_least(&sum, a + b + 1);
+                self.unrolling.solver.add_clause([xa1, yb1, !above]);
+            }
+        }
+        sum
+    }
+
+    fn less_or_equal(&mut self, x: &Count, y: &Count) -> Lit {
+        let holds = self.unrolling.solver.new_var().positive();
+        let mut exceeded = vec![];
+        for c in 1..=x.len() as i64 {
+            let (xc, yc) = (self.at_least(x, c), self.at_least(y, c));
+            self.unrolling.solver.add_clause([!holds, !xc, yc]);
+            // The witness that x reaches c but y does not
+            let exceeds = self.unrolling.solver.new_var().positive();
+            self.unrolling.solver.add_clause([!exceeds, xc]);
+            self.unrolling.solver.add_clause([!exceeds, !yc]);
+            exceeded.push(exceeds);
+        }
+        self.unrolling
+            .solver
+            .add_clause(exceeded.into_iter().chain([holds]));
+        holds
+    }
+
+    /// The run of the satisfying assignment, firing the transitions to obtain the markings
+    fn solve(&mut self) -> petri::Result<Option<Counterexample<'a>>> {
+        if !self.unrolling.solve() {
+            return Ok(None);
+        }
+        let loop_start = self
+            .unrolling
+            .loops
+            .iter()
+            .position(|l| self.unrolling.solver.value(*l) == Some(true))
+            .unwrap();
+        let mut marking = self.net.initial_marking();
+        let mut firings = vec![];
+        for (i, fired) in self.fired.iter().enumerate() {
+            let transition = fired
+                .iter()
+                .position(|f| self.unrolling.solver.value(*f) == Some(true))
+                .map(|t| &self.incidence.transitions[t]);
+            let Some(transition) = transition else {
+                continue;
+            };
+            let (name, next) = self
+                .net
+                .transitions(&marking)?
+                .into_iter()
+                .find(|(name, _)| name == transition)
+                .expect("The encoding only fires enabled transitions");
+            marking = next.clone();
+            firings.push((i, (name, next)));
+        }
+        let (stem, cycle) = firings.into_iter().partition(|(i, _)| *i < loop_start);
+        let steps = |firings: Vec<(usize, _)>| firings.into_iter().map(|(_, f)| f).collect();
+        Ok(Some(Counterexample {
+            stem: steps(stem),
+            cycle: steps(cycle),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::check::Checker;
+
+    #[test]
+    pub fn bounded_checks() {
+        // A token moves from p0 to p3 and back, t4 can only fire once p3 holds two tokens
+        let net = petri::from_tina(
+            "tr t0 p0 -> p1\ntr t1 p1 -> p2\ntr t2 p2 -> p3\ntr t3 p3 -> p0\n\
+             tr t4 p3*2 -> p4\npl p0 (1)",
+        )
+        .unwrap();
+        let checker = Checker::new(&net, false);
+        for f in [
+            "G tokens(p0,p1,p2,p3)<=1",
+            "G tokens(p1)<=0",
+            "G F 1<=tokens(p0)",
+            "G !t4",
+            "F G tokens(p3)<=0",
+            "X X 1<=tokens(p2)",
+            "G F t1",
+            "U tokens(p3)<=0 1<=tokens(p2)",
+        ] {
+            let formula = Formula::parse(f).unwrap();
+            let explicit = checker.check(&formula).unwrap().counterexample;
+            let bounded = bmc(&net, &formula, 6).unwrap().counterexample;
+            assert_eq!(explicit.is_some(), bounded.is_some(), "'{}'", f);
+            // Counterexamples are as short as possible
+            if let Some(counterexample) = bounded {
+                let firings = counterexample.stem.len() + counterexample.cycle.len();
+                assert!(firings <= 4, "'{}': {:?}", f, counterexample);
+            }
+        }
+
+        // p2 is only marked after two firings, which a bound of one misses
+        let deadlock = petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p2\npl p0 (1)").unwrap();
+        let formula = Formula::parse("G tokens(p2)<=0").unwrap();
+        let counterexample = bmc(&deadlock, &formula, 2).unwrap().counterexample.unwrap();
+        assert_eq!(counterexample.stem.len(), 2);
+        assert!(counterexample.cycle.is_empty());
+        let outcome = bmc(&deadlock, &formula, 1).unwrap();
+        assert!(outcome.counterexample.is_none() && outcome.bounded);
+        assert!(bmc(&deadlock, &Formula::parse("F tokens(p5)<=0").unwrap(), 1).is_err());
+    }
+}
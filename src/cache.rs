@@ -0,0 +1,202 @@
+// Artifacts of earlier runs persisted in a directory
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ltl::Formula;
+use petri::{Marking, PetriNet};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::transform::IndexedNba;
+
+/// Successors of markings, see `Checker`
+pub type Explored<'a> = HashMap<Marking, Vec<(&'a str, Marking)>>;
+
+/// Directory holding the artifacts, so a run with a tweaked property reuses the automata and
+/// statespace of the net
+pub struct Cache {
+    directory: PathBuf,
+}
+
+#[derive(Serialize)]
+struct Entry<'a, T> {
+    key: &'a str,
+    value: &'a T,
+}
+
+#[derive(Deserialize)]
+struct Stored<T> {
+    key: String,
+    value: T,
+}
+
+/// A reduced net with the reductions applied to it
+#[derive(Serialize, Deserialize)]
+struct Reduced {
+    pnml: String,
+    reductions: Vec<String>,
+}
+
+impl Cache {
+    /// Use the directory, which is created if it does not exist
+    pub fn new(directory: &Path) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        Ok(Cache {
+            directory: directory.to_path_buf(),
+        })
+    }
+
+    /// NBA of the formula, translated if it is not cached
+    pub fn automaton(&self, formula: &Formula) -> IndexedNba {
+        let key = format!("{:?}", formula.root_expr);
+        self.load("nba", &key).unwrap_or_else(|| {
+            let nba = IndexedNba::from_formula(formula);
+            self.store("nba", &key, &nba);
+            nba
+        })
+    }
+
+    /// The net after its structural reductions with their descriptions, reduced if it is not
+    /// cached
+    pub fn reduced(&self, net: &PetriNet) -> (PetriNet, Vec<String>) {
+        let key = net_key(net);
+        let cached = self.load("reduced", &key).and_then(|reduced: Reduced| {
+            let mut net = petri::from_xml(&reduced.pnml).ok()?;
+            if key.starts_with("safe") {
+                net.assert_safe().ok()?;
+            }
+            Some((net, reduced.reductions))
+        });
+        cached.unwrap_or_else(|| {
+            let mut reduced = net.clone();
+            let reductions = reduced.reduce().iter().map(|r| r.to_string()).collect();
+            let entry = Reduced {
+                pnml: reduced.to_pnml(),
+                reductions,
+            };
+            self.store("reduced", &key, &entry);
+            (reduced, entry.reductions)
+        })
+    }
+
+    /// Successors of the markings of the net explored by earlier runs
+    pub fn explored<'a>(&self, net: &'a PetriNet) -> Explored<'a> {
+        let stored: Vec<(Marking, Vec<(String, Marking)>)> =
+            self.load("explored", &net_key(net)).unwrap_or_default();
+        let transitions = net.transition_names();
+        stored
+            .into_iter()
+            .map(|(marking, successors)| {
+                let successors = successors
+                    .into_iter()
+                    .filter_map(|(t, next)| {
+                        let t = transitions.iter().find(|name| **name == t)?;
+                        Some((*t, next))
+                    })
+                    .collect();
+                (marking, successors)
+            })
+            .collect()
+    }
+
+    /// Replace the successors stored for the net, as a list since JSON only has string keys
+    pub fn store_explored(&self, net: &PetriNet, explored: &Explored) {
+        let explored = explored.iter().collect::<Vec<_>>();
+        self.store("explored", &net_key(net), &explored);
+    }
+
+    /// JSON file named after the kind of artifact and a hash of what it is computed from
+    fn path(&self, kind: &str, key: &str) -> PathBuf {
+        self.directory
+            .join(format!("{}-{:016x}.json", kind, fnv(key.as_bytes())))
+    }
+
+    /// The artifact stored for the key, a hash collision or a file that does not parse is
+    /// treated like a missing one
+    fn load<T: DeserializeOwned>(&self, kind: &str, key: &str) -> Option<T> {
+        let path = self.path(kind, key);
+        let stored: Stored<T> = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+        if stored.key != key {
+            return None;
+        }
+        tracing::info!(file = %path.display(), "reusing");
+        Some(stored.value)
+    }
+
+    /// The cache only saves work, failing to write it is logged and otherwise ignored
+    fn store<T: Serialize>(&self, kind: &str, key: &str, value: &T) {
+        let path = self.path(kind, key);
+        let written = serde_json::to_vec(&Entry { key, value })
+            .map_err(io::Error::from)
+            .and_then(|json| fs::write(&path, json));
+        if let Err(e) = written {
+            tracing::warn!(file = %path.display(), error = %e, "could not write");
+        }
+    }
+}
+
+/// Content identifying the net, markings of safe nets are checked differently
+fn net_key(net: &PetriNet) -> String {
+    match net.is_safe() {
+        true => format!("safe\n{}", net.to_pnml()),
+        false => net.to_pnml(),
+    }
+}
+
+/// FNV-1a hash, which unlike the hasher of the standard library is equal across builds
+fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::check::Checker;
+
+    #[test]
+    pub fn cached_artifacts() {
+        let directory = std::env::temp_dir().join(format!("lmc-cache-{}", std::process::id()));
+        let cache = Cache::new(&directory).unwrap();
+        let net = petri::from_tina(
+            "tr t0 p0 -> p1\ntr t1 p1 -> p0\ntr t2 p1 -> p2\ntr t3 p2 -> p2\npl p0 (1)\npl p3",
+        )
+        .unwrap();
+
+        let formula = Formula::parse("G F 1<=tokens(p0)").unwrap();
+        let checked = Checker::new(&net, false).with_cache(&cache);
+        let outcome = checked.check(&formula).unwrap();
+        checked.store_explored();
+        let explored = cache.explored(&net);
+        assert_eq!(explored.len(), 3);
+        // A tweaked property runs on the markings explored before
+        let reused = Checker::new(&net, false).with_cache(&cache);
+        let tweaked = Formula::parse("G F 1<=tokens(p1)").unwrap();
+        assert!(reused.check(&tweaked).unwrap().counterexample.is_some());
+        assert_eq!(
+            reused.check(&formula).unwrap().counterexample,
+            outcome.counterexample
+        );
+        let nba = cache.automaton(&formula);
+        assert_eq!(cache.automaton(&formula).edges, nba.edges);
+
+        let (reduced, reductions) = cache.reduced(&net);
+        assert_eq!(reductions, ["removed isolated place 'p3'"]);
+        let (cached, cached_reductions) = cache.reduced(&net);
+        assert_eq!(cached.to_pnml(), reduced.to_pnml());
+        assert_eq!(cached_reductions, reductions);
+
+        // Corrupted files are computed again
+        for file in fs::read_dir(&directory).unwrap() {
+            fs::write(file.unwrap().path(), "{").unwrap();
+        }
+        assert!(cache.explored(&net).is_empty());
+        assert_eq!(cache.reduced(&net).1, reductions);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
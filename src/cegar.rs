@@ -0,0 +1,350 @@
+// Counterexample guided abstraction refinement over the token counts of nets
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use itertools::Itertools;
+use ltl::xml::{Atom, IntegerExpr};
+use ltl::{Expr, Formula};
+use petri::{Incidence, Marking, PetriNet};
+use ts::{Path, TransitionSystem};
+
+use crate::check::{accepted_run, check_atoms, Counterexample, Outcome, Product};
+use crate::error::{Error, Result};
+use crate::transform::{petri_atom, IndexedNba};
+
+/// Refinements before giving up on a property
+const MAX_REFINEMENTS: usize = 64;
+/// Repetitions of an abstract cycle replayed on the net before its markings have to repeat
+const MAX_ROUNDS: usize = 64;
+
+/// Tokens in every place, counts at the threshold of the place stand for at least as many
+type AbstractMarking = Vec<usize>;
+
+/// Check whether every run of the net satisfies the formula on abstractions of the net, which
+/// gives up after a number of refinements
+///
+/// Every run of the net is a run of the abstraction with the same propositions, so a property
+/// holding for the abstraction holds for the net. A spurious counterexample doubles the thresholds
+/// of the places it abstracted wrongly.
+pub fn cegar<'a>(net: &'a PetriNet, formula: &Formula) -> Result<Outcome<'a>> {
+    let _span = tracing::info_span!("cegar").entered();
+    check_atoms(net, formula)?;
+    let mut abstraction = Abstraction::new(net, formula)?;
+    let mut states = 0;
+    for refinement in 0..=MAX_REFINEMENTS {
+        if let Some(reason) = ts::stopped() {
+            return Err(petri::Error::Stopped(reason).into());
+        }
+        let mut refined = false;
+        for conjunct in formula.decompose() {
+            let negation = Formula {
+                root_expr: Expr::Not(Box::new(conjunct.root_expr.clone())),
+            };
+            let product = Product::new(&abstraction, IndexedNba::from_formula(&negation));
+            let Ok(run) = accepted_run(&product, &mut states);
+            let Some((_, stem, cycle)) = run else {
+                continue;
+            };
+            match abstraction.concretize(&stem, &cycle)? {
+                Ok(counterexample) => {
+                    return Ok(Outcome {
+                        counterexample: Some(counterexample),
+                        states,
+                        bounded: false,
+                        stages: vec![],
+                    })
+                }
+                Err(places) => {
+                    tracing::debug!(
+                        refinement = refinement + 1,
+                        places = %places
+                            .iter()
+                            .map(|p| &abstraction.incidence.places[*p])
+                            .join(", "),
+                        "spurious counterexample, refining"
+                    );
+                    for p in places {
+                        abstraction.thresholds[p] *= 2;
+                    }
+                    refined = true;
+                    break;
+                }
+            }
+        }
+        if !refined {
+            return Ok(Outcome {
+                counterexample: None,
+                states,
+                bounded: false,
+                stages: vec![],
+            });
+        }
+    }
+    Ok(Outcome {
+        counterexample: None,
+        states,
+        bounded: true,
+        stages: vec![],
+    })
+}
+
+/// Net whose markings are abstracted by the thresholds of the places. Every place counts its
+/// tokens exactly below its threshold, the threshold itself stands for that many tokens or more.
+/// Only removing tokens from a place at its threshold is nondeterministic, the place may then hold
+/// any count between the remainder and the threshold.
+struct Abstraction<'a> {
+    net: &'a PetriNet,
+    transitions: Vec<&'a str>,
+    incidence: Incidence,
+    thresholds: Vec<usize>,
+    atoms: HashMap<String, Atom>,
+}
+
+impl<'a> Abstraction<'a> {
+    /// Thresholds deciding the arcs and the atomic propositions of the formula, which may only
+    /// compare tokens with constants
+    fn new(net: &'a PetriNet, formula: &Formula) -> Result<Self> {
+        let incidence = net.incidence();
+        let mut thresholds = incidence
+            .pre
+            .iter()
+            .map(|weights| weights.iter().max().unwrap_or(&0) + 1)
+            .collect_vec();
+        let atoms = formula
+            .root_expr
+            .alphabet()
+            .into_iter()
+            .map(|ap| Ok((ap.to_string(), petri_atom(net, &ap.to_string())?)))
+            .collect::<petri::Result<HashMap<_, _>>>()?;
+        for atom in atoms.values() {
+            let Atom::IntegerLe(lhs, rhs) = atom else {
+                continue;
+            };
+            let (places, constant) = match (lhs, rhs) {
+                (IntegerExpr::TokensCount(places), IntegerExpr::Constant(c))
+                | (IntegerExpr::Constant(c), IntegerExpr::TokensCount(places)) => (places, *c),
+                (IntegerExpr::Constant(_), IntegerExpr::Constant(_)) => continue,
+                _ => {
+                    return Err(Error::Unsupported(format!(
+                    "Abstraction refinement needs propositions comparing tokens with constants, \
+                     not '{}'",
+                    atom
+                )))
+                }
+            };
+            for place in places {
+                let p = incidence.places.iter().position(|n| n == place).unwrap();
+                thresholds[p] = thresholds[p].max(constant + 1);
+            }
+        }
+        Ok(Abstraction {
+            net,
+            transitions: net.transition_names(),
+            incidence,
+            thresholds,
+            atoms,
+        })
+    }
+
+    fn abstraction(&self, marking: &Marking) -> AbstractMarking {
+        self.incidence
+            .places
+            .iter()
+            .zip(&self.thresholds)
+            .map(|(place, threshold)| self.net.tokens(marking, place).unwrap().min(*threshold))
+            .collect()
+    }
+
+    /// Places whose tokens the abstract marking does not count exactly
+    fn inexact(&self, marking: &AbstractMarking) -> impl Iterator<Item = usize> + '_ {
+        let places = marking.clone();
+        (0..places.len()).filter(move |p| places[*p] == self.thresholds[*p])
+    }
+
+    /// Fire the transitions of an abstract lasso in the net, giving the run or the places to
+    /// refine if it is spurious
+    ///
+    /// The cycle is fired repeatedly until the markings repeat. The run is spurious if a marking
+    /// is abstracted differently than the abstract run assumed, then the places which differ are
+    /// refined. Cycles whose markings keep growing refine the places at their threshold on the
+    /// cycle.
+    fn concretize(
+        &self,
+        stem: &Path<Self>,
+        cycle: &Path<Self>,
+    ) -> petri::Result<std::result::Result<Counterexample<'a>, Vec<usize>>> {
+        let mut marking = self.net.initial_marking();
+        let mut firings = vec![];
+        for (transition, expected) in stem {
+            let differing = self.fire(transition, expected, &mut marking, &mut firings)?;
+            if !differing.is_empty() {
+                return Ok(Err(differing));
+            }
+        }
+        if cycle.is_empty() {
+            return Ok(Ok(Counterexample {
+                stem: firings,
+                cycle: vec![],
+            }));
+        }
+        // Markings at the start of each repetition with the number of firings before them
+        let mut starts = HashMap::new();
+        let mut fired = firings.len();
+        for _ in 0..MAX_ROUNDS {
+            if let Some(start) = starts.insert(marking.clone(), fired) {
+                let cycle = firings.split_off(start);
+                return Ok(Ok(Counterexample {
+                    stem: firings,
+                    cycle,
+                }));
+            }
+            for (transition, expected) in cycle {
+                let differing = self.fire(transition, expected, &mut marking, &mut firings)?;
+                if !differing.is_empty() {
+                    return Ok(Err(differing));
+                }
+            }
+            fired += cycle.len();
+        }
+        let growing = cycle
+            .iter()
+            .flat_map(|(_, state)| self.inexact(state))
+            .unique()
+            .collect();
+        Ok(Err(growing))
+    }
+}
+
+impl<'a> Abstraction<'a> {
+    /// Fire the transition in the marking, giving the places abstracted differently than
+    /// expected afterwards
+    fn fire(
+        &self,
+        transition: &str,
+        expected: &AbstractMarking,
+        marking: &mut Marking,
+        firings: &mut Vec<(&'a str, Marking)>,
+    ) -> petri::Result<Vec<usize>> {
+        let (name, next) = self
+            .net
+            .transitions(marking)?
+            .into_iter()
+            .find(|(name, _)| *name == transition)
+            .expect("The abstraction decides which transitions are enabled");
+        let reached = self.abstraction(&next);
+        *marking = next.clone();
+        firings.push((name, next));
+        Ok((0..reached.len())
+            .filter(|p| reached[*p] != expected[*p])
+            .collect())
+    }
+}
+
+impl<'a> TransitionSystem for Abstraction<'a> {
+    type State = AbstractMarking;
+    type Label = &'a str;
+    type Error = Infallible;
+
+    fn initial_states(&self) -> Vec<AbstractMarking> {
+        vec![self.abstraction(&self.net.initial_marking())]
+    }
+
+    fn successors(&self, marking: &AbstractMarking) -> std::result::Result<Path<Self>, Infallible> {
+        let mut successors = vec![];
+        for (t, transition) in self.transitions.iter().enumerate() {
+            let places = 0..marking.len();
+            if places
+                .clone()
+                .any(|p| marking[p] < self.incidence.pre[p][t])
+            {
+                continue;
+            }
+            // The counts each place may hold after firing
+            let counts = places.map(|p| {
+                let (pre, post) = (self.incidence.pre[p][t], self.incidence.post[p][t]);
+                let threshold = self.thresholds[p];
+                let least = (marking[p] + post - pre).min(threshold);
+                match marking[p] == threshold {
+                    true => least..=threshold,
+                    false => least..=least,
+                }
+            });
+            for next in counts.multi_cartesian_product() {
+                successors.push((*transition, next));
+            }
+        }
+        Ok(successors)
+    }
+
+    fn holds(&self, marking: &AbstractMarking, proposition: &str) -> bool {
+        let tokens = |place: &str| {
+            let p = self.incidence.places.iter().position(|n| n == place);
+            p.map_or(0, |p| marking[p])
+        };
+        match &self.atoms[proposition] {
+            Atom::Fireable(t) => {
+                let t = self.transitions.iter().position(|n| n == t);
+                let t = t.expect("The atoms refer to transitions of the net");
+                (0..marking.len()).all(|p| marking[p] >= self.incidence.pre[p][t])
+            }
+            // The thresholds exceed the constants, so the smallest counts decide the comparison
+            Atom::IntegerLe(lhs, rhs) => lhs.evaluate(tokens) <= rhs.evaluate(tokens),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::check::Checker;
+
+    #[test]
+    pub fn abstraction_refinement() {
+        // Many processes compete for a single lock
+        let net = petri::from_tina(
+            "tr enter idle lock -> busy\ntr leave busy -> idle lock\npl idle (300)\npl lock (1)",
+        )
+        .unwrap();
+        let parse = |f: &str| Formula::parse(f).unwrap();
+        let outcome = cegar(&net, &parse("G tokens(busy)<=1")).unwrap();
+        assert!(outcome.counterexample.is_none() && !outcome.bounded);
+        // Far fewer states than the 301 * 2 markings of the net are explored
+        assert!(outcome.states < 100, "{}", outcome.states);
+
+        // Taking two tokens at a time leaves one behind, which the first abstraction misses
+        let net = petri::from_tina("tr t0 p0*2 -> p1\npl p0 (3)").unwrap();
+        let counterexample = cegar(&net, &parse("G tokens(p1)<=0"))
+            .unwrap()
+            .counterexample
+            .unwrap();
+        assert_eq!(counterexample.stem.len(), 1);
+        assert!(counterexample.cycle.is_empty());
+
+        let net = petri::from_tina(
+            "tr t0 p0 -> p1 p1\ntr t1 p1 p1 -> p2\ntr t2 p2 -> p0\ntr t3 p1 p2 -> p3\npl p0 (2)",
+        )
+        .unwrap();
+        let checker = Checker::new(&net, false);
+        for f in [
+            "G tokens(p3)<=0",
+            "G F 1<=tokens(p0)",
+            "F G tokens(p1,p2)<=2",
+            "G | tokens(p1)<=1 F t1",
+            "G !t3",
+            "X X 3<=tokens(p1,p2)",
+        ] {
+            let formula = parse(f);
+            let explicit = checker.check(&formula).unwrap().counterexample;
+            let outcome = cegar(&net, &formula).unwrap();
+            assert!(!outcome.bounded, "'{}'", f);
+            assert_eq!(
+                explicit.is_some(),
+                outcome.counterexample.is_some(),
+                "'{}'",
+                f
+            );
+        }
+        assert!(cegar(&net, &parse("G tokens(p0)<=tokens(p1)")).is_err());
+    }
+}
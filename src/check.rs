@@ -0,0 +1,724 @@
+// LTL model checking of Petri nets
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
+
+use itertools::Itertools;
+use ltl::alphabet::Valuation;
+use ltl::xml::{Atom, IntegerExpr};
+use ltl::{Expr, Formula};
+use petri::{Marking, PetriNet, StubbornSets};
+use ts::{Path, TransitionSystem};
+
+use crate::cache::{Cache, Explored};
+use crate::fairness::{FairLasso, Fairness};
+use crate::report::{peak_memory, AutomatonStage, ProductStage, Stages};
+use crate::transform::{petri_atom, petri_atom_holds, IndexedNba};
+
+/// Run of a net violating a formula, the transitions fired with the markings they lead to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample<'a> {
+    /// Firings from the initial marking to the cycle
+    pub stem: Vec<(&'a str, Marking)>,
+    /// Firings repeated forever after the stem, empty if the stem ends in a deadlock
+    pub cycle: Vec<(&'a str, Marking)>,
+}
+
+/// Outcome of checking a property of a net
+pub struct Outcome<'a> {
+    pub counterexample: Option<Counterexample<'a>>,
+    /// States of the products visited by the searches
+    pub states: usize,
+    /// Whether only runs up to a bound were searched, so the property may be violated although
+    /// there is no counterexample
+    pub bounded: bool,
+    /// Statistics of the conjuncts checked, empty for engines without automata
+    pub stages: Vec<Stages>,
+}
+
+/// Successor markings of a net, computed once for all properties checked
+type Successors<'a> = Rc<RefCell<Explored<'a>>>;
+
+/// Checks properties of a net, sharing the explored markings and the automata of repeated
+/// formulas between them
+pub struct Checker<'a> {
+    net: &'a PetriNet,
+    por: bool,
+    fairness: Fairness,
+    successors: Successors<'a>,
+    automata: RefCell<HashMap<Expr, IndexedNba>>,
+    cache: Option<&'a Cache>,
+    /// Whether the components of the products are counted for reports
+    components: bool,
+}
+
+impl<'a> Checker<'a> {
+    /// With `por` the markings are explored by stubborn sets, unless the formula contains next
+    /// operators. Stubborn sets depend on the formula, so their markings are not shared.
+    pub fn new(net: &'a PetriNet, por: bool) -> Self {
+        Checker {
+            net,
+            por,
+            fairness: Fairness::new(),
+            successors: Rc::default(),
+            automata: RefCell::default(),
+            cache: None,
+            components: false,
+        }
+    }
+
+    /// Count the strongly connected components of the products in the stages of the outcomes,
+    /// which explores every product entirely
+    pub fn with_components(mut self) -> Self {
+        self.components = true;
+        self
+    }
+
+    /// Start from the markings explored by earlier runs and keep the automata in the cache,
+    /// `store_explored` adds the markings explored since
+    pub fn with_cache(mut self, cache: &'a Cache) -> Self {
+        self.successors = Rc::new(RefCell::new(cache.explored(self.net)));
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Write the markings explored so far to the cache, if any
+    pub fn store_explored(&self) {
+        if let Some(cache) = self.cache {
+            cache.store_explored(self.net, &self.successors.borrow());
+        }
+    }
+
+    /// Markings explored so far with their successors
+    pub fn explored(&self) -> Explored<'a> {
+        self.successors.borrow().clone()
+    }
+
+    /// Add the markings explored by another checker of the net, such as one of another thread
+    pub fn add_explored(&self, explored: Explored<'a>) {
+        self.successors.borrow_mut().extend(explored);
+    }
+
+    /// Only consider runs which are fair for the constraints. Stubborn sets ignore fairness, so
+    /// the markings are no longer reduced.
+    pub fn with_fairness(mut self, fairness: Fairness) -> Self {
+        self.fairness = fairness;
+        self
+    }
+
+    /// Check whether every fair run of the net satisfies the formula, otherwise give a
+    /// counterexample
+    ///
+    /// The product of the reachability graph with the NBA of the negation is explored on the fly
+    /// by a nested depth first search, an accepting lasso projects to a counterexample. Deadlocks
+    /// are extended by stuttering. Under fairness assumptions the product is explored entirely and
+    /// searched for a fair accepting cycle instead, see `fairness`.
+    pub fn check(&self, formula: &Formula) -> petri::Result<Outcome<'a>> {
+        check_atoms(self.net, formula)?;
+        self.fairness.validate(self.net)?;
+        let mut states = 0;
+        let mut stages = vec![];
+        // Conjuncts over disjoint propositions are checked separately with smaller automata
+        for conjunct in formula.decompose() {
+            let negation = Formula {
+                root_expr: Expr::Not(Box::new(conjunct.root_expr.clone())),
+            };
+            let _span = tracing::debug_span!("conjunct", negation = %negation).entered();
+            let start = Instant::now();
+            let (nba, translated) = self.automaton(&negation);
+            let automaton = AutomatonStage::new(&nba, translated, start.elapsed());
+            tracing::debug!(states = nba.edges.len(), "automaton");
+            let reduce = self.por
+                && self.fairness.is_empty()
+                && conjunct.metrics().fragments.stutter_invariant;
+            let mut system = PetriSystem::new(self.net, nba.aps.iter(), reduce)?;
+            if !reduce {
+                system.successors = Some(self.successors.clone());
+            }
+            let mut product = Product::new(system, nba);
+            if !self.fairness.is_empty() {
+                product = product.exhaustive();
+            }
+            let components = match self.components {
+                true => Some(ts::components(&product)?),
+                false => None,
+            };
+
+            let start = Instant::now();
+            let visited = states;
+            let counterexample = match self.fairness.is_empty() {
+                true => accepted_run(&product, &mut states)?
+                    .map(|(_, stem, cycle)| Counterexample { stem, cycle }),
+                false => self.fair_run(product, &mut states)?,
+            };
+            stages.push(Stages {
+                formula: negation.to_string(),
+                automaton,
+                product: ProductStage {
+                    states: states - visited,
+                    components: components.as_ref().map(Vec::len),
+                    cyclic_components: components
+                        .as_ref()
+                        .map(|components| components.iter().filter(|c| c.cyclic).count()),
+                    time: start.elapsed().as_secs_f64(),
+                    peak_memory: peak_memory(),
+                },
+            });
+            if counterexample.is_some() {
+                return Ok(Outcome {
+                    counterexample,
+                    states,
+                    bounded: false,
+                    stages,
+                });
+            }
+        }
+        Ok(Outcome {
+            counterexample: None,
+            states,
+            bounded: false,
+            stages,
+        })
+    }
+
+    /// Fair run accepted by the product, the product states are added to `visited`
+    fn fair_run(
+        &self,
+        product: Product<PetriSystem<'a>>,
+        visited: &mut usize,
+    ) -> petri::Result<Option<Counterexample<'a>>> {
+        let error = RefCell::new(None);
+        let successors = |s: &ProductState<PetriSystem>| {
+            product.successors(s).unwrap_or_else(|e| {
+                error.borrow_mut().get_or_insert(e);
+                vec![]
+            })
+        };
+        let enabled =
+            |(marking, _): &ProductState<PetriSystem>| marking.active_transitions(self.net);
+        let accepting = |s: &ProductState<PetriSystem>| product.accepting(s);
+        let graph = self
+            .fairness
+            .explore(product.initial_states(), successors, enabled, accepting);
+        *visited += graph.len();
+        if let Some(e) = error.into_inner() {
+            return Err(e);
+        }
+        // Only deadlocks stutter, so a stuttering cycle leaves the stem ending in the deadlock
+        let firings = |path: Vec<(Option<&'a str>, ProductState<PetriSystem>)>| {
+            path.into_iter()
+                .filter_map(|(fired, (marking, _))| Some((fired?, marking)))
+                .collect_vec()
+        };
+        Ok(self
+            .fairness
+            .fair_lasso(&graph)
+            .map(|(stem, cycle): FairLasso<_>| Counterexample {
+                stem: firings(stem),
+                cycle: firings(cycle),
+            }))
+    }
+
+    /// Automaton of the formula, with the states of the GNBA and NBA if it had to be translated
+    fn automaton(&self, formula: &Formula) -> (IndexedNba, Option<(usize, usize)>) {
+        if let Some(nba) = self.automata.borrow().get(&formula.root_expr) {
+            return (nba.clone(), None);
+        }
+        let (nba, translated) = match self.cache {
+            Some(cache) => (cache.automaton(formula), None),
+            None => {
+                let (nba, gnba_states, nba_states) = IndexedNba::translate(formula);
+                (nba, Some((gnba_states, nba_states)))
+            }
+        };
+        self.automata
+            .borrow_mut()
+            .insert(formula.root_expr.clone(), nba.clone());
+        (nba, translated)
+    }
+}
+
+/// Run of a system, the states from an initial one to a cycle and the states repeated forever
+/// after it
+pub type Run<S> = (Vec<S>, Vec<S>);
+
+/// Check whether every run of the system satisfies the formula, otherwise give a run violating
+/// it. Deadlocks are extended by repeating their state.
+pub fn check_system<T: TransitionSystem>(
+    system: &T,
+    formula: &Formula,
+) -> Result<Option<Run<T::State>>, T::Error> {
+    for conjunct in formula.decompose() {
+        let negation = Formula {
+            root_expr: Expr::Not(Box::new(conjunct.root_expr.clone())),
+        };
+        let product = Product::new(system, IndexedNba::from_formula(&negation));
+        let Some((initial, stem, cycle)) = accepted_run(&product, &mut 0)? else {
+            continue;
+        };
+        let stem = [initial]
+            .into_iter()
+            .chain(stem.into_iter().map(|(_, s)| s))
+            .collect_vec();
+        let cycle = match cycle.is_empty() {
+            true => vec![stem.last().unwrap().clone()],
+            false => cycle.into_iter().map(|(_, s)| s).collect(),
+        };
+        return Ok(Some((stem, cycle)));
+    }
+    Ok(None)
+}
+
+/// Run of a system from an initial state, the edges to a cycle and the edges of the cycle
+pub type Accepted<T> = (<T as TransitionSystem>::State, Path<T>, Path<T>);
+
+/// Run of the system accepted by the product, the cycle is empty if the run ends in a deadlock.
+/// The product states visited are added to `visited`.
+pub fn accepted_run<T: TransitionSystem>(
+    product: &Product<T>,
+    visited: &mut usize,
+) -> Result<Option<Accepted<T>>, T::Error> {
+    let search = ts::search(product, |s| product.accepting(s))?;
+    *visited += search.states;
+    let Some((stem, cycle)) = search.lasso else {
+        return Ok(None);
+    };
+    // The lasso only gives the states entered, the initial state it starts in is the one
+    // entering the stem or, without a stem, the state closing the cycle
+    let initial = match stem.first() {
+        Some((_, entered)) => {
+            let mut initial = None;
+            for state in product.initial_states() {
+                if product
+                    .successors(&state)?
+                    .iter()
+                    .any(|(_, s)| s == entered)
+                {
+                    initial = Some(state);
+                    break;
+                }
+            }
+            initial.expect("The stem starts in an initial state")
+        }
+        None => cycle.last().expect("Cycles are not empty").1.clone(),
+    };
+    let (last, _) = cycle.last().expect("Cycles are not empty").1.clone();
+    // Stuttering happens in deadlocks and in the states the product does not explore further
+    let edges = |path: Path<Product<T>>| {
+        path.into_iter()
+            .filter_map(|(label, (s, _))| Some((label?, s)))
+            .collect_vec()
+    };
+    let mut stem = edges(stem);
+    let mut cycle = edges(cycle);
+    if cycle.is_empty() {
+        let (rest, rest_cycle) = continue_run(&product.system, last)?;
+        stem.extend(rest);
+        cycle = rest_cycle;
+    }
+    Ok(Some((initial.0, stem, cycle)))
+}
+
+/// Follow the first successors from the state until a state repeats, giving the edges up to
+/// the repeated state and the edges of the cycle, which is empty if a deadlock is reached. Once
+/// the automaton accepts every word any such run is a counterexample, so the search stops there.
+fn continue_run<T: TransitionSystem>(
+    system: &T,
+    state: T::State,
+) -> Result<(Path<T>, Path<T>), T::Error> {
+    // Number of edges leading to each state of the run
+    let mut positions = HashMap::from([(state.clone(), 0)]);
+    let mut path: Path<T> = vec![];
+    let mut current = state;
+    loop {
+        let Some((label, next)) = system.successors(&current)?.into_iter().next() else {
+            return Ok((path, vec![]));
+        };
+        path.push((label, next.clone()));
+        if let Some(&position) = positions.get(&next) {
+            let cycle = path.split_off(position);
+            return Ok((path, cycle));
+        }
+        positions.insert(next.clone(), path.len());
+        current = next;
+    }
+}
+
+/// Fail on atomic propositions which are neither transitions nor places or compare unknown
+/// places, which the search would only notice when it evaluates them
+pub fn check_atoms(net: &PetriNet, formula: &Formula) -> petri::Result<()> {
+    for ap in formula.root_expr.alphabet() {
+        petri_atom(net, &ap.to_string())?;
+    }
+    Ok(())
+}
+
+/// Product of the reachability graph of a net and the NBA of a formula
+pub fn petri_product<'a>(
+    net: &'a PetriNet,
+    formula: &Formula,
+    reduce: bool,
+) -> petri::Result<Product<PetriSystem<'a>>> {
+    let nba = IndexedNba::from_formula(formula);
+    let system = PetriSystem::new(net, nba.aps.iter(), reduce)?;
+    Ok(Product::new(system, nba))
+}
+
+/// Reachability graph of a net whose atomic propositions are described by `ltl::xml::Atom`
+pub struct PetriSystem<'a> {
+    net: &'a PetriNet,
+    atoms: HashMap<String, Atom>,
+    reduction: Option<StubbornSets<'a>>,
+    /// Successors shared with other products of the net
+    successors: Option<Successors<'a>>,
+}
+
+impl<'a> PetriSystem<'a> {
+    /// With `reduce` only stubborn sets of transitions keeping the atoms visible are fired.
+    /// Unknown atoms are rejected, see `check_atoms`, and only the given ones are evaluated.
+    pub fn new<'b>(
+        net: &'a PetriNet,
+        aps: impl IntoIterator<Item = &'b str>,
+        reduce: bool,
+    ) -> petri::Result<Self> {
+        let aps = aps.into_iter().collect_vec();
+        let atoms = aps
+            .iter()
+            .map(|ap| petri_atom(net, ap))
+            .collect::<petri::Result<Vec<_>>>()?;
+        let reduction = if reduce {
+            Some(observing(net, &atoms)?)
+        } else {
+            None
+        };
+        Ok(PetriSystem {
+            net,
+            atoms: aps.into_iter().map(String::from).zip(atoms).collect(),
+            reduction,
+            successors: None,
+        })
+    }
+}
+
+impl<'a> TransitionSystem for PetriSystem<'a> {
+    type State = Marking;
+    type Label = &'a str;
+    type Error = petri::Error;
+
+    fn initial_states(&self) -> Vec<Marking> {
+        vec![self.net.initial_marking()]
+    }
+
+    fn successors(&self, marking: &Marking) -> petri::Result<Vec<(&'a str, Marking)>> {
+        if let Some(reduction) = &self.reduction {
+            return reduction.transitions(marking);
+        }
+        let Some(shared) = &self.successors else {
+            return self.net.transitions(marking);
+        };
+        if let Some(successors) = shared.borrow().get(marking) {
+            return Ok(successors.clone());
+        }
+        let successors = self.net.transitions(marking)?;
+        shared
+            .borrow_mut()
+            .insert(marking.clone(), successors.clone());
+        Ok(successors)
+    }
+
+    fn holds(&self, marking: &Marking, proposition: &str) -> bool {
+        match &self.atoms[proposition] {
+            Atom::Fireable(t) => self.net.holds(marking, t),
+            atom => petri_atom_holds(self.net, marking, &[], atom).unwrap_or(false),
+        }
+    }
+}
+
+/// State of the product, a state of the system and a state of the NBA
+pub type ProductState<T> = (<T as TransitionSystem>::State, usize);
+
+/// Product of a transition system and the NBA of a formula over its atomic propositions.
+/// Deadlocks of the system are extended by stuttering, labeled with `None`. Below automaton
+/// states accepting every word the system is not explored, those product states only stutter.
+pub struct Product<T> {
+    system: T,
+    nba: IndexedNba,
+    prune: bool,
+}
+
+impl<T: TransitionSystem> Product<T> {
+    pub fn new(system: T, nba: IndexedNba) -> Self {
+        Product {
+            system,
+            nba,
+            prune: true,
+        }
+    }
+
+    /// Explore the system below automaton states accepting every word as well, which is needed
+    /// when the cycles have to be runs of the system
+    pub fn exhaustive(self) -> Self {
+        Product {
+            prune: false,
+            ..self
+        }
+    }
+
+    pub fn accepting(&self, (_, q): &ProductState<T>) -> bool {
+        self.nba.accepting[*q]
+    }
+}
+
+impl<T: TransitionSystem> TransitionSystem for Product<T> {
+    type State = ProductState<T>;
+    type Label = Option<T::Label>;
+    type Error = T::Error;
+
+    fn initial_states(&self) -> Vec<ProductState<T>> {
+        self.system
+            .initial_states()
+            .into_iter()
+            .cartesian_product(self.nba.initial_states.iter().copied())
+            .collect()
+    }
+
+    fn successors(
+        &self,
+        (state, q): &ProductState<T>,
+    ) -> Result<Vec<(Option<T::Label>, ProductState<T>)>, T::Error> {
+        if self.prune && self.nba.universal(*q) {
+            return Ok(vec![(None, (state.clone(), *q))]);
+        }
+        let valuation = (self.nba.aps.iter().enumerate()).fold(Valuation::empty(), |v, (i, ap)| {
+            v.with(i, self.system.holds(state, ap))
+        });
+        let mut next = (self.system.successors(state)?.into_iter())
+            .map(|(label, s)| (Some(label), s))
+            .collect_vec();
+        if next.is_empty() {
+            next.push((None, state.clone()));
+        }
+
+        let mut successors = vec![];
+        for (literals, target) in &self.nba.edges[*q] {
+            if literals.holds(valuation) {
+                successors.extend(
+                    next.iter()
+                        .map(|(label, s)| (label.clone(), (s.clone(), *target))),
+                );
+            }
+        }
+        Ok(successors)
+    }
+
+    fn holds(&self, (state, _): &ProductState<T>, proposition: &str) -> bool {
+        self.system.holds(state, proposition)
+    }
+}
+
+/// Stubborn sets which keep the places and transitions the atoms refer to visible. The atoms are
+/// resolved by `petri_atom`, so bare place names are observed like token counts.
+fn observing<'a>(net: &'a PetriNet, atoms: &[Atom]) -> petri::Result<StubbornSets<'a>> {
+    let mut places = vec![];
+    let mut fireable = vec![];
+    for atom in atoms {
+        match atom {
+            Atom::Fireable(t) => fireable.push(t.as_str()),
+            Atom::IntegerLe(lhs, rhs) => {
+                for expr in [lhs, rhs] {
+                    if let IntegerExpr::TokensCount(counted) = expr {
+                        places.extend(counted.iter().map(String::as_str));
+                    }
+                }
+            }
+        }
+    }
+    StubbornSets::ltl(net, &places, &fireable)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn kripke_products() {
+        // A request may be served or kept pending forever
+        let mut kripke = ts::Kripke::new();
+        let idle = kripke.add_state("idle", []);
+        let requested = kripke.add_state("requested", ["req"]);
+        let granted = kripke.add_state("granted", ["grant"]);
+        kripke.add_initial(idle);
+        kripke.add_edge(idle, requested);
+        kripke.add_edge(requested, requested);
+        kripke.add_edge(requested, granted);
+        kripke.add_edge(granted, idle);
+        let violated = |f: &str| {
+            let Ok(run) = check_system(&kripke, &Formula::parse(f).unwrap());
+            run
+        };
+        assert_eq!(violated("G F | req grant"), None);
+        // Staying in requested forever
+        let (stem, cycle) = violated("G F grant").unwrap();
+        assert_eq!(
+            (stem[0], stem.last(), cycle),
+            (idle, Some(&requested), vec![requested])
+        );
+        // Every run requests at some point
+        let (stem, cycle) = violated("G !req").unwrap();
+        assert_eq!(stem[0], idle);
+        assert!(stem.contains(&requested) || cycle.contains(&requested));
+    }
+
+    #[test]
+    pub fn petri_checks() {
+        // The token in p0 either cycles through t0 or is consumed by t1
+        let net = petri::from_tina("tr t0 p0 -> p0\ntr t1 p0 -> p1\npl p0 (1)").unwrap();
+        for por in [false, true] {
+            let checker = Checker::new(&net, por);
+            let check = |f: &str| {
+                let outcome = checker.check(&Formula::parse(f).unwrap()).unwrap();
+                let counterexample = outcome.counterexample?;
+                let firings = |firings: Vec<(&str, Marking)>| {
+                    firings
+                        .into_iter()
+                        .map(|(t, m)| format!("{} {}", t, net.marking_label(&m)))
+                        .collect_vec()
+                };
+                Some((firings(counterexample.stem), firings(counterexample.cycle)))
+            };
+            assert_eq!(check("G tokens(p0,p1)<=1"), None);
+            assert_eq!(
+                check("F 1<=tokens(p1)"),
+                Some((vec![], vec!["t0 p0".to_string()]))
+            );
+            // After firing t1 the net deadlocks
+            assert_eq!(check("G F t0"), Some((vec!["t1 p1".to_string()], vec![])));
+            assert!(checker
+                .check(&Formula::parse("F 1<=tokens(p2)").unwrap())
+                .is_err());
+            // Both markings were explored once for all properties
+            assert_eq!(checker.successors.borrow().len(), if por { 0 } else { 2 });
+            check("G tokens(p0,p1)<=1");
+            assert_eq!(checker.automata.borrow().len(), 3);
+        }
+
+        // Once p1 is marked every continuation violates the property, the search stops there and
+        // the counterexample is completed by a cycle of the net
+        let net = petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p0\npl p0 (1)").unwrap();
+        let outcome = Checker::new(&net, false)
+            .check(&Formula::parse("G tokens(p1)<=0").unwrap())
+            .unwrap();
+        assert!(outcome.states > 0);
+        let counterexample = outcome.counterexample.unwrap();
+        let transitions = |firings: &[(&str, Marking)]| firings.iter().map(|(t, _)| *t).join(" ");
+        assert_eq!(transitions(&counterexample.stem), "t0 t1");
+        assert_eq!(transitions(&counterexample.cycle), "t0 t1");
+    }
+
+    #[test]
+    pub fn atoms_across_engines() {
+        // Both tokens move on once, so p1 is marked eventually but not from the start
+        let net = petri::from_tina("tr t0 p0 -> p1\ntr t1 p2 -> p3\npl p0 (1)\npl p2 (1)").unwrap();
+        let checker = Checker::new(&net, false);
+        let violated = |f: &str| {
+            let f = Formula::parse(f).unwrap();
+            [
+                checker.check(&f).ok().map(|o| o.counterexample.is_some()),
+                crate::bmc::bmc(&net, &f, 4)
+                    .ok()
+                    .map(|o| o.counterexample.is_some()),
+                crate::cegar::cegar(&net, &f)
+                    .ok()
+                    .map(|o| o.counterexample.is_some()),
+            ]
+        };
+        assert_eq!(violated("F p1"), [Some(false); 3]);
+        assert_eq!(violated("G !p1"), [Some(true); 3]);
+        assert_eq!(violated("F t0"), [Some(false); 3]);
+        // Names which are neither places nor transitions are rejected instead of never holding
+        assert_eq!(violated("F p4"), [None; 3]);
+        let unknown = [("0".to_string(), Formula::parse("F p4").unwrap())];
+        assert!(crate::smv::to_smv(&net, &unknown).is_err());
+    }
+
+    #[test]
+    pub fn reduced_place_propositions() {
+        // Firing t1 first marks p3 while p1 is empty, so t1 changes a proposition and may not be
+        // delayed behind t0
+        let net = petri::from_tina("tr t0 p0 -> p1\ntr t1 p2 -> p3\npl p0 (1)\npl p2 (1)").unwrap();
+        for f in ["G !& p3 !p1", "G | !p3 p1", "F p1", "G | p0 p1"] {
+            let f = Formula::parse(f).unwrap();
+            let violated = |por| {
+                let outcome = Checker::new(&net, por).check(&f).unwrap();
+                outcome.counterexample.is_some()
+            };
+            assert_eq!(violated(true), violated(false), "{}", f);
+        }
+        assert!(Checker::new(&net, true)
+            .check(&Formula::parse("G !& p3 !p1").unwrap())
+            .unwrap()
+            .counterexample
+            .is_some());
+    }
+
+    #[test]
+    pub fn fair_checks() {
+        // The token in p0 may loop through t2 forever instead of moving on to p1
+        let net =
+            petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p0\ntr t2 p0 -> p0\npl p0 (1)").unwrap();
+        let transitions = |firings: &[(&str, Marking)]| firings.iter().map(|(t, _)| *t).join(" ");
+        let check = |fairness: &Fairness, f: &str| {
+            Checker::new(&net, true)
+                .with_fairness(fairness.clone())
+                .check(&Formula::parse(f).unwrap())
+                .map(|outcome| outcome.counterexample)
+        };
+        let unfair = check(&Fairness::new(), "G F 1<=tokens(p1)").unwrap();
+        assert_eq!(transitions(&unfair.unwrap().cycle), "t2");
+
+        // t0 stays enabled while looping, so a weakly fair run fires it
+        let mut weak = Fairness::new();
+        weak.add_weak(["t0"]);
+        assert_eq!(check(&weak, "G F 1<=tokens(p1)").unwrap(), None);
+        let counterexample = check(&weak, "G tokens(p1)<=0").unwrap().unwrap();
+        assert!(transitions(&counterexample.cycle).contains("t0"));
+
+        let mut unknown = Fairness::new();
+        unknown.add_strong(["t3"]);
+        assert!(check(&unknown, "G F 1<=tokens(p1)").is_err());
+    }
+
+    #[test]
+    pub fn golden_counterexamples() {
+        // The counterexamples have to match those of earlier runs, so neither the automata nor
+        // the search may depend on the order of hash maps
+        let net = petri::from_tina(include_str!("../golden/mutex.net")).unwrap();
+        let checker = Checker::new(&net, false);
+        let mut out = String::new();
+        for f in [
+            "G !& 1<=tokens(critical_1) 1<=tokens(critical_2)",
+            "G F enter_1",
+            "G | !1<=tokens(waiting_1) F 1<=tokens(critical_1)",
+            "F G 1<=tokens(idle_2)",
+        ] {
+            out.push_str(&format!("{}\n", f));
+            let outcome = checker.check(&Formula::parse(f).unwrap()).unwrap();
+            let Some(counterexample) = outcome.counterexample else {
+                out.push_str("  holds\n");
+                continue;
+            };
+            for (part, firings) in [
+                ("stem", counterexample.stem),
+                ("cycle", counterexample.cycle),
+            ] {
+                let firings = (firings.iter())
+                    .map(|(t, m)| format!("{} ({})", t, net.marking_label(m)))
+                    .join(", ");
+                out.push_str(&format!("  {}: {}\n", part, firings));
+            }
+        }
+        assert_eq!(out, include_str!("../golden/mutex.txt"));
+    }
+}
@@ -0,0 +1,128 @@
+// Format conversions by `lmc convert`
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use buchi::nba::Buchi;
+use clap::ValueEnum;
+use parity::Graph;
+use petri::PetriNet;
+
+/// Formats written by `lmc convert`
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// PNML for nets
+    Pnml,
+    /// The textual .net format of TINA for nets
+    Tina,
+    /// The Hanoi Omega-Automata format for automata
+    Hoa,
+    /// SPIN's never claims for automata, which are degeneralized
+    Never,
+    /// Graphviz for nets and automata
+    Dot,
+    /// PGSolver's format for games, with compressed priorities and vertices numbered from 0
+    Pgsolver,
+}
+
+impl Target {
+    /// Name of the format on the command line, such as "hoa"
+    fn name(&self) -> &'static str {
+        self.to_possible_value()
+            .expect("Every format can be selected")
+            .get_name()
+    }
+}
+
+/// A model read by `lmc convert`
+pub enum Model {
+    Net(PetriNet),
+    Automaton(Buchi),
+    Game(Graph),
+}
+
+impl Model {
+    /// Read the input of the file at the path. The kind of model is detected by the extension of
+    /// the file or, for stdin and unknown extensions, by how its content starts: 'parity' for
+    /// games, 'HOA:' for automata, 'never' for never claims and anything else for nets.
+    pub fn parse(path: &Path, input: &str) -> Result<Model> {
+        let start = input.trim_start();
+        let model = match path.extension().and_then(|e| e.to_str()) {
+            Some("pg" | "gm") => Model::Game(parity::parse_game(input)?),
+            Some("hoa") => Model::Automaton(buchi::hoa::parse(input)?),
+            Some("never" | "pml") => Model::Automaton(buchi::never::parse(input)?),
+            _ if start.starts_with("parity") => Model::Game(parity::parse_game(input)?),
+            _ if start.starts_with("HOA:") => Model::Automaton(buchi::hoa::parse(input)?),
+            _ if start.starts_with("never") => Model::Automaton(buchi::never::parse(input)?),
+            _ => Model::Net(petri::parse(input, petri::Format::detect(path, input))?),
+        };
+        Ok(model)
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Model::Net(_) => "nets",
+            Model::Automaton(_) => "automata",
+            Model::Game(_) => "games",
+        }
+    }
+
+    /// The model in the target format, each kind is only written in the formats which describe it
+    pub fn convert(&self, target: Target) -> Result<String> {
+        let converted = match (self, target) {
+            (Model::Net(net), Target::Pnml) => net.to_pnml(),
+            (Model::Net(net), Target::Tina) => net.to_tina(),
+            (Model::Net(net), Target::Dot) => net.to_dot(),
+            (Model::Automaton(automaton), Target::Hoa) => format!("{}\n", automaton.hoa()),
+            (Model::Automaton(automaton), Target::Never) => automaton.never_claim(),
+            (Model::Automaton(automaton), Target::Dot) => automaton.to_dot(),
+            (Model::Game(game), Target::Pgsolver) => game.normalize().to_string(),
+            (model, target) => bail!("{} cannot be written as {}", model.kind(), target.name()),
+        };
+        Ok(converted)
+    }
+}
+
+/// Convert the input of the file at the path
+pub fn convert(path: &Path, input: &str, target: Target) -> Result<String> {
+    let model = Model::parse(path, input)
+        .with_context(|| format!("Could not parse {}", path.to_string_lossy()))?;
+    model.convert(target)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn conversions() {
+        let stdin = Path::new("-");
+        let tina = "net net\npl p (1)\npl q\ntr t p -> q\n";
+        let pnml = convert(stdin, tina, Target::Pnml).unwrap();
+        assert_eq!(convert(stdin, &pnml, Target::Tina).unwrap(), tina);
+        assert!(convert(stdin, tina, Target::Dot)
+            .unwrap()
+            .starts_with("digraph"));
+        let error = convert(stdin, tina, Target::Hoa).unwrap_err();
+        assert_eq!(error.to_string(), "nets cannot be written as hoa");
+
+        let never = "never {\naccept_init:\n  if\n  :: (a) -> goto accept_init\n  fi;\n}\n";
+        let hoa = convert(stdin, never, Target::Hoa).unwrap();
+        assert!(hoa.starts_with("HOA: v1"));
+        // Marks of states are moved to the transitions of HOA automata, which adds a state
+        let written = buchi::never::parse(&convert(stdin, &hoa, Target::Never).unwrap()).unwrap();
+        assert_eq!(written.stats().states, 2);
+        assert!(written
+            .equivalent(&buchi::never::parse(never).unwrap())
+            .is_ok());
+        assert!(convert(Path::new("a.never"), never, Target::Dot).is_ok());
+        assert!(convert(stdin, never, Target::Pgsolver).is_err());
+
+        let game = "parity 2;\n0 4 0 1;\n1 6 1 0;\n";
+        assert_eq!(
+            convert(stdin, game, Target::Pgsolver).unwrap(),
+            "parity 2;\n0 0 0 1;\n1 0 1 0;\n"
+        );
+        assert!(convert(Path::new("game.pg"), tina, Target::Pgsolver).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+// Errors of the library, those of the crates it is built on and its own checks
+
+use std::result;
+use thiserror::Error;
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Formula(#[from] ltl::error::Error),
+    #[error(transparent)]
+    Automaton(#[from] buchi::error::Error),
+    #[error(transparent)]
+    Net(#[from] petri::Error),
+    #[error(transparent)]
+    Game(#[from] parity::Error),
+    #[error(transparent)]
+    System(#[from] ts::Error),
+    /// The property is outside of what the chosen procedure handles
+    #[error("{0}")]
+    Unsupported(String),
+    /// Inputs and outputs of a synthesis problem do not fit the specification
+    #[error("{0}")]
+    Specification(String),
+    /// An exported counterexample does not replay on the net
+    #[error("{0}")]
+    Replay(String),
+    /// A certificate does not show the property to hold
+    #[error("{0}")]
+    Witness(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error<T, E: Into<Error>>(result: result::Result<T, E>) -> String {
+        match result {
+            Ok(_) => panic!("malformed input was accepted"),
+            Err(e) => e.into().to_string(),
+        }
+    }
+
+    #[test]
+    pub fn malformed_input() {
+        let game = |text| error(parity::parse_game(text));
+        assert_eq!(
+            game("parity 2;\n0 1 2 1;\n1 0 0 0;"),
+            "invalid parity game in line 2: expected '<vertex> <priority> <owner> <successors>;', \
+             found '0 1 2 1;'"
+        );
+        assert!(game("parity 99999999999999999999;").starts_with("invalid parity game header"));
+        assert!(game("parity 2;\n0 1 0 5;\n1 0 0 0;").contains("vertex 5 is not one of the 2"));
+        assert_eq!(game("parity 2;\n0 1 0 0;"), "vertex 1 has no successors");
+
+        let automaton = "Start: 0\nAcceptance: 1 Inf(0)\n--BODY--\nState: 0\n{x} 0 {3}\n--END--";
+        assert_eq!(
+            error(buchi::hoa::parse(automaton)),
+            "invalid HOA automaton in line 2: mark 3 is not one of the 1 acceptance sets"
+        );
+        assert!(error(ltl::Formula::parse("U a")).contains("'U a'"));
+    }
+}
@@ -0,0 +1,436 @@
+// Interactive exploration of nets, Kripke structures and Büchi automata by `lmc explore`
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::io;
+
+use anyhow::{bail, Result};
+use buchi::nba::Buchi;
+use itertools::Itertools;
+use petri::{MarkingIndex, PetriNet};
+use ts::{Kripke, Path, TransitionSystem};
+
+use lmc::transform::{petri_atom, petri_atom_holds};
+
+/// System which can be stepped through, with what is printed about its states and edges
+pub trait Explorable: TransitionSystem {
+    fn state_label(&self, state: &Self::State) -> String;
+
+    fn edge_label(&self, label: &Self::Label) -> String;
+
+    /// Whether the atomic proposition, written as in properties of the system, holds in the state
+    fn evaluate(&self, state: &Self::State, proposition: &str) -> Result<bool>;
+
+    /// Propositions holding in the state which are not part of its label
+    fn propositions(&self, _state: &Self::State) -> Vec<String> {
+        vec![]
+    }
+
+    fn accepting(&self, _state: &Self::State) -> bool {
+        false
+    }
+
+    /// Answer the query about all reachable states, explored into `markings` on first use
+    fn query(&self, _query: &str, _markings: &mut Option<MarkingIndex>) -> Result<String> {
+        bail!("Queries are only answered about the markings of nets")
+    }
+}
+
+impl Explorable for PetriNet {
+    fn state_label(&self, marking: &Self::State) -> String {
+        self.marking_label(marking)
+    }
+
+    fn edge_label(&self, transition: &String) -> String {
+        transition.clone()
+    }
+
+    /// Propositions are read as by the checkers, see `petri_atom`
+    fn evaluate(&self, marking: &Self::State, proposition: &str) -> Result<bool> {
+        let fireable = marking.active_transitions(self);
+        let atom = petri_atom(self, proposition)?;
+        Ok(petri_atom_holds(self, marking, &fireable, &atom)?)
+    }
+
+    fn query(&self, query: &str, markings: &mut Option<MarkingIndex>) -> Result<String> {
+        let markings = match markings {
+            Some(markings) => markings,
+            None => markings.insert(self.marking_index()?),
+        };
+        Ok(crate::answer_query(self, markings, query)?.to_string())
+    }
+}
+
+impl Explorable for Kripke {
+    fn state_label(&self, state: &usize) -> String {
+        self.name(*state).to_string()
+    }
+
+    fn edge_label(&self, _: &()) -> String {
+        String::new()
+    }
+
+    fn evaluate(&self, state: &usize, proposition: &str) -> Result<bool> {
+        Ok(self.holds(state, proposition))
+    }
+
+    fn propositions(&self, state: &usize) -> Vec<String> {
+        self.labels(*state).iter().cloned().collect()
+    }
+}
+
+impl Explorable for Buchi {
+    fn state_label(&self, state: &Self::State) -> String {
+        self.label_of(*state)
+    }
+
+    fn edge_label(&self, word: &Self::Label) -> String {
+        word.id.clone()
+    }
+
+    fn evaluate(&self, state: &Self::State, proposition: &str) -> Result<bool> {
+        Ok(self.holds(state, proposition))
+    }
+
+    /// In some acceptance set, the state of a generalized automaton need not be in all of them
+    fn accepting(&self, state: &Self::State) -> bool {
+        self.accepting_sets().iter().any(|set| set.contains(state))
+    }
+}
+
+/// Path from an initial state to the current state of a system. Successors are only computed for
+/// the states entered, so nets whose statespace is far too large to explore can be stepped
+/// through.
+pub struct Explorer<'a, T: Explorable> {
+    system: &'a T,
+    /// States from an initial one on, each with the edge entering it
+    path: Vec<(Option<T::Label>, T::State)>,
+    /// Edges leaving the current state
+    successors: Path<T>,
+}
+
+impl<'a, T: Explorable> Explorer<'a, T>
+where
+    T::Error: std::error::Error + Send + Sync + 'static,
+{
+    /// Start in the first initial state
+    pub fn new(system: &'a T) -> Result<Self> {
+        let mut explorer = Explorer {
+            system,
+            path: vec![],
+            successors: vec![],
+        };
+        explorer.reset(0)?;
+        Ok(explorer)
+    }
+
+    pub fn state(&self) -> &T::State {
+        &self.path.last().expect("The initial state is never left").1
+    }
+
+    pub fn successors(&self) -> &Path<T> {
+        &self.successors
+    }
+
+    /// Start over in the initial state with the index
+    pub fn reset(&mut self, initial: usize) -> Result<()> {
+        let initial_states = self.system.initial_states();
+        let Some(state) = initial_states.get(initial) else {
+            bail!(
+                "No initial state {}, the system has {}",
+                initial,
+                initial_states.len()
+            );
+        };
+        self.path.clear();
+        self.enter(None, state.clone())
+    }
+
+    /// Follow the edge with the number or the label, or the only one to the state with the label
+    pub fn follow(&mut self, edge: &str) -> Result<()> {
+        let index = match edge.parse::<usize>() {
+            Ok(index) if index < self.successors.len() => index,
+            Ok(index) => bail!(
+                "No edge {}, {} edges leave the state",
+                index,
+                self.successors.len()
+            ),
+            Err(_) => {
+                let edges = self.successors.iter();
+                let mut indices = (edges.clone())
+                    .positions(|(label, _)| self.system.edge_label(label) == edge)
+                    .collect_vec();
+                if indices.is_empty() {
+                    indices = (edges
+                        .positions(|(_, state)| self.system.state_label(state) == edge))
+                    .collect_vec();
+                }
+                match indices.as_slice() {
+                    [index] => *index,
+                    [] => bail!(
+                        "No edge is labeled '{}' or leads to a state labeled so",
+                        edge
+                    ),
+                    _ => bail!("Several edges match '{}', follow one by its number", edge),
+                }
+            }
+        };
+        let (label, state) = self.successors[index].clone();
+        self.enter(Some(label), state)
+    }
+
+    /// Return to the previous state, giving the label of the edge left, none in an initial state
+    pub fn back(&mut self) -> Result<Option<String>> {
+        if self.path.len() < 2 {
+            return Ok(None);
+        }
+        let (label, _) = self.path.pop().expect("The path has a previous state");
+        self.successors = self.system.successors(self.state())?;
+        Ok(label.map(|l| self.system.edge_label(&l)))
+    }
+
+    fn enter(&mut self, label: Option<T::Label>, state: T::State) -> Result<()> {
+        self.successors = self.system.successors(&state)?;
+        self.path.push((label, state));
+        Ok(())
+    }
+
+    /// States of the path with the labels of the edges between them
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        for (label, state) in &self.path {
+            match label.as_ref().map(|l| self.system.edge_label(l)) {
+                Some(label) if !label.is_empty() => write!(path, " -{}-> ", label).unwrap(),
+                Some(_) => path.push_str(" -> "),
+                None => {}
+            }
+            path.push_str(&self.system.state_label(state));
+        }
+        path
+    }
+
+    /// States within `depth` steps of the current one in DOT format. The current state is drawn
+    /// bold, deadlocks with a double border and states whose successors are left out dashed. This
+    /// helps to see why a counterexample takes the turns it takes.
+    pub fn dot(&self, depth: usize) -> Result<String> {
+        let mut out = String::new();
+        writeln!(&mut out, "digraph g {{\nmindist = 2.0").unwrap();
+        let mut index = HashMap::from([(self.state().clone(), 0)]);
+        let mut queue = VecDeque::from([(self.state().clone(), 0)]);
+        let mut next = 0;
+        while let Some((state, distance)) = queue.pop_front() {
+            let successors = self.system.successors(&state)?;
+            let mut style = match (distance, successors.is_empty()) {
+                (0, _) => vec!["style = bold"],
+                (_, true) => vec!["peripheries = 2"],
+                _ if distance == depth => vec!["style = dashed"],
+                _ => vec![],
+            };
+            if self.system.accepting(&state) {
+                style.push("shape = doublecircle");
+            }
+            let source = index[&state];
+            writeln!(
+                &mut out,
+                "s{} [label = \"{}\"{}]",
+                source,
+                escape(&self.system.state_label(&state)),
+                style.iter().map(|s| format!(", {}", s)).join("")
+            )
+            .unwrap();
+            if distance == depth {
+                continue;
+            }
+            for (label, successor) in successors {
+                let target = *index.entry(successor.clone()).or_insert_with(|| {
+                    next += 1;
+                    queue.push_back((successor, distance + 1));
+                    next
+                });
+                writeln!(
+                    &mut out,
+                    "s{} -> s{} [label = \"{}\"]",
+                    source,
+                    target,
+                    escape(&self.system.edge_label(&label))
+                )
+                .unwrap();
+            }
+        }
+        out.push('}');
+        out.push('\n');
+        Ok(out)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+const EXPLORATION_HELP: &str = "Commands:
+  <edge>         follow the edge with the number or label, or the edge to the state with the label
+  back           return to the previous state
+  reset [N]      return to the initial state, or to the N-th one of several
+  holds <AP>...  evaluate atomic propositions in the current state
+  query <QUERY>  answer a query about the reachable markings of a net, such as 'count where deadlock'
+  path           print the states from the initial one to the current one
+  dot [DEPTH]    print the states up to DEPTH steps from the current one in dot format, 1 by default
+  help           print this help
+  quit           end the exploration";
+
+/// Explore the system by the commands read from stdin
+pub fn repl<T: Explorable>(out: &mut dyn io::Write, system: &T) -> Result<()>
+where
+    T::Error: std::error::Error + Send + Sync + 'static,
+{
+    let mut explorer = Explorer::new(system)?;
+    let mut markings = None;
+    writeln!(out, "{}", EXPLORATION_HELP)?;
+    print_state(out, &explorer)?;
+    for line in io::stdin().lines() {
+        let line = line?;
+        let words = line.split_whitespace().collect_vec();
+        let number = |word: Option<&&str>, default| match word.map(|w| w.parse()) {
+            Some(Ok(n)) => Ok(n),
+            Some(Err(_)) => Err(format!("Invalid number '{}'", word.unwrap())),
+            None => Ok(default),
+        };
+        let outcome = match words.as_slice() {
+            [] => continue,
+            ["quit" | "exit"] => break,
+            ["help"] => {
+                writeln!(out, "{}", EXPLORATION_HELP)?;
+                continue;
+            }
+            ["path"] => {
+                writeln!(out, "{}", explorer.path())?;
+                continue;
+            }
+            ["holds", propositions @ ..] if !propositions.is_empty() => {
+                for proposition in propositions {
+                    match system.evaluate(explorer.state(), proposition) {
+                        Ok(holds) => writeln!(out, "{}: {}", proposition, holds)?,
+                        Err(e) => writeln!(out, "{}: {}", proposition, e)?,
+                    }
+                }
+                continue;
+            }
+            ["query", _, ..] => {
+                let query = line.trim_start().trim_start_matches("query");
+                match system.query(query, &mut markings) {
+                    Ok(answer) => writeln!(out, "{}", answer)?,
+                    Err(e) => writeln!(out, "{}", e)?,
+                }
+                continue;
+            }
+            ["dot", depth @ ..] if depth.len() <= 1 => {
+                match number(depth.first(), 1) {
+                    Ok(depth) => write!(out, "{}", explorer.dot(depth)?)?,
+                    Err(e) => writeln!(out, "{}", e)?,
+                }
+                continue;
+            }
+            ["back"] => match explorer.back()? {
+                Some(label) if !label.is_empty() => {
+                    writeln!(out, "Went back over '{}'", label)?;
+                    Ok(())
+                }
+                Some(_) => Ok(()),
+                None => {
+                    writeln!(out, "Already at an initial state")?;
+                    continue;
+                }
+            },
+            ["reset", initial @ ..] if initial.len() <= 1 => match number(initial.first(), 0) {
+                Ok(initial) => explorer.reset(initial),
+                Err(e) => Err(anyhow::anyhow!(e)),
+            },
+            [edge] => explorer.follow(edge),
+            _ => Err(anyhow::anyhow!(
+                "Unknown command '{}', try 'help'",
+                line.trim()
+            )),
+        };
+        match outcome {
+            Ok(()) => print_state(out, &explorer)?,
+            Err(e) => writeln!(out, "{}", e)?,
+        }
+    }
+    Ok(())
+}
+
+fn print_state<T: Explorable>(out: &mut dyn io::Write, explorer: &Explorer<T>) -> io::Result<()>
+where
+    T::Error: std::error::Error + Send + Sync + 'static,
+{
+    let system = explorer.system;
+    let state = explorer.state();
+    let accepting = match system.accepting(state) {
+        true => " (accepting)",
+        false => "",
+    };
+    writeln!(out, "State: {}{}", system.state_label(state), accepting)?;
+    let propositions = system.propositions(state);
+    if !propositions.is_empty() {
+        writeln!(out, "Propositions: {}", propositions.join(", "))?;
+    }
+    if explorer.successors().is_empty() {
+        return writeln!(out, "Deadlock, no edge leaves the state");
+    }
+    for (index, (label, successor)) in explorer.successors().iter().enumerate() {
+        let label = system.edge_label(label);
+        let arrow = match label.is_empty() {
+            true => "->".to_string(),
+            false => format!("{} ->", label),
+        };
+        writeln!(
+            out,
+            "  {:>2}  {} {}",
+            index,
+            arrow,
+            system.state_label(successor)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn exploration() {
+        let net = petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p0 p2\npl p0 (1)").unwrap();
+        let mut explorer = Explorer::new(&net).unwrap();
+        assert_eq!(explorer.successors().len(), 1);
+        assert!(explorer.follow("t1").is_err());
+        explorer.follow("t0").unwrap();
+        assert!(net.evaluate(explorer.state(), "tokens(p1)<=1").unwrap());
+        assert!(net.evaluate(explorer.state(), "t1").unwrap());
+        assert!(!net.evaluate(explorer.state(), "p0").unwrap());
+        explorer.follow("0").unwrap();
+        assert_eq!(explorer.path(), "p0 -t0-> p1 -t1-> p0 p2");
+        assert_eq!(explorer.back().unwrap(), Some("t1".to_string()));
+        assert_eq!(net.state_label(explorer.state()), "p1");
+        let dot = explorer.dot(2).unwrap();
+        assert!(dot.contains("s0 [label = \"p1\", style = bold]"));
+        assert!(dot.contains("s2 [label = \"p1 p2\", style = dashed]"));
+        let mut markings = None;
+        assert!(net.query("count where p2>0", &mut markings).is_err());
+        let bounded = petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p0\npl p0 (1)").unwrap();
+        assert_eq!(
+            bounded.query("exists where p1=1", &mut markings).unwrap(),
+            "exists where p1=1: true, for example 'p1'"
+        );
+        assert!(markings.is_some());
+
+        let kripke = Kripke::parse(
+            "state s0 : a\nstate s1\nstate s2 : b\ninit s0\ns0 -> s1 s2\ns1 -> s1\ns2 -> s2",
+        )
+        .unwrap();
+        let mut explorer = Explorer::new(&kripke).unwrap();
+        explorer.follow("s2").unwrap();
+        assert_eq!(kripke.propositions(explorer.state()), vec!["b"]);
+        assert!(explorer.reset(1).is_err());
+    }
+}
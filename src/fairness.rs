@@ -0,0 +1,426 @@
+// Fairness assumptions for the LTL checks of Petri nets
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::hash::Hash;
+
+use petri::PetriNet;
+
+/// Constraints on the transitions of a net. A weak one requires one of its transitions to fire
+/// infinitely often if one of them is enabled from some point on, a strong one already if one of
+/// them is enabled infinitely often. Without them a run may ignore an enabled transition forever,
+/// which makes nearly every liveness property of a concurrent net fail.
+#[derive(Debug, Default, Clone)]
+pub struct Fairness {
+    weak: Vec<BTreeSet<String>>,
+    strong: Vec<BTreeSet<String>>,
+}
+
+/// Explored product with the constraints touched by its states and edges
+pub struct FairGraph<'a, S> {
+    states: Vec<S>,
+    /// Successors of every state
+    edges: Vec<Vec<Edge<'a>>>,
+    /// Constraints with an enabled transition in every state
+    enabled: Vec<Vec<usize>>,
+    accepting: Vec<bool>,
+    /// The initial states come first
+    initial: usize,
+}
+
+/// Edge to a state with the fired transition and the constraints it belongs to
+type Edge<'a> = (usize, Option<&'a str>, Vec<usize>);
+
+/// Edges of a fair run from an initial state to a cycle and the edges of the cycle, labeled with
+/// the fired transitions
+pub type FairLasso<'a, S> = (Vec<(Option<&'a str>, S)>, Vec<(Option<&'a str>, S)>);
+
+impl Fairness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require weak fairness for the set of transitions
+    pub fn add_weak<S: ToString>(&mut self, transitions: impl IntoIterator<Item = S>) {
+        self.weak
+            .push(transitions.into_iter().map(|t| t.to_string()).collect());
+    }
+
+    /// Require strong fairness for the set of transitions
+    pub fn add_strong<S: ToString>(&mut self, transitions: impl IntoIterator<Item = S>) {
+        self.strong
+            .push(transitions.into_iter().map(|t| t.to_string()).collect());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weak.is_empty() && self.strong.is_empty()
+    }
+
+    /// Check that the constraints only refer to transitions of the net
+    pub fn validate(&self, net: &PetriNet) -> petri::Result<()> {
+        for transition in self.weak.iter().chain(&self.strong).flatten() {
+            if !net.has_transition(transition) {
+                return Err(petri::Error::UnknownTransition(transition.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Indices of the constraints containing one of the transitions, the weak constraints come
+    /// first
+    fn touched<'a>(&self, transitions: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+        let transitions: Vec<_> = transitions.into_iter().collect();
+        self.weak
+            .iter()
+            .chain(&self.strong)
+            .enumerate()
+            .filter(|(_, set)| transitions.iter().any(|t| set.contains(*t)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Explore the whole product, the components are only known afterwards
+    ///
+    /// Edges are labeled with the fired transition, `None` if no transition fires, and `enabled`
+    /// gives the transitions enabled in a state.
+    pub fn explore<'a, S: Clone + Eq + Hash>(
+        &self,
+        initial: Vec<S>,
+        successors: impl Fn(&S) -> Vec<(Option<&'a str>, S)>,
+        enabled: impl Fn(&S) -> Vec<&'a str>,
+        accepting: impl Fn(&S) -> bool,
+    ) -> FairGraph<'a, S> {
+        let mut index = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut graph = FairGraph {
+            states: vec![],
+            edges: vec![],
+            enabled: vec![],
+            accepting: vec![],
+            initial: 0,
+        };
+        let mut id = |s: S, graph: &mut FairGraph<'a, S>, queue: &mut VecDeque<S>| {
+            *index.entry(s.clone()).or_insert_with(|| {
+                graph.edges.push(vec![]);
+                graph.enabled.push(self.touched(enabled(&s)));
+                graph.accepting.push(accepting(&s));
+                graph.states.push(s.clone());
+                queue.push_back(s);
+                graph.edges.len() - 1
+            })
+        };
+        for s in initial {
+            id(s, &mut graph, &mut queue);
+        }
+        graph.initial = graph.states.len();
+        let mut source = 0;
+        while let Some(s) = queue.pop_front() {
+            for (fired, target) in successors(&s) {
+                let target = id(target, &mut graph, &mut queue);
+                let touched = self.touched(fired);
+                graph.edges[source].push((target, fired, touched));
+            }
+            source += 1;
+        }
+        graph
+    }
+
+    /// Fair run of the explored product visiting an accepting state infinitely often
+    pub fn fair_lasso<'a, S: Clone>(&self, graph: &FairGraph<'a, S>) -> Option<FairLasso<'a, S>> {
+        let all = vec![true; graph.states.len()];
+        let component = self.fair_cycle(graph, all)?;
+        let inside = |s: usize| component.contains(&s);
+        let path = |edges: Vec<(usize, Option<&'a str>)>| {
+            edges
+                .into_iter()
+                .map(|(s, fired)| (fired, graph.states[s].clone()))
+                .collect()
+        };
+
+        let (entry, stem) = graph.path(0..graph.initial, |_| true, inside, false);
+        // The cycle passes an accepting state, a state disabling every weak constraint it does
+        // not fire and an edge of every constraint fired inside the component
+        let mut cycle = vec![];
+        let mut current = entry;
+        let walk = |cycle: &mut Vec<_>, current: &mut usize, target: &dyn Fn(usize) -> bool| {
+            let (end, edges) = graph.path([*current], inside, target, false);
+            cycle.extend(edges);
+            *current = end;
+        };
+        walk(&mut cycle, &mut current, &|s| graph.accepting[s]);
+        for c in 0..self.weak.len() + self.strong.len() {
+            let fires = |s: usize| {
+                graph.edges[s]
+                    .iter()
+                    .find(|(t, _, touched)| inside(*t) && touched.contains(&c))
+            };
+            if component.iter().any(|s| fires(*s).is_some()) {
+                walk(&mut cycle, &mut current, &|s| fires(s).is_some());
+                let (t, fired, _) = fires(current).unwrap();
+                cycle.push((*t, *fired));
+                current = *t;
+            } else if c < self.weak.len() {
+                walk(&mut cycle, &mut current, &|s| {
+                    !graph.enabled[s].contains(&c)
+                });
+            }
+        }
+        let (_, back) = graph.path([current], inside, |s| s == entry, cycle.is_empty());
+        cycle.extend(back);
+        Some((path(stem), path(cycle)))
+    }
+
+    /// Search the states of the subgraph for a fair component with an accepting state
+    ///
+    /// A component is fair if it fires a transition of every weak constraint or has a state in
+    /// which it is disabled, and does the same for every strong constraint. Components only
+    /// failing strong constraints are refined by removing the states in which these are enabled
+    /// (Emerson-Lei).
+    fn fair_cycle<S>(&self, graph: &FairGraph<S>, subgraph: Vec<bool>) -> Option<BTreeSet<usize>> {
+        for component in components(graph, &subgraph) {
+            let inside = |s: &usize| subgraph[*s] && component.contains(s);
+            let edges = || {
+                component
+                    .iter()
+                    .flat_map(|s| &graph.edges[*s])
+                    .filter(|(t, _, _)| inside(t))
+            };
+            // Single states only form a component with a self loop
+            if edges().next().is_none() || !component.iter().any(|s| graph.accepting[*s]) {
+                continue;
+            }
+
+            let fired: BTreeSet<usize> = edges().flat_map(|(_, _, c)| c).copied().collect();
+            let always_enabled =
+                |c: usize| component.iter().all(|s| graph.enabled[*s].contains(&c));
+            let weak = 0..self.weak.len();
+            if weak
+                .clone()
+                .any(|c| !fired.contains(&c) && always_enabled(c))
+            {
+                // Every cycle inside the component ignores the transitions
+                continue;
+            }
+            let violated: Vec<usize> = (self.weak.len()..self.weak.len() + self.strong.len())
+                .filter(|c| !fired.contains(c))
+                .filter(|c| component.iter().any(|s| graph.enabled[*s].contains(c)))
+                .collect();
+            if violated.is_empty() {
+                return Some(component);
+            }
+
+            // Fair cycles inside the component have to avoid the violated constraints entirely
+            let mut refined = vec![false; subgraph.len()];
+            for s in &component {
+                refined[*s] = !violated.iter().any(|c| graph.enabled[*s].contains(c));
+            }
+            if let Some(fair) = self.fair_cycle(graph, refined) {
+                return Some(fair);
+            }
+        }
+        None
+    }
+}
+
+impl<'a, S> FairGraph<'a, S> {
+    /// Number of states explored
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Shortest path staying within the states from one of the sources to a target state,
+    /// giving the target and the edges with the states they lead to. With `nonempty` the path
+    /// takes at least one edge.
+    fn path(
+        &self,
+        sources: impl IntoIterator<Item = usize>,
+        within: impl Fn(usize) -> bool,
+        target: impl Fn(usize) -> bool,
+        nonempty: bool,
+    ) -> (usize, Vec<(usize, Option<&'a str>)>) {
+        let mut parents = HashMap::new();
+        let mut queue = VecDeque::new();
+        for s in sources {
+            if !nonempty && target(s) {
+                return (s, vec![]);
+            }
+            parents.insert(s, None);
+            queue.push_back(s);
+        }
+        while let Some(s) = queue.pop_front() {
+            for (t, fired, _) in &self.edges[s] {
+                if !within(*t) {
+                    continue;
+                }
+                if target(*t) {
+                    let mut path = vec![(*t, *fired)];
+                    let mut current = s;
+                    while let Some(Some((parent, fired))) = parents.get(&current) {
+                        path.push((current, *fired));
+                        current = *parent;
+                    }
+                    path.reverse();
+                    return (*t, path);
+                }
+                if !parents.contains_key(t) {
+                    parents.insert(*t, Some((s, *fired)));
+                    queue.push_back(*t);
+                }
+            }
+        }
+        panic!("The components are strongly connected and reachable")
+    }
+}
+
+/// Strongly connected components of the subgraph using Tarjan's algorithm
+fn components<S>(graph: &FairGraph<S>, subgraph: &[bool]) -> Vec<BTreeSet<usize>> {
+    let n = graph.edges.len();
+    let mut order = vec![None; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+    let mut components = vec![];
+    let mut next = 0;
+
+    for root in (0..n).filter(|s| subgraph[*s]) {
+        if order[root].is_some() {
+            continue;
+        }
+        // Pairs of states and the index of the next edge to follow
+        let mut call_stack = vec![(root, 0)];
+        order[root] = Some(next);
+        low[root] = next;
+        next += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((s, edge)) = call_stack.pop() {
+            let successor = graph.edges[s][edge..]
+                .iter()
+                .enumerate()
+                .find(|(_, (t, _, _))| subgraph[*t])
+                .map(|(i, (t, _, _))| (edge + i, *t));
+            match successor {
+                Some((i, t)) => {
+                    call_stack.push((s, i + 1));
+                    match order[t] {
+                        None => {
+                            order[t] = Some(next);
+                            low[t] = next;
+                            next += 1;
+                            stack.push(t);
+                            on_stack[t] = true;
+                            call_stack.push((t, 0));
+                        }
+                        Some(o) if on_stack[t] => low[s] = low[s].min(o),
+                        Some(_) => {}
+                    }
+                }
+                None => {
+                    if let Some((parent, _)) = call_stack.last() {
+                        low[*parent] = low[*parent].min(low[s]);
+                    }
+                    if Some(low[s]) == order[s] {
+                        let mut component = BTreeSet::new();
+                        while let Some(t) = stack.pop() {
+                            on_stack[t] = false;
+                            component.insert(t);
+                            if t == s {
+                                break;
+                            }
+                        }
+                        components.push(component);
+                    }
+                }
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Two states which can both loop or switch, the transitions are named after their target
+    /// and only the self loop of 'a' is accepting
+    fn lasso(fairness: &Fairness) -> Option<FairLasso<'static, (&'static str, bool)>> {
+        let graph = fairness.explore(
+            vec![("a", false)],
+            |&(s, _)| {
+                let other = if s == "a" { "b" } else { "a" };
+                vec![(Some(s), (s, s == "a")), (Some(other), (other, false))]
+            },
+            |_| vec!["a", "b"],
+            |&(_, accepting)| accepting,
+        );
+        fairness.fair_lasso(&graph)
+    }
+
+    fn cycle(fairness: &Fairness) -> bool {
+        lasso(fairness).is_some()
+    }
+
+    #[test]
+    pub fn fair_cycles() {
+        assert!(cycle(&Fairness::new()));
+
+        // Switching to b infinitely often still allows to loop in a infinitely often
+        let mut weak = Fairness::new();
+        weak.add_weak(["b"]);
+        let (stem, fair) = lasso(&weak).unwrap();
+        assert!(stem.is_empty());
+        assert!(fair.contains(&(Some("b"), ("b", false))));
+        assert!(fair.contains(&(Some("a"), ("a", true))));
+        assert_eq!(fair.last().unwrap().1 .0, "a");
+
+        // A transition which is never enabled cannot be required to fire
+        let mut strong = Fairness::new();
+        strong.add_strong(["b", "c"]);
+        assert!(cycle(&strong));
+    }
+
+    #[test]
+    pub fn weak_and_strong_fairness() {
+        // State 0 loops and is accepting, 'leave' is enabled in it but only 'back' in state 2
+        let successors = |s: &usize| match s {
+            0 => vec![(Some("loop"), 0), (Some("leave"), 1), (Some("go"), 2)],
+            1 => vec![(Some("stay"), 1)],
+            _ => vec![(Some("back"), 0)],
+        };
+        let enabled = |s: &usize| match s {
+            0 => vec!["loop", "leave", "go"],
+            1 => vec!["stay"],
+            _ => vec!["back"],
+        };
+        let accepting = |s: &usize| *s == 0;
+        let fair =
+            |fairness: &Fairness, graph: &FairGraph<'static, usize>| fairness.fair_lasso(graph);
+        let graph = Fairness::new().explore(vec![0], successors, enabled, accepting);
+        assert_eq!(graph.len(), 3);
+        assert!(fair(&Fairness::new(), &graph).is_some());
+
+        // Visiting state 2 infinitely often disables 'leave' infinitely often
+        let mut weak = Fairness::new();
+        weak.add_weak(["leave"]);
+        let graph = weak.explore(vec![0], successors, enabled, accepting);
+        let (_, cycle) = fair(&weak, &graph).unwrap();
+        assert!(cycle.contains(&(Some("go"), 2)));
+
+        let mut strong = Fairness::new();
+        strong.add_strong(["leave"]);
+        let graph = strong.explore(vec![0], successors, enabled, accepting);
+        assert!(fair(&strong, &graph).is_none());
+
+        // Without state 2 even weak fairness forces the run to leave
+        let successors = |s: &usize| match s {
+            0 => vec![(Some("loop"), 0), (Some("leave"), 1)],
+            _ => vec![(Some("stay"), 1)],
+        };
+        let graph = weak.explore(vec![0], successors, enabled, accepting);
+        assert!(fair(&weak, &graph).is_none());
+    }
+}
@@ -0,0 +1,370 @@
+// Verification jobs described in a TOML file, run one after another by `lmc run`
+
+use std::ffi::OsStr;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use lmc::check;
+use lmc::results::Verdict;
+use serde_derive::{Deserialize, Serialize};
+use ts::Stop;
+
+use crate::limits::{self, Limits};
+use crate::{
+    fairness, read_input, read_kripke, read_petri, read_properties, verify_net, Algorithm,
+    Checking, Engine, Format, Outputs, Status, Syntax,
+};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobFile {
+    /// Seconds
+    timeout: Option<f64>,
+    /// Megabytes
+    max_memory: Option<usize>,
+    #[serde(rename = "job")]
+    jobs: Vec<Job>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Job {
+    name: Option<String>,
+    model: Option<PathBuf>,
+    properties: Option<PathBuf>,
+    #[serde(default = "default_syntax")]
+    syntax: Syntax,
+    /// Ids of the properties checked, all of them if empty
+    #[serde(default)]
+    property: Vec<String>,
+    #[serde(default = "default_engine")]
+    engine: Engine,
+    #[serde(default = "default_depth")]
+    depth: usize,
+    #[serde(default = "default_threads")]
+    threads: usize,
+    #[serde(default)]
+    por: bool,
+    #[serde(default)]
+    fair_weak: Vec<String>,
+    #[serde(default)]
+    fair_strong: Vec<String>,
+    game: Option<PathBuf>,
+    algorithm: Option<Algorithm>,
+    timeout: Option<f64>,
+    max_memory: Option<usize>,
+}
+
+fn default_syntax() -> Syntax {
+    Syntax::Prefix
+}
+
+fn default_engine() -> Engine {
+    Engine::Explicit
+}
+
+fn default_depth() -> usize {
+    20
+}
+
+fn default_threads() -> usize {
+    1
+}
+
+/// Outcome of a job in the report of `lmc run`
+#[derive(Serialize)]
+pub struct JobReport {
+    pub name: String,
+    /// Seconds
+    pub time: f64,
+    #[serde(flatten)]
+    pub outcome: JobOutcome,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum JobOutcome {
+    Properties {
+        properties: Vec<PropertyVerdict>,
+    },
+    Game {
+        even_region: usize,
+        odd_region: usize,
+    },
+    Error {
+        error: String,
+    },
+}
+
+#[derive(Serialize)]
+pub struct PropertyVerdict {
+    pub id: String,
+    pub formula: String,
+    pub verdict: Verdict,
+}
+
+/// Run the jobs of the file and print the report, `limits` hold for jobs without their own
+///
+/// Every [[job]] either checks the LTL properties of a model, a Petri net or a Kripke structure
+/// in the .kts format, or solves a parity game:
+///
+/// ```toml
+/// timeout = 60
+///
+/// [[job]]
+/// name = "philosophers"
+/// model = "nets/philosophers.pnml"
+/// properties = "nets/philosophers.xml"
+/// engine = "explicit"
+/// threads = 4
+///
+/// [[job]]
+/// game = "games/arbiter.pg"
+/// algorithm = "zielonka"
+/// ```
+///
+/// Paths are relative to the file. Options of nets are named like those of `lmc check`, with
+/// `threads` for --jobs. The limits given at the top of the file or for a job hold for each job
+/// on its own. A job stopped by them reports its unchecked properties as unknown, an interrupt
+/// also skips the remaining jobs. A job failing, for example on an unreadable model, is reported
+/// without ending the others.
+pub fn run(
+    out: &mut dyn io::Write,
+    path: &OsStr,
+    limits: Limits,
+    format: Format,
+) -> Result<Status> {
+    let content = read_input(path)?;
+    let file: JobFile = toml::from_str(&content)
+        .with_context(|| format!("Could not parse jobs from {}", path.to_string_lossy()))?;
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+    let defaults = Limits {
+        timeout: file.timeout.map(Duration::from_secs_f64).or(limits.timeout),
+        max_memory: file.max_memory.map(|mb| mb << 20).or(limits.max_memory),
+    };
+    let mut reports = vec![];
+    for (index, job) in file.jobs.iter().enumerate() {
+        let name = job
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("job-{}", index + 1));
+        let _span = tracing::info_span!("job", name = %name).entered();
+        if ts::stopped() == Some(Stop::Interrupt) {
+            tracing::warn!("skipped after the interrupt");
+            continue;
+        }
+        tracing::info!("running");
+        let limits = Limits {
+            timeout: job
+                .timeout
+                .map(Duration::from_secs_f64)
+                .or(defaults.timeout),
+            max_memory: job.max_memory.map(|mb| mb << 20).or(defaults.max_memory),
+        };
+        let watch = limits::watch(limits, true);
+        let start = Instant::now();
+        let outcome = run_job(job, directory).unwrap_or_else(|e| JobOutcome::Error {
+            error: format!("{:#}", e),
+        });
+        drop(watch);
+        if ts::stopped() != Some(Stop::Interrupt) {
+            ts::resume();
+        }
+        reports.push(JobReport {
+            name,
+            time: start.elapsed().as_secs_f64(),
+            outcome,
+        });
+    }
+    match format {
+        Format::Text => print_reports(out, &reports)?,
+        Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(&reports)?)?,
+    }
+    let failed = (reports.iter())
+        .filter(|r| matches!(r.outcome, JobOutcome::Error { .. }))
+        .count();
+    if failed > 0 {
+        bail!("{} of {} jobs failed", failed, file.jobs.len());
+    }
+    let mut status = Status::Holds;
+    for report in &reports {
+        status = status.max(report.outcome.status());
+    }
+    if reports.len() < file.jobs.len() {
+        status = status.max(Status::Unknown);
+    }
+    Ok(status)
+}
+
+fn run_job(job: &Job, directory: &Path) -> Result<JobOutcome> {
+    let path = |path: &Path| directory.join(path).into_os_string();
+    match (&job.model, &job.properties, &job.game) {
+        (Some(model), Some(properties), None) => {
+            let properties = read_properties(&path(properties), job.syntax, &job.property)?;
+            let model = path(model);
+            if Path::new(&model).extension().is_some_and(|e| e == "kts") {
+                let kripke = read_kripke(&model)?;
+                let properties = properties.iter().map(|(id, f)| {
+                    let Ok(run) = check::check_system(&kripke, f);
+                    PropertyVerdict {
+                        id: id.clone(),
+                        formula: f.to_string(),
+                        verdict: match run {
+                            Some(_) => Verdict::Violated,
+                            None => Verdict::Holds,
+                        },
+                    }
+                });
+                return Ok(JobOutcome::Properties {
+                    properties: properties.collect(),
+                });
+            }
+            let net = read_petri(&model, &[], &[], false)?;
+            let checking = Checking {
+                engine: job.engine,
+                depth: job.depth,
+                por: job.por,
+                jobs: job.threads,
+                fairness: fairness(&job.fair_weak, &job.fair_strong),
+                vacuity: false,
+                mcc: false,
+                report: false,
+                print: false,
+                witnesses: false,
+                cache: None,
+            };
+            let outputs = Outputs {
+                results: None,
+                counterexamples: None,
+                witnesses: None,
+            };
+            let results = verify_net(
+                &mut io::sink(),
+                &net,
+                &model,
+                &properties,
+                &checking,
+                &outputs,
+            )?;
+            let properties = results.properties.into_iter().map(|p| PropertyVerdict {
+                id: p.id,
+                formula: p.formula,
+                verdict: p.verdict,
+            });
+            Ok(JobOutcome::Properties {
+                properties: properties.collect(),
+            })
+        }
+        (None, None, Some(game)) => {
+            let game = path(game);
+            let game = parity::parse_game(&read_input(&game)?).with_context(|| {
+                format!("Could not parse parity game {}", game.to_string_lossy())
+            })?;
+            let solution = job.algorithm.unwrap_or(Algorithm::Fpi).solver()(&game);
+            Ok(JobOutcome::Game {
+                even_region: solution.even_region.len(),
+                odd_region: solution.odd_region.len(),
+            })
+        }
+        _ => bail!("A job either has a model with properties or a game"),
+    }
+}
+
+impl JobOutcome {
+    fn status(&self) -> Status {
+        match self {
+            JobOutcome::Properties { properties } => {
+                Status::of(properties.iter().map(|p| p.verdict))
+            }
+            JobOutcome::Game { .. } | JobOutcome::Error { .. } => Status::Holds,
+        }
+    }
+}
+
+/// Print a line for each job, with the properties which are not known to hold below it
+fn print_reports(out: &mut dyn io::Write, reports: &[JobReport]) -> io::Result<()> {
+    let width = (reports.iter())
+        .map(|r| r.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for report in reports {
+        let time = format!("{:.3}s", report.time);
+        match &report.outcome {
+            JobOutcome::Properties { properties } => {
+                let count =
+                    |verdict: Verdict| (properties.iter()).filter(|p| p.verdict == verdict).count();
+                writeln!(
+                    out,
+                    "{:width$}  {} hold, {} violated, {} unknown in {}",
+                    report.name,
+                    count(Verdict::Holds),
+                    count(Verdict::Violated),
+                    count(Verdict::Unknown),
+                    time
+                )?;
+                let undecided = properties.iter().filter(|p| p.verdict != Verdict::Holds);
+                for property in undecided.sorted_by_key(|p| p.verdict == Verdict::Unknown) {
+                    let verdict = match property.verdict {
+                        Verdict::Violated => "violated",
+                        _ => "unknown",
+                    };
+                    writeln!(out, "{:width$}    {}: {}", "", property.id, verdict)?;
+                }
+            }
+            JobOutcome::Game {
+                even_region,
+                odd_region,
+            } => writeln!(
+                out,
+                "{:width$}  even wins {} vertices, odd wins {} in {}",
+                report.name, even_region, odd_region, time
+            )?,
+            JobOutcome::Error { error } => {
+                writeln!(out, "{:width$}  failed: {}", report.name, error)?
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn job_files() {
+        let file: JobFile = toml::from_str(
+            "timeout = 60\n\
+             [[job]]\n\
+             model = \"net.pnml\"\n\
+             properties = \"net.xml\"\n\
+             engine = \"bmc\"\n\
+             [[job]]\n\
+             game = \"game.pg\"\n\
+             algorithm = \"zielonka\"",
+        )
+        .unwrap();
+        assert_eq!(file.timeout, Some(60.0));
+        assert_eq!(file.jobs.len(), 2);
+        assert!(matches!(file.jobs[0].engine, Engine::Bmc));
+        assert_eq!(file.jobs[0].depth, 20);
+        assert!(matches!(file.jobs[1].algorithm, Some(Algorithm::Zielonka)));
+        assert!(toml::from_str::<JobFile>("[[job]]\nmodle = \"net.pnml\"").is_err());
+
+        let directory = std::env::temp_dir().join("lmc-jobs");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("game.pg"), "parity 2;\n0 2 0 1;\n1 1 1 0;").unwrap();
+        let outcome = run_job(&file.jobs[1], &directory).unwrap();
+        assert!(matches!(
+            outcome,
+            JobOutcome::Game {
+                even_region: 2,
+                odd_region: 0
+            }
+        ));
+        assert!(run_job(&file.jobs[0], &directory).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+// Model checking of Petri nets, Kripke structures and LTL specifications as a library
+
+pub use buchi;
+pub use ltl;
+pub use parity;
+pub use petri;
+pub use ts;
+
+pub mod alternating;
+pub mod bmc;
+pub mod cache;
+pub mod cegar;
+pub mod check;
+pub mod error;
+pub mod fairness;
+pub mod monitor;
+pub mod replay;
+pub mod report;
+pub mod results;
+pub mod smv;
+pub mod synth;
+pub mod tableau;
+pub mod transform;
+pub mod vacuity;
+pub mod witness;
+
+pub use error::{Error, Result};
+
+/// Types and functions used by most programs checking properties
+pub mod prelude {
+    pub use buchi::nba::Buchi;
+    pub use ltl::Formula;
+    pub use parity::{Graph, Solution};
+    pub use petri::{Marking, PetriNet};
+    pub use ts::{Kripke, TransitionSystem};
+
+    pub use crate::check::{check_system, Checker, Counterexample, Outcome};
+    pub use crate::fairness::Fairness;
+    pub use crate::synth::{synthesize, Synthesis};
+    pub use crate::transform::{ltl_to_gnba, petri_atom, petri_atom_holds, IndexedNba};
+}
+
+#[cfg(test)]
+mod test {
+    use super::prelude::*;
+
+    #[test]
+    pub fn prelude() {
+        let net = petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p0\npl p0 (1)").unwrap();
+        let checker = Checker::new(&net, false);
+        let holds = Formula::parse("G F 1<=tokens(p1)").unwrap();
+        assert!(checker.check(&holds).unwrap().counterexample.is_none());
+        let violated = Formula::parse("G tokens(p1)<=0").unwrap();
+        assert!(checker.check(&violated).unwrap().counterexample.is_some());
+
+        let kripke = Kripke::parse("state s0 : a\nstate s1\ninit s0\ns0 -> s1\ns1 -> s0").unwrap();
+        let Ok(run) = check_system(&kripke, &Formula::parse("G F a").unwrap());
+        assert!(run.is_none());
+    }
+}
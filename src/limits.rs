@@ -0,0 +1,83 @@
+// Limits on the time and memory of a run and handling of interrupts
+
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use ts::Stop;
+
+use crate::Status;
+use lmc::report::resident_memory;
+
+/// How often the watchdog checks the limits
+const INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy)]
+pub struct Limits {
+    pub timeout: Option<Duration>,
+    /// Bytes of resident memory
+    pub max_memory: Option<usize>,
+}
+
+/// Watchdog of the limits, which ends when dropped
+pub struct Watch {
+    done: Arc<AtomicBool>,
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Stop on the first interrupt if the command is `graceful`, otherwise end it right away, as on
+/// a second interrupt
+pub fn handle_interrupts(graceful: bool) -> Result<()> {
+    ctrlc::set_handler(move || {
+        if !graceful || ts::stopped() == Some(Stop::Interrupt) {
+            tracing::error!("interrupted");
+            // As if ended by the signal
+            process::exit(130);
+        }
+        ts::stop(Stop::Interrupt);
+        tracing::warn!("interrupted, reporting what was found so far, interrupt again to quit");
+    })
+    .context("Could not handle interrupts")
+}
+
+/// Watch the limits from now on, `graceful` commands report what they found after being stopped.
+/// A thread checks the elapsed time and the resident memory a few times a second and asks the
+/// explorations to stop once a limit is exceeded, see `ts::stop`.
+pub fn watch(limits: Limits, graceful: bool) -> Watch {
+    let done = Arc::new(AtomicBool::new(false));
+    if limits.timeout.is_none() && limits.max_memory.is_none() {
+        return Watch { done };
+    }
+    let start = Instant::now();
+    let watching = done.clone();
+    thread::spawn(move || {
+        while !watching.load(Ordering::SeqCst) {
+            thread::sleep(INTERVAL);
+            let reason = if limits.timeout.is_some_and(|t| start.elapsed() >= t) {
+                Stop::Timeout
+            } else if limits
+                .max_memory
+                .is_some_and(|max| resident_memory() >= Some(max))
+            {
+                Stop::Memory
+            } else {
+                continue;
+            };
+            ts::stop(reason);
+            if !graceful {
+                tracing::error!("stopped, {}", reason);
+                process::exit(Status::Unknown as i32);
+            }
+            return;
+        }
+    });
+    Watch { done }
+}
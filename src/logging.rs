@@ -0,0 +1,47 @@
+// Diagnostics of a run, logged to stderr by `tracing`
+
+use std::io::{self, IsTerminal};
+
+use anyhow::{anyhow, Result};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum LogFormat {
+    Text,
+    /// A JSON object per line with the fields of the event and the spans around it, and a line
+    /// with the time spent when a span closes
+    Json,
+}
+
+/// Log the progress of lmc by default, more with each of `verbose` and only errors if `quiet`
+///
+/// Phases such as reading a model or checking a property open spans, so every event carries the
+/// phases it happened in with their fields. The progress of explorations and the verdicts are
+/// logged at the info level, the steps of the algorithms at the debug and trace levels. RUST_LOG
+/// selects the levels of single modules, for example 'lmc::cegar=debug,parity=trace', and
+/// overrides the flags.
+pub fn init(verbose: u8, quiet: bool, format: LogFormat) -> Result<()> {
+    let level = match (quiet, verbose) {
+        (true, _) => "error",
+        (false, 0) => "warn,lmc=info",
+        (false, 1) => "debug",
+        (false, _) => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal());
+    match format {
+        LogFormat::Text if verbose == 0 => subscriber.try_init(),
+        // The time spent in each phase
+        LogFormat::Text => subscriber.with_span_events(FmtSpan::CLOSE).try_init(),
+        LogFormat::Json => (subscriber.json())
+            .with_span_events(FmtSpan::CLOSE)
+            .with_current_span(true)
+            .with_span_list(true)
+            .try_init(),
+    }
+    .map_err(|e| anyhow!("Could not set up logging: {}", e))
+}
@@ -1,50 +1,328 @@
-mod transform;
+mod convert;
+mod explore;
+mod jobs;
+mod limits;
+mod logging;
+mod output;
+mod watch;
 
-use crate::transform::petri_to_gnba;
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use env_logger::Env;
+use crate::limits::Limits;
+use crate::logging::LogFormat;
+use crate::output::{
+    Choice, Cover, HomeMarkings, LtlDocument, ParityDocument, PetriDocument, QueryAnswer, Reach,
+    Statespace, Stopped, TransitionLiveness, Violated,
+};
+use anyhow::{bail, Context, Result};
+use buchi::nba::Buchi;
+use clap::{Args, Parser, Subcommand};
 use itertools::Itertools;
+use lmc::alternating::ltl_to_nba_alternating;
+use lmc::cache::Cache;
+use lmc::check::{Checker, Counterexample, Run};
+use lmc::fairness::Fairness;
+use lmc::report::Report;
+use lmc::results::{Counterexamples, PropertyResult, Results, Verdict, Violation};
+use lmc::synth::{Solver, Synthesis};
+use lmc::transform::{ltl_to_gnba, ltl_to_gnba_powerset};
+use lmc::witness::{Witness, Witnesses};
+use lmc::{bmc, cegar, check, monitor, replay, results, smv, synth, vacuity, witness};
 use ltl::Formula;
-use petri::PetriNet;
-use std::ffi::OsString;
+use parity::{Graph, MetaData};
+use petri::{
+    Answer, Bound, Ctmc, HashCompaction, Liveness, Marking, MarkingIndex, MarkingStore, PetriNet,
+    Query, Simulation, Visited,
+};
+use serde_derive::Deserialize;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Write;
+use std::io::{BufWriter, Write as _};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
 use std::{
     collections::{HashSet, VecDeque},
-    fs,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
     time::{Duration, SystemTime},
 };
-use transform::ltl_to_gnba;
+use ts::{Kripke, Stop};
 
 // opt parsing
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 #[clap(propagate_version = true)]
+#[clap(after_help = EXIT_CODES)]
 /// Provides analysis for PetriNets, LTL property verification and various LTL model checking
 /// toolings.
 struct Cli {
-    /// Show diagnostic debug information, effect is the same as setting RUST_LOG=debug
-    #[clap(short, long)]
-    verbose: bool,
+    /// Log the phases of the run and the time spent in them on stderr, -vv also logs every step
+    /// of the algorithms. RUST_LOG selects the levels of single modules instead, for example
+    /// 'lmc::cegar=debug,parity=trace'.
+    #[clap(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Only log errors, leaving out the progress of explorations and the verdicts
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Log as text or as a JSON object per line
+    #[clap(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+    /// Write what is printed to the file instead of stdout
+    #[clap(long, global = true, value_name = "FILE")]
+    output: Option<OsString>,
+    /// Stop after this many seconds. `petri` and `check` then report what was found so far, the
+    /// properties not checked in time are unknown, other commands end right away. So does the
+    /// first interrupt, the second one always ends the command.
+    #[clap(long, global = true, value_name = "SECONDS")]
+    timeout: Option<f64>,
+    /// Stop once the process takes about this many megabytes of memory, like --timeout. Exploring
+    /// the statespace of a net by a single thread stops once the visited and queued markings
+    /// take about this many megabytes.
+    #[clap(long, global = true, value_name = "MB")]
+    max_memory: Option<usize>,
     #[clap(subcommand)]
     command: Commands,
 }
+const EXIT_CODES: &str = "EXIT CODES:
+    0    Every property holds, or the command checks none
+    1    A property is violated, an automaton is not included or a specification is unrealizable
+    2    Unknown, a property or the statespace was not decided, for example after --timeout
+    3    Invalid inputs or options and other errors
+    130  Ended by a second interrupt or by an interrupt to a command which cannot be stopped";
+
+impl Cli {
+    fn limits(&self) -> Limits {
+        Limits {
+            timeout: self.timeout.map(Duration::from_secs_f64),
+            max_memory: self.max_memory.map(|mb| mb << 20),
+        }
+    }
+}
+
+// Parsed once, the size of the variants does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Analyse the statespace of PetriNets provided by the given files
     Petri {
-        /// Number of PNML files which contain PetriNets to be analysed
+        /// File containing the PetriNet to be analysed, in PNML, the TINA .net format or LoLA's
+        /// format, detected by the extension or the content. '-' reads the net from stdin.
         file: OsString,
         /// Explore the statespace of the petri net
         #[clap(short, long)]
         analyse: bool,
-        /// Verify the petri net against an LTL specification
+        /// Threads exploring the statespace with the explicit engine or checking the LTL
+        /// properties, 0 uses one per core. Stubborn sets are always explored by a single thread
+        /// and the threads checking properties do not share the markings they explore.
+        #[clap(short, long, default_value_t = 1)]
+        jobs: usize,
+        /// Only store hashes of the visited markings, which saves memory but misses markings
+        /// with a small probability that is reported. Only used by a single thread.
+        #[clap(long)]
+        hash_compaction: bool,
+        /// Keep the visited markings in files below the directory instead of in memory, so
+        /// --analyse also explores statespaces exceeding the memory, by a single thread and
+        /// without reporting the liveness of transitions or home markings
+        #[clap(long, value_name = "DIR", conflicts_with_all = &["por", "hash-compaction"])]
+        disk: Option<PathBuf>,
+        /// Megabytes of successor markings kept in memory before they are written to --disk
+        #[clap(long, default_value_t = 256, requires = "disk")]
+        disk_memory: usize,
+        /// Stop exploring after visiting this many markings and report what was found so far.
+        /// Only checked by a single thread.
+        #[clap(long, value_name = "N")]
+        max_states: Option<usize>,
+        /// Compose the net with the nets in the files, synchronizing the transitions given by
+        /// --sync, which every net must contain
+        #[clap(long, value_name = "FILE")]
+        compose: Vec<OsString>,
+        /// Transitions fired together by the composed nets
+        #[clap(
+            long,
+            value_delimiter = ',',
+            requires = "compose",
+            value_name = "TRANSITIONS"
+        )]
+        sync: Vec<String>,
+        /// Assert that the net is 1-safe, markings with more than one token in a place are
+        /// reported as errors
+        #[clap(long)]
+        safe: bool,
+        /// Apply structural reductions preserving deadlocks before exploring the statespace
+        /// with --analyse, the liveness of transitions and home markings are then not reported
+        #[clap(long)]
+        reduce: bool,
+        /// Step through the net interactively, reading commands from stdin
+        #[clap(long)]
+        simulate: bool,
+        /// Print the net or its reachability graph in dot format
+        #[clap(long, value_enum, value_name = "GRAPH")]
+        dot: Option<DotGraph>,
+        /// Most markings drawn in the reachability graph with --dot reachability
+        #[clap(long, default_value_t = 1000)]
+        dot_limit: usize,
+        /// Print the incidence matrix, the change of tokens in each place by each transition
+        #[clap(long)]
+        incidence: bool,
+        /// Print the mean number of tokens in each place and the throughput of each transition
+        /// in the long run, firing transitions with the rates of the net or 1
+        #[clap(long)]
+        steady_state: bool,
+        /// Write the Markov chain of the reachable markings to the file in PRISM's explicit
+        /// transition format
+        #[clap(long, value_name = "FILE")]
+        ctmc: Option<OsString>,
+        /// Write the net and its LTL properties given with --ltl to the file as an SMV model, for
+        /// cross-checking the verdicts with NuSMV or nuXmv. The net has to be bounded.
+        #[clap(long, value_name = "FILE")]
+        smv: Option<OsString>,
+        /// Print the most tokens each place can hold, also for unbounded nets
+        #[clap(long)]
+        bounds: bool,
+        /// Check whether a reachable marking covers the query, a comma separated list of lower
+        /// bounds such as 'p1>=2, p3>=1'. Also works for unbounded nets.
+        #[clap(long, value_name = "QUERY")]
+        cover: Option<String>,
+        /// Answer the query about the reachable markings, such as 'count where p3>0 and deadlock',
+        /// 'exists marking where p1+p2>4' or 'forall where not deadlock'. Conditions compare sums
+        /// of places and numbers and combine them with 'and', 'or' and 'not'. The markings are
+        /// explored once for all queries, so the net has to be bounded.
+        #[clap(long, value_name = "QUERY")]
+        query: Vec<String>,
+        /// Search a firing sequence reaching the marking, a comma separated list of token counts
+        /// such as 'p1=2, p3=1' where other places hold no tokens
+        #[clap(long, value_name = "MARKING")]
+        reach: Option<String>,
+        /// Explore only stubborn sets of transitions, which preserves deadlocks and, if no
+        /// fairness is assumed, next-free LTL properties
+        #[clap(long)]
+        por: bool,
+        /// Verify the petri net against the LTL properties in the file, either in the MCC XML
+        /// format or in plain text with one formula per line, '-' reads them from stdin
         #[clap(short, long)]
         ltl: Option<OsString>,
+        #[clap(flatten)]
+        selection: PropertySelection,
+        #[clap(flatten)]
+        net_checking: NetChecking,
+        /// Report LTL properties which are contradictions or only vacuously satisfied by the net
+        #[clap(
+            long,
+            requires = "ltl",
+            conflicts_with_all = &["mcc", "results", "cex-out", "witness-out"]
+        )]
+        vacuity: bool,
+        /// Print the sizes of the automata and products built for each LTL property with the
+        /// time and peak memory of each stage, also added to --results. Counts the strongly
+        /// connected components of the products, which explores them entirely.
+        #[clap(long, requires = "ltl", conflicts_with_all = &["vacuity", "mcc"])]
+        report: bool,
+        /// Print the outcome as text or as a single JSON document, which leaves out --simulate,
+        /// --dot, --incidence, --steady-state, --vacuity and --mcc
+        #[clap(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+        /// Keep the automata of the formulas, the net reduced by --reduce and the markings
+        /// explored by the LTL checks in the directory, reusing them when run again on the same
+        /// net
+        #[clap(long, value_name = "DIR")]
+        cache: Option<PathBuf>,
+        /// Check the LTL properties again whenever the file of the net or of the properties
+        /// changes, until interrupted. Verdicts are kept while the places, transitions, arcs and
+        /// initial marking of the net stay the same, so only new and edited properties are
+        /// checked then, and the automata of unchanged properties are reused.
+        #[clap(
+            long,
+            requires = "ltl",
+            conflicts_with_all = &[
+                "analyse", "simulate", "dot", "incidence", "steady-state", "ctmc", "smv", "bounds",
+                "cover", "query", "reach", "results", "cex-out", "witness-out",
+            ]
+        )]
+        watch: bool,
+    },
+    /// Check whether a Petri net or a Kripke structure satisfies the LTL properties in a file,
+    /// with the options of `lmc petri` for checking properties of nets
+    Check {
+        /// Petri net in PNML, the TINA .net format or LoLA's format, or a Kripke structure in the
+        /// .kts format, detected by the extension. '-' reads a net from stdin.
+        model: OsString,
+        /// LTL properties in the MCC XML format or in plain text with one formula per line, '-'
+        /// reads them from stdin
+        properties: OsString,
+        #[clap(flatten)]
+        selection: PropertySelection,
+        #[clap(flatten)]
+        net_checking: NetChecking,
+        /// Threads checking the properties of nets, 0 uses one per core
+        #[clap(short, long, default_value_t = 1)]
+        jobs: usize,
+        /// Explore only stubborn sets of transitions for next-free properties of nets
+        #[clap(long)]
+        por: bool,
+    },
+    /// Step through the states of a model interactively, by commands read from stdin, listing the
+    /// edges leaving each state and evaluating atomic propositions in it
+    Explore {
+        /// Petri net in PNML, the TINA .net format or LoLA's format, a Kripke structure in the
+        /// .kts format or a Büchi automaton in HOA format, detected by the extension
+        model: OsString,
+    },
+    /// Run the verification jobs described in a TOML file and report their outcomes together
+    ///
+    /// Each [[job]] table of the file either checks the properties of a model like `lmc check`,
+    /// given by the keys 'model' and 'properties', or solves the parity game of the key 'game'
+    /// with an 'algorithm'. Checking jobs may set 'name', 'syntax', 'property' for a list of ids,
+    /// 'engine', 'depth', 'threads' for --jobs, 'por', 'fair_weak' and 'fair_strong'. The keys
+    /// 'timeout' in seconds and 'max_memory' in megabytes may be given at the top of the file or
+    /// for a job. Like --timeout and --max-memory they hold for each job on its own. Paths are
+    /// relative to the file.
+    Run {
+        /// TOML file with a [[job]] table for each job, '-' reads it from stdin
+        file: OsString,
+        /// Print the report as an aligned table or as a single JSON document
+        #[clap(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Confirm counterexamples written by --cex-out by firing their transitions in the net
+    Replay {
+        /// File containing the net the counterexamples were found for
+        file: OsString,
+        /// Counterexamples in JSON as written by --cex-out
+        counterexamples: OsString,
+        /// Only replay the counterexamples of the properties with the given ids
+        #[clap(long, value_delimiter = ',')]
+        property: Vec<String>,
+    },
+    /// Check the certificates written by --witness-out against the net
+    Audit {
+        /// File containing the net the certificates were written for
+        file: OsString,
+        /// Certificates in JSON as written by --witness-out
+        witnesses: OsString,
+        /// Only audit the certificates of the properties with the given ids
+        #[clap(long, value_delimiter = ',')]
+        property: Vec<String>,
+    },
+    /// Verify an explicit Kripke structure against LTL properties
+    Kripke {
+        /// File in the .kts format, listing the states with the propositions holding in them,
+        /// the initial states and the edges, '-' reads it from stdin
+        file: OsString,
+        /// LTL properties in the MCC XML format or in plain text with one formula per line, '-'
+        /// reads them from stdin
+        #[clap(short, long)]
+        ltl: OsString,
+        #[clap(flatten)]
+        selection: PropertySelection,
     },
     /// Operate on LTL formulas
-    LTL {
+    Ltl {
         /// LTL formulas in prefix notation, for example '& a b' or '| X a G b'
         formula: String,
+        /// Syntax of the formula
+        #[clap(long, value_enum, default_value_t = Syntax::Prefix)]
+        syntax: Syntax,
         #[clap(short, long)]
         /// Convert the LTL formulas to PNF form
         pnf: bool,
@@ -61,116 +339,895 @@ enum Commands {
         /// Create a dot file for viewing the generated GNBA
         #[clap(short, long)]
         dot: bool,
+        /// Construction used to translate the formula into a GNBA
+        #[clap(long, value_enum, default_value_t = Translation::OnTheFly)]
+        translation: Translation,
+        /// Search for a lasso shaped model with at most k + 1 positions using a SAT solver
+        #[clap(short, long, value_name = "K")]
+        bmc: Option<usize>,
+        /// Check whether the formula is a tautology, a contradiction or contains irrelevant
+        /// occurrences of propositions
+        #[clap(long)]
+        vacuity: bool,
+        /// Print structural metrics of the formula such as its size and temporal depth
+        #[clap(long)]
+        info: bool,
+        /// Generate a deterministic monitor detecting bad prefixes of a safety formula or good
+        /// prefixes of a co-safety formula
+        #[clap(long, value_enum, value_name = "FORMAT")]
+        monitor: Option<MonitorFormat>,
+        /// Report the subformulas violated by a counterexample, given as lasso such as
+        /// "{a}, ({a, b}, {})ʷ"
+        #[clap(long, value_name = "TRACE")]
+        explain: Option<String>,
+        /// Print the outcome as text or as a single JSON document, which leaves out --dot
+        #[clap(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
     },
     Parity {
-        /// Parity game file to parse
+        /// Parity game file to parse, '-' reads the game from stdin
         file: OsString,
-        /// Print the vertices won by each player to stdout
+        /// Print the vertices won by each player
         #[clap(short, long)]
         regions: bool,
-        /// Print the strategy derived for the input to stdout
+        /// Print the strategy derived for the input
         #[clap(short, long)]
         strategy: bool,
+        /// Print the strategy for other tools instead of in PGSolver's format, implies --strategy
+        #[clap(long, value_enum, value_name = "FORMAT")]
+        strategy_format: Option<StrategyFormat>,
         /// Which algorithm to use to solve the parity game
         #[clap(short, long)]
         #[clap(value_enum)]
         algorithm: Option<Algorithm>,
-        /// Write the strategy to the given file
+        /// Print the regions and the strategy as text or as a single JSON document
+        #[clap(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Compare Büchi automata in HOA format
+    Buchi {
+        /// Check whether every word accepted by the first automaton is accepted by the second one
+        #[clap(long, number_of_values = 2, value_names = &["A", "B"])]
+        include: Option<Vec<OsString>>,
+        /// Check whether both automata accept the same words
+        #[clap(
+            long,
+            number_of_values = 2,
+            value_names = &["A", "B"],
+            conflicts_with = "include"
+        )]
+        equivalent: Option<Vec<OsString>>,
+    },
+    /// Convert nets between PNML and TINA, automata between HOA and never claims, both into DOT,
+    /// and normalize parity games by compressing their priorities and numbering their vertices
+    /// densely
+    Convert {
+        /// Net in PNML, the TINA .net format or LoLA's format, Büchi automaton in HOA format or as
+        /// never claim, or parity game in PGSolver's format, detected by the extension or the
+        /// content. '-' reads it from stdin.
+        file: OsString,
+        /// Format to write the model in
+        #[clap(long, value_enum)]
+        to: convert::Target,
+    },
+    /// Synthesize a controller from an LTL specification
+    Synth {
+        /// LTL specification in prefix notation, for example 'G | !r F g'
+        #[clap(required_unless_present = "tlsf")]
+        formula: Option<String>,
+        /// Syntax of the formula
+        #[clap(long, value_enum, default_value_t = Syntax::Prefix)]
+        syntax: Syntax,
+        /// Read the specification and the partition of the propositions from a TLSF file with
+        /// Mealy semantics
+        #[clap(long, conflicts_with = "formula")]
+        tlsf: Option<OsString>,
+        /// Atomic propositions controlled by the environment
+        #[clap(short, long, value_delimiter = ',')]
+        inputs: Vec<String>,
+        /// Atomic propositions controlled by the system
+        #[clap(short, long, value_delimiter = ',')]
+        outputs: Vec<String>,
+        /// Maximal number of visits to rejecting states in bounded synthesis, which finds small
+        /// controllers, before the specification is determinized
+        #[clap(short, long, default_value_t = 4)]
+        bound: usize,
+        /// Which algorithm to use to solve the synthesis games
+        #[clap(short, long)]
+        #[clap(value_enum)]
+        algorithm: Option<Algorithm>,
+        /// Print the controller in dot format instead of HOA
         #[clap(short, long)]
-        target: Option<OsString>,
+        dot: bool,
     },
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Syntax {
+    /// Prefix notation, for example 'G | !r F g'
+    Prefix,
+    /// Infix notation as used by Spot, for example 'G (r -> F g)'
+    Spot,
+}
+
+impl Syntax {
+    fn parse(&self, formula: &str) -> Result<Formula, ltl::error::Error> {
+        match self {
+            Syntax::Prefix => Formula::parse(formula),
+            Syntax::Spot => ltl::spot::parse(formula),
+        }
+    }
+}
+
+/// Which of the LTL properties in a file are checked, shared by the commands checking them
+#[derive(Args)]
+struct PropertySelection {
+    /// Syntax of the formulas in plain text property files
+    #[clap(long, value_enum, default_value_t = Syntax::Prefix)]
+    syntax: Syntax,
+    /// Only check the properties with the given ids
+    #[clap(long, value_delimiter = ',')]
+    property: Vec<String>,
+}
+
+/// How the LTL properties of nets are checked and where their verdicts go, shared by
+/// `lmc petri --ltl` and `lmc check`
+#[derive(Args)]
+struct NetChecking {
+    /// How the statespace of nets is explored, bmc and cegar only check their LTL properties
+    #[clap(long, value_enum, default_value_t = Engine::Explicit)]
+    engine: Engine,
+    /// Most steps of the counterexamples searched by --engine bmc
+    #[clap(long, default_value_t = 20)]
+    depth: usize,
+    /// Only consider runs of the net which fire each of the transitions infinitely often if it
+    /// is enabled from some point on (weak fairness)
+    #[clap(long, value_delimiter = ',', value_name = "TRANSITIONS")]
+    fair_weak: Vec<String>,
+    /// Only consider runs of the net which fire each of the transitions infinitely often if it
+    /// is enabled infinitely often (strong fairness)
+    #[clap(long, value_delimiter = ',', value_name = "TRANSITIONS")]
+    fair_strong: Vec<String>,
+    /// Print the verdicts of the LTL properties as the answer lines of the Model Checking
+    /// Contest, 'FORMULA <id> TRUE|FALSE TECHNIQUES ...' or 'FORMULA <id> CANNOT_COMPUTE',
+    /// instead of the properties with their counterexamples
+    #[clap(long)]
+    mcc: bool,
+    /// Write the verdicts of the LTL properties with the time and states spent on each and
+    /// their counterexamples to the file in JSON
+    #[clap(long, value_name = "FILE")]
+    results: Option<OsString>,
+    /// Write the counterexamples of the violated LTL properties to the file in JSON, with the
+    /// fired transitions and the markings they lead to, which `lmc replay` confirms
+    #[clap(long, value_name = "FILE")]
+    cex_out: Option<OsString>,
+    /// Write certificates of the LTL properties which hold to the file in JSON, which
+    /// `lmc audit` checks. Invariants are certified by the reachable markings, other
+    /// properties by the strongly connected components of the product with the automaton of
+    /// their negation, which are explored entirely.
+    #[clap(long, value_name = "FILE")]
+    witness_out: Option<OsString>,
+}
+
+impl NetChecking {
+    /// Whether an option differs from its default, which only matters for nets
+    fn is_set(&self) -> bool {
+        !matches!(self.engine, Engine::Explicit) || self.has_ltl_options()
+    }
+
+    /// Whether an option differs from its default which only matters for LTL checks of nets
+    fn has_ltl_options(&self) -> bool {
+        !self.fair_weak.is_empty()
+            || !self.fair_strong.is_empty()
+            || self.mcc
+            || self.results.is_some()
+            || self.cex_out.is_some()
+            || self.witness_out.is_some()
+    }
+
+    /// Files the verdicts are written to
+    fn outputs(&self) -> Outputs<'_> {
+        Outputs {
+            results: self.results.as_ref(),
+            counterexamples: self.cex_out.as_ref(),
+            witnesses: self.witness_out.as_ref(),
+        }
+    }
+}
+
+/// Outcome of a command, the exit code of the process
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    /// Every property holds, or the command does not check any
+    Holds = 0,
+    /// A property is violated, which outweighs unknown ones
+    Violated = 1,
+    /// Not every property was decided, for example after --timeout
+    Unknown = 2,
+}
+
+impl Status {
+    /// Exit code of invalid inputs and other errors
+    const ERROR: u8 = 3;
+
+    fn of(verdicts: impl IntoIterator<Item = Verdict>) -> Status {
+        let verdicts = verdicts.into_iter().map(|verdict| match verdict {
+            Verdict::Holds => Status::Holds,
+            Verdict::Violated => Status::Violated,
+            Verdict::Unknown => Status::Unknown,
+        });
+        verdicts.fold(Status::Holds, Status::max)
+    }
+
+    /// Combine the outcomes of two parts of a command
+    fn max(self, other: Status) -> Status {
+        match (self, other) {
+            (Status::Violated, _) | (_, Status::Violated) => Status::Violated,
+            (Status::Unknown, _) | (_, Status::Unknown) => Status::Unknown,
+            _ => Status::Holds,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    /// A single JSON document on stdout, see `output`
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Engine {
+    /// Breadth first search storing every reachable marking
+    Explicit,
+    /// Sets of markings as binary decision diagrams, for very large statespaces
+    Symbolic,
+    /// Bounded model checking of the LTL properties with a SAT solver, which finds short
+    /// counterexamples in huge nets but cannot show that a property holds
+    Bmc,
+    /// Abstraction of the token counts refined on spurious counterexamples, for the LTL
+    /// properties of nets with many tokens
+    Cegar,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum DotGraph {
+    /// Places and transitions of the net
+    Net,
+    /// Markings reachable from the initial one
+    Reachability,
+}
+
 #[derive(clap::ValueEnum, Clone, Copy)]
+enum Translation {
+    /// Tableau construction which only creates reachable states
+    OnTheFly,
+    /// Enumerate every elementary set of the closure of the formula
+    Powerset,
+    /// Alternating automaton of the subformulas, made non-deterministic by Miyano-Hayashi
+    Alternating,
+}
+
+impl Translation {
+    fn gnba(&self, formula: &Formula) -> Buchi {
+        match self {
+            Translation::OnTheFly => ltl_to_gnba(formula),
+            Translation::Powerset => ltl_to_gnba_powerset(formula),
+            Translation::Alternating => ltl_to_nba_alternating(formula),
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum StrategyFormat {
+    /// The 'paritysol' solution format of PGSolver
+    Pgsolver,
+    /// The header 'vertex,winner,next' and a row per vertex
+    Csv,
+    /// An array of the choices in the strategy of --format json
+    Json,
+    /// The game with the winning regions and the strategy for Graphviz
+    Dot,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum MonitorFormat {
+    Hoa,
+    Dot,
+    /// Standalone Rust source code
+    Rust,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum Algorithm {
-    FPI,
+    Fpi,
     Zielonka,
     Tangle,
-    SPM,
+    Spm,
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+impl Algorithm {
+    fn solver(&self) -> Solver {
+        match self {
+            Algorithm::Fpi => Graph::fpi,
+            Algorithm::Zielonka => Graph::zielonka,
+            Algorithm::Tangle => Graph::tangle,
+            Algorithm::Spm => Graph::spm,
+        }
+    }
+}
 
-    if cli.verbose {
-        env_logger::Builder::from_env(Env::default().default_filter_or("debug")).init();
-    } else {
-        env_logger::init();
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            // Help and version are printed like errors
+            return match e.use_stderr() {
+                true => ExitCode::from(Status::ERROR),
+                false => ExitCode::SUCCESS,
+            };
+        }
+    };
+    match lmc(&cli) {
+        Ok(status) => ExitCode::from(status as u8),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(Status::ERROR)
+        }
     }
+}
+
+fn lmc(cli: &Cli) -> Result<Status> {
+    logging::init(cli.verbose, cli.quiet, cli.log_format)?;
+
+    let mut out: Box<dyn io::Write> = match &cli.output {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("Could not create {}", path.to_string_lossy())
+            })?))
+        }
+        None => Box::new(io::stdout()),
+    };
+    let graceful = matches!(
+        cli.command,
+        Commands::Petri { .. } | Commands::Check { .. } | Commands::Run { .. }
+    );
+    limits::handle_interrupts(graceful)?;
+    // Jobs are watched one by one, each with its own limits
+    let _watch = (!matches!(cli.command, Commands::Run { .. }))
+        .then(|| limits::watch(cli.limits(), graceful));
+    let status = run(cli, &mut out)?;
+    out.flush()?;
+    Ok(status)
+}
 
+/// Run the command, printing to `out`
+fn run(cli: &Cli, out: &mut dyn io::Write) -> Result<Status> {
+    let mut status = Status::Holds;
     match &cli.command {
-        Commands::Petri { file, analyse, ltl } => {
+        Commands::Petri {
+            file,
+            analyse,
+            jobs,
+            hash_compaction,
+            disk,
+            disk_memory,
+            max_states,
+            compose,
+            sync,
+            safe,
+            reduce,
+            simulate,
+            dot,
+            dot_limit,
+            incidence,
+            steady_state,
+            ctmc,
+            smv,
+            bounds,
+            cover,
+            query,
+            reach,
+            por,
+            ltl,
+            selection: PropertySelection { syntax, property },
+            net_checking,
+            vacuity,
+            report,
+            format,
+            cache,
+            watch,
+        } => {
+            let json = *format == Format::Json;
+            if json && (*simulate || dot.is_some() || *incidence || *steady_state) {
+                bail!("--simulate, --dot, --incidence and --steady-state only print text");
+            }
+            if *simulate && file == "-" {
+                bail!(
+                    "--simulate reads its commands from stdin, the net has to be read from a file"
+                );
+            }
+            if (!property.is_empty() || net_checking.has_ltl_options()) && ltl.is_none() {
+                bail!("--property, --mcc, the fairness options and the outputs of verdicts need --ltl");
+            }
+            let cache = match cache {
+                Some(directory) => Some(Cache::new(directory).with_context(|| {
+                    format!("Could not use '{}' as cache", directory.display())
+                })?),
+                None => None,
+            };
+            let checking = Checking {
+                engine: net_checking.engine,
+                depth: net_checking.depth,
+                por: *por,
+                jobs: *jobs,
+                fairness: fairness(&net_checking.fair_weak, &net_checking.fair_strong),
+                vacuity: *vacuity,
+                mcc: net_checking.mcc,
+                report: *report,
+                print: !json,
+                witnesses: net_checking.witness_out.is_some(),
+                cache: cache.as_ref(),
+            };
+            if let (true, Some(properties)) = (*watch, ltl) {
+                if json
+                    || [file, properties]
+                        .into_iter()
+                        .chain(compose)
+                        .any(|f| f == "-")
+                {
+                    bail!("--watch prints text and reads the net and the properties from files");
+                }
+                let watched = watch::Watched {
+                    net: file,
+                    compose,
+                    sync,
+                    safe: *safe,
+                    properties,
+                    syntax: *syntax,
+                    ids: property,
+                };
+                return watch::watch(out, &watched, &checking);
+            }
+            let net = read_petri(file, compose, sync, *safe)?;
+            let mut document = PetriDocument {
+                net: file.to_string_lossy().into(),
+                ..Default::default()
+            };
             if *analyse {
-                println!("-- Analysing PNML file '{}'", file.to_string_lossy());
-                analyse_petri_net(&file)?;
+                if let Engine::Bmc | Engine::Cegar = net_checking.engine {
+                    bail!(
+                        "The bmc and cegar engines only check LTL properties, not the statespace"
+                    );
+                }
+                if !json {
+                    writeln!(out, "-- Analysing net file '{}'", file.to_string_lossy())?;
+                }
+                let analysis = Analysis {
+                    engine: net_checking.engine,
+                    reduce: *reduce,
+                    por: *por,
+                    jobs: *jobs,
+                    hash_compaction: *hash_compaction,
+                    disk: disk.as_deref().map(|dir| (dir, *disk_memory << 20)),
+                    max_states: *max_states,
+                    max_memory: cli.max_memory.map(|mb| mb << 20),
+                    cache: cache.as_ref(),
+                };
+                let statespace = analyse_petri_net(net.clone(), &analysis)?;
+                if statespace.stopped.is_some() {
+                    status = Status::Unknown;
+                }
+                match json {
+                    true => document.statespace = Some(statespace),
+                    false => print_statespace(out, &statespace)?,
+                }
             }
 
-            if let Some(path) = ltl {
-                let file_content = fs::read_to_string(path)?;
-                let formulas = ltl::xml::parse(&file_content);
-                let net = read_petri(file)?;
-                // gnba of the petri net
-                let _gnba = petri_to_gnba(net);
-                match formulas {
-                    Some(formulas) => {
-                        for (id, f) in formulas {
-                            println!("{}: '{}'", id, f);
-                            println!("{}", ltl_to_gnba(&f).hoa());
+            if *simulate {
+                simulate_petri_net(out, &net)?;
+            }
+
+            match dot {
+                Some(DotGraph::Net) => write!(out, "{}", net.to_dot())?,
+                Some(DotGraph::Reachability) => {
+                    write!(out, "{}", net.reachability_graph_dot(*dot_limit)?)?
+                }
+                None => {}
+            }
+
+            if *incidence {
+                print_incidence(out, &net)?;
+            }
+
+            if *steady_state || ctmc.is_some() {
+                let chain = net.ctmc()?;
+                if let Some(path) = ctmc {
+                    fs::write(path, chain.to_tra())?;
+                }
+                if *steady_state {
+                    print_steady_state(out, &net, &chain)?;
+                }
+            }
+
+            if *bounds {
+                match json {
+                    true => {
+                        let bounds = net.bounds().into_iter().map(|(place, bound)| {
+                            let bound = match bound {
+                                Bound::Finite(n) => Some(n),
+                                Bound::Unbounded => None,
+                            };
+                            (place.to_string(), bound)
+                        });
+                        document.bounds = Some(bounds.collect());
+                    }
+                    false => print_bounds(out, &net)?,
+                }
+            }
+
+            if let Some(path) = smv {
+                let properties = match ltl {
+                    Some(ltl) => read_properties(ltl, *syntax, property)?,
+                    None => vec![],
+                };
+                fs::write(path, smv::to_smv(&net, &properties)?)?;
+            }
+
+            if let Some(query) = cover {
+                let query = parse_places(query, ">=")?;
+                let coverable = net.coverable(&query)?;
+                if json {
+                    document.cover = Some(Cover {
+                        query: places_string(&query, ">="),
+                        coverable,
+                    });
+                } else if coverable {
+                    writeln!(
+                        out,
+                        "Coverable, a reachable marking satisfies '{}'",
+                        places_string(&query, ">=")
+                    )?;
+                } else {
+                    writeln!(
+                        out,
+                        "Not coverable, no reachable marking satisfies '{}'",
+                        places_string(&query, ">=")
+                    )?;
+                }
+            }
+
+            if let Some(spec) = reach {
+                let tokens = parse_places(spec, "=")?;
+                let target = net.marking(&tokens)?;
+                if json {
+                    let firings = match net.state_equation(&target) {
+                        true => net.reachable(&target)?,
+                        false => None,
+                    };
+                    document.reach = Some(Reach {
+                        marking: places_string(&tokens, "="),
+                        firings: firings.map(|f| f.into_iter().map(String::from).collect()),
+                    });
+                } else if !net.state_equation(&target) {
+                    writeln!(
+                        out,
+                        "Not reachable, the state equation for '{}' has no solution",
+                        places_string(&tokens, "=")
+                    )?;
+                } else {
+                    match net.reachable(&target)? {
+                        Some(sequence) if sequence.is_empty() => writeln!(
+                            out,
+                            "Reachable, '{}' is the initial marking",
+                            places_string(&tokens, "=")
+                        )?,
+                        Some(sequence) => writeln!(
+                            out,
+                            "Reachable by firing {}",
+                            sequence.iter().map(|t| format!("'{}'", t)).join(", ")
+                        )?,
+                        None => writeln!(
+                            out,
+                            "Not reachable, no reachable marking is '{}'",
+                            places_string(&tokens, "=")
+                        )?,
+                    }
+                }
+            }
+
+            if !query.is_empty() {
+                let markings = net.marking_index()?;
+                let answers = (query.iter())
+                    .map(|q| answer_query(&net, &markings, q))
+                    .collect::<Result<Vec<_>>>()?;
+                match json {
+                    true => document.queries = Some(answers),
+                    false => {
+                        for answer in answers {
+                            writeln!(out, "{}", answer)?;
                         }
-                        // Analyse the petri net by creating the intersection
                     }
-                    None => println!(
-                        "Could not parse formulas from file {}",
-                        path.to_string_lossy()
-                    ),
                 }
             }
+
+            if let Some(path) = ltl {
+                let formulas = read_properties(path, *syntax, property)?;
+                if json && (*vacuity || net_checking.mcc) {
+                    bail!("--vacuity and --mcc only print text");
+                }
+                let outputs = net_checking.outputs();
+                let summary = verify_net(out, &net, file, &formulas, &checking, &outputs)?;
+                status = status.max(Status::of(summary.properties.iter().map(|p| p.verdict)));
+                document.properties = Some(summary.properties);
+            }
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&document)?)?;
+            }
+        }
+        Commands::Check {
+            model,
+            properties,
+            selection,
+            net_checking,
+            jobs,
+            por,
+        } => {
+            let formulas = read_properties(properties, selection.syntax, &selection.property)?;
+            if Path::new(model).extension().is_some_and(|e| e == "kts") {
+                if net_checking.is_set() || *por {
+                    bail!("Only the properties of Petri nets are checked with engine options or outputs");
+                }
+                status = check_kripke(out, &read_kripke(model)?, &formulas)?;
+            } else {
+                let net = read_petri(model, &[], &[], false)?;
+                let checking = Checking {
+                    engine: net_checking.engine,
+                    depth: net_checking.depth,
+                    por: *por,
+                    jobs: *jobs,
+                    fairness: fairness(&net_checking.fair_weak, &net_checking.fair_strong),
+                    vacuity: false,
+                    mcc: net_checking.mcc,
+                    report: false,
+                    print: true,
+                    witnesses: net_checking.witness_out.is_some(),
+                    cache: None,
+                };
+                let outputs = net_checking.outputs();
+                let summary = verify_net(out, &net, model, &formulas, &checking, &outputs)?;
+                status = Status::of(summary.properties.iter().map(|p| p.verdict));
+            }
+        }
+        Commands::Explore { model } => {
+            if model == "-" {
+                bail!(
+                    "explore reads its commands from stdin, the model has to be read from a file"
+                );
+            }
+            match Path::new(model).extension().and_then(|e| e.to_str()) {
+                Some("kts") => explore::repl(out, &read_kripke(model)?)?,
+                Some("hoa") => {
+                    let automaton = buchi::hoa::parse(&read_input(model)?)
+                        .with_context(|| format!("Could not parse {}", model.to_string_lossy()))?;
+                    explore::repl(out, &automaton)?
+                }
+                _ => explore::repl(out, &read_petri(model, &[], &[], false)?)?,
+            }
+        }
+        Commands::Run { file, format } => {
+            status = jobs::run(out, file, cli.limits(), *format)?;
+        }
+        Commands::Replay {
+            file,
+            counterexamples,
+            property,
+        } => {
+            let net = read_petri(file, &[], &[], false)?;
+            let content = read_input(counterexamples)?;
+            let counterexamples: Counterexamples =
+                serde_json::from_str(&content).with_context(|| {
+                    format!(
+                        "Could not parse counterexamples from {}",
+                        counterexamples.to_string_lossy()
+                    )
+                })?;
+            if let Some(id) = property
+                .iter()
+                .find(|id| !counterexamples.properties.iter().any(|p| &p.id == *id))
+            {
+                bail!("No counterexample for property '{}'", id);
+            }
+            let mut failed = 0;
+            for violation in counterexamples.properties {
+                if !property.is_empty() && !property.contains(&violation.id) {
+                    continue;
+                }
+                writeln!(out, "{}: '{}'", violation.id, violation.formula)?;
+                match replay::replay(&net, &violation.counterexample) {
+                    Ok(replay) => {
+                        writeln!(out, "Counterexample confirmed:")?;
+                        writeln!(out, "{}", replay.table(&net))?;
+                    }
+                    Err(e) => {
+                        writeln!(out, "Counterexample does not replay: {}", e)?;
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                bail!("{} counterexamples could not be confirmed", failed);
+            }
+        }
+        Commands::Audit {
+            file,
+            witnesses,
+            property,
+        } => {
+            let net = read_petri(file, &[], &[], false)?;
+            let content = read_input(witnesses)?;
+            let witnesses: Witnesses = serde_json::from_str(&content).with_context(|| {
+                format!(
+                    "Could not parse certificates from {}",
+                    witnesses.to_string_lossy()
+                )
+            })?;
+            if let Some(id) = property
+                .iter()
+                .find(|id| !witnesses.properties.iter().any(|p| &p.id == *id))
+            {
+                bail!("No certificate for property '{}'", id);
+            }
+            let mut failed = 0;
+            for witness in witnesses.properties {
+                if !property.is_empty() && !property.contains(&witness.id) {
+                    continue;
+                }
+                writeln!(out, "{}: '{}'", witness.id, witness.formula)?;
+                match witness::audit(&net, &witness) {
+                    Ok(()) => writeln!(out, "Certificate confirmed, the property holds")?,
+                    Err(e) => {
+                        writeln!(out, "Certificate rejected: {}", e)?;
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                bail!("{} certificates could not be confirmed", failed);
+            }
+        }
+        Commands::Kripke {
+            file,
+            ltl,
+            selection: PropertySelection { syntax, property },
+        } => {
+            let kripke = read_kripke(file)?;
+            status = check_kripke(out, &kripke, &read_properties(ltl, *syntax, property)?)?;
         }
-        Commands::LTL {
+        Commands::Ltl {
             formula,
+            syntax,
             pnf,
             satisfiable,
             nba,
             gnba,
             dot,
+            translation,
+            bmc,
+            vacuity,
+            info,
+            monitor,
+            explain,
+            format,
         } => {
-            let parsed_formula = Formula::parse(formula)?;
-            println!("Formula: '{}'", parsed_formula);
+            let parsed_formula = syntax.parse(formula)?;
+            if *format == Format::Json {
+                if *dot {
+                    bail!("--dot only prints text");
+                }
+                let document = ltl_document(
+                    &parsed_formula,
+                    &LtlOptions {
+                        pnf: *pnf,
+                        satisfiable: *satisfiable,
+                        nba: *nba,
+                        gnba: *gnba,
+                        translation: *translation,
+                        bmc: *bmc,
+                        vacuity: *vacuity,
+                        info: *info,
+                        monitor: *monitor,
+                        explain: explain.as_deref(),
+                    },
+                )?;
+                writeln!(out, "{}", serde_json::to_string_pretty(&document)?)?;
+                return Ok(Status::Holds);
+            }
+            writeln!(out, "Formula: '{}'", parsed_formula)?;
+            if *info {
+                writeln!(out, "--- Metrics ---\n{}", parsed_formula.metrics())?;
+            }
             let pnf_formula = parsed_formula.pnf();
             if *pnf {
-                println!("PNF: '{}'", pnf_formula);
+                writeln!(out, "PNF: '{}'", pnf_formula)?;
             }
 
             if *gnba || *nba || *satisfiable {
-                println!("--- Creating GNBA ---");
-                let gnba_f = ltl_to_gnba(&pnf_formula);
+                writeln!(out, "--- Creating GNBA ---")?;
+                let gnba_f = translation.gnba(&pnf_formula);
 
                 if *gnba {
-                    println!("--- Generated GNBA ---\n{}", gnba_f.hoa());
+                    writeln!(out, "--- Generated GNBA ---\n{}", gnba_f.hoa())?;
                     if *dot {
-                        println!("--- GNBA dot ---\n{}", gnba_f.to_dot());
+                        writeln!(out, "--- GNBA dot ---\n{}", gnba_f.to_dot())?;
                     }
                 }
 
                 if *nba {
-                    println!("--- Creating NBA ---");
+                    writeln!(out, "--- Creating NBA ---")?;
                     let nba_f = gnba_f.gnba_to_nba();
                     if *nba {
-                        println!("--- Generated NBA ---\n{}", nba_f.hoa());
+                        writeln!(out, "--- Generated NBA ---\n{}", nba_f.hoa())?;
                         if *dot {
-                            println!("--- NBA dot ---\n{}", nba_f.to_dot());
+                            writeln!(out, "--- NBA dot ---\n{}", nba_f.to_dot())?;
                         }
                     }
                 }
             }
             if *satisfiable {
-                println!("--- Checking Satisfiability ---");
+                writeln!(out, "--- Checking Satisfiability ---")?;
                 // Negate the formula and verify it
-                let negation = Formula::parse(&format!("!{}", formula))?;
-                let trace = ltl_to_gnba(&negation).verify();
+                let negation = parsed_formula.negation();
+                let trace = translation.gnba(&negation).verify();
                 match trace {
-                    Ok(_) => println!("False"),
-                    Err(trace) => println!("Found counterexample trace:\n{}", trace),
+                    Ok(_) => writeln!(out, "False")?,
+                    Err(trace) => writeln!(out, "Found counterexample trace:\n{}", trace)?,
+                }
+            }
+            if let Some(k) = bmc {
+                writeln!(out, "--- Bounded Satisfiability Check (k = {}) ---", k)?;
+                match ltl::bmc::satisfiable(&parsed_formula, *k) {
+                    Some(lasso) => writeln!(out, "Satisfiable, found witness:\n{}", lasso)?,
+                    None => writeln!(
+                        out,
+                        "No lasso shaped model with at most {} positions",
+                        k + 1
+                    )?,
+                }
+            }
+            if let Some(trace) = explain {
+                writeln!(out, "--- Counterexample Explanation ---")?;
+                let lasso: ltl::bmc::Lasso = trace.parse()?;
+                let failures = lasso.explain(&parsed_formula);
+                if failures.is_empty() {
+                    writeln!(out, "The trace {} satisfies the formula", lasso)?;
+                }
+                for failure in failures {
+                    let part = if lasso.in_cycle(failure.position) {
+                        "cycle"
+                    } else {
+                        "prefix"
+                    };
+                    writeln!(out, "{} ({})", failure, part)?;
+                }
+            }
+            if *vacuity {
+                writeln!(out, "--- Vacuity Check ---")?;
+                writeln!(out, "{}", vacuity::ltl_report(&parsed_formula))?;
+            }
+            if let Some(format) = monitor {
+                writeln!(out, "--- Monitor ---")?;
+                let Some(monitor) = monitor::Monitor::new(&parsed_formula) else {
+                    bail!("Monitors can only be generated for safety or co-safety formulas");
+                };
+                match format {
+                    MonitorFormat::Hoa => writeln!(out, "{}", monitor.hoa())?,
+                    MonitorFormat::Dot => writeln!(out, "{}", monitor.to_dot())?,
+                    MonitorFormat::Rust => writeln!(out, "{}", monitor.to_rust())?,
                 }
             }
         }
@@ -178,101 +1235,1156 @@ fn main() -> Result<()> {
             file,
             regions,
             strategy,
+            strategy_format,
             algorithm,
-            target,
+            format,
         } => {
-            let input = fs::read_to_string(file)?;
+            let strategy = *strategy || strategy_format.is_some();
+            if *format == Format::Json && strategy_format.is_some_and(|f| f != StrategyFormat::Json)
+            {
+                bail!("--format json writes the strategy as JSON, expected --strategy-format json");
+            }
+            let input = read_input(file)?;
             let game = parity::parse_game(&input).context("Could not parse parity game")?;
-            let algorithm = algorithm.unwrap_or(Algorithm::FPI);
-            let sol = match algorithm {
-                Algorithm::FPI => game.fpi(),
-                Algorithm::Zielonka => game.zielonka(),
-                Algorithm::Tangle => game.tangle(),
-                Algorithm::SPM => game.spm(),
-            };
+            let sol = algorithm.unwrap_or(Algorithm::Fpi).solver()(&game);
+            if *format == Format::Json {
+                let name = |m: &&MetaData| match &m.label {
+                    Some(label) => label.to_string(),
+                    None => format!("{}/{}", m.id, m.priority),
+                };
+                let region = |region: &HashSet<&MetaData>| {
+                    region.iter().sorted_by_key(|m| m.id).map(name).collect()
+                };
+                let document = ParityDocument {
+                    even_region: regions.then(|| region(&sol.even_region)),
+                    odd_region: regions.then(|| region(&sol.odd_region)),
+                    strategy: strategy.then(|| Choice::all(&sol)),
+                };
+                writeln!(out, "{}", serde_json::to_string_pretty(&document)?)?;
+                return Ok(Status::Holds);
+            }
 
             if *regions {
                 if !sol.even_region.is_empty() {
-                    println!(
+                    writeln!(
+                        out,
                         "won by even: {}",
                         sol.even_region
                             .iter()
                             .sorted_by_key(|m| m.id)
                             .map(|m| match &m.label {
-                                Some(label) => format!("{}", label),
+                                Some(label) => label.to_string(),
                                 None => format!("{}/{}", m.id, m.priority),
                             })
                             .collect_vec()
                             .join(" ")
-                    );
+                    )?;
                 }
                 if !sol.odd_region.is_empty() {
-                    println!(
+                    writeln!(
+                        out,
                         "won by odd: {}",
                         sol.odd_region
                             .iter()
                             .sorted_by_key(|m| m.id)
                             .map(|m| match &m.label {
-                                Some(label) => format!("{}", label),
+                                Some(label) => label.to_string(),
                                 None => format!("{}/{}", m.id, m.priority),
                             })
                             .collect_vec()
                             .join(" ")
-                    );
+                    )?;
+                }
+            }
+
+            if strategy {
+                match strategy_format.unwrap_or(StrategyFormat::Pgsolver) {
+                    StrategyFormat::Pgsolver => writeln!(out, "{}", sol)?,
+                    StrategyFormat::Csv => write!(out, "{}", sol.to_csv())?,
+                    StrategyFormat::Json => {
+                        writeln!(out, "{}", serde_json::to_string_pretty(&Choice::all(&sol))?)?
+                    }
+                    StrategyFormat::Dot => write!(out, "{}", game.to_dot(&sol))?,
+                }
+            }
+        }
+        Commands::Buchi {
+            include,
+            equivalent,
+        } => {
+            let (files, check): (_, fn(&Buchi, &Buchi) -> _) = match (include, equivalent) {
+                // The second automaton has to include the first one
+                (Some(files), _) => (files, |a, b| b.includes(a)),
+                (None, Some(files)) => (files, |a, b| a.equivalent(b)),
+                (None, None) => bail!("Expected --include or --equivalent"),
+            };
+            let automata = files
+                .iter()
+                .map(|path| {
+                    let automaton = buchi::hoa::parse(&read_input(path)?)
+                        .with_context(|| format!("Could not parse {}", path.to_string_lossy()))?;
+                    writeln!(out, "{}: {}", path.to_string_lossy(), automaton.stats())?;
+                    Ok(automaton)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let outcome = check(&automata[0], &automata[1]);
+            if outcome.is_err() {
+                status = Status::Violated;
+            }
+            match outcome {
+                Ok(()) if include.is_some() => writeln!(out, "Included")?,
+                Ok(()) => writeln!(out, "Equivalent")?,
+                Err(trace) if include.is_some() => writeln!(
+                    out,
+                    "Not included, only the first automaton accepts:\n{}",
+                    trace
+                )?,
+                Err(trace) => writeln!(
+                    out,
+                    "Not equivalent, only one automaton accepts:\n{}",
+                    trace
+                )?,
+            }
+        }
+        Commands::Convert { file, to } => {
+            let input = read_input(file)?;
+            write!(out, "{}", convert::convert(Path::new(file), &input, *to)?)?;
+        }
+        Commands::Synth {
+            formula,
+            syntax,
+            tlsf,
+            inputs,
+            outputs,
+            bound,
+            algorithm,
+            dot,
+        } => {
+            let (parsed_formula, inputs, outputs) = match (tlsf, formula) {
+                (Some(path), _) => {
+                    let spec = ltl::tlsf::parse(&read_input(path)?)?;
+                    if let Some(title) = &spec.title {
+                        writeln!(out, "Specification: {}", title)?;
+                    }
+                    (spec.formula, spec.inputs, spec.outputs)
+                }
+                (None, Some(formula)) => (syntax.parse(formula)?, inputs.clone(), outputs.clone()),
+                (None, None) => unreachable!("clap requires a formula or a TLSF file"),
+            };
+            writeln!(out, "Formula: '{}'", parsed_formula)?;
+            let solver = algorithm.unwrap_or(Algorithm::Fpi).solver();
+            let print = |out: &mut dyn io::Write, controller: synth::Controller| match *dot {
+                true => writeln!(out, "{}", controller.to_dot()),
+                false => writeln!(out, "{}", controller.hoa()),
+            };
+            match synth::synthesize(&parsed_formula, &inputs, &outputs, *bound, solver)? {
+                Synthesis::Realizable(controller) => {
+                    writeln!(out, "realizable")?;
+                    print(out, controller)?;
+                }
+                Synthesis::Unrealizable(counter_strategy) => {
+                    writeln!(out, "unrealizable")?;
+                    print(out, counter_strategy)?;
+                    status = Status::Violated;
                 }
             }
+        }
+    }
+
+    Ok(status)
+}
+
+/// Read the properties with the given ids from the file, all of them if no ids are given
+fn read_properties(
+    path: &OsString,
+    syntax: Syntax,
+    ids: &[String],
+) -> Result<Vec<(String, Formula)>> {
+    let content = read_input(path)?;
+    // CTL properties of the MCC quantify paths below the root of the formula
+    if content.contains("<exists-path>")
+        || content.matches("<all-paths>").count() > content.matches("<property>").count()
+    {
+        bail!(
+            "{} contains CTL properties, only LTL properties are checked",
+            path.to_string_lossy()
+        );
+    }
+    let properties = if content.trim_start().starts_with('<') {
+        ltl::xml::parse(&content)
+    } else {
+        ltl::text::parse(&content, |f| syntax.parse(f))
+    }
+    .with_context(|| format!("Could not parse properties from {}", path.to_string_lossy()))?;
+
+    if let Some(id) = ids
+        .iter()
+        .find(|id| !properties.iter().any(|p| &p.0 == *id))
+    {
+        bail!("No property with id '{}' in {}", id, path.to_string_lossy());
+    }
+    Ok(properties
+        .into_iter()
+        .filter(|(id, _)| ids.is_empty() || ids.contains(id))
+        .collect())
+}
+
+/// Read the file, or stdin if the path is '-'. Only a single input is read from stdin.
+fn read_input(path: &OsStr) -> Result<String> {
+    static STDIN_READ: AtomicBool = AtomicBool::new(false);
+    if path != "-" {
+        return fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.to_string_lossy()));
+    }
+    if STDIN_READ.swap(true, Ordering::SeqCst) {
+        bail!("Only a single input can be read from stdin");
+    }
+    io::read_to_string(io::stdin()).context("Could not read stdin")
+}
+
+fn read_petri(
+    path: &OsString,
+    compose: &[OsString],
+    sync: &[String],
+    safe: bool,
+) -> Result<PetriNet> {
+    let read = |path: &OsString| -> Result<PetriNet> {
+        let _span = tracing::info_span!("read", file = %path.to_string_lossy()).entered();
+        let input = read_input(path)?;
+        let net = petri::parse(&input, petri::Format::detect(Path::new(path), &input))?;
+        tracing::debug!(
+            places = net.place_names().len(),
+            transitions = net.transition_names().len(),
+            "read net"
+        );
+        Ok(net)
+    };
+    let mut net = read(path)?;
+    let sync = sync.iter().map(|t| t.as_str()).collect_vec();
+    for other in compose {
+        net = net.compose(&read(other)?, &sync)?;
+    }
+    if safe {
+        net.assert_safe()?;
+    }
+    Ok(net)
+}
+
+/// Answer the query about the reachable markings of the net
+fn answer_query(net: &PetriNet, markings: &MarkingIndex, query: &str) -> Result<QueryAnswer> {
+    let query = Query::parse(net, query)?;
+    let answer = query.answer(markings);
+    let count = match answer {
+        Answer::Count(count, _) => Some(count),
+        _ => None,
+    };
+    Ok(QueryAnswer {
+        query: query.to_string(),
+        markings: markings.len(),
+        count,
+        holds: answer.holds(),
+        marking: (answer.marking()).map(|m| net.marking_label(&markings.get(m))),
+    })
+}
+
+/// Parse a comma separated list of token counts of places such as 'p1>=2, p3>=1', where the
+/// operator separates places and counts
+fn parse_places<'a>(spec: &'a str, operator: &str) -> Result<Vec<(&'a str, usize)>> {
+    spec.split(',')
+        .map(|entry| {
+            let (place, tokens) = entry.split_once(operator).with_context(|| {
+                format!(
+                    "Expected 'place{}tokens', found '{}'",
+                    operator,
+                    entry.trim()
+                )
+            })?;
+            let tokens = tokens
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid number of tokens in '{}'", entry.trim()))?;
+            Ok((place.trim(), tokens))
+        })
+        .collect()
+}
+
+fn places_string(places: &[(&str, usize)], operator: &str) -> String {
+    places
+        .iter()
+        .map(|(place, tokens)| format!("{}{}{}", place, operator, tokens))
+        .join(", ")
+}
+
+/// How the statespace is explored by --analyse
+struct Analysis<'a> {
+    engine: Engine,
+    reduce: bool,
+    por: bool,
+    jobs: usize,
+    hash_compaction: bool,
+    /// Directory for the visited markings and bytes of successors kept in memory
+    disk: Option<(&'a Path, usize)>,
+    max_states: Option<usize>,
+    /// Bytes
+    max_memory: Option<usize>,
+    /// Where the reduced net is kept
+    cache: Option<&'a Cache>,
+}
+
+/// How the LTL properties are checked by --ltl
+struct Checking<'a> {
+    engine: Engine,
+    depth: usize,
+    por: bool,
+    jobs: usize,
+    fairness: Fairness,
+    vacuity: bool,
+    /// Whether only the answer lines of the Model Checking Contest are printed
+    mcc: bool,
+    report: bool,
+    /// Whether the checked properties are printed as text
+    print: bool,
+    /// Whether the properties which hold are certified
+    witnesses: bool,
+    cache: Option<&'a Cache>,
+}
+
+/// Property checked with the text printed for it
+struct Checked {
+    output: String,
+    /// Verdict, missing if only the vacuity was reported
+    result: Option<PropertyResult>,
+    violation: Option<Violation>,
+    witness: Option<Witness>,
+}
+
+/// What `lmc ltl` computes for a formula
+struct LtlOptions<'a> {
+    pnf: bool,
+    satisfiable: bool,
+    nba: bool,
+    gnba: bool,
+    translation: Translation,
+    bmc: Option<usize>,
+    vacuity: bool,
+    info: bool,
+    monitor: Option<MonitorFormat>,
+    explain: Option<&'a str>,
+}
 
-            if let Some(path) = target {
-                fs::write(path, sol.to_string())?;
+fn ltl_document(formula: &Formula, options: &LtlOptions) -> Result<LtlDocument> {
+    let mut document = LtlDocument {
+        formula: formula.to_string(),
+        ..Default::default()
+    };
+    if options.info {
+        document.metrics = Some(formula.metrics());
+    }
+    let pnf = formula.pnf();
+    if options.pnf {
+        document.pnf = Some(pnf.to_string());
+    }
+    if options.gnba || options.nba {
+        let gnba = options.translation.gnba(&pnf);
+        if options.gnba {
+            document.gnba = Some(gnba.hoa());
+        }
+        if options.nba {
+            document.nba = Some(gnba.gnba_to_nba().hoa());
+        }
+    }
+    if options.satisfiable {
+        let trace = options.translation.gnba(&formula.negation()).verify();
+        document.satisfiable = Some(trace.err().map(|trace| trace.to_string()));
+    }
+    if let Some(k) = options.bmc {
+        document.bmc = Some(ltl::bmc::satisfiable(formula, k));
+    }
+    if let Some(trace) = options.explain {
+        let lasso: ltl::bmc::Lasso = trace.parse()?;
+        let failures = lasso.explain(formula).into_iter().map(|failure| Violated {
+            subformula: failure.subformula.to_string(),
+            position: failure.position,
+            in_cycle: lasso.in_cycle(failure.position),
+        });
+        document.explain = Some(failures.collect());
+    }
+    if options.vacuity {
+        document.vacuity = Some(vacuity::ltl_report(formula));
+    }
+    if let Some(format) = options.monitor {
+        let Some(monitor) = monitor::Monitor::new(formula) else {
+            bail!("Monitors can only be generated for safety or co-safety formulas");
+        };
+        document.monitor = Some(match format {
+            MonitorFormat::Hoa => monitor.hoa(),
+            MonitorFormat::Dot => monitor.to_dot(),
+            MonitorFormat::Rust => monitor.to_rust(),
+        });
+    }
+    Ok(document)
+}
+
+/// Files the outcomes of the LTL properties are written to
+struct Outputs<'a> {
+    results: Option<&'a OsString>,
+    counterexamples: Option<&'a OsString>,
+    witnesses: Option<&'a OsString>,
+}
+
+fn fairness(weak: &[String], strong: &[String]) -> Fairness {
+    let mut fairness = Fairness::new();
+    for t in weak {
+        fairness.add_weak([t]);
+    }
+    for t in strong {
+        fairness.add_strong([t]);
+    }
+    fairness
+}
+
+/// Check the LTL properties of the net read from `file` and write their outcomes, which are
+/// also returned
+fn verify_net(
+    out: &mut dyn io::Write,
+    net: &PetriNet,
+    file: &OsString,
+    properties: &[(String, Formula)],
+    checking: &Checking,
+    outputs: &Outputs,
+) -> Result<Results> {
+    let bounded = matches!(checking.engine, Engine::Bmc | Engine::Cegar);
+    if bounded && (checking.vacuity || !checking.fairness.is_empty()) {
+        bail!("The bmc and cegar engines neither report vacuity nor assume fairness");
+    }
+    if checking.witnesses && (bounded || !checking.fairness.is_empty()) {
+        bail!("Witnesses are neither written by the bmc and cegar engines nor under fairness");
+    }
+    let mut summary = Results {
+        net: file.to_string_lossy().into(),
+        properties: vec![],
+    };
+    let mut counterexamples = Counterexamples {
+        net: file.to_string_lossy().into(),
+        properties: vec![],
+    };
+    let mut witnesses = Witnesses {
+        net: file.to_string_lossy().into(),
+        properties: vec![],
+    };
+    for checked in check_properties(out, net, properties, checking)? {
+        summary.properties.extend(checked.result);
+        counterexamples.properties.extend(checked.violation);
+        witnesses.properties.extend(checked.witness);
+    }
+    if let Some(path) = outputs.results {
+        fs::write(path, serde_json::to_string_pretty(&summary)?)?;
+    }
+    if let Some(path) = outputs.counterexamples {
+        fs::write(path, serde_json::to_string_pretty(&counterexamples)?)?;
+    }
+    if let Some(path) = outputs.witnesses {
+        fs::write(path, serde_json::to_string_pretty(&witnesses)?)?;
+    }
+    Ok(summary)
+}
+
+fn read_kripke(path: &OsString) -> Result<Kripke> {
+    Kripke::parse(&read_input(path)?).with_context(|| {
+        format!(
+            "Could not read Kripke structure from {}",
+            path.to_string_lossy()
+        )
+    })
+}
+
+fn check_kripke(
+    out: &mut dyn io::Write,
+    kripke: &Kripke,
+    properties: &[(String, Formula)],
+) -> io::Result<Status> {
+    let mut status = Status::Holds;
+    for (id, f) in properties {
+        writeln!(out, "{}: '{}'", id, f)?;
+        let Ok(run) = check::check_system(kripke, f);
+        match run {
+            None => writeln!(out, "Property holds")?,
+            Some(run) => {
+                writeln!(out, "Property violated, counterexample:")?;
+                print_run(out, kripke, &run)?;
+                status = Status::Violated;
             }
-            if *strategy {
-                println!("{}", sol)
+        }
+    }
+    Ok(status)
+}
+
+/// Check the properties by `jobs` threads or one per core if it is 0, printing each property
+/// once it is checked. The threads do not share the markings they explore. The checked
+/// properties are returned in their original order.
+fn check_properties(
+    out: &mut dyn io::Write,
+    net: &PetriNet,
+    properties: &[(String, Formula)],
+    checking: &Checking,
+) -> Result<Vec<Checked>> {
+    let checker = || {
+        let mut checker = Checker::new(net, checking.por).with_fairness(checking.fairness.clone());
+        if let Some(cache) = checking.cache {
+            checker = checker.with_cache(cache);
+        }
+        if checking.report {
+            checker = checker.with_components();
+        }
+        checker
+    };
+    let jobs = match checking.jobs {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        jobs => jobs,
+    };
+    let main = checker();
+    if jobs == 1 || properties.len() < 2 {
+        let mut checked = vec![];
+        for (id, f) in properties {
+            let property = check_property(net, &main, id, f, checking)?;
+            if checking.print {
+                write!(out, "{}", property.output)?;
             }
+            checked.push(property);
         }
+        main.store_explored();
+        return Ok(checked);
     }
 
-    Ok(())
+    // Threads take the next unchecked property until none is left
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let checked = thread::scope(|scope| -> Result<Vec<Checked>> {
+        let workers = (0..jobs.min(properties.len()))
+            .map(|_| {
+                let sender = sender.clone();
+                let next = &next;
+                scope.spawn(move || {
+                    let checker = checker();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::SeqCst);
+                        let Some((id, f)) = properties.get(i) else {
+                            break;
+                        };
+                        let property = check_property(net, &checker, id, f, checking);
+                        let failed = property.is_err();
+                        if sender.send((i, property)).is_err() || failed {
+                            break;
+                        }
+                    }
+                    checker.explored()
+                })
+            })
+            .collect_vec();
+        drop(sender);
+        let mut checked = (0..properties.len()).map(|_| None).collect_vec();
+        for (i, property) in receiver {
+            let property = match property {
+                Ok(property) => property,
+                Err(e) => {
+                    // Leave no property for the other threads
+                    next.store(properties.len(), Ordering::SeqCst);
+                    return Err(e);
+                }
+            };
+            if checking.print {
+                write!(out, "{}", property.output)?;
+            }
+            checked[i] = Some(property);
+        }
+        for worker in workers {
+            main.add_explored(worker.join().expect("Checking threads do not panic"));
+        }
+        Ok(checked.into_iter().flatten().collect())
+    })?;
+    main.store_explored();
+    Ok(checked)
 }
 
-fn read_petri(path: &OsString) -> petri::Result<PetriNet> {
-    let file_content = fs::read_to_string(path)?;
-    petri::from_xml(&file_content).into()
+/// Check a single property, collecting the text printed for it
+fn check_property(
+    net: &PetriNet,
+    checker: &Checker,
+    id: &str,
+    f: &Formula,
+    checking: &Checking,
+) -> Result<Checked> {
+    let _span = tracing::info_span!("check", property = id).entered();
+    let mut output = format!("{}: '{}'\n", id, f);
+    let mut checked = Checked {
+        output: String::new(),
+        result: None,
+        violation: None,
+        witness: None,
+    };
+    if checking.vacuity {
+        let report = vacuity::petri_report(net, f, &checking.fairness, checking.por)?;
+        writeln!(output, "{}", report)?;
+        checked.output = output;
+        return Ok(checked);
+    }
+    let start = SystemTime::now();
+    let outcome = match checking.engine {
+        Engine::Bmc => bmc::bmc(net, f, checking.depth).map_err(lmc::Error::from),
+        Engine::Cegar => cegar::cegar(net, f),
+        _ => checker.check(f).map_err(lmc::Error::from),
+    };
+    // Properties whose check was stopped are unknown, like those of bounded searches
+    let (outcome, stopped) = match outcome {
+        Ok(outcome) => (outcome, None),
+        Err(e) => match e {
+            lmc::Error::Net(petri::Error::Stopped(reason)) => {
+                let outcome = check::Outcome {
+                    counterexample: None,
+                    states: 0,
+                    bounded: true,
+                    stages: vec![],
+                };
+                (outcome, Some(reason))
+            }
+            e => return Err(e.into()),
+        },
+    };
+    match (&outcome.counterexample, stopped) {
+        (None, Some(reason)) => writeln!(output, "Unknown, {}", reason)?,
+        (None, None) if outcome.bounded => match checking.engine {
+            Engine::Cegar => writeln!(output, "Unknown, the abstraction was not refined enough")?,
+            _ => writeln!(output, "No violation found up to depth {}", checking.depth)?,
+        },
+        (None, None) => {
+            writeln!(output, "Property holds")?;
+            if checking.witnesses {
+                checked.witness = Some(witness::certify(net, id, f)?);
+            }
+        }
+        (Some(counterexample), _) => {
+            writeln!(output, "Property violated, counterexample:")?;
+            output.push_str(&counterexample_string(net, counterexample));
+            checked.violation = Some(Violation {
+                id: id.to_string(),
+                formula: f.to_string(),
+                counterexample: results::Run::new(net, counterexample),
+            });
+        }
+    }
+    let time = start.elapsed().unwrap();
+    let mut result = PropertyResult::new(net, id, f, time, &outcome);
+    tracing::info!(
+        verdict = ?result.verdict,
+        time = time.as_secs_f64(),
+        states = outcome.states,
+        "checked"
+    );
+    if checking.report {
+        let report = Report::new(f, &outcome);
+        write!(output, "{}", report)?;
+        result.report = Some(report);
+    }
+    if checking.mcc {
+        output = format!("{}\n", result.mcc(&checking.techniques()));
+    }
+    checked.result = Some(result);
+    checked.output = output;
+    Ok(checked)
 }
 
-fn analyse_petri_net(path: &OsString) -> Result<()> {
-    let net = read_petri(path)?;
+impl Checking<'_> {
+    /// Techniques reported to the Model Checking Contest
+    fn techniques(&self) -> Vec<&'static str> {
+        let mut techniques = match self.engine {
+            Engine::Bmc => vec!["BOUNDED_MODEL_CHECKING", "SAT_SMT"],
+            Engine::Cegar => vec!["ABSTRACTIONS", "EXPLICIT"],
+            Engine::Explicit | Engine::Symbolic => vec!["AUTOMATA_THEORY", "EXPLICIT"],
+        };
+        // Only the checker of the explicit and symbolic engines explores stubborn sets
+        let checker = matches!(self.engine, Engine::Explicit | Engine::Symbolic);
+        if self.por && self.fairness.is_empty() && checker {
+            techniques.push("STUBBORN_SETS");
+        }
+        techniques.push(match self.jobs {
+            1 => "SEQUENTIAL_PROCESSING",
+            _ => "PARALLEL_PROCESSING",
+        });
+        techniques
+    }
+}
+
+fn analyse_petri_net(mut net: PetriNet, analysis: &Analysis) -> Result<Statespace> {
+    let Analysis {
+        engine,
+        reduce,
+        por,
+        jobs,
+        hash_compaction,
+        disk,
+        max_states,
+        max_memory,
+        cache,
+    } = *analysis;
+    let _span = tracing::info_span!("analyse").entered();
+    let mut statespace = Statespace::default();
+    if reduce {
+        let reductions = match cache {
+            Some(cache) => {
+                let (reduced, reductions) = cache.reduced(&net);
+                net = reduced;
+                reductions
+            }
+            None => net.reduce().iter().map(|r| r.to_string()).collect(),
+        };
+        statespace.reductions = Some(reductions);
+    }
 
     let start = SystemTime::now();
-    // Find all possible markings
-    let mut visited = HashSet::new();
+    if let Engine::Symbolic = engine {
+        let summary = net.symbolic_analysis()?;
+        statespace.time = start.elapsed().unwrap().as_secs_f64();
+        statespace.markings = summary.markings;
+        statespace.deadlocks = summary.deadlocks;
+        statespace.bdd = Some((summary.bits, summary.nodes));
+        return Ok(statespace);
+    }
+    if let Some((directory, memory)) = disk {
+        let summary = net.disk_analysis(directory, memory)?;
+        statespace.time = start.elapsed().unwrap().as_secs_f64();
+        statespace.markings = summary.markings as u128;
+        statespace.deadlocks = summary.deadlocks as u128;
+        statespace.disk = Some((summary.layers, summary.written));
+        return Ok(statespace);
+    }
+    let limited = max_states.is_some() || max_memory.is_some();
+    if jobs != 1 && !por && !hash_compaction && !limited {
+        let markings = net.reachable_markings(jobs)?;
+        statespace.time = start.elapsed().unwrap().as_secs_f64();
+        statespace.markings = markings.len() as u128;
+        statespace.deadlocks = markings.iter().filter(|m| net.deadlock(m).unwrap()).count() as u128;
+        if !reduce {
+            statespace.liveness = Some(liveness(&net)?);
+            statespace.home_markings = Some(home_markings(&net)?);
+        }
+        return Ok(statespace);
+    }
+    // Find all possible markings, or a subset containing all deadlocks
+    let reduction = por.then(|| petri::StubbornSets::deadlocks(&net));
+    let mut visited = if hash_compaction {
+        Visited::Compacted(HashCompaction::new())
+    } else {
+        Visited::Interned(MarkingStore::new())
+    };
     let mut queue = VecDeque::new();
     queue.push_back(net.initial_marking());
-    visited.insert(net.initial_marking());
+    visited.insert(&net.initial_marking());
 
+    // Stubborn sets are empty exactly in deadlocks
+    let mut deadlock_count = 0;
+    let mut explored = 0;
+    let mut last_report = start;
+    let mut stopped = None;
+    let marking_size = std::mem::size_of::<Marking>() + net.place_names().len();
     while let Some(marking) = queue.pop_front() {
-        let next_markings = net.next_markings(&marking)?;
-        for m in next_markings {
-            if !visited.contains(&m) {
-                visited.insert(m.clone());
+        // Queued markings are not interned, estimate them by their counts
+        let memory = visited.memory() + queue.len() * marking_size;
+        if max_states.is_some_and(|max| visited.len() >= max) {
+            stopped = Some("--max-states");
+        } else if max_memory.is_some_and(|max| memory >= max) {
+            stopped = Some("--max-memory");
+        } else if let Some(reason) = ts::stopped() {
+            stopped = Some(stop_cause(reason));
+        }
+        if stopped.is_some() {
+            queue.push_front(marking);
+            break;
+        }
+        explored += 1;
+        if explored % 1024 == 0 && last_report.elapsed().unwrap() >= Duration::from_secs(1) {
+            last_report = SystemTime::now();
+            let seconds = start.elapsed().unwrap().as_secs_f64();
+            tracing::info!(
+                explored,
+                queued = queue.len(),
+                rate = (explored as f64 / seconds).round(),
+                bytes = memory,
+                "exploring"
+            );
+        }
+        let next_markings = match &reduction {
+            Some(reduction) => reduction.transitions(&marking),
+            None => net.transitions(&marking),
+        };
+        let next_markings = match next_markings {
+            Err(petri::Error::Stopped(reason)) => {
+                queue.push_front(marking);
+                explored -= 1;
+                stopped = Some(stop_cause(reason));
+                break;
+            }
+            next_markings => next_markings?,
+        };
+        if next_markings.is_empty() {
+            deadlock_count += 1;
+        }
+        for (_, m) in next_markings {
+            if visited.insert(&m) {
                 queue.push_back(m);
             }
         }
     }
 
-    let elapsed = start.elapsed().unwrap();
+    statespace.time = start.elapsed().unwrap().as_secs_f64();
+    statespace.markings = visited.len() as u128;
+    statespace.deadlocks = deadlock_count;
+    statespace.stubborn_sets = por;
+    if let Some(limit) = stopped {
+        statespace.stopped = Some(Stopped {
+            limit: limit.to_string(),
+            explored,
+            queued: queue.len(),
+        });
+    } else if !por && !reduce {
+        statespace.liveness = Some(liveness(&net)?);
+        statespace.home_markings = Some(home_markings(&net)?);
+    }
+    statespace.memory = Some(visited.memory());
+    if let Visited::Compacted(hashes) = &visited {
+        statespace.collision_probability = Some(hashes.collision_probability());
+    }
+    Ok(statespace)
+}
+
+/// Option or event which stopped an exploration
+fn stop_cause(reason: Stop) -> &'static str {
+    match reason {
+        Stop::Interrupt => "an interrupt",
+        Stop::Timeout => "--timeout",
+        Stop::Memory => "--max-memory",
+    }
+}
+
+fn print_statespace(out: &mut dyn io::Write, statespace: &Statespace) -> io::Result<()> {
+    if let Some(reductions) = &statespace.reductions {
+        writeln!(out, "Applied {} structural reductions", reductions.len())?;
+        for reduction in reductions {
+            writeln!(out, "  {}", reduction)?;
+        }
+    }
+    print_elapsed(out, Duration::from_secs_f64(statespace.time))?;
+    if let Some(stopped) = &statespace.stopped {
+        writeln!(
+            out,
+            "Stopped by {} after visiting {} markings, {} were left to explore",
+            stopped.limit, statespace.markings, stopped.queued
+        )?;
+        writeln!(
+            out,
+            "Found {} of the deadlocks among the {} explored markings",
+            statespace.deadlocks, stopped.explored
+        )?;
+    } else if statespace.stubborn_sets {
+        writeln!(
+            out,
+            "Explored {} markings with stubborn sets, {} reachable markings are deadlocks",
+            statespace.markings, statespace.deadlocks
+        )?;
+    } else {
+        writeln!(
+            out,
+            "Found {} reachable markings, out of which {} are deadlocks",
+            statespace.markings, statespace.deadlocks
+        )?;
+    }
+    if let Some((bits, nodes)) = statespace.bdd {
+        writeln!(
+            out,
+            "Encoded with {} bits per place, {} BDD nodes",
+            bits, nodes
+        )?;
+    }
+    if let Some((layers, written)) = statespace.disk {
+        writeln!(
+            out,
+            "Explored {} layers, writing {} bytes to disk",
+            layers, written
+        )?;
+    }
+    if let Some(liveness) = &statespace.liveness {
+        print_liveness(out, liveness)?;
+    }
+    if let Some(home) = &statespace.home_markings {
+        print_home_markings(out, home)?;
+    }
+    if let Some(memory) = statespace.memory {
+        writeln!(out, "Visited markings take {} bytes", memory)?;
+    }
+    if let Some(probability) = statespace.collision_probability {
+        writeln!(
+            out,
+            "Probability of missed markings due to hash collisions: at most {:e}",
+            probability
+        )?;
+    }
+    Ok(())
+}
+
+const SIMULATION_HELP: &str = "Commands:
+  <transition>   fire the transition
+  undo           return to the previous marking
+  reset          return to the initial marking
+  random [N]     fire up to N random transitions, 1 by default
+  trace          print the transitions fired so far
+  help           print this help
+  quit           end the simulation";
+
+fn simulate_petri_net(out: &mut dyn io::Write, net: &PetriNet) -> Result<()> {
+    let mut simulation = Simulation::new(net);
+    let print_state = |out: &mut dyn io::Write, simulation: &Simulation| {
+        writeln!(out, "Marking: {}", net.marking_label(simulation.marking()))?;
+        let enabled = simulation.enabled();
+        match enabled.is_empty() {
+            true => writeln!(out, "Deadlock, no transition is enabled"),
+            false => writeln!(out, "Enabled: {}", enabled.join(", ")),
+        }
+    };
+    writeln!(out, "{}", SIMULATION_HELP)?;
+    print_state(out, &simulation)?;
+    let mut seed = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_nanos() as u64;
+    for line in io::stdin().lines() {
+        let line = line?;
+        let words = line.split_whitespace().collect_vec();
+        match words.as_slice() {
+            [] => continue,
+            ["quit" | "exit"] => break,
+            ["help"] => writeln!(out, "{}", SIMULATION_HELP)?,
+            ["trace"] => writeln!(out, "{}", simulation.trace().join(", "))?,
+            ["undo"] => match simulation.undo() {
+                Some(transition) => writeln!(out, "Undid '{}'", transition)?,
+                None => writeln!(out, "Already at the initial marking")?,
+            },
+            ["reset"] => simulation.reset(),
+            ["random", steps @ ..] if steps.len() <= 1 => {
+                let steps = match steps.first().map(|s| s.parse()) {
+                    Some(Ok(steps)) => steps,
+                    Some(Err(_)) => {
+                        writeln!(out, "Invalid number of steps '{}'", steps[0])?;
+                        continue;
+                    }
+                    None => 1,
+                };
+                seed = seed.wrapping_add(1);
+                let fired = simulation.random_walk(steps, seed)?;
+                writeln!(out, "Fired {}", fired.join(", "))?;
+            }
+            [transition] => {
+                if let Err(e) = simulation.fire(transition) {
+                    writeln!(out, "{}", e)?;
+                    continue;
+                }
+            }
+            _ => {
+                writeln!(out, "Unknown command '{}', try 'help'", line.trim())?;
+                continue;
+            }
+        }
+        print_state(out, &simulation)?;
+    }
+    Ok(())
+}
+
+fn print_incidence(out: &mut dyn io::Write, net: &PetriNet) -> io::Result<()> {
+    let incidence = net.incidence();
+    let matrix = incidence.matrix();
+    let width = |labels: &[String]| labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let place_width = width(&incidence.places);
+    let column_width = width(&incidence.transitions).max(3);
+    write!(out, "{:place_width$}", "")?;
+    for transition in &incidence.transitions {
+        write!(out, "  {:>column_width$}", transition)?;
+    }
+    writeln!(out)?;
+    for (place, row) in incidence.places.iter().zip(matrix) {
+        write!(out, "{:place_width$}", place)?;
+        for change in row {
+            write!(out, "  {:>column_width$}", change)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn print_steady_state(out: &mut dyn io::Write, net: &PetriNet, chain: &Ctmc) -> Result<()> {
+    let distribution = chain.steady_state(1e-10);
+    let mut rows = vec![("Place".to_string(), "Mean tokens".to_string())];
+    for place in net.place_names() {
+        let mut mean = 0.0;
+        for (marking, p) in chain.markings.iter().zip(&distribution) {
+            mean += p * net.tokens(marking, place)? as f64;
+        }
+        rows.push((place.to_string(), format!("{:.6}", mean)));
+    }
+    rows.push((String::new(), String::new()));
+    rows.push(("Transition".to_string(), "Throughput".to_string()));
+    for transition in net.transition_names() {
+        let rate = net.rate(transition)?.unwrap_or(1.0);
+        let mut throughput = 0.0;
+        for (marking, p) in chain.markings.iter().zip(&distribution) {
+            if marking.active_transitions(net).contains(&transition) {
+                throughput += p * rate;
+            }
+        }
+        rows.push((transition.to_string(), format!("{:.6}", throughput)));
+    }
+    let width = rows
+        .iter()
+        .map(|(l, _)| l.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (label, value) in rows {
+        if label.is_empty() {
+            writeln!(out)?;
+        } else {
+            writeln!(out, "{:width$}  {}", label, value, width = width)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_bounds(out: &mut dyn io::Write, net: &PetriNet) -> io::Result<()> {
+    let bounds = net.bounds();
+    let width = bounds
+        .iter()
+        .map(|(place, _)| place.chars().count())
+        .chain([5])
+        .max()
+        .unwrap_or(0);
+    writeln!(out, "{:width$}  Bound", "Place", width = width)?;
+    for (place, bound) in bounds {
+        writeln!(out, "{:width$}  {}", place, bound, width = width)?;
+    }
+    Ok(())
+}
+
+fn liveness(net: &PetriNet) -> Result<TransitionLiveness> {
+    let liveness = net.liveness()?;
+    let with = |level: Liveness| {
+        liveness
+            .iter()
+            .filter(|(_, l)| *l == level)
+            .map(|(t, _)| t.to_string())
+            .collect_vec()
+    };
+    Ok(TransitionLiveness {
+        live: with(Liveness::Live),
+        quasi_live: with(Liveness::QuasiLive),
+        dead: with(Liveness::Dead),
+    })
+}
+
+fn print_liveness(out: &mut dyn io::Write, liveness: &TransitionLiveness) -> io::Result<()> {
+    writeln!(
+        out,
+        "Transitions: {} live, {} quasi-live, {} dead",
+        liveness.live.len(),
+        liveness.quasi_live.len(),
+        liveness.dead.len()
+    )?;
+    if !liveness.quasi_live.is_empty() {
+        writeln!(
+            out,
+            "  quasi-live but not live: {}",
+            liveness.quasi_live.join(", ")
+        )?;
+    }
+    if !liveness.dead.is_empty() {
+        writeln!(out, "  dead: {}", liveness.dead.join(", "))?;
+    }
+    Ok(())
+}
+
+/// Print the firings of the counterexample with the markings they lead to, first those
+/// leading to the cycle and then those repeated forever
+fn counterexample_string(net: &PetriNet, counterexample: &Counterexample) -> String {
+    let marking = |m: &Marking| match net.marking_label(m) {
+        label if label.is_empty() => "no tokens".to_string(),
+        label => format!("'{}'", label),
+    };
+    let mut lines = vec![
+        "  stem:".to_string(),
+        format!("    initial marking {}", marking(&net.initial_marking())),
+    ];
+    for (t, m) in &counterexample.stem {
+        lines.push(format!("    fire '{}' -> {}", t, marking(m)));
+    }
+    if counterexample.cycle.is_empty() {
+        lines.push("  loop:".into());
+        lines.push("    deadlock, no transition is enabled".into());
+    } else {
+        lines.push("  loop, repeated forever:".into());
+        for (t, m) in &counterexample.cycle {
+            lines.push(format!("    fire '{}' -> {}", t, marking(m)));
+        }
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn print_run(
+    out: &mut dyn io::Write,
+    kripke: &Kripke,
+    (stem, cycle): &Run<usize>,
+) -> io::Result<()> {
+    let state = |s: &usize| {
+        format!(
+            "'{}' {{{}}}",
+            kripke.name(*s),
+            kripke.labels(*s).iter().join(", ")
+        )
+    };
+    writeln!(out, "  stem:")?;
+    for s in stem {
+        writeln!(out, "    {}", state(s))?;
+    }
+    writeln!(out, "  loop, repeated forever:")?;
+    for s in cycle {
+        writeln!(out, "    {}", state(s))?;
+    }
+    Ok(())
+}
+
+fn home_markings(net: &PetriNet) -> Result<HomeMarkings> {
+    let home = net.home_markings()?;
+    Ok(HomeMarkings {
+        count: home.len(),
+        example: home.first().map(|m| net.marking_label(m)),
+        // The initial marking comes first if it is a home marking
+        reversible: home.first() == Some(&net.initial_marking()),
+    })
+}
+
+fn print_home_markings(out: &mut dyn io::Write, home: &HomeMarkings) -> io::Result<()> {
+    match &home.example {
+        Some(marking) => writeln!(out, "Home markings: {}, such as '{}'", home.count, marking)?,
+        None => writeln!(out, "Home markings: none")?,
+    }
+    writeln!(
+        out,
+        "Reversible: {}",
+        if home.reversible { "yes" } else { "no" }
+    )?;
+    Ok(())
+}
+
+fn print_elapsed(out: &mut dyn io::Write, elapsed: Duration) -> io::Result<()> {
     if elapsed <= Duration::from_millis(1) {
-        println!("-- Analysis took {}μs", elapsed.as_micros());
+        writeln!(out, "-- Analysis took {}μs", elapsed.as_micros())?;
     } else if elapsed <= Duration::from_secs(1) {
-        println!("-- Analysis took {}ms", elapsed.as_millis());
+        writeln!(out, "-- Analysis took {}ms", elapsed.as_millis())?;
     } else {
-        println!("-- Analysis took {}s", elapsed.as_secs_f64());
+        writeln!(out, "-- Analysis took {}s", elapsed.as_secs_f64())?;
     }
-
-    let deadlock_count = visited.iter().filter(|m| net.deadlock(&m).unwrap()).count();
-    println!(
-        "Found {} reachable markings, out of which {} are deadlocks",
-        visited.len(),
-        deadlock_count
-    );
     Ok(())
 }
@@ -0,0 +1,356 @@
+// Deterministic monitors for safety and co-safety formulas
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+
+use itertools::Itertools;
+use ltl::alphabet::ApSet;
+use ltl::Formula;
+
+use crate::transform::IndexedNba;
+
+/// Verdict of a monitor after reading a bad prefix of the monitored formula
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    /// Every continuation violates the formula
+    Violated,
+    /// Every continuation satisfies the formula
+    Satisfied,
+}
+
+/// Deterministic automaton reading valuations of the propositions of a formula, state 0 is the
+/// initial state
+pub struct Monitor {
+    formula: Formula,
+    aps: ApSet,
+    /// Verdict once the bad prefix has been read
+    conclusion: Verdict,
+    /// State reached after a bad prefix, there is none if the monitored formula is valid
+    bad: Option<usize>,
+    /// Successor of every state for every valuation, the i-th bit of a valuation is the value
+    /// of the i-th proposition
+    transitions: Vec<Vec<usize>>,
+}
+
+impl Monitor {
+    /// Monitor for a formula in the safety or co-safety fragment, `None` for other formulas
+    ///
+    /// A prefix is bad if it has no continuation satisfying the formula. Once the states of the
+    /// NBA without an accepting continuation are removed, a prefix is bad iff there is no run on
+    /// it, so the subset construction detects bad prefixes by reaching the empty set. Co-safety
+    /// formulas are monitored through their negation, whose bad prefixes are good prefixes of the
+    /// formula.
+    pub fn new(formula: &Formula) -> Option<Self> {
+        let fragments = formula.metrics().fragments;
+        let (monitored, conclusion) = if fragments.safety {
+            (formula.clone(), Verdict::Violated)
+        } else if fragments.guarantee {
+            (formula.negation(), Verdict::Satisfied)
+        } else {
+            return None;
+        };
+
+        let nba = IndexedNba::from_formula(&monitored);
+        let productive = productive_states(&nba);
+
+        let initial: BTreeSet<usize> = nba
+            .initial_states
+            .iter()
+            .copied()
+            .filter(|q| productive[*q])
+            .collect();
+        let mut index = HashMap::from([(initial.clone(), 0)]);
+        let mut subsets = vec![initial];
+        let mut transitions = vec![];
+        while transitions.len() < subsets.len() {
+            let subset = subsets[transitions.len()].clone();
+            let mut successors = vec![];
+            // Valuations are enumerated in the order of their bits
+            for valuation in nba.aps.valuations() {
+                let target: BTreeSet<usize> = subset
+                    .iter()
+                    .flat_map(|q| &nba.edges[*q])
+                    .filter(|(literals, _)| literals.holds(valuation))
+                    .map(|(_, target)| *target)
+                    .filter(|q| productive[*q])
+                    .collect();
+                let next = index.len();
+                let id = *index.entry(target.clone()).or_insert_with(|| {
+                    subsets.push(target);
+                    next
+                });
+                successors.push(id);
+            }
+            transitions.push(successors);
+        }
+
+        Some(Monitor {
+            formula: formula.clone(),
+            aps: nba.aps,
+            conclusion,
+            bad: index.get(&BTreeSet::new()).copied(),
+            transitions,
+        })
+    }
+
+    pub fn states(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Transform the monitor into a HOA formatted string, the state reached after a bad prefix
+    /// has no successors so exactly the words without bad prefix are accepted
+    pub fn hoa(&self) -> String {
+        let mut out = String::new();
+        writeln!(&mut out, "HOA: v1").unwrap();
+        writeln!(&mut out, "name: \"{}\"", self.name()).unwrap();
+        writeln!(&mut out, "States: {}", self.states()).unwrap();
+        writeln!(&mut out, "Start: 0").unwrap();
+        writeln!(
+            &mut out,
+            "AP: {}{}",
+            self.aps.len(),
+            self.aps.iter().map(|ap| format!(" \"{}\"", ap)).join("")
+        )
+        .unwrap();
+        writeln!(&mut out, "acc-name: all\nAcceptance: 0 t").unwrap();
+        // The state reached after a bad prefix has no successors
+        let complete = if self.bad.is_none() { " complete" } else { "" };
+        writeln!(&mut out, "properties: deterministic{}", complete).unwrap();
+        writeln!(&mut out, "--BODY--").unwrap();
+        for (state, successors) in self.transitions.iter().enumerate() {
+            if Some(state) == self.bad {
+                writeln!(&mut out, "State: {} \"{:?}\"", state, self.conclusion).unwrap();
+                continue;
+            }
+            writeln!(&mut out, "State: {}", state).unwrap();
+            for (valuation, target) in successors.iter().enumerate() {
+                let label = if self.aps.is_empty() {
+                    "t".into()
+                } else {
+                    (0..self.aps.len())
+                        .map(|i| {
+                            if valuation >> i & 1 == 1 {
+                                i.to_string()
+                            } else {
+                                format!("!{}", i)
+                            }
+                        })
+                        .join("&")
+                };
+                writeln!(&mut out, "  [{}] {}", label, target).unwrap();
+            }
+        }
+        write!(&mut out, "--END--").unwrap();
+        out
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+
+        writeln!(&mut out, "digraph g {{\nmindist = 2.0").unwrap();
+        if let Some(bad) = self.bad {
+            writeln!(
+                &mut out,
+                "\"s{}\" [label = \"{:?}\", shape=doublecircle]",
+                bad, self.conclusion
+            )
+            .unwrap();
+        }
+        for (state, successors) in self.transitions.iter().enumerate() {
+            if Some(state) == self.bad {
+                continue;
+            }
+            for (valuation, target) in successors.iter().enumerate() {
+                writeln!(
+                    &mut out,
+                    "\"s{}\" -> {{\"s{}\"}} [label = \"{}\"]",
+                    state,
+                    target,
+                    self.print_valuation(valuation)
+                )
+                .unwrap();
+            }
+        }
+        writeln!(&mut out, "init [label=\"\", shape=point]\ninit -> \"s0\"").unwrap();
+
+        out.push('}');
+        out.push('\n');
+        out
+    }
+
+    /// Standalone Rust source of the monitor, the transitions are a single match over the
+    /// current state and the values of the propositions
+    pub fn to_rust(&self) -> String {
+        let mut out = String::new();
+        writeln!(&mut out, "// Monitor for the {}", self.name()).unwrap();
+        writeln!(&mut out, "//").unwrap();
+        writeln!(
+            &mut out,
+            "// The valuations passed to `step` contain the values of {}",
+            if self.aps.is_empty() {
+                "no propositions".into()
+            } else {
+                self.aps.iter().map(|ap| format!("'{}'", ap)).join(", ")
+            }
+        )
+        .unwrap();
+        writeln!(&mut out).unwrap();
+        writeln!(
+            &mut out,
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum Verdict {{\n    Inconclusive,\n    {:?},\n}}\n",
+            self.conclusion
+        )
+        .unwrap();
+        writeln!(
+            &mut out,
+            "#[derive(Debug, Clone, Copy, Default)]\npub struct Monitor {{\n    state: usize,\n}}\n"
+        )
+        .unwrap();
+        writeln!(&mut out, "impl Monitor {{").unwrap();
+        writeln!(
+            &mut out,
+            "    pub fn step(&mut self, valuation: [bool; {}]) -> Verdict {{",
+            self.aps.len()
+        )
+        .unwrap();
+        writeln!(
+            &mut out,
+            "        self.state = match (self.state, valuation) {{"
+        )
+        .unwrap();
+        for (state, successors) in self.transitions.iter().enumerate() {
+            if Some(state) == self.bad {
+                continue;
+            }
+            let by_target = successors
+                .iter()
+                .enumerate()
+                .filter(|(_, target)| Some(**target) != self.bad)
+                .into_group_map_by(|(_, target)| **target);
+            for (target, valuations) in by_target.into_iter().sorted_by_key(|(t, _)| *t) {
+                let patterns = valuations
+                    .iter()
+                    .map(|(valuation, _)| {
+                        let values = (0..self.aps.len())
+                            .map(|i| valuation >> i & 1 == 1)
+                            .join(", ");
+                        format!("({}, [{}])", state, values)
+                    })
+                    .join("\n            | ");
+                writeln!(&mut out, "            {} => {},", patterns, target).unwrap();
+            }
+        }
+        if let Some(bad) = self.bad {
+            writeln!(&mut out, "            _ => {},", bad).unwrap();
+        }
+        writeln!(&mut out, "        }};").unwrap();
+        match self.bad {
+            Some(bad) => writeln!(
+                &mut out,
+                "        if self.state == {} {{\n            Verdict::{:?}\n        }} else {{\n            Verdict::Inconclusive\n        }}",
+                bad, self.conclusion
+            )
+            .unwrap(),
+            None => writeln!(&mut out, "        Verdict::Inconclusive").unwrap(),
+        }
+        writeln!(&mut out, "    }}\n}}").unwrap();
+        out
+    }
+
+    fn name(&self) -> String {
+        let fragment = match self.conclusion {
+            Verdict::Satisfied => "co-safety",
+            _ => "safety",
+        };
+        format!("{} formula '{}'", fragment, self.formula)
+    }
+
+    fn print_valuation(&self, valuation: usize) -> String {
+        if self.aps.is_empty() {
+            return "true".into();
+        }
+        self.aps
+            .iter()
+            .enumerate()
+            .map(|(i, ap)| {
+                if valuation >> i & 1 == 1 {
+                    ap.to_string()
+                } else {
+                    format!("¬{}", ap)
+                }
+            })
+            .join(" ∧ ")
+    }
+}
+
+/// States from which an accepting cycle is reachable
+fn productive_states(nba: &IndexedNba) -> Vec<bool> {
+    // States reachable in at least one step
+    let reachable = (0..nba.edges.len())
+        .map(|q| {
+            let mut seen = HashSet::new();
+            let mut stack = vec![q];
+            while let Some(p) = stack.pop() {
+                for (_, target) in &nba.edges[p] {
+                    if seen.insert(*target) {
+                        stack.push(*target);
+                    }
+                }
+            }
+            seen
+        })
+        .collect_vec();
+    (0..nba.edges.len())
+        .map(|q| {
+            reachable[q]
+                .iter()
+                .chain([&q])
+                .any(|p| nba.accepting[*p] && reachable[*p].contains(p))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Verdict after reading the word, `None` if it is inconclusive
+    fn run(monitor: &Monitor, word: &[&[&str]]) -> Option<Verdict> {
+        let state = word.iter().fold(0, |state, valuation| {
+            let valuation = monitor.aps.valuation(valuation.iter().copied());
+            monitor.transitions[state][valuation.bits() as usize]
+        });
+        (Some(state) == monitor.bad).then_some(monitor.conclusion)
+    }
+
+    #[test]
+    pub fn safety_monitor() {
+        let monitor = Monitor::new(&Formula::parse("G | !r X g").unwrap()).unwrap();
+        assert_eq!(run(&monitor, &[&["r"], &["g"], &[]]), None);
+        // The violated state has no successors
+        assert!(monitor.hoa().contains("properties: deterministic\n"));
+        assert_eq!(run(&monitor, &[&["r"], &[]]), Some(Verdict::Violated));
+        // The verdict is final
+        assert_eq!(
+            run(&monitor, &[&["r"], &[], &["g"]]),
+            Some(Verdict::Violated)
+        );
+
+        // Bad prefixes are detected as early as possible
+        let monitor = Monitor::new(&Formula::parse("& G a G !a").unwrap()).unwrap();
+        assert_eq!(run(&monitor, &[]), Some(Verdict::Violated));
+        let monitor = Monitor::new(&Formula::parse("G | a !a").unwrap()).unwrap();
+        assert_eq!(monitor.states(), 1);
+        assert!(monitor.hoa().contains("properties: deterministic complete\n"));
+    }
+
+    #[test]
+    pub fn co_safety_monitor() {
+        let monitor = Monitor::new(&Formula::parse("U a b").unwrap()).unwrap();
+        assert_eq!(run(&monitor, &[&["a"], &["a"]]), None);
+        assert_eq!(run(&monitor, &[&["a"], &["b"]]), Some(Verdict::Satisfied));
+        assert_eq!(run(&monitor, &[&[], &["b"]]), None);
+
+        assert!(Monitor::new(&Formula::parse("G F a").unwrap()).is_none());
+    }
+}
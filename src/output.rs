@@ -0,0 +1,257 @@
+// Documents printed by --format json, a single one for each run of a subcommand
+// Every part of a document is only present if the option producing it was given
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use ltl::bmc::Lasso;
+use ltl::metrics::Metrics;
+use parity::{Owner, Solution};
+use serde_derive::Serialize;
+
+use lmc::results::PropertyResult;
+
+/// Outcome of `lmc petri`
+#[derive(Serialize, Default)]
+pub struct PetriDocument {
+    /// File the net was read from
+    pub net: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statespace: Option<Statespace>,
+    /// Most tokens of each place, `null` if it is unbounded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<BTreeMap<String, Option<usize>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<Cover>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reach: Option<Reach>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queries: Option<Vec<QueryAnswer>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<Vec<PropertyResult>>,
+}
+
+/// Outcome of exploring the statespace with --analyse
+#[derive(Serialize, Default)]
+pub struct Statespace {
+    /// Structural reductions applied before exploring, with --reduce
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reductions: Option<Vec<String>>,
+    /// Seconds
+    pub time: f64,
+    /// Visited markings, all reachable ones unless only stubborn sets were explored or the
+    /// exploration was stopped
+    pub markings: u128,
+    pub deadlocks: u128,
+    pub stubborn_sets: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped: Option<Stopped>,
+    /// Bits per place and nodes of the binary decision diagrams of the symbolic engine
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bdd: Option<(u32, usize)>,
+    /// Layers explored and bytes written with --disk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk: Option<(usize, u64)>,
+    /// Bytes taken by the visited markings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<usize>,
+    /// Probability that hash compaction missed markings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collision_probability: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liveness: Option<TransitionLiveness>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub home_markings: Option<HomeMarkings>,
+}
+
+/// Exploration stopped by --max-states or --max-memory
+#[derive(Serialize)]
+pub struct Stopped {
+    pub limit: String,
+    /// Markings whose successors were computed, the deadlocks are among them
+    pub explored: usize,
+    /// Markings visited but not explored
+    pub queued: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct TransitionLiveness {
+    pub live: Vec<String>,
+    /// Fireable from the initial marking but not from every reachable marking
+    pub quasi_live: Vec<String>,
+    pub dead: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct HomeMarkings {
+    pub count: usize,
+    /// A home marking, the initial one if the net is reversible
+    pub example: Option<String>,
+    pub reversible: bool,
+}
+
+#[derive(Serialize)]
+pub struct Cover {
+    pub query: String,
+    pub coverable: bool,
+}
+
+#[derive(Serialize)]
+pub struct Reach {
+    pub marking: String,
+    /// Shortest firing sequence reaching the marking, `null` if it is not reachable
+    pub firings: Option<Vec<String>>,
+}
+
+/// Answer to a query about the reachable markings given by --query
+#[derive(Serialize)]
+pub struct QueryAnswer {
+    pub query: String,
+    /// Reachable markings the query was answered about
+    pub markings: usize,
+    /// Markings satisfying the condition of a count query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    /// Whether some marking satisfies the condition of an exists query or every marking that of
+    /// a forall query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holds: Option<bool>,
+    /// Marking satisfying the condition of an exists query or violating that of a forall query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marking: Option<String>,
+}
+
+impl Display for QueryAnswer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.query)?;
+        match (self.count, self.holds, &self.marking) {
+            (Some(count), _, _) => write!(f, "{} of {} markings", count, self.markings),
+            (_, Some(true), Some(marking)) => write!(f, "true, for example '{}'", marking),
+            (_, Some(false), Some(marking)) => write!(f, "false, not for '{}'", marking),
+            (_, holds, _) => write!(f, "{}", holds.unwrap_or_default()),
+        }
+    }
+}
+
+/// Outcome of `lmc ltl`
+#[derive(Serialize, Default)]
+pub struct LtlDocument {
+    pub formula: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<Metrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pnf: Option<String>,
+    /// Automata in HOA format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gnba: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nba: Option<String>,
+    /// Word violating the formula found by --satisfiable, `null` if every word satisfies it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub satisfiable: Option<Option<String>>,
+    /// Lasso shaped model found by --bmc, `null` if there is none of that size
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bmc: Option<Option<Lasso>>,
+    /// Subformulas violated by the trace of --explain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explain: Option<Vec<Violated>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vacuity: Option<String>,
+    /// Monitor in the format given by --monitor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<String>,
+}
+
+/// Subformula violated at a position of a trace
+#[derive(Serialize)]
+pub struct Violated {
+    pub subformula: String,
+    pub position: usize,
+    /// Whether the position is part of the repeated cycle
+    pub in_cycle: bool,
+}
+
+/// Outcome of `lmc parity`, vertices are named by their label or as id/priority
+#[derive(Serialize, Default)]
+pub struct ParityDocument {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub even_region: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub odd_region: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<Vec<Choice>>,
+}
+
+/// Winner of a vertex with the successor it moves to if it owns the vertex
+#[derive(Serialize)]
+pub struct Choice {
+    pub vertex: usize,
+    pub winner: Player,
+    pub next: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Player {
+    Even,
+    Odd,
+}
+
+impl From<Owner> for Player {
+    fn from(owner: Owner) -> Self {
+        match owner {
+            Owner::Even => Player::Even,
+            Owner::Odd => Player::Odd,
+        }
+    }
+}
+
+impl Choice {
+    /// Choices of the solution ordered by vertex
+    pub fn all(solution: &Solution) -> Vec<Choice> {
+        let mut choices = solution
+            .strategy
+            .iter()
+            .map(|(vertex, strategy)| Choice {
+                vertex: *vertex,
+                winner: strategy.winner.into(),
+                next: strategy.next_node_id,
+            })
+            .collect::<Vec<_>>();
+        choices.sort_by_key(|c| c.vertex);
+        choices
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn documents() {
+        let json = serde_json::to_value(PetriDocument {
+            net: "net".into(),
+            bounds: Some(BTreeMap::from([
+                ("p0".into(), Some(1)),
+                ("p1".into(), None),
+            ])),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"net": "net", "bounds": {"p0": 1, "p1": null}})
+        );
+
+        let game = parity::parse_game("parity 2;\n0 2 0 1;\n1 1 1 0;").unwrap();
+        let solution = game.zielonka();
+        let document = ParityDocument {
+            strategy: Some(Choice::all(&solution)),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(document).unwrap();
+        assert_eq!(json["strategy"][0]["vertex"], 0);
+        assert_eq!(json["strategy"][0]["winner"], "even");
+        assert!(json.get("even_region").is_none());
+    }
+}
@@ -0,0 +1,148 @@
+// Confirming exported counterexamples by firing their transitions again
+
+use itertools::Itertools;
+use petri::{Marking, PetriNet, Simulation};
+
+use crate::error::{Error, Result};
+use crate::results::{tokens, Firing, Run, Tokens};
+
+/// Markings of a confirmed counterexample with the transitions leading to them
+pub struct Replay<'a> {
+    pub initial: Marking,
+    pub stem: Vec<(&'a str, Marking)>,
+    pub cycle: Vec<(&'a str, Marking)>,
+}
+
+/// Fire the transitions of the run from the initial marking of the net and check that they lead
+/// to the recorded markings, that the cycle returns to the marking it started in and that a run
+/// without a cycle ends in a deadlock
+pub fn replay<'a>(net: &'a PetriNet, run: &Run) -> Result<Replay<'a>> {
+    let mut simulation = Simulation::new(net);
+    let initial = simulation.marking().clone();
+    expect(net, &initial, &run.initial, "the initial marking")?;
+    let mut fire = |firings: &[Firing]| -> Result<Vec<(&'a str, Marking)>> {
+        let mut fired = vec![];
+        for firing in firings {
+            simulation.fire(&firing.transition)?;
+            let marking = simulation.marking().clone();
+            let step = format!("firing '{}'", firing.transition);
+            expect(net, &marking, &firing.marking, &step)?;
+            fired.push((*simulation.trace().last().unwrap(), marking));
+        }
+        Ok(fired)
+    };
+    let stem = fire(&run.stem)?;
+    let loop_start = stem.last().map_or(&initial, |(_, m)| m).clone();
+    let cycle = fire(&run.cycle)?;
+    match cycle.last() {
+        Some((_, end)) if *end != loop_start => {
+            return Err(Error::Replay(format!(
+                "the cycle ends in '{}' instead of returning to '{}'",
+                net.marking_label(end),
+                net.marking_label(&loop_start)
+            )))
+        }
+        None if !simulation.enabled().is_empty() => {
+            return Err(Error::Replay(format!(
+                "the run ends without a cycle but '{}' is enabled",
+                simulation.enabled().join("', '")
+            )))
+        }
+        _ => {}
+    }
+    Ok(Replay {
+        initial,
+        stem,
+        cycle,
+    })
+}
+
+fn expect(net: &PetriNet, marking: &Marking, recorded: &Tokens, step: &str) -> Result<()> {
+    let reached = tokens(net, marking);
+    if reached != *recorded {
+        return Err(Error::Replay(format!(
+            "{} gives '{}' instead of the recorded '{}'",
+            step,
+            label(&reached),
+            label(recorded)
+        )));
+    }
+    Ok(())
+}
+
+fn label(tokens: &Tokens) -> String {
+    tokens
+        .iter()
+        .map(|(place, count)| match count {
+            1 => place.clone(),
+            _ => format!("{}*{}", place, count),
+        })
+        .join(" ")
+}
+
+impl Replay<'_> {
+    /// Table of the token counts after each firing, with a column for every place marked at
+    /// some point of the run
+    pub fn table(&self, net: &PetriNet) -> String {
+        let markings = [&self.initial]
+            .into_iter()
+            .chain(self.stem.iter().chain(&self.cycle).map(|(_, m)| m))
+            .collect_vec();
+        let places = net
+            .place_names()
+            .into_iter()
+            .filter(|p| markings.iter().any(|m| net.tokens(m, p).unwrap_or(0) > 0))
+            .collect_vec();
+        let steps = self.stem.iter().chain(&self.cycle).map(|(t, _)| t.len());
+        let width = steps.chain(["initial".len()]).max().unwrap_or(0);
+        let row = |step: &str, marking: &Marking| {
+            let counts = places.iter().map(|p| {
+                let count = net.tokens(marking, p).unwrap_or(0);
+                format!("{:>w$}", count, w = p.len())
+            });
+            format!("    {:<width$}  {}", step, counts.format("  "))
+        };
+
+        let mut table = vec![format!(
+            "    {:<width$}  {}",
+            "",
+            places.iter().format("  ")
+        )];
+        table.push(row("initial", &self.initial));
+        table.extend(self.stem.iter().map(|(t, m)| row(t, m)));
+        if self.cycle.is_empty() {
+            table.push("  deadlock, no transition is enabled".into());
+        } else {
+            table.push("  loop, repeated forever:".into());
+            table.extend(self.cycle.iter().map(|(t, m)| row(t, m)));
+        }
+        table.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::check::Checker;
+    use ltl::Formula;
+
+    #[test]
+    pub fn replays() {
+        let net = petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p0\npl p0 (1)").unwrap();
+        let outcome = Checker::new(&net, false)
+            .check(&Formula::parse("G tokens(p1)<=0").unwrap())
+            .unwrap();
+        let mut run = Run::new(&net, &outcome.counterexample.unwrap());
+        let replayed = replay(&net, &run).unwrap();
+        assert_eq!(replayed.stem.len() + replayed.cycle.len(), 4);
+        assert!(replayed.table(&net).contains("loop, repeated forever"));
+
+        // A cycle has to return to where it started
+        run.cycle.pop();
+        assert!(replay(&net, &run).is_err());
+        run.cycle.clear();
+        assert!(replay(&net, &run).is_err());
+        run.stem[0].marking = Tokens::from([("p0".to_string(), 1)]);
+        assert!(replay(&net, &run).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+// Statistics of the stages checking an LTL property of a net
+
+use std::fmt::Display;
+use std::fs;
+use std::time::Duration;
+
+use ltl::Formula;
+use serde_derive::Serialize;
+
+use crate::check::Outcome;
+use crate::transform::IndexedNba;
+
+/// Stages of checking a property, printed by --report and added to --results
+#[derive(Serialize)]
+pub struct Report {
+    /// Nodes of the syntax tree of the property
+    pub size: usize,
+    pub temporal_depth: usize,
+    pub propositions: usize,
+    pub conjuncts: Vec<Stages>,
+}
+
+/// Stages of checking a conjunct of a property: the automata translated from its negation and
+/// the product of these with the reachability graph, each with its time and the peak memory of
+/// the process after it
+#[derive(Serialize, Clone)]
+pub struct Stages {
+    /// Negation of the conjunct, which the automaton accepts
+    pub formula: String,
+    pub automaton: AutomatonStage,
+    pub product: ProductStage,
+}
+
+#[derive(Serialize, Clone)]
+pub struct AutomatonStage {
+    /// States of the generalized automaton, unknown if the automaton was translated before
+    pub gnba_states: Option<usize>,
+    /// States of the degeneralized automaton before it is shrunk
+    pub nba_states: Option<usize>,
+    pub states: usize,
+    pub edges: usize,
+    /// Seconds
+    pub time: f64,
+    /// Bytes
+    pub peak_memory: Option<usize>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ProductStage {
+    /// States visited by the search
+    pub states: usize,
+    /// Strongly connected components of the whole product, only counted for reports
+    pub components: Option<usize>,
+    /// Components through which a cycle runs
+    pub cyclic_components: Option<usize>,
+    /// Seconds
+    pub time: f64,
+    /// Bytes
+    pub peak_memory: Option<usize>,
+}
+
+impl Report {
+    pub fn new(formula: &Formula, outcome: &Outcome) -> Self {
+        let metrics = formula.metrics();
+        Report {
+            size: metrics.size,
+            temporal_depth: metrics.temporal_depth,
+            propositions: metrics.propositions,
+            conjuncts: outcome.stages.clone(),
+        }
+    }
+}
+
+impl AutomatonStage {
+    /// Stage of the automaton, with the states of the GNBA and NBA if it was translated
+    pub fn new(nba: &IndexedNba, translated: Option<(usize, usize)>, time: Duration) -> Self {
+        AutomatonStage {
+            gnba_states: translated.map(|(gnba, _)| gnba),
+            nba_states: translated.map(|(_, nba)| nba),
+            states: nba.edges.len(),
+            edges: nba.edges.iter().map(Vec::len).sum(),
+            time: time.as_secs_f64(),
+            peak_memory: peak_memory(),
+        }
+    }
+}
+
+/// Most resident memory of the process so far in bytes, where the system reports it in /proc
+pub fn peak_memory() -> Option<usize> {
+    memory_status("VmHWM:")
+}
+
+/// Resident memory of the process in bytes, where the system reports it
+pub fn resident_memory() -> Option<usize> {
+    memory_status("VmRSS:")
+}
+
+/// Field of the process status given in kilobytes, in bytes
+fn memory_status(field: &str) -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with(field))?;
+    let kilobytes: usize = line
+        .trim_start_matches(field)
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kilobytes << 10)
+}
+
+/// Time and peak memory of a stage such as "1.2ms, peak memory 5.3 MB"
+fn resources(time: f64, peak_memory: Option<usize>) -> String {
+    let time = Duration::from_secs_f64(time);
+    let time = if time <= Duration::from_millis(1) {
+        format!("{}μs", time.as_micros())
+    } else if time <= Duration::from_secs(1) {
+        format!("{}ms", time.as_millis())
+    } else {
+        format!("{}s", time.as_secs_f64())
+    };
+    match peak_memory {
+        Some(bytes) => format!("{}, peak memory {:.1} MB", time, bytes as f64 / 1e6),
+        None => time,
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "  formula: size {}, temporal depth {}, propositions {}",
+            self.size, self.temporal_depth, self.propositions
+        )?;
+        for stages in &self.conjuncts {
+            writeln!(f, "  conjunct '{}'", stages.formula)?;
+            let automaton = &stages.automaton;
+            write!(f, "    automaton: ")?;
+            if let (Some(gnba), Some(nba)) = (automaton.gnba_states, automaton.nba_states) {
+                write!(f, "{} GNBA states, {} NBA states, reduced to ", gnba, nba)?;
+            }
+            writeln!(
+                f,
+                "{} states with {} edges, {}",
+                automaton.states,
+                automaton.edges,
+                resources(automaton.time, automaton.peak_memory)
+            )?;
+            let product = &stages.product;
+            write!(f, "    product: {} states visited", product.states)?;
+            if let (Some(all), Some(cyclic)) = (product.components, product.cyclic_components) {
+                write!(f, ", {} SCCs, {} cyclic", all, cyclic)?;
+            }
+            writeln!(f, ", {}", resources(product.time, product.peak_memory))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::check::Checker;
+
+    #[test]
+    pub fn stage_report() {
+        let net = petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p0\npl p0 (1)").unwrap();
+        let checker = Checker::new(&net, false).with_components();
+        let formula = Formula::parse("& G F 1<=tokens(p0) G F t1").unwrap();
+        let outcome = checker.check(&formula).unwrap();
+        let report = Report::new(&formula, &outcome);
+        assert_eq!(report.conjuncts.len(), 2);
+        let stages = &report.conjuncts[0];
+        assert_eq!(stages.formula, "¬(G (F 1<=tokens(p0)))");
+        assert!(stages.automaton.gnba_states.is_some());
+        assert_eq!(
+            stages.product.states,
+            outcome.states - report.conjuncts[1].product.states
+        );
+        assert_eq!(stages.product.cyclic_components, Some(1));
+
+        // Repeated formulas reuse their automata
+        let again = checker.check(&formula).unwrap();
+        assert!(again.stages[0].automaton.gnba_states.is_none());
+        let printed = Report::new(&formula, &again).to_string();
+        assert!(printed.starts_with("  formula: size 7, temporal depth 2, propositions 2\n"));
+        assert!(printed.contains("    automaton: 2 states with 3 edges"));
+        let uncounted = Checker::new(&net, false).check(&formula).unwrap();
+        assert!(uncounted.stages[0].product.components.is_none());
+    }
+}
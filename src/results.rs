@@ -0,0 +1,199 @@
+// Machine readable summary of the LTL properties checked for a net and their counterexamples
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use ltl::Formula;
+use petri::{Marking, PetriNet};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::check::{Counterexample, Outcome};
+use crate::report::Report;
+
+#[derive(Serialize)]
+pub struct Results {
+    /// File the net was read from
+    pub net: String,
+    pub properties: Vec<PropertyResult>,
+}
+
+#[derive(Serialize)]
+pub struct PropertyResult {
+    pub id: String,
+    pub formula: String,
+    pub verdict: Verdict,
+    /// Seconds spent checking the property
+    pub time: f64,
+    /// States of the products visited
+    pub states: usize,
+    pub counterexample: Option<Run>,
+    /// Statistics of the stages, with --report
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<Report>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Holds,
+    Violated,
+    /// No counterexample up to the bound of a bounded search
+    Unknown,
+}
+
+/// Counterexamples written by --cex-out and confirmed by `lmc replay`
+#[derive(Serialize, Deserialize)]
+pub struct Counterexamples {
+    /// File the net was read from
+    pub net: String,
+    pub properties: Vec<Violation>,
+}
+
+/// Property with a run violating it
+#[derive(Serialize, Deserialize)]
+pub struct Violation {
+    pub id: String,
+    pub formula: String,
+    pub counterexample: Run,
+}
+
+/// Counterexample from the initial marking, the cycle is empty if the stem ends in a deadlock
+#[derive(Serialize, Deserialize)]
+pub struct Run {
+    pub initial: Tokens,
+    pub stem: Vec<Firing>,
+    pub cycle: Vec<Firing>,
+}
+
+/// Transition fired with the marking it leads to
+#[derive(Serialize, Deserialize)]
+pub struct Firing {
+    pub transition: String,
+    pub marking: Tokens,
+}
+
+/// Token counts of the marked places
+pub type Tokens = BTreeMap<String, usize>;
+
+pub fn tokens(net: &PetriNet, marking: &Marking) -> Tokens {
+    net.place_names()
+        .into_iter()
+        .filter_map(|place| match net.tokens(marking, place) {
+            Ok(0) | Err(_) => None,
+            Ok(count) => Some((place.to_string(), count)),
+        })
+        .collect()
+}
+
+impl Run {
+    pub fn new(net: &PetriNet, counterexample: &Counterexample) -> Self {
+        let firings = |firings: &[(&str, Marking)]| {
+            firings
+                .iter()
+                .map(|(t, m)| Firing {
+                    transition: t.to_string(),
+                    marking: tokens(net, m),
+                })
+                .collect()
+        };
+        Run {
+            initial: tokens(net, &net.initial_marking()),
+            stem: firings(&counterexample.stem),
+            cycle: firings(&counterexample.cycle),
+        }
+    }
+}
+
+impl PropertyResult {
+    pub fn new(
+        net: &PetriNet,
+        id: &str,
+        formula: &Formula,
+        time: Duration,
+        outcome: &Outcome,
+    ) -> Self {
+        PropertyResult {
+            id: id.to_string(),
+            formula: formula.to_string(),
+            verdict: match outcome.counterexample {
+                Some(_) => Verdict::Violated,
+                None if outcome.bounded => Verdict::Unknown,
+                None => Verdict::Holds,
+            },
+            time: time.as_secs_f64(),
+            states: outcome.states,
+            counterexample: outcome.counterexample.as_ref().map(|c| Run::new(net, c)),
+            report: None,
+        }
+    }
+}
+
+impl PropertyResult {
+    /// Answer line of the Model Checking Contest with the techniques used
+    pub fn mcc(&self, techniques: &[&str]) -> String {
+        let verdict = match self.verdict {
+            Verdict::Holds => "TRUE",
+            Verdict::Violated => "FALSE",
+            Verdict::Unknown => return format!("FORMULA {} CANNOT_COMPUTE", self.id),
+        };
+        format!(
+            "FORMULA {} {} TECHNIQUES {}",
+            self.id,
+            verdict,
+            techniques.join(" ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::check::Checker;
+
+    #[test]
+    pub fn json_summary() {
+        let net = petri::from_tina("tr t0 p0 -> p1\npl p0 (1)").unwrap();
+        let checker = Checker::new(&net, false);
+        let properties = ["G tokens(p0)<=1", "G 1<=tokens(p0)"]
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let formula = Formula::parse(f).unwrap();
+                let outcome = checker.check(&formula).unwrap();
+                PropertyResult::new(&net, &i.to_string(), &formula, Duration::ZERO, &outcome)
+            })
+            .collect();
+        let results = Results {
+            net: "net".into(),
+            properties,
+        };
+        let json: serde_json::Value = serde_json::to_value(&results).unwrap();
+        assert_eq!(json["properties"][0]["verdict"], "holds");
+        assert!(json["properties"][0]["counterexample"].is_null());
+        assert_eq!(json["properties"][1]["verdict"], "violated");
+        // Firing t0 leads to a deadlock
+        let counterexample = &json["properties"][1]["counterexample"];
+        assert_eq!(counterexample["stem"][0]["transition"], "t0");
+        assert_eq!(counterexample["initial"], serde_json::json!({"p0": 1}));
+        assert_eq!(
+            counterexample["stem"][0]["marking"],
+            serde_json::json!({"p1": 1})
+        );
+        assert_eq!(counterexample["cycle"], serde_json::json!([]));
+    }
+
+    #[test]
+    pub fn mcc_answers() {
+        let net = petri::from_tina("tr t0 p0 -> p1\npl p0 (1)").unwrap();
+        let formula = Formula::parse("G tokens(p0)<=1").unwrap();
+        let mut outcome = Checker::new(&net, false).check(&formula).unwrap();
+        let result = PropertyResult::new(&net, "P-00", &formula, Duration::ZERO, &outcome);
+        assert_eq!(
+            result.mcc(&["EXPLICIT", "SEQUENTIAL_PROCESSING"]),
+            "FORMULA P-00 TRUE TECHNIQUES EXPLICIT SEQUENTIAL_PROCESSING"
+        );
+        outcome.bounded = true;
+        let result = PropertyResult::new(&net, "P-01", &formula, Duration::ZERO, &outcome);
+        assert_eq!(result.mcc(&["EXPLICIT"]), "FORMULA P-01 CANNOT_COMPUTE");
+    }
+}
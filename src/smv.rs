@@ -0,0 +1,97 @@
+// LTL properties of nets in the syntax of NuSMV
+
+use ltl::xml::{Atom, IntegerExpr};
+use ltl::{Expr, Formula};
+use petri::PetriNet;
+
+use crate::transform::petri_atom;
+
+/// SMV model of the net with a specification for each property, whose atomic propositions refer
+/// to the variables of `PetriNet::to_smv`
+pub fn to_smv(net: &PetriNet, properties: &[(String, Formula)]) -> petri::Result<String> {
+    let specs = properties
+        .iter()
+        .map(|(id, f)| Ok((format!("{}: '{}'", id, f), expr(net, &f.root_expr)?)))
+        .collect::<petri::Result<Vec<_>>>()?;
+    net.to_smv(specs)
+}
+
+/// NuSMV writes release as 'V' and has neither weak until nor strong release, which are rewritten
+/// into until and globally, and the step bounded operators are unfolded into next operators
+fn expr(net: &PetriNet, e: &Expr) -> petri::Result<String> {
+    let binary = |op: &str, lhs: &Expr, rhs: &Expr| -> petri::Result<String> {
+        Ok(format!("({} {} {})", expr(net, lhs)?, op, expr(net, rhs)?))
+    };
+    Ok(match e {
+        Expr::True => "TRUE".into(),
+        Expr::False => "FALSE".into(),
+        Expr::Atomic(ap) => atom(net, &petri_atom(net, ap)?)?,
+        Expr::Not(e) => format!("!{}", expr(net, e)?),
+        Expr::Next(e) => format!("X {}", expr(net, e)?),
+        Expr::Globally(e) => format!("G {}", expr(net, e)?),
+        Expr::Finally(e) => format!("F {}", expr(net, e)?),
+        Expr::And(lhs, rhs) => binary("&", lhs, rhs)?,
+        Expr::Or(lhs, rhs) => binary("|", lhs, rhs)?,
+        Expr::Until(lhs, rhs) => binary("U", lhs, rhs)?,
+        Expr::Release(lhs, rhs) => binary("V", lhs, rhs)?,
+        // a W b = (a U b) ∨ G a
+        Expr::WeakUntil(lhs, rhs) => {
+            format!("({} | G {})", binary("U", lhs, rhs)?, expr(net, lhs)?)
+        }
+        // a M b = b U (a ∧ b)
+        Expr::StrongRelease(lhs, rhs) => {
+            format!("({} U {})", expr(net, rhs)?, binary("&", lhs, rhs)?)
+        }
+        bounded => expr(net, &bounded.unfold())?,
+    })
+}
+
+fn atom(net: &PetriNet, atom: &Atom) -> petri::Result<String> {
+    let integer = |e: &IntegerExpr| -> petri::Result<String> {
+        Ok(match e {
+            IntegerExpr::Constant(c) => c.to_string(),
+            IntegerExpr::TokensCount(places) if places.len() == 1 => net.smv_place(&places[0])?,
+            IntegerExpr::TokensCount(places) => format!(
+                "({})",
+                places
+                    .iter()
+                    .map(|p| net.smv_place(p))
+                    .collect::<petri::Result<Vec<_>>>()?
+                    .join(" + ")
+            ),
+        })
+    };
+    Ok(match atom {
+        Atom::Fireable(t) => net.smv_transition(t)?,
+        Atom::IntegerLe(lhs, rhs) => format!("({} <= {})", integer(lhs)?, integer(rhs)?),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn smv_properties() {
+        let net = petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p0\npl p0 (1)").unwrap();
+        let property = |f: &str| {
+            let f = Formula::parse(f).unwrap();
+            expr(&net, &f.root_expr)
+        };
+        assert_eq!(
+            property("G F tokens(p0,p1)<=1").unwrap(),
+            "G F ((p_p0 + p_p1) <= 1)"
+        );
+        let spot = |f: &str| expr(&net, &ltl::spot::parse(f).unwrap().root_expr).unwrap();
+        assert_eq!(spot("t0 W t1"), "((t_t0 U t_t1) | G t_t0)");
+        assert_eq!(spot("t0 M t1"), "(t_t1 U (t_t0 & t_t1))");
+        assert_eq!(spot("F[<=1] t1"), "(t_t1 | X t_t1)");
+        assert_eq!(property("F p1").unwrap(), "F (1 <= p_p1)");
+        assert!(property("F t2").is_err());
+        assert!(property("F tokens(p2)<=0").is_err());
+
+        let properties = [("one".into(), Formula::parse("G t0").unwrap())];
+        let smv = to_smv(&net, &properties).unwrap();
+        assert!(smv.ends_with("-- one: 'G t0'\nLTLSPEC G t_t0;\n"));
+    }
+}
@@ -0,0 +1,651 @@
+// Reactive synthesis of LTL specifications
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+use buchi::nba::DeterministicAutomaton;
+use itertools::Itertools;
+use ltl::alphabet::{ApSet, Literals, Valuation};
+use ltl::{Expr, Formula};
+use parity::{Graph, MetaData, NodeIndex, Owner, Solution};
+
+use crate::error::{Error, Result};
+use crate::transform::{ltl_to_gnba, IndexedNba};
+
+pub mod gr1;
+
+/// Solver used for the synthesis games, for example `Graph::zielonka`
+pub type Solver = fn(&Graph) -> Solution;
+
+pub enum Synthesis {
+    /// The specification is realizable and the controller implements it
+    Realizable(Controller),
+    /// The environment can violate the specification with the given counter strategy
+    Unrealizable(Controller),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Player {
+    System,
+    Environment,
+}
+
+/// A Mealy machine reading input valuations and producing output valuations. If the machine
+/// belongs to the environment it picks the inputs and reacts to every possible output.
+pub struct Controller {
+    pub player: Player,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    size: usize,
+    transitions: Vec<(usize, BTreeSet<String>, BTreeSet<String>, usize)>,
+}
+
+/// Synthesize a controller for the formula, trying every bound up to the given one before solving
+/// the parity game of a deterministic automaton
+///
+/// The NBA of the negated specification is read as a universal co-Büchi automaton for the
+/// specification and the number of visits to rejecting states is bounded by k, which turns the
+/// problem into a safety game. If neither the system wins the game for the specification nor the
+/// environment wins the game for the negated specification the bound is increased. Specifications
+/// in the GR(1) fragment are tried with the dedicated solver in `gr1` first.
+///
+/// Bounded synthesis finds small controllers quickly but may not decide the specification within
+/// the bound. The NBA of the specification is then determinized into a parity automaton, whose
+/// game one of the players wins.
+pub fn synthesize(
+    formula: &Formula,
+    inputs: &[String],
+    outputs: &[String],
+    bound: usize,
+    solver: Solver,
+) -> Result<Synthesis> {
+    let _span = tracing::info_span!("synthesize").entered();
+    for input in inputs {
+        if outputs.contains(input) {
+            return Err(Error::Specification(format!(
+                "'{}' is declared as input and output",
+                input
+            )));
+        }
+    }
+    for ap in formula.root_expr.alphabet() {
+        if let Expr::Atomic(name) = &ap {
+            if !inputs.contains(name) && !outputs.contains(name) {
+                return Err(Error::Specification(format!(
+                    "'{}' is neither declared as input nor as output",
+                    name
+                )));
+            }
+        }
+    }
+
+    if let Some(spec) = gr1::Gr1::from_formula(formula, inputs, outputs) {
+        tracing::info!(
+            "solving GR(1) game with {} assumptions and {} guarantees",
+            spec.assumptions(),
+            spec.guarantees()
+        );
+        if let Some(controller) = spec.solve() {
+            return Ok(Synthesis::Realizable(controller));
+        }
+        tracing::info!("not strictly realizable, falling back to bounded synthesis");
+    }
+
+    let negation = formula.negation();
+    // The NBA of the negation accepts exactly the violating runs
+    let system_ucw = Ucw::from_nba_of(&negation);
+    let environment_ucw = Ucw::from_nba_of(formula);
+    let input_valuations = valuations(inputs);
+    let output_valuations = valuations(outputs);
+    let synthesis = |player, (size, transitions): (usize, MachineTransitions)| {
+        let controller = Controller {
+            player,
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+            size,
+            transitions: transitions
+                .into_iter()
+                .map(|(s, i, o, t)| {
+                    (
+                        s,
+                        input_valuations[i].clone(),
+                        output_valuations[o].clone(),
+                        t,
+                    )
+                })
+                .collect(),
+        };
+        match player {
+            Player::System => Synthesis::Realizable(controller),
+            Player::Environment => Synthesis::Unrealizable(controller),
+        }
+    };
+
+    for k in 0..=bound {
+        let _span = tracing::info_span!("bound", k).entered();
+        tracing::info!("trying to synthesize with bound {}", k);
+        for (player, ucw) in [
+            (Player::System, &system_ucw),
+            (Player::Environment, &environment_ucw),
+        ] {
+            let game = Game::safety(ucw, player, &input_valuations, &output_valuations, k);
+            tracing::debug!(
+                "safety game for {:?} has {} vertices",
+                player,
+                game.graph.len()
+            );
+            if let Some(controller) = game.solve(solver) {
+                return Ok(synthesis(player, controller));
+            }
+        }
+    }
+
+    tracing::info!(
+        "no winner within bound {}, determinizing the specification",
+        bound
+    );
+    let dpa = ltl_to_gnba(formula).gnba_to_nba().reduce().determinize();
+    for player in [Player::System, Player::Environment] {
+        let game = Game::parity(&dpa, player, &input_valuations, &output_valuations);
+        tracing::debug!(
+            "parity game for {:?} has {} vertices",
+            player,
+            game.graph.len()
+        );
+        if let Some(controller) = game.solve(solver) {
+            return Ok(synthesis(player, controller));
+        }
+    }
+    unreachable!("one of the players wins the parity game")
+}
+
+/// All possible valuations of the given atomic propositions, a valuation is the set of true ones
+fn valuations(aps: &[String]) -> Vec<BTreeSet<String>> {
+    aps.iter()
+        .cloned()
+        .powerset()
+        .map(BTreeSet::from_iter)
+        .collect()
+}
+
+/// Universal co-Büchi automaton, a word is accepted if no run visits rejecting states infinitely
+/// often
+struct Ucw {
+    aps: ApSet,
+    initial_states: Vec<usize>,
+    rejecting: Vec<bool>,
+    // Edges with the literals which have to hold to take them
+    edges: Vec<Vec<(Literals, usize)>>,
+}
+
+impl Ucw {
+    /// The accepting states of the NBA for the formula become the rejecting states of the UCW
+    fn from_nba_of(formula: &Formula) -> Self {
+        let nba = IndexedNba::from_formula(formula);
+        Ucw {
+            aps: nba.aps,
+            initial_states: nba.initial_states,
+            rejecting: nba.accepting,
+            edges: nba.edges,
+        }
+    }
+
+    /// Counters of the initial states, `None` if the bound is already exceeded
+    fn initial_counters(&self, bound: usize) -> Option<Vec<Option<usize>>> {
+        let mut counters = vec![None; self.rejecting.len()];
+        for &q in &self.initial_states {
+            let count = self.rejecting[q] as usize;
+            if count > bound {
+                return None;
+            }
+            counters[q] = Some(count);
+        }
+        Some(counters)
+    }
+
+    /// Track the maximal number of rejecting visits of each state after reading the valuation
+    fn successor(
+        &self,
+        counters: &[Option<usize>],
+        valuation: Valuation,
+        bound: usize,
+    ) -> Option<Vec<Option<usize>>> {
+        let mut next = vec![None; counters.len()];
+        for (q, count) in counters.iter().enumerate() {
+            let count = match count {
+                Some(c) => *c,
+                None => continue,
+            };
+            for (literals, target) in &self.edges[q] {
+                if literals.holds(valuation) {
+                    let new_count = count + self.rejecting[*target] as usize;
+                    if new_count > bound {
+                        return None;
+                    }
+                    next[*target] = next[*target].max(Some(new_count));
+                }
+            }
+        }
+        Some(next)
+    }
+}
+
+/// Transitions of a machine between numbered states, labeled by input and output valuation indices
+type MachineTransitions = Vec<(usize, usize, usize, usize)>;
+
+enum Position {
+    // The environment picks an input, leading to one choice vertex per input
+    Round(Vec<usize>),
+    // The system picks an output after the input was fixed, the targets are indexed by output
+    Choice(usize, Vec<(usize, usize)>),
+    // The bound was exceeded
+    Bad,
+}
+
+/// Game of the system and the environment on the states of an automaton for the specification, in
+/// which the player whose controller is sought is even
+struct Game {
+    graph: Graph,
+    player: Player,
+    positions: Vec<Position>,
+    nodes: Vec<NodeIndex>,
+    /// Whether the player wins by staying in its winning region, as in safety games
+    safety: bool,
+}
+
+impl Game {
+    fn new(player: Player, safety: bool) -> Self {
+        Game {
+            graph: Graph::new(),
+            player,
+            positions: vec![],
+            nodes: vec![],
+            safety,
+        }
+    }
+
+    /// Owners of the vertices in which the input and the output are picked, the player is even
+    fn owners(&self) -> (Owner, Owner) {
+        match self.player {
+            Player::System => (Owner::Odd, Owner::Even),
+            Player::Environment => (Owner::Even, Owner::Odd),
+        }
+    }
+
+    /// Safety game where the player tries to keep the counters of the UCW within the bound
+    fn safety(
+        ucw: &Ucw,
+        player: Player,
+        inputs: &[BTreeSet<String>],
+        outputs: &[BTreeSet<String>],
+        bound: usize,
+    ) -> Self {
+        // The bad vertex has the only odd priority
+        let mut game = Game::new(player, true);
+        let (round_owner, choice_owner) = game.owners();
+        let mut rounds = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        let initial = ucw.initial_counters(bound);
+        game.round(&mut rounds, &mut queue, initial, round_owner);
+
+        while let Some((counters, round)) = queue.pop_front() {
+            for (i, input) in inputs.iter().enumerate() {
+                let targets = outputs
+                    .iter()
+                    .map(|output| {
+                        let aps = input.iter().chain(output.iter()).map(String::as_str);
+                        let valuation = ucw.aps.valuation(aps);
+                        let next = ucw.successor(&counters, valuation, bound);
+                        game.round(&mut rounds, &mut queue, next, round_owner)
+                    })
+                    .collect();
+                game.choice(round, i, targets, choice_owner);
+            }
+        }
+
+        game
+    }
+
+    /// Parity game on the states of the deterministic automaton of the specification, which the
+    /// system wins if the run on the play is accepted and the environment if it is rejected
+    ///
+    /// Round vertices are states of the automaton paired with the priority of the transition
+    /// entering them, mirrored at an even bound for the max even priorities of the solvers as in
+    /// `Buchi::to_parity_game`. Shifting the priorities by one complements the automaton for the
+    /// environment.
+    fn parity(
+        dpa: &DeterministicAutomaton,
+        player: Player,
+        inputs: &[BTreeSet<String>],
+        outputs: &[BTreeSet<String>],
+    ) -> Self {
+        let mut game = Game::new(player, false);
+        let (round_owner, choice_owner) = game.owners();
+        let shift = usize::from(player == Player::Environment);
+        let bound = (dpa.priorities() + shift).next_multiple_of(2);
+        let mut rounds: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        // The initial vertex is only visited once, so its priority does not matter
+        let initial = game.vertex(Position::Round(vec![]), round_owner, 0);
+        queue.push_back((0, initial));
+
+        while let Some((state, round)) = queue.pop_front() {
+            for (i, input) in inputs.iter().enumerate() {
+                let targets = outputs
+                    .iter()
+                    .map(|output| {
+                        let aps = input.iter().chain(output.iter()).map(String::as_str);
+                        let valuation = dpa.aps().valuation(aps);
+                        let letter = dpa
+                            .letters()
+                            .find(|w| w.literals.is_some_and(|l| l.holds(valuation)))
+                            .expect("The letters are the valuations of the propositions");
+                        let (next, priority) = dpa.successor(state, letter).unwrap();
+                        let priority = bound - priority - shift;
+                        *rounds.entry((next, priority)).or_insert_with(|| {
+                            let vertex =
+                                game.vertex(Position::Round(vec![]), round_owner, priority);
+                            queue.push_back((next, vertex));
+                            vertex
+                        })
+                    })
+                    .collect();
+                game.choice(round, i, targets, choice_owner);
+            }
+        }
+
+        game
+    }
+
+    /// Let the round vertex move to a vertex picking the output for the input, the targets are
+    /// indexed by output
+    fn choice(&mut self, round: usize, input: usize, targets: Vec<usize>, owner: Owner) {
+        let choice = self.vertex(Position::Choice(input, vec![]), owner, 0);
+        self.add_edge(round, choice);
+        if let Position::Round(choices) = &mut self.positions[round] {
+            choices.push(choice);
+        }
+        for target in targets.iter().unique() {
+            self.add_edge(choice, *target);
+        }
+        self.positions[choice] = Position::Choice(input, targets.into_iter().enumerate().collect());
+    }
+
+    fn vertex(&mut self, position: Position, owner: Owner, priority: usize) -> usize {
+        let id = self.positions.len();
+        self.positions.push(position);
+        self.nodes.push(self.graph.add_vertex(MetaData {
+            id,
+            label: None,
+            owner,
+            priority,
+        }));
+        id
+    }
+
+    fn add_edge(&mut self, source: usize, target: usize) {
+        self.graph.add_edge(self.nodes[source], self.nodes[target]);
+    }
+
+    /// Get or create the vertex for the given counters, `None` is the bad vertex
+    fn round(
+        &mut self,
+        rounds: &mut HashMap<Option<Vec<Option<usize>>>, usize>,
+        queue: &mut VecDeque<(Vec<Option<usize>>, usize)>,
+        counters: Option<Vec<Option<usize>>>,
+        owner: Owner,
+    ) -> usize {
+        if let Some(vertex) = rounds.get(&counters) {
+            return *vertex;
+        }
+        let vertex = match &counters {
+            Some(c) => {
+                let vertex = self.vertex(Position::Round(vec![]), owner, 0);
+                queue.push_back((c.clone(), vertex));
+                vertex
+            }
+            None => {
+                let vertex = self.vertex(Position::Bad, Owner::Odd, 1);
+                self.add_edge(vertex, vertex);
+                vertex
+            }
+        };
+        rounds.insert(counters, vertex);
+        vertex
+    }
+
+    /// Solve the game and extract the strategy of the player starting in the initial vertex.
+    /// Returns the number of controller states and the transitions labeled with the indices of
+    /// the input and output valuations.
+    fn solve(&self, solver: Solver) -> Option<(usize, MachineTransitions)> {
+        let solution = solver(&self.graph);
+        let winning: HashSet<usize> = solution.even_region.iter().map(|m| m.id).collect();
+        if !winning.contains(&0) {
+            return None;
+        }
+
+        let chosen = |vertex: usize, successors: &[usize]| {
+            solution
+                .strategy
+                .get(&vertex)
+                .and_then(|s| s.next_node_id)
+                .filter(|n| winning.contains(n))
+                // Fall back to any winning successor if the solver left the vertex without
+                // strategy, which only wins safety games
+                .or_else(|| {
+                    (successors.iter().cloned()).find(|t| self.safety && winning.contains(t))
+                })
+                .expect("Winning vertex without winning successor")
+        };
+
+        let mut states = HashMap::from([(0, 0)]);
+        let mut queue = VecDeque::from([0]);
+        let mut transitions = vec![];
+
+        while let Some(round) = queue.pop_front() {
+            let choices = match &self.positions[round] {
+                Position::Round(choices) => choices,
+                _ => unreachable!("the player never moves to the bad vertex"),
+            };
+
+            let mut edges = vec![];
+            match self.player {
+                // The system picks the output for every input
+                Player::System => {
+                    for &c in choices {
+                        if let Position::Choice(input, targets) = &self.positions[c] {
+                            let target = chosen(c, &targets.iter().map(|t| t.1).collect_vec());
+                            let output = targets.iter().find(|t| t.1 == target).unwrap().0;
+                            edges.push((*input, output, target));
+                        }
+                    }
+                }
+                // The environment picks one input and has to handle every output
+                Player::Environment => {
+                    let c = chosen(round, choices);
+                    if let Position::Choice(input, targets) = &self.positions[c] {
+                        for (output, target) in targets {
+                            edges.push((*input, *output, *target));
+                        }
+                    }
+                }
+            }
+
+            for (input, output, target) in edges {
+                let next = states.len();
+                let target_state = *states.entry(target).or_insert_with(|| {
+                    queue.push_back(target);
+                    next
+                });
+                transitions.push((states[&round], input, output, target_state));
+            }
+        }
+
+        Some((states.len(), transitions))
+    }
+}
+
+impl Controller {
+    fn aps(&self) -> impl Iterator<Item = &String> {
+        self.inputs.iter().chain(self.outputs.iter())
+    }
+
+    fn print_valuation(aps: &[String], valuation: &BTreeSet<String>) -> String {
+        if aps.is_empty() {
+            return "true".into();
+        }
+        aps.iter()
+            .map(|ap| {
+                if valuation.contains(ap) {
+                    ap.clone()
+                } else {
+                    format!("¬{}", ap)
+                }
+            })
+            .join(" ∧ ")
+    }
+
+    /// Transform the controller into a HOA formatted string, the atomic propositions chosen by the
+    /// controller are marked as controllable
+    pub fn hoa(&self) -> String {
+        let mut out = String::new();
+        let aps = self.aps().collect_vec();
+        let controllable = match self.player {
+            Player::System => self.inputs.len()..aps.len(),
+            Player::Environment => 0..self.inputs.len(),
+        };
+
+        writeln!(&mut out, "HOA: v1").unwrap();
+        writeln!(&mut out, "States: {}", self.size).unwrap();
+        writeln!(&mut out, "Start: 0").unwrap();
+        writeln!(
+            &mut out,
+            "AP: {}{}",
+            aps.len(),
+            aps.iter().map(|ap| format!(" \"{}\"", ap)).join("")
+        )
+        .unwrap();
+        writeln!(&mut out, "acc-name: all\nAcceptance: 0 t").unwrap();
+        writeln!(
+            &mut out,
+            "controllable-AP:{}",
+            controllable.map(|i| format!(" {}", i)).join("")
+        )
+        .unwrap();
+        writeln!(&mut out, "--BODY--").unwrap();
+        for state in 0..self.size {
+            writeln!(&mut out, "State: {}", state).unwrap();
+            for (_, input, output, target) in self.transitions.iter().filter(|t| t.0 == state) {
+                let label = if aps.is_empty() {
+                    "t".into()
+                } else {
+                    aps.iter()
+                        .enumerate()
+                        .map(|(i, ap)| {
+                            if input.contains(*ap) || output.contains(*ap) {
+                                i.to_string()
+                            } else {
+                                format!("!{}", i)
+                            }
+                        })
+                        .join("&")
+                };
+                writeln!(&mut out, "  [{}] {}", label, target).unwrap();
+            }
+        }
+        write!(&mut out, "--END--").unwrap();
+        out
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+
+        writeln!(&mut out, "digraph g {{\nmindist = 2.0").unwrap();
+        for (source, input, output, target) in &self.transitions {
+            writeln!(
+                &mut out,
+                "\"s{}\" -> {{\"s{}\"}} [label = \"{} / {}\"]",
+                source,
+                target,
+                Controller::print_valuation(&self.inputs, input),
+                Controller::print_valuation(&self.outputs, output)
+            )
+            .unwrap();
+        }
+        writeln!(&mut out, "init [label=\"\", shape=point]\ninit -> \"s0\"").unwrap();
+
+        out.push('}');
+        out.push('\n');
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ltl::Formula;
+    use parity::Graph;
+
+    use super::*;
+
+    fn synth(formula: &str) -> Synthesis {
+        let formula = Formula::parse(formula).unwrap();
+        synthesize(&formula, &["r".into()], &["g".into()], 2, Graph::zielonka).unwrap()
+    }
+
+    #[test]
+    pub fn response_is_realizable() {
+        assert!(matches!(synth("G | !r F g"), Synthesis::Realizable(_)));
+    }
+
+    #[test]
+    pub fn predicting_inputs_is_unrealizable() {
+        assert!(matches!(
+            synth("G & | !g X r | g X !r"),
+            Synthesis::Unrealizable(_)
+        ));
+    }
+
+    #[test]
+    pub fn undeclared_propositions() {
+        let formula = Formula::parse("G a").unwrap();
+        assert!(synthesize(&formula, &[], &[], 2, Graph::zielonka).is_err());
+    }
+
+    #[test]
+    pub fn parity_games_decide_beyond_the_bound() {
+        let (inputs, outputs) = (valuations(&["r".into()]), valuations(&["g".into()]));
+        for (formula, realizable) in [
+            ("G | !r F g", true),
+            ("G & | !g X r | g X !r", false),
+            ("& G F r G F g", false),
+            ("| F G !r G F g", true),
+            ("F G g", true),
+            ("G F r", false),
+        ] {
+            let formula = Formula::parse(formula).unwrap();
+            let dpa = ltl_to_gnba(&formula).gnba_to_nba().reduce().determinize();
+            for solver in [
+                Graph::zielonka as Solver,
+                Graph::fpi,
+                Graph::tangle,
+                Graph::spm,
+            ] {
+                for (player, wins) in [
+                    (Player::System, realizable),
+                    (Player::Environment, !realizable),
+                ] {
+                    let game = Game::parity(&dpa, player, &inputs, &outputs);
+                    let won = game.solve(solver).is_some();
+                    assert_eq!(won, wins, "'{}' for {:?}", formula, player);
+                }
+            }
+        }
+
+        // No visit to a rejecting state is allowed with bound 0, so the parity game decides
+        let response = Formula::parse("G | !r F g").unwrap();
+        let synthesis = synthesize(&response, &["r".into()], &["g".into()], 0, Graph::zielonka);
+        assert!(matches!(synthesis.unwrap(), Synthesis::Realizable(_)));
+    }
+}
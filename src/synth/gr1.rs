@@ -0,0 +1,520 @@
+// Synthesis for the GR(1) fragment
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use ltl::{Expr, Formula};
+
+use super::{Controller, Player};
+
+/// Valuations are enumerated explicitly, which only works for few propositions
+const MAX_PROPOSITIONS: usize = 12;
+
+/// Propositional formula over the valuations of the current and the next step, the variables
+/// index the inputs followed by the outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Prop {
+    Const(bool),
+    Var { index: usize, next: bool },
+    Not(Box<Prop>),
+    And(Box<Prop>, Box<Prop>),
+    Or(Box<Prop>, Box<Prop>),
+}
+
+impl Prop {
+    /// `None` if the expression is not a propositional formula with `X` only on propositions
+    fn compile(expr: &Expr, vars: &[String], next: bool) -> Option<Self> {
+        let compile = |e: &Expr| Prop::compile(e, vars, next).map(Box::new);
+        Some(match expr {
+            Expr::True => Prop::Const(true),
+            Expr::False => Prop::Const(false),
+            Expr::Atomic(ap) => Prop::Var {
+                index: vars.iter().position(|v| v == ap)?,
+                next,
+            },
+            Expr::Not(e) => Prop::Not(compile(e)?),
+            Expr::And(lhs, rhs) => Prop::And(compile(lhs)?, compile(rhs)?),
+            Expr::Or(lhs, rhs) => Prop::Or(compile(lhs)?, compile(rhs)?),
+            Expr::Next(e) if !next => Prop::compile(e, vars, true)?,
+            _ => return None,
+        })
+    }
+
+    fn eval(&self, current: usize, next: usize) -> bool {
+        match self {
+            Prop::Const(b) => *b,
+            Prop::Var { index, next: false } => current >> index & 1 == 1,
+            Prop::Var { index, next: true } => next >> index & 1 == 1,
+            Prop::Not(p) => !p.eval(current, next),
+            Prop::And(lhs, rhs) => lhs.eval(current, next) && rhs.eval(current, next),
+            Prop::Or(lhs, rhs) => lhs.eval(current, next) || rhs.eval(current, next),
+        }
+    }
+
+    /// The variables together with whether they refer to the next step
+    fn vars(&self) -> Vec<(usize, bool)> {
+        match self {
+            Prop::Const(_) => vec![],
+            Prop::Var { index, next } => vec![(*index, *next)],
+            Prop::Not(p) => p.vars(),
+            Prop::And(lhs, rhs) | Prop::Or(lhs, rhs) => {
+                let mut vars = lhs.vars();
+                vars.extend(rhs.vars());
+                vars
+            }
+        }
+    }
+
+    /// Whether the formula only refers to the current step
+    fn is_current(&self) -> bool {
+        self.vars().iter().all(|(_, next)| !next)
+    }
+
+    /// The same formula evaluated on the next step
+    fn at_next(&self) -> Self {
+        match self {
+            Prop::Const(b) => Prop::Const(*b),
+            Prop::Var { index, .. } => Prop::Var {
+                index: *index,
+                next: true,
+            },
+            Prop::Not(p) => Prop::Not(Box::new(p.at_next())),
+            Prop::And(lhs, rhs) => Prop::And(Box::new(lhs.at_next()), Box::new(rhs.at_next())),
+            Prop::Or(lhs, rhs) => Prop::Or(Box::new(lhs.at_next()), Box::new(rhs.at_next())),
+        }
+    }
+}
+
+/// Initial conditions, safety constraints and justice requirements of one player
+#[derive(Debug, Default)]
+struct Requirements {
+    init: Vec<Prop>,
+    trans: Vec<Prop>,
+    justice: Vec<Prop>,
+}
+
+impl Requirements {
+    fn from_conjuncts(conjuncts: Vec<&Expr>, vars: &[String]) -> Option<Self> {
+        let mut requirements = Requirements::default();
+        for c in conjuncts {
+            match c {
+                Expr::Globally(e) => requirements.add_globally(e, vars)?,
+                _ => {
+                    let p = Prop::compile(c, vars, false)?;
+                    if !p.is_current() {
+                        return None;
+                    }
+                    requirements.init.push(p);
+                }
+            }
+        }
+        Some(requirements)
+    }
+
+    fn add_globally(&mut self, expr: &Expr, vars: &[String]) -> Option<()> {
+        for c in conjuncts(expr) {
+            match c {
+                Expr::Globally(e) => self.add_globally(e, vars)?,
+                Expr::Finally(e) => {
+                    let p = Prop::compile(e, vars, false)?;
+                    if !p.is_current() {
+                        return None;
+                    }
+                    self.justice.push(p);
+                }
+                _ => {
+                    let p = Prop::compile(c, vars, false)?;
+                    // Invariants have to hold initially and after every step
+                    if p.is_current() {
+                        self.init.push(p.clone());
+                        self.trans.push(p.at_next());
+                    } else {
+                        self.trans.push(p);
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Justice requirements of the player, `true` if there are none
+    fn justice(&self) -> Vec<Prop> {
+        if self.justice.is_empty() {
+            vec![Prop::Const(true)]
+        } else {
+            self.justice.clone()
+        }
+    }
+}
+
+fn conjuncts(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::And(lhs, rhs) => {
+            let mut result = conjuncts(lhs);
+            result.extend(conjuncts(rhs));
+            result
+        }
+        _ => vec![expr],
+    }
+}
+
+/// A GR(1) specification over the given inputs and outputs: `assumptions -> guarantees` where
+/// both sides consist of initial conditions, safety constraints over the current and the next
+/// step (`G` of formulas using `X` only directly on propositions) and justice requirements (`G F`
+/// of propositions)
+#[derive(Debug)]
+pub struct Gr1 {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    environment: Requirements,
+    system: Requirements,
+}
+
+impl Gr1 {
+    /// Split the formula into assumptions and guarantees, `None` if it is not in the fragment
+    pub fn from_formula(formula: &Formula, inputs: &[String], outputs: &[String]) -> Option<Self> {
+        if inputs.len() + outputs.len() > MAX_PROPOSITIONS {
+            return None;
+        }
+        let vars = inputs.iter().chain(outputs).cloned().collect::<Vec<_>>();
+
+        // Nested implications a -> (b -> g) are collected into a single one
+        let mut assumptions = vec![];
+        let mut guarantees = &formula.root_expr;
+        while let Expr::Or(lhs, rhs) = guarantees {
+            match &**lhs {
+                Expr::Not(a) => {
+                    assumptions.extend(conjuncts(a));
+                    guarantees = rhs;
+                }
+                _ => break,
+            }
+        }
+
+        let environment = Requirements::from_conjuncts(assumptions, &vars)?;
+        let system = Requirements::from_conjuncts(conjuncts(guarantees), &vars)?;
+        // The environment can neither choose nor constrain the outputs of the next step
+        let is_input = |(index, _): &(usize, bool)| *index < inputs.len();
+        let is_controllable = |(index, next): &(usize, bool)| !next || *index < inputs.len();
+        if !environment
+            .init
+            .iter()
+            .flat_map(Prop::vars)
+            .all(|v| is_input(&v))
+            || !environment
+                .trans
+                .iter()
+                .flat_map(Prop::vars)
+                .all(|v| is_controllable(&v))
+        {
+            return None;
+        }
+
+        Some(Gr1 {
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+            environment,
+            system,
+        })
+    }
+
+    pub fn assumptions(&self) -> usize {
+        let e = &self.environment;
+        e.init.len() + e.trans.len() + e.justice.len()
+    }
+
+    pub fn guarantees(&self) -> usize {
+        let s = &self.system;
+        s.init.len() + s.trans.len() + s.justice.len()
+    }
+
+    /// Solve the game, `None` if the specification is not strictly realizable
+    ///
+    /// The game is played on the valuations of the propositions and solved with the nested
+    /// fixpoint of Piterman, Pnueli and Sa'ar, which is a lot cheaper than bounded synthesis. In a
+    /// strictly realizable specification the system may only violate its safety constraints after
+    /// the environment violated its own ones. This implies realizability of the implication but
+    /// not the other way around, so losing the game is not conclusive.
+    pub fn solve(&self) -> Option<Controller> {
+        let game = Game::new(self);
+        let winning = game.solve();
+
+        // Every initial input allowed by the assumptions needs an initial output into the
+        // winning region
+        let initial_outputs = (0..game.inputs)
+            .map(|x| {
+                if !self.environment.init.iter().all(|p| p.eval(x, x)) {
+                    return Some(None);
+                }
+                (0..game.outputs)
+                    .map(|y| x | y << self.inputs.len())
+                    .find(|&s| winning.z[s] && self.system.init.iter().all(|p| p.eval(s, s)))
+                    .map(Some)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(self.controller(&game, &winning, &initial_outputs))
+    }
+
+    /// Mealy machine implementing the winning strategy, the memory is the last valuation and the
+    /// index of the justice requirement the system is working towards
+    fn controller(&self, game: &Game, winning: &Winning, initial: &[Option<usize>]) -> Controller {
+        // Controller states: 0 is initial, then (valuation, goal) pairs, `None` is the sink entered
+        // once the environment violated its safety assumptions
+        let mut states: HashMap<Option<(usize, usize)>, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut transitions = vec![];
+        let mut size = 1;
+        let mut state_of = |key: Option<(usize, usize)>, queue: &mut VecDeque<_>| {
+            *states.entry(key).or_insert_with(|| {
+                queue.push_back((key, size));
+                size += 1;
+                size - 1
+            })
+        };
+
+        for (x, choice) in initial.iter().enumerate() {
+            let (output, target) = match choice {
+                Some(s) => (s >> self.inputs.len(), state_of(Some((*s, 0)), &mut queue)),
+                None => (0, state_of(None, &mut queue)),
+            };
+            transitions.push((0, x, output, target));
+        }
+
+        while let Some((key, id)) = queue.pop_front() {
+            for x in 0..game.inputs {
+                let (output, target) = match key.and_then(|(s, j)| {
+                    let m = game.moves[s].iter().find(|(input, _)| *input == x)?;
+                    Some(winning.choose(game, s, j, &m.1))
+                }) {
+                    Some((t, j)) => (t >> self.inputs.len(), state_of(Some((t, j)), &mut queue)),
+                    None => (0, state_of(None, &mut queue)),
+                };
+                transitions.push((id, x, output, target));
+            }
+        }
+
+        let valuation = |aps: &[String], bits: usize| -> BTreeSet<String> {
+            aps.iter()
+                .enumerate()
+                .filter(|(i, _)| bits >> i & 1 == 1)
+                .map(|(_, ap)| ap.clone())
+                .collect()
+        };
+        Controller {
+            player: Player::System,
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            size,
+            transitions: transitions
+                .into_iter()
+                .map(|(s, x, y, t)| {
+                    (
+                        s,
+                        valuation(&self.inputs, x),
+                        valuation(&self.outputs, y),
+                        t,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The game graph, a vertex is a valuation with the inputs in the low bits
+struct Game {
+    inputs: usize,
+    outputs: usize,
+    /// For every vertex the inputs allowed by the assumptions and the successors allowed by the
+    /// guarantees after reading them
+    moves: Vec<Vec<(usize, Vec<usize>)>>,
+    env_justice: Vec<Vec<bool>>,
+    sys_justice: Vec<Vec<bool>>,
+}
+
+/// Result of the fixpoint computation
+struct Winning {
+    z: Vec<bool>,
+    /// For every justice requirement of the system the layers of the attractor, every layer is
+    /// split by the justice requirements of the environment
+    layers: Vec<Vec<Vec<Vec<bool>>>>,
+    /// Vertices with a move into `z`
+    cpre_z: Vec<bool>,
+}
+
+impl Game {
+    fn new(spec: &Gr1) -> Self {
+        let inputs = 1 << spec.inputs.len();
+        let outputs = 1 << spec.outputs.len();
+        let size = inputs * outputs;
+        let moves = (0..size)
+            .map(|s| {
+                (0..inputs)
+                    .filter(|&x| spec.environment.trans.iter().all(|p| p.eval(s, x)))
+                    .map(|x| {
+                        let targets = (0..outputs)
+                            .map(|y| x | y << spec.inputs.len())
+                            .filter(|&t| spec.system.trans.iter().all(|p| p.eval(s, t)))
+                            .collect();
+                        (x, targets)
+                    })
+                    .collect()
+            })
+            .collect();
+        let evaluate = |justice: Vec<Prop>| {
+            justice
+                .iter()
+                .map(|p| (0..size).map(|s| p.eval(s, s)).collect())
+                .collect()
+        };
+
+        Game {
+            inputs,
+            outputs,
+            moves,
+            env_justice: evaluate(spec.environment.justice()),
+            sys_justice: evaluate(spec.system.justice()),
+        }
+    }
+
+    /// Whether the system can force the next vertex into the set from `s`
+    fn cpre_at(&self, s: usize, set: &[bool]) -> bool {
+        self.moves[s]
+            .iter()
+            .all(|(_, targets)| targets.iter().any(|&t| set[t]))
+    }
+
+    fn cpre(&self, set: &[bool]) -> Vec<bool> {
+        (0..set.len()).map(|s| self.cpre_at(s, set)).collect()
+    }
+
+    fn solve(&self) -> Winning {
+        let size = self.moves.len();
+        let mut z = vec![true; size];
+        loop {
+            let cpre_z = self.cpre(&z);
+            let mut next_z = z.clone();
+            let mut layers = vec![];
+            for sys_justice in &self.sys_justice {
+                let goal = and(sys_justice, &cpre_z);
+                let mut y = vec![false; size];
+                let mut rings = vec![];
+                loop {
+                    let base = or(&goal, &self.cpre(&y));
+                    let xs = self
+                        .env_justice
+                        .iter()
+                        .map(|env_justice| {
+                            let mut x = vec![true; size];
+                            loop {
+                                let cpre_x = self.cpre(&x);
+                                let next_x = (0..size)
+                                    .map(|s| base[s] || (!env_justice[s] && cpre_x[s]))
+                                    .collect::<Vec<_>>();
+                                if next_x == x {
+                                    break x;
+                                }
+                                x = next_x;
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    let next_y = xs.iter().fold(vec![false; size], |y, x| or(&y, x));
+                    if next_y == y {
+                        break;
+                    }
+                    rings.push(xs);
+                    y = next_y;
+                }
+                next_z = and(&next_z, &y);
+                layers.push(rings);
+            }
+
+            if next_z == z {
+                return Winning { z, layers, cpre_z };
+            }
+            z = next_z;
+        }
+    }
+}
+
+impl Winning {
+    /// Pick the successor of `s` among the targets while working towards justice requirement
+    /// `j`, returns the successor and the next requirement
+    fn choose(&self, game: &Game, s: usize, j: usize, targets: &[usize]) -> (usize, usize) {
+        let pick = |set: &[bool]| {
+            *targets
+                .iter()
+                .find(|&&t| set[t])
+                .expect("The winning region is closed under the strategy")
+        };
+        if game.sys_justice[j][s] && self.cpre_z[s] {
+            return (pick(&self.z), (j + 1) % game.sys_justice.len());
+        }
+
+        let rings = &self.layers[j];
+        let rank = rings
+            .iter()
+            .position(|xs| xs.iter().any(|x| x[s]))
+            .expect("The winning region is covered by the layers");
+        if rank > 0 {
+            let lower = rings[rank - 1]
+                .iter()
+                .fold(vec![false; self.z.len()], |y, x| or(&y, x));
+            if game.cpre_at(s, &lower) {
+                return (pick(&lower), j);
+            }
+        }
+        // Stay in the layer while the environment violates one of its justice requirements
+        let x = rings[rank]
+            .iter()
+            .zip(&game.env_justice)
+            .find(|(x, env_justice)| x[s] && !env_justice[s] && game.cpre_at(s, x))
+            .map(|(x, _)| x)
+            .expect("Vertices in a layer either progress or stay in it");
+        (pick(x), j)
+    }
+}
+
+fn and(lhs: &[bool], rhs: &[bool]) -> Vec<bool> {
+    lhs.iter().zip(rhs).map(|(a, b)| *a && *b).collect()
+}
+
+fn or(lhs: &[bool], rhs: &[bool]) -> Vec<bool> {
+    lhs.iter().zip(rhs).map(|(a, b)| *a || *b).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn spec(formula: &str) -> Option<Gr1> {
+        Gr1::from_formula(
+            &Formula::parse(formula).unwrap(),
+            &["r".into()],
+            &["g".into()],
+        )
+    }
+
+    #[test]
+    pub fn detection() {
+        assert!(spec("G | !r X g").is_some());
+        assert!(spec("& g G F g").is_some());
+        assert!(spec("| !& !r G F r G F g").is_some());
+        assert!(spec("G | !r F g").is_none());
+        assert!(spec("G X X g").is_none());
+        // The environment cannot constrain the next output
+        assert!(spec("| !G X g G F g").is_none());
+    }
+
+    #[test]
+    pub fn solve() {
+        assert!(spec("& G | !r X g G F g").unwrap().solve().is_some());
+        // The system would have to predict the next input
+        assert!(spec("G & | !g X r | g X !r").unwrap().solve().is_none());
+        // Grants are only allowed with a request, so the system relies on infinitely many
+        assert!(spec("& G | !g r G F g").unwrap().solve().is_none());
+        let controller = spec("| !G F r & G | !g r G F g").unwrap().solve().unwrap();
+        for (_, input, output, _) in &controller.transitions {
+            assert!(!output.contains("g") || input.contains("r"));
+        }
+    }
+}
@@ -0,0 +1,319 @@
+// On the fly translation of LTL formulas to GNBAs
+
+use std::collections::{BTreeSet, HashMap};
+
+use buchi::nba::Buchi;
+use itertools::Itertools;
+use ltl::alphabet::Literals;
+use ltl::intern::{ExprId, ExprTable, Node};
+use ltl::{Expr, Formula};
+
+#[derive(Clone)]
+struct TableauNode {
+    /// Predecessors of the node, `None` is the initial pseudo node
+    incoming: BTreeSet<Option<usize>>,
+    /// Obligations which still have to be expanded
+    new: Vec<ExprId>,
+    /// Expanded obligations of the current position
+    old: BTreeSet<ExprId>,
+    /// Obligations of the next position
+    next: BTreeSet<ExprId>,
+}
+
+impl TableauNode {
+    fn successor_of(index: Option<usize>, new: Vec<ExprId>) -> Self {
+        TableauNode {
+            incoming: BTreeSet::from([index]),
+            new,
+            old: BTreeSet::new(),
+            next: BTreeSet::new(),
+        }
+    }
+}
+
+/// Translate the formula into a GNBA, edges are labeled with the literals of their source
+///
+/// The tableau construction by Gerth, Peled, Vardi and Wolper expands the obligations of the
+/// formula into nodes recording what has to hold now and at the next position. Only nodes
+/// reachable from the formula are created, in contrast to `ltl_to_gnba_powerset`.
+pub fn ltl_to_gnba(formula: &Formula) -> Buchi {
+    let formula = formula.pnf();
+    let mut table = ExprTable::new();
+    let root = table.intern(&formula.root_expr);
+    let nodes = expand(&mut table, root);
+    let untils = formula
+        .closure()
+        .iter()
+        .map(|e| table.intern(e))
+        .collect_vec()
+        .into_iter()
+        .filter(|&e| matches!(table.node(e), Node::Until(_, _)))
+        .collect_vec();
+
+    let print_set = |set: &mut dyn Iterator<Item = &ExprId>| {
+        Expr::print_set(&set.map(|id| table.expr(*id)).collect())
+    };
+    let aps = formula.aps();
+    let mut gnba = Buchi::new();
+    gnba.set_aps(aps.clone());
+    let states = nodes
+        .iter()
+        .map(|n| gnba.new_labeled_state(print_set(&mut n.old.iter())))
+        .collect_vec();
+    let labels = nodes
+        .iter()
+        .map(|n| {
+            n.old
+                .iter()
+                .filter_map(|e| aps.literal(&table.expr(*e)))
+                .fold(Literals::empty(), |l, literal| {
+                    l.and(&literal)
+                        .expect("Nodes with contradicting literals are dropped")
+                })
+        })
+        .collect_vec();
+
+    for (node, state) in nodes.iter().zip(&states) {
+        for source in &node.incoming {
+            match source {
+                None => gnba.set_initial_state(*state),
+                Some(i) => gnba.add_guarded_transition(states[*i], *state, labels[*i]),
+            }
+        }
+    }
+
+    // Every until has to be fulfilled eventually
+    for until in untils {
+        if let Node::Until(_, rhs) = table.node(until) {
+            gnba.add_accepting_set(
+                nodes
+                    .iter()
+                    .zip(&states)
+                    .filter(|(n, _)| !n.old.contains(&until) || n.old.contains(rhs))
+                    .map(|(_, s)| *s),
+            );
+        }
+    }
+
+    gnba
+}
+
+/// Expand the formula into fully expanded nodes, the formula has to be in PNF
+fn expand(table: &mut ExprTable, root: ExprId) -> Vec<TableauNode> {
+    let mut nodes: Vec<TableauNode> = vec![];
+    let mut index: HashMap<(BTreeSet<ExprId>, BTreeSet<ExprId>), usize> = HashMap::new();
+    let mut pending = vec![TableauNode::successor_of(None, vec![root])];
+
+    while let Some(mut node) = pending.pop() {
+        let Some(expr) = node.new.pop() else {
+            // Merge with an equally expanded node or continue with the next position
+            let key = (node.old.clone(), node.next.clone());
+            match index.get(&key) {
+                Some(&i) => nodes[i].incoming.extend(node.incoming),
+                None => {
+                    index.insert(key, nodes.len());
+                    pending.push(TableauNode::successor_of(
+                        Some(nodes.len()),
+                        node.next.iter().copied().collect(),
+                    ));
+                    nodes.push(node);
+                }
+            }
+            continue;
+        };
+        if node.old.contains(&expr) {
+            pending.push(node);
+            continue;
+        }
+
+        // The alternative obligations of disjunctive operators
+        let mut alternative = None;
+        match *table.node(expr) {
+            // Contradicting obligations, the node is dropped
+            Node::False => continue,
+            Node::True => {}
+            Node::Atomic(_) | Node::Not(_) => {
+                if node.old.contains(&table.negated(expr)) {
+                    continue;
+                }
+            }
+            Node::And(lhs, rhs) => node.new.extend([lhs, rhs]),
+            Node::Next(e) => {
+                node.next.insert(e);
+            }
+            Node::Or(lhs, rhs) => {
+                let mut other = node.clone();
+                other.new.push(rhs);
+                alternative = Some(other);
+                node.new.push(lhs);
+            }
+            // a U b = b | (a & X (a U b))
+            Node::Until(lhs, rhs) => {
+                let mut other = node.clone();
+                other.new.push(lhs);
+                other.next.insert(expr);
+                alternative = Some(other);
+                node.new.push(rhs);
+            }
+            // a R b = (a & b) | (b & X (a R b))
+            Node::Release(lhs, rhs) => {
+                let mut other = node.clone();
+                other.new.push(rhs);
+                other.next.insert(expr);
+                alternative = Some(other);
+                node.new.extend([lhs, rhs]);
+            }
+            Node::Globally(_)
+            | Node::Finally(_)
+            | Node::WeakUntil(_, _)
+            | Node::StrongRelease(_, _)
+            | Node::BoundedFinally(_, _)
+            | Node::BoundedGlobally(_, _)
+            | Node::BoundedUntil(_, _, _) => {
+                unreachable!("These operators do not occur in PNF")
+            }
+        }
+
+        node.old.insert(expr);
+        if let Some(mut other) = alternative {
+            other.old.insert(expr);
+            pending.push(other);
+        }
+        pending.push(node);
+    }
+
+    nodes
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use ltl::arbitrary::{formulas, APS};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::transform::{ltl_to_gnba_powerset, IndexedNba};
+
+    /// Search the product of both NBAs for an accepting lasso, the flag tracks whether an
+    /// accepting state of the first automaton has been visited since the last one of the second
+    fn intersect(a: &IndexedNba, b: &IndexedNba) -> bool {
+        // Both automata are translated from formulas over the same propositions, unless one of
+        // them is unsatisfiable and has no states at all
+        if a.initial_states.is_empty() || b.initial_states.is_empty() {
+            return false;
+        }
+        assert_eq!(a.aps, b.aps);
+        let compatible = |la: &Literals, lb: &Literals| la.and(lb).is_some();
+        let successors = |&(p, q, flag): &(usize, usize, bool)| {
+            let flag = if flag {
+                !b.accepting[q]
+            } else {
+                a.accepting[p]
+            };
+            let mut successors = vec![];
+            for (la, p_next) in &a.edges[p] {
+                for (lb, q_next) in &b.edges[q] {
+                    if compatible(la, lb) {
+                        successors.push((*p_next, *q_next, flag));
+                    }
+                }
+            }
+            successors
+        };
+        let initial = a
+            .initial_states
+            .iter()
+            .cartesian_product(&b.initial_states)
+            .map(|(p, q)| (*p, *q, false))
+            .collect_vec();
+        // Accepting states lie on a cycle iff they reach themselves
+        let mut reachable: HashSet<_> = initial.iter().cloned().collect();
+        let mut stack = initial;
+        while let Some(s) = stack.pop() {
+            for next in successors(&s) {
+                if reachable.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        reachable
+            .iter()
+            .filter(|(_, q, flag)| *flag && b.accepting[*q])
+            .any(|seed| {
+                let mut seen = HashSet::new();
+                let mut stack = vec![*seed];
+                while let Some(s) = stack.pop() {
+                    for next in successors(&s) {
+                        if next == *seed {
+                            return true;
+                        }
+                        if seen.insert(next) {
+                            stack.push(next);
+                        }
+                    }
+                }
+                false
+            })
+    }
+
+    #[test]
+    pub fn same_language_as_powerset() {
+        for f in [
+            "a",
+            "X !a",
+            "U a b",
+            "R a b",
+            "G | !r F g",
+            "& G F a G F b",
+            "| X X a U b !c",
+            "& F a G !a",
+            "U[<=2] a F[<=1] b",
+        ] {
+            let formula = Formula::parse(f).unwrap();
+            let nba = |f: &Formula| IndexedNba::new(&ltl_to_gnba(f).gnba_to_nba());
+            let powerset = |f: &Formula| IndexedNba::new(&ltl_to_gnba_powerset(f).gnba_to_nba());
+            assert!(
+                !intersect(&nba(&formula), &powerset(&formula.negation())),
+                "{} accepts too much",
+                f
+            );
+            assert!(
+                !intersect(&powerset(&formula), &nba(&formula.negation())),
+                "{} accepts too little",
+                f
+            );
+        }
+    }
+
+    proptest! {
+        // The powerset construction grows quickly, so the formulas are kept shallow
+        #[test]
+        fn random_formulas_same_language_as_powerset(formula in formulas(&APS, 2)) {
+            let nba = |f: &Formula| IndexedNba::new(&ltl_to_gnba(f).gnba_to_nba());
+            let powerset = |f: &Formula| IndexedNba::new(&ltl_to_gnba_powerset(f).gnba_to_nba());
+            prop_assert!(!intersect(&nba(&formula), &powerset(&formula.negation())));
+            prop_assert!(!intersect(&powerset(&formula), &nba(&formula.negation())));
+        }
+    }
+
+    #[test]
+    pub fn only_reachable_states() {
+        let formula = Formula::parse("U a b").unwrap();
+        // The powerset construction creates all five elementary sets
+        assert_eq!(ltl_to_gnba_powerset(&formula).states().len(), 5);
+        assert_eq!(ltl_to_gnba(&formula).states().len(), 3);
+
+        // Contradictions have no states at all
+        let contradiction = Formula::parse("& a !a").unwrap();
+        assert!(ltl_to_gnba(&contradiction).states().is_empty());
+    }
+
+    #[test]
+    pub fn unconstrained_letters() {
+        // Positions the formula says nothing about are printed as true, the trace violates X X a
+        let negation = Formula::parse("X X a").unwrap().negation();
+        let trace = ltl_to_gnba(&negation).verify().unwrap_err();
+        assert_eq!(trace.to_string(), "true, true, ¬a, (true)ʷ");
+    }
+}
@@ -1,145 +1,196 @@
 // Transform an LTL formula to a GNBA/NBA
 
-use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::Infallible;
 
-use buchi::nba::Buchi;
+use buchi::nba::{Buchi, State};
 use itertools::Itertools;
+use ltl::alphabet::{ApSet, Literals};
+use ltl::intern::{ExprId, ExprTable, Node};
+use ltl::xml::{Atom, IntegerExpr};
 use ltl::{Expr, Formula};
-use petri::PetriNet;
-
-pub fn _ts_and_buchi_product(ts: Buchi, a: Buchi) -> Buchi {
-    let mut product = Buchi::new();
-    let mut states = HashMap::new();
-    for ts_transitions in ts.transitions() {
-        for a_transitions in a.transitions() {
-            if a_transitions.label == ts_transitions.to {
-                let source_label = format!(
-                    "<s{},q{}>({},{})",
-                    ts_transitions.from_state.id,
-                    a_transitions.from_state.id,
-                    ts_transitions.from,
-                    a_transitions.from
-                );
-                let target_label = format!(
-                    "<s{},q{}>({},{})",
-                    ts_transitions.to_state.id,
-                    a_transitions.to_state.id,
-                    ts_transitions.to,
-                    a_transitions.to
-                );
-
-                let source_state = states
-                    .entry(source_label.clone())
-                    .or_insert_with(|| product.new_labeled_state(source_label))
-                    .clone();
-                let target_state = states
-                    .entry(target_label.clone())
-                    .or_insert_with(|| product.new_labeled_state(target_label));
-
-                product.add_transition(source_state, *target_state, ts_transitions.label);
+use petri::{Marking, PetriNet};
+use serde_derive::{Deserialize, Serialize};
+use ts::TransitionSystem;
+
+use crate::tableau;
+
+/// The atom an atomic proposition of a Petri net property stands for, which every engine
+/// evaluates. As in the reachability graph a name is fireable if it is a transition and otherwise
+/// has a token if it is a place. Other names and comparisons over unknown places are errors.
+pub fn petri_atom(net: &PetriNet, proposition: &str) -> petri::Result<Atom> {
+    match Atom::from_name(proposition) {
+        Atom::Fireable(t) if net.has_transition(&t) => Ok(Atom::Fireable(t)),
+        Atom::Fireable(p) if net.has_place(&p) => Ok(Atom::IntegerLe(
+            IntegerExpr::Constant(1),
+            IntegerExpr::TokensCount(vec![p]),
+        )),
+        Atom::Fireable(name) => Err(petri::Error::UnknownNode(name)),
+        Atom::IntegerLe(lhs, rhs) => {
+            for expr in [&lhs, &rhs] {
+                if let IntegerExpr::TokensCount(places) = expr {
+                    if let Some(place) = places.iter().find(|p| !net.has_place(p)) {
+                        return Err(petri::Error::UnknownPlace(place.clone()));
+                    }
+                }
             }
+            Ok(Atom::IntegerLe(lhs, rhs))
         }
     }
+}
 
-    for s0 in ts.initial_states() {
-        for q_t in a
-            .transitions()
-            .iter()
-            .filter(|t| a.initial_states().contains(&t.from_state))
-        {
-            if q_t.label == ts.label(s0).unwrap() {
-                let init_label = format!(
-                    "<s{},q{}>({},{})",
-                    s0.id,
-                    q_t.to_state.id,
-                    ts.label(s0).unwrap(),
-                    q_t.to
-                );
-                let init_state = states
-                    .entry(init_label.clone())
-                    .or_insert_with(|| product.new_labeled_state(init_label));
-                product.set_initial_state(*init_state);
+/// Evaluate an atomic proposition of a Petri net property in a marking, `fireable` are the labels
+/// of the transitions enabled in the marking
+pub fn petri_atom_holds(
+    net: &PetriNet,
+    marking: &Marking,
+    fireable: &[&str],
+    atom: &Atom,
+) -> petri::Result<bool> {
+    match atom {
+        Atom::Fireable(t) => Ok(fireable.contains(&t.as_str())),
+        Atom::IntegerLe(lhs, rhs) => {
+            let mut error = None;
+            let mut tokens = |place: &str| {
+                net.tokens(marking, place).unwrap_or_else(|e| {
+                    error = Some(e);
+                    0
+                })
+            };
+            let holds = lhs.evaluate(&mut tokens) <= rhs.evaluate(&mut tokens);
+            match error {
+                Some(e) => Err(e),
+                None => Ok(holds),
             }
         }
     }
+}
 
-    product
+/// NBA of a formula with states numbered from 0 and edge labels parsed into literals
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IndexedNba {
+    pub aps: ApSet,
+    pub initial_states: Vec<usize>,
+    pub accepting: Vec<bool>,
+    pub edges: Vec<Vec<(Literals, usize)>>,
 }
 
-pub fn petri_to_gnba(net: PetriNet) -> Buchi {
-    // Collect all markings
-    let mut gnba = Buchi::new();
-    let mut states = HashMap::new();
+impl IndexedNba {
+    /// Whether every word is accepted from the state, as it is accepting and loops on every
+    /// letter
+    pub fn universal(&self, q: usize) -> bool {
+        self.accepting[q] && self.edges[q].contains(&(Literals::empty(), q))
+    }
 
-    let initial_marking = net.initial_marking();
-    let initial_label = petri_state_to_string(&initial_marking.active_transitions(&net));
-    let initial_state = gnba.new_labeled_state(initial_label.clone());
-    states.insert(initial_label, initial_state);
-    gnba.set_initial_state(initial_state);
+    /// Translate the formula and shrink the NBA before it is used in a product
+    pub fn from_formula(formula: &Formula) -> Self {
+        Self::translate(formula).0
+    }
 
-    // Visit all markings and fill up gnba as we go
-    let mut visited = HashSet::new();
-    let mut queue = VecDeque::new();
-    queue.push_back(initial_marking.clone());
-    visited.insert(initial_marking);
+    /// Translate like `from_formula`, also giving the states of the GNBA and of the NBA before
+    /// it is shrunk
+    pub fn translate(formula: &Formula) -> (Self, usize, usize) {
+        let gnba = ltl_to_gnba(formula);
+        let nba = gnba.gnba_to_nba();
+        let nba_states = nba.stats().states;
+        (Self::new(&nba.reduce()), gnba.stats().states, nba_states)
+    }
 
-    while let Some(marking) = queue.pop_front() {
-        let next_markings = net
-            .transitions(&marking)
-            .expect("Markings are inconsistent with petri net, this shouldn't happen");
-        for (label, m) in next_markings {
-            // Insert transition into gnba
-            let source_label = petri_state_to_string(&marking.active_transitions(&net));
-            let target_label = petri_state_to_string(&m.active_transitions(&net));
+    pub fn new(nba: &Buchi) -> Self {
+        let index: HashMap<State, usize> = nba
+            .states()
+            .into_iter()
+            .sorted()
+            .enumerate()
+            .map(|(i, s)| (s, i))
+            .collect();
+
+        let accepting_states: HashSet<State> =
+            nba.accepting_sets().iter().flatten().cloned().collect();
+        // Without acceptance sets every infinite run of the nba is accepting
+        let accepting = nba
+            .states()
+            .into_iter()
+            .sorted()
+            .map(|s| nba.accepting_sets().is_empty() || accepting_states.contains(&s))
+            .collect();
+
+        let mut edges = vec![vec![]; index.len()];
+        for t in nba.transitions() {
+            let literals = t
+                .literals
+                .expect("Transitions of translated automata are guarded");
+            edges[index[&t.from_state]].push((literals, index[&t.to_state]));
+        }
 
-            let source_state = states
-                .entry(source_label.clone())
-                .or_insert_with(|| gnba.new_labeled_state(source_label))
-                .clone();
+        IndexedNba {
+            aps: nba.aps().clone(),
+            initial_states: nba
+                .initial_states()
+                .iter()
+                .map(|s| index[s])
+                .sorted()
+                .collect(),
+            accepting,
+            edges,
+        }
+    }
+}
 
-            let target_state = states
-                .entry(target_label.clone())
-                .or_insert_with(|| gnba.new_labeled_state(target_label));
+/// The automaton as a graph with edges labeled by their guards
+impl TransitionSystem for IndexedNba {
+    type State = usize;
+    type Label = Literals;
+    type Error = Infallible;
 
-            gnba.add_transition(source_state, *target_state, label);
-            if !visited.contains(&m) {
-                visited.insert(m.clone());
-                queue.push_back(m);
-            }
-        }
+    fn initial_states(&self) -> Vec<usize> {
+        self.initial_states.clone()
     }
 
-    gnba
+    fn successors(&self, q: &usize) -> Result<Vec<(Literals, usize)>, Infallible> {
+        Ok(self.edges[*q].clone())
+    }
 }
 
-fn petri_state_to_string(active_transitions: &Vec<&str>) -> String {
-    format!(
-        "{{{}}}",
-        active_transitions
-            .iter()
-            .cloned()
-            .sorted()
-            .collect_vec()
-            .join(", ")
-    )
+/// Translate the formula with the on the fly tableau, only reachable states are created
+pub fn ltl_to_gnba(formula: &Formula) -> Buchi {
+    tableau::ltl_to_gnba(formula)
 }
 
-pub fn ltl_to_gnba(formula: &Formula) -> Buchi {
+/// Translate the formula by enumerating all elementary sets of its closure
+pub fn ltl_to_gnba_powerset(formula: &Formula) -> Buchi {
     let mut gnba = Buchi::new();
-    let mut states = HashMap::new();
     let formula = formula.pnf();
-    let closure = formula.closure();
-    let elementary = formula.elementary();
-    let alphabet = formula.alphabet();
-
-    // Populate the states
-    for e in &elementary {
-        states.insert(e, gnba.new_labeled_state(Expr::print_set(e)));
-    }
+    // Work on interned subformulas, the elementary sets share all of their subtrees
+    let mut table = ExprTable::new();
+    let elementary = formula.elementary_ids(&mut table);
+    let closure = formula
+        .closure()
+        .iter()
+        .map(|e| table.intern(e))
+        .collect_vec();
+    let aps = formula.aps();
+    gnba.set_aps(aps.clone());
+    // The literals of the alphabet of the formula
+    let alphabet: Vec<_> = formula
+        .alphabet()
+        .iter()
+        .filter_map(|e| Some((table.intern(e), aps.literal(e)?)))
+        .collect();
+    let root = table.intern(&formula.root_expr);
+    let table = table;
+    let print_set =
+        |set: &BTreeSet<ExprId>| Expr::print_set(&set.iter().map(|id| table.expr(*id)).collect());
+
+    // Populate the states, the index of an elementary set is the index of its state
+    let states = elementary
+        .iter()
+        .map(|e| gnba.new_labeled_state(print_set(e)))
+        .collect_vec();
 
     // Set initial states
-    for (b_set, state) in &states {
-        if b_set.contains(&formula.root_expr) {
+    for (b_set, state) in elementary.iter().zip(&states) {
+        if b_set.contains(&root) {
             gnba.set_initial_state(*state);
         }
     }
@@ -148,146 +199,52 @@ pub fn ltl_to_gnba(formula: &Formula) -> Buchi {
     // TODO this should generate a set of sets of states
     // Then also change the verification procedure
     // This should be simply just checking that all states in one acceptance set are contained within a single SCC
-    for expr in &closure {
-        if let until @ Expr::Until(_, rhs) = expr {
-            let accepting_set = states
+    for &until in &closure {
+        if let Node::Until(_, rhs) = table.node(until) {
+            let accepting_set = elementary
                 .iter()
-                .filter_map(|(b_set, state)| {
-                    if !b_set.contains(until) || b_set.contains(rhs) {
-                        Some(state)
-                    } else {
-                        None
-                    }
-                })
-                .cloned()
+                .zip(&states)
+                .filter(|(b_set, _)| !b_set.contains(&until) || b_set.contains(rhs))
+                .map(|(_, state)| *state)
                 .collect::<HashSet<_>>();
-            gnba.add_accepting_set(accepting_set.into_iter());
+            gnba.add_accepting_set(accepting_set);
         }
     }
 
     // Configure transitions
-    for s in &elementary {
-        let intersection = BTreeSet::from_iter(s.intersection(&alphabet).cloned());
-
-        let label = Expr::print_set(&intersection);
-
-        let mut target_sets = Vec::<BTreeSet<&BTreeSet<Expr>>>::new();
-        for expr in &closure {
-            let potential_targets = if let next @ Expr::Next(ex) = expr {
-                elementary
-                    .iter()
-                    .filter(|s_prime| {
-                        (s.contains(next) && s_prime.contains(ex))
-                            || (!s.contains(next) && !s_prime.contains(ex))
-                    })
-                    .collect()
-            } else if let until @ Expr::Until(a, b) = expr {
-                if s.contains(until) {
-                    elementary
-                        .iter()
-                        .filter(|s_prime| {
-                            s.contains(b) || (s.contains(a) && s_prime.contains(until))
-                        })
-                        .collect()
-                } else {
-                    elementary
-                        .iter()
-                        .filter(|s_prime| {
-                            !(s.contains(b) || (s.contains(a) && s_prime.contains(until)))
-                        })
-                        .collect()
-                }
-            } else if let release @ Expr::Release(a, b) = expr {
-                if s.contains(release) {
-                    elementary
-                        .iter()
-                        .filter(|s_prime| {
-                            (s.contains(a) && s.contains(b))
-                                || (s.contains(b) && s_prime.contains(release))
-                        })
-                        .collect()
-                // If the current state does not contain the release proposition to the opposite
-                } else {
-                    elementary
-                        .iter()
-                        .filter(|s_prime| {
-                            !((s.contains(a) && s.contains(b))
-                                || (s.contains(b) && s_prime.contains(release)))
-                        })
-                        .collect()
-                }
-            } else {
-                continue;
-            };
-
-            target_sets.push(potential_targets);
-        }
-
-        let mut all_states: BTreeSet<_> = elementary.iter().collect();
-        for t in &target_sets {
-            all_states = all_states.intersection(t).cloned().collect();
-        }
-
-        let intersection = all_states;
-
-        // Add the states
-        for t in intersection {
-            gnba.add_transition(
-                *states.get(s).unwrap(),
-                *states.get(t).unwrap(),
-                label.clone(),
-            );
+    for (s, source) in elementary.iter().zip(&states) {
+        let literals = alphabet
+            .iter()
+            .filter(|(id, _)| s.contains(id))
+            .fold(Literals::empty(), |l, (_, literal)| {
+                l.and(literal).expect("Elementary sets are consistent")
+            });
+        for (s_prime, target) in elementary.iter().zip(&states) {
+            if closure.iter().all(|&e| tableau_step(&table, s, s_prime, e)) {
+                gnba.add_guarded_transition(*source, *target, literals);
+            }
         }
     }
 
     gnba
 }
 
-#[cfg(test)]
-mod test {
-    use buchi::nba::Buchi;
-    use ltl::Formula;
-
-    use super::{ltl_to_gnba, _ts_and_buchi_product};
-
-    #[test]
-    pub fn small_product() {
-        let mut ts = Buchi::new();
-        let s0 = ts.new_labeled_state("{a, b}".into());
-        let s1 = ts.new_labeled_state("{a}".into());
-        let s2 = ts.new_labeled_state("{a}".into());
-        let s3 = ts.new_labeled_state("{a, b}".into());
-        ts.add_transition(s0, s1, "");
-        ts.add_transition(s1, s3, "");
-        ts.add_transition(s3, s1, "");
-        ts.add_transition(s3, s2, "");
-        ts.add_transition(s2, s1, "");
-        ts.add_transition(s2, s0, "");
-        ts.set_initial_state(s0);
-
-        let mut a = Buchi::new();
-        let q0 = a.new_labeled_state("q0".into());
-        let q1 = a.new_labeled_state("q1".into());
-        let q2 = a.new_labeled_state("q2".into());
-        a.add_transition(q0, q0, "{}");
-        a.add_transition(q0, q0, "{b}");
-        a.add_transition(q0, q1, "{a}");
-        a.add_transition(q0, q1, "{a, b}");
-        a.add_transition(q1, q1, "{}");
-        a.add_transition(q1, q1, "{b}");
-        a.add_transition(q1, q0, "{a}");
-        a.add_transition(q1, q0, "{a, b}");
-        a.add_transition(q1, q2, "{b}");
-        a.add_transition(q1, q2, "{a, b}");
-        a.add_transition(q2, q2, "{true}");
-
-        a.set_initial_state(q0);
-        a.add_accepting_set([q2]);
-
-        println!("TS\n{}", ts.to_dot());
-        println!("A\n{}", a.to_dot());
-        let product = _ts_and_buchi_product(ts, a);
-        println!("Product:\n{}", product.to_dot());
-        panic!("Hey")
+/// Whether a transition from `s` to `s_prime` respects the expansion law of the expression
+fn tableau_step(
+    table: &ExprTable,
+    s: &BTreeSet<ExprId>,
+    s_prime: &BTreeSet<ExprId>,
+    expr: ExprId,
+) -> bool {
+    match table.node(expr) {
+        Node::Next(e) => s.contains(&expr) == s_prime.contains(e),
+        Node::Until(a, b) => {
+            s.contains(&expr) == (s.contains(b) || (s.contains(a) && s_prime.contains(&expr)))
+        }
+        Node::Release(a, b) => {
+            s.contains(&expr)
+                == ((s.contains(a) && s.contains(b)) || (s.contains(b) && s_prime.contains(&expr)))
+        }
+        _ => true,
     }
 }
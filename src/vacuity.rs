@@ -0,0 +1,213 @@
+// Vacuity checks for LTL formulas, either on their own or with respect to a Petri net
+
+use std::cell::RefCell;
+
+use ltl::{Expr, Formula};
+use petri::PetriNet;
+use ts::{accepting_lasso, TransitionSystem};
+
+use crate::check::{check_atoms, petri_product, PetriSystem, ProductState};
+use crate::fairness::Fairness;
+use crate::transform::IndexedNba;
+
+/// Report whether the formula is a tautology or a contradiction and which occurrences of
+/// propositions never influence whether a word satisfies it
+pub fn ltl_report(formula: &Formula) -> String {
+    let check = formula.vacuity(satisfiable);
+    if check.valid {
+        return "Tautology, every word satisfies the formula".into();
+    }
+    if check.contradiction {
+        return "Contradiction, no word satisfies the formula".into();
+    }
+
+    // Restrict the model to the words satisfying the formula, an occurrence is vacuous if the
+    // strengthened formula is implied by the original one
+    let redundant = formula
+        .vacuity(|f| {
+            satisfiable(&Formula {
+                root_expr: Expr::And(
+                    Box::new(formula.root_expr.clone()),
+                    Box::new(f.root_expr.clone()),
+                ),
+            })
+        })
+        .vacuous;
+    if redundant.is_empty() {
+        return "Satisfiable but not valid, every occurrence of a proposition is relevant".into();
+    }
+    let mut report = "Satisfiable but not valid, irrelevant occurrences:".to_string();
+    for s in redundant {
+        report += &format!(
+            "\n  occurrence {} of '{}', equivalent to '{}'",
+            s.occurrence, s.proposition, s.formula
+        );
+    }
+    report
+}
+
+/// Report whether the net satisfies the formula, contradicts it on every run or only satisfies
+/// it vacuously. With `por` the markings are explored by stubborn sets, unless the formula
+/// contains next operators or fairness is assumed.
+pub fn petri_report(
+    net: &PetriNet,
+    formula: &Formula,
+    fairness: &Fairness,
+    por: bool,
+) -> petri::Result<String> {
+    fairness.validate(net)?;
+    check_atoms(net, formula)?;
+
+    let reduce = por && fairness.is_empty() && formula.metrics().fragments.stutter_invariant;
+    let error = RefCell::new(None);
+    let check = formula.vacuity(|f| petri_exists(net, f, fairness, reduce, &error));
+    if let Some(e) = error.into_inner() {
+        return Err(e);
+    }
+    if check.contradiction {
+        return Ok("Contradiction, no run of the net satisfies the formula".into());
+    }
+    if !check.valid {
+        return Ok("Not satisfied by every run of the net".into());
+    }
+    if check.vacuous.is_empty() {
+        return Ok("Satisfied, every occurrence of a proposition is relevant".into());
+    }
+    let mut report = "Vacuously satisfied, irrelevant occurrences:".to_string();
+    for s in check.vacuous {
+        report += &format!(
+            "\n  occurrence {} of '{}', the net also satisfies '{}'",
+            s.occurrence, s.proposition, s.formula
+        );
+    }
+    Ok(report)
+}
+
+fn satisfiable(formula: &Formula) -> bool {
+    let nba = IndexedNba::from_formula(formula);
+    match accepting_lasso(&nba, |q| nba.accepting[*q]) {
+        Ok(lasso) => lasso.is_some(),
+        Err(never) => match never {},
+    }
+}
+
+/// Whether some fair run of the net satisfies the formula, i.e. the product of the reachability
+/// graph and the NBA of the formula is not empty. Markings which cannot be fired, as a
+/// place would overflow, have no successors and the error is stored in `error`.
+fn petri_exists(
+    net: &PetriNet,
+    formula: &Formula,
+    fairness: &Fairness,
+    reduce: bool,
+    error: &RefCell<Option<petri::Error>>,
+) -> bool {
+    let product = match petri_product(net, formula, reduce) {
+        // Fairness is decided by the transitions fired on the cycles
+        Ok(product) if !fairness.is_empty() => product.exhaustive(),
+        Ok(product) => product,
+        Err(e) => {
+            error.borrow_mut().get_or_insert(e);
+            return false;
+        }
+    };
+    let accepting = |s: &ProductState<PetriSystem>| product.accepting(s);
+
+    if fairness.is_empty() {
+        return accepting_lasso(&product, accepting)
+            .unwrap_or_else(|e| {
+                error.borrow_mut().get_or_insert(e);
+                None
+            })
+            .is_some();
+    }
+    let successors = |s: &ProductState<PetriSystem>| {
+        product.successors(s).unwrap_or_else(|e| {
+            error.borrow_mut().get_or_insert(e);
+            vec![]
+        })
+    };
+    let enabled = |(marking, _): &ProductState<PetriSystem>| marking.active_transitions(net);
+    let graph = fairness.explore(product.initial_states(), successors, enabled, accepting);
+    fairness.fair_lasso(&graph).is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn ltl_reports() {
+        let report = |f: &str| ltl_report(&Formula::parse(f).unwrap());
+        assert!(report("| a !a").starts_with("Tautology"));
+        assert!(report("& G a F !a").starts_with("Contradiction"));
+        assert!(report("G | !r F g").ends_with("every occurrence of a proposition is relevant"));
+        // Whenever the first conjunct holds b is irrelevant for the second one
+        assert!(report("& G a G | a b").contains("occurrence 2 of 'b'"));
+    }
+
+    #[test]
+    pub fn petri_reports() {
+        // A token cycles between p0 and p1, t2 is never fireable
+        let net = petri::from_xml(
+            r#"<pnml><net id="n"><page id="pg">
+            <place id="p0"><initialMarking><text>1</text></initialMarking></place>
+            <place id="p1"/><place id="p2"/>
+            <transition id="t0"/><transition id="t1"/><transition id="t2"/>
+            <arc id="a0" source="p0" target="t0"/><arc id="a1" source="t0" target="p1"/>
+            <arc id="a2" source="p1" target="t1"/><arc id="a3" source="t1" target="p0"/>
+            <arc id="a4" source="p2" target="t2"/><arc id="a5" source="t2" target="p0"/>
+            </page></net></pnml>"#,
+        )
+        .unwrap();
+        // Stubborn sets give the same reports
+        for por in [false, true] {
+            let report = |f: &str| {
+                petri_report(&net, &Formula::parse(f).unwrap(), &Fairness::new(), por).unwrap()
+            };
+
+            assert!(report("G F t1").starts_with("Satisfied"));
+            assert!(report("G tokens(p0,p1)<=1").starts_with("Satisfied"));
+            assert!(report("G 1<=tokens(p1)").starts_with("Contradiction"));
+            assert!(report("F t2").starts_with("Contradiction"));
+            assert!(report("G | !t2 F t0").contains("occurrence 0 of 't2'"));
+            assert!(petri_report(
+                &net,
+                &Formula::parse("G 1<=tokens(p3)").unwrap(),
+                &Fairness::new(),
+                por
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    pub fn fair_petri_reports() {
+        // The token in p0 either cycles through t0 or is consumed by t1
+        let net = petri::from_xml(
+            r#"<pnml><net id="n"><page id="pg">
+            <place id="p0"><initialMarking><text>1</text></initialMarking></place>
+            <place id="p1"/>
+            <transition id="t0"/><transition id="t1"/>
+            <arc id="a0" source="p0" target="t0"/><arc id="a1" source="t0" target="p0"/>
+            <arc id="a2" source="p0" target="t1"/><arc id="a3" source="t1" target="p1"/>
+            </page></net></pnml>"#,
+        )
+        .unwrap();
+        let report = |f: &str, fairness: &Fairness| {
+            petri_report(&net, &Formula::parse(f).unwrap(), fairness, false).unwrap()
+        };
+
+        assert!(report("F 1<=tokens(p1)", &Fairness::new()).starts_with("Not satisfied"));
+        let mut weak = Fairness::new();
+        weak.add_weak(["t1"]);
+        assert!(report("F 1<=tokens(p1)", &weak).starts_with("Satisfied"));
+        // Firing t0 forever is fair for t0 alone
+        let mut weak = Fairness::new();
+        weak.add_weak(["t0"]);
+        assert!(report("F 1<=tokens(p1)", &weak).starts_with("Not satisfied"));
+
+        let mut unknown = Fairness::new();
+        unknown.add_strong(["t2"]);
+        assert!(petri_report(&net, &Formula::parse("F t0").unwrap(), &unknown, false).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+// Checking the LTL properties of a net again whenever its files change, by `lmc petri --watch`
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::time::{Duration, SystemTime};
+use std::{fs, io, iter, process, thread};
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use lmc::cache::Cache;
+use ltl::Formula;
+use petri::{Change, PetriNet};
+
+use crate::{Checked, Checking, Status, Syntax};
+
+/// How often the files are polled for changes
+const INTERVAL: Duration = Duration::from_millis(250);
+
+/// Files of a net and its properties with the options of reading them
+pub struct Watched<'a> {
+    pub net: &'a OsString,
+    pub compose: &'a [OsString],
+    pub sync: &'a [String],
+    pub safe: bool,
+    pub properties: &'a OsString,
+    pub syntax: Syntax,
+    pub ids: &'a [String],
+}
+
+/// Properties checked on the last version of the net, by their ids and formulas
+#[derive(Default)]
+struct Session {
+    net: Option<PetriNet>,
+    checked: HashMap<(String, String), Checked>,
+}
+
+impl Session {
+    /// Print the properties of the net, checking those which were not checked on an equivalent
+    /// version of it. A verdict is kept as long as the places, transitions, arcs and initial
+    /// marking of the net stay the same, so editing the layout of a net or the rates of its
+    /// transitions checks no property again.
+    fn update(
+        &mut self,
+        out: &mut dyn io::Write,
+        net: PetriNet,
+        properties: &[(String, Formula)],
+        checking: &Checking,
+    ) -> Result<Status> {
+        if let Some(previous) = &self.net {
+            let changes = net.changes(previous);
+            for change in &changes {
+                writeln!(out, "-- The net {}", change)?;
+            }
+            if changes.iter().any(Change::alters_runs) {
+                self.checked.clear();
+            }
+        }
+        let key = |(id, f): &(String, Formula)| (id.clone(), f.to_string());
+        let keys = properties.iter().map(key).collect_vec();
+        self.checked.retain(|k, _| keys.contains(k));
+        let unchecked = (properties.iter())
+            .filter(|p| !self.checked.contains_key(&key(p)))
+            .cloned()
+            .collect_vec();
+        if unchecked.len() < properties.len() {
+            writeln!(
+                out,
+                "-- Kept {} of {} verdicts",
+                properties.len() - unchecked.len(),
+                properties.len()
+            )?;
+        }
+        let checked = crate::check_properties(out, &net, &unchecked, checking)?;
+        self.checked.extend(unchecked.iter().map(key).zip(checked));
+        self.net = Some(net);
+
+        let mut status = Status::Holds;
+        for key in &keys {
+            let checked = &self.checked[key];
+            write!(out, "{}", checked.output)?;
+            if let Some(result) = &checked.result {
+                status = status.max(Status::of([result.verdict]));
+            }
+        }
+        Ok(status)
+    }
+
+    /// Read the files again and update the verdicts
+    fn reload(
+        &mut self,
+        out: &mut dyn io::Write,
+        watched: &Watched,
+        checking: &Checking,
+    ) -> Result<Status> {
+        let net = crate::read_petri(watched.net, watched.compose, watched.sync, watched.safe)?;
+        let properties = crate::read_properties(watched.properties, watched.syntax, watched.ids)?;
+        self.update(out, net, &properties, checking)
+    }
+}
+
+/// Check the properties of the net whenever one of the files changes, until interrupted. Gives
+/// the status of the last check, errors in the files are printed and then waited out.
+///
+/// The files are polled a few times a second. The automata of the formulas, the reduced nets and
+/// the explored markings are kept in a cache as with --cache, in a temporary directory unless one
+/// is given, so the properties of an edited net reuse their automata.
+pub fn watch(out: &mut dyn io::Write, watched: &Watched, checking: &Checking) -> Result<Status> {
+    let directory = std::env::temp_dir().join(format!("lmc-watch-{}", process::id()));
+    let temporary = match checking.cache {
+        Some(_) => None,
+        None => Some(
+            Cache::new(&directory)
+                .with_context(|| format!("Could not use '{}' as cache", directory.display()))?,
+        ),
+    };
+    let checking = Checking {
+        fairness: checking.fairness.clone(),
+        print: false,
+        cache: checking.cache.or(temporary.as_ref()),
+        ..*checking
+    };
+    let files = iter::once(watched.net)
+        .chain(watched.compose)
+        .chain(iter::once(watched.properties))
+        .collect_vec();
+    writeln!(
+        out,
+        "-- Watching {}, interrupt to stop",
+        files
+            .iter()
+            .map(|f| format!("'{}'", f.to_string_lossy()))
+            .join(", ")
+    )?;
+
+    let mut session = Session::default();
+    let mut stamps: Option<Vec<Option<(SystemTime, u64)>>> = None;
+    let mut status = Status::Holds;
+    while ts::stopped().is_none() {
+        let current = (files.iter())
+            .map(|f| {
+                fs::metadata(f)
+                    .and_then(|m| Ok((m.modified()?, m.len())))
+                    .ok()
+            })
+            .collect_vec();
+        if stamps.as_ref() != Some(&current) {
+            stamps = Some(current);
+            match session.reload(out, watched, &checking) {
+                Ok(checked) => status = checked,
+                Err(e) => writeln!(out, "Error: {:#}", e)?,
+            }
+            writeln!(out, "-- Waiting for changes")?;
+            out.flush()?;
+        }
+        thread::sleep(INTERVAL);
+    }
+    if temporary.is_some() {
+        fs::remove_dir_all(&directory).ok();
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Engine;
+    use lmc::fairness::Fairness;
+
+    #[test]
+    pub fn delta_checks() {
+        let checking = Checking {
+            engine: Engine::Explicit,
+            depth: 20,
+            por: false,
+            jobs: 1,
+            fairness: Fairness::new(),
+            vacuity: false,
+            mcc: false,
+            report: false,
+            print: false,
+            witnesses: false,
+            cache: None,
+        };
+        let net = |tina: &str| petri::from_tina(tina).unwrap();
+        let properties = |formulas: &[(&str, &str)]| {
+            (formulas.iter())
+                .map(|(id, f)| (id.to_string(), Formula::parse(f).unwrap()))
+                .collect_vec()
+        };
+        let mut session = Session::default();
+        let mut update = |tina: &str, formulas: &[(&str, &str)]| {
+            let mut out = vec![];
+            let status = session
+                .update(&mut out, net(tina), &properties(formulas), &checking)
+                .unwrap();
+            (String::from_utf8(out).unwrap(), status)
+        };
+        let cycle = "tr t p -> q\ntr u q -> p\npl p (1)";
+        let (out, status) = update(cycle, &[("a", "G F 1<=tokens(p)"), ("b", "G 1<=tokens(p)")]);
+        assert!(out.starts_with("a: "));
+        assert!(!out.contains("-- "));
+        assert!(status == Status::Violated);
+
+        // Only the edited property is checked
+        let (out, _) = update(
+            cycle,
+            &[("a", "G F 1<=tokens(p)"), ("b", "G 1<=tokens(q,p)")],
+        );
+        assert!(out.starts_with("-- Kept 1 of 2 verdicts\n"));
+        let (out, status) = update(
+            "pl p (1)\npl q\ntr t p -> q\ntr u q -> p",
+            &[("a", "G F 1<=tokens(p)"), ("b", "G 1<=tokens(q,p)")],
+        );
+        assert!(out.starts_with("-- Kept 2 of 2 verdicts\n"));
+        assert!(status == Status::Holds);
+
+        // Changing the net checks every property again
+        let (out, status) = update(
+            "tr t p -> q\npl p (1)",
+            &[("a", "G F 1<=tokens(p)"), ("b", "G 1<=tokens(q,p)")],
+        );
+        assert!(out.starts_with("-- The net removed transition 'u'\na: "));
+        assert!(!out.contains("Kept"));
+        assert!(status == Status::Violated);
+    }
+}
@@ -0,0 +1,344 @@
+// Certificates of LTL properties holding for a net, exported so the verdicts can be audited
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+use ltl::{Expr, Formula};
+use petri::{Marking, PetriNet};
+use serde_derive::{Deserialize, Serialize};
+use ts::TransitionSystem;
+
+use crate::check::{check_atoms, PetriSystem, Product};
+use crate::error::{Error, Result};
+use crate::results::{tokens, Tokens};
+use crate::transform::{petri_atom, petri_atom_holds, IndexedNba};
+
+/// Certificates written by --witness-out and checked by `lmc audit`
+#[derive(Serialize, Deserialize)]
+pub struct Witnesses {
+    /// File the net was read from
+    pub net: String,
+    pub properties: Vec<Witness>,
+}
+
+/// Property holding for a net with a certificate for each conjunct of `Formula::decompose`
+#[derive(Serialize, Deserialize)]
+pub struct Witness {
+    pub id: String,
+    pub formula: Formula,
+    pub conjuncts: Vec<Certificate>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Certificate {
+    /// Reachable markings of the net, which all satisfy the conjunct `G invariant`
+    Invariant {
+        invariant: Formula,
+        markings: Vec<Tokens>,
+    },
+    /// Reachable states of the product with the automaton of the negated conjunct, split into
+    /// components whose edges only lead into the same or earlier components
+    Decomposition {
+        conjunct: Formula,
+        automaton: IndexedNba,
+        components: Vec<Vec<ProductState>>,
+    },
+}
+
+/// Marking of the net with a state of the automaton
+#[derive(Serialize, Deserialize)]
+pub struct ProductState {
+    pub marking: Tokens,
+    pub state: usize,
+}
+
+/// Certify a property which was checked to hold for the net
+pub fn certify(net: &PetriNet, id: &str, formula: &Formula) -> petri::Result<Witness> {
+    check_atoms(net, formula)?;
+    let mut conjuncts = vec![];
+    for conjunct in formula.decompose() {
+        if let Some(invariant) = invariant(&conjunct) {
+            let markings = reachable(net)?.iter().map(|m| tokens(net, m)).collect();
+            conjuncts.push(Certificate::Invariant {
+                invariant,
+                markings,
+            });
+            continue;
+        }
+        let automaton = IndexedNba::from_formula(&Formula {
+            root_expr: Expr::Not(Box::new(conjunct.root_expr.clone())),
+        });
+        let system = PetriSystem::new(net, automaton.aps.iter(), false)?;
+        let product = Product::new(system, automaton.clone()).exhaustive();
+        let components = ts::components(&product)?
+            .into_iter()
+            .map(|component| {
+                (component.states.iter())
+                    .map(|(marking, state)| ProductState {
+                        marking: tokens(net, marking),
+                        state: *state,
+                    })
+                    .collect()
+            })
+            .collect();
+        conjuncts.push(Certificate::Decomposition {
+            conjunct,
+            automaton,
+            components,
+        });
+    }
+    Ok(Witness {
+        id: id.to_string(),
+        formula: formula.clone(),
+        conjuncts,
+    })
+}
+
+/// Check that the certificates show the property to hold for the net, only trusting the automata
+/// to accept the runs violating the conjuncts
+pub fn audit(net: &PetriNet, witness: &Witness) -> Result<()> {
+    check_atoms(net, &witness.formula)?;
+    let conjuncts = witness.formula.decompose();
+    if conjuncts.len() != witness.conjuncts.len() {
+        return Err(Error::Witness(format!(
+            "the property has {} conjuncts but {} certificates",
+            conjuncts.len(),
+            witness.conjuncts.len()
+        )));
+    }
+    for (conjunct, certificate) in conjuncts.iter().zip(&witness.conjuncts) {
+        match certificate {
+            Certificate::Invariant {
+                invariant: certified,
+                markings,
+            } => {
+                if invariant(conjunct).as_ref() != Some(certified) {
+                    return Err(Error::Witness(format!(
+                        "'{}' is not the invariant of '{}'",
+                        certified, conjunct
+                    )));
+                }
+                audit_invariant(net, certified, markings)?;
+            }
+            Certificate::Decomposition {
+                conjunct: certified,
+                automaton,
+                components,
+            } => {
+                if certified != conjunct {
+                    return Err(Error::Witness(format!(
+                        "the certificate is for '{}' instead of '{}'",
+                        certified, conjunct
+                    )));
+                }
+                audit_decomposition(net, automaton, components)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The markings contain the initial marking, are closed under firing transitions and each of
+/// them satisfies the invariant
+fn audit_invariant(net: &PetriNet, invariant: &Formula, markings: &[Tokens]) -> Result<()> {
+    let markings: Vec<Marking> = markings.iter().map(|t| marking(net, t)).try_collect()?;
+    let certified: HashSet<&Marking> = markings.iter().collect();
+    if !certified.contains(&net.initial_marking()) {
+        return Err(Error::Witness("the initial marking is missing".into()));
+    }
+    for m in &markings {
+        if !holds(net, m, &m.active_transitions(net), &invariant.root_expr)? {
+            return Err(Error::Witness(format!(
+                "'{}' violates '{}'",
+                net.marking_label(m),
+                invariant
+            )));
+        }
+        for (t, next) in net.transitions(m)? {
+            if !certified.contains(&next) {
+                return Err(Error::Witness(format!(
+                    "firing '{}' in '{}' leads to '{}', which is missing",
+                    t,
+                    net.marking_label(m),
+                    net.marking_label(&next)
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Every edge of the product leads into the same or an earlier component, so an infinite run ends
+/// up in a single component, and no component with an edge inside of it contains an accepting
+/// state, so no run is accepted
+fn audit_decomposition(
+    net: &PetriNet,
+    automaton: &IndexedNba,
+    components: &[Vec<ProductState>],
+) -> Result<()> {
+    let states = automaton.edges.len();
+    if automaton.accepting.len() != states
+        || (automaton.initial_states.iter())
+            .chain(automaton.edges.iter().flatten().map(|(_, q)| q))
+            .any(|q| *q >= states)
+    {
+        return Err(Error::Witness(
+            "the automaton refers to states it does not have".into(),
+        ));
+    }
+    let components: Vec<Vec<(Marking, usize)>> = components
+        .iter()
+        .map(|component| {
+            (component.iter())
+                .map(|s| {
+                    if s.state >= states {
+                        return Err(Error::Witness(format!(
+                            "the automaton has no state {}",
+                            s.state
+                        )));
+                    }
+                    Ok((marking(net, &s.marking)?, s.state))
+                })
+                .try_collect()
+        })
+        .try_collect()?;
+    let label = |(marking, q): &(Marking, usize)| {
+        format!("'{}' in automaton state {}", net.marking_label(marking), q)
+    };
+    // Component of each state of the product
+    let mut index = HashMap::new();
+    for (i, component) in components.iter().enumerate() {
+        for state in component {
+            if index.insert(state.clone(), i).is_some() {
+                return Err(Error::Witness(format!("{} is listed twice", label(state))));
+            }
+        }
+    }
+
+    let system = PetriSystem::new(net, automaton.aps.iter(), false)?;
+    let product = Product::new(system, automaton.clone()).exhaustive();
+    if let Some(initial) = (product.initial_states().into_iter()).find(|s| !index.contains_key(s)) {
+        return Err(Error::Witness(format!(
+            "the initial state {} is missing",
+            label(&initial)
+        )));
+    }
+    for (i, component) in components.iter().enumerate() {
+        let mut inner = false;
+        for state in component {
+            for (_, next) in product.successors(state)? {
+                match index.get(&next) {
+                    None => {
+                        return Err(Error::Witness(format!(
+                            "{} leads to {}, which is missing",
+                            label(state),
+                            label(&next)
+                        )))
+                    }
+                    Some(j) if *j > i => {
+                        return Err(Error::Witness(format!(
+                            "{} in component {} leads to the later component {}",
+                            label(state),
+                            i,
+                            j
+                        )))
+                    }
+                    Some(j) => inner |= *j == i,
+                }
+            }
+        }
+        if let Some(state) = (component.iter()).find(|s| inner && product.accepting(s)) {
+            return Err(Error::Witness(format!(
+                "component {} has an edge inside of it and contains the accepting {}",
+                i,
+                label(state)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `φ` of a conjunct `G φ` where φ has no temporal operators
+fn invariant(conjunct: &Formula) -> Option<Formula> {
+    let Expr::Globally(inner) = &conjunct.root_expr else {
+        return None;
+    };
+    let inner = Formula {
+        root_expr: inner.as_ref().clone(),
+    };
+    inner.metrics().fragments.propositional.then_some(inner)
+}
+
+/// Markings reachable from the initial one, breadth first
+fn reachable(net: &PetriNet) -> petri::Result<Vec<Marking>> {
+    let initial = net.initial_marking();
+    let mut visited = HashSet::from([initial.clone()]);
+    let mut queue = VecDeque::from([initial]);
+    let mut markings = vec![];
+    while let Some(marking) = queue.pop_front() {
+        for next in net.next_markings(&marking)? {
+            if visited.insert(next.clone()) {
+                queue.push_back(next);
+            }
+        }
+        markings.push(marking);
+    }
+    Ok(markings)
+}
+
+fn marking(net: &PetriNet, tokens: &Tokens) -> petri::Result<Marking> {
+    net.marking(&tokens.iter().map(|(p, c)| (p.as_str(), *c)).collect_vec())
+}
+
+/// Evaluate an expression without temporal operators in a marking, `fireable` are the
+/// transitions enabled in it
+fn holds(net: &PetriNet, marking: &Marking, fireable: &[&str], expr: &Expr) -> petri::Result<bool> {
+    let holds = |e: &Expr| holds(net, marking, fireable, e);
+    Ok(match expr {
+        Expr::True => true,
+        Expr::False => false,
+        Expr::Atomic(ap) => petri_atom_holds(net, marking, fireable, &petri_atom(net, ap)?)?,
+        Expr::Not(e) => !holds(e)?,
+        Expr::And(lhs, rhs) => holds(lhs)? && holds(rhs)?,
+        Expr::Or(lhs, rhs) => holds(lhs)? || holds(rhs)?,
+        _ => unreachable!("Invariants have no temporal operators"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn audited_witnesses() {
+        let net = petri::from_tina("tr t0 p0 -> p1\ntr t1 p1 -> p0\npl p0 (1)").unwrap();
+        let witness = |f: &str| certify(&net, "0", &Formula::parse(f).unwrap()).unwrap();
+
+        let mut invariant = witness("& G tokens(p0,p1)<=1 G F t1");
+        assert_eq!(invariant.conjuncts.len(), 2);
+        audit(&net, &invariant).unwrap();
+        let Certificate::Invariant { markings, .. } = &mut invariant.conjuncts[0] else {
+            panic!("G tokens(p0,p1)<=1 is an invariant");
+        };
+        assert_eq!(markings.len(), 2);
+        // The markings are no longer closed under firing
+        markings.pop();
+        assert!(audit(&net, &invariant).is_err());
+
+        let mut liveness = witness("G F 1<=tokens(p1)");
+        let json = serde_json::to_string(&liveness).unwrap();
+        audit(&net, &serde_json::from_str(&json).unwrap()).unwrap();
+        let Certificate::Decomposition { components, .. } = &mut liveness.conjuncts[0] else {
+            panic!("G F 1<=tokens(p1) is no invariant");
+        };
+        components.reverse();
+        assert!(audit(&net, &liveness).is_err());
+
+        // A violated property has an accepting component with a cycle
+        let violated = witness("G tokens(p1)<=0");
+        assert!(audit(&net, &violated).is_err());
+        let violated = witness("F G 1<=tokens(p1)");
+        assert!(audit(&net, &violated).is_err());
+    }
+}
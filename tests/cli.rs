@@ -0,0 +1,180 @@
+// Runs of the lmc binary, checking what it prints and its exit code
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const MUTEX: &str = include_str!("../golden/mutex.net");
+
+/// Directory of the test below the temporary directory holding the files
+fn files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("lmc-cli-{}-{}", test, std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    for (name, content) in files {
+        fs::write(directory.join(name), content).unwrap();
+    }
+    directory
+}
+
+fn lmc(directory: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lmc"))
+        .current_dir(directory)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+pub fn check_nets_and_kripke_structures() {
+    let directory = files(
+        "check",
+        &[
+            ("mutex.net", MUTEX),
+            (
+                "mutex.txt",
+                "G !& 1<=tokens(critical_1) 1<=tokens(critical_2)\nG F enter_1\n",
+            ),
+            (
+                "toggle.kts",
+                "state s0 : a\nstate s1 : b\ninit s0\ns0 -> s1\ns1 -> s0\n",
+            ),
+            ("toggle.txt", "G | a b\nG a\n"),
+        ],
+    );
+
+    let net = lmc(&directory, &["check", "mutex.net", "mutex.txt"]);
+    let printed = stdout(&net);
+    assert!(printed.contains("line-1: 'G ¬(1<=tokens(critical_1) ∧ 1<=tokens(critical_2))'"));
+    assert!(printed.contains("Property holds"));
+    assert!(printed.contains("Property violated, counterexample:"));
+    assert_eq!(net.status.code(), Some(1));
+    let mcc = lmc(
+        &directory,
+        &[
+            "check",
+            "mutex.net",
+            "mutex.txt",
+            "--mcc",
+            "--property",
+            "line-1",
+        ],
+    );
+    assert!(stdout(&mcc).starts_with("FORMULA line-1 TRUE"));
+    assert_eq!(mcc.status.code(), Some(0));
+
+    let kripke = lmc(&directory, &["check", "toggle.kts", "toggle.txt"]);
+    assert_eq!(
+        stdout(&kripke)
+            .lines()
+            .filter(|l| l.starts_with("Property"))
+            .collect::<Vec<_>>(),
+        ["Property holds", "Property violated, counterexample:"]
+    );
+    assert_eq!(kripke.status.code(), Some(1));
+    // Engines and outputs only apply to nets
+    let engine = lmc(
+        &directory,
+        &["check", "toggle.kts", "toggle.txt", "--engine", "bmc"],
+    );
+    assert_eq!(engine.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&engine.stderr).contains("Only the properties of Petri nets"));
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+pub fn exit_codes() {
+    let directory = files(
+        "exit",
+        &[
+            ("mutex.net", MUTEX),
+            (
+                "holds.txt",
+                "G !& 1<=tokens(critical_1) 1<=tokens(critical_2)\n",
+            ),
+            ("violated.txt", "G F enter_1\n"),
+            (
+                "both.txt",
+                "G !& 1<=tokens(critical_1) 1<=tokens(critical_2)\nG F enter_1\n",
+            ),
+        ],
+    );
+    let code = |args: &[&str]| lmc(&directory, args).status.code();
+    assert_eq!(code(&["check", "mutex.net", "holds.txt"]), Some(0));
+    assert_eq!(code(&["check", "mutex.net", "violated.txt"]), Some(1));
+    // Bounded searches cannot show that a property holds
+    let bounded = ["--engine", "bmc", "--depth", "4"];
+    assert_eq!(
+        code(&[&["check", "mutex.net", "holds.txt"][..], &bounded].concat()),
+        Some(2)
+    );
+
+    // A violated property outweighs unknown ones, which outweigh the ones which hold
+    assert_eq!(code(&["check", "mutex.net", "both.txt"]), Some(1));
+    assert_eq!(
+        code(&[&["check", "mutex.net", "both.txt"][..], &bounded].concat()),
+        Some(1)
+    );
+    let stopped = ["petri", "mutex.net", "--analyse", "--max-states", "3"];
+    assert_eq!(code(&stopped), Some(2));
+    assert_eq!(
+        code(&[&stopped[..], &["--ltl", "holds.txt"]].concat()),
+        Some(2)
+    );
+    assert_eq!(
+        code(&[&stopped[..], &["--ltl", "both.txt"]].concat()),
+        Some(1)
+    );
+
+    assert_eq!(code(&["check", "mutex.net", "missing.txt"]), Some(3));
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+pub fn timeout() {
+    let directory = files("timeout", &[("think.txt", "G F 1<=tokens(Think_1)\n")]);
+    let net = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/inputs/philosophers/Philosophers-1000.pnml"
+    );
+    let timed = |args: &[&str]| lmc(&directory, &[&["--timeout", "0.1"][..], args].concat());
+
+    let check = timed(&["check", net, "think.txt"]);
+    assert!(stdout(&check).contains("Unknown, timed out"));
+    assert_eq!(check.status.code(), Some(2));
+    let analysis = timed(&["petri", net, "--analyse"]);
+    assert!(stdout(&analysis).contains("Stopped by --timeout"));
+    assert_eq!(analysis.status.code(), Some(2));
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+pub fn deep_bmc() {
+    // The encoding grows by a step per depth and the places stay within their semiflows
+    let directory = files("deep-bmc", &[]);
+    let inputs = concat!(env!("CARGO_MANIFEST_DIR"), "/inputs");
+    let net = format!("{}/philosophers/Philosophers-5.pnml", inputs);
+    let properties = format!("{}/LTLFireability.xml", inputs);
+    let check = lmc(
+        &directory,
+        &[
+            "--timeout",
+            "60",
+            "check",
+            "--engine",
+            "bmc",
+            "--depth",
+            "20",
+            &net,
+            &properties,
+        ],
+    );
+    let out = stdout(&check);
+    assert!(!out.contains("timed out"));
+    assert_eq!(out.matches("No violation found up to depth 20").count(), 2);
+    assert_eq!(check.status.code(), Some(1));
+    fs::remove_dir_all(&directory).unwrap();
+}
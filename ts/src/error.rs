@@ -0,0 +1,15 @@
+use std::io;
+use std::result;
+use thiserror::Error;
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("invalid Kripke structure in line {line}: {reason}")]
+    Kts { line: usize, reason: String },
+    #[error("Kripke structure without initial states")]
+    NoInitialState,
+    #[error("could not read file")]
+    IOError(#[from] io::Error),
+}
@@ -0,0 +1,169 @@
+// Explicit Kripke structures and their textual `.kts` format
+
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::Path as FilePath;
+
+use crate::error::{Error, Result};
+use crate::{Path, TransitionSystem};
+
+/// Explicit finite transition system with the atomic propositions holding in each state
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Kripke {
+    names: Vec<String>,
+    labels: Vec<BTreeSet<String>>,
+    initial: Vec<usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl Kripke {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a state in which exactly the given propositions hold, giving its index
+    pub fn add_state<'a>(
+        &mut self,
+        name: impl Into<String>,
+        labels: impl IntoIterator<Item = &'a str>,
+    ) -> usize {
+        self.names.push(name.into());
+        self.labels
+            .push(labels.into_iter().map(String::from).collect());
+        self.edges.push(vec![]);
+        self.names.len() - 1
+    }
+
+    pub fn add_initial(&mut self, state: usize) {
+        if !self.initial.contains(&state) {
+            self.initial.push(state);
+        }
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        if !self.edges[from].contains(&to) {
+            self.edges[from].push(to);
+        }
+    }
+
+    pub fn state(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    pub fn name(&self, state: usize) -> &str {
+        &self.names[state]
+    }
+
+    pub fn labels(&self, state: usize) -> &BTreeSet<String> {
+        &self.labels[state]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Read a structure in the `.kts` format, whose lines declare states with the propositions
+    /// holding in them, the initial states or edges, `#` starts a comment:
+    ///
+    /// ```text
+    /// state idle
+    /// state busy : req
+    /// init idle
+    /// idle -> busy
+    /// busy -> busy idle
+    /// ```
+    ///
+    /// States are declared before they are used and have no propositions unless they are listed
+    /// after the colon. An edge line leads from its first state to every state after the arrow.
+    pub fn parse(input: &str) -> Result<Kripke> {
+        let mut kripke = Kripke::new();
+        for (line, text) in input.lines().enumerate() {
+            let text = text.split('#').next().unwrap_or_default();
+            kripke.declaration(text).map_err(|reason| Error::Kts {
+                line: line + 1,
+                reason,
+            })?;
+        }
+        if kripke.initial.is_empty() {
+            return Err(Error::NoInitialState);
+        }
+        Ok(kripke)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn from_file(path: impl AsRef<FilePath>) -> Result<Kripke> {
+        Kripke::parse(&fs::read_to_string(path)?)
+    }
+
+    fn declaration(&mut self, text: &str) -> std::result::Result<(), String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            [] => {}
+            ["state", name, rest @ ..] => {
+                if self.state(name).is_some() {
+                    return Err(format!("duplicate state '{}'", name));
+                }
+                let labels = match rest {
+                    [] => &[][..],
+                    [":", labels @ ..] => labels,
+                    _ => return Err(format!("expected ':' before the labels of '{}'", name)),
+                };
+                self.add_state(*name, labels.iter().copied());
+            }
+            ["init", states @ ..] if !states.is_empty() => {
+                for state in self.lookup(states)? {
+                    self.add_initial(state);
+                }
+            }
+            [from, "->", targets @ ..] if !targets.is_empty() => {
+                let from = self.lookup(&[from])?[0];
+                for target in self.lookup(targets)? {
+                    self.add_edge(from, target);
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "expected a state, initial states or edges in '{}'",
+                    text.trim()
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, names: &[&str]) -> std::result::Result<Vec<usize>, String> {
+        names
+            .iter()
+            .map(|name| {
+                self.state(name)
+                    .ok_or_else(|| format!("undeclared state '{}'", name))
+            })
+            .collect()
+    }
+}
+
+/// Edges are unlabeled, the states carry the propositions
+impl TransitionSystem for Kripke {
+    type State = usize;
+    type Label = ();
+    type Error = Infallible;
+
+    fn initial_states(&self) -> Vec<usize> {
+        self.initial.clone()
+    }
+
+    fn successors(&self, state: &usize) -> std::result::Result<Path<Self>, Infallible> {
+        Ok(self.edges[*state].iter().map(|&s| ((), s)).collect())
+    }
+
+    fn holds(&self, state: &usize, proposition: &str) -> bool {
+        self.labels[*state].contains(proposition)
+    }
+}
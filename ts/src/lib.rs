@@ -0,0 +1,343 @@
+// Transition systems shared by the model checking pipelines
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+mod error;
+mod kripke;
+mod stop;
+
+pub use error::Error;
+pub use kripke::Kripke;
+pub use stop::{resume, stop, stopped, Stop};
+
+/// System whose successors are generated on demand. Products and emptiness checks are written
+/// once against this interface for nets, automata and explicit structures.
+pub trait TransitionSystem {
+    type State: Clone + Eq + Hash;
+    /// Label of an edge, such as the letter read or the transition fired
+    type Label: Clone;
+    /// Error computing the successors, for example when a net overflows
+    type Error;
+
+    fn initial_states(&self) -> Vec<Self::State>;
+
+    /// Edges leaving the state
+    fn successors(&self, state: &Self::State) -> Result<Path<Self>, Self::Error>;
+
+    /// Whether the atomic proposition holds in the state, none does unless the system says so
+    fn holds(&self, _state: &Self::State, _proposition: &str) -> bool {
+        false
+    }
+}
+
+/// Systems can be borrowed by the products built on them
+impl<T: TransitionSystem> TransitionSystem for &T {
+    type State = T::State;
+    type Label = T::Label;
+    type Error = T::Error;
+
+    fn initial_states(&self) -> Vec<T::State> {
+        (*self).initial_states()
+    }
+
+    fn successors(&self, state: &T::State) -> Result<Path<T>, T::Error> {
+        (*self).successors(state)
+    }
+
+    fn holds(&self, state: &T::State, proposition: &str) -> bool {
+        (*self).holds(state, proposition)
+    }
+}
+
+/// Edges of a path, each with its label and the state it leads to
+pub type Path<T> = Vec<(
+    <T as TransitionSystem>::Label,
+    <T as TransitionSystem>::State,
+)>;
+
+/// Edges from an initial state to a cycle and the edges of the cycle
+pub type Lasso<T> = (Path<T>, Path<T>);
+
+/// Search for a reachable cycle through an accepting state, giving the edges leading from an
+/// initial state to the cycle and the edges on the cycle
+///
+/// The nested depth first search (Courcoubetis, Vardi, Wolper and Yannakakis) starts a second
+/// search from every accepting state once the first search backtracks from it. The second search
+/// looks for a cycle back to that state and shares its visited set between all of its runs. Only
+/// the part of the system the search visits is explored and it stops at the first lasso.
+pub fn accepting_lasso<T: TransitionSystem>(
+    ts: &T,
+    accepting: impl Fn(&T::State) -> bool,
+) -> Result<Option<Lasso<T>>, T::Error> {
+    Ok(search(ts, accepting)?.lasso)
+}
+
+/// Outcome of the search for an accepting lasso
+pub struct Search<T: TransitionSystem> {
+    pub lasso: Option<Lasso<T>>,
+    /// Number of distinct states the search visited
+    pub states: usize,
+}
+
+/// Search for an accepting lasso like `accepting_lasso`, also counting the states visited
+pub fn search<T: TransitionSystem>(
+    ts: &T,
+    accepting: impl Fn(&T::State) -> bool,
+) -> Result<Search<T>, T::Error> {
+    let mut visited = HashSet::new();
+    let mut nested_visited = HashSet::new();
+    for initial in ts.initial_states() {
+        if !visited.insert(initial.clone()) {
+            continue;
+        }
+        // The path of the search with the edge entering each state and its remaining successors
+        let mut path = vec![(None, initial.clone(), ts.successors(&initial)?.into_iter())];
+        while let Some((_, _, successors)) = path.last_mut() {
+            if let Some((label, next)) = successors.next() {
+                if visited.insert(next.clone()) {
+                    let successors = ts.successors(&next)?.into_iter();
+                    path.push((Some(label), next, successors));
+                }
+                continue;
+            }
+            let (entering, state, _) = path.pop().unwrap();
+            if !accepting(&state) {
+                continue;
+            }
+            if let Some(cycle) = cycle(ts, &state, &mut nested_visited)? {
+                let stem = path
+                    .into_iter()
+                    .filter_map(|(label, state, _)| Some((label?, state)))
+                    .chain(entering.map(|label| (label, state)))
+                    .collect();
+                return Ok(Search {
+                    lasso: Some((stem, cycle)),
+                    states: visited.len(),
+                });
+            }
+        }
+    }
+    Ok(Search {
+        lasso: None,
+        states: visited.len(),
+    })
+}
+
+/// Edges of a cycle from the state back to itself, states visited by previous searches are
+/// skipped
+fn cycle<T: TransitionSystem>(
+    ts: &T,
+    seed: &T::State,
+    visited: &mut HashSet<T::State>,
+) -> Result<Option<Path<T>>, T::Error> {
+    let mut path = vec![(None, seed.clone(), ts.successors(seed)?.into_iter())];
+    while let Some((_, _, successors)) = path.last_mut() {
+        let Some((label, next)) = successors.next() else {
+            path.pop();
+            continue;
+        };
+        if next == *seed {
+            let cycle = path
+                .into_iter()
+                .filter_map(|(label, state, _)| Some((label?, state)))
+                .chain([(label, next)])
+                .collect();
+            return Ok(Some(cycle));
+        }
+        if visited.insert(next.clone()) {
+            let successors = ts.successors(&next)?.into_iter();
+            path.push((Some(label), next, successors));
+        }
+    }
+    Ok(None)
+}
+
+/// Strongly connected component of the reachable states of a system
+pub struct Component<S> {
+    pub states: Vec<S>,
+    /// Whether a cycle runs through the states, a single state needs an edge to itself
+    pub cyclic: bool,
+}
+
+/// Strongly connected components of the states reachable from the initial ones, every component
+/// is listed after the components reachable from it. Tarjan's algorithm is run with an explicit
+/// stack so deep systems do not overflow the call stack.
+pub fn components<T: TransitionSystem>(ts: &T) -> Result<Vec<Component<T::State>>, T::Error> {
+    let mut tarjan = Tarjan {
+        numbers: HashMap::new(),
+        states: vec![],
+        lowlink: vec![],
+        on_stack: vec![],
+        looping: vec![],
+        stack: vec![],
+    };
+    let mut components = vec![];
+    for initial in ts.initial_states() {
+        if tarjan.numbers.contains_key(&initial) {
+            continue;
+        }
+        let successors = ts.successors(&initial)?.into_iter();
+        let mut path = vec![(tarjan.enter(initial), successors)];
+        while let Some((v, successors)) = path.last_mut() {
+            let v = *v;
+            if let Some((_, next)) = successors.next() {
+                match tarjan.numbers.get(&next) {
+                    Some(&w) => {
+                        if tarjan.on_stack[w] {
+                            tarjan.lowlink[v] = tarjan.lowlink[v].min(w);
+                        }
+                        tarjan.looping[v] |= w == v;
+                    }
+                    None => {
+                        let successors = ts.successors(&next)?.into_iter();
+                        path.push((tarjan.enter(next), successors));
+                    }
+                }
+                continue;
+            }
+            path.pop();
+            if let Some((parent, _)) = path.last() {
+                tarjan.lowlink[*parent] = tarjan.lowlink[*parent].min(tarjan.lowlink[v]);
+            }
+            if tarjan.lowlink[v] == v {
+                components.push(tarjan.component(v));
+            }
+        }
+    }
+    Ok(components)
+}
+
+/// States numbered in the order they are reached, which is their index in Tarjan's sense
+struct Tarjan<S> {
+    numbers: HashMap<S, usize>,
+    states: Vec<S>,
+    lowlink: Vec<usize>,
+    on_stack: Vec<bool>,
+    /// Whether the state has an edge to itself
+    looping: Vec<bool>,
+    stack: Vec<usize>,
+}
+
+impl<S: Clone + Eq + Hash> Tarjan<S> {
+    fn enter(&mut self, state: S) -> usize {
+        let v = self.states.len();
+        self.numbers.insert(state.clone(), v);
+        self.states.push(state);
+        self.lowlink.push(v);
+        self.on_stack.push(true);
+        self.looping.push(false);
+        self.stack.push(v);
+        v
+    }
+
+    /// Pop the component whose first reached state is `v` from the stack
+    fn component(&mut self, v: usize) -> Component<S> {
+        let split = self.stack.iter().rposition(|w| *w == v).unwrap();
+        let members = self.stack.split_off(split);
+        for w in &members {
+            self.on_stack[*w] = false;
+        }
+        Component {
+            cyclic: members.len() > 1 || self.looping[v],
+            states: members.iter().map(|w| self.states[*w].clone()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    /// 0 -> 1 -> 2 -> 1 with the state as the label of the edges entering it
+    struct Chain;
+
+    impl TransitionSystem for Chain {
+        type State = usize;
+        type Label = usize;
+        type Error = Infallible;
+
+        fn initial_states(&self) -> Vec<usize> {
+            vec![0]
+        }
+
+        fn successors(&self, state: &usize) -> Result<Vec<(usize, usize)>, Infallible> {
+            let next = if *state == 1 { 2 } else { 1 };
+            Ok(vec![(next, next)])
+        }
+
+        fn holds(&self, state: &usize, proposition: &str) -> bool {
+            state.to_string() == proposition
+        }
+    }
+
+    #[test]
+    pub fn lasso_search() {
+        let lasso = accepting_lasso(&Chain, |s| Chain.holds(s, "2")).unwrap();
+        assert_eq!(lasso, Some((vec![(1, 1), (2, 2)], vec![(1, 1), (2, 2)])));
+        assert_eq!(accepting_lasso(&Chain, |s| *s == 0).unwrap(), None);
+        assert_eq!(search(&Chain, |s| *s == 0).unwrap().states, 3);
+
+        let components = components(&Chain).unwrap();
+        let states = components.iter().map(|c| (c.states.clone(), c.cyclic));
+        assert_eq!(
+            states.collect::<Vec<_>>(),
+            vec![(vec![1, 2], true), (vec![0], false)]
+        );
+    }
+
+    #[test]
+    pub fn kripke_structure() {
+        // A request is eventually granted, after which the structure waits for the next one
+        let mut kripke = Kripke::new();
+        let idle = kripke.add_state("idle", []);
+        let requested = kripke.add_state("requested", ["req"]);
+        let granted = kripke.add_state("granted", ["req", "grant"]);
+        kripke.add_initial(idle);
+        kripke.add_edge(idle, requested);
+        kripke.add_edge(requested, requested);
+        kripke.add_edge(requested, granted);
+        kripke.add_edge(granted, idle);
+        assert_eq!(kripke.state("granted"), Some(granted));
+        assert!(kripke.holds(&granted, "grant") && !kripke.holds(&requested, "grant"));
+
+        let (stem, cycle) = accepting_lasso(&kripke, |s| kripke.holds(s, "grant"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(stem, vec![((), requested), ((), granted)]);
+        assert_eq!(cycle, vec![((), idle), ((), requested), ((), granted)]);
+        assert_eq!(accepting_lasso(&kripke, |s| *s == 3).unwrap(), None);
+    }
+
+    #[test]
+    pub fn kts_format() {
+        let kripke = Kripke::parse(
+            "# a request is served eventually\n\
+             state idle\n\
+             state busy : req\n\
+             init idle\n\
+             idle -> busy\n\
+             busy -> busy idle # may stay busy",
+        )
+        .unwrap();
+        assert_eq!(kripke.len(), 2);
+        assert_eq!(kripke.initial_states(), vec![0]);
+        assert_eq!(kripke.successors(&1).unwrap(), vec![((), 1), ((), 0)]);
+        assert!(kripke.holds(&1, "req") && !kripke.holds(&0, "req"));
+
+        let line = |input: &str| match Kripke::parse(input) {
+            Err(Error::Kts { line, .. }) => Some(line),
+            _ => None,
+        };
+        assert_eq!(line("state a\ninit a\na -> b"), Some(3));
+        assert_eq!(line("state a\nstate a"), Some(2));
+        assert_eq!(line("state a req"), Some(1));
+        assert_eq!(line("state a\ninit"), Some(2));
+        assert!(matches!(
+            Kripke::parse("state a\na -> a"),
+            Err(Error::NoInitialState)
+        ));
+    }
+}
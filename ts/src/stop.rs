@@ -0,0 +1,51 @@
+// Stopping explorations before they are complete
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// A single flag for the whole process, only the first reason is kept
+static STOP: AtomicU8 = AtomicU8::new(0);
+
+/// Why explorations were stopped. They poll the flag between states and give up with the reason,
+/// so whoever started them still reports what was found so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Interrupt,
+    Timeout,
+    Memory,
+}
+
+/// Ask every exploration to stop, unless it was already asked for another reason
+pub fn stop(reason: Stop) {
+    let code = match reason {
+        Stop::Interrupt => 1,
+        Stop::Timeout => 2,
+        Stop::Memory => 3,
+    };
+    let _ = STOP.compare_exchange(0, code, Ordering::SeqCst, Ordering::SeqCst);
+}
+
+/// Let explorations run again after they were stopped, such as for the next of several jobs
+pub fn resume() {
+    STOP.store(0, Ordering::SeqCst);
+}
+
+/// Why explorations were asked to stop, if they were
+pub fn stopped() -> Option<Stop> {
+    match STOP.load(Ordering::Relaxed) {
+        0 => None,
+        1 => Some(Stop::Interrupt),
+        2 => Some(Stop::Timeout),
+        _ => Some(Stop::Memory),
+    }
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stop::Interrupt => write!(f, "interrupted"),
+            Stop::Timeout => write!(f, "timed out"),
+            Stop::Memory => write!(f, "out of memory"),
+        }
+    }
+}
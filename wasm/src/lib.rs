@@ -0,0 +1,247 @@
+// JavaScript interface for running the translations and solvers in the browser, without the
+// file and thread support of the lmc library. Built by `wasm-pack build --target web`.
+
+use std::collections::HashMap;
+
+use lmc::buchi::hoa;
+use lmc::buchi::nba::{Buchi, Word};
+use lmc::ltl;
+use lmc::ltl::alphabet::Literals;
+use lmc::parity::Graph;
+use lmc::transform::ltl_to_gnba;
+use wasm_bindgen::prelude::*;
+
+/// LTL formula, such as 'G | !r F g' in prefix notation or 'G (r -> F g)' in Spot's
+#[wasm_bindgen]
+pub struct Formula {
+    formula: ltl::Formula,
+}
+
+#[wasm_bindgen]
+impl Formula {
+    /// Parse the formula in the syntax "prefix" or "spot"
+    #[wasm_bindgen(constructor)]
+    pub fn new(text: &str, syntax: &str) -> Result<Formula, JsError> {
+        let formula = match syntax {
+            "prefix" => ltl::Formula::parse(text),
+            "spot" => ltl::spot::parse(text),
+            _ => return Err(JsError::new(&format!("Unknown syntax '{}'", syntax))),
+        };
+        Ok(Formula {
+            formula: formula.map_err(|e| JsError::new(&e.to_string()))?,
+        })
+    }
+
+    /// Equivalent formula in positive normal form
+    pub fn pnf(&self) -> Formula {
+        Formula {
+            formula: self.formula.pnf(),
+        }
+    }
+
+    pub fn negation(&self) -> Formula {
+        Formula {
+            formula: self.formula.negation(),
+        }
+    }
+
+    /// Atomic propositions in lexicographic order
+    pub fn propositions(&self) -> Vec<String> {
+        self.formula.aps().iter().map(String::from).collect()
+    }
+
+    /// Büchi automaton accepting the runs satisfying the formula, with an acceptance set per
+    /// until subformula if `generalized`
+    pub fn automaton(&self, generalized: bool) -> Automaton {
+        let gnba = ltl_to_gnba(&self.formula.pnf());
+        Automaton {
+            automaton: if generalized {
+                gnba
+            } else {
+                gnba.gnba_to_nba()
+            },
+        }
+    }
+
+    /// The formula in Spot's syntax, undefined if a proposition contains a double quote
+    pub fn spot(&self) -> Option<String> {
+        self.formula.to_spot()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_text(&self) -> String {
+        self.formula.to_string()
+    }
+}
+
+/// Büchi automaton over valuations of atomic propositions
+#[wasm_bindgen]
+pub struct Automaton {
+    automaton: Buchi,
+}
+
+#[wasm_bindgen]
+impl Automaton {
+    /// Read an automaton in the Hanoi Omega Automata format
+    #[wasm_bindgen(js_name = fromHoa)]
+    pub fn from_hoa(text: &str) -> Result<Automaton, JsError> {
+        Ok(Automaton {
+            automaton: hoa::parse(text).map_err(|e| JsError::new(&e.to_string()))?,
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn states(&self) -> usize {
+        self.automaton.states().len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn transitions(&self) -> usize {
+        self.automaton.stats().transitions
+    }
+
+    /// Whether the automaton accepts no word
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.automaton.verify().is_ok()
+    }
+
+    /// Whether the word prefix cycle^ω is accepted, every letter lists the propositions holding
+    /// in it such as "{a, b}"
+    pub fn accepts(&self, prefix: Vec<String>, cycle: Vec<String>) -> Result<bool, JsError> {
+        let aps = self.automaton.aps();
+        let letter = |label: &String| -> Result<Word, JsError> {
+            let valuation = aps
+                .parse_valuation(label)
+                .ok_or_else(|| JsError::new(&format!("Invalid letter '{}'", label)))?;
+            let literals = (0..aps.len()).fold(Literals::empty(), |literals, i| {
+                literals.with(i, valuation.contains(i))
+            });
+            Ok(Word::guard(aps, literals))
+        };
+        let prefix = prefix.iter().map(letter).collect::<Result<Vec<_>, _>>()?;
+        let cycle = cycle.iter().map(letter).collect::<Result<Vec<_>, _>>()?;
+        Ok(self.automaton.accepts(&prefix, &cycle))
+    }
+
+    pub fn hoa(&self) -> String {
+        self.automaton.hoa()
+    }
+
+    pub fn dot(&self) -> String {
+        self.automaton.to_dot()
+    }
+}
+
+/// Parity game in the PGSolver format
+#[wasm_bindgen]
+pub struct ParityGame {
+    graph: Graph,
+}
+
+#[wasm_bindgen]
+impl ParityGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new(text: &str) -> Result<ParityGame, JsError> {
+        Ok(ParityGame {
+            graph: lmc::parity::parse_game(text).map_err(|e| JsError::new(&e.to_string()))?,
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn vertices(&self) -> usize {
+        self.graph.len()
+    }
+
+    /// Solve the game with "fpi", "zielonka", "tangle" or "spm"
+    pub fn solve(&self, algorithm: &str) -> Result<Solution, JsError> {
+        let solution = match algorithm {
+            "fpi" => self.graph.fpi(),
+            "zielonka" => self.graph.zielonka(),
+            "tangle" => self.graph.tangle(),
+            "spm" => self.graph.spm(),
+            _ => return Err(JsError::new(&format!("Unknown algorithm '{}'", algorithm))),
+        };
+        let mut even = solution
+            .even_region
+            .iter()
+            .map(|v| v.id)
+            .collect::<Vec<_>>();
+        let mut odd = solution.odd_region.iter().map(|v| v.id).collect::<Vec<_>>();
+        even.sort_unstable();
+        odd.sort_unstable();
+        Ok(Solution {
+            even,
+            odd,
+            strategy: (solution.strategy.iter())
+                .filter_map(|(vertex, strategy)| Some((*vertex, strategy.next_node_id?)))
+                .collect(),
+        })
+    }
+}
+
+/// Winning regions of the players and the successors the winners move to
+#[wasm_bindgen]
+pub struct Solution {
+    even: Vec<usize>,
+    odd: Vec<usize>,
+    strategy: HashMap<usize, usize>,
+}
+
+#[wasm_bindgen]
+impl Solution {
+    /// Vertices won by even, in ascending order
+    #[wasm_bindgen(getter)]
+    pub fn even(&self) -> Vec<usize> {
+        self.even.clone()
+    }
+
+    /// Vertices won by odd, in ascending order
+    #[wasm_bindgen(getter)]
+    pub fn odd(&self) -> Vec<usize> {
+        self.odd.clone()
+    }
+
+    /// 0 if even wins from the vertex, 1 if odd does, undefined for unknown vertices
+    pub fn winner(&self, vertex: usize) -> Option<u8> {
+        if self.even.binary_search(&vertex).is_ok() {
+            Some(0)
+        } else if self.odd.binary_search(&vertex).is_ok() {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    /// Successor the winner moves to from one of its vertices, undefined for the others
+    pub fn strategy(&self, vertex: usize) -> Option<usize> {
+        self.strategy.get(&vertex).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Errors are JavaScript values, only the successful calls run outside of wasm32
+    #[test]
+    pub fn demo() {
+        let formula = Formula::new("G (a -> F b)", "spot").unwrap_or_else(|_| panic!());
+        assert_eq!(formula.propositions(), ["a", "b"]);
+        let automaton = formula.automaton(false);
+        assert!(!automaton.is_empty());
+        let letters = |letters: &[&str]| letters.iter().map(|l| l.to_string()).collect();
+        let accepts = |prefix: &[&str], cycle: &[&str]| {
+            (automaton.accepts(letters(prefix), letters(cycle))).unwrap_or_else(|_| panic!())
+        };
+        assert!(accepts(&[], &["{a, b}"]));
+        assert!(!accepts(&["{a}"], &["{}"]));
+        assert!(automaton.dot().starts_with("digraph"));
+
+        let game = ParityGame::new("parity 2;\n0 2 0 1;\n1 1 1 0;").unwrap_or_else(|_| panic!());
+        let solution = game.solve("zielonka").unwrap_or_else(|_| panic!());
+        assert_eq!(solution.even(), [0, 1]);
+        assert_eq!(solution.winner(1), Some(0));
+        assert_eq!(solution.winner(2), None);
+    }
+}